@@ -1,21 +1,23 @@
-use crate::{Condvar, Mutex};
+use crate::sys::barrier as sys;
 use std::fmt;
+use std::panic::{RefUnwindSafe, UnwindSafe};
 use std::pin::Pin;
 use std::sync::Arc;
 
 /// A barrier enables multiple threads to synchronize the beginning
 /// of some computation.
+///
+/// This is a `pthread_barrier_t` where the platform has one, and a
+/// synchronization barrier on Windows. Elsewhere, including on the platforms
+/// with a futex-like backend, it is built on the mutex and condition variable
+/// of the backend.
 pub struct Barrier {
-    lock: Mutex<BarrierState>,
-    cvar: Condvar,
-    num_threads: usize,
+    inner: sys::Barrier,
 }
 
-// The inner state of a double barrier
-struct BarrierState {
-    count: usize,
-    generation_id: usize,
-}
+impl UnwindSafe for Barrier {}
+
+impl RefUnwindSafe for Barrier {}
 
 /// A `BarrierWaitResult` is returned by [`Barrier::wait()`] when all threads
 /// in the [`Barrier`] have rendezvoused.
@@ -36,12 +38,7 @@ impl Barrier {
     /// [`wait()`]: Barrier::wait
     pub const fn uninit(n: usize) -> Barrier {
         Barrier {
-            lock: Mutex::uninit(BarrierState {
-                count: 0,
-                generation_id: 0,
-            }),
-            cvar: Condvar::uninit(),
-            num_threads: n,
+            inner: sys::Barrier::uninit(n),
         }
     }
 
@@ -68,8 +65,7 @@ impl Barrier {
     /// Initializes the barrier.
     #[inline]
     pub fn init(self: Pin<&Self>) {
-        self.lock().init();
-        self.cvar().init();
+        self.inner().init();
     }
 
     /// Blocks the current thread until all threads have rendezvoused here.
@@ -82,32 +78,12 @@ impl Barrier {
     /// from this function, and all other threads will receive a result that
     /// will return `false` from [`BarrierWaitResult::is_leader()`].
     pub fn wait(self: Pin<&Self>) -> BarrierWaitResult {
-        let mut lock = self.lock().lock().unwrap();
-        let local_gen = lock.generation_id;
-        lock.count += 1;
-        if lock.count < self.num_threads {
-            // We need a while loop to guard against spurious wakeups.
-            // https://en.wikipedia.org/wiki/Spurious_wakeup
-            while local_gen == lock.generation_id && lock.count < self.num_threads {
-                lock = self.cvar().wait(lock).unwrap();
-            }
-            BarrierWaitResult(false)
-        } else {
-            lock.count = 0;
-            lock.generation_id = lock.generation_id.wrapping_add(1);
-            self.cvar().notify_all();
-            BarrierWaitResult(true)
-        }
-    }
-
-    #[inline]
-    fn lock(self: Pin<&Self>) -> Pin<&Mutex<BarrierState>> {
-        unsafe { self.map_unchecked(|this| &this.lock) }
+        BarrierWaitResult(self.inner().wait())
     }
 
     #[inline]
-    fn cvar(self: Pin<&Self>) -> Pin<&Condvar> {
-        unsafe { self.map_unchecked(|this| &this.cvar) }
+    fn inner(self: Pin<&Self>) -> Pin<&sys::Barrier> {
+        unsafe { self.map_unchecked(|this| &this.inner) }
     }
 }
 
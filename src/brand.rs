@@ -0,0 +1,158 @@
+use std::cell::UnsafeCell;
+use std::fmt;
+use std::marker::PhantomData;
+
+// Invariant in `'id`, so that brands can neither be shortened nor extended
+// into one another.
+type InvariantLifetime<'id> = PhantomData<fn(&'id ()) -> &'id ()>;
+
+/// A unique access token for [`BrandedCell`]s.
+///
+/// Every `Brand` has its own `'id` lifetime, which no other brand shares.
+/// Cells created with a brand can only be accessed by presenting that brand:
+/// a shared reference to the brand gives shared access to all of its cells,
+/// and a mutable reference gives mutable access.
+///
+/// Putting the brand in a [`Mutex`] makes the mutex protect every cell of the
+/// brand at once. Locking the mutex yields the brand, and the cells can then
+/// be accessed without any further locking or guards. This lets a single
+/// pinned mutex protect a whole graph of cells, which can be shared freely
+/// between threads.
+///
+/// `Brand` is a zero-sized type, so all of the checks happen at compile time.
+///
+/// # Examples
+///
+/// ```
+/// use pinned_sync::{Brand, BrandedCell, Mutex};
+/// use std::thread;
+///
+/// Brand::with(|brand| {
+///     let mutex = Mutex::boxed(brand);
+///     let cells: Vec<_> = (0..4).map(BrandedCell::new).collect();
+///
+///     thread::scope(|s| {
+///         for _ in 0..4 {
+///             s.spawn(|| {
+///                 let mut brand = mutex.as_ref().lock().unwrap();
+///                 for cell in &cells {
+///                     *cell.borrow_mut(&mut brand) += 1;
+///                 }
+///             });
+///         }
+///     });
+///
+///     let brand = mutex.as_ref().lock().unwrap();
+///     let sum: i32 = cells.iter().map(|cell| *cell.borrow(&brand)).sum();
+///     assert_eq!(sum, 22);
+/// });
+/// ```
+///
+/// Cells can not be accessed with another brand:
+///
+/// ```compile_fail
+/// use pinned_sync::{Brand, BrandedCell};
+///
+/// Brand::with(|mut a| {
+///     Brand::with(|b| {
+///         let cell = BrandedCell::new(0);
+///         *cell.borrow_mut(&mut a) += 1;
+///         cell.borrow(&b);
+///     });
+/// });
+/// ```
+///
+/// [`Mutex`]: crate::Mutex
+pub struct Brand<'id> {
+    _id: InvariantLifetime<'id>,
+}
+
+impl Brand<'_> {
+    /// Calls `f` with a brand with a new, unique lifetime.
+    #[inline]
+    pub fn with<R>(f: impl for<'id> FnOnce(Brand<'id>) -> R) -> R {
+        f(Brand { _id: PhantomData })
+    }
+}
+
+impl fmt::Debug for Brand<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Brand").finish_non_exhaustive()
+    }
+}
+
+/// A cell which can only be accessed through its [`Brand`].
+///
+/// Borrowing the cell borrows the brand, so the borrow rules of the brand
+/// apply to all of its cells together: any number of cells can be borrowed
+/// through `&Brand`, or a single one through `&mut Brand`.
+pub struct BrandedCell<'id, T: ?Sized> {
+    _id: InvariantLifetime<'id>,
+    value: UnsafeCell<T>,
+}
+
+// Sharing a cell shares its value through `&Brand`, and lets it be mutated
+// through `&mut Brand` from another thread.
+unsafe impl<T: ?Sized + Send + Sync> Sync for BrandedCell<'_, T> {}
+
+impl<'id, T> BrandedCell<'id, T> {
+    /// Creates a new cell containing the given value.
+    #[inline]
+    pub const fn new(value: T) -> Self {
+        Self {
+            _id: PhantomData,
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    /// Consumes this cell, returning the underlying data.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+
+    /// Replaces the value of the cell, returning the old value.
+    #[inline]
+    pub fn replace(&self, value: T, brand: &mut Brand<'id>) -> T {
+        std::mem::replace(self.borrow_mut(brand), value)
+    }
+}
+
+impl<'id, T: ?Sized> BrandedCell<'id, T> {
+    /// Immutably borrows the value of the cell.
+    #[inline]
+    pub fn borrow<'a>(&'a self, _brand: &'a Brand<'id>) -> &'a T {
+        // Safety: The brand is borrowed for as long as the value, so it can
+        // not be mutably borrowed through it.
+        unsafe { &*self.value.get() }
+    }
+
+    /// Mutably borrows the value of the cell.
+    #[inline]
+    pub fn borrow_mut<'a>(&'a self, _brand: &'a mut Brand<'id>) -> &'a mut T {
+        // Safety: The brand is mutably borrowed for as long as the value, so
+        // no other cell of the brand can be borrowed.
+        unsafe { &mut *self.value.get() }
+    }
+
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// Since this call borrows the cell mutably, no brand is needed.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut T {
+        self.value.get_mut()
+    }
+}
+
+impl<T: Default> Default for BrandedCell<'_, T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for BrandedCell<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BrandedCell").finish_non_exhaustive()
+    }
+}
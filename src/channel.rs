@@ -0,0 +1,381 @@
+//! A blocking, bounded multi-producer multi-consumer channel.
+//!
+//! A [`Channel`] is a queue of at most `N` values, stored inline, which any
+//! number of threads can send to and receive from. Sending blocks while the
+//! channel is full, and receiving blocks while it is empty. Like the other
+//! primitives of this crate, it is pinned and initialized in place, so that it
+//! needs no allocation of its own and can be embedded with other pinned state.
+//!
+//! There are no separate sender and receiver handles, so the channel is never
+//! disconnected by dropping them. Instead, it is [closed] explicitly, after
+//! which sending fails and receiving drains the values which are left. The
+//! errors are those of [`std::sync::mpsc`], where "disconnected" means closed.
+//!
+//! [closed]: Channel::close
+//!
+//! # Examples
+//!
+//! ```
+//! use pinned_sync::channel::Channel;
+//! use std::thread;
+//!
+//! let channel = Channel::<i32, 16>::arc();
+//!
+//! let consumers: Vec<_> = (0..4)
+//!     .map(|_| {
+//!         let channel = channel.clone();
+//!         thread::spawn(move || {
+//!             let mut sum = 0;
+//!             while let Ok(value) = channel.as_ref().recv() {
+//!                 sum += value;
+//!             }
+//!             sum
+//!         })
+//!     })
+//!     .collect();
+//!
+//! for value in 1..=100 {
+//!     channel.as_ref().send(value).unwrap();
+//! }
+//! channel.as_ref().close();
+//!
+//! let sum: i32 = consumers.into_iter().map(|c| c.join().unwrap()).sum();
+//! assert_eq!(sum, 5050);
+//! ```
+
+use crate::sys::{condvar, mutex};
+use std::cell::UnsafeCell;
+use std::fmt;
+use std::marker::PhantomPinned;
+use std::mem::MaybeUninit;
+use std::pin::Pin;
+use std::sync::mpsc::{RecvError, RecvTimeoutError, SendError, TryRecvError, TrySendError};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// A blocking, bounded channel of capacity `N`.
+///
+/// See the [module documentation](self) for details.
+pub struct Channel<T, const N: usize> {
+    lock: mutex::Mutex,
+    // Waited on by receivers while the channel is empty.
+    not_empty: condvar::Condvar,
+    // Waited on by senders while the channel is full.
+    not_full: condvar::Condvar,
+    state: UnsafeCell<State>,
+    // The values are in `head..head + len`, wrapping around at `N`.
+    buffer: UnsafeCell<MaybeUninit<[T; N]>>,
+    _p: PhantomPinned,
+}
+
+struct State {
+    head: usize,
+    len: usize,
+    closed: bool,
+}
+
+unsafe impl<T: Send, const N: usize> Send for Channel<T, N> {}
+unsafe impl<T: Send, const N: usize> Sync for Channel<T, N> {}
+
+impl<T, const N: usize> Channel<T, N> {
+    /// Create a new, uninitialized, empty channel.
+    ///
+    /// This is *NOT* equivalent to `MaybeUninit::uninit().assume_init()`, which will cause
+    /// undefined behaviour if used to create a new channel.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `N` is zero.
+    #[inline]
+    pub const fn uninit() -> Self {
+        assert!(N > 0, "channel capacity must not be zero");
+        Self {
+            lock: mutex::Mutex::uninit(),
+            not_empty: condvar::Condvar::uninit(),
+            not_full: condvar::Condvar::uninit(),
+            state: UnsafeCell::new(State {
+                head: 0,
+                len: 0,
+                closed: false,
+            }),
+            buffer: UnsafeCell::new(MaybeUninit::uninit()),
+            _p: PhantomPinned,
+        }
+    }
+
+    /// Create a new, initialized, empty channel.
+    ///
+    /// The resulting channel is wrapped and ready for use.
+    #[inline]
+    pub fn boxed() -> Pin<Box<Self>> {
+        let this = Box::pin(Self::uninit());
+        this.as_ref().init();
+        this
+    }
+
+    /// Create a new, initialized, empty channel.
+    ///
+    /// The resulting channel is wrapped and ready for use.
+    #[inline]
+    pub fn arc() -> Pin<Arc<Self>> {
+        let this = Arc::pin(Self::uninit());
+        this.as_ref().init();
+        this
+    }
+
+    /// Initialize a channel, making it ready for use.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the channel was already initialized.
+    #[inline]
+    pub fn init(self: Pin<&Self>) {
+        self.lock().init();
+        self.not_empty().init();
+        self.not_full().init();
+    }
+
+    /// Sends a value, blocking the current thread while the channel is full.
+    ///
+    /// # Errors
+    ///
+    /// If the channel is closed, the value is returned back in the error.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the channel is not initialized.
+    pub fn send(self: Pin<&Self>, value: T) -> Result<(), SendError<T>> {
+        let mut lock = self.lock().lock();
+        loop {
+            // Safety: the state is only accessed with the lock held.
+            let state = unsafe { &*self.state.get() };
+            if state.closed {
+                return Err(SendError(value));
+            }
+            if state.len < N {
+                break;
+            }
+            // Safety: the condvar is only ever used with `self.lock`.
+            lock = unsafe { self.not_full().wait(lock) };
+        }
+        unsafe { self.push(value) };
+        Ok(())
+    }
+
+    /// Attempts to send a value without blocking.
+    ///
+    /// # Errors
+    ///
+    /// If the channel is full or closed, the value is returned back in the
+    /// error.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the channel is not initialized.
+    pub fn try_send(self: Pin<&Self>, value: T) -> Result<(), TrySendError<T>> {
+        let _lock = self.lock().lock();
+        // Safety: the state is only accessed with the lock held.
+        let state = unsafe { &*self.state.get() };
+        if state.closed {
+            return Err(TrySendError::Disconnected(value));
+        }
+        if state.len == N {
+            return Err(TrySendError::Full(value));
+        }
+        unsafe { self.push(value) };
+        Ok(())
+    }
+
+    /// Receives a value, blocking the current thread while the channel is
+    /// empty.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the channel is closed and empty.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the channel is not initialized.
+    pub fn recv(self: Pin<&Self>) -> Result<T, RecvError> {
+        self.recv_deadline(None).map_err(|_| RecvError)
+    }
+
+    /// Attempts to receive a value without blocking.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the channel is empty, or closed and
+    /// empty.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the channel is not initialized.
+    pub fn try_recv(self: Pin<&Self>) -> Result<T, TryRecvError> {
+        let _lock = self.lock().lock();
+        // Safety: the state is only accessed with the lock held.
+        let state = unsafe { &*self.state.get() };
+        if state.len > 0 {
+            Ok(unsafe { self.pop() })
+        } else if state.closed {
+            Err(TryRecvError::Disconnected)
+        } else {
+            Err(TryRecvError::Empty)
+        }
+    }
+
+    /// Receives a value, blocking the current thread while the channel is
+    /// empty, or until the timeout expires.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the timeout expired, or if the
+    /// channel is closed and empty.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the channel is not initialized.
+    pub fn recv_timeout(self: Pin<&Self>, dur: Duration) -> Result<T, RecvTimeoutError> {
+        // A timeout which can not be represented is as good as no timeout.
+        self.recv_deadline(Instant::now().checked_add(dur))
+    }
+
+    /// Closes the channel.
+    ///
+    /// Every blocked sender fails, and so do the ones which come after. The
+    /// values which are left in the channel can still be received, after which
+    /// receiving fails as well. Closing a channel which is already closed does
+    /// nothing.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the channel is not initialized.
+    pub fn close(self: Pin<&Self>) {
+        let _lock = self.lock().lock();
+        // Safety: the state is only accessed with the lock held.
+        unsafe { (*self.state.get()).closed = true };
+        self.not_empty().notify_all();
+        self.not_full().notify_all();
+    }
+
+    /// Returns whether the channel is closed.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the channel is not initialized.
+    pub fn is_closed(self: Pin<&Self>) -> bool {
+        let _lock = self.lock().lock();
+        unsafe { (*self.state.get()).closed }
+    }
+
+    /// Returns the number of values in the channel.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the channel is not initialized.
+    pub fn len(self: Pin<&Self>) -> usize {
+        let _lock = self.lock().lock();
+        unsafe { (*self.state.get()).len }
+    }
+
+    /// Returns whether the channel is empty.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the channel is not initialized.
+    pub fn is_empty(self: Pin<&Self>) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the capacity of the channel, `N`.
+    #[inline]
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    fn recv_deadline(self: Pin<&Self>, deadline: Option<Instant>) -> Result<T, RecvTimeoutError> {
+        let mut lock = self.lock().lock();
+        loop {
+            // Safety: the state is only accessed with the lock held.
+            let state = unsafe { &*self.state.get() };
+            if state.len > 0 {
+                break;
+            }
+            if state.closed {
+                return Err(RecvTimeoutError::Disconnected);
+            }
+            // Safety: the condvar is only ever used with `self.lock`.
+            lock = match deadline {
+                None => unsafe { self.not_empty().wait(lock) },
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return Err(RecvTimeoutError::Timeout);
+                    }
+                    unsafe {
+                        self.not_empty()
+                            .get_ref()
+                            .wait_timeout(lock, deadline - now)
+                            .1
+                    }
+                }
+            };
+        }
+        Ok(unsafe { self.pop() })
+    }
+
+    // Must be called with the lock held, and the channel not full.
+    unsafe fn push(self: Pin<&Self>, value: T) {
+        let state = &mut *self.state.get();
+        self.slot((state.head + state.len) % N).write(value);
+        state.len += 1;
+        self.not_empty().notify_one();
+    }
+
+    // Must be called with the lock held, and the channel not empty.
+    unsafe fn pop(self: Pin<&Self>) -> T {
+        let state = &mut *self.state.get();
+        let value = self.slot(state.head).read();
+        state.head = (state.head + 1) % N;
+        state.len -= 1;
+        self.not_full().notify_one();
+        value
+    }
+
+    #[inline]
+    fn slot(&self, index: usize) -> *mut T {
+        debug_assert!(index < N);
+        unsafe { self.buffer.get().cast::<T>().add(index) }
+    }
+
+    #[inline]
+    fn lock(self: Pin<&Self>) -> Pin<&mutex::Mutex> {
+        unsafe { self.map_unchecked(|this| &this.lock) }
+    }
+
+    #[inline]
+    fn not_empty(self: Pin<&Self>) -> Pin<&condvar::Condvar> {
+        unsafe { self.map_unchecked(|this| &this.not_empty) }
+    }
+
+    #[inline]
+    fn not_full(self: Pin<&Self>) -> Pin<&condvar::Condvar> {
+        unsafe { self.map_unchecked(|this| &this.not_full) }
+    }
+}
+
+impl<T, const N: usize> Drop for Channel<T, N> {
+    fn drop(&mut self) {
+        let State { head, len, .. } = *self.state.get_mut();
+        for i in 0..len {
+            unsafe { self.slot((head + i) % N).drop_in_place() };
+        }
+    }
+}
+
+impl<T, const N: usize> fmt::Debug for Channel<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Channel")
+            .field("capacity", &N)
+            .finish_non_exhaustive()
+    }
+}
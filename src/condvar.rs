@@ -1,12 +1,19 @@
-use crate::sys::condvar as sys;
-use crate::MutexGuard;
+use crate::raw::{self, RawCondvar, RawRwLock};
+use crate::sys_common::clock::Timestamp;
+use crate::sys_common::wait_queue::WaitQueue;
+use crate::{LockId, LockResult, MutexGuard, PoisonError, RwLockReadGuard, RwLockWriteGuard};
+use std::fmt;
 use std::marker::PhantomPinned;
+use std::panic::{RefUnwindSafe, UnwindSafe};
 use std::pin::Pin;
+use std::ptr;
+use std::sync::atomic::{AtomicUsize, Ordering::Relaxed};
 use std::sync::Arc;
-use std::sync::LockResult;
-use std::sync::PoisonError;
-use std::time::Duration;
-use std::time::Instant;
+use std::time::{Duration, Instant};
+
+// How often suspend-aware timed waits check the boot clock, which bounds how
+// late they time out after the system resumes.
+const SUSPEND_CHECK_INTERVAL: Duration = Duration::from_secs(1);
 
 /// A type indicating whether a timed wait on a condition variable returned
 /// due to a time out or not.
@@ -15,7 +22,7 @@ use std::time::Instant;
 ///
 /// [`wait_timeout`]: Condvar::wait_timeout
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
-pub struct WaitTimeoutResult(bool);
+pub struct WaitTimeoutResult(pub(crate) bool);
 
 impl WaitTimeoutResult {
     /// Returns `true` if the wait was known to have timed out.
@@ -24,6 +31,85 @@ impl WaitTimeoutResult {
     }
 }
 
+/// Statistics about the wakeups of the predicate-based waits on a condition
+/// variable.
+///
+/// It is returned by the [`wakeup_stats`] method, for condition variables
+/// which were created with [`track_wakeups`] enabled.
+///
+/// A high number of spurious wakeups compared to the number of waits usually
+/// means that [`notify_all`] is used where [`notify_one`] would do, or that
+/// notifications are sent when the condition waited for did not change.
+///
+/// All counts wrap around on overflow.
+///
+/// [`wakeup_stats`]: Condvar::wakeup_stats
+/// [`track_wakeups`]: Condvar::track_wakeups
+/// [`notify_all`]: Condvar::notify_all
+/// [`notify_one`]: Condvar::notify_one
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+pub struct WakeupStats {
+    waits: usize,
+    wakeups: usize,
+    spurious_wakeups: usize,
+}
+
+impl WakeupStats {
+    /// Returns the number of calls to [`wait_while`] and
+    /// [`wait_timeout_while`] which returned without an error.
+    ///
+    /// [`wait_while`]: Condvar::wait_while
+    /// [`wait_timeout_while`]: Condvar::wait_timeout_while
+    pub fn waits(&self) -> usize {
+        self.waits
+    }
+
+    /// Returns the number of times those calls were woken up, including the
+    /// wakeups after which the condition was satisfied.
+    pub fn wakeups(&self) -> usize {
+        self.wakeups
+    }
+
+    /// Returns the number of times those calls were woken up and found the
+    /// condition still unsatisfied.
+    pub fn spurious_wakeups(&self) -> usize {
+        self.spurious_wakeups
+    }
+}
+
+struct WakeupCounters {
+    enabled: bool,
+    waits: AtomicUsize,
+    wakeups: AtomicUsize,
+    spurious_wakeups: AtomicUsize,
+}
+
+impl WakeupCounters {
+    const fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            waits: AtomicUsize::new(0),
+            wakeups: AtomicUsize::new(0),
+            spurious_wakeups: AtomicUsize::new(0),
+        }
+    }
+
+    #[inline]
+    fn record(&self, wakeups: usize, satisfied: bool) {
+        if self.enabled {
+            // Only the last wakeup can have satisfied the condition.
+            let spurious = if satisfied && wakeups > 0 {
+                wakeups - 1
+            } else {
+                wakeups
+            };
+            self.waits.fetch_add(1, Relaxed);
+            self.wakeups.fetch_add(wakeups, Relaxed);
+            self.spurious_wakeups.fetch_add(spurious, Relaxed);
+        }
+    }
+}
+
 /// A Condition Variable
 ///
 /// Condition variables represent the ability to block a thread such that it
@@ -35,11 +121,33 @@ impl WaitTimeoutResult {
 /// Functions in this module will block the current **thread** of execution.
 /// Note that any attempt to use multiple mutexes on the same condition
 /// variable may result in a runtime panic.
-pub struct Condvar {
-    inner: sys::Condvar,
+///
+/// # Backends
+///
+/// The condition variable itself is provided by the backend `B`, which
+/// defaults to the [`raw::Condvar`] of the platform, and waits with the
+/// guards of a [`Mutex`] with the matching backend. Condition variables with
+/// another backend are created with [`with_backend`], and otherwise behave
+/// the same.
+///
+/// [`Mutex`]: crate::Mutex
+/// [`with_backend`]: Self::with_backend
+pub struct Condvar<B: RawCondvar = raw::Condvar> {
+    inner: B,
+    counters: WakeupCounters,
+    queue: WaitQueue,
+    // The number of threads waiting on `queue` with a read-write lock, which
+    // the platform condvar can not wait on. Notifications go to `queue` while
+    // it is not zero, even if fair wakeups are disabled.
+    rwlock_waiters: AtomicUsize,
+    suspend_aware: bool,
     _p: PhantomPinned,
 }
 
+impl<B: RawCondvar> UnwindSafe for Condvar<B> {}
+
+impl<B: RawCondvar> RefUnwindSafe for Condvar<B> {}
+
 impl Condvar {
     /// Create a new, uninitialized condvar.
     ///
@@ -47,20 +155,33 @@ impl Condvar {
     /// undefined behaviour if used to create a new condvar.
     #[inline]
     pub const fn uninit() -> Self {
-        Self {
-            inner: sys::Condvar::uninit(),
-            _p: PhantomPinned,
-        }
+        Self::with_backend()
     }
 
-    /// Initialize a condvar, making it ready for use.
-    ///
-    /// # Panics
-    ///
-    /// This function may panic if the condvar was already initialized.
-    #[inline]
-    pub fn init(self: Pin<&Self>) {
-        self.inner().init()
+    cfg_static_new! {
+        /// Create a new, initialized condvar, in a `const` context.
+        ///
+        /// Unlike one created with [`uninit`](Self::uninit), the condvar is
+        /// ready for use as soon as it is pinned, and must not be initialized
+        /// with [`init`](Self::init). This makes it possible to declare a
+        /// condvar as a `static` without initializing it at runtime.
+        ///
+        /// This is only available on the backends whose primitives are complete
+        /// when constructed, which are all of them except for the generic
+        /// pthread backend and ESP-IDF.
+        // Not `Default`, which would be missing on the other backends.
+        #[allow(clippy::new_without_default)]
+        #[inline]
+        pub const fn new() -> Self {
+            Self {
+                inner: raw::Condvar::new(),
+                counters: WakeupCounters::new(false),
+                queue: WaitQueue::new(false),
+                rwlock_waiters: AtomicUsize::new(0),
+                suspend_aware: false,
+                _p: PhantomPinned,
+            }
+        }
     }
 
     /// Create a new, initialized condition variable.
@@ -72,7 +193,7 @@ impl Condvar {
         this.as_ref().init();
         this
     }
-    
+
     /// Create a new, initialized condition variable.
     ///
     /// The resulting condition variable is wrapped and ready for use.
@@ -83,6 +204,148 @@ impl Condvar {
         this
     }
 
+    cfg_interruptible! {
+        /// Blocks the current thread until this condition variable receives a
+        /// notification or a signal is handled by the thread.
+        ///
+        /// This is the same as [`wait`], except that the wait is given up if a
+        /// signal handler installed without `SA_RESTART` runs while the thread
+        /// is blocked. This lets a handler which only sets a flag break a
+        /// thread out of a long wait, such as to shut down.
+        ///
+        /// With [`fair`] wakeups, the thread is parked instead of waiting on
+        /// the platform condition variable, and signals are not noticed.
+        ///
+        /// This is only available on the Linux futex backend.
+        ///
+        /// # Errors
+        ///
+        /// If the wait was interrupted by a signal, [`LockError::Interrupted`]
+        /// is returned, and the mutex is unlocked. This function will return an
+        /// error if the mutex being waited on is poisoned when this thread
+        /// re-acquires the lock.
+        ///
+        /// # Panics
+        ///
+        /// This function may [`panic!`] if it is used with more than one mutex
+        /// over time.
+        ///
+        /// [`wait`]: Self::wait
+        /// [`fair`]: Self::fair
+        /// [`LockError::Interrupted`]: crate::LockError::Interrupted
+        pub fn wait_interruptible<'a, T>(
+            self: Pin<&Self>,
+            lock: MutexGuard<'a, T>,
+        ) -> Result<MutexGuard<'a, T>, crate::LockError<MutexGuard<'a, T>>> {
+            if self.queue.enabled() {
+                return Ok(self.wait(lock)?);
+            }
+            let mut interrupted = false;
+            let lock = lock.map(|guard| match self.inner().wait_interruptible(guard) {
+                Ok(guard) => guard,
+                Err(guard) => {
+                    interrupted = true;
+                    guard
+                }
+            });
+            if interrupted {
+                drop(lock);
+                return Err(crate::LockError::Interrupted);
+            }
+            Ok(lock?)
+        }
+    }
+}
+
+impl<B: RawCondvar> Condvar<B> {
+    /// Create a new, uninitialized condvar using the backend `B`.
+    ///
+    /// This is *NOT* equivalent to `MaybeUninit::uninit().assume_init()`, which will cause
+    /// undefined behaviour if used to create a new condvar.
+    #[inline]
+    pub const fn with_backend() -> Self {
+        Self {
+            inner: B::UNINIT,
+            counters: WakeupCounters::new(false),
+            queue: WaitQueue::new(false),
+            rwlock_waiters: AtomicUsize::new(0),
+            suspend_aware: false,
+            _p: PhantomPinned,
+        }
+    }
+
+    /// Enables or disables wakeup tracking for this condvar.
+    ///
+    /// When it is enabled, [`wait_while`] and [`wait_timeout_while`] count how
+    /// many times they were woken up before the condition was satisfied, which
+    /// can be read with [`wakeup_stats`]. This costs a few atomic operations
+    /// per call.
+    ///
+    /// Wakeup tracking is disabled by default.
+    ///
+    /// This must be called before the condvar is pinned.
+    ///
+    /// [`wait_while`]: Self::wait_while
+    /// [`wait_timeout_while`]: Self::wait_timeout_while
+    /// [`wakeup_stats`]: Self::wakeup_stats
+    #[inline]
+    pub fn track_wakeups(mut self, enabled: bool) -> Self {
+        self.counters = WakeupCounters::new(enabled);
+        self
+    }
+
+    /// Enables or disables first-in, first-out wakeups for this condvar.
+    ///
+    /// When it is enabled, [`notify_one`] always wakes up the thread which
+    /// has been waiting the longest, and [`notify_all`] wakes up the waiting
+    /// threads in the order they started waiting, on every platform. This
+    /// avoids starving specific waiters in fairness-sensitive code, such as
+    /// queues, where the order of the platform is unspecified. The waiters are
+    /// then queued by this condvar instead of the platform, which costs an
+    /// allocation per wait.
+    ///
+    /// Fair wakeups are disabled by default.
+    ///
+    /// This must be called before the condvar is pinned.
+    ///
+    /// [`notify_one`]: Self::notify_one
+    /// [`notify_all`]: Self::notify_all
+    #[inline]
+    pub fn fair(mut self, enabled: bool) -> Self {
+        self.queue = WaitQueue::new(enabled);
+        self
+    }
+
+    /// Makes the timeouts of this condvar include the time the system spends
+    /// suspended.
+    ///
+    /// By default, timeouts are measured on a clock which stops while the
+    /// system is suspended, so a 30 second timeout can stretch across hours
+    /// of sleep. When this is enabled, they are measured against
+    /// `CLOCK_BOOTTIME` instead, and a wait whose deadline passed during
+    /// suspension times out at most about a second after the system resumes.
+    /// To do so, timed waits wake up once a second to check the time.
+    ///
+    /// This only has an effect on Linux and Android, where `CLOCK_BOOTTIME` is
+    /// available.
+    ///
+    /// This must be called before the condvar is pinned.
+    #[inline]
+    pub fn suspend_aware(mut self, enabled: bool) -> Self {
+        self.suspend_aware = enabled;
+        self
+    }
+
+    /// Initialize a condvar, making it ready for use.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the condvar was already initialized.
+    #[inline]
+    pub fn init(self: Pin<&Self>) {
+        self.inner().init()
+    }
+
     /// Wakes up one blocked thread on this condvar.
     ///
     /// If there is a blocked thread on this condition variable, then it will
@@ -100,7 +363,11 @@ impl Condvar {
     /// [`notify_all`]: Self::notify_all
     #[inline]
     pub fn notify_one(self: Pin<&Self>) {
-        self.inner().notify_one()
+        if self.queued() {
+            self.get_ref().queue.notify_one()
+        } else {
+            self.inner().notify_one()
+        }
     }
 
     /// Wakes up all blocked threads on this condvar.
@@ -118,7 +385,84 @@ impl Condvar {
     /// [`notify_one`]: Self::notify_one
     #[inline]
     pub fn notify_all(self: Pin<&Self>) {
-        self.inner().notify_all()
+        if self.queued() {
+            self.get_ref().queue.notify_all()
+        } else {
+            self.inner().notify_all()
+        }
+    }
+
+    /// Wakes up at most `n` blocked threads on this condvar.
+    ///
+    /// This is for handing out work to a pool of threads: waking up as many
+    /// threads as there are new tasks, rather than all of them with
+    /// [`notify_all`], spares the others from waking up only to find nothing
+    /// to do.
+    ///
+    /// Where the condvar is built on a futex, or on a queue of its own, the
+    /// threads are woken up in one call. Elsewhere, such as with pthread
+    /// condition variables, this is a loop of [`notify_one`], which costs one
+    /// call per thread to wake up whether it is waiting or not, so a very
+    /// large `n` is better served by [`notify_all`].
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the condvar is not initialized.
+    ///
+    /// [`notify_one`]: Self::notify_one
+    /// [`notify_all`]: Self::notify_all
+    #[inline]
+    pub fn notify_many(self: Pin<&Self>, n: usize) {
+        if n == 0 {
+            return;
+        }
+        if self.queued() {
+            self.get_ref().queue.notify_many(n)
+        } else {
+            self.inner().notify_many(n)
+        }
+    }
+
+    // Whether the waiters are on `queue` rather than the platform condvar.
+    #[inline]
+    fn queued(self: Pin<&Self>) -> bool {
+        // A waiter counts itself before it releases its lock, so a thread which
+        // acquired the lock since and then notifies sees it.
+        self.queue.enabled() || self.rwlock_waiters.load(Relaxed) != 0
+    }
+
+    #[inline]
+    fn notify(self: Pin<&Self>, all: bool) {
+        if all {
+            self.notify_all()
+        } else {
+            self.notify_one()
+        }
+    }
+
+    /// Returns the identifier of this condvar, which stays the same for as long
+    /// as it is alive.
+    #[inline]
+    pub fn id(self: Pin<&Self>) -> LockId {
+        LockId::of(self.get_ref())
+    }
+
+    /// Returns the wakeup statistics of this condvar, or [`None`] if wakeup
+    /// tracking is disabled.
+    ///
+    /// See [`track_wakeups`].
+    ///
+    /// [`track_wakeups`]: Self::track_wakeups
+    pub fn wakeup_stats(self: Pin<&Self>) -> Option<WakeupStats> {
+        let counters = &self.get_ref().counters;
+        if !counters.enabled {
+            return None;
+        }
+        Some(WakeupStats {
+            waits: counters.waits.load(Relaxed),
+            wakeups: counters.wakeups.load(Relaxed),
+            spurious_wakeups: counters.spurious_wakeups.load(Relaxed),
+        })
     }
 
     /// Blocks the current thread until this condition variable receives a
@@ -152,8 +496,99 @@ impl Condvar {
     /// [`notify_all`]: Self::notify_all
     /// [poisoning]: super::Mutex#poisoning
     /// [`Mutex`]: super::Mutex
-    pub fn wait<'a, T>(self: Pin<&Self>, lock: MutexGuard<'a, T>) -> LockResult<MutexGuard<'a, T>> {
-        lock.map(|guard| unsafe { self.inner().wait(guard) })
+    pub fn wait<'a, T>(
+        self: Pin<&Self>,
+        lock: MutexGuard<'a, T, B::Mutex>,
+    ) -> LockResult<MutexGuard<'a, T, B::Mutex>> {
+        if self.queue.enabled() {
+            let waiter = self.queue.push();
+            return lock.unlocked(|| {
+                self.queue.park(&waiter, None);
+            });
+        }
+        lock.map(|guard| self.inner().wait(guard))
+    }
+
+    /// Blocks the current thread until this condition variable receives a
+    /// notification, releasing the read lock of an [`RwLock`] in the
+    /// meantime.
+    ///
+    /// This is the equivalent of [`wait`] for a state protected by an
+    /// [`RwLock`], so that threads which only read it can wait for it to
+    /// change. The read lock is released once the current thread is queued on
+    /// this condvar, so that any calls to [`notify_one`] or [`notify_all`]
+    /// which happen logically after the lock is released are candidates to
+    /// wake it up. When this function call returns, the read lock will have
+    /// been re-acquired.
+    ///
+    /// The platform condition variables can only wait with a mutex, so the
+    /// waiters are queued by this condvar instead, as with [`fair`] wakeups.
+    /// A condvar which is waited on with a read-write lock must not be waited
+    /// on with a mutex as well. Like [`wait`], this function is susceptible to
+    /// spurious wakeups.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the lock is poisoned when this
+    /// thread re-acquires it.
+    ///
+    /// # Panics
+    ///
+    /// This function may [`panic!`] if it is used with more than one lock over
+    /// time.
+    ///
+    /// This function may panic if the condvar is not initialized.
+    ///
+    /// [`RwLock`]: crate::RwLock
+    /// [`wait`]: Self::wait
+    /// [`notify_one`]: Self::notify_one
+    /// [`notify_all`]: Self::notify_all
+    /// [`fair`]: Self::fair
+    pub fn wait_read<'a, T: ?Sized, R: RawRwLock>(
+        self: Pin<&Self>,
+        guard: RwLockReadGuard<'a, T, R>,
+    ) -> LockResult<RwLockReadGuard<'a, T, R>> {
+        self.rwlock_waiters.fetch_add(1, Relaxed);
+        let waiter = self.queue.push();
+        RwLockReadGuard::unlocked(guard, || {
+            self.queue.park(&waiter, None);
+            self.rwlock_waiters.fetch_sub(1, Relaxed);
+        })
+    }
+
+    /// Blocks the current thread until this condition variable receives a
+    /// notification, releasing the write lock of an [`RwLock`] in the
+    /// meantime.
+    ///
+    /// See [`wait_read`], which this is the equivalent of for a write lock.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the lock is poisoned when this
+    /// thread re-acquires it.
+    ///
+    /// # Panics
+    ///
+    /// This function may [`panic!`] if it is used with more than one lock over
+    /// time.
+    ///
+    /// This function panics if the lock is [frozen] while waiting.
+    ///
+    /// This function may panic if the condvar is not initialized.
+    ///
+    /// [`RwLock`]: crate::RwLock
+    /// [`wait_read`]: Self::wait_read
+    /// [frozen]: crate::RwLock::freeze
+    pub fn wait_write<'a, T: ?Sized, R: RawRwLock>(
+        self: Pin<&Self>,
+        guard: RwLockWriteGuard<'a, T, R>,
+    ) -> LockResult<RwLockWriteGuard<'a, T, R>> {
+        self.rwlock_waiters.fetch_add(1, Relaxed);
+        let waiter = self.queue.push();
+        RwLockWriteGuard::unlocked(guard, || {
+            self.queue.park(&waiter, None);
+            self.rwlock_waiters.fetch_sub(1, Relaxed);
+        })
     }
 
     /// Blocks the current thread until this condition variable receives a
@@ -185,15 +620,18 @@ impl Condvar {
     /// [`Mutex`]: super::Mutex
     pub fn wait_while<'a, T, F>(
         self: Pin<&Self>,
-        mut guard: MutexGuard<'a, T>,
+        mut guard: MutexGuard<'a, T, B::Mutex>,
         mut condition: F,
-    ) -> LockResult<MutexGuard<'a, T>>
+    ) -> LockResult<MutexGuard<'a, T, B::Mutex>>
     where
         F: FnMut(&mut T) -> bool,
     {
+        let mut wakeups = 0;
         while condition(&mut *guard) {
             guard = self.wait(guard)?;
+            wakeups += 1;
         }
+        self.counters.record(wakeups, true);
         Ok(guard)
     }
 
@@ -234,15 +672,54 @@ impl Condvar {
     /// [`wait_timeout_while`]: Self::wait_timeout_while
     pub fn wait_timeout<'a, T>(
         self: Pin<&Self>,
-        lock: MutexGuard<'a, T>,
+        lock: MutexGuard<'a, T, B::Mutex>,
         dur: Duration,
-    ) -> LockResult<(MutexGuard<'a, T>, WaitTimeoutResult)> {
+    ) -> LockResult<(MutexGuard<'a, T, B::Mutex>, WaitTimeoutResult)> {
+        let start = Timestamp::now(self.suspend_aware);
+        if start.is_suspend_aware() {
+            return self.wait_timeout_since(lock, start, dur);
+        }
+        self.wait_timeout_monotonic(lock, dur)
+    }
+
+    // Waits in slices, so that the time spent suspended is noticed.
+    fn wait_timeout_since<'a, T>(
+        self: Pin<&Self>,
+        mut lock: MutexGuard<'a, T, B::Mutex>,
+        start: Timestamp,
+        dur: Duration,
+    ) -> LockResult<(MutexGuard<'a, T, B::Mutex>, WaitTimeoutResult)> {
+        loop {
+            let remaining = match dur.checked_sub(start.elapsed()) {
+                Some(remaining) => remaining,
+                None => return Ok((lock, WaitTimeoutResult(true))),
+            };
+            let timeout = remaining.min(SUSPEND_CHECK_INTERVAL);
+            let (guard, result) = self.wait_timeout_monotonic(lock, timeout)?;
+            if !result.timed_out() {
+                return Ok((guard, result));
+            }
+            lock = guard;
+        }
+    }
+
+    fn wait_timeout_monotonic<'a, T>(
+        self: Pin<&Self>,
+        lock: MutexGuard<'a, T, B::Mutex>,
+        dur: Duration,
+    ) -> LockResult<(MutexGuard<'a, T, B::Mutex>, WaitTimeoutResult)> {
         let mut timeout = false;
-        match lock.map(|guard| unsafe {
-            let (ok, guard) = self.inner().wait_timeout(guard, dur);
-            timeout = !ok;
-            guard
-        }) {
+        let lock = if self.queue.enabled() {
+            let waiter = self.queue.push();
+            lock.unlocked(|| timeout = !self.queue.park(&waiter, Some(dur)))
+        } else {
+            lock.map(|guard| {
+                let (guard, timed_out) = self.inner().wait_timeout(guard, dur);
+                timeout = timed_out;
+                guard
+            })
+        };
+        match lock {
             Ok(v) => Ok((v, WaitTimeoutResult(timeout))),
             Err(v) => Err(PoisonError::new((
                 v.into_inner(),
@@ -281,28 +758,158 @@ impl Condvar {
     /// [`wait_timeout`]: Self::wait_timeout
     pub fn wait_timeout_while<'a, T, F>(
         self: Pin<&Self>,
-        mut guard: MutexGuard<'a, T>,
+        mut guard: MutexGuard<'a, T, B::Mutex>,
         dur: Duration,
         mut condition: F,
-    ) -> LockResult<(MutexGuard<'a, T>, WaitTimeoutResult)>
+    ) -> LockResult<(MutexGuard<'a, T, B::Mutex>, WaitTimeoutResult)>
     where
         F: FnMut(&mut T) -> bool,
     {
-        let start = Instant::now();
+        let start = Timestamp::now(self.suspend_aware);
+        let mut wakeups = 0;
         loop {
             if !condition(&mut *guard) {
+                self.counters.record(wakeups, true);
                 return Ok((guard, WaitTimeoutResult(false)));
             }
             let timeout = match dur.checked_sub(start.elapsed()) {
                 Some(timeout) => timeout,
-                None => return Ok((guard, WaitTimeoutResult(true))),
+                None => {
+                    self.counters.record(wakeups, false);
+                    return Ok((guard, WaitTimeoutResult(true)));
+                }
             };
-            guard = self.wait_timeout(guard, timeout)?.0;
+            let (g, result) = self.wait_timeout(guard, timeout)?;
+            guard = g;
+            if !result.timed_out() {
+                wakeups += 1;
+            }
+        }
+    }
+
+    /// Waits on this condition variable for a notification, timing out once
+    /// a deadline is reached.
+    ///
+    /// The semantics of this function are equivalent to [`wait_timeout`],
+    /// except that the deadline is absolute, so that several waits can share
+    /// a single deadline without recomputing the time left for each of them.
+    /// If the deadline has already been reached, this returns a timeout
+    /// right away, after releasing and acquiring the lock again.
+    ///
+    /// The deadline is on the clock of [`Instant`], so it is not affected by
+    /// [`suspend_aware`].
+    ///
+    /// The returned [`WaitTimeoutResult`] value indicates if the timeout is
+    /// known to have elapsed.
+    ///
+    /// # Panics
+    ///
+    /// This function may [`panic!`] if it is used with more than one mutex
+    /// over time.
+    ///
+    /// This function may panic if the condvar is not initialized.
+    ///
+    /// [`wait_timeout`]: Self::wait_timeout
+    /// [`suspend_aware`]: Self::suspend_aware
+    pub fn wait_until<'a, T>(
+        self: Pin<&Self>,
+        lock: MutexGuard<'a, T, B::Mutex>,
+        deadline: Instant,
+    ) -> LockResult<(MutexGuard<'a, T, B::Mutex>, WaitTimeoutResult)> {
+        self.wait_timeout_monotonic(lock, deadline.saturating_duration_since(Instant::now()))
+    }
+
+    /// Waits on this condition variable for a notification, timing out once
+    /// a deadline is reached.
+    ///
+    /// The semantics of this function are equivalent to [`wait_timeout_while`],
+    /// except that the deadline is absolute. See [`wait_until`].
+    ///
+    /// The returned [`WaitTimeoutResult`] value indicates if the deadline was
+    /// reached without the condition being met.
+    ///
+    /// # Panics
+    ///
+    /// This function may [`panic!`] if it is used with more than one mutex
+    /// over time.
+    ///
+    /// This function may panic if the condvar is not initialized.
+    ///
+    /// [`wait_timeout_while`]: Self::wait_timeout_while
+    /// [`wait_until`]: Self::wait_until
+    pub fn wait_while_until<'a, T, F>(
+        self: Pin<&Self>,
+        mut guard: MutexGuard<'a, T, B::Mutex>,
+        deadline: Instant,
+        mut condition: F,
+    ) -> LockResult<(MutexGuard<'a, T, B::Mutex>, WaitTimeoutResult)>
+    where
+        F: FnMut(&mut T) -> bool,
+    {
+        let mut wakeups = 0;
+        loop {
+            if !condition(&mut *guard) {
+                self.counters.record(wakeups, true);
+                return Ok((guard, WaitTimeoutResult(false)));
+            }
+            if Instant::now() >= deadline {
+                self.counters.record(wakeups, false);
+                return Ok((guard, WaitTimeoutResult(true)));
+            }
+            let (g, result) = self.wait_until(guard, deadline)?;
+            guard = g;
+            if !result.timed_out() {
+                wakeups += 1;
+            }
         }
     }
 
     #[inline]
-    fn inner(self: Pin<&Self>) -> Pin<&sys::Condvar> {
+    fn inner(self: Pin<&Self>) -> Pin<&B> {
         unsafe { self.map_unchecked(|this| &this.inner) }
     }
 }
+
+/// A notification of a condvar deferred until a mutex is unlocked.
+///
+/// See [`MutexGuard::notify_on_unlock`].
+pub(crate) struct PendingNotify<'a>(Option<(Pin<&'a Condvar>, bool)>);
+
+impl<'a> PendingNotify<'a> {
+    #[inline]
+    pub(crate) const fn new() -> Self {
+        Self(None)
+    }
+
+    /// Adds a notification, upgrading a pending notification of the same
+    /// condvar to `notify_all` if `all` is set.
+    pub(crate) fn add(&mut self, condvar: Pin<&'a Condvar>, all: bool) {
+        match &mut self.0 {
+            Some((pending, pending_all)) if ptr::eq(pending.get_ref(), condvar.get_ref()) => {
+                *pending_all |= all;
+            }
+            pending => {
+                // Only one condvar is deferred, so a notification of another
+                // one is sent right away.
+                if let Some((condvar, all)) = pending.replace((condvar, all)) {
+                    condvar.notify(all);
+                }
+            }
+        }
+    }
+}
+
+impl Drop for PendingNotify<'_> {
+    #[inline]
+    fn drop(&mut self) {
+        if let Some((condvar, all)) = self.0.take() {
+            condvar.notify(all);
+        }
+    }
+}
+
+impl<B: RawCondvar> fmt::Pointer for Condvar<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Pointer::fmt(&LockId::of(self), f)
+    }
+}
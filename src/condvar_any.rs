@@ -0,0 +1,211 @@
+use crate::sys::{condvar, mutex};
+use crate::WaitTimeoutResult;
+use std::fmt;
+use std::marker::PhantomPinned;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A lock guard which can be released and acquired again, so that it can be
+/// waited on with a [`CondvarAny`].
+///
+/// This is implemented for the guards of [`Mutex`], and so of [`FairMutex`],
+/// of [`ReentrantMutex`] and of [`SpinLock`], and can be implemented for the
+/// guards of other locks.
+///
+/// [`Mutex`]: crate::Mutex
+/// [`FairMutex`]: crate::FairMutex
+/// [`ReentrantMutex`]: crate::ReentrantMutex
+/// [`SpinLock`]: crate::SpinLock
+pub trait Unlockable: Sized {
+    /// What acquiring the lock again results in, such as
+    /// [`LockResult<Self>`] for a lock which can be poisoned.
+    ///
+    /// [`LockResult<Self>`]: crate::LockResult
+    type Relocked;
+
+    /// Releases the lock, calls `f`, and acquires the lock again.
+    fn with_unlocked(self, f: impl FnOnce()) -> Self::Relocked;
+}
+
+/// A condition variable which can be used with any lock.
+///
+/// A [`Condvar`] only waits on the guards of a [`Mutex`]. This is the
+/// equivalent of C++'s `std::condition_variable_any`: it waits on any guard
+/// implementing [`Unlockable`], such as the guard of a [`ReentrantMutex`], a
+/// [`FairMutex`], or a lock of another crate.
+///
+/// This is a condition variable with a mutex of its own, which is held from
+/// before the lock of the waiter is released until the waiter is blocked, and
+/// by the notifying threads. A notification which is sent after the waiter
+/// released its lock is thus never missed, but every wait and notification
+/// takes that mutex, which makes it somewhat slower than a [`Condvar`].
+///
+/// Like a [`Condvar`], a `CondvarAny` may wake up spuriously, and the
+/// condition waited for must be checked again in a loop.
+///
+/// [`Condvar`]: crate::Condvar
+/// [`Mutex`]: crate::Mutex
+/// [`ReentrantMutex`]: crate::ReentrantMutex
+/// [`FairMutex`]: crate::FairMutex
+///
+/// # Examples
+///
+/// ```
+/// use pinned_sync::{CondvarAny, FairMutex};
+/// use std::thread;
+///
+/// let mutex = FairMutex::arc(false);
+/// let condvar = CondvarAny::arc();
+/// let (mutex2, condvar2) = (mutex.clone(), condvar.clone());
+///
+/// thread::spawn(move || {
+///     *mutex2.as_ref().lock().unwrap() = true;
+///     condvar2.as_ref().notify_one();
+/// });
+///
+/// let mut started = mutex.as_ref().lock().unwrap();
+/// while !*started {
+///     started = condvar.as_ref().wait(started).unwrap();
+/// }
+/// ```
+pub struct CondvarAny {
+    lock: mutex::Mutex,
+    cvar: condvar::Condvar,
+    _p: PhantomPinned,
+}
+
+unsafe impl Send for CondvarAny {}
+unsafe impl Sync for CondvarAny {}
+
+impl CondvarAny {
+    /// Create a new, uninitialized condition variable.
+    ///
+    /// This is *NOT* equivalent to `MaybeUninit::uninit().assume_init()`, which will cause
+    /// undefined behaviour if used to create a new condition variable.
+    #[inline]
+    pub const fn uninit() -> Self {
+        Self {
+            lock: mutex::Mutex::uninit(),
+            cvar: condvar::Condvar::uninit(),
+            _p: PhantomPinned,
+        }
+    }
+
+    /// Create a new, initialized condition variable.
+    ///
+    /// The resulting condition variable is wrapped and ready for use.
+    #[inline]
+    pub fn boxed() -> Pin<Box<Self>> {
+        let this = Box::pin(Self::uninit());
+        this.as_ref().init();
+        this
+    }
+
+    /// Create a new, initialized condition variable.
+    ///
+    /// The resulting condition variable is wrapped and ready for use.
+    #[inline]
+    pub fn arc() -> Pin<Arc<Self>> {
+        let this = Arc::pin(Self::uninit());
+        this.as_ref().init();
+        this
+    }
+
+    /// Initialize a condition variable, making it ready for use.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the condition variable was already
+    /// initialized.
+    #[inline]
+    pub fn init(self: Pin<&Self>) {
+        self.lock().init();
+        self.cvar().init();
+    }
+
+    /// Blocks the current thread until this condition variable receives a
+    /// notification, releasing the lock of `guard` in the meantime.
+    ///
+    /// The lock is acquired again before returning, with the result of
+    /// [`Unlockable::with_unlocked`].
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the condition variable is not initialized.
+    pub fn wait<G: Unlockable>(self: Pin<&Self>, guard: G) -> G::Relocked {
+        let lock = self.lock().lock();
+        guard.with_unlocked(move || {
+            // Safety: the condvar is only ever used with `self.lock`.
+            let lock = unsafe { self.cvar().wait(lock) };
+            // Released before the lock of the guard is acquired again, which
+            // would otherwise be acquired in the opposite order of `wait`.
+            drop(lock);
+        })
+    }
+
+    /// Blocks the current thread until this condition variable receives a
+    /// notification or the timeout expires, releasing the lock of `guard` in
+    /// the meantime.
+    ///
+    /// The lock is acquired again before returning, with the result of
+    /// [`Unlockable::with_unlocked`]. The returned [`WaitTimeoutResult`]
+    /// indicates if the timeout is known to have elapsed.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the condition variable is not initialized.
+    pub fn wait_timeout<G: Unlockable>(
+        self: Pin<&Self>,
+        guard: G,
+        dur: Duration,
+    ) -> (G::Relocked, WaitTimeoutResult) {
+        let lock = self.lock().lock();
+        let mut notified = false;
+        let guard = guard.with_unlocked(|| {
+            // Safety: the condvar is only ever used with `self.lock`.
+            let (woken, lock) = unsafe { self.cvar().get_ref().wait_timeout(lock, dur) };
+            notified = woken;
+            drop(lock);
+        });
+        (guard, WaitTimeoutResult(!notified))
+    }
+
+    /// Wakes up one blocked thread on this condition variable, if any.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the condition variable is not initialized.
+    pub fn notify_one(self: Pin<&Self>) {
+        // Taking the lock makes sure that a waiter which released its own lock
+        // is blocked on the condvar already.
+        let _lock = self.lock().lock();
+        self.cvar().notify_one();
+    }
+
+    /// Wakes up all blocked threads on this condition variable.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the condition variable is not initialized.
+    pub fn notify_all(self: Pin<&Self>) {
+        let _lock = self.lock().lock();
+        self.cvar().notify_all();
+    }
+
+    #[inline]
+    fn lock(self: Pin<&Self>) -> Pin<&mutex::Mutex> {
+        unsafe { self.map_unchecked(|this| &this.lock) }
+    }
+
+    #[inline]
+    fn cvar(self: Pin<&Self>) -> Pin<&condvar::Condvar> {
+        unsafe { self.map_unchecked(|this| &this.cvar) }
+    }
+}
+
+impl fmt::Debug for CondvarAny {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("CondvarAny { .. }")
+    }
+}
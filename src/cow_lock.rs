@@ -0,0 +1,222 @@
+use crate::{Mutex, MutexGuard, Primitives};
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::thread;
+
+/// A copy-on-write lock, giving readers snapshots of the data.
+///
+/// Readers get an [`Arc`] of the current value with [`read`], which they can
+/// keep for as long as they like. Writers never modify a value which readers
+/// can see: [`write`] clones the current value, and the modified copy is
+/// published when the guard is dropped. Readers only wait for the pointer to
+/// the value to be cloned or replaced, never for a writer to finish.
+///
+/// This suits data which is read much more often than it is written, such as
+/// configuration or routing tables, and is cheap enough to clone.
+///
+/// There is no poisoning: if a writer panics, its copy is discarded and the
+/// published value is left as it was.
+///
+/// [`read`]: Self::read
+/// [`write`]: Self::write
+///
+/// # Examples
+///
+/// ```
+/// use pinned_sync::CowLock;
+///
+/// let routes = CowLock::boxed(vec!["/"]);
+/// let before = routes.as_ref().read();
+///
+/// routes.as_ref().write().push("/about");
+///
+/// assert_eq!(*before, ["/"]);
+/// assert_eq!(*routes.as_ref().read(), ["/", "/about"]);
+/// ```
+pub struct CowLock<T> {
+    current: Mutex<Arc<T>>,
+    writer: Mutex<()>,
+}
+
+impl<T> CowLock<T> {
+    /// Create a new, uninitialized copy-on-write lock.
+    ///
+    /// This is *NOT* equivalent to `MaybeUninit::uninit().assume_init()`, which will cause
+    /// undefined behaviour if used to create a new copy-on-write lock.
+    #[inline]
+    pub fn uninit(value: T) -> Self {
+        Self {
+            current: Mutex::uninit(Arc::new(value)).poisoning(false),
+            writer: Mutex::uninit(()).poisoning(false),
+        }
+    }
+
+    /// Create a new, initialized copy-on-write lock.
+    ///
+    /// The resulting copy-on-write lock is wrapped and ready for use.
+    #[inline]
+    pub fn boxed(value: T) -> Pin<Box<Self>> {
+        let this = Box::pin(Self::uninit(value));
+        this.as_ref().init();
+        this
+    }
+
+    /// Create a new, initialized copy-on-write lock.
+    ///
+    /// The resulting copy-on-write lock is wrapped and ready for use.
+    #[inline]
+    pub fn arc(value: T) -> Pin<Arc<Self>> {
+        let this = Arc::pin(Self::uninit(value));
+        this.as_ref().init();
+        this
+    }
+
+    /// Initialize a copy-on-write lock, making it ready for use.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the lock was already initialized.
+    #[inline]
+    pub fn init(self: Pin<&Self>) {
+        self.current().init();
+        self.writer().init();
+    }
+
+    /// Returns a snapshot of the current value.
+    ///
+    /// The snapshot is not affected by later writes.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the lock is not initialized.
+    #[inline]
+    pub fn read(self: Pin<&Self>) -> Arc<T> {
+        self.current().lock().unwrap().clone()
+    }
+
+    /// Replaces the current value, returning the previous one.
+    ///
+    /// This waits for the writer holding the lock, if any, to publish its
+    /// value first.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the lock is not initialized.
+    pub fn replace(self: Pin<&Self>, value: T) -> Arc<T> {
+        let _writer = self.writer().lock().unwrap();
+        self.publish(value)
+    }
+
+    /// Consumes this copy-on-write lock, returning the current value.
+    pub fn into_inner(self) -> Arc<T> {
+        self.current.into_inner().unwrap()
+    }
+
+    #[inline]
+    fn publish(self: Pin<&Self>, value: T) -> Arc<T> {
+        std::mem::replace(&mut *self.current().lock().unwrap(), Arc::new(value))
+    }
+
+    #[inline]
+    fn current(self: Pin<&Self>) -> Pin<&Mutex<Arc<T>>> {
+        unsafe { self.map_unchecked(|this| &this.current) }
+    }
+
+    #[inline]
+    fn writer(self: Pin<&Self>) -> Pin<&Mutex<()>> {
+        unsafe { self.map_unchecked(|this| &this.writer) }
+    }
+}
+
+impl<T: Clone> CowLock<T> {
+    /// Locks this copy-on-write lock for writing, blocking the current thread
+    /// until no other writer holds it.
+    ///
+    /// The returned guard gives mutable access to a copy of the current
+    /// value, which is published when the guard is dropped. Readers keep
+    /// seeing the previous value until then.
+    ///
+    /// # Panics
+    ///
+    /// This function might panic when called if the lock is already held for
+    /// writing by the current thread.
+    ///
+    /// This function may panic if the lock is not initialized.
+    pub fn write(self: Pin<&Self>) -> CowLockWriteGuard<'_, T> {
+        let writer = self.writer().lock().unwrap();
+        let value = T::clone(&self.read());
+        CowLockWriteGuard {
+            _writer: writer,
+            lock: self,
+            value: Some(value),
+            panicking: thread::panicking(),
+        }
+    }
+}
+
+impl<T> Primitives for CowLock<T> {
+    type Pinned<'a>
+        = Pin<&'a Self>
+    where
+        Self: 'a;
+
+    #[inline]
+    fn init_pinned(self: Pin<&Self>) -> Pin<&Self> {
+        self.init();
+        self
+    }
+}
+
+impl<T> fmt::Debug for CowLock<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CowLock").finish_non_exhaustive()
+    }
+}
+
+/// An RAII guard of a [`CowLock`] held for writing. When this structure is
+/// dropped, the modified copy is published, and the lock is unlocked.
+///
+/// The copy can be accessed through this guard via its [`Deref`] and
+/// [`DerefMut`] implementations.
+pub struct CowLockWriteGuard<'a, T> {
+    _writer: MutexGuard<'a, ()>,
+    lock: Pin<&'a CowLock<T>>,
+    value: Option<T>,
+    // Whether the thread was already panicking when the lock was acquired.
+    panicking: bool,
+}
+
+impl<T> Deref for CowLockWriteGuard<'_, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        self.value.as_ref().unwrap()
+    }
+}
+
+impl<T> DerefMut for CowLockWriteGuard<'_, T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut T {
+        self.value.as_mut().unwrap()
+    }
+}
+
+impl<T> Drop for CowLockWriteGuard<'_, T> {
+    fn drop(&mut self) {
+        // A copy modified by a writer which panicked may be inconsistent.
+        if self.panicking || !thread::panicking() {
+            if let Some(value) = self.value.take() {
+                self.lock.publish(value);
+            }
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for CowLockWriteGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
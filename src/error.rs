@@ -0,0 +1,313 @@
+use crate::WaitTimeoutResult;
+use std::error::Error;
+use std::fmt;
+use std::sync;
+
+/// A type of error which can be returned whenever a lock is acquired.
+///
+/// Both [`Mutex`]es and [`RwLock`]s are poisoned whenever a thread fails while
+/// the lock is held. The precise semantics for when a lock is poisoned is
+/// documented on each lock, but once a lock is poisoned then all future
+/// acquisitions will return this error.
+///
+/// This is the equivalent of [`std::sync::PoisonError`], and can be converted
+/// from and into it.
+///
+/// [`Mutex`]: crate::Mutex
+/// [`RwLock`]: crate::RwLock
+pub struct PoisonError<T> {
+    guard: T,
+}
+
+/// An enumeration of possible errors associated with a [`TryLockResult`] which
+/// can occur while trying to acquire a lock, from the [`try_lock`] method on a
+/// [`Mutex`] or the [`try_read`] and [`try_write`] methods on an [`RwLock`].
+///
+/// This is the equivalent of [`std::sync::TryLockError`], and can be converted
+/// from and into it.
+///
+/// [`try_lock`]: crate::Mutex::try_lock
+/// [`try_read`]: crate::RwLock::try_read
+/// [`try_write`]: crate::RwLock::try_write
+/// [`Mutex`]: crate::Mutex
+/// [`RwLock`]: crate::RwLock
+#[non_exhaustive]
+pub enum TryLockError<T> {
+    /// The lock could not be acquired because another thread failed while
+    /// holding the lock.
+    Poisoned(PoisonError<T>),
+    /// The lock could not be acquired at this time because the operation would
+    /// otherwise block.
+    WouldBlock,
+    /// The read lock could not be acquired because the maximum number of
+    /// readers was reached.
+    TooManyReaders,
+}
+
+/// An enumeration of possible errors which can occur while blocking to acquire
+/// a lock, from the methods which can give up waiting for it, such as the
+/// signal-interruptible ones and [`RwLock::read_checked`].
+///
+/// Unlike with a [`PoisonError`], the lock is not held when the wait was given
+/// up, so those variants do not carry a guard.
+///
+/// [`RwLock::read_checked`]: crate::RwLock::read_checked
+#[non_exhaustive]
+pub enum LockError<T> {
+    /// The lock was acquired, but another thread failed while holding it.
+    Poisoned(PoisonError<T>),
+    /// The wait for the lock was interrupted by a signal.
+    Interrupted,
+    /// The read lock could not be acquired because the maximum number of
+    /// readers was reached.
+    TooManyReaders,
+}
+
+/// An enumeration of possible errors of a wait on a condition variable with a
+/// timeout, where timing out is an error.
+///
+/// This is what [`from_result`] turns the result of
+/// [`Condvar::wait_timeout`] and similar methods into, so that a timeout can be
+/// propagated with `?`. Both variants carry the guard, as the lock is held
+/// again either way.
+///
+/// [`from_result`]: WaitTimeoutError::from_result
+/// [`Condvar::wait_timeout`]: crate::Condvar::wait_timeout
+pub enum WaitTimeoutError<T> {
+    /// Another thread failed while holding the lock.
+    Poisoned(PoisonError<T>),
+    /// The timeout elapsed before the condition variable was notified.
+    TimedOut(T),
+}
+
+/// A type alias for the result of a lock method which can be poisoned.
+///
+/// The [`Ok`] variant of this result indicates that the primitive was not
+/// poisoned, and the `Guard` is contained within. The [`Err`] variant indicates
+/// that the primitive was poisoned. Note that the [`Err`] variant *also* carries
+/// the associated guard, and it can be acquired through the [`into_inner`]
+/// method.
+///
+/// [`into_inner`]: PoisonError::into_inner
+pub type LockResult<Guard> = Result<Guard, PoisonError<Guard>>;
+
+/// A type alias for the result of a nonblocking locking method.
+///
+/// For more information, see [`LockResult`]. A `TryLockResult` doesn't
+/// necessarily hold the associated guard in the [`Err`] type as the lock may not
+/// have been acquired for other reasons.
+pub type TryLockResult<Guard> = Result<Guard, TryLockError<Guard>>;
+
+impl<T> fmt::Debug for PoisonError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PoisonError").finish_non_exhaustive()
+    }
+}
+
+impl<T> fmt::Display for PoisonError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        "poisoned lock: another task failed inside".fmt(f)
+    }
+}
+
+impl<T> Error for PoisonError<T> {}
+
+impl<T> PoisonError<T> {
+    /// Creates a `PoisonError`.
+    pub fn new(guard: T) -> PoisonError<T> {
+        PoisonError { guard }
+    }
+
+    /// Consumes this error indicating that a lock is poisoned, returning the
+    /// underlying guard to allow access regardless.
+    pub fn into_inner(self) -> T {
+        self.guard
+    }
+
+    /// Reaches into this error indicating that a lock is poisoned, returning a
+    /// reference to the underlying guard to allow access regardless.
+    pub fn get_ref(&self) -> &T {
+        &self.guard
+    }
+
+    /// Reaches into this error indicating that a lock is poisoned, returning a
+    /// mutable reference to the underlying guard to allow access regardless.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.guard
+    }
+}
+
+impl<T> From<sync::PoisonError<T>> for PoisonError<T> {
+    fn from(err: sync::PoisonError<T>) -> PoisonError<T> {
+        PoisonError::new(err.into_inner())
+    }
+}
+
+impl<T> From<PoisonError<T>> for sync::PoisonError<T> {
+    fn from(err: PoisonError<T>) -> sync::PoisonError<T> {
+        sync::PoisonError::new(err.into_inner())
+    }
+}
+
+impl<T> From<PoisonError<T>> for TryLockError<T> {
+    fn from(err: PoisonError<T>) -> TryLockError<T> {
+        TryLockError::Poisoned(err)
+    }
+}
+
+impl<T> TryLockError<T> {
+    #[inline]
+    pub(crate) fn map<U, F>(self, f: F) -> TryLockError<U>
+    where
+        F: FnOnce(T) -> U,
+    {
+        match self {
+            TryLockError::Poisoned(err) => {
+                TryLockError::Poisoned(PoisonError::new(f(err.into_inner())))
+            }
+            TryLockError::WouldBlock => TryLockError::WouldBlock,
+            TryLockError::TooManyReaders => TryLockError::TooManyReaders,
+        }
+    }
+}
+
+impl<T> From<PoisonError<T>> for LockError<T> {
+    fn from(err: PoisonError<T>) -> LockError<T> {
+        LockError::Poisoned(err)
+    }
+}
+
+impl<T> From<sync::PoisonError<T>> for LockError<T> {
+    fn from(err: sync::PoisonError<T>) -> LockError<T> {
+        LockError::Poisoned(err.into())
+    }
+}
+
+impl<T> fmt::Debug for LockError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            LockError::Poisoned(..) => f.write_str("Poisoned(..)"),
+            LockError::Interrupted => f.write_str("Interrupted"),
+            LockError::TooManyReaders => f.write_str("TooManyReaders"),
+        }
+    }
+}
+
+impl<T> fmt::Display for LockError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            LockError::Poisoned(..) => "poisoned lock: another task failed inside",
+            LockError::Interrupted => "waiting for the lock was interrupted by a signal",
+            LockError::TooManyReaders => "read failed because of too many readers",
+        }
+        .fmt(f)
+    }
+}
+
+impl<T> Error for LockError<T> {}
+
+impl<T> WaitTimeoutError<T> {
+    /// Turns the result of a wait with a timeout into a result whose error
+    /// is either the poisoning or the timeout.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pinned_sync::{Condvar, Mutex, WaitTimeoutError};
+    /// use std::time::Duration;
+    ///
+    /// let mutex = Mutex::boxed(());
+    /// let condvar = Condvar::boxed();
+    ///
+    /// let guard = mutex.as_ref().lock().unwrap();
+    /// let result = condvar.as_ref().wait_timeout(guard, Duration::from_millis(1));
+    /// assert!(matches!(
+    ///     WaitTimeoutError::from_result(result),
+    ///     Err(WaitTimeoutError::TimedOut(_))
+    /// ));
+    /// ```
+    pub fn from_result(
+        result: LockResult<(T, WaitTimeoutResult)>,
+    ) -> Result<T, WaitTimeoutError<T>> {
+        match result {
+            Ok((guard, timeout)) if timeout.timed_out() => Err(WaitTimeoutError::TimedOut(guard)),
+            Ok((guard, _)) => Ok(guard),
+            Err(err) => Err(WaitTimeoutError::Poisoned(PoisonError::new(
+                err.into_inner().0,
+            ))),
+        }
+    }
+
+    /// Consumes this error, returning the guard of the lock, which is held
+    /// again whether the lock was poisoned or the wait timed out.
+    pub fn into_inner(self) -> T {
+        match self {
+            WaitTimeoutError::Poisoned(err) => err.into_inner(),
+            WaitTimeoutError::TimedOut(guard) => guard,
+        }
+    }
+}
+
+impl<T> fmt::Debug for WaitTimeoutError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            WaitTimeoutError::Poisoned(..) => f.write_str("Poisoned(..)"),
+            WaitTimeoutError::TimedOut(..) => f.write_str("TimedOut(..)"),
+        }
+    }
+}
+
+impl<T> fmt::Display for WaitTimeoutError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            WaitTimeoutError::Poisoned(..) => "poisoned lock: another task failed inside",
+            WaitTimeoutError::TimedOut(..) => "wait on a condition variable timed out",
+        }
+        .fmt(f)
+    }
+}
+
+impl<T> Error for WaitTimeoutError<T> {}
+
+impl<T> fmt::Debug for TryLockError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            TryLockError::Poisoned(..) => f.write_str("Poisoned(..)"),
+            TryLockError::WouldBlock => f.write_str("WouldBlock"),
+            TryLockError::TooManyReaders => f.write_str("TooManyReaders"),
+        }
+    }
+}
+
+impl<T> fmt::Display for TryLockError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            TryLockError::Poisoned(..) => "poisoned lock: another task failed inside",
+            TryLockError::WouldBlock => "try_lock failed because the operation would block",
+            TryLockError::TooManyReaders => "try_read failed because of too many readers",
+        }
+        .fmt(f)
+    }
+}
+
+impl<T> Error for TryLockError<T> {}
+
+impl<T> From<sync::TryLockError<T>> for TryLockError<T> {
+    fn from(err: sync::TryLockError<T>) -> TryLockError<T> {
+        match err {
+            sync::TryLockError::Poisoned(err) => TryLockError::Poisoned(err.into()),
+            sync::TryLockError::WouldBlock => TryLockError::WouldBlock,
+        }
+    }
+}
+
+impl<T> From<TryLockError<T>> for sync::TryLockError<T> {
+    fn from(err: TryLockError<T>) -> sync::TryLockError<T> {
+        match err {
+            TryLockError::Poisoned(err) => sync::TryLockError::Poisoned(err.into()),
+            TryLockError::WouldBlock | TryLockError::TooManyReaders => {
+                sync::TryLockError::WouldBlock
+            }
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use crate::sys::{condvar, mutex};
+use std::fmt;
+use std::marker::PhantomPinned;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering::*};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// An auto-reset event, or binary semaphore.
+///
+/// An event is either signaled or not. [`signal`] sets it, waking up a thread
+/// waiting for it if there is one, and [`wait`] blocks until it is set and
+/// resets it. Signaling an event which is already signaled does nothing, so
+/// the signals sent before anyone waits only count once.
+///
+/// This is cheaper than a [`Mutex`] and [`Condvar`] pair for a simple "something
+/// happened" handoff: signaling an event nobody waits for, and waiting for an
+/// event which is already signaled, are a single atomic operation each.
+///
+/// See [`ManualResetEvent`] for an event which stays signaled until it is
+/// reset explicitly.
+///
+/// [`signal`]: Self::signal
+/// [`wait`]: Self::wait
+/// [`Mutex`]: crate::Mutex
+/// [`Condvar`]: crate::Condvar
+/// [`ManualResetEvent`]: crate::ManualResetEvent
+///
+/// # Examples
+///
+/// ```
+/// use pinned_sync::Event;
+/// use std::thread;
+///
+/// let event = Event::arc();
+/// let event2 = event.clone();
+///
+/// thread::spawn(move || {
+///     // Do some work, then let the other thread know.
+///     event2.as_ref().signal();
+/// });
+///
+/// event.as_ref().wait();
+/// ```
+pub struct Event {
+    signaled: AtomicBool,
+    // The number of threads which may be blocked on `cvar`. Signals only take
+    // the lock if it is not zero.
+    waiters: AtomicUsize,
+    lock: mutex::Mutex,
+    cvar: condvar::Condvar,
+    _p: PhantomPinned,
+}
+
+unsafe impl Send for Event {}
+unsafe impl Sync for Event {}
+
+impl Event {
+    /// Create a new, uninitialized event, which is not signaled.
+    ///
+    /// This is *NOT* equivalent to `MaybeUninit::uninit().assume_init()`, which will cause
+    /// undefined behaviour if used to create a new event.
+    #[inline]
+    pub const fn uninit() -> Self {
+        Self {
+            signaled: AtomicBool::new(false),
+            waiters: AtomicUsize::new(0),
+            lock: mutex::Mutex::uninit(),
+            cvar: condvar::Condvar::uninit(),
+            _p: PhantomPinned,
+        }
+    }
+
+    /// Create a new, initialized event, which is not signaled.
+    ///
+    /// The resulting event is wrapped and ready for use.
+    #[inline]
+    pub fn boxed() -> Pin<Box<Self>> {
+        let this = Box::pin(Self::uninit());
+        this.as_ref().init();
+        this
+    }
+
+    /// Create a new, initialized event, which is not signaled.
+    ///
+    /// The resulting event is wrapped and ready for use.
+    #[inline]
+    pub fn arc() -> Pin<Arc<Self>> {
+        let this = Arc::pin(Self::uninit());
+        this.as_ref().init();
+        this
+    }
+
+    /// Initialize an event, making it ready for use.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the event was already initialized.
+    #[inline]
+    pub fn init(self: Pin<&Self>) {
+        self.lock().init();
+        self.cvar().init();
+    }
+
+    /// Signals the event, waking up one of the threads waiting for it, if any.
+    ///
+    /// If the event is already signaled, this does nothing.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the event is not initialized.
+    pub fn signal(self: Pin<&Self>) {
+        if self.signaled.swap(true, SeqCst) {
+            return;
+        }
+        // Pairs with the increment in `wait_slow`: either the waiter sees the
+        // event signaled, or we see the waiter. Taking the lock makes sure it
+        // is either blocked on the condvar already, or has yet to check the
+        // event again.
+        if self.waiters.load(SeqCst) != 0 {
+            let _lock = self.lock().lock();
+            self.cvar().notify_one();
+        }
+    }
+
+    /// Resets the event if it is signaled, without blocking.
+    ///
+    /// Returns whether the event was signaled.
+    #[inline]
+    pub fn try_wait(self: Pin<&Self>) -> bool {
+        self.signaled
+            .compare_exchange(true, false, Acquire, Relaxed)
+            .is_ok()
+    }
+
+    /// Blocks the current thread until the event is signaled, and resets it.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the event is not initialized.
+    #[inline]
+    pub fn wait(self: Pin<&Self>) {
+        if !self.try_wait() {
+            self.wait_slow(None);
+        }
+    }
+
+    /// Blocks the current thread until the event is signaled, and resets it,
+    /// or until the timeout expires.
+    ///
+    /// Returns `false` if the timeout expired, in which case the event is left
+    /// as it is.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the event is not initialized.
+    #[inline]
+    pub fn wait_timeout(self: Pin<&Self>, dur: Duration) -> bool {
+        self.try_wait() || self.wait_slow(Some(dur))
+    }
+
+    #[cold]
+    fn wait_slow(self: Pin<&Self>, timeout: Option<Duration>) -> bool {
+        // A timeout which can not be represented is as good as no timeout.
+        let deadline = timeout.and_then(|dur| Instant::now().checked_add(dur));
+        let mut lock = self.lock().lock();
+        self.waiters.fetch_add(1, SeqCst);
+        let signaled = loop {
+            if self
+                .signaled
+                .compare_exchange(true, false, SeqCst, Relaxed)
+                .is_ok()
+            {
+                break true;
+            }
+            // Safety: the condvar is only ever used with `self.lock`.
+            lock = match deadline {
+                None => unsafe { self.cvar().wait(lock) },
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        break false;
+                    }
+                    unsafe { self.cvar().get_ref().wait_timeout(lock, deadline - now).1 }
+                }
+            };
+        };
+        self.waiters.fetch_sub(1, Relaxed);
+        signaled
+    }
+
+    #[inline]
+    fn lock(self: Pin<&Self>) -> Pin<&mutex::Mutex> {
+        unsafe { self.map_unchecked(|this| &this.lock) }
+    }
+
+    #[inline]
+    fn cvar(self: Pin<&Self>) -> Pin<&condvar::Condvar> {
+        unsafe { self.map_unchecked(|this| &this.cvar) }
+    }
+}
+
+impl fmt::Debug for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Event")
+            .field("signaled", &self.signaled.load(Relaxed))
+            .finish_non_exhaustive()
+    }
+}
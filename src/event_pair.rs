@@ -0,0 +1,192 @@
+use crate::{Condvar, Mutex, MutexGuard};
+use std::fmt;
+use std::pin::Pin;
+use std::sync::Arc;
+
+/// One of the two sides of an [`EventPair`].
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum EventPairSide {
+    /// The low side.
+    Low,
+    /// The high side.
+    High,
+}
+
+impl EventPairSide {
+    /// Returns the other side.
+    #[inline]
+    pub fn peer(self) -> Self {
+        match self {
+            EventPairSide::Low => EventPairSide::High,
+            EventPairSide::High => EventPairSide::Low,
+        }
+    }
+
+    #[inline]
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// A pair of linked auto-reset events, for strict alternation between two
+/// threads.
+///
+/// Each of the two threads takes one [side] of the pair. A thread signals the
+/// event of its peer to hand over control, and waits on its own event to get
+/// it back. The events are auto-reset: a signal wakes up a single wait, and a
+/// signal sent before the peer waits is not lost.
+///
+/// [`signal_and_wait`] signals the peer and starts waiting in a single step,
+/// so a ping-pong between two threads is one call per turn.
+///
+/// [side]: EventPairSide
+/// [`signal_and_wait`]: Self::signal_and_wait
+///
+/// # Examples
+///
+/// ```
+/// use pinned_sync::{EventPair, EventPairSide};
+/// use std::thread;
+///
+/// let pair = EventPair::arc();
+/// let pair2 = pair.clone();
+///
+/// let pong = thread::spawn(move || {
+///     let pair = pair2.as_ref();
+///     pair.wait(EventPairSide::High);
+///     for _ in 0..9 {
+///         pair.signal_and_wait(EventPairSide::High);
+///     }
+///     pair.signal(EventPairSide::High);
+/// });
+///
+/// for _ in 0..10 {
+///     pair.as_ref().signal_and_wait(EventPairSide::Low);
+/// }
+/// pong.join().unwrap();
+/// ```
+pub struct EventPair {
+    // Whether the event of each side is signaled.
+    lock: Mutex<[bool; 2]>,
+    cvars: [Condvar; 2],
+}
+
+impl EventPair {
+    /// Create a new, uninitialized event pair, with neither event signaled.
+    ///
+    /// This is *NOT* equivalent to `MaybeUninit::uninit().assume_init()`, which will cause
+    /// undefined behaviour if used to create a new event pair.
+    #[inline]
+    pub const fn uninit() -> Self {
+        Self {
+            lock: Mutex::uninit([false; 2]),
+            cvars: [Condvar::uninit(), Condvar::uninit()],
+        }
+    }
+
+    /// Create a new, initialized event pair, with neither event signaled.
+    ///
+    /// The resulting event pair is wrapped and ready for use.
+    #[inline]
+    pub fn boxed() -> Pin<Box<Self>> {
+        let this = Box::pin(Self::uninit());
+        this.as_ref().init();
+        this
+    }
+
+    /// Create a new, initialized event pair, with neither event signaled.
+    ///
+    /// The resulting event pair is wrapped and ready for use.
+    #[inline]
+    pub fn arc() -> Pin<Arc<Self>> {
+        let this = Arc::pin(Self::uninit());
+        this.as_ref().init();
+        this
+    }
+
+    /// Initialize an event pair, making it ready for use.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the event pair was already initialized.
+    #[inline]
+    pub fn init(self: Pin<&Self>) {
+        self.lock().init();
+        self.cvar(EventPairSide::Low).init();
+        self.cvar(EventPairSide::High).init();
+    }
+
+    /// Signals the event of the peer of `side`, waking it up if it is waiting.
+    ///
+    /// If the event is already signaled, this does nothing.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the event pair is not initialized.
+    pub fn signal(self: Pin<&Self>, side: EventPairSide) {
+        let mut signaled = self.lock().lock().unwrap();
+        self.signal_locked(&mut signaled, side.peer());
+    }
+
+    /// Blocks the current thread until the event of `side` is signaled, and
+    /// resets it.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the event pair is not initialized.
+    pub fn wait(self: Pin<&Self>, side: EventPairSide) {
+        let signaled = self.lock().lock().unwrap();
+        self.wait_locked(signaled, side);
+    }
+
+    /// Signals the event of the peer of `side`, and blocks the current thread
+    /// until the event of `side` is signaled, resetting it.
+    ///
+    /// This is equivalent to [`signal`] followed by [`wait`], but done in a
+    /// single step.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the event pair is not initialized.
+    ///
+    /// [`signal`]: Self::signal
+    /// [`wait`]: Self::wait
+    pub fn signal_and_wait(self: Pin<&Self>, side: EventPairSide) {
+        let mut signaled = self.lock().lock().unwrap();
+        self.signal_locked(&mut signaled, side.peer());
+        self.wait_locked(signaled, side);
+    }
+
+    #[inline]
+    fn signal_locked(self: Pin<&Self>, signaled: &mut [bool; 2], side: EventPairSide) {
+        if !signaled[side.index()] {
+            signaled[side.index()] = true;
+            self.cvar(side).notify_one();
+        }
+    }
+
+    #[inline]
+    fn wait_locked(self: Pin<&Self>, signaled: MutexGuard<'_, [bool; 2]>, side: EventPairSide) {
+        let mut signaled = self
+            .cvar(side)
+            .wait_while(signaled, |signaled| !signaled[side.index()])
+            .unwrap();
+        signaled[side.index()] = false;
+    }
+
+    #[inline]
+    fn lock(self: Pin<&Self>) -> Pin<&Mutex<[bool; 2]>> {
+        unsafe { self.map_unchecked(|this| &this.lock) }
+    }
+
+    #[inline]
+    fn cvar(self: Pin<&Self>, side: EventPairSide) -> Pin<&Condvar> {
+        unsafe { self.map_unchecked(|this| &this.cvars[side.index()]) }
+    }
+}
+
+impl fmt::Debug for EventPair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventPair").finish_non_exhaustive()
+    }
+}
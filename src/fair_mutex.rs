@@ -0,0 +1,187 @@
+use crate::raw;
+use crate::{LockResult, Mutex, MutexGuard, TryLockResult};
+use std::fmt;
+use std::pin::Pin;
+use std::sync::Arc;
+
+/// A mutual exclusion primitive which is acquired in FIFO order.
+///
+/// The pthread and futex mutexes behind [`Mutex`] are unfair: a thread which
+/// unlocks and locks again in a loop usually wins the race for the mutex
+/// against the threads waiting for it, which may then wait indefinitely. A
+/// `FairMutex` hands the lock to the threads in the order they asked for it,
+/// so that every thread gets it after at most one critical section per thread
+/// in line before it.
+///
+/// This is a [`Mutex`] on the [`raw::FairMutex`] ticket lock, so it poisons
+/// and hands out guards the same way. It is slower than a [`Mutex`] under
+/// contention, as the lock can not be taken by a running thread while the
+/// next one in line is being woken up.
+///
+/// With the `elision` feature, critical sections may still run concurrently
+/// as hardware transactions, outside of the queue.
+///
+/// # Examples
+///
+/// ```
+/// use pinned_sync::FairMutex;
+/// use std::thread;
+///
+/// let log = FairMutex::arc(Vec::new());
+///
+/// let threads: Vec<_> = (0..4)
+///     .map(|i| {
+///         let log = log.clone();
+///         thread::spawn(move || {
+///             for _ in 0..10 {
+///                 log.as_ref().lock().unwrap().push(i);
+///             }
+///         })
+///     })
+///     .collect();
+/// for thread in threads {
+///     thread.join().unwrap();
+/// }
+/// assert_eq!(log.as_ref().lock().unwrap().len(), 40);
+/// ```
+pub struct FairMutex<T: ?Sized> {
+    inner: Mutex<T, raw::FairMutex>,
+}
+
+impl<T> FairMutex<T> {
+    /// Create a new, uninitialized fair mutex.
+    ///
+    /// This is *NOT* equivalent to `MaybeUninit::uninit().assume_init()`, which will cause
+    /// undefined behaviour if used to create a new fair mutex.
+    #[inline]
+    pub const fn uninit(value: T) -> Self {
+        Self {
+            inner: Mutex::with_backend(value),
+        }
+    }
+
+    /// Create a new, initialized fair mutex.
+    ///
+    /// The resulting fair mutex is wrapped and ready for use.
+    #[inline]
+    pub fn boxed(value: T) -> Pin<Box<Self>> {
+        let this = Box::pin(Self::uninit(value));
+        this.as_ref().init();
+        this
+    }
+
+    /// Create a new, initialized fair mutex.
+    ///
+    /// The resulting fair mutex is wrapped and ready for use.
+    #[inline]
+    pub fn arc(value: T) -> Pin<Arc<Self>> {
+        let this = Arc::pin(Self::uninit(value));
+        this.as_ref().init();
+        this
+    }
+
+    /// Consumes this fair mutex, returning the underlying data.
+    ///
+    /// # Errors
+    ///
+    /// If another user of this mutex panicked while holding the mutex, then
+    /// this call will return an error instead.
+    #[inline]
+    pub fn into_inner(self) -> LockResult<T> {
+        self.inner.into_inner()
+    }
+}
+
+impl<T: ?Sized> FairMutex<T> {
+    /// Initialize a fair mutex, making it ready for use.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the fair mutex was already initialized.
+    #[inline]
+    pub fn init(self: Pin<&Self>) {
+        self.inner().init()
+    }
+
+    /// Acquires the mutex, blocking the current thread until every thread
+    /// which asked for it before has released it.
+    ///
+    /// See [`Mutex::lock`].
+    ///
+    /// # Errors
+    ///
+    /// If another user of this mutex panicked while holding the mutex, then
+    /// this call will return an error once the mutex is acquired.
+    ///
+    /// # Panics
+    ///
+    /// This function deadlocks if the lock is already held by the current
+    /// thread.
+    ///
+    /// This function may panic if the mutex is not initialized.
+    #[inline]
+    pub fn lock(self: Pin<&Self>) -> LockResult<MutexGuard<'_, T, raw::FairMutex>> {
+        self.inner().lock()
+    }
+
+    /// Attempts to acquire this lock.
+    ///
+    /// The lock is only acquired if it is free and no other thread is waiting
+    /// for it, so that this does not jump the queue. See [`Mutex::try_lock`].
+    ///
+    /// # Errors
+    ///
+    /// If another user of this mutex panicked while holding the mutex, then
+    /// this call will return an error if the mutex would otherwise be
+    /// acquired.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the mutex is not initialized.
+    #[inline]
+    pub fn try_lock(self: Pin<&Self>) -> TryLockResult<MutexGuard<'_, T, raw::FairMutex>> {
+        self.inner().try_lock()
+    }
+
+    /// Determines whether the mutex is poisoned.
+    ///
+    /// See [`Mutex::is_poisoned`].
+    #[inline]
+    pub fn is_poisoned(self: Pin<&Self>) -> bool {
+        self.inner().is_poisoned()
+    }
+
+    /// Clears the poisoned state from a mutex.
+    ///
+    /// See [`Mutex::clear_poison`].
+    #[inline]
+    pub fn clear_poison(self: Pin<&Self>) {
+        self.inner().clear_poison()
+    }
+
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// Since this call borrows the `FairMutex` mutably, no actual locking
+    /// needs to take place -- the mutable borrow statically guarantees no
+    /// locks exist.
+    ///
+    /// # Errors
+    ///
+    /// If another user of this mutex panicked while holding the mutex, then
+    /// this call will return an error instead.
+    #[inline]
+    pub fn get_mut(&mut self) -> LockResult<&mut T> {
+        self.inner.get_mut()
+    }
+
+    #[inline]
+    fn inner(self: Pin<&Self>) -> Pin<&Mutex<T, raw::FairMutex>> {
+        unsafe { self.map_unchecked(|this| &this.inner) }
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for FairMutex<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FairMutex").finish_non_exhaustive()
+    }
+}
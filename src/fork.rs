@@ -0,0 +1,130 @@
+use crate::{Mutex, RwLock};
+use std::cell::UnsafeCell;
+use std::hint;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering::*};
+use std::sync::Once;
+
+/// A primitive which can be restored to a defined state in the child process
+/// of a `fork`.
+///
+/// See [`reinit_on_fork`].
+pub trait ReinitAfterFork: Sync {
+    /// Restores the primitive to an unlocked state in the child process of a
+    /// `fork`.
+    ///
+    /// # Safety
+    ///
+    /// This must only be called in the child process of a `fork`, before any
+    /// thread is spawned. The current thread must not hold a guard of the
+    /// primitive.
+    unsafe fn reinit_after_fork(self: Pin<&Self>);
+}
+
+impl<T: ?Sized + Send + Sync> ReinitAfterFork for Mutex<T> {
+    #[inline]
+    unsafe fn reinit_after_fork(self: Pin<&Self>) {
+        Mutex::reinit_after_fork(self)
+    }
+}
+
+impl<T: ?Sized + Send + Sync> ReinitAfterFork for RwLock<T> {
+    #[inline]
+    unsafe fn reinit_after_fork(self: Pin<&Self>) {
+        RwLock::reinit_after_fork(self)
+    }
+}
+
+struct Registry {
+    locked: AtomicBool,
+    primitives: UnsafeCell<Vec<Pin<&'static dyn ReinitAfterFork>>>,
+}
+
+unsafe impl Sync for Registry {}
+
+static REGISTRY: Registry = Registry {
+    locked: AtomicBool::new(false),
+    primitives: UnsafeCell::new(Vec::new()),
+};
+
+static INSTALL: Once = Once::new();
+
+impl Registry {
+    // A spin lock, as it is held across `fork` and released in the child,
+    // where only the forking thread exists.
+    fn lock(&self) {
+        while self
+            .locked
+            .compare_exchange_weak(false, true, Acquire, Relaxed)
+            .is_err()
+        {
+            hint::spin_loop();
+        }
+    }
+
+    fn unlock(&self) {
+        self.locked.store(false, Release);
+    }
+}
+
+extern "C" fn prepare() {
+    REGISTRY.lock();
+}
+
+extern "C" fn parent() {
+    REGISTRY.unlock();
+}
+
+extern "C" fn child() {
+    // Safety: The registry is locked by this thread, and the primitives were
+    // registered with `reinit_on_fork`.
+    unsafe {
+        for primitive in &*REGISTRY.primitives.get() {
+            primitive.reinit_after_fork();
+        }
+    }
+    REGISTRY.unlock();
+}
+
+/// Registers a primitive to be restored with its `reinit_after_fork` method
+/// in the child process of every `fork` from now on.
+///
+/// Only the thread which called `fork` exists in the child process, so any
+/// lock held by another thread at that time would never be unlocked. The
+/// first call installs handlers with `pthread_atfork`, which restore every
+/// registered primitive before `fork` returns in the child.
+///
+/// Mutexes and read-write locks which were held for writing are poisoned, as
+/// the data may have been left half-updated.
+///
+/// # Safety
+///
+/// No thread may call `fork` while holding a guard of the primitive.
+///
+/// # Panics
+///
+/// This function panics if the handlers could not be installed.
+///
+/// # Examples
+///
+/// ```
+/// use pinned_sync::{reinit_on_fork, Mutex};
+/// use std::pin::Pin;
+///
+/// static MUTEX: Mutex<i32> = Mutex::uninit(0);
+///
+/// let mutex = Pin::static_ref(&MUTEX);
+/// mutex.init();
+///
+/// // Safety: The mutex is never held while forking.
+/// unsafe { reinit_on_fork(mutex) };
+/// ```
+pub unsafe fn reinit_on_fork(primitive: Pin<&'static dyn ReinitAfterFork>) {
+    INSTALL.call_once(|| {
+        let r = libc::pthread_atfork(Some(prepare), Some(parent), Some(child));
+        assert_eq!(r, 0, "failed to install the fork handlers");
+    });
+    REGISTRY.lock();
+    (*REGISTRY.primitives.get()).push(primitive);
+    REGISTRY.unlock();
+}
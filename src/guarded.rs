@@ -0,0 +1,204 @@
+use crate::sys_common::poison;
+use crate::{LockResult, Mutex, MutexGuard, TryLockResult};
+use std::cell::UnsafeCell;
+use std::pin::Pin;
+use std::ptr;
+use std::sync::Arc;
+
+/// A mutual exclusion primitive which does not contain any data.
+///
+/// This is useful when the data protected by the lock can not physically
+/// live inside of a [`Mutex`], for example when it is spread over the columns
+/// of a struct-of-arrays. The data is then wrapped in [`GuardedBy`], which
+/// only gives access to it through a guard of this lock.
+///
+/// Poisoning works the same as for [`Mutex`].
+pub struct RawPinnedMutex {
+    inner: Mutex<()>,
+}
+
+impl RawPinnedMutex {
+    /// Create a new, uninitialized mutex.
+    ///
+    /// This is *NOT* equivalent to `MaybeUninit::uninit().assume_init()`, which will cause
+    /// undefined behaviour if used to create a new mutex.
+    #[inline]
+    pub const fn uninit() -> Self {
+        Self {
+            inner: Mutex::uninit(()),
+        }
+    }
+
+    /// Create a new, initialized mutex.
+    ///
+    /// The resulting mutex is wrapped and ready for use.
+    #[inline]
+    pub fn boxed() -> Pin<Box<Self>> {
+        let this = Box::pin(Self::uninit());
+        this.as_ref().init();
+        this
+    }
+
+    /// Create a new, initialized mutex.
+    ///
+    /// The resulting mutex is wrapped and ready for use.
+    #[inline]
+    pub fn arc() -> Pin<Arc<Self>> {
+        let this = Arc::pin(Self::uninit());
+        this.as_ref().init();
+        this
+    }
+
+    /// Initialize a mutex, making it ready for use.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the mutex was already initialized.
+    #[inline]
+    pub fn init(self: Pin<&Self>) {
+        self.inner().init()
+    }
+
+    /// Acquires a mutex, blocking the current thread until it is able to do so.
+    ///
+    /// See [`Mutex::lock`].
+    ///
+    /// # Errors
+    ///
+    /// If another user of this mutex panicked while holding the mutex, then
+    /// this call will return an error once the mutex is acquired.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the mutex is not initialized.
+    #[inline]
+    pub fn lock(self: Pin<&Self>) -> LockResult<RawPinnedMutexGuard<'_>> {
+        poison::map_result(self.inner().lock(), |guard| self.guard(guard))
+    }
+
+    /// Attempts to acquire this lock.
+    ///
+    /// See [`Mutex::try_lock`].
+    ///
+    /// # Errors
+    ///
+    /// If another user of this mutex panicked while holding the mutex, then
+    /// this call will return an error if the mutex would otherwise be
+    /// acquired.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the mutex is not initialized.
+    #[inline]
+    pub fn try_lock(self: Pin<&Self>) -> TryLockResult<RawPinnedMutexGuard<'_>> {
+        self.inner()
+            .try_lock()
+            .map(|guard| self.guard(guard))
+            .map_err(|error| error.map(|guard| self.guard(guard)))
+    }
+
+    /// Determines whether the mutex is poisoned.
+    ///
+    /// If another thread is active, the mutex can still become poisoned at any
+    /// time. You should not trust a `false` value for program correctness
+    /// without additional synchronization.
+    #[inline]
+    pub fn is_poisoned(self: Pin<&Self>) -> bool {
+        self.inner().is_poisoned()
+    }
+
+    #[inline]
+    fn guard<'a>(self: Pin<&'a Self>, guard: MutexGuard<'a, ()>) -> RawPinnedMutexGuard<'a> {
+        RawPinnedMutexGuard {
+            _guard: guard,
+            mutex: self,
+        }
+    }
+
+    #[inline]
+    fn inner(self: Pin<&Self>) -> Pin<&Mutex<()>> {
+        unsafe { self.map_unchecked(|this| &this.inner) }
+    }
+}
+
+/// An RAII guard for a [`RawPinnedMutex`].
+///
+/// The guard does not give access to any data by itself, but it is used as a
+/// proof of ownership of the lock by [`GuardedBy`].
+pub struct RawPinnedMutexGuard<'a> {
+    _guard: MutexGuard<'a, ()>,
+    mutex: Pin<&'a RawPinnedMutex>,
+}
+
+/// Data which may only be accessed while a [`RawPinnedMutex`] is locked.
+///
+/// The data is stored outside of the lock, but every access requires a guard
+/// of the lock it was created with. Shared access requires a shared reference
+/// to the guard, and exclusive access requires an exclusive reference to it,
+/// so two values guarded by the same lock can not be mutated at the same time
+/// through the same guard.
+pub struct GuardedBy<'lock, T: ?Sized> {
+    lock: Pin<&'lock RawPinnedMutex>,
+    data: UnsafeCell<T>,
+}
+
+unsafe impl<T: ?Sized + Send> Send for GuardedBy<'_, T> {}
+
+unsafe impl<T: ?Sized + Send + Sync> Sync for GuardedBy<'_, T> {}
+
+impl<'lock, T> GuardedBy<'lock, T> {
+    /// Creates a value which is protected by `lock`.
+    #[inline]
+    pub fn new(lock: Pin<&'lock RawPinnedMutex>, value: T) -> Self {
+        Self {
+            lock,
+            data: UnsafeCell::new(value),
+        }
+    }
+
+    /// Consumes this value, returning the underlying data.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.data.into_inner()
+    }
+}
+
+impl<'lock, T: ?Sized> GuardedBy<'lock, T> {
+    /// Returns a shared reference to the underlying data.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `guard` does not belong to the lock protecting
+    /// this value.
+    #[inline]
+    pub fn get<'a>(&'a self, guard: &'a RawPinnedMutexGuard<'_>) -> &'a T {
+        self.check(guard);
+        unsafe { &*self.data.get() }
+    }
+
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `guard` does not belong to the lock protecting
+    /// this value.
+    #[inline]
+    pub fn get_mut<'a>(&'a self, guard: &'a mut RawPinnedMutexGuard<'_>) -> &'a mut T {
+        self.check(guard);
+        unsafe { &mut *self.data.get() }
+    }
+
+    /// Returns the lock protecting this value.
+    #[inline]
+    pub fn lock(&self) -> Pin<&'lock RawPinnedMutex> {
+        self.lock
+    }
+
+    #[inline]
+    fn check(&self, guard: &RawPinnedMutexGuard<'_>) {
+        assert!(
+            ptr::eq(&*self.lock, &*guard.mutex),
+            "attempted to access guarded data with the guard of another lock"
+        );
+    }
+}
@@ -0,0 +1,229 @@
+use crate::{Condvar, Mutex, MutexGuard, PoisonError};
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+use std::pin::Pin;
+use std::sync::Arc;
+
+/// A mutual exclusion primitive locking keys instead of data.
+///
+/// Locking a key blocks other threads which try to lock an equal key until
+/// the returned guard is dropped, while other keys can be locked
+/// concurrently. This is useful to allow only one operation at a time per
+/// resource, such as a file or an id, out of an unbounded set of resources.
+///
+/// Only the keys which are currently locked take up memory.
+///
+/// Unlike [`Mutex`], a `KeyedMutex` is not poisoned when a thread panics while
+/// holding a key, as it does not protect any data itself.
+///
+/// # Examples
+///
+/// ```
+/// use pinned_sync::KeyedMutex;
+///
+/// let locks = KeyedMutex::boxed();
+/// let a = locks.as_ref().lock("a");
+/// // "a" is locked, but "b" is not.
+/// assert!(locks.as_ref().try_lock("a").is_none());
+/// assert!(locks.as_ref().try_lock("b").is_some());
+/// drop(a);
+/// assert!(locks.as_ref().try_lock("a").is_some());
+/// ```
+pub struct KeyedMutex<K> {
+    keys: Mutex<Option<HashMap<K, Key>>>,
+}
+
+// The state of a key which is locked.
+struct Key {
+    // Whether a guard of the key exists. When it is dropped while there are
+    // waiters, the key stays in the map unlocked until a waiter locks it.
+    locked: bool,
+    waiters: usize,
+    // Boxed so that waiters can wait on it while the map is modified.
+    cvar: Pin<Box<Condvar>>,
+}
+
+impl<K> KeyedMutex<K> {
+    /// Create a new, uninitialized keyed mutex.
+    ///
+    /// This is *NOT* equivalent to `MaybeUninit::uninit().assume_init()`, which will cause
+    /// undefined behaviour if used to create a new keyed mutex.
+    #[inline]
+    pub const fn uninit() -> Self {
+        Self {
+            keys: Mutex::uninit(None),
+        }
+    }
+
+    /// Create a new, initialized keyed mutex.
+    ///
+    /// The resulting keyed mutex is wrapped and ready for use.
+    #[inline]
+    pub fn boxed() -> Pin<Box<Self>> {
+        let this = Box::pin(Self::uninit());
+        this.as_ref().init();
+        this
+    }
+
+    /// Create a new, initialized keyed mutex.
+    ///
+    /// The resulting keyed mutex is wrapped and ready for use.
+    #[inline]
+    pub fn arc() -> Pin<Arc<Self>> {
+        let this = Arc::pin(Self::uninit());
+        this.as_ref().init();
+        this
+    }
+
+    /// Initialize a keyed mutex, making it ready for use.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the keyed mutex was already initialized.
+    #[inline]
+    pub fn init(self: Pin<&Self>) {
+        self.keys().init()
+    }
+
+    #[inline]
+    fn keys(self: Pin<&Self>) -> Pin<&Mutex<Option<HashMap<K, Key>>>> {
+        unsafe { self.map_unchecked(|this| &this.keys) }
+    }
+
+    #[inline]
+    fn lock_keys(self: Pin<&Self>) -> MutexGuard<'_, Option<HashMap<K, Key>>> {
+        self.keys().lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<K: Hash + Eq + Clone> KeyedMutex<K> {
+    /// Locks a key, blocking the current thread until it is able to do so.
+    ///
+    /// Upon returning, the thread is the only thread with the key locked. The
+    /// key is unlocked when the returned guard is dropped.
+    ///
+    /// The exact behavior on locking a key in the thread which already holds
+    /// it is left unspecified. However, this function will not return on the
+    /// second call (it might panic or deadlock, for example).
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the keyed mutex is not initialized.
+    pub fn lock(self: Pin<&Self>, key: K) -> KeyGuard<'_, K> {
+        let mut keys = self.lock_keys();
+        while let Some(state) = keys.get_or_insert_with(HashMap::new).get_mut(&key) {
+            if !state.locked {
+                state.locked = true;
+                return KeyGuard::new(self, key);
+            }
+            state.waiters += 1;
+            // Safety: The condition variable is boxed, and the key is not
+            // removed while it has waiters.
+            let cvar = unsafe { Pin::new_unchecked(&*(&*state.cvar as *const Condvar)) };
+            keys = cvar.wait(keys).unwrap_or_else(PoisonError::into_inner);
+            keys.as_mut().unwrap().get_mut(&key).unwrap().waiters -= 1;
+        }
+        Self::insert(&mut keys, key.clone());
+        KeyGuard::new(self, key)
+    }
+
+    /// Attempts to lock a key.
+    ///
+    /// If the key is locked by another guard, then [`None`] is returned.
+    /// Otherwise, a guard is returned, which unlocks the key when dropped.
+    ///
+    /// This function does not block.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the keyed mutex is not initialized.
+    pub fn try_lock(self: Pin<&Self>, key: K) -> Option<KeyGuard<'_, K>> {
+        let mut keys = self.lock_keys();
+        match keys.get_or_insert_with(HashMap::new).get_mut(&key) {
+            Some(state) if state.locked => return None,
+            Some(state) => state.locked = true,
+            None => Self::insert(&mut keys, key.clone()),
+        }
+        Some(KeyGuard::new(self, key))
+    }
+
+    /// Returns `true` if the key is currently locked.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the keyed mutex is not initialized.
+    pub fn is_locked<Q>(self: Pin<&Self>, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let keys = self.lock_keys();
+        keys.as_ref()
+            .and_then(|keys| keys.get(key))
+            .is_some_and(|state| state.locked)
+    }
+
+    fn insert(keys: &mut Option<HashMap<K, Key>>, key: K) {
+        keys.get_or_insert_with(HashMap::new).insert(
+            key,
+            Key {
+                locked: true,
+                waiters: 0,
+                cvar: Condvar::boxed(),
+            },
+        );
+    }
+
+    fn unlock(self: Pin<&Self>, key: &K) {
+        let mut keys = self.lock_keys();
+        let map = keys.as_mut().unwrap();
+        let state = map.get_mut(key).unwrap();
+        if state.waiters == 0 {
+            map.remove(key);
+        } else {
+            state.locked = false;
+            state.cvar.as_ref().notify_one();
+        }
+    }
+}
+
+impl<K> fmt::Debug for KeyedMutex<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("KeyedMutex { .. }")
+    }
+}
+
+/// An RAII guard of a key locked in a [`KeyedMutex`]. When this structure is
+/// dropped (falls out of scope), the key will be unlocked.
+pub struct KeyGuard<'a, K: Hash + Eq + Clone> {
+    mutex: Pin<&'a KeyedMutex<K>>,
+    key: K,
+}
+
+impl<'a, K: Hash + Eq + Clone> KeyGuard<'a, K> {
+    #[inline]
+    fn new(mutex: Pin<&'a KeyedMutex<K>>, key: K) -> Self {
+        Self { mutex, key }
+    }
+
+    /// Returns the locked key.
+    #[inline]
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+}
+
+impl<K: Hash + Eq + Clone> Drop for KeyGuard<'_, K> {
+    #[inline]
+    fn drop(&mut self) {
+        self.mutex.unlock(&self.key)
+    }
+}
+
+impl<K: Hash + Eq + Clone + fmt::Debug> fmt::Debug for KeyGuard<'_, K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyGuard").field("key", &self.key).finish()
+    }
+}
@@ -0,0 +1,355 @@
+use crate::{Condvar, Mutex, MutexGuard, PoisonError};
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+use std::pin::Pin;
+use std::sync::Arc;
+
+/// A reader-writer lock locking keys instead of data.
+///
+/// This is the reader-writer equivalent of [`KeyedMutex`]: each key can be
+/// locked by a number of readers or by at most one writer at any point in
+/// time, while other keys can be locked concurrently. This is useful for
+/// example for caches, which let many threads read an entry at once, but
+/// need exclusive access to invalidate it.
+///
+/// Only the keys which are currently locked take up memory.
+///
+/// The priority policy of the lock is unspecified. In particular, a key which
+/// is continuously read may starve writers of that key.
+///
+/// Unlike [`RwLock`], a `KeyedRwLock` is not poisoned when a thread panics
+/// while holding a key, as it does not protect any data itself.
+///
+/// [`KeyedMutex`]: crate::KeyedMutex
+/// [`RwLock`]: crate::RwLock
+///
+/// # Examples
+///
+/// ```
+/// use pinned_sync::KeyedRwLock;
+///
+/// let locks = KeyedRwLock::boxed();
+/// let r1 = locks.as_ref().read("a");
+/// let r2 = locks.as_ref().read("a");
+/// assert!(locks.as_ref().try_write("a").is_none());
+/// drop((r1, r2));
+/// assert!(locks.as_ref().try_write("a").is_some());
+/// ```
+pub struct KeyedRwLock<K> {
+    keys: Mutex<Option<HashMap<K, Key>>>,
+}
+
+// The state of a key which is locked.
+struct Key {
+    readers: usize,
+    writer: bool,
+    waiters: usize,
+    // Boxed so that waiters can wait on it while the map is modified.
+    cvar: Pin<Box<Condvar>>,
+}
+
+impl Key {
+    #[inline]
+    fn is_locked(&self) -> bool {
+        self.writer || self.readers > 0
+    }
+}
+
+impl<K> KeyedRwLock<K> {
+    /// Create a new, uninitialized keyed read-write lock.
+    ///
+    /// This is *NOT* equivalent to `MaybeUninit::uninit().assume_init()`, which will cause
+    /// undefined behaviour if used to create a new keyed read-write lock.
+    #[inline]
+    pub const fn uninit() -> Self {
+        Self {
+            keys: Mutex::uninit(None),
+        }
+    }
+
+    /// Create a new, initialized keyed read-write lock.
+    ///
+    /// The resulting keyed read-write lock is wrapped and ready for use.
+    #[inline]
+    pub fn boxed() -> Pin<Box<Self>> {
+        let this = Box::pin(Self::uninit());
+        this.as_ref().init();
+        this
+    }
+
+    /// Create a new, initialized keyed read-write lock.
+    ///
+    /// The resulting keyed read-write lock is wrapped and ready for use.
+    #[inline]
+    pub fn arc() -> Pin<Arc<Self>> {
+        let this = Arc::pin(Self::uninit());
+        this.as_ref().init();
+        this
+    }
+
+    /// Initialize a keyed read-write lock, making it ready for use.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the keyed read-write lock was already
+    /// initialized.
+    #[inline]
+    pub fn init(self: Pin<&Self>) {
+        self.keys().init()
+    }
+
+    #[inline]
+    fn keys(self: Pin<&Self>) -> Pin<&Mutex<Option<HashMap<K, Key>>>> {
+        unsafe { self.map_unchecked(|this| &this.keys) }
+    }
+
+    #[inline]
+    fn lock_keys(self: Pin<&Self>) -> MutexGuard<'_, Option<HashMap<K, Key>>> {
+        self.keys().lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<K: Hash + Eq + Clone> KeyedRwLock<K> {
+    /// Locks a key with shared read access, blocking the current thread until
+    /// it can be acquired.
+    ///
+    /// The calling thread will be blocked until there is no writer holding
+    /// the key. There may be other readers holding the key when this method
+    /// returns. The key is unlocked when the returned guard is dropped.
+    ///
+    /// # Panics
+    ///
+    /// This function might panic when called if the key is already locked for
+    /// writing by the current thread.
+    ///
+    /// This function may panic if the keyed read-write lock is not
+    /// initialized.
+    pub fn read(self: Pin<&Self>, key: K) -> KeyReadGuard<'_, K> {
+        self.acquire(&key, |state| !state.writer, |state| state.readers += 1);
+        KeyReadGuard { lock: self, key }
+    }
+
+    /// Attempts to lock a key with shared read access.
+    ///
+    /// If the key is locked for writing, then [`None`] is returned.
+    /// Otherwise, a guard is returned, which unlocks the key when dropped.
+    ///
+    /// This function does not block.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the keyed read-write lock is not
+    /// initialized.
+    pub fn try_read(self: Pin<&Self>, key: K) -> Option<KeyReadGuard<'_, K>> {
+        if self.try_acquire(&key, |state| !state.writer, |state| state.readers += 1) {
+            Some(KeyReadGuard { lock: self, key })
+        } else {
+            None
+        }
+    }
+
+    /// Locks a key with exclusive write access, blocking the current thread
+    /// until it can be acquired.
+    ///
+    /// This function will not return while other writers or other readers
+    /// currently hold the key. The key is unlocked when the returned guard is
+    /// dropped.
+    ///
+    /// # Panics
+    ///
+    /// This function might panic when called if the key is already locked by
+    /// the current thread.
+    ///
+    /// This function may panic if the keyed read-write lock is not
+    /// initialized.
+    pub fn write(self: Pin<&Self>, key: K) -> KeyWriteGuard<'_, K> {
+        self.acquire(
+            &key,
+            |state| !state.is_locked(),
+            |state| state.writer = true,
+        );
+        KeyWriteGuard { lock: self, key }
+    }
+
+    /// Attempts to lock a key with exclusive write access.
+    ///
+    /// If the key is locked by any reader or writer, then [`None`] is
+    /// returned. Otherwise, a guard is returned, which unlocks the key when
+    /// dropped.
+    ///
+    /// This function does not block.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the keyed read-write lock is not
+    /// initialized.
+    pub fn try_write(self: Pin<&Self>, key: K) -> Option<KeyWriteGuard<'_, K>> {
+        if self.try_acquire(
+            &key,
+            |state| !state.is_locked(),
+            |state| state.writer = true,
+        ) {
+            Some(KeyWriteGuard { lock: self, key })
+        } else {
+            None
+        }
+    }
+
+    /// Returns `true` if the key is currently locked, for reading or writing.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the keyed read-write lock is not
+    /// initialized.
+    pub fn is_locked<Q>(self: Pin<&Self>, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let keys = self.lock_keys();
+        keys.as_ref()
+            .and_then(|keys| keys.get(key))
+            .is_some_and(Key::is_locked)
+    }
+
+    fn acquire(
+        self: Pin<&Self>,
+        key: &K,
+        free: impl Fn(&Key) -> bool,
+        lock: impl FnOnce(&mut Key),
+    ) {
+        let mut keys = self.lock_keys();
+        loop {
+            let state = Self::state(&mut keys, key);
+            if free(state) {
+                lock(state);
+                return;
+            }
+            state.waiters += 1;
+            // Safety: The condition variable is boxed, and the key is not
+            // removed while it has waiters.
+            let cvar = unsafe { Pin::new_unchecked(&*(&*state.cvar as *const Condvar)) };
+            keys = cvar.wait(keys).unwrap_or_else(PoisonError::into_inner);
+            Self::state(&mut keys, key).waiters -= 1;
+        }
+    }
+
+    fn try_acquire(
+        self: Pin<&Self>,
+        key: &K,
+        free: impl Fn(&Key) -> bool,
+        lock: impl FnOnce(&mut Key),
+    ) -> bool {
+        let mut keys = self.lock_keys();
+        let state = Self::state(&mut keys, key);
+        if free(state) {
+            lock(state);
+            true
+        } else {
+            // Do not leave behind a key created for this attempt.
+            Self::release(&mut keys, key, |_| {});
+            false
+        }
+    }
+
+    fn unlock(self: Pin<&Self>, key: &K, unlock: impl FnOnce(&mut Key)) {
+        Self::release(&mut self.lock_keys(), key, unlock)
+    }
+
+    // Returns the state of a key, inserting it unlocked if it is not in the map.
+    fn state<'a>(keys: &'a mut Option<HashMap<K, Key>>, key: &K) -> &'a mut Key {
+        keys.get_or_insert_with(HashMap::new)
+            .entry(key.clone())
+            .or_insert_with(|| Key {
+                readers: 0,
+                writer: false,
+                waiters: 0,
+                cvar: Condvar::boxed(),
+            })
+    }
+
+    // Unlocks a key with `unlock`, then removes it from the map or wakes up its
+    // waiters.
+    fn release(keys: &mut Option<HashMap<K, Key>>, key: &K, unlock: impl FnOnce(&mut Key)) {
+        let map = keys.as_mut().unwrap();
+        let state = map.get_mut(key).unwrap();
+        unlock(state);
+        if state.is_locked() {
+            return;
+        }
+        if state.waiters == 0 {
+            map.remove(key);
+        } else {
+            // Both readers and writers may be waiting, so wake up everyone.
+            state.cvar.as_ref().notify_all();
+        }
+    }
+}
+
+impl<K> fmt::Debug for KeyedRwLock<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("KeyedRwLock { .. }")
+    }
+}
+
+/// An RAII guard of a key locked for reading in a [`KeyedRwLock`]. When this
+/// structure is dropped (falls out of scope), the key will be unlocked.
+pub struct KeyReadGuard<'a, K: Hash + Eq + Clone> {
+    lock: Pin<&'a KeyedRwLock<K>>,
+    key: K,
+}
+
+impl<K: Hash + Eq + Clone> KeyReadGuard<'_, K> {
+    /// Returns the locked key.
+    #[inline]
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+}
+
+impl<K: Hash + Eq + Clone> Drop for KeyReadGuard<'_, K> {
+    #[inline]
+    fn drop(&mut self) {
+        self.lock.unlock(&self.key, |state| state.readers -= 1)
+    }
+}
+
+impl<K: Hash + Eq + Clone + fmt::Debug> fmt::Debug for KeyReadGuard<'_, K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyReadGuard")
+            .field("key", &self.key)
+            .finish()
+    }
+}
+
+/// An RAII guard of a key locked for writing in a [`KeyedRwLock`]. When this
+/// structure is dropped (falls out of scope), the key will be unlocked.
+pub struct KeyWriteGuard<'a, K: Hash + Eq + Clone> {
+    lock: Pin<&'a KeyedRwLock<K>>,
+    key: K,
+}
+
+impl<K: Hash + Eq + Clone> KeyWriteGuard<'_, K> {
+    /// Returns the locked key.
+    #[inline]
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+}
+
+impl<K: Hash + Eq + Clone> Drop for KeyWriteGuard<'_, K> {
+    #[inline]
+    fn drop(&mut self) {
+        self.lock.unlock(&self.key, |state| state.writer = false)
+    }
+}
+
+impl<K: Hash + Eq + Clone + fmt::Debug> fmt::Debug for KeyWriteGuard<'_, K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyWriteGuard")
+            .field("key", &self.key)
+            .finish()
+    }
+}
@@ -0,0 +1,194 @@
+use crate::{LockId, LockResult, Mutex, MutexGuard, TryLockResult};
+use std::fmt;
+use std::pin::Pin;
+use std::sync::{Arc, Once};
+use std::time::{Duration, Instant};
+
+/// A mutual exclusion primitive which initializes itself on first use.
+///
+/// A [`Mutex`] has to be initialized with [`Mutex::init`] once it is pinned and
+/// before it is locked, which is easy to get wrong when there is no single
+/// place where that can be done, such as for a `static` reachable from many
+/// entry points. A `LazyInitMutex` performs the initialization the first time
+/// it is locked instead, guarded by a [`Once`], so that it is ready for use as
+/// soon as it is pinned. Afterwards, locking costs one extra atomic load.
+///
+/// The guards are those of [`Mutex`], so they can be used with a
+/// [`Condvar`](crate::Condvar) as usual.
+///
+/// # Examples
+///
+/// ```
+/// use pinned_sync::LazyInitMutex;
+/// use std::pin::Pin;
+/// use std::thread;
+///
+/// static COUNTER: LazyInitMutex<i32> = LazyInitMutex::new(0);
+///
+/// let threads: Vec<_> = (0..4)
+///     .map(|_| {
+///         thread::spawn(|| {
+///             *Pin::static_ref(&COUNTER).lock().unwrap() += 1;
+///         })
+///     })
+///     .collect();
+/// for thread in threads {
+///     thread.join().unwrap();
+/// }
+/// assert_eq!(*Pin::static_ref(&COUNTER).lock().unwrap(), 4);
+/// ```
+pub struct LazyInitMutex<T: ?Sized> {
+    once: Once,
+    inner: Mutex<T>,
+}
+
+impl<T> LazyInitMutex<T> {
+    /// Create a new mutex, which will be initialized the first time it is
+    /// locked.
+    #[inline]
+    pub const fn new(value: T) -> Self {
+        Self {
+            once: Once::new(),
+            inner: Mutex::uninit(value),
+        }
+    }
+
+    /// Create a new mutex.
+    ///
+    /// The resulting mutex is wrapped and ready for use.
+    #[inline]
+    pub fn boxed(value: T) -> Pin<Box<Self>> {
+        Box::pin(Self::new(value))
+    }
+
+    /// Create a new mutex.
+    ///
+    /// The resulting mutex is wrapped and ready for use.
+    #[inline]
+    pub fn arc(value: T) -> Pin<Arc<Self>> {
+        Arc::pin(Self::new(value))
+    }
+
+    /// Consumes this mutex, returning the underlying data.
+    ///
+    /// # Errors
+    ///
+    /// If another user of this mutex panicked while holding the mutex, then
+    /// this call will return an error instead.
+    #[inline]
+    pub fn into_inner(self) -> LockResult<T> {
+        self.inner.into_inner()
+    }
+}
+
+impl<T: ?Sized> LazyInitMutex<T> {
+    /// Acquires the mutex, blocking the current thread until it is able to do
+    /// so, after initializing it if this is the first use.
+    ///
+    /// See [`Mutex::lock`].
+    ///
+    /// # Errors
+    ///
+    /// If another user of this mutex panicked while holding the mutex, then
+    /// this call will return an error once the mutex is acquired.
+    ///
+    /// # Panics
+    ///
+    /// This function might panic when called if the lock is already held by
+    /// the current thread.
+    #[inline]
+    pub fn lock(self: Pin<&Self>) -> LockResult<MutexGuard<'_, T>> {
+        self.inner().lock()
+    }
+
+    /// Attempts to acquire this lock, after initializing it if this is the
+    /// first use.
+    ///
+    /// See [`Mutex::try_lock`].
+    ///
+    /// # Errors
+    ///
+    /// If another user of this mutex panicked while holding the mutex, then
+    /// this call will return an error if the mutex would otherwise be
+    /// acquired.
+    #[inline]
+    pub fn try_lock(self: Pin<&Self>) -> TryLockResult<MutexGuard<'_, T>> {
+        self.inner().try_lock()
+    }
+
+    /// Attempts to acquire this lock, blocking the current thread until it is
+    /// able to do so or the timeout expires.
+    ///
+    /// See [`Mutex::try_lock_for`].
+    #[inline]
+    pub fn try_lock_for(self: Pin<&Self>, dur: Duration) -> TryLockResult<MutexGuard<'_, T>> {
+        self.inner().try_lock_for(dur)
+    }
+
+    /// Attempts to acquire this lock, blocking the current thread until it is
+    /// able to do so or the deadline is reached.
+    ///
+    /// See [`Mutex::try_lock_until`].
+    #[inline]
+    pub fn try_lock_until(self: Pin<&Self>, deadline: Instant) -> TryLockResult<MutexGuard<'_, T>> {
+        self.inner().try_lock_until(deadline)
+    }
+
+    /// Returns the identifier of this mutex, which stays the same for as long
+    /// as it is alive.
+    #[inline]
+    pub fn id(self: Pin<&Self>) -> LockId {
+        self.mutex().id()
+    }
+
+    /// Determines whether the mutex is poisoned.
+    ///
+    /// See [`Mutex::is_poisoned`].
+    #[inline]
+    pub fn is_poisoned(self: Pin<&Self>) -> bool {
+        self.mutex().is_poisoned()
+    }
+
+    /// Clears the poisoned state from a mutex.
+    ///
+    /// See [`Mutex::clear_poison`].
+    #[inline]
+    pub fn clear_poison(self: Pin<&Self>) {
+        self.mutex().clear_poison()
+    }
+
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// Since this call borrows the `LazyInitMutex` mutably, no actual locking
+    /// needs to take place -- the mutable borrow statically guarantees no
+    /// locks exist.
+    ///
+    /// # Errors
+    ///
+    /// If another user of this mutex panicked while holding the mutex, then
+    /// this call will return an error instead.
+    #[inline]
+    pub fn get_mut(&mut self) -> LockResult<&mut T> {
+        self.inner.get_mut()
+    }
+
+    // The mutex, initialized.
+    #[inline]
+    fn inner(self: Pin<&Self>) -> Pin<&Mutex<T>> {
+        let mutex = self.mutex();
+        self.once.call_once(|| mutex.init());
+        mutex
+    }
+
+    // The mutex, possibly not initialized yet.
+    #[inline]
+    fn mutex(self: Pin<&Self>) -> Pin<&Mutex<T>> {
+        unsafe { self.map_unchecked(|this| &this.inner) }
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for LazyInitMutex<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LazyInitMutex").finish_non_exhaustive()
+    }
+}
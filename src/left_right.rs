@@ -0,0 +1,252 @@
+use crate::{Mutex, Primitives};
+use std::cell::UnsafeCell;
+use std::fmt;
+use std::ops::Deref;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering::SeqCst};
+use std::sync::Arc;
+use std::thread;
+
+/// A left-right lock, keeping two copies of the data so that readers never
+/// wait.
+///
+/// Readers access whichever copy is currently active, which only takes a
+/// couple of atomic operations and never blocks, even while a writer is
+/// modifying the data. A writer applies its operation to the inactive copy,
+/// makes it the active one, waits for the readers of the previous copy to
+/// leave, and then applies the same operation to that copy too.
+///
+/// This suits structures whose read latency matters more than the doubled
+/// memory and the doubled cost of writes. Writers are serialized, and a
+/// writer waits for the readers which started before it, so guards should
+/// not be held for long.
+///
+/// If a write operation panics, the copy it was modifying is restored from
+/// the other one, so there is no poisoning.
+///
+/// # Examples
+///
+/// ```
+/// use pinned_sync::LeftRight;
+///
+/// let lock = LeftRight::boxed(Vec::new());
+///
+/// lock.as_ref().write(|v| v.push(1));
+/// assert_eq!(*lock.as_ref().read(), [1]);
+/// ```
+pub struct LeftRight<T> {
+    sides: [UnsafeCell<T>; 2],
+    // The side which readers access.
+    active: AtomicUsize,
+    // The counter which readers announce themselves on.
+    version: AtomicUsize,
+    readers: [AtomicUsize; 2],
+    writer: Mutex<()>,
+}
+
+unsafe impl<T: Send> Send for LeftRight<T> {}
+
+unsafe impl<T: Send + Sync> Sync for LeftRight<T> {}
+
+impl<T: Clone> LeftRight<T> {
+    /// Create a new, uninitialized left-right lock, with two copies of
+    /// `value`.
+    ///
+    /// This is *NOT* equivalent to `MaybeUninit::uninit().assume_init()`, which will cause
+    /// undefined behaviour if used to create a new left-right lock.
+    #[inline]
+    pub fn uninit(value: T) -> Self {
+        Self {
+            sides: [UnsafeCell::new(value.clone()), UnsafeCell::new(value)],
+            active: AtomicUsize::new(0),
+            version: AtomicUsize::new(0),
+            readers: [AtomicUsize::new(0), AtomicUsize::new(0)],
+            writer: Mutex::uninit(()).poisoning(false),
+        }
+    }
+
+    /// Create a new, initialized left-right lock, with two copies of `value`.
+    ///
+    /// The resulting left-right lock is wrapped and ready for use.
+    #[inline]
+    pub fn boxed(value: T) -> Pin<Box<Self>> {
+        let this = Box::pin(Self::uninit(value));
+        this.as_ref().init();
+        this
+    }
+
+    /// Create a new, initialized left-right lock, with two copies of `value`.
+    ///
+    /// The resulting left-right lock is wrapped and ready for use.
+    #[inline]
+    pub fn arc(value: T) -> Pin<Arc<Self>> {
+        let this = Arc::pin(Self::uninit(value));
+        this.as_ref().init();
+        this
+    }
+
+    /// Applies `op` to the data, blocking the current thread until no other
+    /// writer holds the lock.
+    ///
+    /// `op` is called twice, once for each copy, and must have the same
+    /// effect on both of them.
+    ///
+    /// # Panics
+    ///
+    /// This function might panic when called if the lock is already held for
+    /// writing by the current thread, or deadlock if the current thread holds
+    /// a read guard.
+    ///
+    /// This function may panic if the lock is not initialized.
+    pub fn write<F: FnMut(&mut T)>(self: Pin<&Self>, mut op: F) {
+        let _writer = self.writer().lock().unwrap();
+        let active = self.active.load(SeqCst);
+        let inactive = 1 - active;
+
+        // Safety: Only writers access the inactive side.
+        unsafe { self.apply(inactive, &mut op) };
+        self.active.store(inactive, SeqCst);
+
+        // New readers announce themselves on the other counter, so that the
+        // readers which may still access the previous side can be waited for
+        // without being starved by new ones.
+        let version = self.version.load(SeqCst);
+        self.wait_for_readers(1 - version);
+        self.version.store(1 - version, SeqCst);
+        self.wait_for_readers(version);
+
+        // Safety: Every reader now accesses the other side.
+        unsafe { self.apply(active, &mut op) };
+    }
+
+    /// Applies `op` to a side no reader accesses, restoring the side from the
+    /// other one if it panics.
+    unsafe fn apply<F: FnMut(&mut T)>(self: Pin<&Self>, side: usize, op: &mut F) {
+        struct Restore<'a, T: Clone>(&'a LeftRight<T>, usize);
+
+        impl<T: Clone> Drop for Restore<'_, T> {
+            fn drop(&mut self) {
+                let LeftRight { sides, .. } = self.0;
+                unsafe { *sides[self.1].get() = (*sides[1 - self.1].get()).clone() };
+            }
+        }
+
+        let restore = Restore(self.get_ref(), side);
+        op(&mut *self.sides[side].get());
+        std::mem::forget(restore);
+    }
+}
+
+impl<T> LeftRight<T> {
+    /// Initialize a left-right lock, making it ready for use.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the lock was already initialized.
+    #[inline]
+    pub fn init(self: Pin<&Self>) {
+        self.writer().init();
+    }
+
+    /// Locks the active copy of the data with shared read access.
+    ///
+    /// This function never blocks.
+    pub fn read(self: Pin<&Self>) -> LeftRightReadGuard<'_, T> {
+        let version = self.version.load(SeqCst);
+        self.readers[version].fetch_add(1, SeqCst);
+        let side = self.active.load(SeqCst);
+        LeftRightReadGuard {
+            lock: self,
+            version,
+            side,
+        }
+    }
+
+    /// Consumes this left-right lock, returning the underlying data.
+    pub fn into_inner(self) -> T {
+        let [left, right] = self.sides;
+        if self.active.into_inner() == 0 {
+            left.into_inner()
+        } else {
+            right.into_inner()
+        }
+    }
+
+    /// Returns a mutable reference to the active copy of the data.
+    ///
+    /// Since this call borrows the `LeftRight` mutably, no actual locking
+    /// needs to take place -- the mutable borrow statically guarantees no
+    /// locks exist. The other copy is not updated, so this is only useful
+    /// right before [`into_inner`].
+    ///
+    /// [`into_inner`]: Self::into_inner
+    pub fn get_mut(&mut self) -> &mut T {
+        let active = *self.active.get_mut();
+        self.sides[active].get_mut()
+    }
+
+    fn wait_for_readers(&self, version: usize) {
+        while self.readers[version].load(SeqCst) != 0 {
+            thread::yield_now();
+        }
+    }
+
+    #[inline]
+    fn writer(self: Pin<&Self>) -> Pin<&Mutex<()>> {
+        unsafe { self.map_unchecked(|this| &this.writer) }
+    }
+}
+
+impl<T> Primitives for LeftRight<T> {
+    type Pinned<'a>
+        = Pin<&'a Self>
+    where
+        Self: 'a;
+
+    #[inline]
+    fn init_pinned(self: Pin<&Self>) -> Pin<&Self> {
+        self.init();
+        self
+    }
+}
+
+impl<T> fmt::Debug for LeftRight<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LeftRight").finish_non_exhaustive()
+    }
+}
+
+/// An RAII guard of the shared read access of a [`LeftRight`] lock. When
+/// this structure is dropped, the read access is released.
+///
+/// The data can be accessed through this guard via its [`Deref`]
+/// implementation.
+pub struct LeftRightReadGuard<'a, T> {
+    lock: Pin<&'a LeftRight<T>>,
+    version: usize,
+    side: usize,
+}
+
+impl<T> Deref for LeftRightReadGuard<'_, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        // Safety: The writer does not modify this side until this reader
+        // leaves.
+        unsafe { &*self.lock.sides[self.side].get() }
+    }
+}
+
+impl<T> Drop for LeftRightReadGuard<'_, T> {
+    #[inline]
+    fn drop(&mut self) {
+        self.lock.readers[self.version].fetch_sub(1, SeqCst);
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for LeftRightReadGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
@@ -1,13 +1,85 @@
 //! This is a proof-of-concept crate for pinned-sync RFC.
 
+#[macro_use]
+mod macros;
+
 mod barrier;
+mod brand;
+pub mod channel;
+mod combining_mutex;
 mod condvar;
+mod condvar_any;
+mod cow_lock;
+mod error;
+mod event;
+mod event_pair;
+mod fair_mutex;
+#[cfg(unix)]
+mod fork;
+mod guarded;
+mod keyed_mutex;
+mod keyed_rwlock;
+mod lazy_init_mutex;
+mod left_right;
+mod lock_id;
+#[cfg(feature = "profiling")]
+mod lock_trace;
+mod manual_reset_event;
+mod monitor;
 mod mutex;
+pub mod nopoison;
+mod once_map;
+mod ordered;
+mod parker;
+mod phaser;
+mod pod;
+pub mod raw;
+mod remutex;
 mod rwlock;
+mod scope;
+mod sharded_lock;
+#[cfg(unix)]
+mod signal_safe_lock;
+mod spin_lock;
+mod striped;
 mod sys;
 mod sys_common;
+mod weak;
 
 pub use barrier::*;
+pub use brand::*;
+pub use combining_mutex::*;
 pub use condvar::*;
+pub use condvar_any::*;
+pub use cow_lock::*;
+pub use error::*;
+pub use event::*;
+pub use event_pair::*;
+pub use fair_mutex::*;
+#[cfg(unix)]
+pub use fork::*;
+pub use guarded::*;
+pub use keyed_mutex::*;
+pub use keyed_rwlock::*;
+pub use lazy_init_mutex::*;
+pub use left_right::*;
+pub use lock_id::*;
+#[cfg(feature = "profiling")]
+pub use lock_trace::*;
+pub use manual_reset_event::*;
+pub use monitor::*;
 pub use mutex::*;
+pub use once_map::*;
+pub use ordered::*;
+pub use parker::*;
+pub use phaser::*;
+pub use pod::*;
+pub use remutex::*;
 pub use rwlock::*;
+pub use scope::*;
+pub use sharded_lock::*;
+#[cfg(unix)]
+pub use signal_safe_lock::*;
+pub use spin_lock::*;
+pub use striped::*;
+pub use weak::*;
@@ -0,0 +1,59 @@
+use std::fmt;
+
+/// A stable identifier of a pinned primitive.
+///
+/// It is derived from the address of the primitive, which can not change once
+/// it is pinned, so it identifies the same primitive for as long as it is
+/// alive, across threads. This allows correlating events about the same lock
+/// in logs and traces. Once a primitive is dropped, its identifier may be
+/// reused by another one.
+///
+/// It is returned by the `id` method of the primitives, and is formatted as a
+/// hexadecimal address by [`Display`](fmt::Display), [`Debug`](fmt::Debug)
+/// and [`Pointer`](fmt::Pointer), the same way as the `{:p}` formatting of the
+/// primitive itself.
+///
+/// # Examples
+///
+/// ```
+/// use pinned_sync::Mutex;
+///
+/// let mutex = Mutex::boxed(0);
+/// let id = mutex.as_ref().id();
+///
+/// assert_eq!(id, mutex.as_ref().id());
+/// assert_eq!(format!("{}", id), format!("{:p}", *mutex));
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct LockId(usize);
+
+impl LockId {
+    #[inline]
+    pub(crate) fn of<T: ?Sized>(primitive: &T) -> Self {
+        LockId(primitive as *const T as *const () as usize)
+    }
+
+    /// Returns the identifier as an integer.
+    #[inline]
+    pub fn as_usize(self) -> usize {
+        self.0
+    }
+}
+
+impl fmt::Debug for LockId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Pointer::fmt(self, f)
+    }
+}
+
+impl fmt::Display for LockId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Pointer::fmt(self, f)
+    }
+}
+
+impl fmt::Pointer for LockId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Pointer::fmt(&(self.0 as *const ()), f)
+    }
+}
@@ -0,0 +1,289 @@
+use crate::LockId;
+use std::cell::Cell;
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering::*};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// A recording of the time threads spent waiting for and holding locks.
+///
+/// Between [`start`] and [`stop`], every [`Mutex`] and [`RwLock`] acquired by
+/// any thread is recorded as two intervals: the time the thread waited for
+/// the lock, and the time it held it. The recording can be exported as a
+/// [Chrome trace] with [`write_chrome_trace`], which can be opened in
+/// `chrome://tracing` or [Perfetto] to see lock contention on a timeline,
+/// alongside application spans traced the same way.
+///
+/// This is only available with the `profiling` feature.
+///
+/// # Examples
+///
+/// ```
+/// use pinned_sync::{LockTrace, Mutex};
+///
+/// let mutex = Mutex::boxed(0);
+///
+/// LockTrace::start();
+/// *mutex.as_ref().lock().unwrap() += 1;
+/// let trace = LockTrace::stop();
+///
+/// let mut json = Vec::new();
+/// trace.write_chrome_trace(&mut json).unwrap();
+/// ```
+///
+/// [`start`]: Self::start
+/// [`stop`]: Self::stop
+/// [`write_chrome_trace`]: Self::write_chrome_trace
+/// [`Mutex`]: crate::Mutex
+/// [`RwLock`]: crate::RwLock
+/// [Chrome trace]: https://docs.google.com/document/d/1CvAClvFfyA5R-PhYUmn5OOQtYMH4h6I0nSsKchNAySU
+/// [Perfetto]: https://ui.perfetto.dev
+#[derive(Debug, Clone, Default)]
+pub struct LockTrace {
+    events: Vec<LockEvent>,
+    threads: Vec<(u64, String)>,
+}
+
+/// Whether a [`LockEvent`] is a wait for a lock or a hold of it.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+pub enum LockEventKind {
+    /// The thread was blocked until it acquired the lock.
+    Wait,
+    /// The thread held the lock.
+    Hold,
+}
+
+/// An interval of a [`LockTrace`].
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct LockEvent {
+    kind: LockEventKind,
+    lock: LockId,
+    primitive: &'static str,
+    thread: u64,
+    start: Duration,
+    duration: Duration,
+}
+
+impl LockEvent {
+    /// Returns whether this is a wait or a hold.
+    #[inline]
+    pub fn kind(&self) -> LockEventKind {
+        self.kind
+    }
+
+    /// Returns the identifier of the lock.
+    #[inline]
+    pub fn lock(&self) -> LockId {
+        self.lock
+    }
+
+    /// Returns the kind of lock, such as `"Mutex"` or `"RwLock::write"`.
+    #[inline]
+    pub fn primitive(&self) -> &'static str {
+        self.primitive
+    }
+
+    /// Returns a number identifying the thread, unique for the lifetime of
+    /// the process.
+    #[inline]
+    pub fn thread(&self) -> u64 {
+        self.thread
+    }
+
+    /// Returns when the interval started, relative to [`LockTrace::start`].
+    #[inline]
+    pub fn start(&self) -> Duration {
+        self.start
+    }
+
+    /// Returns the length of the interval.
+    #[inline]
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+}
+
+struct Recorder {
+    epoch: Option<Instant>,
+    // Incremented on every `start`, so that threads describe themselves once
+    // per recording.
+    session: u64,
+    trace: LockTrace,
+}
+
+static RECORDING: AtomicBool = AtomicBool::new(false);
+
+static RECORDER: Mutex<Recorder> = Mutex::new(Recorder {
+    epoch: None,
+    session: 0,
+    trace: LockTrace {
+        events: Vec::new(),
+        threads: Vec::new(),
+    },
+});
+
+static NEXT_THREAD: AtomicU64 = AtomicU64::new(1);
+
+thread_local! {
+    // The number of the current thread, and the last session it was
+    // described in.
+    static THREAD: (u64, Cell<u64>) = (NEXT_THREAD.fetch_add(1, Relaxed), Cell::new(0));
+}
+
+impl LockTrace {
+    /// Starts recording, discarding anything recorded before.
+    ///
+    /// Guards which were acquired before this call are not recorded.
+    pub fn start() {
+        let mut recorder = RECORDER.lock().unwrap_or_else(|e| e.into_inner());
+        recorder.epoch = Some(Instant::now());
+        recorder.session += 1;
+        recorder.trace = LockTrace::default();
+        RECORDING.store(true, Relaxed);
+    }
+
+    /// Stops recording, returning everything recorded since [`start`].
+    ///
+    /// Guards which are still held are not part of the result.
+    ///
+    /// [`start`]: Self::start
+    pub fn stop() -> LockTrace {
+        RECORDING.store(false, Relaxed);
+        let mut recorder = RECORDER.lock().unwrap_or_else(|e| e.into_inner());
+        recorder.epoch = None;
+        std::mem::take(&mut recorder.trace)
+    }
+
+    /// Returns the recorded intervals, in the order in which they ended.
+    #[inline]
+    pub fn events(&self) -> &[LockEvent] {
+        &self.events
+    }
+
+    /// Writes the recording in the Chrome trace event JSON format.
+    ///
+    /// Every interval becomes a complete event on the track of its thread,
+    /// named after the kind of interval and of lock, with the identifier of
+    /// the lock as an argument. Threads are named after [`Thread::name`] where
+    /// available.
+    ///
+    /// # Errors
+    ///
+    /// This function returns any error returned by the writer.
+    ///
+    /// [`Thread::name`]: std::thread::Thread::name
+    pub fn write_chrome_trace<W: Write>(&self, writer: W) -> io::Result<()> {
+        let mut w = io::BufWriter::new(writer);
+        let pid = std::process::id();
+        write!(w, "{{\"traceEvents\":[")?;
+        let mut first = true;
+        for (thread, name) in &self.threads {
+            if !first {
+                write!(w, ",")?;
+            }
+            first = false;
+            write!(
+                w,
+                "{{\"name\":\"thread_name\",\"ph\":\"M\",\"pid\":{},\"tid\":{},\"args\":{{\"name\":\"{}\"}}}}",
+                pid,
+                thread,
+                JsonStr(name),
+            )?;
+        }
+        for event in &self.events {
+            if !first {
+                write!(w, ",")?;
+            }
+            first = false;
+            let kind = match event.kind {
+                LockEventKind::Wait => "wait",
+                LockEventKind::Hold => "hold",
+            };
+            write!(
+                w,
+                "{{\"name\":\"{} {}\",\"cat\":\"lock\",\"ph\":\"X\",\"ts\":{},\"dur\":{},\"pid\":{},\"tid\":{},\"args\":{{\"lock\":\"{:p}\"}}}}",
+                kind,
+                JsonStr(event.primitive),
+                Micros(event.start),
+                Micros(event.duration),
+                pid,
+                event.thread,
+                event.lock,
+            )?;
+        }
+        write!(w, "],\"displayTimeUnit\":\"ns\"}}")?;
+        w.flush()
+    }
+}
+
+/// Whether a [`LockTrace`] is being recorded.
+#[inline]
+pub(crate) fn recording() -> bool {
+    RECORDING.load(Relaxed)
+}
+
+/// Adds an interval to the [`LockTrace`] being recorded, if any.
+#[cold]
+pub(crate) fn record(
+    kind: LockEventKind,
+    lock: LockId,
+    primitive: &'static str,
+    start: Instant,
+    end: Instant,
+) {
+    // This may run during thread destruction, after the thread number is
+    // gone, in which case the interval is lost.
+    let _ = THREAD.try_with(|(thread, session)| {
+        let mut recorder = RECORDER.lock().unwrap_or_else(|e| e.into_inner());
+        let epoch = match recorder.epoch {
+            Some(epoch) if start >= epoch => epoch,
+            _ => return,
+        };
+        if session.get() != recorder.session {
+            session.set(recorder.session);
+            let current = thread::current();
+            let name = match current.name() {
+                Some(name) => name.to_owned(),
+                None => format!("{:?}", current.id()),
+            };
+            recorder.trace.threads.push((*thread, name));
+        }
+        recorder.trace.events.push(LockEvent {
+            kind,
+            lock,
+            primitive,
+            thread: *thread,
+            start: start - epoch,
+            duration: end - start,
+        });
+    });
+}
+
+// Formats a duration as a fractional number of microseconds.
+struct Micros(Duration);
+
+impl fmt::Display for Micros {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let nanos = self.0.as_nanos();
+        write!(f, "{}.{:03}", nanos / 1000, nanos % 1000)
+    }
+}
+
+// Formats the contents of a JSON string.
+struct JsonStr<'a>(&'a str);
+
+impl fmt::Display for JsonStr<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for c in self.0.chars() {
+            match c {
+                '"' => f.write_str("\\\"")?,
+                '\\' => f.write_str("\\\\")?,
+                c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+                c => write!(f, "{}", c)?,
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,218 @@
+/// Locks a mutex for the duration of a block.
+///
+/// `lock!(mutex => data { ... })` acquires `mutex`, binds `data` to a mutable
+/// reference to the protected value and evaluates the block. The guard is
+/// dropped as soon as the block finishes, so it can not accidentally outlive
+/// the code which needs it. The value of the block is the value of the macro.
+///
+/// `mutex` can be any pinned pointer to a [`Mutex`], such as
+/// `Pin<Box<Mutex<T>>>`, `Pin<Arc<Mutex<T>>>` or `Pin<&Mutex<T>>`; the
+/// `.as_ref()` call is done by the macro.
+///
+/// # Poisoning
+///
+/// By default, the macro panics if the mutex is poisoned, which is the same as
+/// calling [`unwrap()`] on the result of [`Mutex::lock`]. The policy can be
+/// chosen explicitly by adding `poison = <policy>` after the binding:
+///
+/// - `poison = panic` panics if the mutex is poisoned (the default).
+/// - `poison = ignore` gives access to the data even if the mutex is
+///   poisoned.
+///
+/// # Examples
+///
+/// ```
+/// use pinned_sync::{lock, Mutex};
+///
+/// let mutex = Mutex::boxed(0);
+///
+/// lock!(mutex => data {
+///     *data += 1;
+/// });
+///
+/// let value = lock!(mutex => data, poison = ignore { *data });
+/// assert_eq!(value, 1);
+/// ```
+///
+/// [`Mutex`]: crate::Mutex
+/// [`Mutex::lock`]: crate::Mutex::lock
+/// [`unwrap()`]: Result::unwrap
+#[macro_export]
+macro_rules! lock {
+    ($mutex:expr => $data:ident $body:block) => {
+        $crate::lock!($mutex => $data, poison = panic $body)
+    };
+    ($mutex:expr => $data:ident, poison = panic $body:block) => {{
+        let mut guard = ::std::pin::Pin::as_ref(&$mutex).lock().unwrap();
+        let $data = &mut *guard;
+        $body
+    }};
+    ($mutex:expr => $data:ident, poison = ignore $body:block) => {{
+        let mut guard = match ::std::pin::Pin::as_ref(&$mutex).lock() {
+            Ok(guard) => guard,
+            Err(error) => error.into_inner(),
+        };
+        let $data = &mut *guard;
+        $body
+    }};
+}
+
+/// Declares pinned primitives as `static`s, initialized on first use.
+///
+/// `pinned_static! { static NAME: Type = init; }` declares a `static` of the
+/// primitive created by `init`, such as [`Mutex::uninit`], which must be a
+/// constant expression. `NAME` dereferences to a `Pin<&'static Type>`, and
+/// the first time it does, the primitive is initialized with its `init`
+/// method, exactly once even if several threads race for it. So the
+/// primitive can be used right away, without `unsafe` code and without a
+/// place to initialize it.
+///
+/// This works with any type with an `init(self: Pin<&Self>)` method, such as
+/// [`Mutex`], [`RwLock`] and [`Condvar`]. As they are initialized by the
+/// macro, they must be created uninitialized.
+///
+/// Several `static`s can be declared at once, each of them with attributes
+/// and a visibility.
+///
+/// # Examples
+///
+/// ```
+/// use pinned_sync::{pinned_static, Condvar, Mutex};
+/// use std::thread;
+///
+/// pinned_static! {
+///     /// Whether the worker is done.
+///     static DONE: Mutex<bool> = Mutex::uninit(false);
+///     static DONE_CHANGED: Condvar = Condvar::uninit();
+/// }
+///
+/// let worker = thread::spawn(|| {
+///     *DONE.lock().unwrap() = true;
+///     DONE_CHANGED.notify_all();
+/// });
+///
+/// let done = DONE.lock().unwrap();
+/// let done = DONE_CHANGED.wait_while(done, |done| !*done).unwrap();
+/// assert!(*done);
+/// # drop(done);
+/// # worker.join().unwrap();
+/// ```
+///
+/// [`Mutex`]: crate::Mutex
+/// [`Mutex::uninit`]: crate::Mutex::uninit
+/// [`RwLock`]: crate::RwLock
+/// [`Condvar`]: crate::Condvar
+#[macro_export]
+macro_rules! pinned_static {
+    ($($(#[$attr:meta])* $vis:vis static $name:ident: $ty:ty = $init:expr;)*) => {
+        $(
+            $(#[$attr])*
+            #[allow(non_camel_case_types)]
+            $vis struct $name {
+                __private: (),
+            }
+
+            #[doc(hidden)]
+            $vis static $name: $name = $name { __private: () };
+
+            impl ::std::ops::Deref for $name {
+                type Target = ::std::pin::Pin<&'static $ty>;
+
+                fn deref(&self) -> &Self::Target {
+                    static VALUE: $ty = $init;
+                    static PINNED: ::std::pin::Pin<&'static $ty> =
+                        ::std::pin::Pin::static_ref(&VALUE);
+                    static INIT: ::std::sync::Once = ::std::sync::Once::new();
+                    INIT.call_once(|| PINNED.init());
+                    &PINNED
+                }
+            }
+        )*
+    };
+}
+
+// Expands to the given items only on the backends whose primitives are
+// complete as soon as they are constructed, so that they can be created
+// initialized in a `const` context. This is every backend but the generic
+// pthread one, whose mutexes must be initialized at runtime to be of the
+// normal kind, and ESP-IDF, whose semaphores are allocated.
+macro_rules! cfg_static_new {
+    ($($item:item)*) => {
+        $(
+            #[cfg(any(
+                feature = "parking-lot-core",
+                feature = "thread-park",
+                feature = "spin",
+                not(unix),
+                target_os = "linux",
+                target_os = "android",
+                target_os = "freebsd",
+                target_os = "fuchsia",
+                target_os = "netbsd",
+                target_os = "redox",
+                target_os = "illumos",
+                target_os = "solaris",
+                target_os = "emscripten",
+            ))]
+            $item
+        )*
+    };
+}
+
+// Expands to the first group of items when lock guards are `Send`, and to the
+// second one otherwise. That takes the `send_guard` feature and a backend
+// whose locks may be released by any thread: the `parking_lot_core`, thread
+// park and spin backends, and the ones built on futexes. The pthread, Windows
+// SRW and ESP-IDF locks must be released by the thread which acquired them,
+// so their guards are never `Send`.
+macro_rules! cfg_send_guard {
+    (if { $($yes:item)* } else { $($no:item)* }) => {
+        cfg_if::cfg_if! {
+            if #[cfg(all(
+                feature = "send_guard",
+                any(
+                    feature = "parking-lot-core",
+                    feature = "thread-park",
+                    feature = "spin",
+                    target_os = "linux",
+                    target_os = "android",
+                    target_os = "freebsd",
+                    target_os = "fuchsia",
+                    target_os = "netbsd",
+                    target_os = "redox",
+                    target_os = "emscripten",
+                    all(target_arch = "wasm32", target_feature = "atomics"),
+                    not(any(unix, windows)),
+                ),
+            ))] {
+                $($yes)*
+            } else {
+                $($no)*
+            }
+        }
+    };
+    ($($item:item)*) => {
+        cfg_send_guard! {
+            if { $($item)* } else {}
+        }
+    };
+}
+
+// Expands to the given items only on the Linux futex backend, the only one
+// whose waits can be interrupted by a signal. The others restart their waits
+// when a signal is handled, or block in ways which signals do not end.
+macro_rules! cfg_interruptible {
+    ($($item:item)*) => {
+        $(
+            #[cfg(all(
+                any(target_os = "linux", target_os = "android"),
+                not(any(
+                    feature = "parking-lot-core",
+                    feature = "thread-park",
+                    feature = "spin",
+                )),
+            ))]
+            $item
+        )*
+    };
+}
@@ -0,0 +1,216 @@
+use crate::sys::{condvar, mutex};
+use std::cell::UnsafeCell;
+use std::fmt;
+use std::marker::PhantomPinned;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering::*};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// A manual-reset event.
+///
+/// An event is either set or not. [`set`] sets it, waking up every thread
+/// waiting for it, and it stays set until [`reset`] is called, so that
+/// [`wait`] returns right away in the meantime. This is the event of Windows
+/// of the same name.
+///
+/// Every thread waiting when the event is set is woken up, even if the event
+/// is reset again before it gets to run.
+///
+/// See [`Event`] for an event which is reset by the wait it wakes up.
+///
+/// [`set`]: Self::set
+/// [`reset`]: Self::reset
+/// [`wait`]: Self::wait
+/// [`Event`]: crate::Event
+///
+/// # Examples
+///
+/// ```
+/// use pinned_sync::ManualResetEvent;
+/// use std::thread;
+///
+/// let ready = ManualResetEvent::arc();
+///
+/// let workers: Vec<_> = (0..4)
+///     .map(|_| {
+///         let ready = ready.clone();
+///         thread::spawn(move || ready.as_ref().wait())
+///     })
+///     .collect();
+///
+/// // Release every worker at once.
+/// ready.as_ref().set();
+/// for worker in workers {
+///     worker.join().unwrap();
+/// }
+/// ```
+pub struct ManualResetEvent {
+    set: AtomicBool,
+    // The number of threads which may be blocked on `cvar`. Setting the event
+    // only takes the lock if it is not zero.
+    waiters: AtomicUsize,
+    // Incremented under `lock` when the event is set with waiters, so that
+    // they are released even if it is reset before they run.
+    generation: UnsafeCell<usize>,
+    lock: mutex::Mutex,
+    cvar: condvar::Condvar,
+    _p: PhantomPinned,
+}
+
+unsafe impl Send for ManualResetEvent {}
+unsafe impl Sync for ManualResetEvent {}
+
+impl ManualResetEvent {
+    /// Create a new, uninitialized event, which is not set.
+    ///
+    /// This is *NOT* equivalent to `MaybeUninit::uninit().assume_init()`, which will cause
+    /// undefined behaviour if used to create a new event.
+    #[inline]
+    pub const fn uninit() -> Self {
+        Self {
+            set: AtomicBool::new(false),
+            waiters: AtomicUsize::new(0),
+            generation: UnsafeCell::new(0),
+            lock: mutex::Mutex::uninit(),
+            cvar: condvar::Condvar::uninit(),
+            _p: PhantomPinned,
+        }
+    }
+
+    /// Create a new, initialized event, which is not set.
+    ///
+    /// The resulting event is wrapped and ready for use.
+    #[inline]
+    pub fn boxed() -> Pin<Box<Self>> {
+        let this = Box::pin(Self::uninit());
+        this.as_ref().init();
+        this
+    }
+
+    /// Create a new, initialized event, which is not set.
+    ///
+    /// The resulting event is wrapped and ready for use.
+    #[inline]
+    pub fn arc() -> Pin<Arc<Self>> {
+        let this = Arc::pin(Self::uninit());
+        this.as_ref().init();
+        this
+    }
+
+    /// Initialize an event, making it ready for use.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the event was already initialized.
+    #[inline]
+    pub fn init(self: Pin<&Self>) {
+        self.lock().init();
+        self.cvar().init();
+    }
+
+    /// Returns whether the event is set.
+    #[inline]
+    pub fn is_set(&self) -> bool {
+        self.set.load(Acquire)
+    }
+
+    /// Sets the event, waking up every thread waiting for it.
+    ///
+    /// If the event is already set, this does nothing.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the event is not initialized.
+    pub fn set(self: Pin<&Self>) {
+        if self.set.swap(true, SeqCst) {
+            return;
+        }
+        // Pairs with the increment in `wait_slow`: either the waiter sees the
+        // event set, or we see the waiter.
+        if self.waiters.load(SeqCst) != 0 {
+            let _lock = self.lock().lock();
+            // Safety: the generation is only accessed with the lock held.
+            unsafe { *self.generation.get() = (*self.generation.get()).wrapping_add(1) };
+            self.cvar().notify_all();
+        }
+    }
+
+    /// Resets the event, so that threads which wait for it block until it is
+    /// set again.
+    #[inline]
+    pub fn reset(&self) {
+        self.set.store(false, Relaxed);
+    }
+
+    /// Blocks the current thread until the event is set.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the event is not initialized.
+    #[inline]
+    pub fn wait(self: Pin<&Self>) {
+        if !self.is_set() {
+            self.wait_slow(None);
+        }
+    }
+
+    /// Blocks the current thread until the event is set, or until the timeout
+    /// expires.
+    ///
+    /// Returns `false` if the timeout expired.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the event is not initialized.
+    #[inline]
+    pub fn wait_timeout(self: Pin<&Self>, dur: Duration) -> bool {
+        self.is_set() || self.wait_slow(Some(dur))
+    }
+
+    #[cold]
+    fn wait_slow(self: Pin<&Self>, timeout: Option<Duration>) -> bool {
+        // A timeout which can not be represented is as good as no timeout.
+        let deadline = timeout.and_then(|dur| Instant::now().checked_add(dur));
+        let mut lock = self.lock().lock();
+        self.waiters.fetch_add(1, SeqCst);
+        // Safety: the generation is only accessed with the lock held.
+        let generation = unsafe { *self.generation.get() };
+        let set = loop {
+            if self.set.load(SeqCst) || unsafe { *self.generation.get() } != generation {
+                break true;
+            }
+            // Safety: the condvar is only ever used with `self.lock`.
+            lock = match deadline {
+                None => unsafe { self.cvar().wait(lock) },
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        break false;
+                    }
+                    unsafe { self.cvar().get_ref().wait_timeout(lock, deadline - now).1 }
+                }
+            };
+        };
+        self.waiters.fetch_sub(1, Relaxed);
+        set
+    }
+
+    #[inline]
+    fn lock(self: Pin<&Self>) -> Pin<&mutex::Mutex> {
+        unsafe { self.map_unchecked(|this| &this.lock) }
+    }
+
+    #[inline]
+    fn cvar(self: Pin<&Self>) -> Pin<&condvar::Condvar> {
+        unsafe { self.map_unchecked(|this| &this.cvar) }
+    }
+}
+
+impl fmt::Debug for ManualResetEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ManualResetEvent")
+            .field("set", &self.is_set())
+            .finish_non_exhaustive()
+    }
+}
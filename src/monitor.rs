@@ -0,0 +1,229 @@
+use crate::{Condvar, LockResult, Mutex, MutexGuard, TryLockResult, WaitTimeoutResult};
+use std::fmt;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A [`Mutex`] and the [`Condvar`] which is used with it, in a single pinned
+/// value.
+///
+/// Waiting for a condition on some shared data takes a mutex and a condition
+/// variable which are always used together. Keeping them in the same pinned
+/// allocation usually means a tuple and a projection for each of them. A
+/// `Monitor` does that, and pairs the condition variable with its mutex for
+/// good, so that it can not be used with another one.
+///
+/// # Examples
+///
+/// ```
+/// use pinned_sync::Monitor;
+/// use std::thread;
+///
+/// let started = Monitor::arc(false);
+/// let started2 = started.clone();
+///
+/// thread::spawn(move || {
+///     *started2.as_ref().lock().unwrap() = true;
+///     started2.as_ref().notify_one();
+/// });
+///
+/// let guard = started.as_ref().lock().unwrap();
+/// let guard = started.as_ref().wait_while(guard, |started| !*started).unwrap();
+/// assert!(*guard);
+/// ```
+pub struct Monitor<T> {
+    mutex: Mutex<T>,
+    condvar: Condvar,
+}
+
+impl<T> Monitor<T> {
+    /// Create a new, uninitialized monitor.
+    ///
+    /// This is *NOT* equivalent to `MaybeUninit::uninit().assume_init()`, which will cause
+    /// undefined behaviour if used to create a new monitor.
+    #[inline]
+    pub const fn uninit(value: T) -> Self {
+        Self::from_parts(Mutex::uninit(value), Condvar::uninit())
+    }
+
+    /// Create a new, uninitialized monitor from an uninitialized mutex and
+    /// condition variable, such as ones configured with their builder
+    /// methods.
+    #[inline]
+    pub const fn from_parts(mutex: Mutex<T>, condvar: Condvar) -> Self {
+        Self { mutex, condvar }
+    }
+
+    /// Create a new, initialized monitor.
+    ///
+    /// The resulting monitor is wrapped and ready for use.
+    #[inline]
+    pub fn boxed(value: T) -> Pin<Box<Self>> {
+        let this = Box::pin(Self::uninit(value));
+        this.as_ref().init();
+        this
+    }
+
+    /// Create a new, initialized monitor.
+    ///
+    /// The resulting monitor is wrapped and ready for use.
+    #[inline]
+    pub fn arc(value: T) -> Pin<Arc<Self>> {
+        let this = Arc::pin(Self::uninit(value));
+        this.as_ref().init();
+        this
+    }
+
+    /// Initialize a monitor, making it ready for use.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the monitor was already initialized.
+    #[inline]
+    pub fn init(self: Pin<&Self>) {
+        self.mutex().init();
+        self.condvar().init();
+    }
+
+    /// Acquires the mutex of the monitor, blocking the current thread until it
+    /// is able to do so.
+    ///
+    /// See [`Mutex::lock`].
+    #[inline]
+    pub fn lock(self: Pin<&Self>) -> LockResult<MutexGuard<'_, T>> {
+        self.mutex().lock()
+    }
+
+    /// Attempts to acquire the mutex of the monitor.
+    ///
+    /// See [`Mutex::try_lock`].
+    #[inline]
+    pub fn try_lock(self: Pin<&Self>) -> TryLockResult<MutexGuard<'_, T>> {
+        self.mutex().try_lock()
+    }
+
+    /// Blocks the current thread until the monitor is notified, releasing the
+    /// mutex in the meantime.
+    ///
+    /// See [`Condvar::wait`].
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `guard` belongs to another mutex.
+    #[inline]
+    pub fn wait<'a>(
+        self: Pin<&'a Self>,
+        guard: MutexGuard<'a, T>,
+    ) -> LockResult<MutexGuard<'a, T>> {
+        self.check(&guard);
+        self.condvar().wait(guard)
+    }
+
+    /// Blocks the current thread until the monitor is notified and the
+    /// condition is false, releasing the mutex in the meantime.
+    ///
+    /// See [`Condvar::wait_while`].
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `guard` belongs to another mutex.
+    #[inline]
+    pub fn wait_while<'a, F>(
+        self: Pin<&'a Self>,
+        guard: MutexGuard<'a, T>,
+        condition: F,
+    ) -> LockResult<MutexGuard<'a, T>>
+    where
+        F: FnMut(&mut T) -> bool,
+    {
+        self.check(&guard);
+        self.condvar().wait_while(guard, condition)
+    }
+
+    /// Blocks the current thread until the monitor is notified or the timeout
+    /// expires, releasing the mutex in the meantime.
+    ///
+    /// See [`Condvar::wait_timeout`].
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `guard` belongs to another mutex.
+    #[inline]
+    pub fn wait_timeout<'a>(
+        self: Pin<&'a Self>,
+        guard: MutexGuard<'a, T>,
+        dur: Duration,
+    ) -> LockResult<(MutexGuard<'a, T>, WaitTimeoutResult)> {
+        self.check(&guard);
+        self.condvar().wait_timeout(guard, dur)
+    }
+
+    /// Blocks the current thread until the monitor is notified and the
+    /// condition is false, or the timeout expires, releasing the mutex in the
+    /// meantime.
+    ///
+    /// See [`Condvar::wait_timeout_while`].
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `guard` belongs to another mutex.
+    #[inline]
+    pub fn wait_timeout_while<'a, F>(
+        self: Pin<&'a Self>,
+        guard: MutexGuard<'a, T>,
+        dur: Duration,
+        condition: F,
+    ) -> LockResult<(MutexGuard<'a, T>, WaitTimeoutResult)>
+    where
+        F: FnMut(&mut T) -> bool,
+    {
+        self.check(&guard);
+        self.condvar().wait_timeout_while(guard, dur, condition)
+    }
+
+    /// Wakes up one thread blocked waiting on this monitor.
+    ///
+    /// See [`Condvar::notify_one`].
+    #[inline]
+    pub fn notify_one(self: Pin<&Self>) {
+        self.condvar().notify_one()
+    }
+
+    /// Wakes up all threads blocked waiting on this monitor.
+    ///
+    /// See [`Condvar::notify_all`].
+    #[inline]
+    pub fn notify_all(self: Pin<&Self>) {
+        self.condvar().notify_all()
+    }
+
+    /// Returns the mutex of the monitor.
+    #[inline]
+    pub fn mutex(self: Pin<&Self>) -> Pin<&Mutex<T>> {
+        // Safety: The mutex is structurally pinned.
+        unsafe { self.map_unchecked(|this| &this.mutex) }
+    }
+
+    /// Returns the condition variable of the monitor.
+    ///
+    /// It must only be used with the mutex of the monitor.
+    #[inline]
+    pub fn condvar(self: Pin<&Self>) -> Pin<&Condvar> {
+        // Safety: The condition variable is structurally pinned.
+        unsafe { self.map_unchecked(|this| &this.condvar) }
+    }
+
+    #[inline]
+    fn check(self: Pin<&Self>, guard: &MutexGuard<'_, T>) {
+        assert!(
+            MutexGuard::mutex(guard).id() == self.mutex().id(),
+            "attempted to wait on a monitor with a guard of another mutex"
+        );
+    }
+}
+
+impl<T> fmt::Debug for Monitor<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Monitor").finish_non_exhaustive()
+    }
+}
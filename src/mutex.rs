@@ -1,16 +1,20 @@
-use crate::sys::mutex as sys;
-use crate::sys_common::poison;
+use crate::raw::{self, RawMutex};
+use crate::sys_common::marker::GuardMarker;
+use crate::sys_common::{bias, elision, poison, take, trace};
+use crate::condvar::PendingNotify;
+use crate::{Condvar, LockId, LockResult, PoisonError, TryLockError, TryLockResult, Unlockable};
+use std::alloc::{self, Layout};
 use std::cell::UnsafeCell;
-use std::marker::PhantomPinned;
+use std::fmt;
+use std::marker::{PhantomData, PhantomPinned};
 use std::mem;
 use std::ops::{Deref, DerefMut};
+use std::panic::{RefUnwindSafe, UnwindSafe};
 use std::pin::Pin;
 use std::ptr;
 use std::sync::Arc;
-use std::sync::LockResult;
-use std::sync::PoisonError;
-use std::sync::TryLockError;
-use std::sync::TryLockResult;
+use std::thread;
+use std::time::{Duration, Instant};
 
 /// A mutual exclusion primitive useful for protecting shared data
 ///
@@ -39,22 +43,35 @@ use std::sync::TryLockResult;
 /// the guard that would have otherwise been returned on a successful lock. This
 /// allows access to the data, despite the lock being poisoned.
 ///
+/// # Backends
+///
+/// The lock itself is provided by the backend `B`, which defaults to the
+/// [`raw::Mutex`] of the platform. Mutexes with another backend are created
+/// with [`with_backend`], and otherwise behave the same.
+///
 /// [`new`]: Self::new
 /// [`lock`]: Self::lock
 /// [`try_lock`]: Self::try_lock
 /// [`unwrap()`]: Result::unwrap
 /// [`PoisonError`]: super::PoisonError
 /// [`into_inner`]: super::PoisonError::into_inner
-pub struct Mutex<T: ?Sized> {
-    inner: sys::Mutex,
+/// [`with_backend`]: Self::with_backend
+pub struct Mutex<T: ?Sized, B: RawMutex = raw::Mutex> {
+    inner: B,
     poison: poison::Flag,
+    held: elision::Held,
+    bias: bias::Bias,
     _p: PhantomPinned,
     data: UnsafeCell<T>,
 }
 
-unsafe impl<T: ?Sized + Send> Send for Mutex<T> {}
+unsafe impl<T: ?Sized + Send, B: RawMutex> Send for Mutex<T, B> {}
+
+unsafe impl<T: ?Sized + Send + Sync, B: RawMutex> Sync for Mutex<T, B> {}
 
-unsafe impl<T: ?Sized + Send + Sync> Sync for Mutex<T> {}
+impl<T: ?Sized, B: RawMutex> UnwindSafe for Mutex<T, B> {}
+
+impl<T: ?Sized, B: RawMutex> RefUnwindSafe for Mutex<T, B> {}
 
 impl<T> Mutex<T> {
     /// Create a new, uninitialized mutex.
@@ -63,11 +80,42 @@ impl<T> Mutex<T> {
     /// undefined behaviour if used to create a new mutex.
     #[inline]
     pub const fn uninit(value: T) -> Self {
-        Self {
-            inner: sys::Mutex::uninit(),
-            _p: PhantomPinned,
-            poison: poison::Flag::new(),
-            data: UnsafeCell::new(value),
+        Self::with_backend(value)
+    }
+
+    cfg_static_new! {
+        /// Create a new, initialized mutex, in a `const` context.
+        ///
+        /// Unlike one created with [`uninit`](Self::uninit), the mutex is ready
+        /// for use as soon as it is pinned, and must not be initialized with
+        /// [`init`](Self::init). This makes it possible to declare a mutex as a
+        /// `static` without initializing it at runtime.
+        ///
+        /// This is only available on the backends whose primitives are complete
+        /// when constructed, which are all of them except for the generic
+        /// pthread backend and ESP-IDF.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// use pinned_sync::Mutex;
+        /// use std::pin::Pin;
+        ///
+        /// static COUNTER: Mutex<i32> = Mutex::new(0);
+        ///
+        /// *Pin::static_ref(&COUNTER).lock().unwrap() += 1;
+        /// assert_eq!(*Pin::static_ref(&COUNTER).lock().unwrap(), 1);
+        /// ```
+        #[inline]
+        pub const fn new(value: T) -> Self {
+            Self {
+                inner: raw::Mutex::new(),
+                _p: PhantomPinned,
+                poison: poison::Flag::new(),
+                held: elision::Held::new(),
+                bias: bias::Bias::new(),
+                data: UnsafeCell::new(value),
+            }
         }
     }
 
@@ -92,7 +140,162 @@ impl<T> Mutex<T> {
     }
 }
 
-impl<T: ?Sized> Mutex<T> {
+impl<T, B: RawMutex> Mutex<T, B> {
+    /// Create a new, uninitialized mutex using the backend `B`.
+    ///
+    /// This is *NOT* equivalent to `MaybeUninit::uninit().assume_init()`, which will cause
+    /// undefined behaviour if used to create a new mutex.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pinned_sync::{raw, Mutex};
+    ///
+    /// let mutex = Box::pin(Mutex::<_, raw::Mutex>::with_backend(0));
+    /// mutex.as_ref().init();
+    /// *mutex.as_ref().lock().unwrap() += 1;
+    /// ```
+    #[inline]
+    pub const fn with_backend(value: T) -> Self {
+        Self {
+            inner: B::UNINIT,
+            _p: PhantomPinned,
+            poison: poison::Flag::new(),
+            held: elision::Held::new(),
+            bias: bias::Bias::new(),
+            data: UnsafeCell::new(value),
+        }
+    }
+
+    /// Enables or disables poisoning for this mutex.
+    ///
+    /// Poisoning is enabled by default. When it is disabled, a panic while the
+    /// mutex is held does not poison it, and locking always returns the guard.
+    /// This skips the bookkeeping needed for poisoning, which may be useful
+    /// for hot locks in code bases that treat a panic while a lock is held as
+    /// fatal anyway.
+    ///
+    /// This must be called before the mutex is pinned.
+    #[inline]
+    pub const fn poisoning(mut self, enabled: bool) -> Self {
+        self.poison = if enabled {
+            poison::Flag::new()
+        } else {
+            poison::Flag::disabled()
+        };
+        self
+    }
+
+    /// Enables or disables biased locking for this mutex.
+    ///
+    /// A biased mutex belongs to the first thread which locks it, which then
+    /// locks and unlocks it without atomic read-modify-write operations. The
+    /// first time another thread locks the mutex, the bias is revoked, which is
+    /// expensive, and from then on the mutex behaves like a normal one. Until
+    /// then, [`try_lock`] fails on the other threads, as an attempt does not
+    /// revoke the bias. The owner keeps the bias when it waits on a condvar.
+    ///
+    /// This is useful for mutexes which are shared between threads but are, in
+    /// practice, only ever used by a single thread.
+    ///
+    /// Biased locking is disabled by default. It is not supported where the
+    /// `send_guard` feature makes guards `Send`, as a guard of the owner must
+    /// be dropped by the owner: this then does nothing.
+    ///
+    /// This must be called before the mutex is pinned.
+    ///
+    /// [`try_lock`]: Self::try_lock
+    #[inline]
+    pub fn biased(mut self, enabled: bool) -> Self {
+        self.bias = if enabled {
+            bias::Bias::enabled()
+        } else {
+            bias::Bias::new()
+        };
+        self
+    }
+
+    /// Enables or disables direct handoff for this mutex.
+    ///
+    /// With handoff, unlocking a mutex which other threads are waiting for
+    /// hands it directly to the thread which has waited the longest, and
+    /// yields to the scheduler so that it can run. Without it, the woken thread
+    /// has to race for the mutex, which the unlocking thread often wins again
+    /// when it locks the mutex in a loop, starving the others and causing
+    /// convoys. Handoff makes the mutex fair, at the cost of throughput.
+    ///
+    /// Handoff is disabled by default. It is only supported by the
+    /// `parking-lot-core` and `thread-park` backends, the latter of which is
+    /// also the one of the platforms without a dedicated backend, and is
+    /// ignored by the others.
+    ///
+    /// This must be called before the mutex is pinned.
+    #[inline]
+    pub fn handoff(mut self, enabled: bool) -> Self {
+        self.inner = self.inner.handoff(enabled);
+        self
+    }
+}
+
+impl<T> Mutex<[T]> {
+    /// Create a new, initialized mutex protecting a slice of the values
+    /// yielded by the iterator.
+    ///
+    /// Unlike [`boxed`], the length of the slice does not need to be known at
+    /// compile time.
+    ///
+    /// The resulting mutex is wrapped and ready for use.
+    ///
+    /// [`boxed`]: Self::boxed
+    pub fn boxed_slice<I: IntoIterator<Item = T>>(iter: I) -> Pin<Box<Self>> {
+        let this = Box::into_pin(Self::uninit_slice(iter.into_iter().collect()));
+        this.as_ref().init();
+        this
+    }
+
+    /// Create a new, initialized mutex protecting a slice of the values
+    /// yielded by the iterator.
+    ///
+    /// Unlike [`arc`], the length of the slice does not need to be known at
+    /// compile time.
+    ///
+    /// The resulting mutex is wrapped and ready for use.
+    ///
+    /// [`arc`]: Self::arc
+    pub fn arc_slice<I: IntoIterator<Item = T>>(iter: I) -> Pin<Arc<Self>> {
+        // The mutex is moved into the `Arc`, which is fine as it is not
+        // initialized yet.
+        let this: Arc<Self> = Arc::from(Self::uninit_slice(iter.into_iter().collect()));
+        // Safety: The `Arc` is never unpinned.
+        let this = unsafe { Pin::new_unchecked(this) };
+        this.as_ref().init();
+        this
+    }
+
+    fn uninit_slice(mut values: Vec<T>) -> Box<Self> {
+        let len = values.len();
+        // `Mutex<[T; 0]>` can be unsized into `Mutex<[T]>`, so it has the same
+        // layout, except for the length of the slice.
+        let header = Layout::new::<Mutex<[T; 0]>>();
+        let offset = mem::offset_of!(Mutex<[T; 0]>, data);
+        let layout = Layout::array::<T>(len)
+            .and_then(|data| Layout::from_size_align(offset + data.size(), header.align()))
+            .expect("capacity overflow")
+            .pad_to_align();
+        unsafe {
+            let p = alloc::alloc(layout);
+            if p.is_null() {
+                alloc::handle_alloc_error(layout);
+            }
+            ptr::write(p as *mut Mutex<[T; 0]>, Mutex::uninit([]));
+            ptr::copy_nonoverlapping(values.as_ptr(), p.add(offset) as *mut T, len);
+            values.set_len(0);
+            Box::from_raw(ptr::slice_from_raw_parts_mut(p as *mut T, len) as *mut Self)
+        }
+    }
+}
+
+impl<T: ?Sized, B: RawMutex> Mutex<T, B> {
     /// Initialize a mutex, making it ready for use.
     ///
     /// # Panics
@@ -126,12 +329,26 @@ impl<T: ?Sized> Mutex<T> {
     ///
     /// This function may panic if the mutex is not initialized.
     #[inline]
-    pub fn lock(self: Pin<&Self>) -> LockResult<MutexGuard<T>> {
-        let guard = self.inner().lock();
+    pub fn lock(self: Pin<&Self>) -> LockResult<MutexGuard<'_, T, B>> {
+        let wait = trace::Wait::start();
+        let guard = if self.bias.enter() {
+            Acquired::Biased
+        } else if elision::elide(|| !self.held.get() && !self.bias.active()) {
+            Acquired::Elided
+        } else {
+            self.bias.revoke(true);
+            let guard = self.inner().lock();
+            self.held.acquire();
+            Acquired::Real(guard)
+        };
+        let trace = wait.acquired(self.id(), "Mutex");
         poison::map_result(self.poison.borrow(), |poison| MutexGuard {
             guard,
             mutex: self,
             poison,
+            _trace: trace,
+            notify: PendingNotify::new(),
+            _marker: PhantomData,
         })
     }
 
@@ -153,17 +370,191 @@ impl<T: ?Sized> Mutex<T> {
     ///
     /// This function may panic if the mutex is not initialized.
     #[inline]
-    pub fn try_lock(self: Pin<&Self>) -> TryLockResult<MutexGuard<T>> {
-        let guard = self.inner().try_lock().ok_or(TryLockError::WouldBlock)?;
+    pub fn try_lock(self: Pin<&Self>) -> TryLockResult<MutexGuard<'_, T, B>> {
+        let guard = if self.bias.enter() {
+            Acquired::Biased
+        } else {
+            // Revoking the bias is too expensive for an attempt, so the
+            // attempt fails while the mutex is biased towards another thread.
+            if !self.bias.revoked() {
+                return Err(TryLockError::WouldBlock);
+            }
+            let guard = self.inner().try_lock().ok_or(TryLockError::WouldBlock)?;
+            self.held.acquire();
+            Acquired::Real(guard)
+        };
+        let trace = trace::Hold::start(self.id(), "Mutex");
         Ok(poison::map_result(self.poison.borrow(), |poison| {
             MutexGuard {
                 guard,
                 mutex: self,
                 poison,
+                _trace: trace,
+                notify: PendingNotify::new(),
+                _marker: PhantomData,
             }
         })?)
     }
 
+    /// Attempts to acquire this lock, blocking the current thread until it is
+    /// able to do so or the timeout expires.
+    ///
+    /// If the lock could not be acquired in time, then [`Err`] is returned.
+    /// Otherwise, an RAII guard is returned. The lock will be unlocked when the
+    /// guard is dropped.
+    ///
+    /// # Errors
+    ///
+    /// If the timeout expired, [`TryLockError::WouldBlock`] is returned. If
+    /// another user of this mutex panicked while holding the mutex, then this
+    /// call will return an error if the mutex would otherwise be acquired.
+    ///
+    /// # Panics
+    ///
+    /// This function might panic when called if the lock is already held by the
+    /// current thread.
+    ///
+    /// This function may panic if the mutex is not initialized.
+    #[inline]
+    pub fn try_lock_for(self: Pin<&Self>, dur: Duration) -> TryLockResult<MutexGuard<'_, T, B>> {
+        match Instant::now().checked_add(dur) {
+            Some(deadline) => self.try_lock_until(deadline),
+            // A timeout which can not be represented is as good as no timeout.
+            None => Ok(self.lock()?),
+        }
+    }
+
+    /// Attempts to acquire this lock, blocking the current thread until it is
+    /// able to do so or the deadline is reached.
+    ///
+    /// If the lock could not be acquired in time, then [`Err`] is returned.
+    /// Otherwise, an RAII guard is returned. The lock will be unlocked when the
+    /// guard is dropped.
+    ///
+    /// The platform lock is waited on with a timeout where it can be, such as
+    /// with `pthread_mutex_timedlock` or a futex. Elsewhere, such as on Windows
+    /// and macOS, the lock is polled, and may be noticed to be released a
+    /// little late.
+    ///
+    /// # Errors
+    ///
+    /// If the deadline was reached, [`TryLockError::WouldBlock`] is returned.
+    /// If another user of this mutex panicked while holding the mutex, then
+    /// this call will return an error if the mutex would otherwise be
+    /// acquired.
+    ///
+    /// # Panics
+    ///
+    /// This function might panic when called if the lock is already held by the
+    /// current thread.
+    ///
+    /// This function may panic if the mutex is not initialized.
+    pub fn try_lock_until(
+        self: Pin<&Self>,
+        deadline: Instant,
+    ) -> TryLockResult<MutexGuard<'_, T, B>> {
+        let wait = trace::Wait::start();
+        let guard = if self.bias.enter() {
+            Acquired::Biased
+        } else {
+            while !self.bias.revoke(false) {
+                if Instant::now() >= deadline {
+                    return Err(TryLockError::WouldBlock);
+                }
+                thread::yield_now();
+            }
+            let guard = self
+                .inner()
+                .try_lock_until(deadline)
+                .ok_or(TryLockError::WouldBlock)?;
+            self.held.acquire();
+            Acquired::Real(guard)
+        };
+        let trace = wait.acquired(self.id(), "Mutex");
+        Ok(poison::map_result(self.poison.borrow(), |poison| {
+            MutexGuard {
+                guard,
+                mutex: self,
+                poison,
+                _trace: trace,
+                notify: PendingNotify::new(),
+                _marker: PhantomData,
+            }
+        })?)
+    }
+
+    /// Acquires this mutex without a guard, blocking the current thread until
+    /// it is able to do so.
+    ///
+    /// The mutex stays locked until [`unlock_raw`] is called, which makes it
+    /// possible to build other guard types on top of the mutex, or to hand the
+    /// lock over to foreign code which unlocks it later. The data can be
+    /// accessed through [`data_ptr`] in the meantime.
+    ///
+    /// Locking this way neither checks for nor causes poisoning.
+    ///
+    /// # Safety
+    ///
+    /// While the mutex is locked this way, it must only be unlocked with
+    /// [`unlock_raw`].
+    ///
+    /// # Panics
+    ///
+    /// This function might panic when called if the lock is already held by
+    /// the current thread.
+    ///
+    /// [`unlock_raw`]: Self::unlock_raw
+    /// [`data_ptr`]: Self::data_ptr
+    #[inline]
+    pub unsafe fn lock_raw(self: Pin<&Self>) {
+        mem::forget(self.lock_real());
+    }
+
+    /// Unlocks this mutex, which was locked with [`lock_raw`].
+    ///
+    /// # Safety
+    ///
+    /// The mutex must be locked with [`lock_raw`], and not unlocked since.
+    /// Unless the `send_guard` feature makes guards `Send` on this backend,
+    /// this must be called by the thread which locked it, as some platforms
+    /// require.
+    ///
+    /// [`lock_raw`]: Self::lock_raw
+    #[inline]
+    pub unsafe fn unlock_raw(self: Pin<&Self>) {
+        self.bias.set_real(false);
+        self.held.release();
+        self.inner().unlock();
+    }
+
+    /// Forcibly unlocks this mutex, whose guard was leaked.
+    ///
+    /// This makes a mutex usable again after its guard was lost without being
+    /// dropped, such as with [`mem::forget`] or when unwinding was stopped at
+    /// an FFI boundary. The mutex is not poisoned.
+    ///
+    /// # Safety
+    ///
+    /// The mutex must be locked by a [`MutexGuard`] which was leaked, and
+    /// which is never used again. Unless the `send_guard` feature makes
+    /// guards `Send` on this backend, this must be called by the thread which
+    /// locked it, as some platforms require.
+    pub unsafe fn force_unlock(self: Pin<&Self>) {
+        if self.bias.active() {
+            // The owner of the bias entered without the real lock.
+            self.bias.exit();
+        } else {
+            self.unlock_raw();
+        }
+    }
+
+    /// Returns the identifier of this mutex, which stays the same for as long
+    /// as it is alive.
+    #[inline]
+    pub fn id(self: Pin<&Self>) -> LockId {
+        LockId::of(self.get_ref())
+    }
+
     /// Determines whether the mutex is poisoned.
     ///
     /// If another thread is active, the mutex can still become poisoned at any
@@ -174,6 +565,56 @@ impl<T: ?Sized> Mutex<T> {
         self.poison.get()
     }
 
+    /// Clears the poisoned state from a mutex.
+    ///
+    /// If the mutex is poisoned, it will remain poisoned until this function
+    /// is called. This allows recovering from a poisoned state and marking
+    /// that it has recovered. For example, if the value is overwritten by a
+    /// known-good value, then the mutex can be marked as un-poisoned. Or
+    /// possibly, the value could be inspected to determine if it is in a
+    /// consistent state, and if so the poison is removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pinned_sync::Mutex;
+    /// use std::thread;
+    ///
+    /// let mutex = Mutex::arc(0);
+    /// let c_mutex = mutex.clone();
+    ///
+    /// let _ = thread::spawn(move || {
+    ///     let _lock = c_mutex.as_ref().lock().unwrap();
+    ///     panic!(); // the mutex gets poisoned
+    /// })
+    /// .join();
+    ///
+    /// assert!(mutex.as_ref().is_poisoned());
+    /// let x = mutex.as_ref().lock().unwrap_or_else(|mut e| {
+    ///     **e.get_mut() = 1;
+    ///     mutex.as_ref().clear_poison();
+    ///     e.into_inner()
+    /// });
+    /// assert!(!mutex.as_ref().is_poisoned());
+    /// assert_eq!(*x, 1);
+    /// ```
+    #[inline]
+    pub fn clear_poison(self: Pin<&Self>) {
+        self.poison.clear();
+    }
+
+    /// Returns a raw pointer to the underlying data, without locking the
+    /// mutex.
+    ///
+    /// As the mutex is pinned, the pointer stays valid for as long as the
+    /// mutex is alive. It is up to the caller to make sure that reads and
+    /// writes through it are synchronized, such as by only using it while
+    /// holding the lock.
+    #[inline]
+    pub fn data_ptr(self: Pin<&Self>) -> *mut T {
+        self.data.get()
+    }
+
     /// Consumes this mutex, returning the underlying data.
     ///
     /// # Errors
@@ -188,6 +629,66 @@ impl<T: ?Sized> Mutex<T> {
         poison::map_result(poison.borrow(), |_| data.into_inner())
     }
 
+    /// Consumes a boxed mutex, returning the underlying data.
+    ///
+    /// This is the same as [`into_inner`], for a `Mutex` which was pinned in a
+    /// box, such as one created with [`boxed`]. The mutex itself is dropped in
+    /// place.
+    ///
+    /// # Errors
+    ///
+    /// If another user of this mutex panicked while holding the mutex, then
+    /// this call will return an error instead.
+    ///
+    /// [`into_inner`]: Self::into_inner
+    /// [`boxed`]: Self::boxed
+    pub fn into_inner_boxed(this: Pin<Box<Self>>) -> LockResult<T>
+    where
+        T: Sized,
+    {
+        unsafe { take::take_box(this, |this| this.take()) }
+    }
+
+    /// Consumes a shared mutex if this is its only reference, returning the
+    /// underlying data.
+    ///
+    /// This is the same as [`into_inner`], for a `Mutex` which was pinned in an
+    /// [`Arc`], such as one created with [`arc`]. If there are other strong or
+    /// weak references to the mutex, it is returned back in [`Err`]. The
+    /// mutex itself is dropped in place.
+    ///
+    /// # Errors
+    ///
+    /// If another user of this mutex panicked while holding the mutex, then
+    /// the inner result is an error.
+    ///
+    /// [`into_inner`]: Self::into_inner
+    /// [`arc`]: Self::arc
+    pub fn try_into_inner_arc(this: Pin<Arc<Self>>) -> Result<LockResult<T>, Pin<Arc<Self>>>
+    where
+        T: Sized,
+    {
+        unsafe { take::take_arc(this, |this| this.take()) }
+    }
+
+    // Moves the data out and drops the rest of the mutex in place.
+    unsafe fn take(&mut self) -> LockResult<T>
+    where
+        T: Sized,
+    {
+        let Self {
+            inner,
+            poison,
+            held: _,
+            bias: _,
+            _p: _,
+            data,
+        } = self;
+        let data = ptr::read(data.get());
+        ptr::drop_in_place(inner);
+        poison::map_result(poison.borrow(), |_| data)
+    }
+
     /// Returns a mutable reference to the underlying data.
     ///
     /// Since this call borrows the `Mutex` mutably, no actual locking needs to
@@ -202,42 +703,411 @@ impl<T: ?Sized> Mutex<T> {
         poison::map_result(self.poison.borrow(), |_| data)
     }
 
+    /// Returns a mutable reference to the underlying data of a pinned mutex.
+    ///
+    /// This is the same as [`get_mut`], for a `Mutex` which is already pinned.
+    /// The data is not structurally pinned, so it can be accessed mutably.
+    ///
+    /// # Errors
+    ///
+    /// If another user of this mutex panicked while holding the mutex, then
+    /// this call will return an error instead.
+    ///
+    /// [`get_mut`]: Self::get_mut
+    pub fn get_pin_mut(self: Pin<&mut Self>) -> LockResult<&mut T> {
+        // Safety: The mutex itself is not moved, only the data is borrowed.
+        unsafe { self.get_unchecked_mut() }.get_mut()
+    }
+
     #[inline]
-    fn inner(self: Pin<&Self>) -> Pin<&sys::Mutex> {
+    fn inner(self: Pin<&Self>) -> Pin<&B> {
         unsafe { self.map_unchecked(|this| &this.inner) }
     }
+
+    // Takes the real lock, which the owner of the bias does without revoking
+    // it, as every other thread revokes it before taking the real lock.
+    #[inline]
+    fn lock_real(self: Pin<&Self>) -> B::Guard<'_> {
+        let owned = self.bias.owned();
+        if !owned {
+            self.bias.revoke(true);
+        }
+        let guard = self.inner().lock();
+        self.held.acquire();
+        if owned {
+            self.bias.set_real(true);
+        }
+        guard
+    }
+}
+
+impl<T: ?Sized> Mutex<T> {
+    cfg_interruptible! {
+        /// Acquires a mutex, blocking the current thread until it is able to do
+        /// so or a signal is handled by the thread.
+        ///
+        /// This is the same as [`lock`], except that the wait is given up if a
+        /// signal handler installed without `SA_RESTART` runs while the thread
+        /// is blocked, so that a signal can break a thread out of the wait,
+        /// such as to shut down. A handler which only sets a flag is enough.
+        ///
+        /// This is only available on the Linux futex backend.
+        ///
+        /// # Errors
+        ///
+        /// If the wait was interrupted by a signal, [`LockError::Interrupted`]
+        /// is returned, and the mutex is not locked. If another user of this
+        /// mutex panicked while holding the mutex, then this call will return
+        /// an error once the mutex is acquired.
+        ///
+        /// # Panics
+        ///
+        /// This function might panic when called if the lock is already held by
+        /// the current thread.
+        ///
+        /// [`lock`]: Self::lock
+        /// [`LockError::Interrupted`]: crate::LockError::Interrupted
+        pub fn lock_interruptible(
+            self: Pin<&Self>,
+        ) -> Result<MutexGuard<'_, T>, crate::LockError<MutexGuard<'_, T>>> {
+            let wait = trace::Wait::start();
+            let guard = if self.bias.enter() {
+                Acquired::Biased
+            } else {
+                self.bias.revoke(true);
+                let guard = self
+                    .inner()
+                    .lock_interruptible()
+                    .ok_or(crate::LockError::Interrupted)?;
+                self.held.acquire();
+                Acquired::Real(guard)
+            };
+            let trace = wait.acquired(self.id(), "Mutex");
+            Ok(poison::map_result(self.poison.borrow(), |poison| {
+                MutexGuard {
+                    guard,
+                    mutex: self,
+                    poison,
+                    _trace: trace,
+                    notify: PendingNotify::new(),
+                    _marker: PhantomData,
+                }
+            })?)
+        }
+    }
+
+    /// Restores the mutex to an unlocked state in the child process of a
+    /// `fork`.
+    ///
+    /// Only the thread which called `fork` exists in the child process, so a
+    /// mutex which was held by another thread at that time is never unlocked.
+    /// This makes it usable again. The data may have been left half-updated
+    /// by that thread, so the mutex is poisoned if it was held.
+    ///
+    /// See [`reinit_on_fork`] to do this automatically after every `fork`.
+    ///
+    /// # Safety
+    ///
+    /// This must only be called in the child process of a `fork`, before any
+    /// thread is spawned. The current thread must not hold a guard of the
+    /// mutex.
+    ///
+    /// [`reinit_on_fork`]: crate::reinit_on_fork
+    #[cfg(unix)]
+    pub unsafe fn reinit_after_fork(self: Pin<&Self>) {
+        let held = self.bias.active() || self.inner().try_lock().map(mem::forget).is_none();
+        self.bias.reset_after_fork();
+        self.held.release();
+        self.inner().reinit_after_fork();
+        if held {
+            self.poison.poison();
+        }
+    }
 }
 
-pub struct MutexGuard<'a, T: ?Sized> {
-    // This is suboptimal but necessary for `fallback` as `sync::Mutex` does not provide raw
-    // unlocking.
-    guard: sys::MutexGuard<'a>,
-    mutex: Pin<&'a Mutex<T>>,
+impl<T: Clone, B: RawMutex> Mutex<T, B> {
+    /// Acquires a mutex like [`lock`], and takes a snapshot of the data which
+    /// is restored if the thread panics while holding the guard.
+    ///
+    /// This is an alternative to poisoning for data which is cheap to clone:
+    /// instead of leaving the data half-updated and the mutex poisoned, a
+    /// panic rolls the data back to how it was when the lock was acquired,
+    /// and the mutex is not poisoned. This also applies when poisoning is
+    /// disabled.
+    ///
+    /// # Errors
+    ///
+    /// If another user of this mutex panicked while holding the mutex, then
+    /// this call will return an error once the mutex is acquired.
+    ///
+    /// # Panics
+    ///
+    /// This function might panic when called if the lock is already held by
+    /// the current thread.
+    ///
+    /// This function may panic if the mutex is not initialized.
+    ///
+    /// [`lock`]: Self::lock
+    pub fn lock_transactional(self: Pin<&Self>) -> LockResult<MutexTransactionalGuard<'_, T, B>> {
+        poison::map_result(self.lock(), |guard| MutexTransactionalGuard {
+            snapshot: Some((*guard).clone()),
+            panicking: thread::panicking(),
+            guard,
+        })
+    }
+}
+
+impl<T: ?Sized + 'static, B: RawMutex + 'static> Mutex<T, B> {
+    /// Acquires a mutex like [`lock`], returning a guard which owns a
+    /// reference to the mutex instead of borrowing it.
+    ///
+    /// The guard has no lifetime, so it can be stored in a struct, or, where
+    /// the `send_guard` feature makes guards `Send`, sent to another thread.
+    /// The mutex stays alive at least until the guard is dropped.
+    ///
+    /// # Errors
+    ///
+    /// If another user of this mutex panicked while holding the mutex, then
+    /// this call will return an error once the mutex is acquired.
+    ///
+    /// # Panics
+    ///
+    /// This function might panic when called if the lock is already held by
+    /// the current thread.
+    ///
+    /// This function may panic if the mutex is not initialized.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pinned_sync::{ArcMutexGuard, Mutex};
+    ///
+    /// struct Session {
+    ///     log: ArcMutexGuard<Vec<String>>,
+    /// }
+    ///
+    /// let log = Mutex::arc(Vec::new());
+    /// let mut session = Session {
+    ///     log: log.clone().lock_arc().unwrap(),
+    /// };
+    /// session.log.push("started".to_string());
+    /// drop(session);
+    ///
+    /// assert_eq!(*log.as_ref().lock().unwrap(), ["started"]);
+    /// ```
+    ///
+    /// [`lock`]: Self::lock
+    pub fn lock_arc(self: Pin<Arc<Self>>) -> LockResult<ArcMutexGuard<T, B>> {
+        // Safety: The guard is dropped before the `Arc`, which keeps the
+        // pinned mutex alive until then.
+        let mutex: Pin<&'static Self> = unsafe { Pin::new_unchecked(&*(&*self as *const Self)) };
+        poison::map_result(mutex.lock(), |guard| ArcMutexGuard {
+            guard,
+            mutex: self,
+        })
+    }
+}
+
+impl<T: ?Sized, B: RawMutex> fmt::Pointer for Mutex<T, B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Pointer::fmt(&LockId::of(self), f)
+    }
+}
+
+pub struct MutexGuard<'a, T: ?Sized, B: RawMutex = raw::Mutex> {
+    // Dropped first, so that the hold ends before the lock is released.
+    _trace: trace::Hold,
+    // The guard of the backend, as not every backend provides raw unlocking.
+    guard: Acquired<'a, B>,
+    mutex: Pin<&'a Mutex<T, B>>,
     poison: poison::Guard,
+    // Dropped after `guard`, so that the notifications are sent once the lock
+    // is released.
+    notify: PendingNotify<'a>,
+    _marker: PhantomData<GuardMarker>,
 }
 
-unsafe impl<T: ?Sized + Sync> Sync for MutexGuard<'_, T> {}
+unsafe impl<T: ?Sized + Sync, B: RawMutex> Sync for MutexGuard<'_, T, B> {}
 
-impl<'a, T: ?Sized> MutexGuard<'a, T> {
+impl<T: ?Sized, B: RawMutex> UnwindSafe for MutexGuard<'_, T, B> {}
+
+impl<T: ?Sized, B: RawMutex> RefUnwindSafe for MutexGuard<'_, T, B> {}
+
+/// How a [`MutexGuard`] acquired the lock.
+enum Acquired<'a, B: RawMutex + 'a> {
+    /// The lock was acquired for real.
+    Real(B::Guard<'a>),
+    /// The lock was elided, and the critical section is a hardware transaction.
+    Elided,
+    /// The lock was entered by the owner of the bias.
+    Biased,
+}
+
+impl<'a, T: ?Sized, B: RawMutex> MutexGuard<'a, T, B> {
     #[inline]
-    pub(crate) fn map(self, f: impl FnOnce(sys::MutexGuard<'a>) -> sys::MutexGuard<'a>) -> LockResult<Self> {
+    pub(crate) fn map(self, f: impl FnOnce(B::Guard<'a>) -> B::Guard<'a>) -> LockResult<Self> {
         let (guard, mutex, poison) = unsafe {
             let guard = ptr::read(&self.guard);
             let mutex = ptr::read(&self.mutex);
             let poison = ptr::read(&self.poison);
+            // The lock is released while `f` runs, which ends the hold at the
+            // end of this block.
+            let _trace = ptr::read(&self._trace);
+            // The notifications can not wait for the guard to be dropped, so
+            // they are sent before the lock is released.
+            let _notify = ptr::read(&self.notify);
             mem::forget(self);
             (guard, mutex, poison)
         };
 
+        // Waiting requires the real lock.
+        let guard = match guard {
+            Acquired::Real(guard) => guard,
+            Acquired::Elided => elision::abort(),
+            Acquired::Biased => {
+                // Nobody else can hold the real lock until the bias is revoked,
+                // which waits for the owner to leave the critical section.
+                let guard = mutex.inner().lock();
+                mutex.held.acquire();
+                mutex.bias.set_real(true);
+                mutex.bias.exit();
+                guard
+            }
+        };
         let guard = f(guard);
 
         Self {
-            guard,
+            guard: Acquired::Real(guard),
             mutex,
             poison,
+            _trace: trace::Hold::start(mutex.id(), "Mutex"),
+            notify: PendingNotify::new(),
+            _marker: PhantomData,
         }.repoison()
     }
 
+    /// Unlocks the mutex while `f` runs, and locks it again.
+    pub(crate) fn unlocked(self, f: impl FnOnce()) -> LockResult<Self> {
+        let mutex = self.mutex;
+        self.map(|guard| {
+            mutex.held.release();
+            drop(guard);
+            f();
+            // The owner of the bias stays marked as holding the real lock,
+            // which excludes the other threads without revoking the bias.
+            let guard = mutex.inner().lock();
+            mutex.held.acquire();
+            guard
+        })
+    }
+
+    /// Temporarily unlocks the mutex so that the threads waiting for it can
+    /// take it first, and locks it again.
+    ///
+    /// A thread which holds the mutex for a long time, such as through a loop
+    /// over many items, can call this now and then to avoid starving the
+    /// others, without having to restructure the loop around dropping and
+    /// locking the guard. On the `parking-lot-core` and `thread-park`
+    /// backends, the mutex is handed to the thread which has waited the
+    /// longest, even if [`Mutex::handoff`] is disabled, and the mutex is not
+    /// unlocked at all if nobody is waiting. Pending notifications, such as
+    /// from [`notify_on_unlock`], are sent.
+    ///
+    /// This is an associated function that needs to be used as
+    /// `MutexGuard::bump(guard)`, as a method would shadow a method of `T`
+    /// with the same name.
+    ///
+    /// # Errors
+    ///
+    /// If another user of this mutex panicked while holding it in the
+    /// meantime, then this call will return an error once the mutex is
+    /// acquired again.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pinned_sync::{Mutex, MutexGuard};
+    ///
+    /// let mutex = Mutex::boxed(Vec::new());
+    /// let mut items = mutex.as_ref().lock().unwrap();
+    /// for i in 0..1000 {
+    ///     items.push(i);
+    ///     if i % 100 == 0 {
+    ///         items = MutexGuard::bump(items).unwrap();
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// [`notify_on_unlock`]: Self::notify_on_unlock
+    #[inline]
+    pub fn bump(s: Self) -> LockResult<Self> {
+        // Nobody waits for an elided lock.
+        if let Acquired::Elided = s.guard {
+            return s.repoison();
+        }
+        let mutex = s.mutex;
+        s.map(|guard| {
+            mutex.held.release();
+            let guard = mutex.inner().bump(guard);
+            mutex.held.acquire();
+            guard
+        })
+    }
+
+    /// Wakes up one blocked thread on the condvar once this guard is dropped
+    /// and the mutex is unlocked.
+    ///
+    /// Calling [`Condvar::notify_one`] while holding the lock wakes up a
+    /// thread which immediately blocks again on the mutex, until the current
+    /// thread releases it. Deferring the notification until the mutex is
+    /// unlocked avoids this without having to restructure the code around
+    /// the guard.
+    ///
+    /// Only one condvar is deferred at a time: a pending notification of
+    /// another condvar is sent right away. If the guard is passed to
+    /// [`Condvar::wait`], pending notifications are sent before waiting.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pinned_sync::{Condvar, Mutex};
+    /// use std::thread;
+    ///
+    /// let mutex = Mutex::arc(false);
+    /// let condvar = Condvar::arc();
+    /// let (mutex2, condvar2) = (mutex.clone(), condvar.clone());
+    ///
+    /// thread::spawn(move || {
+    ///     let mut started = mutex2.as_ref().lock().unwrap();
+    ///     *started = true;
+    ///     started.notify_on_unlock(condvar2.as_ref());
+    /// });
+    ///
+    /// let started = mutex.as_ref().lock().unwrap();
+    /// let _started = condvar.as_ref().wait_while(started, |started| !*started).unwrap();
+    /// ```
+    #[inline]
+    pub fn notify_on_unlock(&mut self, condvar: Pin<&'a Condvar>) {
+        self.notify.add(condvar, false);
+    }
+
+    /// Wakes up all blocked threads on the condvar once this guard is dropped
+    /// and the mutex is unlocked.
+    ///
+    /// See [`notify_on_unlock`].
+    ///
+    /// [`notify_on_unlock`]: Self::notify_on_unlock
+    #[inline]
+    pub fn notify_all_on_unlock(&mut self, condvar: Pin<&'a Condvar>) {
+        self.notify.add(condvar, true);
+    }
+
+    // Not a method, so that it does not shadow the methods of `T`.
+    #[inline]
+    pub(crate) fn mutex(this: &Self) -> Pin<&'a Mutex<T, B>> {
+        this.mutex
+    }
+
     #[inline]
     fn repoison(self) -> LockResult<Self> {
         if self.mutex.is_poisoned() {
@@ -248,7 +1118,16 @@ impl<'a, T: ?Sized> MutexGuard<'a, T> {
     }
 }
 
-impl<T: ?Sized> Deref for MutexGuard<'_, T> {
+impl<'a, T: ?Sized, B: RawMutex> Unlockable for MutexGuard<'a, T, B> {
+    type Relocked = LockResult<Self>;
+
+    #[inline]
+    fn with_unlocked(self, f: impl FnOnce()) -> LockResult<Self> {
+        self.unlocked(f)
+    }
+}
+
+impl<T: ?Sized, B: RawMutex> Deref for MutexGuard<'_, T, B> {
     type Target = T;
 
     #[inline]
@@ -257,16 +1136,109 @@ impl<T: ?Sized> Deref for MutexGuard<'_, T> {
     }
 }
 
-impl<T: ?Sized> DerefMut for MutexGuard<'_, T> {
+impl<T: ?Sized, B: RawMutex> DerefMut for MutexGuard<'_, T, B> {
     #[inline]
     fn deref_mut(&mut self) -> &mut T {
         unsafe { &mut *self.mutex.data.get() }
     }
 }
 
-impl<T: ?Sized> Drop for MutexGuard<'_, T> {
+impl<T: ?Sized, B: RawMutex> Drop for MutexGuard<'_, T, B> {
     #[inline]
     fn drop(&mut self) {
         self.mutex.poison.done(&self.poison);
+        match self.guard {
+            Acquired::Real(_) => {
+                self.mutex.bias.set_real(false);
+                self.mutex.held.release();
+            }
+            Acquired::Elided => elision::end(),
+            Acquired::Biased => self.mutex.bias.exit(),
+        }
+    }
+}
+
+/// An RAII guard of a [`Mutex`] which rolls the data back if the thread
+/// panics while holding it.
+///
+/// It is returned by [`Mutex::lock_transactional`].
+pub struct MutexTransactionalGuard<'a, T: Clone, B: RawMutex = raw::Mutex> {
+    guard: MutexGuard<'a, T, B>,
+    snapshot: Option<T>,
+    // Whether the thread was already panicking when the lock was acquired.
+    panicking: bool,
+}
+
+impl<T: Clone, B: RawMutex> MutexTransactionalGuard<'_, T, B> {
+    /// Takes a new snapshot of the data, keeping the changes made so far if
+    /// the thread panics later.
+    pub fn commit(&mut self) {
+        self.snapshot = Some((*self.guard).clone());
+    }
+}
+
+impl<T: Clone, B: RawMutex> Deref for MutexTransactionalGuard<'_, T, B> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<T: Clone, B: RawMutex> DerefMut for MutexTransactionalGuard<'_, T, B> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.guard
+    }
+}
+
+impl<T: Clone, B: RawMutex> Drop for MutexTransactionalGuard<'_, T, B> {
+    #[inline]
+    fn drop(&mut self) {
+        if !self.panicking && thread::panicking() {
+            if let Some(snapshot) = self.snapshot.take() {
+                *self.guard = snapshot;
+                self.guard.poison.forgive();
+            }
+        }
+    }
+}
+
+/// An RAII guard of a [`Mutex`] which owns a reference to it.
+///
+/// It is returned by [`Mutex::lock_arc`]. Unlike a [`MutexGuard`], it does
+/// not borrow the mutex, so it can be kept for as long as needed.
+pub struct ArcMutexGuard<T: ?Sized + 'static, B: RawMutex + 'static = raw::Mutex> {
+    // Dropped first, so that the mutex is unlocked while it is still alive.
+    guard: MutexGuard<'static, T, B>,
+    mutex: Pin<Arc<Mutex<T, B>>>,
+}
+
+impl<T: ?Sized, B: RawMutex> ArcMutexGuard<T, B> {
+    /// Returns the mutex locked by this guard.
+    ///
+    /// This is an associated function that needs to be used as
+    /// `ArcMutexGuard::mutex(&guard)`, as a method would shadow a method of
+    /// `T` with the same name.
+    #[inline]
+    pub fn mutex(s: &Self) -> &Pin<Arc<Mutex<T, B>>> {
+        &s.mutex
+    }
+}
+
+impl<T: ?Sized, B: RawMutex> Deref for ArcMutexGuard<T, B> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<T: ?Sized, B: RawMutex> DerefMut for ArcMutexGuard<T, B> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.guard
     }
 }
@@ -0,0 +1,386 @@
+//! Locks without poisoning.
+//!
+//! The [`Mutex`] and [`RwLock`] of this module are the ones of the crate root
+//! with poisoning disabled, as with [`poisoning(false)`]. A panic while one of
+//! them is held does not poison it, so locking returns the guard directly
+//! instead of a [`LockResult`], and attempting to lock returns an [`Option`].
+//! This suits code bases which treat a panic while a lock is held as fatal
+//! anyway, and would otherwise unwrap every lock.
+//!
+//! The guards are the same as those of the crate root, so they can be used
+//! with a [`Condvar`] as usual.
+//!
+//! # Examples
+//!
+//! ```
+//! use pinned_sync::nopoison::Mutex;
+//! use std::thread;
+//!
+//! let counter = Mutex::arc(0);
+//! let counter2 = counter.clone();
+//!
+//! let _ = thread::spawn(move || {
+//!     let _guard = counter2.as_ref().lock();
+//!     panic!("the mutex is not poisoned");
+//! })
+//! .join();
+//!
+//! *counter.as_ref().lock() += 1;
+//! assert_eq!(*counter.as_ref().lock(), 1);
+//! ```
+//!
+//! [`poisoning(false)`]: crate::Mutex::poisoning
+//! [`LockResult`]: crate::LockResult
+//! [`Condvar`]: crate::Condvar
+
+use crate::{
+    LockId, MutexGuard, RwLockReadGuard, RwLockUpgradableReadGuard, RwLockWriteGuard, TryLockError,
+    TryLockResult,
+};
+use std::fmt;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// A mutual exclusion primitive without poisoning.
+///
+/// See the [module documentation](self) and [`crate::Mutex`].
+pub struct Mutex<T: ?Sized> {
+    inner: crate::Mutex<T>,
+}
+
+impl<T> Mutex<T> {
+    /// Create a new, uninitialized mutex.
+    ///
+    /// This is *NOT* equivalent to `MaybeUninit::uninit().assume_init()`, which will cause
+    /// undefined behaviour if used to create a new mutex.
+    #[inline]
+    pub const fn uninit(value: T) -> Self {
+        Self {
+            inner: crate::Mutex::uninit(value).poisoning(false),
+        }
+    }
+
+    cfg_static_new! {
+        /// Create a new, initialized mutex, in a `const` context.
+        ///
+        /// See [`crate::Mutex::new`].
+        #[inline]
+        pub const fn new(value: T) -> Self {
+            Self {
+                inner: crate::Mutex::new(value).poisoning(false),
+            }
+        }
+    }
+
+    /// Create a new, initialized mutex.
+    ///
+    /// The resulting mutex is wrapped and ready for use.
+    #[inline]
+    pub fn boxed(value: T) -> Pin<Box<Self>> {
+        let this = Box::pin(Self::uninit(value));
+        this.as_ref().init();
+        this
+    }
+
+    /// Create a new, initialized mutex.
+    ///
+    /// The resulting mutex is wrapped and ready for use.
+    #[inline]
+    pub fn arc(value: T) -> Pin<Arc<Self>> {
+        let this = Arc::pin(Self::uninit(value));
+        this.as_ref().init();
+        this
+    }
+
+    /// Consumes this mutex, returning the underlying data.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        unpoisoned(self.inner.into_inner())
+    }
+}
+
+impl<T: ?Sized> Mutex<T> {
+    /// Initialize a mutex, making it ready for use.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the mutex was already initialized.
+    #[inline]
+    pub fn init(self: Pin<&Self>) {
+        self.inner().init()
+    }
+
+    /// Acquires the mutex, blocking the current thread until it is able to do
+    /// so.
+    ///
+    /// See [`crate::Mutex::lock`].
+    ///
+    /// # Panics
+    ///
+    /// This function might panic when called if the lock is already held by
+    /// the current thread.
+    ///
+    /// This function may panic if the mutex is not initialized.
+    #[inline]
+    pub fn lock(self: Pin<&Self>) -> MutexGuard<'_, T> {
+        unpoisoned(self.inner().lock())
+    }
+
+    /// Attempts to acquire this lock.
+    ///
+    /// If the lock could not be acquired at this time, then [`None`] is
+    /// returned. This function does not block.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the mutex is not initialized.
+    #[inline]
+    pub fn try_lock(self: Pin<&Self>) -> Option<MutexGuard<'_, T>> {
+        try_unpoisoned(self.inner().try_lock())
+    }
+
+    /// Attempts to acquire this lock, blocking the current thread until it is
+    /// able to do so or the timeout expires.
+    ///
+    /// See [`crate::Mutex::try_lock_for`].
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the mutex is not initialized.
+    #[inline]
+    pub fn try_lock_for(self: Pin<&Self>, dur: Duration) -> Option<MutexGuard<'_, T>> {
+        try_unpoisoned(self.inner().try_lock_for(dur))
+    }
+
+    /// Attempts to acquire this lock, blocking the current thread until it is
+    /// able to do so or the deadline is reached.
+    ///
+    /// See [`crate::Mutex::try_lock_until`].
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the mutex is not initialized.
+    #[inline]
+    pub fn try_lock_until(self: Pin<&Self>, deadline: Instant) -> Option<MutexGuard<'_, T>> {
+        try_unpoisoned(self.inner().try_lock_until(deadline))
+    }
+
+    /// Returns the identifier of this mutex, which stays the same for as long
+    /// as it is alive.
+    #[inline]
+    pub fn id(self: Pin<&Self>) -> LockId {
+        self.inner().id()
+    }
+
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// Since this call borrows the `Mutex` mutably, no actual locking needs to
+    /// take place -- the mutable borrow statically guarantees no locks exist.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut T {
+        unpoisoned(self.inner.get_mut())
+    }
+
+    #[inline]
+    fn inner(self: Pin<&Self>) -> Pin<&crate::Mutex<T>> {
+        unsafe { self.map_unchecked(|this| &this.inner) }
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for Mutex<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Mutex").finish_non_exhaustive()
+    }
+}
+
+/// A reader-writer lock without poisoning.
+///
+/// See the [module documentation](self) and [`crate::RwLock`].
+pub struct RwLock<T: ?Sized> {
+    inner: crate::RwLock<T>,
+}
+
+impl<T> RwLock<T> {
+    /// Create a new, uninitialized read-write lock.
+    ///
+    /// This is *NOT* equivalent to `MaybeUninit::uninit().assume_init()`, which will cause
+    /// undefined behaviour if used to create a new read-write lock.
+    #[inline]
+    pub const fn uninit(value: T) -> Self {
+        Self {
+            inner: crate::RwLock::uninit(value).poisoning(false),
+        }
+    }
+
+    cfg_static_new! {
+        /// Create a new, initialized read-write lock, in a `const` context.
+        ///
+        /// See [`crate::RwLock::new`].
+        #[inline]
+        pub const fn new(value: T) -> Self {
+            Self {
+                inner: crate::RwLock::new(value).poisoning(false),
+            }
+        }
+    }
+
+    /// Create a new, initialized read-write lock.
+    ///
+    /// The resulting read-write lock is wrapped and ready for use.
+    #[inline]
+    pub fn boxed(value: T) -> Pin<Box<Self>> {
+        let this = Box::pin(Self::uninit(value));
+        this.as_ref().init();
+        this
+    }
+
+    /// Create a new, initialized read-write lock.
+    ///
+    /// The resulting read-write lock is wrapped and ready for use.
+    #[inline]
+    pub fn arc(value: T) -> Pin<Arc<Self>> {
+        let this = Arc::pin(Self::uninit(value));
+        this.as_ref().init();
+        this
+    }
+
+    /// Consumes this read-write lock, returning the underlying data.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        unpoisoned(self.inner.into_inner())
+    }
+}
+
+impl<T: ?Sized> RwLock<T> {
+    /// Initialize a read-write lock, making it ready for use.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the read-write lock was already initialized.
+    #[inline]
+    pub fn init(self: Pin<&Self>) {
+        self.inner().init()
+    }
+
+    /// Locks this rwlock with shared read access, blocking the current thread
+    /// until it can be acquired.
+    ///
+    /// See [`crate::RwLock::read`].
+    ///
+    /// # Panics
+    ///
+    /// This function might panic when called if the lock is already held by
+    /// the current thread.
+    ///
+    /// This function may panic if the lock is not initialized.
+    #[inline]
+    pub fn read(self: Pin<&Self>) -> RwLockReadGuard<'_, T> {
+        unpoisoned(self.inner().read())
+    }
+
+    /// Attempts to acquire this rwlock with shared read access.
+    ///
+    /// If the access could not be granted at this time, including when the
+    /// maximum number of readers is reached, then [`None`] is returned. This
+    /// function does not block.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the lock is not initialized.
+    #[inline]
+    pub fn try_read(self: Pin<&Self>) -> Option<RwLockReadGuard<'_, T>> {
+        try_unpoisoned(self.inner().try_read())
+    }
+
+    /// Locks this rwlock with upgradable read access, blocking the current
+    /// thread until it can be acquired.
+    ///
+    /// See [`crate::RwLock::upgradable_read`].
+    ///
+    /// # Panics
+    ///
+    /// This function might panic when called if the lock is already held by
+    /// the current thread.
+    ///
+    /// This function may panic if the lock is not initialized.
+    #[inline]
+    pub fn upgradable_read(self: Pin<&Self>) -> RwLockUpgradableReadGuard<'_, T> {
+        unpoisoned(self.inner().upgradable_read())
+    }
+
+    /// Locks this rwlock with exclusive write access, blocking the current
+    /// thread until it can be acquired.
+    ///
+    /// See [`crate::RwLock::write`].
+    ///
+    /// # Panics
+    ///
+    /// This function might panic when called if the lock is already held by
+    /// the current thread.
+    ///
+    /// This function panics if the lock is [frozen].
+    ///
+    /// This function may panic if the lock is not initialized.
+    ///
+    /// [frozen]: crate::RwLock::freeze
+    #[inline]
+    pub fn write(self: Pin<&Self>) -> RwLockWriteGuard<'_, T> {
+        unpoisoned(self.inner().write())
+    }
+
+    /// Attempts to lock this rwlock with exclusive write access.
+    ///
+    /// If the lock could not be acquired at this time, then [`None`] is
+    /// returned. This function does not block.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the lock is not initialized.
+    #[inline]
+    pub fn try_write(self: Pin<&Self>) -> Option<RwLockWriteGuard<'_, T>> {
+        try_unpoisoned(self.inner().try_write())
+    }
+
+    /// Returns the identifier of this read-write lock, which stays the same
+    /// for as long as it is alive.
+    #[inline]
+    pub fn id(self: Pin<&Self>) -> LockId {
+        self.inner().id()
+    }
+
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// Since this call borrows the `RwLock` mutably, no actual locking needs to
+    /// take place -- the mutable borrow statically guarantees no locks exist.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut T {
+        unpoisoned(self.inner.get_mut())
+    }
+
+    #[inline]
+    fn inner(self: Pin<&Self>) -> Pin<&crate::RwLock<T>> {
+        unsafe { self.map_unchecked(|this| &this.inner) }
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for RwLock<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RwLock").finish_non_exhaustive()
+    }
+}
+
+// Poisoning is disabled, so the error is never returned.
+#[inline]
+fn unpoisoned<T>(result: crate::LockResult<T>) -> T {
+    result.unwrap_or_else(crate::PoisonError::into_inner)
+}
+
+#[inline]
+fn try_unpoisoned<T>(result: TryLockResult<T>) -> Option<T> {
+    match result {
+        Ok(guard) => Some(guard),
+        Err(TryLockError::Poisoned(err)) => Some(err.into_inner()),
+        Err(TryLockError::WouldBlock) | Err(TryLockError::TooManyReaders) => None,
+    }
+}
@@ -0,0 +1,171 @@
+use crate::Mutex;
+use std::borrow::Borrow;
+use std::collections::hash_map::{DefaultHasher, HashMap};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::pin::Pin;
+use std::sync::{Arc, OnceLock};
+
+const SHARDS: usize = 16;
+
+type Shard<K, V> = Mutex<Option<HashMap<K, Box<OnceLock<V>>>>>;
+
+/// A map whose values are initialized once per key.
+///
+/// [`get_or_init`] returns the value of a key, calling the initializer if the
+/// key has no value yet. However many threads race to initialize the same
+/// key, the initializer runs only once: the other threads block until it
+/// returns, and then all of them get the same value. Initializers for
+/// different keys run concurrently.
+///
+/// This is useful for memoizing the results of expensive computations, such
+/// as loading files or compiling patterns, which are shared between threads.
+///
+/// Values are never removed while the map is shared, so references to them
+/// live as long as the map itself.
+///
+/// [`get_or_init`]: Self::get_or_init
+///
+/// # Examples
+///
+/// ```
+/// use pinned_sync::OnceMap;
+/// use std::thread;
+///
+/// let map = OnceMap::arc();
+/// let threads: Vec<_> = (0..4)
+///     .map(|_| {
+///         let map = map.clone();
+///         thread::spawn(move || *map.as_ref().get_or_init(1, || 1 + 1))
+///     })
+///     .collect();
+/// for t in threads {
+///     assert_eq!(t.join().unwrap(), 2);
+/// }
+/// ```
+pub struct OnceMap<K, V> {
+    shards: [Shard<K, V>; SHARDS],
+}
+
+impl<K, V> OnceMap<K, V> {
+    // Only used as the initializer of `shards`, which can not be written as a
+    // repeat expression of a non-`Copy` value otherwise.
+    #[allow(clippy::declare_interior_mutable_const)]
+    const SHARD: Shard<K, V> = Mutex::uninit(None);
+
+    /// Create a new, uninitialized map.
+    ///
+    /// This is *NOT* equivalent to `MaybeUninit::uninit().assume_init()`, which will cause
+    /// undefined behaviour if used to create a new map.
+    #[inline]
+    pub const fn uninit() -> Self {
+        Self {
+            shards: [Self::SHARD; SHARDS],
+        }
+    }
+
+    /// Create a new, initialized map.
+    ///
+    /// The resulting map is wrapped and ready for use.
+    #[inline]
+    pub fn boxed() -> Pin<Box<Self>> {
+        let this = Box::pin(Self::uninit());
+        this.as_ref().init();
+        this
+    }
+
+    /// Create a new, initialized map.
+    ///
+    /// The resulting map is wrapped and ready for use.
+    #[inline]
+    pub fn arc() -> Pin<Arc<Self>> {
+        let this = Arc::pin(Self::uninit());
+        this.as_ref().init();
+        this
+    }
+
+    /// Initialize a map, making it ready for use.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the map was already initialized.
+    pub fn init(self: Pin<&Self>) {
+        for i in 0..SHARDS {
+            self.shard(i).init();
+        }
+    }
+
+    #[inline]
+    fn shard(self: Pin<&Self>, i: usize) -> Pin<&Shard<K, V>> {
+        unsafe { self.map_unchecked(|this| &this.shards[i]) }
+    }
+}
+
+impl<K: Hash + Eq, V> OnceMap<K, V> {
+    /// Returns the value of the key, if it was initialized.
+    ///
+    /// This does not block on an initializer which is running for the key.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the map is not initialized.
+    pub fn get<Q>(self: Pin<&Self>, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let shard = self.shard_of(key);
+        let entry = lock!(shard => map, poison = ignore {
+            let entry: &OnceLock<V> = map.as_ref()?.get(key)?;
+            entry as *const OnceLock<V>
+        });
+        // Safety: Entries are boxed and never removed while the map is
+        // shared, so they live as long as the map.
+        unsafe { &*entry }.get()
+    }
+
+    /// Returns the value of the key, initializing it with `f` if it was not
+    /// initialized.
+    ///
+    /// If another thread is running the initializer for the same key, this
+    /// blocks until it finishes and returns the value it produced, without
+    /// calling `f`.
+    ///
+    /// If `f` panics, the panic is propagated to the caller and the key stays
+    /// uninitialized, so the next caller runs its own initializer.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the map is not initialized.
+    ///
+    /// Calling this from `f` for the same key deadlocks or panics.
+    pub fn get_or_init<F>(self: Pin<&Self>, key: K, f: F) -> &V
+    where
+        F: FnOnce() -> V,
+    {
+        let shard = self.shard_of(&key);
+        let entry = lock!(shard => map, poison = ignore {
+            let entry: &OnceLock<V> = map
+                .get_or_insert_with(HashMap::new)
+                .entry(key)
+                .or_default();
+            entry as *const OnceLock<V>
+        });
+        // Safety: Entries are boxed and never removed while the map is
+        // shared, so they live as long as the map.
+        unsafe { &*entry }.get_or_init(f)
+    }
+
+    #[inline]
+    fn shard_of<Q: Hash + ?Sized>(self: Pin<&Self>, key: &Q) -> Pin<&Shard<K, V>> {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        self.shard(hasher.finish() as usize % SHARDS)
+    }
+}
+
+impl<K, V> fmt::Debug for OnceMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("OnceMap { .. }")
+    }
+}
@@ -0,0 +1,321 @@
+use crate::sys_common::poison;
+use crate::{
+    LockResult, Mutex, MutexGuard, Primitives, RwLock, RwLockReadGuard, RwLockWriteGuard,
+    TryLockResult,
+};
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use std::pin::Pin;
+use std::sync::Arc;
+
+/// A lock with a level in a lock hierarchy.
+///
+/// Deadlocks between locks can be avoided by always acquiring them in the same
+/// order. `Ordered` makes that order explicit: each lock is given a `LEVEL`,
+/// and a thread may only block on a lock whose level is higher than the
+/// levels of all the locks which it already holds.
+///
+/// In debug builds, every thread keeps track of the levels of the `Ordered`
+/// locks which it holds, and blocking on a lock out of order panics, even if
+/// it would not have deadlocked this time. In release builds, there are no
+/// checks and no overhead.
+///
+/// The non-blocking methods, such as [`try_lock`], can not deadlock, so they
+/// are not checked, but the locks they acquire are tracked.
+///
+/// Guards must be dropped by the thread which acquired them.
+///
+/// [`try_lock`]: Self::try_lock
+///
+/// # Examples
+///
+/// ```
+/// use pinned_sync::{Mutex, Ordered};
+///
+/// let accounts = Ordered::<1, _>::boxed(Mutex::uninit(Vec::<u32>::new()));
+/// let log = Ordered::<2, _>::boxed(Mutex::uninit(String::new()));
+///
+/// let accounts = accounts.as_ref().lock().unwrap();
+/// // Level 2 after level 1 is fine; the other way around would panic.
+/// log.as_ref().lock().unwrap().push_str("locked");
+/// drop(accounts);
+/// ```
+pub struct Ordered<const LEVEL: u8, L: ?Sized> {
+    inner: L,
+}
+
+impl<const LEVEL: u8, L> Ordered<LEVEL, L> {
+    /// Create a new, uninitialized ordered lock, wrapping an uninitialized
+    /// lock.
+    ///
+    /// This is *NOT* equivalent to `MaybeUninit::uninit().assume_init()`, which will cause
+    /// undefined behaviour if used to create a new ordered lock.
+    #[inline]
+    pub const fn uninit(lock: L) -> Self {
+        Self { inner: lock }
+    }
+
+    /// Returns the level of the lock.
+    #[inline]
+    pub const fn level(&self) -> u8 {
+        LEVEL
+    }
+
+}
+
+impl<const LEVEL: u8, L: ?Sized> Ordered<LEVEL, L> {
+    #[inline]
+    fn inner(self: Pin<&Self>) -> Pin<&L> {
+        unsafe { self.map_unchecked(|this| &this.inner) }
+    }
+}
+
+impl<const LEVEL: u8, L: Primitives> Ordered<LEVEL, L> {
+    /// Create a new, initialized ordered lock, wrapping an uninitialized lock.
+    ///
+    /// The resulting ordered lock is wrapped and ready for use.
+    #[inline]
+    pub fn boxed(lock: L) -> Pin<Box<Self>> {
+        let this = Box::pin(Self::uninit(lock));
+        this.as_ref().init();
+        this
+    }
+
+    /// Create a new, initialized ordered lock, wrapping an uninitialized lock.
+    ///
+    /// The resulting ordered lock is wrapped and ready for use.
+    #[inline]
+    pub fn arc(lock: L) -> Pin<Arc<Self>> {
+        let this = Arc::pin(Self::uninit(lock));
+        this.as_ref().init();
+        this
+    }
+
+    /// Initialize an ordered lock, making it ready for use.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the lock was already initialized.
+    #[inline]
+    pub fn init(self: Pin<&Self>) {
+        self.inner().init_pinned();
+    }
+}
+
+impl<const LEVEL: u8, L: Primitives> Primitives for Ordered<LEVEL, L> {
+    type Pinned<'a>
+        = Pin<&'a Self>
+    where
+        Self: 'a;
+
+    #[inline]
+    fn init_pinned(self: Pin<&Self>) -> Pin<&Self> {
+        self.init();
+        self
+    }
+}
+
+impl<const LEVEL: u8, T: ?Sized> Ordered<LEVEL, Mutex<T>> {
+    /// Acquires the mutex, blocking the current thread until it is able to do
+    /// so.
+    ///
+    /// See [`Mutex::lock`].
+    ///
+    /// # Panics
+    ///
+    /// In debug builds, this function panics if the current thread holds an
+    /// ordered lock with the same or a higher level.
+    pub fn lock(self: Pin<&Self>) -> LockResult<OrderedGuard<MutexGuard<'_, T>>> {
+        levels::check(LEVEL);
+        poison::map_result(self.inner().lock(), OrderedGuard::new::<LEVEL>)
+    }
+
+    /// Attempts to acquire the mutex.
+    ///
+    /// See [`Mutex::try_lock`].
+    pub fn try_lock(self: Pin<&Self>) -> TryLockResult<OrderedGuard<MutexGuard<'_, T>>> {
+        match self.inner().try_lock() {
+            Ok(guard) => Ok(OrderedGuard::new::<LEVEL>(guard)),
+            Err(err) => Err(err.map(OrderedGuard::new::<LEVEL>)),
+        }
+    }
+
+    /// Determines whether the mutex is poisoned.
+    ///
+    /// See [`Mutex::is_poisoned`].
+    #[inline]
+    pub fn is_poisoned(self: Pin<&Self>) -> bool {
+        self.inner().is_poisoned()
+    }
+}
+
+impl<const LEVEL: u8, T: ?Sized> Ordered<LEVEL, RwLock<T>> {
+    /// Locks the read-write lock with shared read access, blocking the current
+    /// thread until it can be acquired.
+    ///
+    /// See [`RwLock::read`].
+    ///
+    /// # Panics
+    ///
+    /// In debug builds, this function panics if the current thread holds an
+    /// ordered lock with the same or a higher level.
+    pub fn read(self: Pin<&Self>) -> LockResult<OrderedGuard<RwLockReadGuard<'_, T>>> {
+        levels::check(LEVEL);
+        poison::map_result(self.inner().read(), OrderedGuard::new::<LEVEL>)
+    }
+
+    /// Attempts to lock the read-write lock with shared read access.
+    ///
+    /// See [`RwLock::try_read`].
+    pub fn try_read(self: Pin<&Self>) -> TryLockResult<OrderedGuard<RwLockReadGuard<'_, T>>> {
+        match self.inner().try_read() {
+            Ok(guard) => Ok(OrderedGuard::new::<LEVEL>(guard)),
+            Err(err) => Err(err.map(OrderedGuard::new::<LEVEL>)),
+        }
+    }
+
+    /// Locks the read-write lock with exclusive write access, blocking the
+    /// current thread until it can be acquired.
+    ///
+    /// See [`RwLock::write`].
+    ///
+    /// # Panics
+    ///
+    /// In debug builds, this function panics if the current thread holds an
+    /// ordered lock with the same or a higher level.
+    pub fn write(self: Pin<&Self>) -> LockResult<OrderedGuard<RwLockWriteGuard<'_, T>>> {
+        levels::check(LEVEL);
+        poison::map_result(self.inner().write(), OrderedGuard::new::<LEVEL>)
+    }
+
+    /// Attempts to lock the read-write lock with exclusive write access.
+    ///
+    /// See [`RwLock::try_write`].
+    pub fn try_write(self: Pin<&Self>) -> TryLockResult<OrderedGuard<RwLockWriteGuard<'_, T>>> {
+        match self.inner().try_write() {
+            Ok(guard) => Ok(OrderedGuard::new::<LEVEL>(guard)),
+            Err(err) => Err(err.map(OrderedGuard::new::<LEVEL>)),
+        }
+    }
+
+    /// Determines whether the read-write lock is poisoned.
+    ///
+    /// See [`RwLock::is_poisoned`].
+    #[inline]
+    pub fn is_poisoned(self: Pin<&Self>) -> bool {
+        self.inner().is_poisoned()
+    }
+}
+
+impl<const LEVEL: u8, L: ?Sized> fmt::Debug for Ordered<LEVEL, L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Ordered")
+            .field("level", &LEVEL)
+            .finish_non_exhaustive()
+    }
+}
+
+/// An RAII guard of an [`Ordered`] lock, wrapping the guard of the lock.
+///
+/// The data protected by the lock can be accessed through this guard via its
+/// [`Deref`] and [`DerefMut`] implementations.
+pub struct OrderedGuard<G> {
+    guard: G,
+    level: u8,
+    // The level is tracked by the thread which acquired the lock.
+    _not_send: PhantomData<*const ()>,
+}
+
+unsafe impl<G: Sync> Sync for OrderedGuard<G> {}
+
+impl<G> OrderedGuard<G> {
+    #[inline]
+    fn new<const LEVEL: u8>(guard: G) -> Self {
+        levels::push(LEVEL);
+        Self {
+            guard,
+            level: LEVEL,
+            _not_send: PhantomData,
+        }
+    }
+}
+
+impl<G: Deref> Deref for OrderedGuard<G> {
+    type Target = G::Target;
+
+    #[inline]
+    fn deref(&self) -> &G::Target {
+        &self.guard
+    }
+}
+
+impl<G: DerefMut> DerefMut for OrderedGuard<G> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut G::Target {
+        &mut self.guard
+    }
+}
+
+impl<G> Drop for OrderedGuard<G> {
+    #[inline]
+    fn drop(&mut self) {
+        levels::pop(self.level);
+    }
+}
+
+impl<G: fmt::Debug> fmt::Debug for OrderedGuard<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.guard.fmt(f)
+    }
+}
+
+#[cfg(debug_assertions)]
+mod levels {
+    use std::cell::RefCell;
+
+    thread_local! {
+        // The levels of the ordered locks held by the current thread, in the
+        // order they were acquired.
+        static HELD: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+    }
+
+    pub fn check(level: u8) {
+        let max = HELD.with(|held| held.borrow().iter().copied().max());
+        if let Some(max) = max {
+            if level <= max {
+                panic!(
+                    "lock order violation: acquiring a lock of level {} while holding a lock of level {}",
+                    level, max
+                );
+            }
+        }
+    }
+
+    pub fn push(level: u8) {
+        HELD.with(|held| held.borrow_mut().push(level));
+    }
+
+    pub fn pop(level: u8) {
+        // Guards may be dropped in any order. This may run during thread
+        // destruction, after the list is gone, when nothing is checked anymore.
+        let _ = HELD.try_with(|held| {
+            let mut held = held.borrow_mut();
+            if let Some(i) = held.iter().rposition(|&l| l == level) {
+                held.remove(i);
+            }
+        });
+    }
+}
+
+#[cfg(not(debug_assertions))]
+mod levels {
+    #[inline]
+    pub fn check(_level: u8) {}
+
+    #[inline]
+    pub fn push(_level: u8) {}
+
+    #[inline]
+    pub fn pop(_level: u8) {}
+}
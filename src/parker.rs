@@ -0,0 +1,247 @@
+use crate::sys::{condvar, mutex};
+use std::fmt;
+use std::marker::PhantomPinned;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU8, Ordering::*};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const EMPTY: u8 = 0;
+const PARKED: u8 = 1;
+const NOTIFIED: u8 = 2;
+
+/// A thread parker, for building blocking primitives.
+///
+/// A parker holds a token which is either available or not. [`park`] blocks
+/// until the token is available and consumes it, and [`unpark`], through an
+/// [`Unparker`], makes it available, waking up the parked thread if there is
+/// one. Unparking a parker whose token is already available does nothing, so
+/// an unpark which happens before the park is never lost.
+///
+/// This is the same protocol as [`std::thread::park`], but the token belongs
+/// to the parker instead of the thread, so that it is not shared with every
+/// other user of the thread's token, and waking up is never spurious. Only one
+/// thread may be parked at a time; an [`Event`] can be waited on by several.
+///
+/// [`park`]: Self::park
+/// [`unpark`]: Unparker::unpark
+/// [`Event`]: crate::Event
+///
+/// # Examples
+///
+/// ```
+/// use pinned_sync::Parker;
+/// use std::sync::atomic::{AtomicBool, Ordering};
+/// use std::sync::Arc;
+/// use std::thread;
+///
+/// let parker = Parker::arc();
+/// let done = Arc::new(AtomicBool::new(false));
+///
+/// let (parker2, done2) = (parker.clone(), done.clone());
+/// thread::spawn(move || {
+///     done2.store(true, Ordering::Release);
+///     parker2.as_ref().unparker().unpark();
+/// });
+///
+/// while !done.load(Ordering::Acquire) {
+///     parker.as_ref().park();
+/// }
+/// ```
+pub struct Parker {
+    state: AtomicU8,
+    lock: mutex::Mutex,
+    cvar: condvar::Condvar,
+    _p: PhantomPinned,
+}
+
+unsafe impl Send for Parker {}
+unsafe impl Sync for Parker {}
+
+impl Parker {
+    /// Create a new, uninitialized parker, whose token is not available.
+    ///
+    /// This is *NOT* equivalent to `MaybeUninit::uninit().assume_init()`, which will cause
+    /// undefined behaviour if used to create a new parker.
+    #[inline]
+    pub const fn uninit() -> Self {
+        Self {
+            state: AtomicU8::new(EMPTY),
+            lock: mutex::Mutex::uninit(),
+            cvar: condvar::Condvar::uninit(),
+            _p: PhantomPinned,
+        }
+    }
+
+    /// Create a new, initialized parker, whose token is not available.
+    ///
+    /// The resulting parker is wrapped and ready for use.
+    #[inline]
+    pub fn boxed() -> Pin<Box<Self>> {
+        let this = Box::pin(Self::uninit());
+        this.as_ref().init();
+        this
+    }
+
+    /// Create a new, initialized parker, whose token is not available.
+    ///
+    /// The resulting parker is wrapped and ready for use.
+    #[inline]
+    pub fn arc() -> Pin<Arc<Self>> {
+        let this = Arc::pin(Self::uninit());
+        this.as_ref().init();
+        this
+    }
+
+    /// Initialize a parker, making it ready for use.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the parker was already initialized.
+    #[inline]
+    pub fn init(self: Pin<&Self>) {
+        self.lock().init();
+        self.cvar().init();
+    }
+
+    /// Blocks the current thread until the token is available, and consumes
+    /// it.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if another thread is parked on this parker.
+    ///
+    /// This function may panic if the parker is not initialized.
+    #[inline]
+    pub fn park(self: Pin<&Self>) {
+        if !self.try_park() {
+            self.park_slow(None);
+        }
+    }
+
+    /// Blocks the current thread until the token is available, and consumes
+    /// it, or until the timeout expires.
+    ///
+    /// Returns `false` if the timeout expired.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if another thread is parked on this parker.
+    ///
+    /// This function may panic if the parker is not initialized.
+    #[inline]
+    pub fn park_timeout(self: Pin<&Self>, dur: Duration) -> bool {
+        self.try_park() || self.park_slow(Some(dur))
+    }
+
+    /// Returns a handle which unparks this parker.
+    #[inline]
+    pub fn unparker(self: Pin<&Self>) -> Unparker<'_> {
+        Unparker { parker: self }
+    }
+
+    #[inline]
+    fn try_park(self: Pin<&Self>) -> bool {
+        self.state
+            .compare_exchange(NOTIFIED, EMPTY, Acquire, Relaxed)
+            .is_ok()
+    }
+
+    #[cold]
+    fn park_slow(self: Pin<&Self>, timeout: Option<Duration>) -> bool {
+        // A timeout which can not be represented is as good as no timeout.
+        let deadline = timeout.and_then(|dur| Instant::now().checked_add(dur));
+        let mut lock = self.lock().lock();
+        match self.state.compare_exchange(EMPTY, PARKED, SeqCst, SeqCst) {
+            Ok(_) => {}
+            // Unparked since the fast path, consume the token.
+            Err(NOTIFIED) => {
+                self.state.store(EMPTY, SeqCst);
+                return true;
+            }
+            Err(_) => panic!("attempted to park on a parker which another thread is parked on"),
+        }
+        loop {
+            // Safety: the condvar is only ever used with `self.lock`.
+            lock = match deadline {
+                None => unsafe { self.cvar().wait(lock) },
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        // Unpark may have raced with the timeout, in which
+                        // case the token is consumed all the same.
+                        return self.state.swap(EMPTY, SeqCst) == NOTIFIED;
+                    }
+                    unsafe { self.cvar().get_ref().wait_timeout(lock, deadline - now).1 }
+                }
+            };
+            if self
+                .state
+                .compare_exchange(NOTIFIED, EMPTY, SeqCst, Relaxed)
+                .is_ok()
+            {
+                return true;
+            }
+        }
+    }
+
+    fn unpark(self: Pin<&Self>) {
+        match self.state.swap(NOTIFIED, SeqCst) {
+            EMPTY | NOTIFIED => {}
+            _ => {
+                // Taking the lock makes sure the parked thread is blocked on
+                // the condvar already, so that the notification reaches it.
+                drop(self.lock().lock());
+                self.cvar().notify_one();
+            }
+        }
+    }
+
+    #[inline]
+    fn lock(self: Pin<&Self>) -> Pin<&mutex::Mutex> {
+        unsafe { self.map_unchecked(|this| &this.lock) }
+    }
+
+    #[inline]
+    fn cvar(self: Pin<&Self>) -> Pin<&condvar::Condvar> {
+        unsafe { self.map_unchecked(|this| &this.cvar) }
+    }
+}
+
+impl fmt::Debug for Parker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Parker")
+            .field("notified", &(self.state.load(Relaxed) == NOTIFIED))
+            .finish_non_exhaustive()
+    }
+}
+
+/// A handle which unparks a [`Parker`].
+///
+/// This is obtained with [`Parker::unparker`], and can be copied and sent to
+/// other threads freely.
+#[derive(Clone, Copy)]
+pub struct Unparker<'a> {
+    parker: Pin<&'a Parker>,
+}
+
+impl Unparker<'_> {
+    /// Makes the token of the parker available, waking up the thread parked
+    /// on it, if any.
+    ///
+    /// If the token is already available, this does nothing.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the parker is not initialized.
+    #[inline]
+    pub fn unpark(&self) {
+        self.parker.unpark()
+    }
+}
+
+impl fmt::Debug for Unparker<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Unparker").finish_non_exhaustive()
+    }
+}
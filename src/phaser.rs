@@ -0,0 +1,252 @@
+use crate::sys::{condvar, mutex};
+use std::cell::UnsafeCell;
+use std::fmt;
+use std::marker::PhantomPinned;
+use std::panic::{RefUnwindSafe, UnwindSafe};
+use std::pin::Pin;
+use std::sync::Arc;
+
+/// A reusable barrier whose parties can change from one phase to the next.
+///
+/// A phaser synchronizes a number of registered parties in a sequence of
+/// phases, numbered from zero. Each phase completes once every registered
+/// party has arrived, which releases the parties waiting for it and starts
+/// the next one. Unlike with a [`Barrier`], parties can [`register`] and
+/// [`arrive_and_deregister`] at any time, and can [`arrive`] without waiting
+/// for the others, which allows pipelines where the number of stages in
+/// flight changes over time.
+///
+/// [`Barrier`]: crate::Barrier
+/// [`register`]: Self::register
+/// [`arrive_and_deregister`]: Self::arrive_and_deregister
+/// [`arrive`]: Self::arrive
+///
+/// # Examples
+///
+/// ```
+/// use pinned_sync::Phaser;
+/// use std::thread;
+///
+/// let phaser = Phaser::arc(1);
+///
+/// let workers: Vec<_> = (0..4)
+///     .map(|_| {
+///         phaser.as_ref().register();
+///         let phaser = phaser.clone();
+///         thread::spawn(move || {
+///             for phase in 0..3 {
+///                 // Do the work of this phase, then wait for the others.
+///                 assert_eq!(phaser.as_ref().wait(), phase);
+///             }
+///             phaser.as_ref().arrive_and_deregister();
+///         })
+///     })
+///     .collect();
+///
+/// // The main thread takes part in the first phase only.
+/// assert_eq!(phaser.as_ref().wait(), 0);
+/// phaser.as_ref().arrive_and_deregister();
+/// for worker in workers {
+///     worker.join().unwrap();
+/// }
+/// ```
+pub struct Phaser {
+    lock: mutex::Mutex,
+    cvar: condvar::Condvar,
+    state: UnsafeCell<State>,
+    _p: PhantomPinned,
+}
+
+struct State {
+    phase: usize,
+    parties: usize,
+    arrived: usize,
+}
+
+unsafe impl Send for Phaser {}
+unsafe impl Sync for Phaser {}
+
+impl UnwindSafe for Phaser {}
+
+impl RefUnwindSafe for Phaser {}
+
+impl Phaser {
+    /// Create a new, uninitialized phaser with `parties` registered parties,
+    /// at phase zero.
+    ///
+    /// This is *NOT* equivalent to `MaybeUninit::uninit().assume_init()`, which will cause
+    /// undefined behaviour if used to create a new phaser.
+    #[inline]
+    pub const fn uninit(parties: usize) -> Self {
+        Self {
+            lock: mutex::Mutex::uninit(),
+            cvar: condvar::Condvar::uninit(),
+            state: UnsafeCell::new(State {
+                phase: 0,
+                parties,
+                arrived: 0,
+            }),
+            _p: PhantomPinned,
+        }
+    }
+
+    /// Create a new, initialized phaser with `parties` registered parties.
+    ///
+    /// The resulting phaser is wrapped and ready for use.
+    #[inline]
+    pub fn boxed(parties: usize) -> Pin<Box<Self>> {
+        let this = Box::pin(Self::uninit(parties));
+        this.as_ref().init();
+        this
+    }
+
+    /// Create a new, initialized phaser with `parties` registered parties.
+    ///
+    /// The resulting phaser is wrapped and ready for use.
+    #[inline]
+    pub fn arc(parties: usize) -> Pin<Arc<Self>> {
+        let this = Arc::pin(Self::uninit(parties));
+        this.as_ref().init();
+        this
+    }
+
+    /// Initialize a phaser, making it ready for use.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the phaser was already initialized.
+    #[inline]
+    pub fn init(self: Pin<&Self>) {
+        self.lock().init();
+        self.cvar().init();
+    }
+
+    /// Registers a new party, which has to arrive for the current phase to
+    /// complete.
+    ///
+    /// Returns the current phase.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the phaser is not initialized.
+    pub fn register(self: Pin<&Self>) -> usize {
+        let _lock = self.lock().lock();
+        // Safety: the state is only accessed with the lock held.
+        let state = unsafe { &mut *self.state.get() };
+        state.parties = state
+            .parties
+            .checked_add(1)
+            .expect("overflow in the number of parties of a phaser");
+        state.phase
+    }
+
+    /// Arrives at the current phase, without waiting for the other parties.
+    ///
+    /// Returns the phase which was arrived at. If this is the last party to
+    /// arrive, the phase is completed.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if every registered party already arrived.
+    ///
+    /// This function may panic if the phaser is not initialized.
+    pub fn arrive(self: Pin<&Self>) -> usize {
+        let _lock = self.lock().lock();
+        self.arrive_locked(false)
+    }
+
+    /// Arrives at the current phase and deregisters, so that the next phases
+    /// do not wait for this party anymore.
+    ///
+    /// Returns the phase which was arrived at. If this is the last party to
+    /// arrive, the phase is completed.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if every registered party already arrived.
+    ///
+    /// This function may panic if the phaser is not initialized.
+    pub fn arrive_and_deregister(self: Pin<&Self>) -> usize {
+        let _lock = self.lock().lock();
+        self.arrive_locked(true)
+    }
+
+    /// Arrives at the current phase, and blocks the current thread until every
+    /// other registered party has arrived.
+    ///
+    /// Returns the phase which was completed.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if every registered party already arrived.
+    ///
+    /// This function may panic if the phaser is not initialized.
+    pub fn wait(self: Pin<&Self>) -> usize {
+        let mut lock = self.lock().lock();
+        let phase = self.arrive_locked(false);
+        // We need a while loop to guard against spurious wakeups.
+        // https://en.wikipedia.org/wiki/Spurious_wakeup
+        while phase == unsafe { (*self.state.get()).phase } {
+            // Safety: the condvar is only ever used with `self.lock`.
+            lock = unsafe { self.cvar().wait(lock) };
+        }
+        phase
+    }
+
+    /// Returns the current phase.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the phaser is not initialized.
+    pub fn phase(self: Pin<&Self>) -> usize {
+        let _lock = self.lock().lock();
+        unsafe { (*self.state.get()).phase }
+    }
+
+    /// Returns the number of registered parties.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the phaser is not initialized.
+    pub fn parties(self: Pin<&Self>) -> usize {
+        let _lock = self.lock().lock();
+        unsafe { (*self.state.get()).parties }
+    }
+
+    // Must be called with the lock held.
+    fn arrive_locked(self: Pin<&Self>, deregister: bool) -> usize {
+        // Safety: the state is only accessed with the lock held.
+        let state = unsafe { &mut *self.state.get() };
+        let phase = state.phase;
+        if state.arrived == state.parties {
+            panic!("arrived at a phaser whose registered parties all arrived already");
+        }
+        if deregister {
+            state.parties -= 1;
+        } else {
+            state.arrived += 1;
+        }
+        if state.arrived == state.parties {
+            state.arrived = 0;
+            state.phase = phase.wrapping_add(1);
+            self.cvar().notify_all();
+        }
+        phase
+    }
+
+    #[inline]
+    fn lock(self: Pin<&Self>) -> Pin<&mutex::Mutex> {
+        unsafe { self.map_unchecked(|this| &this.lock) }
+    }
+
+    #[inline]
+    fn cvar(self: Pin<&Self>) -> Pin<&condvar::Condvar> {
+        unsafe { self.map_unchecked(|this| &this.cvar) }
+    }
+}
+
+impl fmt::Debug for Phaser {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("Phaser { .. }")
+    }
+}
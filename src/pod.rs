@@ -0,0 +1,54 @@
+use std::mem::{self, MaybeUninit};
+use std::sync::atomic::{AtomicU8, AtomicUsize, Ordering::Relaxed};
+
+/// Plain old data, which can be read while it is being written.
+///
+/// This is required by the optimistic reads of an [`RwLock`], which copy the
+/// data while a writer may be modifying it, and throw the copy away if it was.
+/// The copy is made with atomic loads, so it is not a data race, but it may
+/// mix the bytes of two values. That must not be undefined behaviour, even if
+/// the torn value is never used.
+///
+/// # Safety
+///
+/// Every bit pattern of the size of the type must be a valid value, so that a
+/// torn copy is valid too. In particular, the type must not contain padding,
+/// references, pointers to owned memory, `bool`, `char` or enums.
+///
+/// [`RwLock`]: crate::RwLock
+pub unsafe trait Pod: Copy {}
+
+macro_rules! impl_pod {
+    ($($t:ty)*) => {
+        $(unsafe impl Pod for $t {})*
+    };
+}
+
+impl_pod!(u8 u16 u32 u64 u128 usize i8 i16 i32 i64 i128 isize f32 f64);
+
+unsafe impl<T: Pod, const N: usize> Pod for [T; N] {}
+
+/// Copies the value at `src` with atomic loads, word by word if it is aligned
+/// for it and byte by byte otherwise, without any ordering.
+///
+/// # Safety
+///
+/// `src` must be valid for reads and aligned.
+pub(crate) unsafe fn load<T: Pod>(src: *const T) -> T {
+    let mut copy = MaybeUninit::<T>::uninit();
+    if mem::align_of::<T>() >= mem::align_of::<usize>() {
+        // The size is a multiple of the alignment, so of the size of a word.
+        let src = src as *const AtomicUsize;
+        let dst = copy.as_mut_ptr() as *mut usize;
+        for i in 0..mem::size_of::<T>() / mem::size_of::<usize>() {
+            *dst.add(i) = (*src.add(i)).load(Relaxed);
+        }
+    } else {
+        let src = src as *const AtomicU8;
+        let dst = copy.as_mut_ptr() as *mut u8;
+        for i in 0..mem::size_of::<T>() {
+            *dst.add(i) = (*src.add(i)).load(Relaxed);
+        }
+    }
+    copy.assume_init()
+}
@@ -0,0 +1,423 @@
+use super::{
+    Condvar, FairMutex, FairMutexGuard, Mutex, MutexGuard, ReadError, ReadGuard, RwLock, WriteGuard,
+};
+use crate::sys_common::timed;
+use std::pin::Pin;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// A backend for [`Mutex`](crate::Mutex).
+///
+/// A [`Mutex`](crate::Mutex) is generic over the lock it is built upon, which
+/// defaults to the raw [`Mutex`] of the platform. Implementing this trait
+/// allows mixing mutexes with other backends in the same program, such as a
+/// priority-inheritance lock for a real-time thread, while keeping the same
+/// front end, with poisoning and guards.
+///
+/// # Safety
+///
+/// The lock must provide mutual exclusion: while a guard returned by
+/// [`lock`], [`try_lock`], [`try_lock_until`] or [`bump`] exists, no other guard of the same lock
+/// may be returned, and the lock must be released when the guard is dropped or
+/// when [`unlock`] is called.
+///
+/// [`lock`]: Self::lock
+/// [`try_lock`]: Self::try_lock
+/// [`try_lock_until`]: Self::try_lock_until
+/// [`bump`]: Self::bump
+/// [`unlock`]: Self::unlock
+pub unsafe trait RawMutex: Send + Sync {
+    /// The RAII guard of the lock, which releases it when dropped.
+    type Guard<'a>
+    where
+        Self: 'a;
+
+    /// A new, uninitialized lock.
+    const UNINIT: Self;
+
+    /// Initialize the lock, making it ready for use. This is called once,
+    /// after the lock is pinned.
+    fn init(self: Pin<&Self>);
+
+    /// Acquires the lock, blocking the current thread until it is able to do
+    /// so.
+    fn lock(self: Pin<&Self>) -> Self::Guard<'_>;
+
+    /// Attempts to acquire the lock without blocking.
+    fn try_lock(self: Pin<&Self>) -> Option<Self::Guard<'_>>;
+
+    /// Releases the lock without a guard.
+    ///
+    /// # Safety
+    ///
+    /// The lock must be held, and the guard which acquired it forgotten.
+    unsafe fn unlock(self: Pin<&Self>);
+
+    /// Attempts to acquire the lock, blocking the current thread until it is
+    /// able to do so or the deadline is reached.
+    ///
+    /// By default, this polls [`try_lock`](Self::try_lock), sleeping in
+    /// between. Locks which can block with a timeout should override it.
+    #[inline]
+    fn try_lock_until(self: Pin<&Self>, deadline: Instant) -> Option<Self::Guard<'_>> {
+        timed::poll_until(deadline, || self.try_lock())
+    }
+
+    /// Releases the lock held by `guard`, lets the threads waiting for it take
+    /// it first, and acquires it again.
+    ///
+    /// By default, this unlocks the lock, yields to the scheduler and locks it
+    /// again. Locks which know whether anybody is waiting, or which can hand
+    /// themselves to a waiting thread, should override it.
+    #[inline]
+    fn bump<'a>(self: Pin<&'a Self>, guard: Self::Guard<'a>) -> Self::Guard<'a> {
+        drop(guard);
+        thread::yield_now();
+        self.lock()
+    }
+
+    /// Enables or disables direct handoff, if the lock supports it.
+    ///
+    /// See [`Mutex::handoff`](crate::Mutex::handoff). This does nothing by
+    /// default.
+    #[inline]
+    fn handoff(self, _enabled: bool) -> Self
+    where
+        Self: Sized,
+    {
+        self
+    }
+}
+
+/// A backend for [`RwLock`](crate::RwLock).
+///
+/// Like [`RawMutex`] for a [`Mutex`](crate::Mutex), this is the lock which an
+/// [`RwLock`](crate::RwLock) is built upon, and defaults to the raw
+/// [`RwLock`] of the platform. The front end adds poisoning, writer policies,
+/// upgradable reads and guards on top of it.
+///
+/// # Safety
+///
+/// The lock must provide shared and exclusive access: while a write guard
+/// exists, no other guard of the same lock may be returned, and while a read
+/// guard exists, no write guard may be returned. Cloning a read guard must
+/// acquire shared access once more. Each access must be released when its
+/// guard is dropped, or when [`read_unlock`] or [`write_unlock`] is called.
+///
+/// [`read_unlock`]: Self::read_unlock
+/// [`write_unlock`]: Self::write_unlock
+pub unsafe trait RawRwLock: Send + Sync {
+    /// The RAII guard of shared read access, which releases it when dropped.
+    type ReadGuard<'a>: Clone
+    where
+        Self: 'a;
+
+    /// The RAII guard of exclusive write access, which releases it when
+    /// dropped.
+    type WriteGuard<'a>
+    where
+        Self: 'a;
+
+    /// A new, uninitialized lock.
+    const UNINIT: Self;
+
+    /// Initialize the lock, making it ready for use. This is called once,
+    /// after the lock is pinned.
+    fn init(self: Pin<&Self>);
+
+    /// Acquires shared read access, blocking the current thread until it is
+    /// able to do so.
+    ///
+    /// Returns [`None`] if the maximum number of readers was reached.
+    fn read(self: Pin<&Self>) -> Option<Self::ReadGuard<'_>>;
+
+    /// Attempts to acquire shared read access without blocking.
+    fn try_read(self: Pin<&Self>) -> Result<Self::ReadGuard<'_>, ReadError>;
+
+    /// Acquires exclusive write access, blocking the current thread until it
+    /// is able to do so.
+    fn write(self: Pin<&Self>) -> Self::WriteGuard<'_>;
+
+    /// Attempts to acquire exclusive write access without blocking.
+    fn try_write(self: Pin<&Self>) -> Option<Self::WriteGuard<'_>>;
+
+    /// Releases shared read access without a guard.
+    ///
+    /// # Safety
+    ///
+    /// The lock must be held for reading, and the guard which acquired it
+    /// forgotten.
+    unsafe fn read_unlock(self: Pin<&Self>);
+
+    /// Releases exclusive write access without a guard.
+    ///
+    /// # Safety
+    ///
+    /// The lock must be held for writing, and the guard which acquired it
+    /// forgotten.
+    unsafe fn write_unlock(self: Pin<&Self>);
+
+    /// Attempts to acquire shared read access, blocking the current thread
+    /// until it is able to do so or the deadline is reached.
+    ///
+    /// By default, this polls [`try_read`](Self::try_read), sleeping in
+    /// between, and returns its last error once the deadline is reached.
+    /// Locks which can block with a timeout should override it.
+    #[inline]
+    fn try_read_until(
+        self: Pin<&Self>,
+        deadline: Instant,
+    ) -> Result<Self::ReadGuard<'_>, ReadError> {
+        let mut error = ReadError::WouldBlock;
+        timed::poll_until(deadline, || match self.try_read() {
+            Ok(guard) => Some(guard),
+            Err(e) => {
+                error = e;
+                None
+            }
+        })
+        .ok_or(error)
+    }
+
+    /// Attempts to acquire exclusive write access, blocking the current
+    /// thread until it is able to do so or the deadline is reached.
+    ///
+    /// By default, this polls [`try_write`](Self::try_write), sleeping in
+    /// between. Locks which can block with a timeout should override it.
+    #[inline]
+    fn try_write_until(self: Pin<&Self>, deadline: Instant) -> Option<Self::WriteGuard<'_>> {
+        timed::poll_until(deadline, || self.try_write())
+    }
+}
+
+/// A backend for [`Condvar`](crate::Condvar).
+///
+/// This is the condition variable which a [`Condvar`](crate::Condvar) is
+/// built upon, and defaults to the raw [`Condvar`] of the platform. It waits
+/// with the mutexes of the backend [`Mutex`](Self::Mutex), so a
+/// [`Condvar`](crate::Condvar) with this backend waits with the guards of a
+/// [`Mutex`](crate::Mutex) with that one. The front end adds poisoning, fair
+/// wakeups and the predicate-based waits on top of it.
+///
+/// # Safety
+///
+/// Waiting must release the mutex and block the current thread atomically,
+/// so that a notification sent after the mutex is released wakes the thread
+/// up. The mutex must be held again when a wait returns.
+pub unsafe trait RawCondvar: Send + Sync {
+    /// The backend of the mutexes which the condition variable waits with.
+    type Mutex: RawMutex;
+
+    /// A new, uninitialized condition variable.
+    const UNINIT: Self;
+
+    /// Initialize the condition variable, making it ready for use. This is
+    /// called once, after the condition variable is pinned.
+    fn init(self: Pin<&Self>);
+
+    /// Releases the mutex of `guard` and blocks the current thread until this
+    /// condition variable receives a notification, then locks the mutex
+    /// again. This may also return spuriously.
+    fn wait<'a>(
+        self: Pin<&Self>,
+        guard: <Self::Mutex as RawMutex>::Guard<'a>,
+    ) -> <Self::Mutex as RawMutex>::Guard<'a>
+    where
+        Self::Mutex: 'a;
+
+    /// Like [`wait`](Self::wait), but gives up after `dur`. Along with the
+    /// guard, returns `true` if the wait is known to have timed out.
+    fn wait_timeout<'a>(
+        self: Pin<&Self>,
+        guard: <Self::Mutex as RawMutex>::Guard<'a>,
+        dur: Duration,
+    ) -> (<Self::Mutex as RawMutex>::Guard<'a>, bool)
+    where
+        Self::Mutex: 'a;
+
+    /// Wakes up one blocked thread.
+    fn notify_one(self: Pin<&Self>);
+
+    /// Wakes up all blocked threads.
+    fn notify_all(self: Pin<&Self>);
+
+    /// Wakes up at most `n` blocked threads.
+    ///
+    /// By default, this calls [`notify_one`](Self::notify_one) `n` times.
+    /// Condition variables which can wake up several threads at once should
+    /// override it.
+    #[inline]
+    fn notify_many(self: Pin<&Self>, n: usize) {
+        for _ in 0..n {
+            self.notify_one();
+        }
+    }
+}
+
+unsafe impl RawMutex for Mutex {
+    type Guard<'a> = MutexGuard<'a>;
+
+    const UNINIT: Self = Mutex::uninit();
+
+    #[inline]
+    fn init(self: Pin<&Self>) {
+        Mutex::init(self)
+    }
+
+    #[inline]
+    fn lock(self: Pin<&Self>) -> MutexGuard<'_> {
+        Mutex::lock(self)
+    }
+
+    #[inline]
+    fn try_lock(self: Pin<&Self>) -> Option<MutexGuard<'_>> {
+        Mutex::try_lock(self)
+    }
+
+    #[inline]
+    unsafe fn unlock(self: Pin<&Self>) {
+        Mutex::unlock(self)
+    }
+
+    #[inline]
+    fn try_lock_until(self: Pin<&Self>, deadline: Instant) -> Option<MutexGuard<'_>> {
+        Mutex::try_lock_until(self, deadline)
+    }
+
+    #[inline]
+    fn bump<'a>(self: Pin<&'a Self>, guard: MutexGuard<'a>) -> MutexGuard<'a> {
+        guard.bump()
+    }
+
+    #[inline]
+    fn handoff(self, enabled: bool) -> Self {
+        Mutex {
+            inner: self.inner.handoff(enabled),
+        }
+    }
+}
+
+unsafe impl RawMutex for FairMutex {
+    type Guard<'a> = FairMutexGuard<'a>;
+
+    const UNINIT: Self = FairMutex::uninit();
+
+    #[inline]
+    fn init(self: Pin<&Self>) {
+        FairMutex::init(self)
+    }
+
+    #[inline]
+    fn lock(self: Pin<&Self>) -> FairMutexGuard<'_> {
+        FairMutex::lock(self)
+    }
+
+    #[inline]
+    fn try_lock(self: Pin<&Self>) -> Option<FairMutexGuard<'_>> {
+        FairMutex::try_lock(self)
+    }
+
+    #[inline]
+    unsafe fn unlock(self: Pin<&Self>) {
+        FairMutex::unlock(self)
+    }
+}
+
+unsafe impl RawRwLock for RwLock {
+    type ReadGuard<'a> = ReadGuard<'a>;
+    type WriteGuard<'a> = WriteGuard<'a>;
+
+    const UNINIT: Self = RwLock::uninit();
+
+    #[inline]
+    fn init(self: Pin<&Self>) {
+        RwLock::init(self)
+    }
+
+    #[inline]
+    fn read(self: Pin<&Self>) -> Option<ReadGuard<'_>> {
+        RwLock::read(self)
+    }
+
+    #[inline]
+    fn try_read(self: Pin<&Self>) -> Result<ReadGuard<'_>, ReadError> {
+        RwLock::try_read(self)
+    }
+
+    #[inline]
+    fn write(self: Pin<&Self>) -> WriteGuard<'_> {
+        RwLock::write(self)
+    }
+
+    #[inline]
+    fn try_write(self: Pin<&Self>) -> Option<WriteGuard<'_>> {
+        RwLock::try_write(self)
+    }
+
+    #[inline]
+    unsafe fn read_unlock(self: Pin<&Self>) {
+        RwLock::read_unlock(self)
+    }
+
+    #[inline]
+    unsafe fn write_unlock(self: Pin<&Self>) {
+        RwLock::write_unlock(self)
+    }
+
+    #[inline]
+    fn try_read_until(self: Pin<&Self>, deadline: Instant) -> Result<ReadGuard<'_>, ReadError> {
+        RwLock::try_read_until(self, deadline)
+    }
+
+    #[inline]
+    fn try_write_until(self: Pin<&Self>, deadline: Instant) -> Option<WriteGuard<'_>> {
+        RwLock::try_write_until(self, deadline)
+    }
+}
+
+unsafe impl RawCondvar for Condvar {
+    type Mutex = Mutex;
+
+    const UNINIT: Self = Condvar::uninit();
+
+    #[inline]
+    fn init(self: Pin<&Self>) {
+        Condvar::init(self)
+    }
+
+    #[inline]
+    fn wait<'a>(self: Pin<&Self>, guard: MutexGuard<'a>) -> MutexGuard<'a>
+    where
+        Mutex: 'a,
+    {
+        Condvar::wait(self, guard)
+    }
+
+    #[inline]
+    fn wait_timeout<'a>(
+        self: Pin<&Self>,
+        guard: MutexGuard<'a>,
+        dur: Duration,
+    ) -> (MutexGuard<'a>, bool)
+    where
+        Mutex: 'a,
+    {
+        let (guard, result) = Condvar::wait_timeout(self, guard, dur);
+        (guard, result.timed_out())
+    }
+
+    #[inline]
+    fn notify_one(self: Pin<&Self>) {
+        Condvar::notify_one(self)
+    }
+
+    #[inline]
+    fn notify_all(self: Pin<&Self>) {
+        Condvar::notify_all(self)
+    }
+
+    #[inline]
+    fn notify_many(self: Pin<&Self>, n: usize) {
+        Condvar::notify_many(self, n)
+    }
+}
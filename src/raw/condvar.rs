@@ -0,0 +1,203 @@
+use crate::raw::MutexGuard;
+use crate::sys::condvar as sys;
+use crate::WaitTimeoutResult;
+use std::fmt;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A raw condition variable.
+///
+/// This is the condition variable of [`Condvar`], to be used with a raw
+/// [`Mutex`].
+///
+/// [`Condvar`]: crate::Condvar
+/// [`Mutex`]: crate::raw::Mutex
+pub struct Condvar {
+    inner: sys::Condvar,
+}
+
+impl Condvar {
+    /// Create a new, uninitialized condition variable.
+    ///
+    /// This is *NOT* equivalent to `MaybeUninit::uninit().assume_init()`, which will cause
+    /// undefined behaviour if used to create a new condition variable.
+    #[inline]
+    pub const fn uninit() -> Self {
+        Self {
+            inner: sys::Condvar::uninit(),
+        }
+    }
+
+    cfg_static_new! {
+        /// Create a new, initialized condition variable, in a `const` context.
+        ///
+        /// Unlike one created with [`uninit`](Self::uninit), the condition
+        /// variable is ready for use as soon as it is pinned, and must not be
+        /// initialized with [`init`](Self::init). This makes it possible to
+        /// declare a condition variable as a `static`.
+        ///
+        /// This is only available on the backends whose primitives are complete
+        /// when constructed, which are all of them except for the generic
+        /// pthread backend and ESP-IDF.
+        // Not `Default`, which would be missing on the other backends.
+        #[allow(clippy::new_without_default)]
+        #[inline]
+        pub const fn new() -> Self {
+            Self {
+                inner: sys::Condvar::new(),
+            }
+        }
+    }
+
+    /// Create a new, initialized condition variable.
+    ///
+    /// The resulting condition variable is wrapped and ready for use.
+    #[inline]
+    pub fn boxed() -> Pin<Box<Self>> {
+        let this = Box::pin(Self::uninit());
+        this.as_ref().init();
+        this
+    }
+
+    /// Create a new, initialized condition variable.
+    ///
+    /// The resulting condition variable is wrapped and ready for use.
+    #[inline]
+    pub fn arc() -> Pin<Arc<Self>> {
+        let this = Arc::pin(Self::uninit());
+        this.as_ref().init();
+        this
+    }
+
+    /// Initialize a condition variable, making it ready for use.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the condition variable was already
+    /// initialized.
+    #[inline]
+    pub fn init(self: Pin<&Self>) {
+        self.inner().init()
+    }
+
+    /// Blocks the current thread until this condition variable receives a
+    /// notification.
+    ///
+    /// The mutex of `guard` is atomically unlocked while the thread is
+    /// blocked, and locked again before this function returns. This function
+    /// is susceptible to spurious wakeups.
+    ///
+    /// # Panics
+    ///
+    /// This function may [`panic!`] if it is used with more than one mutex
+    /// over time.
+    ///
+    /// This function may panic if the condition variable is not initialized.
+    #[inline]
+    pub fn wait<'a>(self: Pin<&Self>, guard: MutexGuard<'a>) -> MutexGuard<'a> {
+        // Safety: Waiting with a second mutex panics.
+        MutexGuard::new(unsafe { self.inner().wait(guard.inner) })
+    }
+
+    /// Waits on this condition variable for a notification, timing out after
+    /// the specified duration.
+    ///
+    /// See [`wait`]. The returned [`WaitTimeoutResult`] tells whether the wait
+    /// is known to have timed out.
+    ///
+    /// # Panics
+    ///
+    /// This function may [`panic!`] if it is used with more than one mutex
+    /// over time.
+    ///
+    /// This function may panic if the condition variable is not initialized.
+    ///
+    /// [`wait`]: Self::wait
+    #[inline]
+    pub fn wait_timeout<'a>(
+        self: Pin<&Self>,
+        guard: MutexGuard<'a>,
+        dur: Duration,
+    ) -> (MutexGuard<'a>, WaitTimeoutResult) {
+        // Safety: Waiting with a second mutex panics.
+        let (notified, guard) = unsafe { self.inner().wait_timeout(guard.inner, dur) };
+        (MutexGuard::new(guard), WaitTimeoutResult(!notified))
+    }
+
+    cfg_interruptible! {
+        /// Blocks the current thread until this condition variable receives a
+        /// notification or a signal is handled by the thread.
+        ///
+        /// See [`wait`]. If a signal handler installed without `SA_RESTART`
+        /// runs while the thread is blocked, the guard is returned in [`Err`]
+        /// instead. The mutex is locked again either way.
+        ///
+        /// This is only available on the Linux futex backend.
+        ///
+        /// # Panics
+        ///
+        /// This function may [`panic!`] if it is used with more than one mutex
+        /// over time.
+        ///
+        /// [`wait`]: Self::wait
+        #[inline]
+        pub fn wait_interruptible<'a>(
+            self: Pin<&Self>,
+            guard: MutexGuard<'a>,
+        ) -> Result<MutexGuard<'a>, MutexGuard<'a>> {
+            // Safety: Waiting with a second mutex panics.
+            let (notified, guard) = unsafe { self.inner().wait_interruptible(guard.inner) };
+            if notified {
+                Ok(MutexGuard::new(guard))
+            } else {
+                Err(MutexGuard::new(guard))
+            }
+        }
+    }
+
+    /// Wakes up one blocked thread on this condition variable.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the condition variable is not initialized.
+    #[inline]
+    pub fn notify_one(self: Pin<&Self>) {
+        self.inner().notify_one()
+    }
+
+    /// Wakes up all blocked threads on this condition variable.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the condition variable is not initialized.
+    #[inline]
+    pub fn notify_all(self: Pin<&Self>) {
+        self.inner().notify_all()
+    }
+
+    /// Wakes up at most `n` blocked threads on this condition variable.
+    ///
+    /// See [`Condvar::notify_many`](crate::Condvar::notify_many).
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the condition variable is not initialized.
+    #[inline]
+    pub fn notify_many(self: Pin<&Self>, n: usize) {
+        if n > 0 {
+            self.inner().notify_many(n)
+        }
+    }
+
+    #[inline]
+    fn inner(self: Pin<&Self>) -> Pin<&sys::Condvar> {
+        unsafe { self.map_unchecked(|this| &this.inner) }
+    }
+}
+
+impl fmt::Debug for Condvar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Condvar").finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,208 @@
+use crate::sys::{condvar, mutex};
+use std::fmt;
+use std::hint;
+use std::marker::PhantomPinned;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering::*};
+use std::sync::Arc;
+
+// How many times the next thread in line polls the lock before blocking.
+const SPIN_LIMIT: u32 = 100;
+
+/// A raw mutual exclusion primitive which is acquired in FIFO order.
+///
+/// This is a ticket lock: each thread which locks it takes the next ticket,
+/// and the lock is held by the thread whose ticket is being served. Unlocking
+/// serves the next ticket, so threads acquire the lock in the order they asked
+/// for it, and no thread can be starved by others which lock it repeatedly.
+///
+/// The cost of fairness is throughput. The lock is handed to the next thread
+/// in line even if it is not running, and the thread which just unlocked it
+/// can not take it back in the meantime. Threads other than the next one in
+/// line block on a condition variable, which every unlock wakes up, so it is
+/// best suited to a moderate number of waiters.
+///
+/// This is the lock of [`FairMutex`].
+///
+/// [`FairMutex`]: crate::FairMutex
+pub struct FairMutex {
+    // The ticket of the next thread to lock.
+    next: AtomicUsize,
+    // The ticket of the thread which holds the lock, or will be the next to.
+    serving: AtomicUsize,
+    // The number of threads which may be blocked on `cvar`. Unlocking only
+    // takes `queue` if it is not zero.
+    waiters: AtomicUsize,
+    queue: mutex::Mutex,
+    cvar: condvar::Condvar,
+    _p: PhantomPinned,
+}
+
+unsafe impl Send for FairMutex {}
+unsafe impl Sync for FairMutex {}
+
+impl FairMutex {
+    /// Create a new, uninitialized fair mutex.
+    ///
+    /// This is *NOT* equivalent to `MaybeUninit::uninit().assume_init()`, which will cause
+    /// undefined behaviour if used to create a new fair mutex.
+    #[inline]
+    pub const fn uninit() -> Self {
+        Self {
+            next: AtomicUsize::new(0),
+            serving: AtomicUsize::new(0),
+            waiters: AtomicUsize::new(0),
+            queue: mutex::Mutex::uninit(),
+            cvar: condvar::Condvar::uninit(),
+            _p: PhantomPinned,
+        }
+    }
+
+    /// Create a new, initialized fair mutex.
+    ///
+    /// The resulting fair mutex is wrapped and ready for use.
+    #[inline]
+    pub fn boxed() -> Pin<Box<Self>> {
+        let this = Box::pin(Self::uninit());
+        this.as_ref().init();
+        this
+    }
+
+    /// Create a new, initialized fair mutex.
+    ///
+    /// The resulting fair mutex is wrapped and ready for use.
+    #[inline]
+    pub fn arc() -> Pin<Arc<Self>> {
+        let this = Arc::pin(Self::uninit());
+        this.as_ref().init();
+        this
+    }
+
+    /// Initialize a fair mutex, making it ready for use.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the fair mutex was already initialized.
+    #[inline]
+    pub fn init(self: Pin<&Self>) {
+        self.queue().init();
+        self.cvar().init();
+    }
+
+    /// Acquires the mutex, blocking the current thread until every thread
+    /// which asked for it before has released it.
+    ///
+    /// # Panics
+    ///
+    /// This function deadlocks if the lock is already held by the current
+    /// thread.
+    ///
+    /// This function may panic if the fair mutex is not initialized.
+    #[inline]
+    pub fn lock(self: Pin<&Self>) -> FairMutexGuard<'_> {
+        let ticket = self.next.fetch_add(1, Relaxed);
+        if self.serving.load(Acquire) != ticket {
+            self.lock_slow(ticket);
+        }
+        FairMutexGuard { mutex: self }
+    }
+
+    /// Attempts to acquire the mutex.
+    ///
+    /// The lock is only acquired if it is free and no other thread is waiting
+    /// for it. Otherwise, [`None`] is returned. This function does not block.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the fair mutex is not initialized.
+    #[inline]
+    pub fn try_lock(self: Pin<&Self>) -> Option<FairMutexGuard<'_>> {
+        let serving = self.serving.load(Acquire);
+        self.next
+            .compare_exchange(serving, serving.wrapping_add(1), Relaxed, Relaxed)
+            .ok()
+            .map(|_| FairMutexGuard { mutex: self })
+    }
+
+    #[cold]
+    fn lock_slow(self: Pin<&Self>, ticket: usize) {
+        // The next thread in line gets the lock as soon as it is released, so
+        // it is worth polling for a bit. The others have to wait for at least
+        // one more critical section.
+        if ticket.wrapping_sub(self.serving.load(Relaxed)) == 1 {
+            for _ in 0..SPIN_LIMIT {
+                hint::spin_loop();
+                if self.serving.load(Acquire) == ticket {
+                    return;
+                }
+            }
+        }
+        let mut queue = self.queue().lock();
+        // Pairs with the load in `unlock`: either this thread sees its ticket
+        // served, or the unlocking thread sees it waiting.
+        self.waiters.fetch_add(1, SeqCst);
+        while self.serving.load(SeqCst) != ticket {
+            // Safety: the condvar is only ever used with `self.queue`.
+            queue = unsafe { self.cvar().wait(queue) };
+        }
+        self.waiters.fetch_sub(1, Relaxed);
+    }
+
+    /// Hands the mutex to the next thread in line without a guard.
+    ///
+    /// # Safety
+    ///
+    /// The mutex must be locked, and the guard which locked it forgotten,
+    /// such as with [`mem::forget`](std::mem::forget).
+    #[inline]
+    pub unsafe fn unlock(self: Pin<&Self>) {
+        self.serving.fetch_add(1, SeqCst);
+        if self.waiters.load(SeqCst) != 0 {
+            self.unlock_slow();
+        }
+    }
+
+    #[cold]
+    fn unlock_slow(self: Pin<&Self>) {
+        // Taking the lock makes sure that every waiter is either blocked on
+        // the condvar already, or has yet to check the ticket being served.
+        // Only the waiter with that ticket goes on; the others wait again.
+        let _queue = self.queue().lock();
+        self.cvar().notify_all();
+    }
+
+    #[inline]
+    fn queue(self: Pin<&Self>) -> Pin<&mutex::Mutex> {
+        unsafe { self.map_unchecked(|this| &this.queue) }
+    }
+
+    #[inline]
+    fn cvar(self: Pin<&Self>) -> Pin<&condvar::Condvar> {
+        unsafe { self.map_unchecked(|this| &this.cvar) }
+    }
+}
+
+impl fmt::Debug for FairMutex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FairMutex").finish_non_exhaustive()
+    }
+}
+
+/// An RAII guard of a raw [`FairMutex`]. When this structure is dropped, the
+/// mutex is handed to the next thread in line.
+pub struct FairMutexGuard<'a> {
+    mutex: Pin<&'a FairMutex>,
+}
+
+impl Drop for FairMutexGuard<'_> {
+    #[inline]
+    fn drop(&mut self) {
+        unsafe { self.mutex.unlock() }
+    }
+}
+
+impl fmt::Debug for FairMutexGuard<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FairMutexGuard").finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,131 @@
+use crate::sys::futex;
+use std::fmt;
+use std::sync::atomic::{AtomicU32, Ordering::*};
+use std::time::Duration;
+
+/// A 32-bit atomic integer which threads can wait on.
+///
+/// This is the futex of the platform, or what stands for it: `futex(2)` on
+/// Linux and Android, `_umtx_op(2)` on FreeBSD, Zircon futexes on Fuchsia,
+/// LWP parking on NetBSD, the futex system call on Redox, and the futex
+/// functions of Emscripten and the atomic wait instructions of WebAssembly.
+/// It is the foundation of the locks of this crate on those platforms, and is
+/// available there whichever backend the locks are built on.
+///
+/// [`wait`] blocks while the value is the expected one, and the wake
+/// operations wake up the threads waiting on it. Comparing the value and going
+/// to sleep is atomic with respect to waking up, so a thread which changes the
+/// value and then wakes up the others can not be missed. A waiting thread may
+/// still wake up spuriously, so it has to check the value again.
+///
+/// Only the threads of the current process can wait on a futex.
+///
+/// [`wait`]: Self::wait
+///
+/// # Examples
+///
+/// ```
+/// use pinned_sync::raw::Futex;
+/// use std::sync::atomic::Ordering;
+/// use std::sync::Arc;
+/// use std::thread;
+///
+/// let futex = Arc::new(Futex::new(0));
+/// let futex2 = futex.clone();
+///
+/// thread::spawn(move || {
+///     futex2.as_atomic().store(1, Ordering::Release);
+///     futex2.wake_all();
+/// });
+///
+/// while futex.as_atomic().load(Ordering::Acquire) == 0 {
+///     futex.wait(0);
+/// }
+/// ```
+pub struct Futex {
+    value: AtomicU32,
+}
+
+impl Futex {
+    /// Create a new futex with the given value.
+    ///
+    /// A futex needs no initialization, and is identified by its address only
+    /// while threads are waiting on it, so it does not need to be pinned.
+    #[inline]
+    pub const fn new(value: u32) -> Self {
+        Self {
+            value: AtomicU32::new(value),
+        }
+    }
+
+    /// Returns the value of the futex, to be read and modified atomically.
+    #[inline]
+    pub fn as_atomic(&self) -> &AtomicU32 {
+        &self.value
+    }
+
+    /// Consumes this futex, returning its value.
+    #[inline]
+    pub fn into_inner(self) -> u32 {
+        self.value.into_inner()
+    }
+
+    /// Blocks the current thread while the value of the futex is `expected`,
+    /// until it is woken up.
+    ///
+    /// This returns immediately if the value is not `expected`, and may also
+    /// return spuriously.
+    ///
+    /// On WebAssembly, this traps if the current thread is not allowed to
+    /// block, such as the main thread of a browser.
+    #[inline]
+    pub fn wait(&self, expected: u32) {
+        futex::wait(&self.value, expected, None);
+    }
+
+    /// Blocks the current thread while the value of the futex is `expected`,
+    /// until it is woken up or the timeout expires.
+    ///
+    /// Returns `false` if the timeout expired. This returns `true` immediately
+    /// if the value is not `expected`, and may also return spuriously.
+    ///
+    /// On WebAssembly, this traps if the current thread is not allowed to
+    /// block, such as the main thread of a browser.
+    #[inline]
+    pub fn wait_timeout(&self, expected: u32, dur: Duration) -> bool {
+        futex::wait(&self.value, expected, Some(dur))
+    }
+
+    /// Wakes up one of the threads waiting on the futex, if any.
+    ///
+    /// Returns whether a thread was woken up. FreeBSD and Fuchsia can not tell,
+    /// and always return `false`.
+    #[inline]
+    pub fn wake_one(&self) -> bool {
+        futex::wake(&self.value, 1)
+    }
+
+    /// Wakes up every thread waiting on the futex.
+    ///
+    /// Returns whether any thread was woken up. FreeBSD and Fuchsia can not
+    /// tell, and always return `false`.
+    #[inline]
+    pub fn wake_all(&self) -> bool {
+        futex::wake(&self.value, i32::MAX)
+    }
+}
+
+impl Default for Futex {
+    #[inline]
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl fmt::Debug for Futex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Futex")
+            .field("value", &self.value.load(Relaxed))
+            .finish()
+    }
+}
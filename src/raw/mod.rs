@@ -0,0 +1,89 @@
+//! The platform blocking primitives, without poisoning or data.
+//!
+//! These are the locks and condition variable which [`Mutex`], [`RwLock`] and
+//! [`Condvar`] are built upon, for crates building their own primitives, such
+//! as channels or executors, which only need the blocking machinery of the
+//! platform.
+//!
+//! The backend is the same as for the rest of the crate:
+//!
+//! - futexes on Linux and Android, and their equivalents on FreeBSD, Fuchsia,
+//!   NetBSD, Redox, Emscripten and `wasm32` with the `atomics` target feature;
+//! - the native `mutex_t`, `cond_t` and `rwlock_t` on illumos and Solaris;
+//! - FreeRTOS semaphores on ESP-IDF;
+//! - pthread on the other unix platforms;
+//! - slim reader/writer locks and condition variables on Windows;
+//! - `std::thread::park` and atomics on the platforms without a dedicated
+//!   backend, or everywhere with the `thread-park` feature;
+//! - `parking_lot_core` everywhere with the `parking-lot-core` feature, and
+//!   atomics and spinning with the `spin` feature.
+//!
+//! Other backends can be plugged into [`Mutex`] by implementing [`RawMutex`],
+//! as the ticket lock [`FairMutex`] does, and into [`RwLock`] and [`Condvar`]
+//! by implementing [`RawRwLock`] and [`RawCondvar`].
+//!
+//! On the platforms which have them, [`Futex`] exposes the futex-like wait and
+//! wake operations which the locks are built upon there.
+//!
+//! # Contracts
+//!
+//! Unlike the front end, these types do not protect any data, so they are
+//! safe to use, but they are only as forgiving as the platform is:
+//!
+//! - Every primitive must be initialized before it is used, and at most once.
+//!   Using an uninitialized primitive may panic.
+//! - Locking a [`raw::Mutex`] which is already held by the current thread
+//!   may deadlock or panic, as may acquiring a [`raw::RwLock`] which is
+//!   already write-locked by the current thread. Use a
+//!   [`raw::ReentrantMutex`] where recursion must be allowed.
+//! - Guards are not `Send`, as some backends require a lock to be released by
+//!   the thread which acquired it. The `send_guard` feature makes them `Send`
+//!   on the other backends, which are all of them but pthread, Windows and
+//!   ESP-IDF.
+//! - A [`raw::Condvar`] may only ever be used with one [`raw::Mutex`], and
+//!   panics if it is used with another one.
+//!
+//! [`Mutex`]: crate::Mutex
+//! [`RwLock`]: crate::RwLock
+//! [`Condvar`]: crate::Condvar
+//! [`raw::Mutex`]: Mutex
+//! [`raw::RwLock`]: RwLock
+//! [`raw::ReentrantMutex`]: ReentrantMutex
+//! [`raw::Condvar`]: Condvar
+
+mod backend;
+mod condvar;
+mod fair_mutex;
+#[cfg(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "freebsd",
+    target_os = "fuchsia",
+    target_os = "netbsd",
+    target_os = "redox",
+    target_os = "emscripten",
+    all(target_arch = "wasm32", target_feature = "atomics"),
+))]
+mod futex;
+mod mutex;
+mod remutex;
+mod rwlock;
+
+pub use crate::sys::ReadError;
+pub use backend::*;
+pub use condvar::*;
+pub use fair_mutex::*;
+#[cfg(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "freebsd",
+    target_os = "fuchsia",
+    target_os = "netbsd",
+    target_os = "redox",
+    target_os = "emscripten",
+    all(target_arch = "wasm32", target_feature = "atomics"),
+))]
+pub use futex::*;
+pub use mutex::*;
+pub use remutex::*;
+pub use rwlock::*;
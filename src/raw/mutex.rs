@@ -0,0 +1,221 @@
+use crate::sys::mutex as sys;
+use crate::sys_common::marker::GuardMarker;
+use std::fmt;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// A raw mutual exclusion primitive.
+///
+/// This is the lock of [`Mutex`], without poisoning and without any data.
+///
+/// [`Mutex`]: crate::Mutex
+pub struct Mutex {
+    pub(super) inner: sys::Mutex,
+}
+
+impl Mutex {
+    /// Create a new, uninitialized mutex.
+    ///
+    /// This is *NOT* equivalent to `MaybeUninit::uninit().assume_init()`, which will cause
+    /// undefined behaviour if used to create a new mutex.
+    #[inline]
+    pub const fn uninit() -> Self {
+        Self {
+            inner: sys::Mutex::uninit(),
+        }
+    }
+
+    cfg_static_new! {
+        /// Create a new, initialized mutex, in a `const` context.
+        ///
+        /// Unlike one created with [`uninit`](Self::uninit), the mutex is ready
+        /// for use as soon as it is pinned, and must not be initialized with
+        /// [`init`](Self::init). This makes it possible to declare a mutex as a
+        /// `static`.
+        ///
+        /// This is only available on the backends whose primitives are complete
+        /// when constructed, which are all of them except for the generic
+        /// pthread backend and ESP-IDF.
+        // Not `Default`, which would be missing on the other backends.
+        #[allow(clippy::new_without_default)]
+        #[inline]
+        pub const fn new() -> Self {
+            Self {
+                inner: sys::Mutex::new(),
+            }
+        }
+    }
+
+    /// Create a new, initialized mutex.
+    ///
+    /// The resulting mutex is wrapped and ready for use.
+    #[inline]
+    pub fn boxed() -> Pin<Box<Self>> {
+        let this = Box::pin(Self::uninit());
+        this.as_ref().init();
+        this
+    }
+
+    /// Create a new, initialized mutex.
+    ///
+    /// The resulting mutex is wrapped and ready for use.
+    #[inline]
+    pub fn arc() -> Pin<Arc<Self>> {
+        let this = Arc::pin(Self::uninit());
+        this.as_ref().init();
+        this
+    }
+
+    /// Initialize a mutex, making it ready for use.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the mutex was already initialized.
+    #[inline]
+    pub fn init(self: Pin<&Self>) {
+        self.inner().init()
+    }
+
+    /// Acquires the mutex, blocking the current thread until it is able to do
+    /// so.
+    ///
+    /// # Panics
+    ///
+    /// This function might panic when called if the lock is already held by
+    /// the current thread.
+    ///
+    /// This function may panic if the mutex is not initialized.
+    #[inline]
+    pub fn lock(self: Pin<&Self>) -> MutexGuard<'_> {
+        MutexGuard::new(self.inner().lock())
+    }
+
+    cfg_interruptible! {
+        /// Acquires the mutex, blocking the current thread until it is able to
+        /// do so or a signal is handled by the thread.
+        ///
+        /// If a signal handler installed without `SA_RESTART` runs while the
+        /// thread is blocked, then [`None`] is returned without the lock.
+        ///
+        /// This is only available on the Linux futex backend.
+        ///
+        /// # Panics
+        ///
+        /// This function might panic when called if the lock is already held
+        /// by the current thread.
+        #[inline]
+        pub fn lock_interruptible(self: Pin<&Self>) -> Option<MutexGuard<'_>> {
+            self.inner().lock_interruptible().map(MutexGuard::new)
+        }
+    }
+
+    /// Attempts to acquire the mutex.
+    ///
+    /// If the lock could not be acquired at this time, then [`None`] is
+    /// returned. This function does not block.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the mutex is not initialized.
+    #[inline]
+    pub fn try_lock(self: Pin<&Self>) -> Option<MutexGuard<'_>> {
+        self.inner().try_lock().map(MutexGuard::new)
+    }
+
+    /// Attempts to acquire the mutex, blocking the current thread until it is
+    /// able to do so or the timeout expires.
+    ///
+    /// If the lock could not be acquired in time, then [`None`] is returned.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the mutex is not initialized.
+    #[inline]
+    pub fn try_lock_for(self: Pin<&Self>, dur: Duration) -> Option<MutexGuard<'_>> {
+        match Instant::now().checked_add(dur) {
+            Some(deadline) => self.try_lock_until(deadline),
+            // A timeout which can not be represented is as good as no timeout.
+            None => Some(self.lock()),
+        }
+    }
+
+    /// Attempts to acquire the mutex, blocking the current thread until it is
+    /// able to do so or the deadline is reached.
+    ///
+    /// If the lock could not be acquired in time, then [`None`] is returned.
+    /// Where the platform lock can not block with a timeout, such as on
+    /// Windows and macOS, this polls the lock instead.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the mutex is not initialized.
+    #[inline]
+    pub fn try_lock_until(self: Pin<&Self>, deadline: Instant) -> Option<MutexGuard<'_>> {
+        self.inner().try_lock_until(deadline).map(MutexGuard::new)
+    }
+
+    /// Unlocks the mutex without a guard.
+    ///
+    /// # Safety
+    ///
+    /// The mutex must be locked, and the guard which locked it forgotten,
+    /// such as with [`mem::forget`](std::mem::forget).
+    #[inline]
+    pub unsafe fn unlock(self: Pin<&Self>) {
+        self.inner().unlock()
+    }
+
+    #[cfg(unix)]
+    #[inline]
+    pub(crate) unsafe fn reinit_after_fork(self: Pin<&Self>) {
+        self.inner().reinit_after_fork()
+    }
+
+    #[inline]
+    fn inner(self: Pin<&Self>) -> Pin<&sys::Mutex> {
+        unsafe { self.map_unchecked(|this| &this.inner) }
+    }
+}
+
+impl fmt::Debug for Mutex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Mutex").finish_non_exhaustive()
+    }
+}
+
+/// An RAII guard of a raw [`Mutex`]. When this structure is dropped, the
+/// mutex is unlocked.
+pub struct MutexGuard<'a> {
+    pub(crate) inner: sys::MutexGuard<'a>,
+    _marker: PhantomData<GuardMarker>,
+}
+
+impl<'a> MutexGuard<'a> {
+    #[inline]
+    pub(crate) fn new(inner: sys::MutexGuard<'a>) -> Self {
+        Self {
+            inner,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Temporarily unlocks the mutex so that the threads waiting for it can
+    /// take it first, and locks it again.
+    ///
+    /// This lets a thread which holds the mutex for a long time, such as
+    /// through a loop, avoid starving the others. If nobody is waiting, this
+    /// returns right away where the platform lock can tell; otherwise the
+    /// mutex is unlocked and locked again.
+    #[inline]
+    pub fn bump(self) -> Self {
+        Self::new(self.inner.bump())
+    }
+}
+
+impl fmt::Debug for MutexGuard<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MutexGuard").finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,121 @@
+use crate::sys::remutex as sys;
+use std::fmt;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::Arc;
+
+/// A raw re-entrant mutual exclusion primitive.
+///
+/// This is the lock of [`ReentrantMutex`], without any data: the thread which
+/// holds it may lock it again without blocking, and it is released once every
+/// guard is dropped. With the pthread backend, this is a
+/// `PTHREAD_MUTEX_RECURSIVE` mutex.
+///
+/// [`ReentrantMutex`]: crate::ReentrantMutex
+pub struct ReentrantMutex {
+    inner: sys::ReentrantMutex,
+}
+
+impl ReentrantMutex {
+    /// Create a new, uninitialized re-entrant mutex.
+    ///
+    /// This is *NOT* equivalent to `MaybeUninit::uninit().assume_init()`, which will cause
+    /// undefined behaviour if used to create a new re-entrant mutex.
+    #[inline]
+    pub const fn uninit() -> Self {
+        Self {
+            inner: sys::ReentrantMutex::uninit(),
+        }
+    }
+
+    /// Create a new, initialized re-entrant mutex.
+    ///
+    /// The resulting re-entrant mutex is wrapped and ready for use.
+    #[inline]
+    pub fn boxed() -> Pin<Box<Self>> {
+        let this = Box::pin(Self::uninit());
+        this.as_ref().init();
+        this
+    }
+
+    /// Create a new, initialized re-entrant mutex.
+    ///
+    /// The resulting re-entrant mutex is wrapped and ready for use.
+    #[inline]
+    pub fn arc() -> Pin<Arc<Self>> {
+        let this = Arc::pin(Self::uninit());
+        this.as_ref().init();
+        this
+    }
+
+    /// Initialize a re-entrant mutex, making it ready for use.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the re-entrant mutex was already initialized.
+    #[inline]
+    pub fn init(self: Pin<&Self>) {
+        self.inner().init()
+    }
+
+    /// Acquires the mutex, blocking the current thread until it is able to do
+    /// so, unless the current thread already holds it.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the lock count would overflow.
+    ///
+    /// This function may panic if the re-entrant mutex is not initialized.
+    #[inline]
+    pub fn lock(self: Pin<&Self>) -> ReentrantMutexGuard<'_> {
+        ReentrantMutexGuard {
+            _inner: self.inner().lock(),
+            _not_send: PhantomData,
+        }
+    }
+
+    /// Attempts to acquire the mutex.
+    ///
+    /// If the lock is held by another thread, then [`None`] is returned. This
+    /// function does not block.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the lock count would overflow.
+    ///
+    /// This function may panic if the re-entrant mutex is not initialized.
+    #[inline]
+    pub fn try_lock(self: Pin<&Self>) -> Option<ReentrantMutexGuard<'_>> {
+        Some(ReentrantMutexGuard {
+            _inner: self.inner().try_lock()?,
+            _not_send: PhantomData,
+        })
+    }
+
+    #[inline]
+    fn inner(self: Pin<&Self>) -> Pin<&sys::ReentrantMutex> {
+        unsafe { self.map_unchecked(|this| &this.inner) }
+    }
+}
+
+impl fmt::Debug for ReentrantMutex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReentrantMutex").finish_non_exhaustive()
+    }
+}
+
+/// An RAII guard of a raw [`ReentrantMutex`]. When every guard of the mutex is
+/// dropped, it is unlocked.
+pub struct ReentrantMutexGuard<'a> {
+    _inner: sys::ReentrantMutexGuard<'a>,
+    // The guard must be dropped by the thread which owns the lock, even with
+    // the `send_guard` feature.
+    _not_send: PhantomData<*const ()>,
+}
+
+impl fmt::Debug for ReentrantMutexGuard<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReentrantMutexGuard")
+            .finish_non_exhaustive()
+    }
+}
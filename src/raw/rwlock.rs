@@ -0,0 +1,307 @@
+use crate::sys::rwlock as sys;
+use crate::sys::ReadError;
+use crate::sys_common::marker::GuardMarker;
+use std::fmt;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// A raw reader-writer lock.
+///
+/// This is the lock of [`RwLock`], without poisoning, writer policies and
+/// data.
+///
+/// [`RwLock`]: crate::RwLock
+pub struct RwLock {
+    inner: sys::RwLock,
+}
+
+impl RwLock {
+    /// Create a new, uninitialized reader-writer lock.
+    ///
+    /// This is *NOT* equivalent to `MaybeUninit::uninit().assume_init()`, which will cause
+    /// undefined behaviour if used to create a new reader-writer lock.
+    #[inline]
+    pub const fn uninit() -> Self {
+        Self {
+            inner: sys::RwLock::uninit(),
+        }
+    }
+
+    cfg_static_new! {
+        /// Create a new, initialized reader-writer lock, in a `const` context.
+        ///
+        /// Unlike one created with [`uninit`](Self::uninit), the reader-writer
+        /// lock is ready for use as soon as it is pinned, and must not be
+        /// initialized with [`init`](Self::init). This makes it possible to
+        /// declare a reader-writer lock as a `static`.
+        ///
+        /// This is only available on the backends whose primitives are complete
+        /// when constructed, which are all of them except for the generic
+        /// pthread backend and ESP-IDF.
+        // Not `Default`, which would be missing on the other backends.
+        #[allow(clippy::new_without_default)]
+        #[inline]
+        pub const fn new() -> Self {
+            Self {
+                inner: sys::RwLock::new(),
+            }
+        }
+    }
+
+    /// Create a new, initialized reader-writer lock.
+    ///
+    /// The resulting reader-writer lock is wrapped and ready for use.
+    #[inline]
+    pub fn boxed() -> Pin<Box<Self>> {
+        let this = Box::pin(Self::uninit());
+        this.as_ref().init();
+        this
+    }
+
+    /// Create a new, initialized reader-writer lock.
+    ///
+    /// The resulting reader-writer lock is wrapped and ready for use.
+    #[inline]
+    pub fn arc() -> Pin<Arc<Self>> {
+        let this = Arc::pin(Self::uninit());
+        this.as_ref().init();
+        this
+    }
+
+    /// Initialize a reader-writer lock, making it ready for use.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the lock was already initialized.
+    #[inline]
+    pub fn init(self: Pin<&Self>) {
+        self.inner().init()
+    }
+
+    /// Locks this lock with shared read access, blocking the current thread
+    /// until it can be acquired.
+    ///
+    /// Returns [`None`] if the maximum number of readers was reached.
+    ///
+    /// # Panics
+    ///
+    /// This function might panic when called if the lock is already held by
+    /// the current thread.
+    ///
+    /// This function may panic if the lock is not initialized.
+    #[inline]
+    pub fn read(self: Pin<&Self>) -> Option<ReadGuard<'_>> {
+        self.inner().read().map(ReadGuard::new)
+    }
+
+    /// Attempts to acquire this lock with shared read access.
+    ///
+    /// This function does not block.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the lock is not initialized.
+    #[inline]
+    pub fn try_read(self: Pin<&Self>) -> Result<ReadGuard<'_>, ReadError> {
+        self.inner().try_read().map(ReadGuard::new)
+    }
+
+    /// Locks this lock with exclusive write access, blocking the current
+    /// thread until it can be acquired.
+    ///
+    /// # Panics
+    ///
+    /// This function might panic when called if the lock is already held by
+    /// the current thread.
+    ///
+    /// This function may panic if the lock is not initialized.
+    #[inline]
+    pub fn write(self: Pin<&Self>) -> WriteGuard<'_> {
+        WriteGuard::new(self.inner().write())
+    }
+
+    /// Attempts to lock this lock with exclusive write access.
+    ///
+    /// If the lock could not be acquired at this time, then [`None`] is
+    /// returned. This function does not block.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the lock is not initialized.
+    #[inline]
+    pub fn try_write(self: Pin<&Self>) -> Option<WriteGuard<'_>> {
+        self.inner().try_write().map(WriteGuard::new)
+    }
+
+    /// Attempts to acquire this lock with shared read access, blocking the
+    /// current thread until it can be acquired or the timeout expires.
+    ///
+    /// If the timeout expired, [`ReadError::WouldBlock`] is returned.
+    ///
+    /// # Panics
+    ///
+    /// This function might panic when called if the lock is already held by
+    /// the current thread.
+    ///
+    /// This function may panic if the lock is not initialized.
+    #[inline]
+    pub fn try_read_for(self: Pin<&Self>, dur: Duration) -> Result<ReadGuard<'_>, ReadError> {
+        match Instant::now().checked_add(dur) {
+            Some(deadline) => self.try_read_until(deadline),
+            // A timeout which can not be represented is as good as no timeout.
+            None => self.read().ok_or(ReadError::TooManyReaders),
+        }
+    }
+
+    /// Attempts to acquire this lock with shared read access, blocking the
+    /// current thread until it can be acquired or the deadline is reached.
+    ///
+    /// If the deadline was reached, [`ReadError::WouldBlock`] is returned.
+    ///
+    /// # Panics
+    ///
+    /// This function might panic when called if the lock is already held by
+    /// the current thread.
+    ///
+    /// This function may panic if the lock is not initialized.
+    #[inline]
+    pub fn try_read_until(self: Pin<&Self>, deadline: Instant) -> Result<ReadGuard<'_>, ReadError> {
+        self.inner().try_read_until(deadline).map(ReadGuard::new)
+    }
+
+    /// Attempts to lock this lock with exclusive write access, blocking the
+    /// current thread until it can be acquired or the timeout expires.
+    ///
+    /// If the lock could not be acquired in time, then [`None`] is returned.
+    ///
+    /// # Panics
+    ///
+    /// This function might panic when called if the lock is already held by
+    /// the current thread.
+    ///
+    /// This function may panic if the lock is not initialized.
+    #[inline]
+    pub fn try_write_for(self: Pin<&Self>, dur: Duration) -> Option<WriteGuard<'_>> {
+        match Instant::now().checked_add(dur) {
+            Some(deadline) => self.try_write_until(deadline),
+            // A timeout which can not be represented is as good as no timeout.
+            None => Some(self.write()),
+        }
+    }
+
+    /// Attempts to lock this lock with exclusive write access, blocking the
+    /// current thread until it can be acquired or the deadline is reached.
+    ///
+    /// If the lock could not be acquired in time, then [`None`] is returned.
+    ///
+    /// # Panics
+    ///
+    /// This function might panic when called if the lock is already held by
+    /// the current thread.
+    ///
+    /// This function may panic if the lock is not initialized.
+    #[inline]
+    pub fn try_write_until(self: Pin<&Self>, deadline: Instant) -> Option<WriteGuard<'_>> {
+        self.inner().try_write_until(deadline).map(WriteGuard::new)
+    }
+
+    /// Releases shared read access without a guard.
+    ///
+    /// # Safety
+    ///
+    /// The lock must be held for reading, and the guard which acquired it
+    /// forgotten, such as with [`mem::forget`](std::mem::forget).
+    #[inline]
+    pub unsafe fn read_unlock(self: Pin<&Self>) {
+        self.inner().read_unlock()
+    }
+
+    /// Releases exclusive write access without a guard.
+    ///
+    /// # Safety
+    ///
+    /// The lock must be held for writing, and the guard which acquired it
+    /// forgotten, such as with [`mem::forget`](std::mem::forget).
+    #[inline]
+    pub unsafe fn write_unlock(self: Pin<&Self>) {
+        self.inner().write_unlock()
+    }
+
+    #[cfg(unix)]
+    #[inline]
+    pub(crate) unsafe fn reinit_after_fork(self: Pin<&Self>) {
+        self.inner().reinit_after_fork()
+    }
+
+    #[inline]
+    fn inner(self: Pin<&Self>) -> Pin<&sys::RwLock> {
+        unsafe { self.map_unchecked(|this| &this.inner) }
+    }
+}
+
+impl fmt::Debug for RwLock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RwLock").finish_non_exhaustive()
+    }
+}
+
+/// An RAII guard of the shared read access of a raw [`RwLock`]. When this
+/// structure is dropped, the shared access is released.
+pub struct ReadGuard<'a> {
+    _inner: sys::ReadGuard<'a>,
+    _marker: PhantomData<GuardMarker>,
+}
+
+impl<'a> ReadGuard<'a> {
+    #[inline]
+    fn new(inner: sys::ReadGuard<'a>) -> Self {
+        Self {
+            _inner: inner,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl Clone for ReadGuard<'_> {
+    /// Makes a new guard sharing the read access of this one, which only
+    /// increments the reader count of the lock.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the maximum number of readers is reached.
+    #[inline]
+    fn clone(&self) -> Self {
+        Self::new(self._inner.clone())
+    }
+}
+
+impl fmt::Debug for ReadGuard<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReadGuard").finish_non_exhaustive()
+    }
+}
+
+/// An RAII guard of the exclusive write access of a raw [`RwLock`]. When this
+/// structure is dropped, the exclusive access is released.
+pub struct WriteGuard<'a> {
+    _inner: sys::WriteGuard<'a>,
+    _marker: PhantomData<GuardMarker>,
+}
+
+impl<'a> WriteGuard<'a> {
+    #[inline]
+    fn new(inner: sys::WriteGuard<'a>) -> Self {
+        Self {
+            _inner: inner,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl fmt::Debug for WriteGuard<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WriteGuard").finish_non_exhaustive()
+    }
+}
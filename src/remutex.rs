@@ -0,0 +1,316 @@
+use crate::sys::remutex as sys;
+use crate::Unlockable;
+use std::cell::{BorrowError, BorrowMutError, Ref, RefCell, RefMut};
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::pin::Pin;
+use std::sync::Arc;
+
+/// A re-entrant mutual exclusion
+///
+/// This mutex will block *other* threads waiting for the lock to become
+/// available. The thread which has already locked the mutex can lock it
+/// multiple times without blocking, preventing a common source of deadlocks.
+///
+/// As the same thread may hold several guards at once, the guards only give
+/// shared access to the data. See [`ReentrantRefCell`] for checked mutable
+/// access.
+///
+/// With the pthread backend, used on the unix systems without a dedicated one
+/// such as macOS, this is a `PTHREAD_MUTEX_RECURSIVE` mutex. Elsewhere, the
+/// recursion is counted on top of the mutex of the backend.
+pub struct ReentrantMutex<T: ?Sized> {
+    inner: sys::ReentrantMutex,
+    data: T,
+}
+
+unsafe impl<T: ?Sized + Send> Send for ReentrantMutex<T> {}
+
+unsafe impl<T: ?Sized + Send> Sync for ReentrantMutex<T> {}
+
+impl<T> ReentrantMutex<T> {
+    /// Create a new, uninitialized re-entrant mutex.
+    ///
+    /// This is *NOT* equivalent to `MaybeUninit::uninit().assume_init()`, which will cause
+    /// undefined behaviour if used to create a new re-entrant mutex.
+    #[inline]
+    pub const fn uninit(value: T) -> Self {
+        Self {
+            inner: sys::ReentrantMutex::uninit(),
+            data: value,
+        }
+    }
+
+    /// Create a new, initialized re-entrant mutex.
+    ///
+    /// The resulting re-entrant mutex is wrapped and ready for use.
+    #[inline]
+    pub fn boxed(value: T) -> Pin<Box<Self>> {
+        let this = Box::pin(Self::uninit(value));
+        this.as_ref().init();
+        this
+    }
+
+    /// Create a new, initialized re-entrant mutex.
+    ///
+    /// The resulting re-entrant mutex is wrapped and ready for use.
+    #[inline]
+    pub fn arc(value: T) -> Pin<Arc<Self>> {
+        let this = Arc::pin(Self::uninit(value));
+        this.as_ref().init();
+        this
+    }
+
+    /// Consumes this re-entrant mutex, returning the underlying data.
+    pub fn into_inner(self) -> T {
+        self.data
+    }
+}
+
+impl<T: ?Sized> ReentrantMutex<T> {
+    /// Initialize a re-entrant mutex, making it ready for use.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the re-entrant mutex was already initialized.
+    #[inline]
+    pub fn init(self: Pin<&Self>) {
+        self.inner().init()
+    }
+
+    /// Acquires a re-entrant mutex, blocking the current thread until it is
+    /// able to do so.
+    ///
+    /// This function will block the caller until it is available to acquire
+    /// the mutex. Upon returning, the thread is the only thread with the mutex
+    /// held. When the thread calling this method already holds the lock, the
+    /// call succeeds without blocking.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the lock count would overflow.
+    ///
+    /// This function may panic if the re-entrant mutex is not initialized.
+    pub fn lock(self: Pin<&Self>) -> ReentrantMutexGuard<'_, T> {
+        ReentrantMutexGuard {
+            lock: self,
+            _guard: self.inner().lock(),
+            _not_send: PhantomData,
+        }
+    }
+
+    /// Attempts to acquire this lock.
+    ///
+    /// If the lock could not be acquired at this time, then [`None`] is
+    /// returned. Otherwise, an RAII guard is returned.
+    ///
+    /// This function does not block.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the lock count would overflow.
+    ///
+    /// This function may panic if the re-entrant mutex is not initialized.
+    pub fn try_lock(self: Pin<&Self>) -> Option<ReentrantMutexGuard<'_, T>> {
+        Some(ReentrantMutexGuard {
+            lock: self,
+            _guard: self.inner().try_lock()?,
+            _not_send: PhantomData,
+        })
+    }
+
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// Since this call borrows the `ReentrantMutex` mutably, no actual locking
+    /// needs to take place -- the mutable borrow statically guarantees no locks
+    /// exist.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.data
+    }
+
+    #[inline]
+    fn inner(self: Pin<&Self>) -> Pin<&sys::ReentrantMutex> {
+        unsafe { self.map_unchecked(|this| &this.inner) }
+    }
+}
+
+/// An RAII implementation of a "scoped lock" of a re-entrant mutex. When this
+/// structure is dropped (falls out of scope), the lock will be unlocked.
+///
+/// The data protected by the mutex can be accessed through this guard via its
+/// [`Deref`] implementation.
+pub struct ReentrantMutexGuard<'a, T: ?Sized> {
+    lock: Pin<&'a ReentrantMutex<T>>,
+    _guard: sys::ReentrantMutexGuard<'a>,
+    // The guard must be dropped by the thread which owns the lock.
+    _not_send: PhantomData<*const ()>,
+}
+
+unsafe impl<T: ?Sized + Sync> Sync for ReentrantMutexGuard<'_, T> {}
+
+/// Only this guard is released while waiting, so waiting with a re-entrant
+/// mutex which the current thread locked more than once keeps it locked.
+impl<T: ?Sized> Unlockable for ReentrantMutexGuard<'_, T> {
+    type Relocked = Self;
+
+    #[inline]
+    fn with_unlocked(self, f: impl FnOnce()) -> Self {
+        let lock = self.lock;
+        drop(self);
+        f();
+        lock.lock()
+    }
+}
+
+impl<T: ?Sized> Deref for ReentrantMutexGuard<'_, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        &self.lock.get_ref().data
+    }
+}
+
+/// A re-entrant mutex protecting a [`RefCell`].
+///
+/// This pairs a [`ReentrantMutex`] with a [`RefCell`], so that code which may
+/// be called re-entrantly gets checked mutable access to the data instead of
+/// only a shared reference. Borrowing rules are enforced at runtime by the
+/// [`RefCell`], exactly as in single threaded code.
+pub struct ReentrantRefCell<T: ?Sized> {
+    inner: ReentrantMutex<RefCell<T>>,
+}
+
+impl<T> ReentrantRefCell<T> {
+    /// Create a new, uninitialized re-entrant cell.
+    ///
+    /// This is *NOT* equivalent to `MaybeUninit::uninit().assume_init()`, which will cause
+    /// undefined behaviour if used to create a new re-entrant cell.
+    #[inline]
+    pub const fn uninit(value: T) -> Self {
+        Self {
+            inner: ReentrantMutex::uninit(RefCell::new(value)),
+        }
+    }
+
+    /// Create a new, initialized re-entrant cell.
+    ///
+    /// The resulting re-entrant cell is wrapped and ready for use.
+    #[inline]
+    pub fn boxed(value: T) -> Pin<Box<Self>> {
+        let this = Box::pin(Self::uninit(value));
+        this.as_ref().init();
+        this
+    }
+
+    /// Create a new, initialized re-entrant cell.
+    ///
+    /// The resulting re-entrant cell is wrapped and ready for use.
+    #[inline]
+    pub fn arc(value: T) -> Pin<Arc<Self>> {
+        let this = Arc::pin(Self::uninit(value));
+        this.as_ref().init();
+        this
+    }
+
+    /// Consumes this re-entrant cell, returning the underlying data.
+    pub fn into_inner(self) -> T {
+        self.inner.into_inner().into_inner()
+    }
+}
+
+impl<T: ?Sized> ReentrantRefCell<T> {
+    /// Initialize a re-entrant cell, making it ready for use.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the re-entrant cell was already initialized.
+    #[inline]
+    pub fn init(self: Pin<&Self>) {
+        self.inner().init()
+    }
+
+    /// Acquires the lock, blocking the current thread until it is able to do
+    /// so.
+    ///
+    /// See [`ReentrantMutex::lock`].
+    #[inline]
+    pub fn lock(self: Pin<&Self>) -> ReentrantRefCellGuard<'_, T> {
+        ReentrantRefCellGuard {
+            guard: self.inner().lock(),
+        }
+    }
+
+    /// Attempts to acquire the lock.
+    ///
+    /// See [`ReentrantMutex::try_lock`].
+    #[inline]
+    pub fn try_lock(self: Pin<&Self>) -> Option<ReentrantRefCellGuard<'_, T>> {
+        Some(ReentrantRefCellGuard {
+            guard: self.inner().try_lock()?,
+        })
+    }
+
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// Since this call borrows the `ReentrantRefCell` mutably, no actual
+    /// locking needs to take place -- the mutable borrow statically guarantees
+    /// no locks exist.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.inner.get_mut().get_mut()
+    }
+
+    #[inline]
+    fn inner(self: Pin<&Self>) -> Pin<&ReentrantMutex<RefCell<T>>> {
+        unsafe { self.map_unchecked(|this| &this.inner) }
+    }
+}
+
+/// An RAII guard of a [`ReentrantRefCell`].
+///
+/// The data can be borrowed through [`borrow`] and [`borrow_mut`], which
+/// follow the rules of [`RefCell`].
+///
+/// [`borrow`]: Self::borrow
+/// [`borrow_mut`]: Self::borrow_mut
+pub struct ReentrantRefCellGuard<'a, T: ?Sized> {
+    guard: ReentrantMutexGuard<'a, RefCell<T>>,
+}
+
+impl<T: ?Sized> ReentrantRefCellGuard<'_, T> {
+    /// Immutably borrows the data.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the data is currently mutably borrowed, possibly through
+    /// another guard held by the same thread.
+    #[inline]
+    pub fn borrow(&self) -> Ref<'_, T> {
+        self.guard.borrow()
+    }
+
+    /// Mutably borrows the data.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the data is currently borrowed, possibly through another
+    /// guard held by the same thread.
+    #[inline]
+    pub fn borrow_mut(&self) -> RefMut<'_, T> {
+        self.guard.borrow_mut()
+    }
+
+    /// Immutably borrows the data, returning an error if the data is currently
+    /// mutably borrowed.
+    #[inline]
+    pub fn try_borrow(&self) -> Result<Ref<'_, T>, BorrowError> {
+        self.guard.try_borrow()
+    }
+
+    /// Mutably borrows the data, returning an error if the data is currently
+    /// borrowed.
+    #[inline]
+    pub fn try_borrow_mut(&self) -> Result<RefMut<'_, T>, BorrowMutError> {
+        self.guard.try_borrow_mut()
+    }
+}
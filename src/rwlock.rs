@@ -1,14 +1,23 @@
-use crate::sys::rwlock as sys;
-use crate::sys_common::poison;
+use crate::sys::mutex as sys_mutex;
+use crate::sys::ReadError;
+use crate::raw::{self, RawRwLock};
+use crate::sys_common::marker::GuardMarker;
+use crate::sys_common::wait_queue::WaitQueue;
+use crate::sys_common::{elision, held, poison, take, trace};
+use crate::pod::{self, Pod};
+use crate::{LockError, LockId, LockResult, TryLockError, TryLockResult};
 use std::cell::UnsafeCell;
-use std::marker::PhantomPinned;
+use std::fmt;
+use std::marker::{PhantomData, PhantomPinned};
+use std::mem::{self, ManuallyDrop};
 use std::ops::Deref;
 use std::ops::DerefMut;
+use std::panic::{RefUnwindSafe, UnwindSafe};
 use std::pin::Pin;
+use std::ptr;
+use std::sync::atomic::{fence, AtomicBool, AtomicUsize, Ordering::*};
 use std::sync::Arc;
-use std::sync::LockResult;
-use std::sync::TryLockError;
-use std::sync::TryLockResult;
+use std::time::{Duration, Instant};
 
 /// A reader-writer lock
 ///
@@ -24,7 +33,8 @@ use std::sync::TryLockResult;
 ///
 /// The priority policy of the lock is dependent on the underlying operating
 /// system's implementation, and this type does not guarantee that any
-/// particular policy will be used.
+/// particular policy will be used, unless a [`WriterPolicy`] is set with
+/// [`writer_policy`].
 ///
 /// The type parameter `T` represents the data that this lock protects. It is
 /// required that `T` satisfies [`Send`] to be shared across threads and
@@ -38,16 +48,70 @@ use std::sync::TryLockResult;
 /// that an `RwLock` may only be poisoned if a panic occurs while it is locked
 /// exclusively (write mode). If a panic occurs in any reader, then the lock
 /// will not be poisoned.
-pub struct RwLock<T: ?Sized> {
-    inner: sys::RwLock,
+///
+/// # Upgradable reads
+///
+/// Only one thread at a time may hold an [upgradable read], which is enforced
+/// with a platform mutex besides the lock itself. Writers hold that mutex as
+/// well, whether or not upgradable reads are ever used, because a write guard
+/// can be downgraded to an upgradable read guard with
+/// [`RwLockWriteGuard::downgrade_to_upgradable`] without letting another
+/// upgradable reader in. So every write lock also locks and unlocks this
+/// mutex, which makes writing somewhat more expensive than with the backend
+/// alone, and makes writers queue on the mutex before the lock.
+///
+/// # Exclusive access
+///
+/// Once an `RwLock` is pinned, it can not be moved out of its pointer, so
+/// [`get_mut`] and [`into_inner`] are only usable before it is pinned. A
+/// pinned `RwLock` can be accessed exclusively with [`get_pin_mut`], as the
+/// data is not structurally pinned, and consumed with [`into_inner_boxed`] or
+/// [`try_into_inner_arc`]. Those move the data out and drop the lock itself
+/// in place, so the lock is never moved after it was pinned.
+///
+/// # Backends
+///
+/// The lock itself is provided by the backend `B`, which defaults to the
+/// [`raw::RwLock`] of the platform. Read-write locks with another backend are
+/// created with [`with_backend`], and otherwise behave the same. The writer
+/// policies and upgradable reads are built on platform mutexes whichever the
+/// backend is.
+///
+/// [`writer_policy`]: Self::writer_policy
+/// [`with_backend`]: Self::with_backend
+/// [upgradable read]: Self::upgradable_read
+/// [`get_mut`]: Self::get_mut
+/// [`into_inner`]: Self::into_inner
+/// [`get_pin_mut`]: Self::get_pin_mut
+/// [`into_inner_boxed`]: Self::into_inner_boxed
+/// [`try_into_inner_arc`]: Self::try_into_inner_arc
+pub struct RwLock<T: ?Sized, B: RawRwLock = raw::RwLock> {
+    inner: B,
     poison: poison::Flag,
+    overflow: Overflow,
+    policy: WriterPolicy,
+    // Only initialized and used with `WriterPolicy::Preferred`. A waiting
+    // writer holds it, keeping new readers out until the writer is admitted.
+    turnstile: sys_mutex::Mutex,
+    // Held by writers and by the upgradable reader, so that no writer can
+    // acquire the lock while an upgradable reader upgrades it.
+    upgrade: sys_mutex::Mutex,
+    // Incremented when a writer acquires and when it releases the lock, so it
+    // is odd while the lock is held for writing. Used by optimistic reads.
+    version: AtomicUsize,
+    // Set once by `freeze`, after which the data is never written again.
+    frozen: AtomicBool,
     _p: PhantomPinned,
     data: UnsafeCell<T>,
 }
 
-unsafe impl<T: ?Sized + Send> Send for RwLock<T> {}
+unsafe impl<T: ?Sized + Send, B: RawRwLock> Send for RwLock<T, B> {}
+
+unsafe impl<T: ?Sized + Send + Sync, B: RawRwLock> Sync for RwLock<T, B> {}
 
-unsafe impl<T: ?Sized + Send + Sync> Sync for RwLock<T> {}
+impl<T: ?Sized, B: RawRwLock> UnwindSafe for RwLock<T, B> {}
+
+impl<T: ?Sized, B: RawRwLock> RefUnwindSafe for RwLock<T, B> {}
 
 impl<T> RwLock<T> {
     /// Create a new, uninitialized read-write lock.
@@ -56,11 +120,35 @@ impl<T> RwLock<T> {
     /// undefined behaviour if used to create a new read-write lock.
     #[inline]
     pub const fn uninit(value: T) -> Self {
-        Self {
-            inner: sys::RwLock::uninit(),
-            _p: PhantomPinned,
-            poison: poison::Flag::new(),
-            data: UnsafeCell::new(value),
+        Self::with_backend(value)
+    }
+
+    cfg_static_new! {
+        /// Create a new, initialized read-write lock, in a `const` context.
+        ///
+        /// Unlike one created with [`uninit`](Self::uninit), the read-write
+        /// lock is ready for use as soon as it is pinned, and must not be
+        /// initialized with [`init`](Self::init). This makes it possible to
+        /// declare a read-write lock as a `static` without initializing it at
+        /// runtime.
+        ///
+        /// This is only available on the backends whose primitives are complete
+        /// when constructed, which are all of them except for the generic
+        /// pthread backend and ESP-IDF.
+        #[inline]
+        pub const fn new(value: T) -> Self {
+            Self {
+                inner: raw::RwLock::new(),
+                _p: PhantomPinned,
+                poison: poison::Flag::new(),
+                overflow: Overflow::new(ReaderOverflow::Panic),
+                policy: WriterPolicy::Native,
+                turnstile: sys_mutex::Mutex::new(),
+                upgrade: sys_mutex::Mutex::new(),
+                version: AtomicUsize::new(0),
+                frozen: AtomicBool::new(false),
+                data: UnsafeCell::new(value),
+            }
         }
     }
 
@@ -83,7 +171,85 @@ impl<T> RwLock<T> {
     }
 }
 
-impl<T: ?Sized> RwLock<T> {
+impl<T, B: RawRwLock> RwLock<T, B> {
+    /// Create a new, uninitialized read-write lock using the backend `B`.
+    ///
+    /// This is *NOT* equivalent to `MaybeUninit::uninit().assume_init()`, which will cause
+    /// undefined behaviour if used to create a new read-write lock.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pinned_sync::{raw, RwLock};
+    ///
+    /// let lock = Box::pin(RwLock::<_, raw::RwLock>::with_backend(0));
+    /// lock.as_ref().init();
+    /// *lock.as_ref().write().unwrap() += 1;
+    /// ```
+    #[inline]
+    pub const fn with_backend(value: T) -> Self {
+        Self {
+            inner: B::UNINIT,
+            _p: PhantomPinned,
+            poison: poison::Flag::new(),
+            overflow: Overflow::new(ReaderOverflow::Panic),
+            policy: WriterPolicy::Native,
+            turnstile: sys_mutex::Mutex::uninit(),
+            upgrade: sys_mutex::Mutex::uninit(),
+            version: AtomicUsize::new(0),
+            frozen: AtomicBool::new(false),
+            data: UnsafeCell::new(value),
+        }
+    }
+
+    /// Enables or disables poisoning for this read-write lock.
+    ///
+    /// Poisoning is enabled by default. When it is disabled, a panic while the
+    /// read-write lock is held does not poison it, and locking always returns the guard.
+    /// This skips the bookkeeping needed for poisoning, which may be useful
+    /// for hot locks in code bases that treat a panic while a lock is held as
+    /// fatal anyway.
+    ///
+    /// This must be called before the read-write lock is pinned.
+    #[inline]
+    pub const fn poisoning(mut self, enabled: bool) -> Self {
+        self.poison = if enabled {
+            poison::Flag::new()
+        } else {
+            poison::Flag::disabled()
+        };
+        self
+    }
+
+    /// Sets what [`read`] does when the maximum number of concurrent readers
+    /// supported by the platform is reached.
+    ///
+    /// The default is [`ReaderOverflow::Panic`]. [`read_checked`] returns an
+    /// error instead, whatever the policy.
+    ///
+    /// This must be called before the read-write lock is pinned.
+    ///
+    /// [`read`]: Self::read
+    /// [`read_checked`]: RwLock::read_checked
+    #[inline]
+    pub fn reader_overflow(mut self, policy: ReaderOverflow) -> Self {
+        self.overflow = Overflow::new(policy);
+        self
+    }
+
+    /// Sets the policy used to order waiting readers and writers.
+    ///
+    /// The default is [`WriterPolicy::Native`].
+    ///
+    /// This must be called before the read-write lock is pinned.
+    #[inline]
+    pub fn writer_policy(mut self, policy: WriterPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+}
+
+impl<T: ?Sized, B: RawRwLock> RwLock<T, B> {
     /// Initialize a read-write lock, making it ready for use.
     ///
     /// # Panics
@@ -91,7 +257,11 @@ impl<T: ?Sized> RwLock<T> {
     /// This function may panic if the read-write lock was already initialized.
     #[inline]
     pub fn init(self: Pin<&Self>) {
-        self.inner().init()
+        self.inner().init();
+        self.upgrade().init();
+        if self.policy == WriterPolicy::Preferred {
+            self.turnstile().init();
+        }
     }
 
     /// Locks this rwlock with shared read access, blocking the current thread
@@ -115,17 +285,86 @@ impl<T: ?Sized> RwLock<T> {
     /// # Panics
     ///
     /// This function might panic when called if the lock is already held by the current thread.
+    /// With the `debug-rwlock` feature, it always panics if the current thread
+    /// holds a write lock on it.
+    ///
+    /// This function panics if the maximum number of readers is reached, unless
+    /// the lock was configured otherwise with [`reader_overflow`].
     ///
     /// This function may panic if the lock is not initialized.
+    ///
+    /// [`reader_overflow`]: Self::reader_overflow
     #[inline]
-    pub fn read(self: Pin<&Self>) -> LockResult<RwLockReadGuard<T>> {
-        let guard = self.inner().read();
+    pub fn read(self: Pin<&Self>) -> LockResult<RwLockReadGuard<'_, T, B>> {
+        held::check_read(self.id());
+        let wait = trace::Wait::start();
+        // Writers change `version` once they acquire the lock, so that aborts
+        // elided readers.
+        let guard = if self.frozen.load(Acquire) {
+            ReadAcquired::Frozen
+        } else if elision::elide(|| self.version.load(Relaxed) & 1 == 0) {
+            ReadAcquired::Elided
+        } else {
+            ReadAcquired::Real(self.read_real())
+        };
+        let trace = wait.acquired(self.id(), "RwLock::read");
         poison::map_result(self.poison.borrow(), |_| RwLockReadGuard {
             _guard: guard,
             lock: self,
+            _held: held::Held::new(self.id(), false),
+            _trace: trace,
+            _marker: PhantomData,
         })
     }
 
+    /// Locks this rwlock with shared read access, blocking the current thread
+    /// until it can be acquired, unless the maximum number of readers is
+    /// reached.
+    ///
+    /// This is the same as [`read`], except that reaching the maximum number
+    /// of readers is returned as an error instead of being handled with the
+    /// policy set with [`reader_overflow`].
+    ///
+    /// # Errors
+    ///
+    /// If the maximum number of readers is reached, [`TooManyReaders`] is
+    /// returned, and the lock is not held. If the RwLock is poisoned, an error
+    /// is returned once the lock is acquired.
+    ///
+    /// # Panics
+    ///
+    /// This function might panic when called if the lock is already held by the current thread.
+    /// With the `debug-rwlock` feature, it always panics if the current thread
+    /// holds a write lock on it.
+    ///
+    /// This function may panic if the lock is not initialized.
+    ///
+    /// [`read`]: Self::read
+    /// [`reader_overflow`]: Self::reader_overflow
+    /// [`TooManyReaders`]: LockError::TooManyReaders
+    pub fn read_checked(
+        self: Pin<&Self>,
+    ) -> Result<RwLockReadGuard<'_, T, B>, LockError<RwLockReadGuard<'_, T, B>>> {
+        held::check_read(self.id());
+        let wait = trace::Wait::start();
+        let guard = if self.frozen.load(Acquire) {
+            ReadAcquired::Frozen
+        } else {
+            let guard = self.try_read_real_checked();
+            ReadAcquired::Real(guard.ok_or(LockError::TooManyReaders)?)
+        };
+        let trace = wait.acquired(self.id(), "RwLock::read");
+        Ok(poison::map_result(self.poison.borrow(), |_| {
+            RwLockReadGuard {
+                _guard: guard,
+                lock: self,
+                _held: held::Held::new(self.id(), false),
+                _trace: trace,
+                _marker: PhantomData,
+            }
+        })?)
+    }
+
     /// Attempts to acquire this rwlock with shared read access.
     ///
     /// If the access could not be granted at this time, then `Err` is returned.
@@ -144,16 +383,30 @@ impl<T: ?Sized> RwLock<T> {
     /// error will only be returned if the lock would have otherwise been
     /// acquired.
     ///
+    /// If the maximum number of readers is reached, [`TooManyReaders`] is
+    /// returned, regardless of the policy set with [`reader_overflow`].
+    ///
     /// # Panics
     ///
     /// This function may panic if the lock is not initialized.
+    ///
+    /// [`TooManyReaders`]: TryLockError::TooManyReaders
+    /// [`reader_overflow`]: Self::reader_overflow
     #[inline]
-    pub fn try_read(self: Pin<&Self>) -> TryLockResult<RwLockReadGuard<T>> {
-        let guard = self.inner().try_read().ok_or(TryLockError::WouldBlock)?;
+    pub fn try_read(self: Pin<&Self>) -> TryLockResult<RwLockReadGuard<'_, T, B>> {
+        let guard = if self.frozen.load(Acquire) {
+            ReadAcquired::Frozen
+        } else {
+            ReadAcquired::Real(self.try_read_real().map_err(read_error)?)
+        };
+        let trace = trace::Hold::start(self.id(), "RwLock::read");
         Ok(poison::map_result(self.poison.borrow(), |_| {
             RwLockReadGuard {
                 _guard: guard,
                 lock: self,
+                _held: held::Held::new(self.id(), false),
+                _trace: trace,
+                _marker: PhantomData,
             }
         })?)
     }
@@ -176,15 +429,34 @@ impl<T: ?Sized> RwLock<T> {
     /// # Panics
     ///
     /// This function might panic when called if the lock is already held by the current thread.
+    /// With the `debug-rwlock` feature, it always panics if the current thread
+    /// holds a read or write lock on it.
+    ///
+    /// This function panics if the lock is [frozen].
     ///
     /// This function may panic if the lock is not initialized.
+    ///
+    /// [frozen]: Self::freeze
     #[inline]
-    pub fn write(self: Pin<&Self>) -> LockResult<RwLockWriteGuard<T>> {
-        let guard = self.inner().write();
+    pub fn write(self: Pin<&Self>) -> LockResult<RwLockWriteGuard<'_, T, B>> {
+        held::check_write(self.id());
+        let wait = trace::Wait::start();
+        let upgrade = self.upgrade().lock();
+        let guard = self.write_real();
+        if self.frozen.load(Relaxed) {
+            drop(guard);
+            panic!("rwlock write lock on a frozen rwlock (rwlock {:p})", self.id());
+        }
+        self.begin_write();
+        let trace = wait.acquired(self.id(), "RwLock::write");
         poison::map_result(self.poison.borrow(), |poison| RwLockWriteGuard {
             _guard: guard,
+            _upgrade: upgrade,
             lock: self,
             poison,
+            _held: held::Held::new(self.id(), true),
+            _trace: trace,
+            _marker: PhantomData,
         })
     }
 
@@ -206,112 +478,1518 @@ impl<T: ?Sized> RwLock<T> {
     /// error will only be returned if the lock would have otherwise been
     /// acquired.
     ///
+    /// If the lock is [frozen], [`WouldBlock`] is returned.
+    ///
     /// # Panics
     ///
     /// This function may panic if the lock is not initialized.
+    ///
+    /// [frozen]: Self::freeze
+    /// [`WouldBlock`]: TryLockError::WouldBlock
     #[inline]
-    pub fn try_write(self: Pin<&Self>) -> TryLockResult<RwLockWriteGuard<T>> {
+    pub fn try_write(self: Pin<&Self>) -> TryLockResult<RwLockWriteGuard<'_, T, B>> {
+        if self.frozen.load(Relaxed) {
+            return Err(TryLockError::WouldBlock);
+        }
+        let upgrade = self.upgrade().try_lock().ok_or(TryLockError::WouldBlock)?;
         let guard = self.inner().try_write().ok_or(TryLockError::WouldBlock)?;
+        // The lock may have been frozen by the last writer.
+        if self.frozen.load(Relaxed) {
+            return Err(TryLockError::WouldBlock);
+        }
+        self.begin_write();
+        let trace = trace::Hold::start(self.id(), "RwLock::write");
         Ok(poison::map_result(self.poison.borrow(), |poison| {
             RwLockWriteGuard {
                 _guard: guard,
+                _upgrade: upgrade,
                 lock: self,
                 poison,
+                _held: held::Held::new(self.id(), true),
+                _trace: trace,
+                _marker: PhantomData,
             }
         })?)
     }
 
-    /// Determines whether the read-write lock is poisoned.
+    /// Attempts to acquire this rwlock with shared read access, blocking the
+    /// current thread until it can be acquired or the timeout expires.
     ///
-    /// If another thread is active, the read-write lock can still become poisoned at any
-    /// time. You should not trust a `false` value for program correctness
-    /// without additional synchronization.
+    /// If the access could not be granted in time, then `Err` is returned.
+    /// Otherwise, an RAII guard is returned which will release the shared access
+    /// when it is dropped.
+    ///
+    /// # Errors
+    ///
+    /// If the timeout expired, [`WouldBlock`] is returned.
+    ///
+    /// This function will return an error if the RwLock is poisoned. An RwLock
+    /// is poisoned whenever a writer panics while holding an exclusive lock. An
+    /// error will only be returned if the lock would have otherwise been
+    /// acquired.
+    ///
+    /// If the maximum number of readers is reached, [`TooManyReaders`] is
+    /// returned, regardless of the policy set with [`reader_overflow`].
+    ///
+    /// # Panics
+    ///
+    /// This function might panic when called if the lock is already held by the current thread.
+    /// With the `debug-rwlock` feature, it always panics if the current thread
+    /// holds a write lock on it.
+    ///
+    /// This function may panic if the lock is not initialized.
+    ///
+    /// [`WouldBlock`]: TryLockError::WouldBlock
+    /// [`TooManyReaders`]: TryLockError::TooManyReaders
+    /// [`reader_overflow`]: Self::reader_overflow
     #[inline]
-    pub fn is_poisoned(self: Pin<&Self>) -> bool {
-        self.poison.get()
+    pub fn try_read_for(
+        self: Pin<&Self>,
+        dur: Duration,
+    ) -> TryLockResult<RwLockReadGuard<'_, T, B>> {
+        match Instant::now().checked_add(dur) {
+            Some(deadline) => self.try_read_until(deadline),
+            // A timeout which can not be represented is as good as no timeout.
+            None => Ok(self.read()?),
+        }
     }
 
-    /// Consumes this read-write lock, returning the underlying data.
+    /// Attempts to acquire this rwlock with shared read access, blocking the
+    /// current thread until it can be acquired or the deadline is reached.
+    ///
+    /// If the access could not be granted in time, then `Err` is returned.
+    /// Otherwise, an RAII guard is returned which will release the shared access
+    /// when it is dropped.
+    ///
+    /// The lock is waited on with a timeout where the platform can, such as
+    /// with a futex. Elsewhere, such as on Windows and macOS, it is polled,
+    /// and may be noticed to be released a little late.
     ///
     /// # Errors
     ///
-    /// If another user of this read-write lock panicked while holding the
-    /// read-write lock, then this call will return an error instead.
-    pub fn into_inner(self) -> LockResult<T>
-    where
-        T: Sized,
-    {
-        let Self { data, poison, .. } = self;
-        poison::map_result(poison.borrow(), |_| data.into_inner())
+    /// If the deadline was reached, [`WouldBlock`] is returned.
+    ///
+    /// This function will return an error if the RwLock is poisoned. An RwLock
+    /// is poisoned whenever a writer panics while holding an exclusive lock. An
+    /// error will only be returned if the lock would have otherwise been
+    /// acquired.
+    ///
+    /// If the maximum number of readers is reached, [`TooManyReaders`] is
+    /// returned, regardless of the policy set with [`reader_overflow`].
+    ///
+    /// # Panics
+    ///
+    /// This function might panic when called if the lock is already held by the current thread.
+    /// With the `debug-rwlock` feature, it always panics if the current thread
+    /// holds a write lock on it.
+    ///
+    /// This function may panic if the lock is not initialized.
+    ///
+    /// [`WouldBlock`]: TryLockError::WouldBlock
+    /// [`TooManyReaders`]: TryLockError::TooManyReaders
+    /// [`reader_overflow`]: Self::reader_overflow
+    pub fn try_read_until(
+        self: Pin<&Self>,
+        deadline: Instant,
+    ) -> TryLockResult<RwLockReadGuard<'_, T, B>> {
+        held::check_read(self.id());
+        let wait = trace::Wait::start();
+        let guard = if self.frozen.load(Acquire) {
+            ReadAcquired::Frozen
+        } else {
+            ReadAcquired::Real(self.read_real_until(deadline).map_err(read_error)?)
+        };
+        let trace = wait.acquired(self.id(), "RwLock::read");
+        Ok(poison::map_result(self.poison.borrow(), |_| {
+            RwLockReadGuard {
+                _guard: guard,
+                lock: self,
+                _held: held::Held::new(self.id(), false),
+                _trace: trace,
+                _marker: PhantomData,
+            }
+        })?)
     }
 
-    /// Returns a mutable reference to the underlying data.
+    /// Attempts to lock this rwlock with exclusive write access, blocking the
+    /// current thread until it can be acquired or the timeout expires.
     ///
-    /// Since this call borrows the `RwLock` mutably, no actual locking needs to
-    /// take place -- the mutable borrow statically guarantees no locks exist.
+    /// If the lock could not be acquired in time, then `Err` is returned.
+    /// Otherwise, an RAII guard is returned which will release the lock when
+    /// it is dropped.
     ///
     /// # Errors
     ///
-    /// If another user of this read-write lock panicked while holding the read-write lock, then
-    /// this call will return an error instead.
-    pub fn get_mut(&mut self) -> LockResult<&mut T> {
-        let data = self.data.get_mut();
-        poison::map_result(self.poison.borrow(), |_| data)
-    }
-
-    #[inline]
-    fn inner(self: Pin<&Self>) -> Pin<&sys::RwLock> {
-        unsafe { self.map_unchecked(|this| &this.inner) }
-    }
-}
-
-pub struct RwLockReadGuard<'a, T: ?Sized> {
-    // This is suboptimal but necessary for `fallback` as `sync::Mutex` does not provide raw
-    // unlocking.
-    _guard: sys::ReadGuard<'a>,
-    lock: Pin<&'a RwLock<T>>,
-}
-
-unsafe impl<T: ?Sized + Sync> Sync for RwLockReadGuard<'_, T> {}
-
-impl<T: ?Sized> Deref for RwLockReadGuard<'_, T> {
-    type Target = T;
-
+    /// If the timeout expired, or if the lock is [frozen], [`WouldBlock`] is
+    /// returned.
+    ///
+    /// This function will return an error if the RwLock is poisoned. An RwLock
+    /// is poisoned whenever a writer panics while holding an exclusive lock. An
+    /// error will only be returned if the lock would have otherwise been
+    /// acquired.
+    ///
+    /// # Panics
+    ///
+    /// This function might panic when called if the lock is already held by the current thread.
+    /// With the `debug-rwlock` feature, it always panics if the current thread
+    /// holds a read or write lock on it.
+    ///
+    /// This function may panic if the lock is not initialized.
+    ///
+    /// [frozen]: Self::freeze
+    /// [`WouldBlock`]: TryLockError::WouldBlock
     #[inline]
-    fn deref(&self) -> &T {
-        unsafe { &*self.lock.data.get() }
+    pub fn try_write_for(
+        self: Pin<&Self>,
+        dur: Duration,
+    ) -> TryLockResult<RwLockWriteGuard<'_, T, B>> {
+        match Instant::now().checked_add(dur) {
+            Some(deadline) => self.try_write_until(deadline),
+            // A timeout which can not be represented is as good as no timeout,
+            // except that a frozen lock is never waited for.
+            None if self.frozen.load(Relaxed) => Err(TryLockError::WouldBlock),
+            None => Ok(self.write()?),
+        }
     }
-}
-
-pub struct RwLockWriteGuard<'a, T: ?Sized> {
-    // This is suboptimal but necessary for `fallback` as `sync::Mutex` does not provide raw
-    // unlocking.
-    _guard: sys::WriteGuard<'a>,
-    lock: Pin<&'a RwLock<T>>,
-    poison: poison::Guard,
-}
-
-unsafe impl<T: ?Sized + Sync> Sync for RwLockWriteGuard<'_, T> {}
-
-impl<T: ?Sized> Deref for RwLockWriteGuard<'_, T> {
-    type Target = T;
 
-    #[inline]
-    fn deref(&self) -> &T {
-        unsafe { &*self.lock.data.get() }
+    /// Attempts to lock this rwlock with exclusive write access, blocking the
+    /// current thread until it can be acquired or the deadline is reached.
+    ///
+    /// If the lock could not be acquired in time, then `Err` is returned.
+    /// Otherwise, an RAII guard is returned which will release the lock when
+    /// it is dropped.
+    ///
+    /// The lock is waited on with a timeout where the platform can, such as
+    /// with a futex. Elsewhere, such as on Windows and macOS, it is polled,
+    /// and may be noticed to be released a little late.
+    ///
+    /// # Errors
+    ///
+    /// If the deadline was reached, or if the lock is [frozen], [`WouldBlock`] is
+    /// returned.
+    ///
+    /// This function will return an error if the RwLock is poisoned. An RwLock
+    /// is poisoned whenever a writer panics while holding an exclusive lock. An
+    /// error will only be returned if the lock would have otherwise been
+    /// acquired.
+    ///
+    /// # Panics
+    ///
+    /// This function might panic when called if the lock is already held by the current thread.
+    /// With the `debug-rwlock` feature, it always panics if the current thread
+    /// holds a read or write lock on it.
+    ///
+    /// This function may panic if the lock is not initialized.
+    ///
+    /// [frozen]: Self::freeze
+    /// [`WouldBlock`]: TryLockError::WouldBlock
+    pub fn try_write_until(
+        self: Pin<&Self>,
+        deadline: Instant,
+    ) -> TryLockResult<RwLockWriteGuard<'_, T, B>> {
+        if self.frozen.load(Relaxed) {
+            return Err(TryLockError::WouldBlock);
+        }
+        held::check_write(self.id());
+        let wait = trace::Wait::start();
+        let upgrade = self
+            .upgrade()
+            .try_lock_until(deadline)
+            .ok_or(TryLockError::WouldBlock)?;
+        let guard = self
+            .write_real_until(deadline)
+            .ok_or(TryLockError::WouldBlock)?;
+        // The lock may have been frozen by the last writer.
+        if self.frozen.load(Relaxed) {
+            return Err(TryLockError::WouldBlock);
+        }
+        self.begin_write();
+        let trace = wait.acquired(self.id(), "RwLock::write");
+        Ok(poison::map_result(self.poison.borrow(), |poison| {
+            RwLockWriteGuard {
+                _guard: guard,
+                _upgrade: upgrade,
+                lock: self,
+                poison,
+                _held: held::Held::new(self.id(), true),
+                _trace: trace,
+                _marker: PhantomData,
+            }
+        })?)
     }
-}
 
-impl<T: ?Sized> DerefMut for RwLockWriteGuard<'_, T> {
-    #[inline]
-    fn deref_mut(&mut self) -> &mut T {
-        unsafe { &mut *self.lock.data.get() }
+    /// Locks this rwlock with upgradable read access, blocking the current
+    /// thread until it can be acquired.
+    ///
+    /// An upgradable read guard gives shared access like a read guard, and
+    /// can be turned into a write guard with
+    /// [`RwLockUpgradableReadGuard::upgrade`], without letting any other
+    /// writer in. This makes check-then-modify patterns race free, without
+    /// holding the write lock while checking.
+    ///
+    /// There may be other readers inside the lock when this method returns,
+    /// but no other upgradable reader or writer: only one thread at a time may
+    /// hold an upgradable read guard.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the RwLock is poisoned. An RwLock
+    /// is poisoned whenever a writer panics while holding an exclusive lock.
+    /// The failure will occur immediately after the lock has been acquired.
+    ///
+    /// # Panics
+    ///
+    /// This function might panic when called if the lock is already held by the current thread.
+    /// With the `debug-rwlock` feature, it always panics if the current thread
+    /// holds a write lock on it.
+    ///
+    /// This function panics if the maximum number of readers is reached, unless
+    /// the lock was configured otherwise with [`reader_overflow`].
+    ///
+    /// This function may panic if the lock is not initialized.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pinned_sync::{RwLock, RwLockUpgradableReadGuard};
+    ///
+    /// let cache = RwLock::boxed(None);
+    ///
+    /// let guard = cache.as_ref().upgradable_read().unwrap();
+    /// if guard.is_none() {
+    ///     // No other writer can fill the cache in the meantime.
+    ///     let mut guard = RwLockUpgradableReadGuard::upgrade(guard);
+    ///     *guard = Some(42);
+    /// }
+    /// assert_eq!(*cache.as_ref().read().unwrap(), Some(42));
+    /// ```
+    ///
+    /// [`reader_overflow`]: Self::reader_overflow
+    pub fn upgradable_read(self: Pin<&Self>) -> LockResult<RwLockUpgradableReadGuard<'_, T, B>> {
+        held::check_read(self.id());
+        let wait = trace::Wait::start();
+        let upgrade = self.upgrade().lock();
+        let guard = self.read_real();
+        let trace = wait.acquired(self.id(), "RwLock::upgradable_read");
+        poison::map_result(self.poison.borrow(), |_| RwLockUpgradableReadGuard {
+            _guard: guard,
+            _upgrade: upgrade,
+            lock: self,
+            _held: held::Held::new(self.id(), false),
+            _trace: trace,
+            _marker: PhantomData,
+        })
     }
-}
 
-impl<T: ?Sized> Drop for RwLockWriteGuard<'_, T> {
-    #[inline]
-    fn drop(&mut self) {
-        self.lock.poison.done(&self.poison);
+    /// Attempts to acquire this rwlock with upgradable read access.
+    ///
+    /// If the access could not be granted at this time, then `Err` is returned.
+    /// Otherwise, an RAII guard is returned which will release the access when
+    /// it is dropped.
+    ///
+    /// This function does not block.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the RwLock is poisoned. An RwLock
+    /// is poisoned whenever a writer panics while holding an exclusive lock. An
+    /// error will only be returned if the lock would have otherwise been
+    /// acquired.
+    ///
+    /// If the maximum number of readers is reached, [`TooManyReaders`] is
+    /// returned.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the lock is not initialized.
+    ///
+    /// [`TooManyReaders`]: TryLockError::TooManyReaders
+    pub fn try_upgradable_read(
+        self: Pin<&Self>,
+    ) -> TryLockResult<RwLockUpgradableReadGuard<'_, T, B>> {
+        let upgrade = self.upgrade().try_lock().ok_or(TryLockError::WouldBlock)?;
+        let guard = self.try_read_real().map_err(read_error)?;
+        let trace = trace::Hold::start(self.id(), "RwLock::upgradable_read");
+        Ok(poison::map_result(self.poison.borrow(), |_| {
+            RwLockUpgradableReadGuard {
+                _guard: guard,
+                _upgrade: upgrade,
+                lock: self,
+                _held: held::Held::new(self.id(), false),
+                _trace: trace,
+                _marker: PhantomData,
+            }
+        })?)
+    }
+
+    /// Attempts to read a copy of the data without acquiring the lock.
+    ///
+    /// The data is copied while checking that no writer holds the lock, in the
+    /// style of a seqlock. This does not write to any shared memory, so
+    /// concurrent optimistic readers do not contend with each other.
+    ///
+    /// Returns [`None`] if a writer held the lock during the attempt, in which
+    /// case the copy may have been torn. Because of that, the data must be
+    /// [`Pod`], so that a torn copy is still a valid value.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the RwLock is poisoned. An RwLock
+    /// is poisoned whenever a writer panics while holding an exclusive lock. An
+    /// error will only be returned if the copy would have otherwise succeeded.
+    #[inline]
+    pub fn try_read_optimistic(self: Pin<&Self>) -> Option<LockResult<T>>
+    where
+        T: Pod,
+    {
+        let before = self.version.load(Acquire);
+        if before % 2 == 1 {
+            return None;
+        }
+        // The copy may be concurrent with a writer, which is detected below.
+        // It is made with atomic loads, and `T: Pod` makes a torn copy valid.
+        let copy = unsafe { pod::load(self.data.get()) };
+        fence(Acquire);
+        if self.version.load(Relaxed) != before {
+            return None;
+        }
+        Some(poison::map_result(self.poison.borrow(), |_| copy))
+    }
+
+    /// Reads the data optimistically, falling back to a read lock.
+    ///
+    /// This calls `f` with a copy of the data made as in
+    /// [`try_read_optimistic`]. If a writer held the lock during the copy, the
+    /// lock is acquired with [`read`] instead and `f` is called with the
+    /// protected data. Either way, `f` is called exactly once.
+    ///
+    /// This is intended for tiny read sections, where the writes to the lock
+    /// made by a read lock would dominate the cost.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the RwLock is poisoned. An RwLock
+    /// is poisoned whenever a writer panics while holding an exclusive lock.
+    ///
+    /// # Panics
+    ///
+    /// See [`read`].
+    ///
+    /// [`try_read_optimistic`]: Self::try_read_optimistic
+    /// [`read`]: Self::read
+    pub fn read_optimistic<R, F>(self: Pin<&Self>, f: F) -> LockResult<R>
+    where
+        T: Pod,
+        F: FnOnce(&T) -> R,
+    {
+        match self.try_read_optimistic() {
+            Some(result) => poison::map_result(result, |copy| f(&copy)),
+            None => poison::map_result(self.read(), |guard| f(&*guard)),
+        }
+    }
+
+    /// Makes the data read-only for as long as the lock is alive.
+    ///
+    /// This waits for the current writer, if any, to release the lock. From
+    /// then on, [`read`] and [`try_read`] only check that the lock is frozen
+    /// instead of acquiring it, so readers never block and do not contend
+    /// with each other. [`write`] panics and [`try_write`] fails with
+    /// [`WouldBlock`]. This is intended for data which is built during
+    /// startup and never changes afterwards.
+    ///
+    /// Freezing is one-way. Freezing a lock which is already frozen does
+    /// nothing. Poisoning is not affected: reads of a poisoned frozen lock
+    /// still return an error.
+    ///
+    /// # Panics
+    ///
+    /// This function might panic when called if the lock is already held by
+    /// the current thread. With the `debug-rwlock` feature, it always panics
+    /// if the current thread holds a read or write lock on it.
+    ///
+    /// This function may panic if the lock is not initialized.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pinned_sync::RwLock;
+    ///
+    /// let config = RwLock::boxed(Vec::new());
+    /// config.as_ref().write().unwrap().push("verbose");
+    /// config.as_ref().freeze();
+    ///
+    /// assert_eq!(*config.as_ref().read().unwrap(), ["verbose"]);
+    /// assert!(config.as_ref().try_write().is_err());
+    /// ```
+    ///
+    /// [`read`]: Self::read
+    /// [`try_read`]: Self::try_read
+    /// [`write`]: Self::write
+    /// [`try_write`]: Self::try_write
+    /// [`WouldBlock`]: TryLockError::WouldBlock
+    pub fn freeze(self: Pin<&Self>) {
+        if self.frozen.load(Relaxed) {
+            return;
+        }
+        held::check_write(self.id());
+        // Holding the write lock makes the writes of the previous writers
+        // visible to readers which see the lock frozen.
+        let _upgrade = self.upgrade().lock();
+        let _guard = self.inner().write();
+        self.frozen.store(true, Release);
+    }
+
+    /// Returns whether [`freeze`] was called on this read-write lock.
+    ///
+    /// [`freeze`]: Self::freeze
+    #[inline]
+    pub fn is_frozen(self: Pin<&Self>) -> bool {
+        self.frozen.load(Relaxed)
+    }
+
+    /// Acquires this rwlock with shared read access without a guard, blocking
+    /// the current thread until it can be acquired.
+    ///
+    /// The read access is held until [`unlock_read_raw`] is called, which
+    /// makes it possible to build other guard types on top of the lock, or to
+    /// hand the lock over to foreign code which releases it later. The data
+    /// can be read through [`data_ptr`] in the meantime.
+    ///
+    /// Locking this way does not check for poisoning.
+    ///
+    /// # Safety
+    ///
+    /// While the lock is held this way, the read access must only be released
+    /// with [`unlock_read_raw`].
+    ///
+    /// # Panics
+    ///
+    /// This function might panic when called if the lock is already held by the current thread.
+    ///
+    /// This function panics if the maximum number of readers is reached, unless
+    /// the lock was configured otherwise with [`reader_overflow`].
+    ///
+    /// [`unlock_read_raw`]: Self::unlock_read_raw
+    /// [`data_ptr`]: Self::data_ptr
+    /// [`reader_overflow`]: Self::reader_overflow
+    #[inline]
+    pub unsafe fn read_raw(self: Pin<&Self>) {
+        held::check_read(self.id());
+        mem::forget(self.read_real());
+    }
+
+    /// Acquires this rwlock with exclusive write access without a guard,
+    /// blocking the current thread until it can be acquired.
+    ///
+    /// The write access is held until [`unlock_write_raw`] is called. The data
+    /// can be accessed through [`data_ptr`] in the meantime.
+    ///
+    /// Locking this way neither checks for nor causes poisoning.
+    ///
+    /// # Safety
+    ///
+    /// While the lock is held this way, the write access must only be released
+    /// with [`unlock_write_raw`].
+    ///
+    /// # Panics
+    ///
+    /// This function might panic when called if the lock is already held by the current thread.
+    ///
+    /// This function panics if the lock is [frozen].
+    ///
+    /// [`unlock_write_raw`]: Self::unlock_write_raw
+    /// [`data_ptr`]: Self::data_ptr
+    /// [frozen]: Self::freeze
+    #[inline]
+    pub unsafe fn write_raw(self: Pin<&Self>) {
+        held::check_write(self.id());
+        let upgrade = self.upgrade().lock();
+        let guard = self.write_real();
+        if self.frozen.load(Relaxed) {
+            drop(guard);
+            panic!("rwlock write lock on a frozen rwlock (rwlock {:p})", self.id());
+        }
+        self.begin_write();
+        mem::forget(guard);
+        mem::forget(upgrade);
+    }
+
+    /// Releases the shared read access acquired with [`read_raw`].
+    ///
+    /// # Safety
+    ///
+    /// The lock must be held with [`read_raw`], and this access not released
+    /// since. Unless the `send_guard` feature makes guards `Send` on this
+    /// backend, this must be called by the thread which acquired it, as some
+    /// platforms require.
+    ///
+    /// [`read_raw`]: Self::read_raw
+    #[inline]
+    pub unsafe fn unlock_read_raw(self: Pin<&Self>) {
+        self.inner().read_unlock();
+        self.overflow.reader_left();
+    }
+
+    /// Releases the exclusive write access acquired with [`write_raw`].
+    ///
+    /// # Safety
+    ///
+    /// The lock must be held with [`write_raw`], and not released since.
+    /// Unless the `send_guard` feature makes guards `Send` on this backend,
+    /// this must be called by the thread which acquired it, as some platforms
+    /// require.
+    ///
+    /// [`write_raw`]: Self::write_raw
+    #[inline]
+    pub unsafe fn unlock_write_raw(self: Pin<&Self>) {
+        self.version.fetch_add(1, Release);
+        self.inner().write_unlock();
+        self.upgrade().unlock();
+    }
+
+    /// Forcibly releases the shared read access of a leaked read guard.
+    ///
+    /// This makes a read-write lock usable again after a read guard was lost
+    /// without being dropped, such as with [`mem::forget`] or when unwinding
+    /// was stopped at an FFI boundary.
+    ///
+    /// # Safety
+    ///
+    /// The lock must be held by an [`RwLockReadGuard`] which was leaked, and
+    /// which is never used again. Unless the `send_guard` feature makes
+    /// guards `Send` on this backend, this must be called by the thread which
+    /// acquired it, as some platforms require.
+    pub unsafe fn force_unlock_read(self: Pin<&Self>) {
+        held::forget(self.id(), false);
+        // A real reader keeps the lock from being frozen, so a guard of a
+        // frozen lock never held it.
+        if !self.frozen.load(Relaxed) {
+            self.unlock_read_raw();
+        }
+    }
+
+    /// Forcibly releases the exclusive write access of a leaked write guard.
+    ///
+    /// This makes a read-write lock usable again after a write guard was lost
+    /// without being dropped, such as with [`mem::forget`] or when unwinding
+    /// was stopped at an FFI boundary. The lock is not poisoned.
+    ///
+    /// # Safety
+    ///
+    /// The lock must be held by an [`RwLockWriteGuard`] which was leaked, and
+    /// which is never used again. Unless the `send_guard` feature makes
+    /// guards `Send` on this backend, this must be called by the thread which
+    /// acquired it, as some platforms require.
+    pub unsafe fn force_unlock_write(self: Pin<&Self>) {
+        held::forget(self.id(), true);
+        self.unlock_write_raw();
+    }
+
+    /// Returns the identifier of this read-write lock, which stays the same for as long
+    /// as it is alive.
+    #[inline]
+    pub fn id(self: Pin<&Self>) -> LockId {
+        LockId::of(self.get_ref())
+    }
+
+    /// Determines whether the read-write lock is poisoned.
+    ///
+    /// If another thread is active, the read-write lock can still become poisoned at any
+    /// time. You should not trust a `false` value for program correctness
+    /// without additional synchronization.
+    #[inline]
+    pub fn is_poisoned(self: Pin<&Self>) -> bool {
+        self.poison.get()
+    }
+
+    /// Clears the poisoned state from a read-write lock.
+    ///
+    /// If the lock is poisoned, it will remain poisoned until this function is
+    /// called. This allows recovering from a poisoned state and marking that
+    /// it has recovered. For example, if the value is overwritten by a
+    /// known-good value, then the lock can be marked as un-poisoned. Or
+    /// possibly, the value could be inspected to determine if it is in a
+    /// consistent state, and if so the poison is removed.
+    #[inline]
+    pub fn clear_poison(self: Pin<&Self>) {
+        self.poison.clear();
+    }
+
+    /// Returns a raw pointer to the underlying data, without locking the
+    /// read-write lock.
+    ///
+    /// As the lock is pinned, the pointer stays valid for as long as the lock
+    /// is alive. It is up to the caller to make sure that reads and writes
+    /// through it are synchronized, such as by only reading through it while
+    /// holding a read guard, and writing while holding the write guard.
+    #[inline]
+    pub fn data_ptr(self: Pin<&Self>) -> *mut T {
+        self.data.get()
+    }
+
+    /// Consumes this read-write lock, returning the underlying data.
+    ///
+    /// # Errors
+    ///
+    /// If another user of this read-write lock panicked while holding the
+    /// read-write lock, then this call will return an error instead.
+    pub fn into_inner(self) -> LockResult<T>
+    where
+        T: Sized,
+    {
+        let Self { data, poison, .. } = self;
+        poison::map_result(poison.borrow(), |_| data.into_inner())
+    }
+
+    /// Consumes a boxed read-write lock, returning the underlying data.
+    ///
+    /// This is the same as [`into_inner`], for a `RwLock` which was pinned in a
+    /// box, such as one created with [`boxed`]. The read-write lock itself is dropped in
+    /// place.
+    ///
+    /// # Errors
+    ///
+    /// If another user of this read-write lock panicked while holding the read-write lock, then
+    /// this call will return an error instead.
+    ///
+    /// [`into_inner`]: Self::into_inner
+    /// [`boxed`]: Self::boxed
+    pub fn into_inner_boxed(this: Pin<Box<Self>>) -> LockResult<T>
+    where
+        T: Sized,
+    {
+        unsafe { take::take_box(this, |this| this.take()) }
+    }
+
+    /// Consumes a shared read-write lock if this is its only reference, returning the
+    /// underlying data.
+    ///
+    /// This is the same as [`into_inner`], for a `RwLock` which was pinned in an
+    /// [`Arc`], such as one created with [`arc`]. If there are other strong or
+    /// weak references to the read-write lock, it is returned back in [`Err`]. The
+    /// read-write lock itself is dropped in place.
+    ///
+    /// # Errors
+    ///
+    /// If another user of this read-write lock panicked while holding the read-write lock, then
+    /// the inner result is an error.
+    ///
+    /// [`into_inner`]: Self::into_inner
+    /// [`arc`]: Self::arc
+    pub fn try_into_inner_arc(this: Pin<Arc<Self>>) -> Result<LockResult<T>, Pin<Arc<Self>>>
+    where
+        T: Sized,
+    {
+        unsafe { take::take_arc(this, |this| this.take()) }
+    }
+
+    // Moves the data out and drops the rest of the read-write lock in place.
+    unsafe fn take(&mut self) -> LockResult<T>
+    where
+        T: Sized,
+    {
+        let Self {
+            inner,
+            poison,
+            overflow: _,
+            policy: _,
+            turnstile,
+            upgrade,
+            version: _,
+            frozen: _,
+            _p: _,
+            data,
+        } = self;
+        let data = ptr::read(data.get());
+        ptr::drop_in_place(inner);
+        ptr::drop_in_place(turnstile);
+        ptr::drop_in_place(upgrade);
+        poison::map_result(poison.borrow(), |_| data)
+    }
+
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// Since this call borrows the `RwLock` mutably, no actual locking needs to
+    /// take place -- the mutable borrow statically guarantees no locks exist.
+    ///
+    /// # Errors
+    ///
+    /// If another user of this read-write lock panicked while holding the read-write lock, then
+    /// this call will return an error instead.
+    pub fn get_mut(&mut self) -> LockResult<&mut T> {
+        let data = self.data.get_mut();
+        poison::map_result(self.poison.borrow(), |_| data)
+    }
+
+    /// Returns a mutable reference to the underlying data of a pinned read-write lock.
+    ///
+    /// This is the same as [`get_mut`], for a `RwLock` which is already pinned.
+    /// The data is not structurally pinned, so it can be accessed mutably.
+    ///
+    /// # Errors
+    ///
+    /// If another user of this read-write lock panicked while holding the read-write lock, then
+    /// this call will return an error instead.
+    ///
+    /// [`get_mut`]: Self::get_mut
+    pub fn get_pin_mut(self: Pin<&mut Self>) -> LockResult<&mut T> {
+        // Safety: The read-write lock itself is not moved, only the data is borrowed.
+        unsafe { self.get_unchecked_mut() }.get_mut()
+    }
+
+    #[inline]
+    fn inner(self: Pin<&Self>) -> Pin<&B> {
+        unsafe { self.map_unchecked(|this| &this.inner) }
+    }
+
+    #[inline]
+    fn begin_write(&self) {
+        self.version.fetch_add(1, Relaxed);
+        fence(Release);
+    }
+
+    #[inline]
+    fn turnstile(self: Pin<&Self>) -> Pin<&sys_mutex::Mutex> {
+        unsafe { self.map_unchecked(|this| &this.turnstile) }
+    }
+
+    #[inline]
+    fn upgrade(self: Pin<&Self>) -> Pin<&sys_mutex::Mutex> {
+        unsafe { self.map_unchecked(|this| &this.upgrade) }
+    }
+
+    // Acquires the backend read lock, behind any writer waiting on the
+    // turnstile.
+    #[inline]
+    fn read_real(self: Pin<&Self>) -> ReadReal<'_, B> {
+        loop {
+            if let Some(guard) = self.try_read_real_checked() {
+                break guard;
+            }
+            match self.overflow.policy {
+                ReaderOverflow::Panic => {
+                    panic!("rwlock maximum reader count exceeded (rwlock {:p})", self.id())
+                }
+                ReaderOverflow::Block => {
+                    if let Some(guard) = self.wait_reader_left() {
+                        break guard;
+                    }
+                }
+            }
+        }
+    }
+
+    // Acquires the backend read lock like `read_real`, but returns `None` if
+    // the maximum number of readers is reached.
+    #[inline]
+    fn try_read_real_checked(self: Pin<&Self>) -> Option<ReadReal<'_, B>> {
+        if self.policy == WriterPolicy::Preferred {
+            drop(self.turnstile().lock());
+        }
+        let guard = self.inner().read()?;
+        Some(ReadReal::new(guard, &self.get_ref().overflow))
+    }
+
+    // Blocks until one of the readers leaves, once the maximum number of
+    // readers is reached. Returns the read lock if it could be acquired
+    // without waiting after all.
+    #[cold]
+    fn wait_reader_left(self: Pin<&Self>) -> Option<ReadReal<'_, B>> {
+        let overflow = &self.get_ref().overflow;
+        overflow.waiting.fetch_add(1, SeqCst);
+        let waiter = overflow.queue.push();
+        // A reader which left before this thread was queued did not notify it.
+        let guard = match self.inner().try_read() {
+            Err(ReadError::TooManyReaders) => {
+                overflow.queue.park(&waiter, None);
+                None
+            }
+            // Waiting for a writer is left to `read` again.
+            result => {
+                // A notification this thread got in the meantime is passed on.
+                if overflow.queue.cancel(&waiter) {
+                    overflow.queue.notify_one();
+                }
+                result.ok().map(|guard| ReadReal::new(guard, overflow))
+            }
+        };
+        overflow.waiting.fetch_sub(1, Relaxed);
+        guard
+    }
+
+    #[inline]
+    fn try_read_real(self: Pin<&Self>) -> Result<ReadReal<'_, B>, ReadError> {
+        if self.policy == WriterPolicy::Preferred {
+            drop(self.turnstile().try_lock().ok_or(ReadError::WouldBlock)?);
+        }
+        let guard = self.inner().try_read()?;
+        Ok(ReadReal::new(guard, &self.get_ref().overflow))
+    }
+
+    #[inline]
+    fn read_real_until(
+        self: Pin<&Self>,
+        deadline: Instant,
+    ) -> Result<ReadReal<'_, B>, ReadError> {
+        if self.policy == WriterPolicy::Preferred {
+            drop(
+                self.turnstile()
+                    .try_lock_until(deadline)
+                    .ok_or(ReadError::WouldBlock)?,
+            );
+        }
+        let guard = self.inner().try_read_until(deadline)?;
+        Ok(ReadReal::new(guard, &self.get_ref().overflow))
+    }
+
+    // Acquires the backend write lock, through the turnstile.
+    #[inline]
+    fn write_real(self: Pin<&Self>) -> B::WriteGuard<'_> {
+        if self.policy == WriterPolicy::Preferred {
+            let _turnstile = self.turnstile().lock();
+            self.inner().write()
+        } else {
+            self.inner().write()
+        }
+    }
+
+    #[inline]
+    fn write_real_until(self: Pin<&Self>, deadline: Instant) -> Option<B::WriteGuard<'_>> {
+        if self.policy == WriterPolicy::Preferred {
+            let _turnstile = self.turnstile().try_lock_until(deadline)?;
+            self.inner().try_write_until(deadline)
+        } else {
+            self.inner().try_write_until(deadline)
+        }
+    }
+}
+
+impl<T: ?Sized> RwLock<T> {
+    /// Restores the read-write lock to an unlocked state in the child process
+    /// of a `fork`.
+    ///
+    /// Only the thread which called `fork` exists in the child process, so a
+    /// lock which was held by other threads at that time is never unlocked.
+    /// This makes it usable again. The data may have been left half-updated
+    /// by a writer, so the lock is poisoned if it was held for writing.
+    ///
+    /// See [`reinit_on_fork`] to do this automatically after every `fork`.
+    ///
+    /// # Safety
+    ///
+    /// This must only be called in the child process of a `fork`, before any
+    /// thread is spawned. The current thread must not hold a guard of the
+    /// lock.
+    ///
+    /// [`reinit_on_fork`]: crate::reinit_on_fork
+    #[cfg(unix)]
+    pub unsafe fn reinit_after_fork(self: Pin<&Self>) {
+        if self.version.load(Relaxed) & 1 == 1 {
+            self.version.fetch_add(1, Relaxed);
+            self.poison.poison();
+        }
+        self.inner().reinit_after_fork();
+        self.upgrade().reinit_after_fork();
+        if self.policy == WriterPolicy::Preferred {
+            self.turnstile().reinit_after_fork();
+        }
+    }
+}
+
+#[inline]
+fn read_error<G>(error: ReadError) -> TryLockError<G> {
+    match error {
+        ReadError::WouldBlock => TryLockError::WouldBlock,
+        ReadError::TooManyReaders => TryLockError::TooManyReaders,
+    }
+}
+
+/// What to do when the maximum number of concurrent readers of an [`RwLock`]
+/// is reached.
+///
+/// This is set with [`RwLock::reader_overflow`]. To get an error instead, use
+/// [`RwLock::read_checked`] or [`RwLock::try_read`].
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum ReaderOverflow {
+    /// Panic with a "maximum reader count exceeded" message.
+    Panic,
+    /// Block until one of the readers releases the lock.
+    Block,
+}
+
+// The reader overflow policy of a read-write lock, and what blocking with
+// `ReaderOverflow::Block` needs.
+struct Overflow {
+    policy: ReaderOverflow,
+    // The readers waiting for another reader to leave, and how many of them
+    // there are, so that leaving readers only notify when there are some.
+    waiting: AtomicUsize,
+    queue: WaitQueue,
+}
+
+impl Overflow {
+    const fn new(policy: ReaderOverflow) -> Self {
+        Self {
+            policy,
+            waiting: AtomicUsize::new(0),
+            queue: WaitQueue::new(true),
+        }
+    }
+
+    // Called once a reader released the backend read lock.
+    #[inline]
+    fn reader_left(&self) {
+        if self.policy == ReaderOverflow::Block {
+            // Pairs with the increment of `waiting` before the reader retries.
+            fence(SeqCst);
+            if self.waiting.load(Relaxed) > 0 {
+                self.queue.notify_one();
+            }
+        }
+    }
+}
+
+// A backend read lock, which wakes up a reader waiting for a free reader slot
+// once it is released.
+struct ReadReal<'a, B: RawRwLock + 'a> {
+    guard: ManuallyDrop<B::ReadGuard<'a>>,
+    overflow: &'a Overflow,
+}
+
+impl<'a, B: RawRwLock> ReadReal<'a, B> {
+    #[inline]
+    fn new(guard: B::ReadGuard<'a>, overflow: &'a Overflow) -> Self {
+        Self {
+            guard: ManuallyDrop::new(guard),
+            overflow,
+        }
+    }
+}
+
+impl<B: RawRwLock> Clone for ReadReal<'_, B> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self::new((*self.guard).clone(), self.overflow)
+    }
+}
+
+impl<B: RawRwLock> Drop for ReadReal<'_, B> {
+    #[inline]
+    fn drop(&mut self) {
+        unsafe { ManuallyDrop::drop(&mut self.guard) };
+        self.overflow.reader_left();
+    }
+}
+
+/// The order in which an [`RwLock`] admits waiting readers and writers.
+///
+/// This is set with [`RwLock::writer_policy`].
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum WriterPolicy {
+    /// Use whatever policy the underlying operating system implements. Some
+    /// platforms prefer readers, in which case a steady stream of readers may
+    /// starve writers indefinitely.
+    Native,
+    /// A waiting writer blocks new readers from acquiring the lock, so it is
+    /// admitted as soon as the readers which already hold the lock release it.
+    ///
+    /// Writers queue for the lock among themselves, so each writer waits for
+    /// at most one batch of readers per writer queued before it.
+    Preferred,
+}
+
+impl<T: ?Sized, B: RawRwLock> fmt::Pointer for RwLock<T, B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Pointer::fmt(&LockId::of(self), f)
+    }
+}
+
+pub struct RwLockReadGuard<'a, T: ?Sized, B: RawRwLock = raw::RwLock> {
+    // Dropped first, so that the hold ends before the lock is released.
+    _trace: trace::Hold,
+    // The guard of the backend, as not every backend provides raw unlocking.
+    _guard: ReadAcquired<'a, B>,
+    lock: Pin<&'a RwLock<T, B>>,
+    _held: held::Held,
+    _marker: PhantomData<GuardMarker>,
+}
+
+unsafe impl<T: ?Sized + Sync, B: RawRwLock> Sync for RwLockReadGuard<'_, T, B> {}
+
+/// How a [`RwLockReadGuard`] acquired the lock.
+enum ReadAcquired<'a, B: RawRwLock + 'a> {
+    /// The lock was acquired for real.
+    Real(ReadReal<'a, B>),
+    /// The lock was elided, and the critical section is a hardware transaction.
+    Elided,
+    /// The lock is frozen, so it does not need to be acquired.
+    Frozen,
+}
+
+impl<T: ?Sized, B: RawRwLock> UnwindSafe for RwLockReadGuard<'_, T, B> {}
+
+impl<T: ?Sized, B: RawRwLock> RefUnwindSafe for RwLockReadGuard<'_, T, B> {}
+
+impl<T: ?Sized, B: RawRwLock> Deref for RwLockReadGuard<'_, T, B> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<'a, T: ?Sized, B: RawRwLock> RwLockReadGuard<'a, T, B> {
+    /// Makes a new guard sharing the read access of an existing one.
+    ///
+    /// This only increments the reader count of the lock, without going
+    /// through [`read`] again, so it never blocks and never fails because
+    /// the lock is poisoned. This allows a read guard to be shared into helper
+    /// structures or iterators.
+    ///
+    /// This is an associated function that needs to be used as
+    /// `RwLockReadGuard::clone(&guard)`, as `guard.clone()` clones the data.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the maximum number of readers is reached.
+    ///
+    /// [`read`]: RwLock::read
+    #[inline]
+    #[allow(clippy::should_implement_trait)]
+    pub fn clone(orig: &Self) -> Self {
+        let guard = match &orig._guard {
+            ReadAcquired::Real(guard) => ReadAcquired::Real(guard.clone()),
+            // The elided transaction can not be shared, so the read lock is
+            // taken for real instead.
+            ReadAcquired::Elided => elision::abort(),
+            ReadAcquired::Frozen => ReadAcquired::Frozen,
+        };
+        RwLockReadGuard {
+            _guard: guard,
+            lock: orig.lock,
+            _held: held::Held::new(orig.lock.id(), false),
+            _trace: trace::Hold::start(orig.lock.id(), "RwLock::read"),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Releases the read lock while `f` runs, and acquires it again.
+    // Not a method, so that it does not shadow the methods of `T`.
+    pub(crate) fn unlocked(s: Self, f: impl FnOnce()) -> LockResult<Self> {
+        // Waiting requires the real lock.
+        if let ReadAcquired::Elided = s._guard {
+            elision::abort();
+        }
+        let lock = s.lock;
+        drop(s);
+        f();
+        lock.read()
+    }
+
+    /// Makes a [`MappedRwLockReadGuard`] for a component of the locked data,
+    /// such as a field, keeping the read access.
+    ///
+    /// The lock stays held until the mapped guard is dropped, so the
+    /// component can be returned from a function which locked the whole.
+    ///
+    /// This is an associated function that needs to be used as
+    /// `RwLockReadGuard::map(guard, ...)`, as a method would shadow a method
+    /// of `T` with the same name.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pinned_sync::{MappedRwLockReadGuard, RwLock, RwLockReadGuard};
+    /// use std::pin::Pin;
+    ///
+    /// struct Config {
+    ///     name: String,
+    ///     retries: u32,
+    /// }
+    ///
+    /// fn name(config: Pin<&RwLock<Config>>) -> MappedRwLockReadGuard<'_, str> {
+    ///     RwLockReadGuard::map(config.read().unwrap(), |config| &*config.name)
+    /// }
+    ///
+    /// let config = RwLock::boxed(Config {
+    ///     name: "pinned".to_string(),
+    ///     retries: 3,
+    /// });
+    /// assert_eq!(&*name(config.as_ref()), "pinned");
+    /// ```
+    #[inline]
+    pub fn map<U, F>(orig: Self, f: F) -> MappedRwLockReadGuard<'a, U, B>
+    where
+        U: ?Sized,
+        F: FnOnce(&T) -> &U,
+    {
+        let data: *const U = f(&*orig);
+        Self::into_mapped(orig, data)
+    }
+
+    /// Makes a [`MappedRwLockReadGuard`] for a component of the locked data,
+    /// if `f` returns one, keeping the read access.
+    ///
+    /// If `f` returns [`None`], the original guard is returned back in
+    /// [`Err`].
+    ///
+    /// This is an associated function that needs to be used as
+    /// `RwLockReadGuard::try_map(guard, ...)`, as a method would shadow a
+    /// method of `T` with the same name.
+    #[inline]
+    pub fn try_map<U, F>(orig: Self, f: F) -> Result<MappedRwLockReadGuard<'a, U, B>, Self>
+    where
+        U: ?Sized,
+        F: FnOnce(&T) -> Option<&U>,
+    {
+        match f(&*orig) {
+            Some(data) => {
+                let data: *const U = data;
+                Ok(Self::into_mapped(orig, data))
+            }
+            None => Err(orig),
+        }
+    }
+
+    fn into_mapped<U: ?Sized>(s: Self, data: *const U) -> MappedRwLockReadGuard<'a, U, B> {
+        let s = ManuallyDrop::new(s);
+        // Safety: `s` is never dropped, so every field which needs dropping is
+        // moved out exactly once. The elided transaction, if any, is ended by
+        // the mapped guard instead.
+        unsafe {
+            MappedRwLockReadGuard {
+                _trace: ptr::read(&s._trace),
+                _guard: ptr::read(&s._guard),
+                data,
+                _held: ptr::read(&s._held),
+                _marker: PhantomData,
+            }
+        }
+    }
+}
+
+impl<T: ?Sized, B: RawRwLock> Drop for RwLockReadGuard<'_, T, B> {
+    #[inline]
+    fn drop(&mut self) {
+        if let ReadAcquired::Elided = self._guard {
+            elision::end();
+        }
+    }
+}
+
+/// An RAII guard of the shared read access of an [`RwLock`], for a component
+/// of the locked data.
+///
+/// This is made from an [`RwLockReadGuard`] with [`RwLockReadGuard::map`] or
+/// [`RwLockReadGuard::try_map`]. When it is dropped, the shared access is
+/// released.
+pub struct MappedRwLockReadGuard<'a, T: ?Sized, B: RawRwLock = raw::RwLock> {
+    // Dropped first, so that the hold ends before the lock is released.
+    _trace: trace::Hold,
+    _guard: ReadAcquired<'a, B>,
+    data: *const T,
+    _held: held::Held,
+    _marker: PhantomData<(&'a T, GuardMarker)>,
+}
+
+unsafe impl<T: ?Sized + Sync, B: RawRwLock> Sync for MappedRwLockReadGuard<'_, T, B> {}
+
+cfg_send_guard! {
+    unsafe impl<'a, T: ?Sized + Sync, B: RawRwLock> Send for MappedRwLockReadGuard<'a, T, B> where
+        B::ReadGuard<'a>: Send
+    {
+    }
+}
+
+impl<T: ?Sized, B: RawRwLock> UnwindSafe for MappedRwLockReadGuard<'_, T, B> {}
+
+impl<T: ?Sized, B: RawRwLock> RefUnwindSafe for MappedRwLockReadGuard<'_, T, B> {}
+
+impl<T: ?Sized, B: RawRwLock> Deref for MappedRwLockReadGuard<'_, T, B> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        unsafe { &*self.data }
+    }
+}
+
+impl<'a, T: ?Sized, B: RawRwLock> MappedRwLockReadGuard<'a, T, B> {
+    /// Makes a guard for a component of the data of this guard, keeping the
+    /// read access.
+    ///
+    /// This is an associated function that needs to be used as
+    /// `MappedRwLockReadGuard::map(guard, ...)`. See [`RwLockReadGuard::map`].
+    #[inline]
+    pub fn map<U, F>(orig: Self, f: F) -> MappedRwLockReadGuard<'a, U, B>
+    where
+        U: ?Sized,
+        F: FnOnce(&T) -> &U,
+    {
+        let data: *const U = f(&*orig);
+        Self::remap(orig, data)
+    }
+
+    /// Makes a guard for a component of the data of this guard, if `f`
+    /// returns one, keeping the read access.
+    ///
+    /// If `f` returns [`None`], the original guard is returned back in
+    /// [`Err`].
+    ///
+    /// This is an associated function that needs to be used as
+    /// `MappedRwLockReadGuard::try_map(guard, ...)`. See
+    /// [`RwLockReadGuard::try_map`].
+    #[inline]
+    pub fn try_map<U, F>(orig: Self, f: F) -> Result<MappedRwLockReadGuard<'a, U, B>, Self>
+    where
+        U: ?Sized,
+        F: FnOnce(&T) -> Option<&U>,
+    {
+        match f(&*orig) {
+            Some(data) => {
+                let data: *const U = data;
+                Ok(Self::remap(orig, data))
+            }
+            None => Err(orig),
+        }
+    }
+
+    fn remap<U: ?Sized>(s: Self, data: *const U) -> MappedRwLockReadGuard<'a, U, B> {
+        let s = ManuallyDrop::new(s);
+        // Safety: `s` is never dropped, so every field which needs dropping is
+        // moved out exactly once.
+        unsafe {
+            MappedRwLockReadGuard {
+                _trace: ptr::read(&s._trace),
+                _guard: ptr::read(&s._guard),
+                data,
+                _held: ptr::read(&s._held),
+                _marker: PhantomData,
+            }
+        }
+    }
+}
+
+impl<T: ?Sized, B: RawRwLock> Drop for MappedRwLockReadGuard<'_, T, B> {
+    #[inline]
+    fn drop(&mut self) {
+        if let ReadAcquired::Elided = self._guard {
+            elision::end();
+        }
+    }
+}
+
+impl<T: ?Sized + fmt::Debug, B: RawRwLock> fmt::Debug for MappedRwLockReadGuard<'_, T, B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: ?Sized + fmt::Display, B: RawRwLock> fmt::Display for MappedRwLockReadGuard<'_, T, B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+pub struct RwLockWriteGuard<'a, T: ?Sized, B: RawRwLock = raw::RwLock> {
+    // Dropped first, so that the hold ends before the lock is released.
+    _trace: trace::Hold,
+    // The guard of the backend, as not every backend provides raw unlocking.
+    _guard: B::WriteGuard<'a>,
+    // Held as long as the lock, so that no upgradable reader can get in
+    // between if the guard is downgraded to an upgradable read guard.
+    _upgrade: sys_mutex::MutexGuard<'a>,
+    lock: Pin<&'a RwLock<T, B>>,
+    poison: poison::Guard,
+    _held: held::Held,
+    _marker: PhantomData<GuardMarker>,
+}
+
+unsafe impl<T: ?Sized + Sync, B: RawRwLock> Sync for RwLockWriteGuard<'_, T, B> {}
+
+impl<T: ?Sized, B: RawRwLock> UnwindSafe for RwLockWriteGuard<'_, T, B> {}
+
+impl<T: ?Sized, B: RawRwLock> RefUnwindSafe for RwLockWriteGuard<'_, T, B> {}
+
+impl<T: ?Sized, B: RawRwLock> Deref for RwLockWriteGuard<'_, T, B> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<T: ?Sized, B: RawRwLock> DerefMut for RwLockWriteGuard<'_, T, B> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.lock.data.get() }
+    }
+}
+
+impl<'a, T: ?Sized, B: RawRwLock> RwLockWriteGuard<'a, T, B> {
+    /// Atomically downgrades a write guard to an upgradable read guard,
+    /// without letting any writer in.
+    ///
+    /// Other readers may acquire the lock as soon as the write access is
+    /// released, but the data stays as this writer left it until the guard
+    /// is upgraded again or dropped.
+    ///
+    /// This is an associated function that needs to be used as
+    /// `RwLockWriteGuard::downgrade_to_upgradable(guard)`.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the maximum number of readers is reached,
+    /// unless the lock was configured otherwise with
+    /// [`RwLock::reader_overflow`].
+    pub fn downgrade_to_upgradable(s: Self) -> RwLockUpgradableReadGuard<'a, T, B> {
+        let s = ManuallyDrop::new(s);
+        let lock = s.lock;
+        lock.poison.done(&s.poison);
+        lock.version.fetch_add(1, Release);
+        // Safety: `s` is never dropped, so every field which needs dropping is
+        // moved out exactly once.
+        let (trace, guard, upgrade, held) = unsafe {
+            (
+                ptr::read(&s._trace),
+                ptr::read(&s._guard),
+                ptr::read(&s._upgrade),
+                ptr::read(&s._held),
+            )
+        };
+        // These only need dropping with the `profiling` and `debug-rwlock`
+        // features.
+        #[allow(clippy::drop_non_drop)]
+        {
+            drop(trace);
+            drop(held);
+        }
+        // Writers wait for `upgrade`, which is still held, so only readers can
+        // get in until the read lock is taken again.
+        drop(guard);
+        let guard = lock.read_real();
+        RwLockUpgradableReadGuard {
+            _guard: guard,
+            _upgrade: upgrade,
+            lock,
+            _held: held::Held::new(lock.id(), false),
+            _trace: trace::Hold::start(lock.id(), "RwLock::upgradable_read"),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Releases the write lock while `f` runs, and acquires it again.
+    // Not a method, so that it does not shadow the methods of `T`.
+    pub(crate) fn unlocked(s: Self, f: impl FnOnce()) -> LockResult<Self> {
+        let lock = s.lock;
+        drop(s);
+        f();
+        lock.write()
+    }
+}
+
+impl<T: ?Sized, B: RawRwLock> Drop for RwLockWriteGuard<'_, T, B> {
+    #[inline]
+    fn drop(&mut self) {
+        self.lock.poison.done(&self.poison);
+        self.lock.version.fetch_add(1, Release);
+    }
+}
+
+/// RAII structure used to release the upgradable read access of a lock when
+/// dropped.
+///
+/// This structure is created by the [`upgradable_read`] and
+/// [`try_upgradable_read`] methods on [`RwLock`]. It gives shared access to
+/// the data, and can be upgraded to a [`RwLockWriteGuard`] with [`upgrade`].
+///
+/// [`upgradable_read`]: RwLock::upgradable_read
+/// [`try_upgradable_read`]: RwLock::try_upgradable_read
+/// [`upgrade`]: Self::upgrade
+pub struct RwLockUpgradableReadGuard<'a, T: ?Sized, B: RawRwLock = raw::RwLock> {
+    // Dropped first, so that the hold ends before the lock is released.
+    _trace: trace::Hold,
+    _guard: ReadReal<'a, B>,
+    // Keeps writers and other upgradable readers out.
+    _upgrade: sys_mutex::MutexGuard<'a>,
+    lock: Pin<&'a RwLock<T, B>>,
+    _held: held::Held,
+    _marker: PhantomData<GuardMarker>,
+}
+
+unsafe impl<T: ?Sized + Sync, B: RawRwLock> Sync for RwLockUpgradableReadGuard<'_, T, B> {}
+
+impl<T: ?Sized, B: RawRwLock> UnwindSafe for RwLockUpgradableReadGuard<'_, T, B> {}
+
+impl<T: ?Sized, B: RawRwLock> RefUnwindSafe for RwLockUpgradableReadGuard<'_, T, B> {}
+
+impl<T: ?Sized, B: RawRwLock> Deref for RwLockUpgradableReadGuard<'_, T, B> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<'a, T: ?Sized, B: RawRwLock> RwLockUpgradableReadGuard<'a, T, B> {
+    /// Atomically upgrades an upgradable read guard to a write guard.
+    ///
+    /// This blocks until the other readers release the lock. No writer can
+    /// acquire the lock in the meantime, so the data is still as this guard
+    /// saw it when the write guard is returned.
+    ///
+    /// This is an associated function that needs to be used as
+    /// `RwLockUpgradableReadGuard::upgrade(guard)`.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the lock is [frozen].
+    ///
+    /// [frozen]: RwLock::freeze
+    pub fn upgrade(s: Self) -> RwLockWriteGuard<'a, T, B> {
+        let RwLockUpgradableReadGuard {
+            _trace,
+            _guard,
+            _upgrade,
+            lock,
+            _held,
+            _marker,
+        } = s;
+        // These only need dropping with the `profiling` and `debug-rwlock`
+        // features.
+        #[allow(clippy::drop_non_drop)]
+        {
+            drop(_trace);
+            drop(_held);
+        }
+        let wait = trace::Wait::start();
+        // Writers wait for `upgrade`, which is still held, so this thread is
+        // the next writer.
+        drop(_guard);
+        let guard = lock.write_real();
+        if lock.frozen.load(Relaxed) {
+            drop(guard);
+            panic!("rwlock write lock on a frozen rwlock (rwlock {:p})", lock.id());
+        }
+        lock.begin_write();
+        let trace = wait.acquired(lock.id(), "RwLock::write");
+        // The lock can only have been poisoned before this guard was created,
+        // in which case that was reported already.
+        let poison = match lock.poison.borrow() {
+            Ok(poison) => poison,
+            Err(error) => error.into_inner(),
+        };
+        RwLockWriteGuard {
+            _guard: guard,
+            _upgrade,
+            lock,
+            poison,
+            _held: held::Held::new(lock.id(), true),
+            _trace: trace,
+            _marker: PhantomData,
+        }
     }
 }
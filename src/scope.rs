@@ -0,0 +1,185 @@
+use crate::{
+    Barrier, Condvar, EventPair, Mutex, RawPinnedMutex, ReentrantMutex, ReentrantRefCell, RwLock,
+};
+use std::array;
+use std::pin::Pin;
+use std::thread::{self, Scope};
+
+/// A set of uninitialized primitives which can be pinned and initialized by
+/// [`scope`].
+///
+/// This is implemented for every primitive of this crate, for arrays of sets
+/// of primitives, and for tuples of up to eight sets of primitives.
+pub trait Primitives {
+    /// The pinned handles to the primitives.
+    type Pinned<'a>
+    where
+        Self: 'a;
+
+    /// Initializes the primitives, returning pinned handles to them.
+    fn init_pinned(self: Pin<&Self>) -> Self::Pinned<'_>;
+}
+
+/// Creates a scope for spawning scoped threads which share the given
+/// primitives.
+///
+/// This works like [`std::thread::scope`], except that the primitives are
+/// first pinned to the stack and initialized, and the closure receives pinned
+/// handles to them, which can be used by every thread spawned in the scope.
+/// This avoids allocating the primitives with `arc()` only to share them with
+/// threads which do not outlive the current function.
+///
+/// The primitives are dropped once all threads spawned in the scope have been
+/// joined.
+///
+/// # Examples
+///
+/// ```
+/// use pinned_sync::{Condvar, Mutex};
+///
+/// let count = pinned_sync::scope((Mutex::uninit(0), Condvar::uninit()), |s, (m, c)| {
+///     for _ in 0..4 {
+///         s.spawn(move || {
+///             *m.lock().unwrap() += 1;
+///             c.notify_one();
+///         });
+///     }
+///     let mut count = m.lock().unwrap();
+///     while *count < 4 {
+///         count = c.wait(count).unwrap();
+///     }
+///     *count
+/// });
+/// assert_eq!(count, 4);
+/// ```
+pub fn scope<'env, P, F, R>(primitives: P, f: F) -> R
+where
+    P: Primitives + 'env,
+    F: for<'scope> FnOnce(&'scope Scope<'scope, 'env>, P::Pinned<'scope>) -> R,
+{
+    let primitives = &primitives;
+    thread::scope(|s| {
+        // Safety: The primitives are never moved, as they are only borrowed
+        // until `thread::scope` returns, and every thread which can use them
+        // is joined before that.
+        let primitives = unsafe { Pin::new_unchecked(&*(primitives as *const P)) };
+        f(s, primitives.init_pinned())
+    })
+}
+
+/// Initializes every primitive of a pinned array or slice.
+///
+/// This saves sharded designs with many locks from projecting each element
+/// with [`Pin::map_unchecked`] to initialize it. Arrays coerce to slices, so
+/// this accepts a `Pin<&[P; N]>` as well. Use [`iter_pinned`] to get pinned
+/// references to the elements afterwards.
+///
+/// Arrays of primitives also implement [`Primitives`], so they can be passed
+/// to [`scope`] directly.
+///
+/// # Panics
+///
+/// This function may panic if any of the primitives was already initialized.
+///
+/// # Examples
+///
+/// ```
+/// use pinned_sync::Mutex;
+/// use std::array;
+///
+/// let shards = Box::pin(array::from_fn::<_, 16, _>(|_| Mutex::uninit(0)));
+/// pinned_sync::init_all(shards.as_ref());
+///
+/// for shard in pinned_sync::iter_pinned(shards.as_ref()) {
+///     *shard.lock().unwrap() += 1;
+/// }
+/// ```
+pub fn init_all<P: Primitives>(primitives: Pin<&[P]>) {
+    for primitive in iter_pinned(primitives) {
+        primitive.init_pinned();
+    }
+}
+
+/// Returns an iterator over pinned references to the elements of a pinned
+/// array or slice.
+///
+/// The elements of a pinned slice are structurally pinned, so this is always
+/// safe.
+pub fn iter_pinned<T>(
+    slice: Pin<&[T]>,
+) -> impl DoubleEndedIterator<Item = Pin<&T>> + ExactSizeIterator {
+    // Safety: The elements of a pinned slice are structurally pinned.
+    slice
+        .get_ref()
+        .iter()
+        .map(|element| unsafe { Pin::new_unchecked(element) })
+}
+
+impl<const N: usize, P: Primitives> Primitives for [P; N] {
+    type Pinned<'a> = [P::Pinned<'a>; N] where Self: 'a;
+
+    #[inline]
+    fn init_pinned(self: Pin<&Self>) -> Self::Pinned<'_> {
+        // Safety: The elements of a pinned array are structurally pinned.
+        array::from_fn(|i| unsafe { self.map_unchecked(|this| &this[i]) }.init_pinned())
+    }
+}
+
+macro_rules! impl_primitives {
+    ($($t:ty),*) => {
+        $(
+            impl<T: ?Sized> Primitives for $t {
+                type Pinned<'a> = Pin<&'a Self> where Self: 'a;
+
+                #[inline]
+                fn init_pinned(self: Pin<&Self>) -> Pin<&Self> {
+                    self.init();
+                    self
+                }
+            }
+        )*
+    };
+}
+
+impl_primitives!(Mutex<T>, RwLock<T>, ReentrantMutex<T>, ReentrantRefCell<T>);
+
+macro_rules! impl_primitives_unit {
+    ($($t:ty),*) => {
+        $(
+            impl Primitives for $t {
+                type Pinned<'a> = Pin<&'a Self>;
+
+                #[inline]
+                fn init_pinned(self: Pin<&Self>) -> Pin<&Self> {
+                    self.init();
+                    self
+                }
+            }
+        )*
+    };
+}
+
+impl_primitives_unit!(Condvar, Barrier, EventPair, RawPinnedMutex);
+
+macro_rules! impl_primitives_tuple {
+    ($($name:ident $idx:tt),*) => {
+        impl<$($name: Primitives),*> Primitives for ($($name,)*) {
+            type Pinned<'a> = ($($name::Pinned<'a>,)*) where Self: 'a;
+
+            #[inline]
+            fn init_pinned(self: Pin<&Self>) -> Self::Pinned<'_> {
+                // Safety: The fields of a pinned tuple are structurally pinned.
+                unsafe { ($(self.map_unchecked(|t| &t.$idx).init_pinned(),)*) }
+            }
+        }
+    };
+}
+
+impl_primitives_tuple!(A 0);
+impl_primitives_tuple!(A 0, B 1);
+impl_primitives_tuple!(A 0, B 1, C 2);
+impl_primitives_tuple!(A 0, B 1, C 2, D 3);
+impl_primitives_tuple!(A 0, B 1, C 2, D 3, E 4);
+impl_primitives_tuple!(A 0, B 1, C 2, D 3, E 4, F 5);
+impl_primitives_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+impl_primitives_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
@@ -0,0 +1,415 @@
+use crate::sys::rwlock as sys;
+use crate::sys::ReadError;
+use crate::sys_common::marker::GuardMarker;
+use crate::sys_common::poison;
+use crate::{LockResult, TryLockError, TryLockResult};
+use std::array;
+use std::cell::{Cell, UnsafeCell};
+use std::fmt;
+use std::marker::{PhantomData, PhantomPinned};
+use std::ops::{Deref, DerefMut};
+use std::panic::{RefUnwindSafe, UnwindSafe};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering::*};
+use std::sync::Arc;
+
+/// The number of shards of a [`ShardedLock`].
+const SHARDS: usize = 8;
+
+/// A reader-writer lock which scales with the number of readers.
+///
+/// An [`RwLock`] keeps a single count of readers, which every reader writes
+/// to, so readers on different cores still contend for its cache line. A
+/// `ShardedLock` is split into several read-write locks, each on its own
+/// cache line. A reader only acquires the shard of its thread, so readers on
+/// different threads mostly touch different shards, while a writer acquires
+/// every shard.
+///
+/// This makes reads much cheaper on machines with many cores, at the cost of
+/// slower writes and a larger lock, so it is only worth it for data which is
+/// read far more often than it is written.
+///
+/// # Poisoning
+///
+/// A `ShardedLock`, like [`RwLock`], will become poisoned on a panic while it
+/// is locked for writing.
+///
+/// [`RwLock`]: crate::RwLock
+///
+/// # Examples
+///
+/// ```
+/// use pinned_sync::ShardedLock;
+///
+/// let lock = ShardedLock::boxed(5);
+///
+/// // Many readers can hold the lock at once.
+/// {
+///     let r1 = lock.as_ref().read().unwrap();
+///     let r2 = lock.as_ref().read().unwrap();
+///     assert_eq!(*r1 + *r2, 10);
+/// }
+///
+/// // Only one writer can.
+/// *lock.as_ref().write().unwrap() += 1;
+/// assert_eq!(*lock.as_ref().read().unwrap(), 6);
+/// ```
+pub struct ShardedLock<T: ?Sized> {
+    shards: [Shard; SHARDS],
+    poison: poison::Flag,
+    _p: PhantomPinned,
+    data: UnsafeCell<T>,
+}
+
+// Keeps every shard on its own cache line, so that readers of different
+// shards do not contend.
+#[repr(align(128))]
+struct Shard {
+    lock: sys::RwLock,
+}
+
+unsafe impl<T: ?Sized + Send> Send for ShardedLock<T> {}
+
+unsafe impl<T: ?Sized + Send + Sync> Sync for ShardedLock<T> {}
+
+impl<T: ?Sized> UnwindSafe for ShardedLock<T> {}
+
+impl<T: ?Sized> RefUnwindSafe for ShardedLock<T> {}
+
+impl<T> ShardedLock<T> {
+    /// Create a new, uninitialized sharded lock.
+    ///
+    /// This is *NOT* equivalent to `MaybeUninit::uninit().assume_init()`, which will cause
+    /// undefined behaviour if used to create a new sharded lock.
+    #[inline]
+    pub const fn uninit(value: T) -> Self {
+        #[allow(clippy::declare_interior_mutable_const)]
+        const SHARD: Shard = Shard {
+            lock: sys::RwLock::uninit(),
+        };
+        Self {
+            shards: [SHARD; SHARDS],
+            poison: poison::Flag::new(),
+            _p: PhantomPinned,
+            data: UnsafeCell::new(value),
+        }
+    }
+
+    /// Create a new, initialized sharded lock.
+    ///
+    /// The resulting sharded lock is wrapped and ready for use.
+    #[inline]
+    pub fn boxed(value: T) -> Pin<Box<Self>> {
+        let this = Box::pin(Self::uninit(value));
+        this.as_ref().init();
+        this
+    }
+
+    /// Create a new, initialized sharded lock.
+    ///
+    /// The resulting sharded lock is wrapped and ready for use.
+    #[inline]
+    pub fn arc(value: T) -> Pin<Arc<Self>> {
+        let this = Arc::pin(Self::uninit(value));
+        this.as_ref().init();
+        this
+    }
+
+    /// Consumes this sharded lock, returning the underlying data.
+    ///
+    /// # Errors
+    ///
+    /// If another user of this sharded lock panicked while holding it for
+    /// writing, then this call will return an error instead.
+    pub fn into_inner(self) -> LockResult<T> {
+        let Self { data, poison, .. } = self;
+        poison::map_result(poison.borrow(), |_| data.into_inner())
+    }
+}
+
+impl<T: ?Sized> ShardedLock<T> {
+    /// Initialize a sharded lock, making it ready for use.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the sharded lock was already initialized.
+    pub fn init(self: Pin<&Self>) {
+        for i in 0..SHARDS {
+            self.shard(i).init();
+        }
+    }
+
+    /// Locks this sharded lock with shared read access, blocking the current
+    /// thread until it can be acquired.
+    ///
+    /// Only the shard of the current thread is acquired, so this does not
+    /// contend with readers on most other threads.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the lock is poisoned. A sharded
+    /// lock is poisoned whenever a writer panics while holding it. The failure
+    /// will occur immediately after the lock has been acquired.
+    ///
+    /// # Panics
+    ///
+    /// This function might panic when called if the lock is already held by
+    /// the current thread.
+    ///
+    /// This function panics if the maximum number of readers of the shard is
+    /// reached.
+    ///
+    /// This function may panic if the lock is not initialized.
+    #[inline]
+    pub fn read(self: Pin<&Self>) -> LockResult<ShardedLockReadGuard<'_, T>> {
+        let guard = match self.shard(current_shard()).read() {
+            Some(guard) => guard,
+            None => panic!("rwlock maximum reader count exceeded"),
+        };
+        poison::map_result(self.poison.borrow(), |_| ShardedLockReadGuard {
+            _guard: guard,
+            lock: self,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Attempts to acquire this sharded lock with shared read access.
+    ///
+    /// If the access could not be granted at this time, then `Err` is
+    /// returned. Otherwise, an RAII guard is returned which will release the
+    /// shared access when it is dropped.
+    ///
+    /// This function does not block.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the lock is poisoned. A sharded
+    /// lock is poisoned whenever a writer panics while holding it. An error
+    /// will only be returned if the lock would have otherwise been acquired.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the lock is not initialized.
+    #[inline]
+    pub fn try_read(self: Pin<&Self>) -> TryLockResult<ShardedLockReadGuard<'_, T>> {
+        let guard = self
+            .shard(current_shard())
+            .try_read()
+            .map_err(|error| match error {
+                ReadError::WouldBlock => TryLockError::WouldBlock,
+                ReadError::TooManyReaders => TryLockError::TooManyReaders,
+            })?;
+        Ok(poison::map_result(self.poison.borrow(), |_| {
+            ShardedLockReadGuard {
+                _guard: guard,
+                lock: self,
+                _marker: PhantomData,
+            }
+        })?)
+    }
+
+    /// Locks this sharded lock with exclusive write access, blocking the
+    /// current thread until it can be acquired.
+    ///
+    /// Every shard is acquired, in order, so this waits for the readers of
+    /// every thread.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the lock is poisoned. A sharded
+    /// lock is poisoned whenever a writer panics while holding it. An error
+    /// will be returned when the lock is acquired.
+    ///
+    /// # Panics
+    ///
+    /// This function might panic when called if the lock is already held by
+    /// the current thread.
+    ///
+    /// This function may panic if the lock is not initialized.
+    pub fn write(self: Pin<&Self>) -> LockResult<ShardedLockWriteGuard<'_, T>> {
+        let guards = array::from_fn(|i| Some(self.shard(i).write()));
+        poison::map_result(self.poison.borrow(), |poison| ShardedLockWriteGuard {
+            _guards: guards,
+            lock: self,
+            poison,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Attempts to lock this sharded lock with exclusive write access.
+    ///
+    /// If any of the shards could not be acquired at this time, then `Err` is
+    /// returned, and the shards which were acquired are released. Otherwise,
+    /// an RAII guard is returned which will release the lock when it is
+    /// dropped.
+    ///
+    /// This function does not block.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the lock is poisoned. A sharded
+    /// lock is poisoned whenever a writer panics while holding it. An error
+    /// will only be returned if the lock would have otherwise been acquired.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the lock is not initialized.
+    pub fn try_write(self: Pin<&Self>) -> TryLockResult<ShardedLockWriteGuard<'_, T>> {
+        let mut guards: [Option<sys::WriteGuard<'_>>; SHARDS] = array::from_fn(|_| None);
+        for (i, guard) in guards.iter_mut().enumerate() {
+            *guard = Some(self.shard(i).try_write().ok_or(TryLockError::WouldBlock)?);
+        }
+        Ok(poison::map_result(self.poison.borrow(), |poison| {
+            ShardedLockWriteGuard {
+                _guards: guards,
+                lock: self,
+                poison,
+                _marker: PhantomData,
+            }
+        })?)
+    }
+
+    /// Determines whether the lock is poisoned.
+    ///
+    /// If another thread is active, the lock can still become poisoned at any
+    /// time. You should not trust a `false` value for program correctness
+    /// without additional synchronization.
+    #[inline]
+    pub fn is_poisoned(self: Pin<&Self>) -> bool {
+        self.poison.get()
+    }
+
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// Since this call borrows the `ShardedLock` mutably, no actual locking
+    /// needs to take place -- the mutable borrow statically guarantees no
+    /// locks exist.
+    ///
+    /// # Errors
+    ///
+    /// If another user of this sharded lock panicked while holding it for
+    /// writing, then this call will return an error instead.
+    pub fn get_mut(&mut self) -> LockResult<&mut T> {
+        let data = self.data.get_mut();
+        poison::map_result(self.poison.borrow(), |_| data)
+    }
+
+    #[inline]
+    fn shard(self: Pin<&Self>, index: usize) -> Pin<&sys::RwLock> {
+        // Safety: The shards are structurally pinned.
+        unsafe { self.map_unchecked(|this| &this.shards[index].lock) }
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for ShardedLock<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShardedLock")
+            .field("poisoned", &self.poison.get())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Returns the shard of the current thread.
+///
+/// Threads are assigned shards round-robin the first time they use a sharded
+/// lock, so that the readers of up to `SHARDS` threads never share one.
+fn current_shard() -> usize {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    thread_local! {
+        static SHARD: Cell<Option<usize>> = const { Cell::new(None) };
+    }
+    SHARD.with(|shard| {
+        shard.get().unwrap_or_else(|| {
+            let index = NEXT.fetch_add(1, Relaxed) % SHARDS;
+            shard.set(Some(index));
+            index
+        })
+    })
+}
+
+/// RAII structure used to release the shared read access of a lock when
+/// dropped.
+///
+/// This structure is created by the [`read`] and [`try_read`] methods on
+/// [`ShardedLock`].
+///
+/// [`read`]: ShardedLock::read
+/// [`try_read`]: ShardedLock::try_read
+pub struct ShardedLockReadGuard<'a, T: ?Sized> {
+    _guard: sys::ReadGuard<'a>,
+    lock: Pin<&'a ShardedLock<T>>,
+    _marker: PhantomData<GuardMarker>,
+}
+
+unsafe impl<T: ?Sized + Sync> Sync for ShardedLockReadGuard<'_, T> {}
+
+impl<T: ?Sized> UnwindSafe for ShardedLockReadGuard<'_, T> {}
+
+impl<T: ?Sized> RefUnwindSafe for ShardedLockReadGuard<'_, T> {}
+
+impl<T: ?Sized> Deref for ShardedLockReadGuard<'_, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for ShardedLockReadGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+/// RAII structure used to release the exclusive write access of a lock when
+/// dropped.
+///
+/// This structure is created by the [`write`] and [`try_write`] methods on
+/// [`ShardedLock`].
+///
+/// [`write`]: ShardedLock::write
+/// [`try_write`]: ShardedLock::try_write
+pub struct ShardedLockWriteGuard<'a, T: ?Sized> {
+    // Every shard, which is always `Some` once the guard is returned.
+    _guards: [Option<sys::WriteGuard<'a>>; SHARDS],
+    lock: Pin<&'a ShardedLock<T>>,
+    poison: poison::Guard,
+    _marker: PhantomData<GuardMarker>,
+}
+
+unsafe impl<T: ?Sized + Sync> Sync for ShardedLockWriteGuard<'_, T> {}
+
+impl<T: ?Sized> UnwindSafe for ShardedLockWriteGuard<'_, T> {}
+
+impl<T: ?Sized> RefUnwindSafe for ShardedLockWriteGuard<'_, T> {}
+
+impl<T: ?Sized> Deref for ShardedLockWriteGuard<'_, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<T: ?Sized> DerefMut for ShardedLockWriteGuard<'_, T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.lock.data.get() }
+    }
+}
+
+impl<T: ?Sized> Drop for ShardedLockWriteGuard<'_, T> {
+    #[inline]
+    fn drop(&mut self) {
+        // The shards are released after this, when the fields are dropped.
+        self.lock.poison.done(&self.poison);
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for ShardedLockWriteGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
@@ -0,0 +1,244 @@
+use std::cell::{Cell, UnsafeCell};
+use std::fmt;
+use std::hint;
+use std::marker::PhantomData;
+use std::mem::MaybeUninit;
+use std::ops::{Deref, DerefMut};
+use std::ptr;
+use std::sync::atomic::{AtomicBool, Ordering::*};
+
+/// A restricted spin lock which can be used from signal handlers.
+///
+/// None of the other primitives may be touched from a signal handler: they may
+/// allocate, block in the operating system, or deadlock when the handler
+/// interrupts the thread which holds the lock. A `SignalSafeLock` is
+/// async-signal-safe: it only spins on an atomic flag, and blocks every signal
+/// with `pthread_sigmask` while it is held, so that no handler can run on the
+/// thread which holds it and wait for it forever.
+///
+/// Signals raised while the lock is held are delivered when it is released.
+/// When several guards are held by the same thread, the signal mask is only
+/// restored once the last of them is dropped, whatever the order.
+/// As waiting threads spin, the lock should only protect short critical
+/// sections. It needs no pinning and no initialization, so that it can be
+/// used in a `static`.
+///
+/// There is no poisoning, as unwinding out of a signal handler is undefined
+/// behaviour anyway.
+///
+/// # Examples
+///
+/// ```
+/// use pinned_sync::SignalSafeLock;
+///
+/// static LAST_SIGNAL: SignalSafeLock<Option<i32>> = SignalSafeLock::new(None);
+///
+/// extern "C" fn handler(signal: i32) {
+///     *LAST_SIGNAL.lock() = Some(signal);
+/// }
+///
+/// *LAST_SIGNAL.lock() = None;
+/// handler(10);
+/// assert_eq!(*LAST_SIGNAL.lock(), Some(10));
+/// ```
+pub struct SignalSafeLock<T: ?Sized> {
+    locked: AtomicBool,
+    data: UnsafeCell<T>,
+}
+
+unsafe impl<T: ?Sized + Send> Send for SignalSafeLock<T> {}
+
+unsafe impl<T: ?Sized + Send> Sync for SignalSafeLock<T> {}
+
+impl<T> SignalSafeLock<T> {
+    /// Creates a new signal-safe lock in an unlocked state ready for use.
+    #[inline]
+    pub const fn new(value: T) -> Self {
+        Self {
+            locked: AtomicBool::new(false),
+            data: UnsafeCell::new(value),
+        }
+    }
+
+    /// Consumes this lock, returning the underlying data.
+    pub fn into_inner(self) -> T {
+        self.data.into_inner()
+    }
+}
+
+impl<T: ?Sized> SignalSafeLock<T> {
+    /// Acquires the lock, spinning until it is able to do so.
+    ///
+    /// Every signal is blocked on the current thread until the guard is
+    /// dropped.
+    ///
+    /// This function deadlocks if the lock is already held by the current
+    /// thread.
+    #[inline]
+    pub fn lock(&self) -> SignalSafeLockGuard<'_, T> {
+        let blocked = SignalsBlocked::new();
+        while self
+            .locked
+            .compare_exchange_weak(false, true, Acquire, Relaxed)
+            .is_err()
+        {
+            while self.locked.load(Relaxed) {
+                hint::spin_loop();
+            }
+        }
+        SignalSafeLockGuard {
+            lock: self,
+            _blocked: blocked,
+        }
+    }
+
+    /// Attempts to acquire this lock.
+    ///
+    /// If the lock could not be acquired at this time, then [`None`] is
+    /// returned. Otherwise, an RAII guard is returned.
+    ///
+    /// This function does not block.
+    #[inline]
+    pub fn try_lock(&self) -> Option<SignalSafeLockGuard<'_, T>> {
+        // If the lock is busy, dropping `blocked` unblocks the signals again.
+        let blocked = SignalsBlocked::new();
+        if self
+            .locked
+            .compare_exchange(false, true, Acquire, Relaxed)
+            .is_ok()
+        {
+            Some(SignalSafeLockGuard {
+                lock: self,
+                _blocked: blocked,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// Since this call borrows the `SignalSafeLock` mutably, no actual locking
+    /// needs to take place -- the mutable borrow statically guarantees no
+    /// locks exist.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.data.get_mut()
+    }
+}
+
+impl<T: Default> Default for SignalSafeLock<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for SignalSafeLock<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SignalSafeLock")
+            .field("locked", &self.locked.load(Relaxed))
+            .finish_non_exhaustive()
+    }
+}
+
+/// An RAII implementation of a "scoped lock" of a [`SignalSafeLock`]. When
+/// this structure is dropped (falls out of scope), the lock will be unlocked
+/// and the signal mask of the thread restored.
+///
+/// The data protected by the lock can be accessed through this guard via its
+/// [`Deref`] and [`DerefMut`] implementations.
+pub struct SignalSafeLockGuard<'a, T: ?Sized> {
+    lock: &'a SignalSafeLock<T>,
+    // Dropped after the lock is released.
+    _blocked: SignalsBlocked,
+}
+
+unsafe impl<T: ?Sized + Sync> Sync for SignalSafeLockGuard<'_, T> {}
+
+impl<T: ?Sized> Deref for SignalSafeLockGuard<'_, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<T: ?Sized> DerefMut for SignalSafeLockGuard<'_, T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.lock.data.get() }
+    }
+}
+
+impl<T: ?Sized> Drop for SignalSafeLockGuard<'_, T> {
+    #[inline]
+    fn drop(&mut self) {
+        self.lock.locked.store(false, Release);
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for SignalSafeLockGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+thread_local! {
+    // The number of `SignalsBlocked` alive on the current thread, and the
+    // signal mask of the thread from before the first of them was created.
+    // Neither needs a destructor, so they are plain thread-local variables,
+    // which are async-signal-safe to access.
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+    static SAVED_MASK: Cell<MaybeUninit<libc::sigset_t>> =
+        const { Cell::new(MaybeUninit::uninit()) };
+}
+
+/// Every signal blocked on the current thread, for as long as this is alive.
+///
+/// These nest: the signal mask of the thread is saved by the first one and
+/// only restored once the last one is dropped, in whichever order they are.
+/// While any is alive, no signal handler can run on the thread, so that the
+/// count can not be changed behind its back.
+struct SignalsBlocked {
+    // The signal mask belongs to the thread which created it.
+    _not_send: PhantomData<*const ()>,
+}
+
+impl SignalsBlocked {
+    #[inline]
+    fn new() -> Self {
+        // Safety: `sigfillset` and `pthread_sigmask` are async-signal-safe,
+        // and only fail on invalid arguments.
+        let old = unsafe {
+            let mut all = MaybeUninit::uninit();
+            let mut old = MaybeUninit::uninit();
+            libc::sigfillset(all.as_mut_ptr());
+            let r = libc::pthread_sigmask(libc::SIG_BLOCK, all.as_ptr(), old.as_mut_ptr());
+            debug_assert_eq!(r, 0);
+            old
+        };
+        let depth = DEPTH.with(Cell::get);
+        if depth == 0 {
+            SAVED_MASK.with(|saved| saved.set(old));
+        }
+        DEPTH.with(|d| d.set(depth + 1));
+        SignalsBlocked {
+            _not_send: PhantomData,
+        }
+    }
+}
+
+impl Drop for SignalsBlocked {
+    #[inline]
+    fn drop(&mut self) {
+        let depth = DEPTH.with(Cell::get) - 1;
+        DEPTH.with(|d| d.set(depth));
+        if depth == 0 {
+            let saved = SAVED_MASK.with(Cell::get);
+            unsafe {
+                let r = libc::pthread_sigmask(libc::SIG_SETMASK, saved.as_ptr(), ptr::null_mut());
+                debug_assert_eq!(r, 0);
+            }
+        }
+    }
+}
@@ -0,0 +1,237 @@
+use crate::sys_common::backoff::Backoff;
+use crate::Unlockable;
+use std::cell::UnsafeCell;
+use std::fmt;
+use std::marker::{PhantomData, PhantomPinned};
+use std::ops::{Deref, DerefMut};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering::*};
+use std::sync::Arc;
+
+/// A mutual exclusion primitive which spins instead of blocking.
+///
+/// This has the same shape as [`Mutex`], but a thread waiting for the lock
+/// never blocks in the operating system: it retries, spinning for twice as
+/// long after each failed attempt, up to a limit. This makes it cheaper than a
+/// [`Mutex`] for very short critical sections, which are over before blocking
+/// would have paid off, and usable on targets without an operating system, as
+/// with the `spin` feature. It should not be held for long, nor across
+/// anything which may block.
+///
+/// There is no poisoning: a panic while the lock is held releases it, and the
+/// next thread to lock it gets the guard.
+///
+/// [`Mutex`]: crate::Mutex
+///
+/// # Examples
+///
+/// ```
+/// use pinned_sync::SpinLock;
+/// use std::thread;
+///
+/// let counter = SpinLock::arc(0);
+///
+/// let threads: Vec<_> = (0..4)
+///     .map(|_| {
+///         let counter = counter.clone();
+///         thread::spawn(move || *counter.as_ref().lock() += 1)
+///     })
+///     .collect();
+/// for thread in threads {
+///     thread.join().unwrap();
+/// }
+/// assert_eq!(*counter.as_ref().lock(), 4);
+/// ```
+pub struct SpinLock<T: ?Sized> {
+    locked: AtomicBool,
+    _p: PhantomPinned,
+    data: UnsafeCell<T>,
+}
+
+unsafe impl<T: ?Sized + Send> Send for SpinLock<T> {}
+
+unsafe impl<T: ?Sized + Send> Sync for SpinLock<T> {}
+
+impl<T> SpinLock<T> {
+    /// Create a new, uninitialized spin lock.
+    ///
+    /// This is *NOT* equivalent to `MaybeUninit::uninit().assume_init()`, which will cause
+    /// undefined behaviour if used to create a new spin lock.
+    #[inline]
+    pub const fn uninit(value: T) -> Self {
+        Self {
+            locked: AtomicBool::new(false),
+            _p: PhantomPinned,
+            data: UnsafeCell::new(value),
+        }
+    }
+
+    /// Create a new, initialized spin lock.
+    ///
+    /// The resulting spin lock is wrapped and ready for use.
+    #[inline]
+    pub fn boxed(value: T) -> Pin<Box<Self>> {
+        let this = Box::pin(Self::uninit(value));
+        this.as_ref().init();
+        this
+    }
+
+    /// Create a new, initialized spin lock.
+    ///
+    /// The resulting spin lock is wrapped and ready for use.
+    #[inline]
+    pub fn arc(value: T) -> Pin<Arc<Self>> {
+        let this = Arc::pin(Self::uninit(value));
+        this.as_ref().init();
+        this
+    }
+
+    /// Consumes this spin lock, returning the underlying data.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.data.into_inner()
+    }
+}
+
+impl<T: ?Sized> SpinLock<T> {
+    /// Initialize a spin lock, making it ready for use.
+    ///
+    /// A spin lock needs no initialization, so this does nothing. It is there
+    /// so that a spin lock can be used in place of a [`Mutex`].
+    ///
+    /// [`Mutex`]: crate::Mutex
+    #[inline]
+    pub fn init(self: Pin<&Self>) {}
+
+    /// Acquires the spin lock, spinning until it is able to do so.
+    ///
+    /// This function deadlocks if the lock is already held by the current
+    /// thread.
+    #[inline]
+    pub fn lock(self: Pin<&Self>) -> SpinLockGuard<'_, T> {
+        if self
+            .locked
+            .compare_exchange_weak(false, true, Acquire, Relaxed)
+            .is_err()
+        {
+            self.lock_contended();
+        }
+        SpinLockGuard {
+            lock: self,
+            _p: PhantomData,
+        }
+    }
+
+    #[cold]
+    fn lock_contended(&self) {
+        let mut backoff = Backoff::new();
+        loop {
+            // Only read the lock while it is held, so that the cache line is
+            // not fought over.
+            while self.locked.load(Relaxed) {
+                backoff.spin();
+            }
+            if self
+                .locked
+                .compare_exchange_weak(false, true, Acquire, Relaxed)
+                .is_ok()
+            {
+                return;
+            }
+        }
+    }
+
+    /// Attempts to acquire this spin lock.
+    ///
+    /// If the lock could not be acquired at this time, then [`None`] is
+    /// returned. Otherwise, an RAII guard is returned.
+    ///
+    /// This function does not spin.
+    #[inline]
+    pub fn try_lock(self: Pin<&Self>) -> Option<SpinLockGuard<'_, T>> {
+        if self
+            .locked
+            .compare_exchange(false, true, Acquire, Relaxed)
+            .is_ok()
+        {
+            Some(SpinLockGuard {
+                lock: self,
+                _p: PhantomData,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// Since this call borrows the `SpinLock` mutably, no actual locking needs
+    /// to take place -- the mutable borrow statically guarantees no locks
+    /// exist.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut T {
+        self.data.get_mut()
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for SpinLock<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SpinLock")
+            .field("locked", &self.locked.load(Relaxed))
+            .finish_non_exhaustive()
+    }
+}
+
+/// An RAII implementation of a "scoped lock" of a [`SpinLock`]. When this
+/// structure is dropped (falls out of scope), the lock will be unlocked.
+///
+/// The data protected by the lock can be accessed through this guard via its
+/// [`Deref`] and [`DerefMut`] implementations.
+pub struct SpinLockGuard<'a, T: ?Sized> {
+    lock: Pin<&'a SpinLock<T>>,
+    // Gives the guard access to `T` mutably, for variance and auto traits.
+    _p: PhantomData<&'a mut T>,
+}
+
+unsafe impl<T: ?Sized + Sync> Sync for SpinLockGuard<'_, T> {}
+
+impl<T: ?Sized> Deref for SpinLockGuard<'_, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<T: ?Sized> DerefMut for SpinLockGuard<'_, T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.lock.data.get() }
+    }
+}
+
+impl<T: ?Sized> Unlockable for SpinLockGuard<'_, T> {
+    type Relocked = Self;
+
+    #[inline]
+    fn with_unlocked(self, f: impl FnOnce()) -> Self {
+        let lock = self.lock;
+        drop(self);
+        f();
+        lock.lock()
+    }
+}
+
+impl<T: ?Sized> Drop for SpinLockGuard<'_, T> {
+    #[inline]
+    fn drop(&mut self) {
+        self.lock.locked.store(false, Release);
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for SpinLockGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
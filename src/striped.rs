@@ -0,0 +1,138 @@
+use crate::Primitives;
+use std::array;
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::pin::Pin;
+use std::sync::Arc;
+
+/// A fixed array of locks, selected by the hash of a key.
+///
+/// Lock striping protects a large table with `N` locks instead of one lock per
+/// entry, or one lock for the whole table: each entry is protected by the
+/// lock chosen by [`get_for`] from the hash of its key. Entries whose keys
+/// hash to different stripes can be accessed concurrently.
+///
+/// The locks are pinned and initialized together with the array.
+///
+/// [`get_for`]: Self::get_for
+///
+/// # Examples
+///
+/// ```
+/// use pinned_sync::{Mutex, Striped};
+///
+/// let locks = Striped::<64, Mutex<()>>::boxed(|_| Mutex::uninit(()));
+/// let guard = locks.as_ref().get_for("some key").lock().unwrap();
+/// // Access the entries of "some key" here.
+/// drop(guard);
+/// ```
+pub struct Striped<const N: usize, L> {
+    locks: [L; N],
+}
+
+impl<const N: usize, L> Striped<N, L> {
+    /// Create a new, uninitialized array of locks.
+    ///
+    /// This is *NOT* equivalent to `MaybeUninit::uninit().assume_init()`, which will cause
+    /// undefined behaviour if used to create a new array of locks.
+    #[inline]
+    pub const fn uninit(locks: [L; N]) -> Self {
+        Self { locks }
+    }
+
+    /// Create a new, uninitialized array of locks, with the lock returned by
+    /// `f` for each index.
+    #[inline]
+    pub fn from_fn<F: FnMut(usize) -> L>(f: F) -> Self {
+        Self::uninit(array::from_fn(f))
+    }
+
+    /// Returns the number of locks, `N`.
+    #[inline]
+    pub const fn len(&self) -> usize {
+        N
+    }
+
+    /// Returns `true` if there are no locks.
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        N == 0
+    }
+
+    /// Returns the lock at the given index.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `index` is out of bounds.
+    #[inline]
+    pub fn get(self: Pin<&Self>, index: usize) -> Pin<&L> {
+        // Safety: The locks are structurally pinned.
+        unsafe { self.map_unchecked(|this| &this.locks[index]) }
+    }
+
+    /// Returns the lock protecting the given key.
+    ///
+    /// Equal keys always map to the same lock.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if there are no locks.
+    #[inline]
+    pub fn get_for<Q: Hash + ?Sized>(self: Pin<&Self>, key: &Q) -> Pin<&L> {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        self.get(hasher.finish() as usize % N)
+    }
+}
+
+impl<const N: usize, L: Primitives> Striped<N, L> {
+    /// Create a new, initialized array of locks, with the lock returned by `f`
+    /// for each index.
+    ///
+    /// The resulting array of locks is wrapped and ready for use.
+    #[inline]
+    pub fn boxed<F: FnMut(usize) -> L>(f: F) -> Pin<Box<Self>> {
+        let this = Box::pin(Self::from_fn(f));
+        this.as_ref().init();
+        this
+    }
+
+    /// Create a new, initialized array of locks, with the lock returned by `f`
+    /// for each index.
+    ///
+    /// The resulting array of locks is wrapped and ready for use.
+    #[inline]
+    pub fn arc<F: FnMut(usize) -> L>(f: F) -> Pin<Arc<Self>> {
+        let this = Arc::pin(Self::from_fn(f));
+        this.as_ref().init();
+        this
+    }
+
+    /// Initialize every lock of the array, making it ready for use.
+    ///
+    /// # Panics
+    ///
+    /// This function may panic if the locks were already initialized.
+    pub fn init(self: Pin<&Self>) {
+        for i in 0..N {
+            self.get(i).init_pinned();
+        }
+    }
+}
+
+impl<const N: usize, L: Primitives> Primitives for Striped<N, L> {
+    type Pinned<'a> = Pin<&'a Self> where Self: 'a;
+
+    #[inline]
+    fn init_pinned(self: Pin<&Self>) -> Pin<&Self> {
+        self.init();
+        self
+    }
+}
+
+impl<const N: usize, L> fmt::Debug for Striped<N, L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Striped").field("len", &N).finish_non_exhaustive()
+    }
+}
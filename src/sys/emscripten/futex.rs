@@ -0,0 +1,55 @@
+use std::ffi::c_void;
+use std::hint;
+use std::os::raw::c_int;
+use std::sync::atomic::{AtomicU32, Ordering::Relaxed};
+use std::time::{Duration, Instant};
+
+extern "C" {
+    fn emscripten_futex_wait(addr: *const c_void, val: u32, max_wait_ms: f64) -> c_int;
+    fn emscripten_futex_wake(addr: *const c_void, count: c_int) -> c_int;
+    fn emscripten_is_main_browser_thread() -> c_int;
+}
+
+/// Blocks the current thread while `futex` is `expected`, until it is woken up
+/// by [`wake`] or the timeout expires.
+///
+/// Returns `false` if the timeout expired. Like any futex wait, this may also
+/// return spuriously.
+///
+/// The main thread of a browser is not allowed to block, so it spins until
+/// `futex` changes instead.
+pub fn wait(futex: &AtomicU32, expected: u32, timeout: Option<Duration>) -> bool {
+    if unsafe { emscripten_is_main_browser_thread() } != 0 {
+        return spin(futex, expected, timeout);
+    }
+    let timeout = match timeout {
+        Some(dur) => dur.as_secs_f64() * 1000.0,
+        None => f64::INFINITY,
+    };
+    let r = unsafe { emscripten_futex_wait(futex.as_ptr().cast(), expected, timeout) };
+    r != -libc::ETIMEDOUT
+}
+
+// Spins while `futex` is `expected`. Nothing is woken up by `wake` in this
+// case, as nothing sleeps.
+fn spin(futex: &AtomicU32, expected: u32, timeout: Option<Duration>) -> bool {
+    // A timeout which can not be represented is as good as no timeout.
+    let deadline = timeout.and_then(|dur| Instant::now().checked_add(dur));
+    while futex.load(Relaxed) == expected {
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            return false;
+        }
+        hint::spin_loop();
+    }
+    true
+}
+
+/// Wakes up at most `count` threads blocked in [`wait`] on `futex`.
+///
+/// Returns whether any thread was woken up. A spinning main thread is not
+/// counted, as it only notices the change of `futex` by itself.
+#[inline]
+pub fn wake(futex: &AtomicU32, count: i32) -> bool {
+    let woken = unsafe { emscripten_futex_wake(futex.as_ptr().cast(), count) };
+    woken > 0
+}
@@ -0,0 +1,19 @@
+//! A backend built on the futex functions of Emscripten.
+//!
+//! `emscripten_futex_wait` and `emscripten_futex_wake` are the wait and wake
+//! operations of a Linux futex under another name, so the primitives are the
+//! ones of the Linux backend, built on top of them instead. Like there, an
+//! uninitialized `Mutex`, `Condvar` or `RwLock` is already usable.
+//!
+//! Browsers do not allow the main thread to block, so it busy-waits instead,
+//! which lets it wait on locks and condition variables like any other thread.
+
+#[path = "../linux/condvar.rs"]
+pub mod condvar;
+#[path = "../linux/mutex.rs"]
+pub mod mutex;
+#[path = "../linux/rwlock.rs"]
+pub mod rwlock;
+
+use super::futex;
+pub use crate::sys_common::{barrier, remutex};
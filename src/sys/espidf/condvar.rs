@@ -0,0 +1,225 @@
+use super::freertos::Semaphore;
+use crate::sys;
+use crate::sys_common::init_assert::InitAssert;
+use std::cell::{Cell, UnsafeCell};
+use std::marker::PhantomPinned;
+use std::pin::Pin;
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, Ordering::*};
+use std::time::Duration;
+
+thread_local! {
+    // The semaphore which the current thread blocks on while waiting.
+    static PARKER: Semaphore = Semaphore::binary();
+}
+
+// A waiting thread, on its own stack. It is unlinked by whoever wakes it up,
+// or by itself when its timeout expires.
+struct Waiter {
+    parker: *const Semaphore,
+    next: Cell<*const Waiter>,
+    notified: Cell<bool>,
+}
+
+struct Queue {
+    head: *const Waiter,
+    tail: *const Waiter,
+}
+
+pub struct Condvar {
+    // Protects `queue`. This is a FreeRTOS mutex rather than a spin lock, as
+    // a thread spinning could keep the holder from running on its core.
+    lock: InitAssert<Semaphore>,
+    queue: UnsafeCell<Queue>,
+    mutex: AtomicPtr<sys::mutex::Mutex>,
+    _p: PhantomPinned,
+}
+
+unsafe impl Send for Condvar {}
+unsafe impl Sync for Condvar {}
+
+impl Condvar {
+    #[inline]
+    pub const fn uninit() -> Self {
+        Self {
+            lock: InitAssert::new(),
+            queue: UnsafeCell::new(Queue {
+                head: ptr::null(),
+                tail: ptr::null(),
+            }),
+            mutex: AtomicPtr::new(ptr::null_mut()),
+            _p: PhantomPinned,
+        }
+    }
+
+    pub fn init(self: Pin<&Self>) {
+        self.lock.init(Semaphore::mutex);
+    }
+
+    #[inline]
+    pub fn notify_one(self: Pin<&Self>) {
+        self.with_queue(|queue| {
+            if let Some(waiter) = queue.pop() {
+                unsafe { wake(waiter) };
+            }
+        });
+    }
+
+    #[inline]
+    pub fn notify_all(self: Pin<&Self>) {
+        self.with_queue(|queue| {
+            while let Some(waiter) = queue.pop() {
+                unsafe { wake(waiter) };
+            }
+        });
+    }
+
+    #[inline]
+    pub fn notify_many(self: Pin<&Self>, n: usize) {
+        self.with_queue(|queue| {
+            for _ in 0..n {
+                match queue.pop() {
+                    Some(waiter) => unsafe { wake(waiter) },
+                    None => break,
+                }
+            }
+        });
+    }
+
+    #[inline]
+    pub unsafe fn wait<'a>(
+        self: Pin<&Self>,
+        lock: sys::mutex::MutexGuard<'a>,
+    ) -> sys::mutex::MutexGuard<'a> {
+        self.sleep(lock, None).1
+    }
+
+    #[inline]
+    pub unsafe fn wait_timeout<'a>(
+        &self,
+        lock: sys::mutex::MutexGuard<'a>,
+        dur: Duration,
+    ) -> (bool, sys::mutex::MutexGuard<'a>) {
+        self.sleep(lock, Some(dur))
+    }
+
+    unsafe fn sleep<'a>(
+        &self,
+        lock: sys::mutex::MutexGuard<'a>,
+        timeout: Option<Duration>,
+    ) -> (bool, sys::mutex::MutexGuard<'a>) {
+        let mutex = lock.mutex;
+        self.verify(&mutex);
+
+        PARKER.with(|parker| {
+            let waiter = Waiter {
+                parker,
+                next: Cell::new(ptr::null()),
+                notified: Cell::new(false),
+            };
+            // Queued before the mutex is unlocked, so no notification sent
+            // after that can be missed.
+            self.with_queue(|queue| queue.push(&waiter));
+            drop(lock);
+
+            let notified = match timeout {
+                None => {
+                    parker.take();
+                    true
+                }
+                Some(dur) => {
+                    parker.take_timeout(dur)
+                        || self.with_queue(|queue| {
+                            if waiter.notified.get() {
+                                // Notified after the timeout expired. The
+                                // semaphore was given under the lock, and is
+                                // taken so that the next wait does not return
+                                // right away.
+                                parker.take();
+                                return true;
+                            }
+                            queue.remove(&waiter);
+                            false
+                        })
+                }
+            };
+            (notified, mutex.lock())
+        })
+    }
+
+    fn with_queue<R>(&self, f: impl FnOnce(&mut Queue) -> R) -> R {
+        let lock = self.lock.get_ref();
+        lock.take();
+        let r = f(unsafe { &mut *self.queue.get() });
+        lock.give();
+        r
+    }
+
+    // Waiting on the same condition variable with different mutexes is not
+    // supported by the other backends, so we remember the first mutex and
+    // panic if another one is ever used.
+    #[inline]
+    fn verify(&self, mutex: &sys::mutex::Mutex) {
+        let mutex = mutex as *const _ as *mut _;
+        match self
+            .mutex
+            .compare_exchange(ptr::null_mut(), mutex, Relaxed, Relaxed)
+        {
+            Ok(_) => {}
+            Err(addr) if addr == mutex => {}
+            Err(_) => panic!("attempted to use a condition variable with two mutexes"),
+        }
+    }
+}
+
+// Gives the semaphore of a waiter which was just unlinked, with the lock of
+// the queue held. The waiter does not return before it sees the semaphore
+// given, so it is still there.
+unsafe fn wake(waiter: *const Waiter) {
+    (*waiter).notified.set(true);
+    (*(*waiter).parker).give();
+}
+
+impl Queue {
+    fn push(&mut self, waiter: &Waiter) {
+        if self.tail.is_null() {
+            self.head = waiter;
+        } else {
+            unsafe { (*self.tail).next.set(waiter) };
+        }
+        self.tail = waiter;
+    }
+
+    fn pop(&mut self) -> Option<*const Waiter> {
+        if self.head.is_null() {
+            return None;
+        }
+        let waiter = self.head;
+        self.head = unsafe { (*waiter).next.get() };
+        if self.head.is_null() {
+            self.tail = ptr::null();
+        }
+        Some(waiter)
+    }
+
+    fn remove(&mut self, waiter: &Waiter) {
+        let mut prev: *const Waiter = ptr::null();
+        let mut current = self.head;
+        while !current.is_null() {
+            if ptr::eq(current, waiter) {
+                let next = waiter.next.get();
+                if prev.is_null() {
+                    self.head = next;
+                } else {
+                    unsafe { (*prev).next.set(next) };
+                }
+                if ptr::eq(self.tail, waiter) {
+                    self.tail = prev;
+                }
+                return;
+            }
+            prev = current;
+            current = unsafe { (*current).next.get() };
+        }
+    }
+}
@@ -0,0 +1,102 @@
+//! The few FreeRTOS functions which semaphores are built on.
+//!
+//! Semaphores are queues of zero-sized items in FreeRTOS, and most of the
+//! semaphore API is macros over the queue API, which are expanded here.
+
+use std::convert::TryInto;
+use std::ffi::c_void;
+use std::ptr;
+use std::time::Duration;
+
+type QueueHandle = *mut c_void;
+type TickType = u32;
+
+// `portMAX_DELAY`, which blocks forever.
+const MAX_DELAY: TickType = TickType::MAX;
+
+const QUEUE_TYPE_MUTEX: u8 = 1;
+const QUEUE_TYPE_BINARY_SEMAPHORE: u8 = 3;
+const SEND_TO_BACK: i32 = 0;
+const PD_TRUE: i32 = 1;
+
+extern "C" {
+    fn xQueueCreateMutex(queue_type: u8) -> QueueHandle;
+    fn xQueueGenericCreate(length: u32, item_size: u32, queue_type: u8) -> QueueHandle;
+    fn xQueueSemaphoreTake(queue: QueueHandle, ticks_to_wait: TickType) -> i32;
+    fn xQueueGenericSend(
+        queue: QueueHandle,
+        item: *const c_void,
+        ticks_to_wait: TickType,
+        copy_position: i32,
+    ) -> i32;
+    fn vQueueDelete(queue: QueueHandle);
+    fn xPortGetTickRateHz() -> u32;
+}
+
+/// A FreeRTOS semaphore, deleted on drop.
+pub struct Semaphore(QueueHandle);
+
+unsafe impl Send for Semaphore {}
+unsafe impl Sync for Semaphore {}
+
+impl Semaphore {
+    /// Creates a mutex, which must be given back by the thread which took it.
+    pub fn mutex() -> Self {
+        Self::new(unsafe { xQueueCreateMutex(QUEUE_TYPE_MUTEX) })
+    }
+
+    /// Creates a binary semaphore, which starts out empty.
+    pub fn binary() -> Self {
+        Self::new(unsafe { xQueueGenericCreate(1, 0, QUEUE_TYPE_BINARY_SEMAPHORE) })
+    }
+
+    fn new(handle: QueueHandle) -> Self {
+        assert!(!handle.is_null(), "failed to allocate a FreeRTOS semaphore");
+        Self(handle)
+    }
+
+    /// Blocks until the semaphore is taken.
+    #[inline]
+    pub fn take(&self) {
+        let r = unsafe { xQueueSemaphoreTake(self.0, MAX_DELAY) };
+        debug_assert_eq!(r, PD_TRUE);
+    }
+
+    /// Takes the semaphore if it is available, without blocking.
+    #[inline]
+    pub fn try_take(&self) -> bool {
+        unsafe { xQueueSemaphoreTake(self.0, 0) == PD_TRUE }
+    }
+
+    /// Blocks until the semaphore is taken or the timeout expires.
+    ///
+    /// Returns `false` if the timeout expired. Timeouts too long to be
+    /// represented in ticks are cut short, which also returns `true`, as a
+    /// spurious wakeup.
+    pub fn take_timeout(&self, dur: Duration) -> bool {
+        let hz = u128::from(unsafe { xPortGetTickRateHz() });
+        // Rounded up, so that the thread sleeps for at least `dur`.
+        let ticks = (dur.as_nanos() * hz).div_ceil(1_000_000_000);
+        match ticks.try_into() {
+            Ok(ticks) if ticks < MAX_DELAY => unsafe {
+                xQueueSemaphoreTake(self.0, ticks) == PD_TRUE
+            },
+            _ => {
+                unsafe { xQueueSemaphoreTake(self.0, MAX_DELAY - 1) };
+                true
+            }
+        }
+    }
+
+    #[inline]
+    pub fn give(&self) {
+        let r = unsafe { xQueueGenericSend(self.0, ptr::null(), 0, SEND_TO_BACK) };
+        debug_assert_eq!(r, PD_TRUE);
+    }
+}
+
+impl Drop for Semaphore {
+    fn drop(&mut self) {
+        unsafe { vQueueDelete(self.0) }
+    }
+}
@@ -0,0 +1,17 @@
+//! A backend for ESP-IDF, built on FreeRTOS semaphores.
+//!
+//! The pthread functions of ESP-IDF are emulated on top of FreeRTOS, with
+//! extra allocations and bookkeeping for every object, so the primitives are
+//! built on FreeRTOS directly instead:
+//!
+//! - `Mutex` is a FreeRTOS mutex, which also gives it priority inheritance.
+//! - `Condvar` keeps a queue of waiting threads, each blocked on a binary
+//!   semaphore of its own, which notifications give.
+//! - `RwLock` is built on the two above.
+
+pub mod condvar;
+mod freertos;
+pub mod mutex;
+pub mod rwlock;
+
+pub use crate::sys_common::{barrier, remutex};
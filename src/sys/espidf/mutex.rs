@@ -0,0 +1,94 @@
+use super::freertos::Semaphore;
+use crate::sys_common::init_assert::InitAssert;
+use std::marker::PhantomPinned;
+use std::pin::Pin;
+use std::thread;
+use std::time::Instant;
+
+pub struct Mutex {
+    lock: InitAssert<Semaphore>,
+    _p: PhantomPinned,
+}
+
+unsafe impl Send for Mutex {}
+unsafe impl Sync for Mutex {}
+
+impl Mutex {
+    #[inline]
+    pub const fn uninit() -> Self {
+        Self {
+            lock: InitAssert::new(),
+            _p: PhantomPinned,
+        }
+    }
+
+    // FreeRTOS wakes up the waiter of the highest priority, which then takes
+    // the mutex before anyone else can, so there is nothing to configure.
+    #[inline]
+    pub fn handoff(self, _enabled: bool) -> Self {
+        self
+    }
+
+    pub fn init(self: Pin<&Self>) {
+        self.lock.init(Semaphore::mutex);
+    }
+
+    /// ESP-IDF has no `fork`, so this is never called.
+    pub unsafe fn reinit_after_fork(self: Pin<&Self>) {}
+
+    #[inline]
+    pub fn lock(self: Pin<&Self>) -> MutexGuard<'_> {
+        self.lock.get_ref().take();
+        MutexGuard { mutex: self }
+    }
+
+    #[inline]
+    pub fn try_lock(self: Pin<&Self>) -> Option<MutexGuard<'_>> {
+        if self.lock.get_ref().try_take() {
+            Some(MutexGuard { mutex: self })
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    pub fn try_lock_until(self: Pin<&Self>, deadline: Instant) -> Option<MutexGuard<'_>> {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        if self.lock.get_ref().take_timeout(timeout) {
+            Some(MutexGuard { mutex: self })
+        } else {
+            None
+        }
+    }
+
+    /// Unlocks the mutex without a guard.
+    ///
+    /// # Safety
+    ///
+    /// The mutex must be locked, and the guard which locked it forgotten.
+    #[inline]
+    pub unsafe fn unlock(self: Pin<&Self>) {
+        drop(MutexGuard { mutex: self });
+    }
+}
+
+pub struct MutexGuard<'a> {
+    pub(super) mutex: Pin<&'a Mutex>,
+}
+impl<'a> MutexGuard<'a> {
+    /// Unlocks the mutex, lets the threads waiting for it run, and locks it
+    /// again.
+    #[inline]
+    pub fn bump(self) -> Self {
+        let mutex = self.mutex;
+        drop(self);
+        thread::yield_now();
+        mutex.lock()
+    }
+}
+impl Drop for MutexGuard<'_> {
+    #[inline]
+    fn drop(&mut self) {
+        self.mutex.lock.get_ref().give();
+    }
+}
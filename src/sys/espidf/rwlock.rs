@@ -0,0 +1,244 @@
+use super::condvar::Condvar;
+use super::mutex::{Mutex, MutexGuard};
+use crate::sys::ReadError;
+use std::cell::UnsafeCell;
+use std::marker::PhantomPinned;
+use std::pin::Pin;
+use std::time::Instant;
+
+struct State {
+    readers: usize,
+    writer: bool,
+    // New readers stay out while writers wait, so that they are not starved.
+    writers_waiting: usize,
+}
+
+pub struct RwLock {
+    mutex: Mutex,
+    readers: Condvar,
+    writers: Condvar,
+    state: UnsafeCell<State>,
+    _p: PhantomPinned,
+}
+
+unsafe impl Send for RwLock {}
+unsafe impl Sync for RwLock {}
+
+impl RwLock {
+    #[inline]
+    pub const fn uninit() -> Self {
+        Self {
+            mutex: Mutex::uninit(),
+            readers: Condvar::uninit(),
+            writers: Condvar::uninit(),
+            state: UnsafeCell::new(State {
+                readers: 0,
+                writer: false,
+                writers_waiting: 0,
+            }),
+            _p: PhantomPinned,
+        }
+    }
+
+    pub fn init(self: Pin<&Self>) {
+        self.mutex().init();
+        self.readers().init();
+        self.writers().init();
+    }
+
+    /// ESP-IDF has no `fork`, so this is never called.
+    pub unsafe fn reinit_after_fork(self: Pin<&Self>) {}
+
+    pub fn try_read(self: Pin<&Self>) -> Result<ReadGuard<'_>, ReadError> {
+        let _lock = self.mutex().lock();
+        let state = unsafe { &mut *self.state.get() };
+        if state.writer || state.writers_waiting > 0 {
+            return Err(ReadError::WouldBlock);
+        }
+        if state.readers == usize::MAX {
+            return Err(ReadError::TooManyReaders);
+        }
+        state.readers += 1;
+        Ok(ReadGuard { lock: self })
+    }
+
+    /// Returns `None` if the maximum number of readers was reached.
+    pub fn read(self: Pin<&Self>) -> Option<ReadGuard<'_>> {
+        let mut lock = self.mutex().lock();
+        loop {
+            let state = unsafe { &mut *self.state.get() };
+            if !state.writer && state.writers_waiting == 0 {
+                if state.readers == usize::MAX {
+                    return None;
+                }
+                state.readers += 1;
+                return Some(ReadGuard { lock: self });
+            }
+            lock = unsafe { self.readers().wait(lock) };
+        }
+    }
+
+    pub fn try_write(self: Pin<&Self>) -> Option<WriteGuard<'_>> {
+        let _lock = self.mutex().lock();
+        let state = unsafe { &mut *self.state.get() };
+        if state.writer || state.readers > 0 {
+            return None;
+        }
+        state.writer = true;
+        Some(WriteGuard { lock: self })
+    }
+
+    pub fn write(self: Pin<&Self>) -> WriteGuard<'_> {
+        let mut lock = self.mutex().lock();
+        unsafe { (*self.state.get()).writers_waiting += 1 };
+        loop {
+            let state = unsafe { &mut *self.state.get() };
+            if !state.writer && state.readers == 0 {
+                state.writers_waiting -= 1;
+                state.writer = true;
+                return WriteGuard { lock: self };
+            }
+            lock = unsafe { self.writers().wait(lock) };
+        }
+    }
+
+    /// Returns [`ReadError::WouldBlock`] if the deadline was reached.
+    pub fn try_read_until(self: Pin<&Self>, deadline: Instant) -> Result<ReadGuard<'_>, ReadError> {
+        let mut lock = self.mutex().lock();
+        loop {
+            let state = unsafe { &mut *self.state.get() };
+            if !state.writer && state.writers_waiting == 0 {
+                if state.readers == usize::MAX {
+                    return Err(ReadError::TooManyReaders);
+                }
+                state.readers += 1;
+                return Ok(ReadGuard { lock: self });
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(ReadError::WouldBlock);
+            }
+            lock = unsafe {
+                self.readers()
+                    .get_ref()
+                    .wait_timeout(lock, deadline - now)
+                    .1
+            };
+        }
+    }
+
+    pub fn try_write_until(self: Pin<&Self>, deadline: Instant) -> Option<WriteGuard<'_>> {
+        let mut lock = self.mutex().lock();
+        unsafe { (*self.state.get()).writers_waiting += 1 };
+        loop {
+            let state = unsafe { &mut *self.state.get() };
+            if !state.writer && state.readers == 0 {
+                state.writers_waiting -= 1;
+                state.writer = true;
+                return Some(WriteGuard { lock: self });
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                state.writers_waiting -= 1;
+                // The readers which were kept out for this writer may go in.
+                if state.writers_waiting == 0 && !state.writer {
+                    self.readers().notify_all();
+                }
+                return None;
+            }
+            lock = unsafe {
+                self.writers()
+                    .get_ref()
+                    .wait_timeout(lock, deadline - now)
+                    .1
+            };
+        }
+    }
+
+    // Runs `f` on the state with the inner mutex locked.
+    fn with_state<R>(self: Pin<&Self>, f: impl FnOnce(&mut State) -> R) -> R {
+        let _lock: MutexGuard<'_> = self.mutex().lock();
+        f(unsafe { &mut *self.state.get() })
+    }
+
+    #[inline]
+    fn mutex(self: Pin<&Self>) -> Pin<&Mutex> {
+        unsafe { self.map_unchecked(|this| &this.mutex) }
+    }
+
+    #[inline]
+    fn readers(self: Pin<&Self>) -> Pin<&Condvar> {
+        unsafe { self.map_unchecked(|this| &this.readers) }
+    }
+
+    #[inline]
+    fn writers(self: Pin<&Self>) -> Pin<&Condvar> {
+        unsafe { self.map_unchecked(|this| &this.writers) }
+    }
+
+    /// Releases a read lock without a guard.
+    ///
+    /// # Safety
+    ///
+    /// The lock must be read locked, and the guard which locked it forgotten.
+    #[inline]
+    pub unsafe fn read_unlock(self: Pin<&Self>) {
+        drop(ReadGuard { lock: self });
+    }
+
+    /// Releases the write lock without a guard.
+    ///
+    /// # Safety
+    ///
+    /// The lock must be write locked, and the guard which locked it forgotten.
+    #[inline]
+    pub unsafe fn write_unlock(self: Pin<&Self>) {
+        drop(WriteGuard { lock: self });
+    }
+}
+
+pub struct ReadGuard<'a> {
+    lock: Pin<&'a RwLock>,
+}
+impl Clone for ReadGuard<'_> {
+    /// Adds a reader without waiting, as the lock is already held for reading.
+    ///
+    /// Panics if the maximum number of readers is reached.
+    fn clone(&self) -> Self {
+        self.lock.with_state(|state| {
+            state.readers = state
+                .readers
+                .checked_add(1)
+                .expect("rwlock maximum reader count exceeded");
+        });
+        ReadGuard { lock: self.lock }
+    }
+}
+impl Drop for ReadGuard<'_> {
+    fn drop(&mut self) {
+        let lock = self.lock;
+        lock.with_state(|state| {
+            state.readers -= 1;
+            if state.readers == 0 && state.writers_waiting > 0 {
+                lock.writers().notify_one();
+            }
+        });
+    }
+}
+
+pub struct WriteGuard<'a> {
+    lock: Pin<&'a RwLock>,
+}
+impl Drop for WriteGuard<'_> {
+    fn drop(&mut self) {
+        let lock = self.lock;
+        lock.with_state(|state| {
+            state.writer = false;
+            if state.writers_waiting > 0 {
+                lock.writers().notify_one();
+            } else {
+                lock.readers().notify_all();
+            }
+        });
+    }
+}
@@ -0,0 +1,86 @@
+use std::convert::TryInto;
+use std::io;
+use std::mem;
+use std::ptr;
+use std::sync::atomic::{AtomicU32, Ordering::*};
+use std::time::Duration;
+
+/// Blocks the current thread while `futex` is `expected`, until it is woken up
+/// by [`wake`] or the timeout expires.
+///
+/// Returns `false` if the timeout expired. Like any futex wait, this may also
+/// return spuriously.
+pub fn wait(futex: &AtomicU32, expected: u32, timeout: Option<Duration>) -> bool {
+    // The deadline is absolute, so it does not need to be recomputed after an
+    // interruption. A timeout which can not be represented is as good as no
+    // timeout.
+    let deadline = timeout.and_then(|dur| {
+        let mut now: libc::timespec = unsafe { mem::zeroed() };
+        let r = unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut now) };
+        assert_eq!(r, 0);
+        let nsec = now.tv_nsec as u32 + dur.subsec_nanos();
+        let sec = now
+            .tv_sec
+            .checked_add(dur.as_secs().try_into().ok()?)?
+            .checked_add((nsec / 1_000_000_000) as libc::time_t)?;
+        Some(libc::_umtx_time {
+            _timeout: libc::timespec {
+                tv_sec: sec,
+                tv_nsec: (nsec % 1_000_000_000) as _,
+            },
+            _flags: libc::UMTX_ABSTIME,
+            _clockid: libc::CLOCK_MONOTONIC as u32,
+        })
+    });
+    loop {
+        if futex.load(Relaxed) != expected {
+            return true;
+        }
+        // The size of the timeout is passed in place of a pointer.
+        let (size, timeout) = match &deadline {
+            Some(deadline) => (
+                mem::size_of::<libc::_umtx_time>(),
+                deadline as *const libc::_umtx_time,
+            ),
+            None => (0, ptr::null()),
+        };
+        let r = unsafe {
+            libc::_umtx_op(
+                futex.as_ptr().cast(),
+                libc::UMTX_OP_WAIT_UINT_PRIVATE,
+                expected.into(),
+                size as *mut libc::c_void,
+                timeout as *mut libc::c_void,
+            )
+        };
+        // Unlike a Linux futex, this also succeeds right away if the futex
+        // changed before the thread went to sleep.
+        if r == 0 {
+            return true;
+        }
+        match io::Error::last_os_error().raw_os_error() {
+            Some(libc::ETIMEDOUT) => return false,
+            // Interrupted by a signal, so sleep again until the same deadline.
+            Some(libc::EINTR) => continue,
+            _ => panic!("umtx wait failed: {}", io::Error::last_os_error()),
+        }
+    }
+}
+
+/// Wakes up at most `count` threads blocked in [`wait`] on `futex`.
+///
+/// Returns whether any thread was woken up. The kernel does not tell, so this
+/// always returns `false`, which callers must treat as "maybe not".
+#[inline]
+pub fn wake(futex: &AtomicU32, count: i32) -> bool {
+    unsafe {
+        libc::_umtx_op(
+            futex.as_ptr().cast(),
+            libc::UMTX_OP_WAKE_PRIVATE,
+            count as libc::c_ulong,
+            ptr::null_mut(),
+            ptr::null_mut(),
+        );
+    }
+    false
+}
@@ -0,0 +1,17 @@
+//! A backend built on FreeBSD `_umtx_op(2)`.
+//!
+//! The `UMTX_OP_WAIT_UINT_PRIVATE` and `UMTX_OP_WAKE_PRIVATE` operations are
+//! the wait and wake operations of a Linux futex under another name, so the
+//! primitives are the ones of the Linux backend, built on top of them instead.
+//! Like there, an uninitialized `Mutex`, `Condvar` or `RwLock` is already
+//! usable, and nothing is allocated by the kernel or libthr.
+
+#[path = "../linux/condvar.rs"]
+pub mod condvar;
+#[path = "../linux/mutex.rs"]
+pub mod mutex;
+#[path = "../linux/rwlock.rs"]
+pub mod rwlock;
+
+use super::futex;
+pub use crate::sys_common::{barrier, remutex};
@@ -0,0 +1,65 @@
+#![allow(non_camel_case_types)]
+
+use std::convert::TryInto;
+use std::sync::atomic::AtomicU32;
+use std::time::Duration;
+
+type zx_futex_t = AtomicU32;
+type zx_handle_t = u32;
+type zx_status_t = i32;
+type zx_time_t = i64;
+
+const ZX_HANDLE_INVALID: zx_handle_t = 0;
+const ZX_TIME_INFINITE: zx_time_t = zx_time_t::MAX;
+
+const ZX_OK: zx_status_t = 0;
+const ZX_ERR_BAD_STATE: zx_status_t = -20;
+const ZX_ERR_TIMED_OUT: zx_status_t = -21;
+
+#[link(name = "zircon")]
+extern "C" {
+    fn zx_clock_get_monotonic() -> zx_time_t;
+    fn zx_futex_wait(
+        value_ptr: *const zx_futex_t,
+        current_value: u32,
+        new_futex_owner: zx_handle_t,
+        deadline: zx_time_t,
+    ) -> zx_status_t;
+    fn zx_futex_wake(value_ptr: *const zx_futex_t, wake_count: u32) -> zx_status_t;
+}
+
+/// Blocks the current thread while `futex` is `expected`, until it is woken up
+/// by [`wake`] or the timeout expires.
+///
+/// Returns `false` if the timeout expired. Like any futex wait, this may also
+/// return spuriously.
+pub fn wait(futex: &AtomicU32, expected: u32, timeout: Option<Duration>) -> bool {
+    // The deadline is absolute. A timeout which can not be represented is as
+    // good as no timeout.
+    let deadline = timeout
+        .and_then(|dur| {
+            let nanos: zx_time_t = dur.as_nanos().try_into().ok()?;
+            unsafe { zx_clock_get_monotonic() }.checked_add(nanos)
+        })
+        .unwrap_or(ZX_TIME_INFINITE);
+    let r = unsafe { zx_futex_wait(futex, expected, ZX_HANDLE_INVALID, deadline) };
+    match r {
+        // `ZX_ERR_BAD_STATE` means the futex changed before the thread went to
+        // sleep.
+        ZX_OK | ZX_ERR_BAD_STATE => true,
+        ZX_ERR_TIMED_OUT => false,
+        _ => panic!("zx_futex_wait failed: {}", r),
+    }
+}
+
+/// Wakes up at most `count` threads blocked in [`wait`] on `futex`.
+///
+/// Returns whether any thread was woken up. The kernel does not tell, so this
+/// always returns `false`, which callers must treat as "maybe not".
+#[inline]
+pub fn wake(futex: &AtomicU32, count: i32) -> bool {
+    unsafe {
+        zx_futex_wake(futex, count as u32);
+    }
+    false
+}
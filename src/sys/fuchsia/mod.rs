@@ -0,0 +1,16 @@
+//! A backend built on Zircon futexes.
+//!
+//! `zx_futex_wait` and `zx_futex_wake` are the wait and wake operations of a
+//! Linux futex under another name, so the primitives are the ones of the Linux
+//! backend, built on top of them instead. Like there, an uninitialized `Mutex`,
+//! `Condvar` or `RwLock` is already usable.
+
+#[path = "../linux/condvar.rs"]
+pub mod condvar;
+#[path = "../linux/mutex.rs"]
+pub mod mutex;
+#[path = "../linux/rwlock.rs"]
+pub mod rwlock;
+
+use super::futex;
+pub use crate::sys_common::{barrier, remutex};
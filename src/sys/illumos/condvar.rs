@@ -0,0 +1,116 @@
+use crate::sys;
+use std::cell::UnsafeCell;
+use std::convert::TryInto;
+use std::marker::PhantomPinned;
+use std::pin::Pin;
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, Ordering::*};
+use std::time::Duration;
+
+extern "C" {
+    fn pthread_cond_reltimedwait_np(
+        cond: *mut libc::pthread_cond_t,
+        mutex: *mut libc::pthread_mutex_t,
+        reltime: *const libc::timespec,
+    ) -> libc::c_int;
+}
+
+pub struct Condvar {
+    inner: UnsafeCell<libc::pthread_cond_t>,
+    mutex: AtomicPtr<libc::pthread_mutex_t>,
+    _p: PhantomPinned,
+}
+
+unsafe impl Send for Condvar {}
+unsafe impl Sync for Condvar {}
+
+impl Condvar {
+    #[inline]
+    pub const fn uninit() -> Self {
+        Self {
+            inner: UnsafeCell::new(libc::PTHREAD_COND_INITIALIZER),
+            mutex: AtomicPtr::new(ptr::null_mut()),
+            _p: PhantomPinned,
+        }
+    }
+
+    #[inline]
+    pub const fn new() -> Self {
+        Self::uninit()
+    }
+
+    #[inline]
+    pub fn init(self: Pin<&Self>) {}
+
+    #[inline]
+    pub fn notify_one(self: Pin<&Self>) {
+        unsafe {
+            let r = libc::pthread_cond_signal(self.inner.get());
+            debug_assert_eq!(r, 0);
+        }
+    }
+
+    #[inline]
+    pub fn notify_all(self: Pin<&Self>) {
+        unsafe {
+            let r = libc::pthread_cond_broadcast(self.inner.get());
+            debug_assert_eq!(r, 0);
+        }
+    }
+
+    // Condition variables can only wake one thread or all of them.
+    #[inline]
+    pub fn notify_many(self: Pin<&Self>, n: usize) {
+        for _ in 0..n {
+            self.notify_one();
+        }
+    }
+
+    #[inline]
+    pub unsafe fn wait<'a>(
+        self: Pin<&Self>,
+        lock: sys::mutex::MutexGuard<'a>,
+    ) -> sys::mutex::MutexGuard<'a> {
+        self.verify(lock.as_raw());
+
+        let r = libc::pthread_cond_wait(self.inner.get(), lock.as_raw());
+        debug_assert_eq!(r, 0);
+        lock
+    }
+
+    pub unsafe fn wait_timeout<'a>(
+        &self,
+        lock: sys::mutex::MutexGuard<'a>,
+        dur: Duration,
+    ) -> (bool, sys::mutex::MutexGuard<'a>) {
+        self.verify(lock.as_raw());
+
+        // Timeouts which do not fit in the kernel's nanosecond clock are
+        // rejected, so they are clamped to 1000 years, which is allowable per
+        // the API of `wait_timeout` because of spurious wakeups.
+        let dur = dur.min(Duration::from_secs(1000 * 365 * 86400));
+        let reltime = libc::timespec {
+            tv_sec: dur.as_secs().try_into().unwrap_or(libc::time_t::MAX),
+            tv_nsec: dur.subsec_nanos() as _,
+        };
+
+        let r = pthread_cond_reltimedwait_np(self.inner.get(), lock.as_raw(), &reltime);
+        debug_assert!(r == libc::ETIMEDOUT || r == 0);
+        (r == 0, lock)
+    }
+
+    // Waiting on the same condition variable with different mutexes is undefined
+    // behaviour for pthreads, so we remember the first mutex and panic if
+    // another one is ever used.
+    #[inline]
+    fn verify(&self, mutex: *mut libc::pthread_mutex_t) {
+        match self
+            .mutex
+            .compare_exchange(ptr::null_mut(), mutex, Relaxed, Relaxed)
+        {
+            Ok(_) => {}
+            Err(addr) if addr == mutex => {}
+            Err(_) => panic!("attempted to use a condition variable with two mutexes"),
+        }
+    }
+}
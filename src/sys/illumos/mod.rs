@@ -0,0 +1,21 @@
+//! A backend for illumos and Solaris.
+//!
+//! The pthread objects of these systems are the native `mutex_t`, `cond_t`
+//! and `rwlock_t` of libc, and unlike elsewhere, their static initializers
+//! produce complete objects: a statically initialized mutex or condition
+//! variable is exactly what `pthread_*_init` would produce with the default
+//! attributes, and destroying one only invalidates it. So an uninitialized
+//! `Mutex` or `Condvar` is already usable, and nothing is ever destroyed.
+//!
+//! Timed waits use `pthread_cond_reltimedwait_np`, which takes a relative
+//! timeout, instead of setting the clock of the condition variable, which
+//! would require initializing it.
+//!
+//! Read-write locks are the ones of the generic pthread backend.
+
+pub mod condvar;
+pub mod mutex;
+#[path = "../unix/rwlock.rs"]
+pub mod rwlock;
+
+pub use crate::sys_common::{barrier, remutex};
@@ -0,0 +1,119 @@
+use crate::sys_common::timed;
+use std::cell::UnsafeCell;
+use std::marker::PhantomPinned;
+use std::pin::Pin;
+use std::ptr;
+use std::thread;
+use std::time::Instant;
+
+pub struct Mutex {
+    // `PTHREAD_MUTEX_INITIALIZER` is a `PTHREAD_MUTEX_NORMAL` mutex private
+    // to the process, like the ones of the generic pthread backend.
+    lock: UnsafeCell<libc::pthread_mutex_t>,
+    _p: PhantomPinned,
+}
+
+unsafe impl Send for Mutex {}
+unsafe impl Sync for Mutex {}
+
+impl Mutex {
+    #[inline]
+    pub const fn uninit() -> Self {
+        Self {
+            lock: UnsafeCell::new(libc::PTHREAD_MUTEX_INITIALIZER),
+            _p: PhantomPinned,
+        }
+    }
+
+    #[inline]
+    pub const fn new() -> Self {
+        Self::uninit()
+    }
+
+    // pthread mutexes do not expose their waiter queue, so direct handoff is
+    // not supported.
+    #[inline]
+    pub fn handoff(self, _enabled: bool) -> Self {
+        self
+    }
+
+    #[inline]
+    pub fn init(self: Pin<&Self>) {}
+
+    /// Initializes the mutex again, unlocked, as the thread which held it may
+    /// not exist in the child of a `fork`.
+    pub unsafe fn reinit_after_fork(self: Pin<&Self>) {
+        ptr::write(self.lock.get(), libc::PTHREAD_MUTEX_INITIALIZER);
+    }
+
+    #[inline]
+    pub fn lock(self: Pin<&Self>) -> MutexGuard<'_> {
+        unsafe {
+            let result = libc::pthread_mutex_lock(self.lock.get());
+            debug_assert_eq!(result, 0);
+        }
+        MutexGuard { mutex: self }
+    }
+
+    #[inline]
+    pub fn try_lock(self: Pin<&Self>) -> Option<MutexGuard<'_>> {
+        unsafe {
+            let result = libc::pthread_mutex_trylock(self.lock.get());
+            if result == 0 {
+                Some(MutexGuard { mutex: self })
+            } else {
+                None
+            }
+        }
+    }
+
+    pub fn try_lock_until(self: Pin<&Self>, deadline: Instant) -> Option<MutexGuard<'_>> {
+        let timeout = timed::realtime(deadline);
+        let result = unsafe { libc::pthread_mutex_timedlock(self.lock.get(), &timeout) };
+        if result == 0 {
+            Some(MutexGuard { mutex: self })
+        } else {
+            debug_assert_eq!(result, libc::ETIMEDOUT);
+            None
+        }
+    }
+
+    /// Unlocks the mutex without a guard.
+    ///
+    /// # Safety
+    ///
+    /// The mutex must be locked, and the guard which locked it forgotten.
+    #[inline]
+    pub unsafe fn unlock(self: Pin<&Self>) {
+        drop(MutexGuard { mutex: self });
+    }
+}
+
+pub struct MutexGuard<'a> {
+    mutex: Pin<&'a Mutex>,
+}
+impl<'a> MutexGuard<'a> {
+    #[inline]
+    pub fn as_raw(&self) -> *mut libc::pthread_mutex_t {
+        self.mutex.lock.get()
+    }
+
+    /// Unlocks the mutex, lets the threads waiting for it run, and locks it
+    /// again.
+    #[inline]
+    pub fn bump(self) -> Self {
+        let mutex = self.mutex;
+        drop(self);
+        thread::yield_now();
+        mutex.lock()
+    }
+}
+impl Drop for MutexGuard<'_> {
+    #[inline]
+    fn drop(&mut self) {
+        unsafe {
+            let result = libc::pthread_mutex_unlock(self.as_raw());
+            debug_assert_eq!(result, 0);
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use super::futex;
+use crate::sys;
+use std::convert::TryInto;
+use std::marker::PhantomPinned;
+use std::mem;
+use std::pin::Pin;
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, AtomicU32, Ordering::*};
+use std::time::Duration;
+
+pub struct Condvar {
+    // Incremented by every notification, so a waiter which read it before
+    // unlocking the mutex does not block if it was notified in between.
+    //
+    // The counter wraps around. A waiter only blocks by mistake if exactly a
+    // multiple of 2^32 notifications happen between its read and its wait,
+    // which would take far longer than the few instructions in between.
+    futex: AtomicU32,
+    mutex: AtomicPtr<sys::mutex::Mutex>,
+    _p: PhantomPinned,
+}
+
+unsafe impl Send for Condvar {}
+unsafe impl Sync for Condvar {}
+
+impl Condvar {
+    #[inline]
+    pub const fn uninit() -> Self {
+        Self {
+            futex: AtomicU32::new(0),
+            mutex: AtomicPtr::new(ptr::null_mut()),
+            _p: PhantomPinned,
+        }
+    }
+
+    #[inline]
+    pub const fn new() -> Self {
+        Self::uninit()
+    }
+
+    // A futex does not need to be initialized, so an uninitialized condition
+    // variable is usable as is.
+    #[inline]
+    pub fn init(self: Pin<&Self>) {}
+
+    #[inline]
+    pub fn notify_one(self: Pin<&Self>) {
+        self.futex.fetch_add(1, Relaxed);
+        futex::wake(&self.futex, 1);
+    }
+
+    #[inline]
+    pub fn notify_all(self: Pin<&Self>) {
+        self.futex.fetch_add(1, Relaxed);
+        futex::wake(&self.futex, i32::MAX);
+    }
+
+    #[inline]
+    pub fn notify_many(self: Pin<&Self>, n: usize) {
+        self.futex.fetch_add(1, Relaxed);
+        futex::wake(&self.futex, n.try_into().unwrap_or(i32::MAX));
+    }
+
+    #[inline]
+    pub unsafe fn wait<'a>(
+        self: Pin<&Self>,
+        lock: sys::mutex::MutexGuard<'a>,
+    ) -> sys::mutex::MutexGuard<'a> {
+        self.sleep(lock, None).1
+    }
+
+    #[inline]
+    pub unsafe fn wait_timeout<'a>(
+        &self,
+        lock: sys::mutex::MutexGuard<'a>,
+        dur: Duration,
+    ) -> (bool, sys::mutex::MutexGuard<'a>) {
+        self.sleep(lock, Some(dur))
+    }
+
+    cfg_interruptible! {
+        #[inline]
+        pub unsafe fn wait_interruptible<'a>(
+            &self,
+            lock: sys::mutex::MutexGuard<'a>,
+        ) -> (bool, sys::mutex::MutexGuard<'a>) {
+            self.sleep_with(lock, futex::wait_interruptible)
+        }
+    }
+
+    unsafe fn sleep<'a>(
+        &self,
+        lock: sys::mutex::MutexGuard<'a>,
+        timeout: Option<Duration>,
+    ) -> (bool, sys::mutex::MutexGuard<'a>) {
+        // This returns `true` on spurious wakeups, which callers of `wait` and
+        // `wait_timeout` must handle anyway, and only `false` once the timeout
+        // really expired.
+        self.sleep_with(lock, |futex, value| futex::wait(futex, value, timeout))
+    }
+
+    // Unlocks the mutex, blocks with `wait` and locks the mutex again,
+    // returning what `wait` returned.
+    unsafe fn sleep_with<'a>(
+        &self,
+        lock: sys::mutex::MutexGuard<'a>,
+        wait: impl FnOnce(&AtomicU32, u32) -> bool,
+    ) -> (bool, sys::mutex::MutexGuard<'a>) {
+        let mutex = lock.mutex;
+        self.verify(&mutex);
+        mem::forget(lock);
+
+        let futex_value = self.futex.load(Relaxed);
+        mutex.unlock_raw();
+        let notified = wait(&self.futex, futex_value);
+        mutex.lock_raw();
+        (notified, sys::mutex::MutexGuard { mutex })
+    }
+
+    // Waiting on the same condition variable with different mutexes is not
+    // supported by the other backends, so we remember the first mutex and
+    // panic if another one is ever used.
+    #[inline]
+    fn verify(&self, mutex: &sys::mutex::Mutex) {
+        let mutex = mutex as *const _ as *mut _;
+        match self
+            .mutex
+            .compare_exchange(ptr::null_mut(), mutex, Relaxed, Relaxed)
+        {
+            Ok(_) => {}
+            Err(addr) if addr == mutex => {}
+            Err(_) => panic!("attempted to use a condition variable with two mutexes"),
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use std::convert::TryInto;
+use std::io;
+use std::ptr;
+use std::sync::atomic::{AtomicU32, Ordering::*};
+use std::time::{Duration, Instant};
+
+/// Blocks the current thread while `futex` is `expected`, until it is woken up
+/// by [`wake`] or the timeout expires.
+///
+/// Returns `false` if the timeout expired. Like any futex wait, this may also
+/// return spuriously.
+pub fn wait(futex: &AtomicU32, expected: u32, timeout: Option<Duration>) -> bool {
+    // A timeout which can not be represented is as good as no timeout.
+    let deadline = timeout.and_then(|dur| Instant::now().checked_add(dur));
+    loop {
+        if futex.load(Relaxed) != expected {
+            return true;
+        }
+        let timespec = match deadline {
+            Some(deadline) => {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if remaining.is_zero() {
+                    return false;
+                }
+                Some(libc::timespec {
+                    tv_sec: remaining.as_secs().try_into().unwrap_or(libc::time_t::MAX),
+                    tv_nsec: remaining.subsec_nanos() as _,
+                })
+            }
+            None => None,
+        };
+        let r = unsafe {
+            libc::syscall(
+                libc::SYS_futex,
+                futex.as_ptr(),
+                libc::FUTEX_WAIT | libc::FUTEX_PRIVATE_FLAG,
+                expected,
+                timespec.as_ref().map_or(ptr::null(), |t| t as *const libc::timespec),
+            )
+        };
+        if r == 0 {
+            return true;
+        }
+        match io::Error::last_os_error().raw_os_error() {
+            // The futex changed before the thread went to sleep.
+            Some(libc::EAGAIN) => return true,
+            Some(libc::ETIMEDOUT) => return false,
+            // Interrupted by a signal, so sleep again with what is left of the
+            // timeout.
+            Some(libc::EINTR) => continue,
+            _ => panic!("futex wait failed: {}", io::Error::last_os_error()),
+        }
+    }
+}
+
+cfg_interruptible! {
+    /// Blocks the current thread while `futex` is `expected`, until it is
+    /// woken up by [`wake`] or a signal is handled by the thread.
+    ///
+    /// Returns `false` if the wait was interrupted by a signal, which is only
+    /// the case for handlers installed without `SA_RESTART`. Like any futex
+    /// wait, this may also return spuriously.
+    pub fn wait_interruptible(futex: &AtomicU32, expected: u32) -> bool {
+        if futex.load(Relaxed) != expected {
+            return true;
+        }
+        let r = unsafe {
+            libc::syscall(
+                libc::SYS_futex,
+                futex.as_ptr(),
+                libc::FUTEX_WAIT | libc::FUTEX_PRIVATE_FLAG,
+                expected,
+                ptr::null::<libc::timespec>(),
+            )
+        };
+        if r == 0 {
+            return true;
+        }
+        match io::Error::last_os_error().raw_os_error() {
+            // The futex changed before the thread went to sleep.
+            Some(libc::EAGAIN) => true,
+            Some(libc::EINTR) => false,
+            _ => panic!("futex wait failed: {}", io::Error::last_os_error()),
+        }
+    }
+}
+
+/// Wakes up at most `count` threads blocked in [`wait`] on `futex`.
+///
+/// Returns whether any thread was woken up.
+#[inline]
+pub fn wake(futex: &AtomicU32, count: i32) -> bool {
+    let r = unsafe {
+        libc::syscall(
+            libc::SYS_futex,
+            futex.as_ptr(),
+            libc::FUTEX_WAKE | libc::FUTEX_PRIVATE_FLAG,
+            count,
+        )
+    };
+    r > 0
+}
@@ -0,0 +1,12 @@
+//! A backend built on Linux futexes.
+//!
+//! Every primitive is one or two `AtomicU32`s, waited on with `futex(2)`.
+//! They need no initialization or destruction, so unlike with pthreads, an
+//! uninitialized `Mutex`, `Condvar` or `RwLock` is already usable.
+
+pub mod condvar;
+pub mod mutex;
+pub mod rwlock;
+
+use super::futex;
+pub use crate::sys_common::{barrier, remutex};
@@ -0,0 +1,225 @@
+use super::futex;
+use std::marker::PhantomPinned;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU32, Ordering::*};
+use std::thread;
+use std::time::Instant;
+
+const UNLOCKED: u32 = 0;
+const LOCKED: u32 = 1;
+// Locked, and other threads may be blocked on it.
+const CONTENDED: u32 = 2;
+
+// How many times to check for the lock to be released before blocking.
+const SPIN_LIMIT: u32 = 100;
+
+pub struct Mutex {
+    state: AtomicU32,
+    _p: PhantomPinned,
+}
+
+unsafe impl Send for Mutex {}
+unsafe impl Sync for Mutex {}
+
+impl Mutex {
+    #[inline]
+    pub const fn uninit() -> Self {
+        Self {
+            state: AtomicU32::new(UNLOCKED),
+            _p: PhantomPinned,
+        }
+    }
+
+    #[inline]
+    pub const fn new() -> Self {
+        Self::uninit()
+    }
+
+    // A futex wakes up an arbitrary waiter, which then races with the others,
+    // so direct handoff is not supported.
+    #[inline]
+    pub fn handoff(self, _enabled: bool) -> Self {
+        self
+    }
+
+    // A futex does not need to be initialized, so an uninitialized mutex is
+    // usable as is.
+    #[inline]
+    pub fn init(self: Pin<&Self>) {}
+
+    /// Resets the mutex to unlocked, as the thread which held it may not exist
+    /// in the child of a `fork`.
+    #[cfg(unix)]
+    pub unsafe fn reinit_after_fork(self: Pin<&Self>) {
+        self.state.store(UNLOCKED, Relaxed);
+    }
+
+    #[inline]
+    pub fn lock(self: Pin<&Self>) -> MutexGuard<'_> {
+        self.lock_raw();
+        MutexGuard { mutex: self }
+    }
+
+    #[inline]
+    pub fn try_lock(self: Pin<&Self>) -> Option<MutexGuard<'_>> {
+        self.state
+            .compare_exchange(UNLOCKED, LOCKED, Acquire, Relaxed)
+            .ok()
+            .map(|_| MutexGuard { mutex: self })
+    }
+
+    #[inline]
+    pub fn try_lock_until(self: Pin<&Self>, deadline: Instant) -> Option<MutexGuard<'_>> {
+        let locked = self
+            .state
+            .compare_exchange(UNLOCKED, LOCKED, Acquire, Relaxed)
+            .is_ok()
+            || self.lock_contended(Some(deadline));
+        if locked {
+            Some(MutexGuard { mutex: self })
+        } else {
+            None
+        }
+    }
+
+    cfg_interruptible! {
+        // Returns `None` if a signal was handled before the lock was acquired.
+        #[inline]
+        pub fn lock_interruptible(self: Pin<&Self>) -> Option<MutexGuard<'_>> {
+            let locked = self
+                .state
+                .compare_exchange(UNLOCKED, LOCKED, Acquire, Relaxed)
+                .is_ok()
+                || self.lock_contended_with(|| futex::wait_interruptible(&self.state, CONTENDED));
+            if locked {
+                Some(MutexGuard { mutex: self })
+            } else {
+                None
+            }
+        }
+    }
+
+    #[inline]
+    pub(super) fn lock_raw(&self) {
+        if self
+            .state
+            .compare_exchange(UNLOCKED, LOCKED, Acquire, Relaxed)
+            .is_err()
+        {
+            self.lock_contended(None);
+        }
+    }
+
+    // Returns `false` if the deadline was reached before the lock was acquired.
+    #[cold]
+    fn lock_contended(&self, deadline: Option<Instant>) -> bool {
+        self.lock_contended_with(|| {
+            let timeout = match deadline {
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return false;
+                    }
+                    Some(deadline - now)
+                }
+                None => None,
+            };
+            futex::wait(&self.state, CONTENDED, timeout);
+            true
+        })
+    }
+
+    // Blocks with `wait` until the lock is acquired. Returns `false` if `wait`
+    // gave up before then.
+    #[cold]
+    fn lock_contended_with(&self, mut wait: impl FnMut() -> bool) -> bool {
+        let mut state = self.spin();
+
+        // Grab the lock if it was released while spinning.
+        if state == UNLOCKED {
+            match self
+                .state
+                .compare_exchange(UNLOCKED, LOCKED, Acquire, Relaxed)
+            {
+                Ok(_) => return true,
+                Err(x) => state = x,
+            }
+        }
+
+        loop {
+            // Mark the lock as contended before blocking, so that the thread
+            // which unlocks it wakes us up. This may mark it as contended when
+            // nobody else is waiting, which only costs a spurious wake call.
+            if state != CONTENDED && self.state.swap(CONTENDED, Acquire) == UNLOCKED {
+                return true;
+            }
+
+            if !wait() {
+                return false;
+            }
+
+            state = self.spin();
+        }
+    }
+
+    // Spins while the lock is held without contention.
+    fn spin(&self) -> u32 {
+        let mut spin = SPIN_LIMIT;
+        loop {
+            let state = self.state.load(Relaxed);
+            if state != LOCKED || spin == 0 {
+                return state;
+            }
+            std::hint::spin_loop();
+            spin -= 1;
+        }
+    }
+
+    /// # Safety
+    ///
+    /// The mutex must be locked, and the guard which locked it forgotten.
+    #[inline]
+    pub(super) unsafe fn unlock_raw(&self) {
+        if self.state.swap(UNLOCKED, Release) == CONTENDED {
+            futex::wake(&self.state, 1);
+        }
+    }
+
+    /// Unlocks the mutex without a guard.
+    ///
+    /// # Safety
+    ///
+    /// The mutex must be locked, and the guard which locked it forgotten.
+    #[inline]
+    pub unsafe fn unlock(self: Pin<&Self>) {
+        self.unlock_raw();
+    }
+}
+
+pub struct MutexGuard<'a> {
+    pub(super) mutex: Pin<&'a Mutex>,
+}
+
+impl<'a> MutexGuard<'a> {
+    /// Unlocks the mutex, lets the threads waiting for it run, and locks it
+    /// again. Does nothing if nobody is waiting.
+    #[inline]
+    pub fn bump(self) -> Self {
+        if self.mutex.state.load(Relaxed) != CONTENDED {
+            return self;
+        }
+        let mutex = self.mutex;
+        drop(self);
+        // The woken thread can not run until it is scheduled, so let it go
+        // first.
+        thread::yield_now();
+        mutex.lock()
+    }
+}
+
+impl Drop for MutexGuard<'_> {
+    #[inline]
+    fn drop(&mut self) {
+        unsafe { self.mutex.unlock_raw() }
+    }
+}
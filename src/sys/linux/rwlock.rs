@@ -0,0 +1,460 @@
+use super::futex;
+use crate::sys::ReadError;
+use std::marker::PhantomPinned;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU32, Ordering::*};
+use std::time::{Duration, Instant};
+
+// The lower 30 bits of the state are the number of readers, or `WRITE_LOCKED`
+// if a writer holds the lock.
+const READ_LOCKED: u32 = 1;
+const MASK: u32 = (1 << 30) - 1;
+const WRITE_LOCKED: u32 = MASK;
+const MAX_READERS: u32 = MASK - 1;
+// Readers are blocked on `state`.
+const READERS_WAITING: u32 = 1 << 30;
+// Writers are blocked on `writer_notify`.
+const WRITERS_WAITING: u32 = 1 << 31;
+
+// How many times to check for the lock to be released before blocking.
+const SPIN_LIMIT: u32 = 100;
+
+// The time left until `deadline`, or `Err` if it was reached.
+#[inline]
+fn remaining(deadline: Option<Instant>) -> Result<Option<Duration>, ()> {
+    match deadline {
+        Some(deadline) => {
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(());
+            }
+            Ok(Some(deadline - now))
+        }
+        None => Ok(None),
+    }
+}
+
+#[inline]
+fn is_unlocked(state: u32) -> bool {
+    state & MASK == 0
+}
+
+#[inline]
+fn is_write_locked(state: u32) -> bool {
+    state & MASK == WRITE_LOCKED
+}
+
+#[inline]
+fn has_readers_waiting(state: u32) -> bool {
+    state & READERS_WAITING != 0
+}
+
+#[inline]
+fn has_writers_waiting(state: u32) -> bool {
+    state & WRITERS_WAITING != 0
+}
+
+#[inline]
+fn has_reached_max_readers(state: u32) -> bool {
+    state & MASK == MAX_READERS
+}
+
+// New readers are kept out while a thread is waiting, so that writers are not
+// starved.
+#[inline]
+fn is_read_lockable(state: u32) -> bool {
+    state & MASK < MAX_READERS && !has_readers_waiting(state) && !has_writers_waiting(state)
+}
+
+pub struct RwLock {
+    state: AtomicU32,
+    // Incremented to wake up a writer.
+    writer_notify: AtomicU32,
+    _p: PhantomPinned,
+}
+
+unsafe impl Send for RwLock {}
+unsafe impl Sync for RwLock {}
+
+impl RwLock {
+    #[inline]
+    pub const fn uninit() -> Self {
+        Self {
+            state: AtomicU32::new(0),
+            writer_notify: AtomicU32::new(0),
+            _p: PhantomPinned,
+        }
+    }
+
+    #[inline]
+    pub const fn new() -> Self {
+        Self::uninit()
+    }
+
+    // A futex does not need to be initialized, so an uninitialized lock is
+    // usable as is.
+    #[inline]
+    pub fn init(self: Pin<&Self>) {}
+
+    /// Resets the lock to unlocked, as the threads which held it may not exist
+    /// in the child of a `fork`.
+    #[cfg(unix)]
+    pub unsafe fn reinit_after_fork(self: Pin<&Self>) {
+        self.state.store(0, Relaxed);
+        self.writer_notify.store(0, Relaxed);
+    }
+
+    #[inline]
+    pub fn try_read(self: Pin<&Self>) -> Result<ReadGuard<'_>, ReadError> {
+        let mut state = self.state.load(Relaxed);
+        loop {
+            if has_reached_max_readers(state) {
+                return Err(ReadError::TooManyReaders);
+            }
+            if !is_read_lockable(state) {
+                return Err(ReadError::WouldBlock);
+            }
+            match self
+                .state
+                .compare_exchange_weak(state, state + READ_LOCKED, Acquire, Relaxed)
+            {
+                Ok(_) => return Ok(ReadGuard { lock: self }),
+                Err(x) => state = x,
+            }
+        }
+    }
+
+    /// Returns `None` if the maximum number of readers was reached.
+    #[inline]
+    pub fn read(self: Pin<&Self>) -> Option<ReadGuard<'_>> {
+        let state = self.state.load(Relaxed);
+        if !is_read_lockable(state)
+            || self
+                .state
+                .compare_exchange_weak(state, state + READ_LOCKED, Acquire, Relaxed)
+                .is_err()
+        {
+            self.read_contended(None).ok()?;
+        }
+        Some(ReadGuard { lock: self })
+    }
+
+    /// Returns [`ReadError::WouldBlock`] if the deadline was reached.
+    #[inline]
+    pub fn try_read_until(self: Pin<&Self>, deadline: Instant) -> Result<ReadGuard<'_>, ReadError> {
+        let state = self.state.load(Relaxed);
+        if !is_read_lockable(state)
+            || self
+                .state
+                .compare_exchange_weak(state, state + READ_LOCKED, Acquire, Relaxed)
+                .is_err()
+        {
+            self.read_contended(Some(deadline))?;
+        }
+        Ok(ReadGuard { lock: self })
+    }
+
+    #[cold]
+    fn read_contended(&self, deadline: Option<Instant>) -> Result<(), ReadError> {
+        let mut state = self.spin_read();
+        loop {
+            if is_read_lockable(state) {
+                match self
+                    .state
+                    .compare_exchange_weak(state, state + READ_LOCKED, Acquire, Relaxed)
+                {
+                    Ok(_) => return Ok(()),
+                    Err(x) => {
+                        state = x;
+                        continue;
+                    }
+                }
+            }
+
+            if has_reached_max_readers(state) {
+                return Err(ReadError::TooManyReaders);
+            }
+
+            // Make sure the thread which unlocks the lock wakes us up.
+            if !has_readers_waiting(state) {
+                if let Err(x) =
+                    self.state
+                        .compare_exchange(state, state | READERS_WAITING, Relaxed, Relaxed)
+                {
+                    state = x;
+                    continue;
+                }
+            }
+
+            // A reader which times out leaves the waiting bit set, which the
+            // next unlock clears.
+            let timeout = remaining(deadline).map_err(|()| ReadError::WouldBlock)?;
+            futex::wait(&self.state, state | READERS_WAITING, timeout);
+
+            state = self.spin_read();
+        }
+    }
+
+    #[inline]
+    pub fn try_write(self: Pin<&Self>) -> Option<WriteGuard<'_>> {
+        let mut state = self.state.load(Relaxed);
+        loop {
+            if !is_unlocked(state) {
+                return None;
+            }
+            match self
+                .state
+                .compare_exchange_weak(state, state + WRITE_LOCKED, Acquire, Relaxed)
+            {
+                Ok(_) => return Some(WriteGuard { lock: self }),
+                Err(x) => state = x,
+            }
+        }
+    }
+
+    #[inline]
+    pub fn write(self: Pin<&Self>) -> WriteGuard<'_> {
+        if self
+            .state
+            .compare_exchange_weak(0, WRITE_LOCKED, Acquire, Relaxed)
+            .is_err()
+        {
+            self.write_contended(None);
+        }
+        WriteGuard { lock: self }
+    }
+
+    #[inline]
+    pub fn try_write_until(self: Pin<&Self>, deadline: Instant) -> Option<WriteGuard<'_>> {
+        let locked = self
+            .state
+            .compare_exchange_weak(0, WRITE_LOCKED, Acquire, Relaxed)
+            .is_ok()
+            || self.write_contended(Some(deadline));
+        if locked {
+            Some(WriteGuard { lock: self })
+        } else {
+            None
+        }
+    }
+
+    // Returns `false` if the deadline was reached before the lock was acquired.
+    #[cold]
+    fn write_contended(&self, deadline: Option<Instant>) -> bool {
+        let mut state = self.spin_write();
+        let mut other_writers_waiting = 0;
+        loop {
+            // A writer which was woken up keeps the waiting bit set, as it
+            // can not know whether other writers are still waiting.
+            if is_unlocked(state) {
+                match self.state.compare_exchange_weak(
+                    state,
+                    state | WRITE_LOCKED | other_writers_waiting,
+                    Acquire,
+                    Relaxed,
+                ) {
+                    Ok(_) => return true,
+                    Err(x) => {
+                        state = x;
+                        continue;
+                    }
+                }
+            }
+
+            // Make sure the thread which unlocks the lock wakes us up.
+            if !has_writers_waiting(state) {
+                if let Err(x) =
+                    self.state
+                        .compare_exchange(state, state | WRITERS_WAITING, Relaxed, Relaxed)
+                {
+                    state = x;
+                    continue;
+                }
+            }
+            other_writers_waiting = WRITERS_WAITING;
+
+            // Read the notification counter before checking the state again,
+            // so a wakeup sent in between is not missed.
+            let seq = self.writer_notify.load(Acquire);
+            state = self.state.load(Relaxed);
+            if is_unlocked(state) || !has_writers_waiting(state) {
+                continue;
+            }
+
+            let timeout = match remaining(deadline) {
+                Ok(timeout) => timeout,
+                Err(()) => {
+                    self.abandon_write();
+                    return false;
+                }
+            };
+            futex::wait(&self.writer_notify, seq, timeout);
+
+            state = self.spin_write();
+        }
+    }
+
+    /// Gives up the waiting bit of a writer which timed out, so that readers
+    /// are not kept out for it.
+    #[cold]
+    fn abandon_write(&self) {
+        let state = self.state.fetch_and(!WRITERS_WAITING, Relaxed);
+        if !has_writers_waiting(state) {
+            return;
+        }
+        // Other writers may still be waiting, so wake one of them up to set
+        // the bit again.
+        self.wake_writer();
+        // The readers which were kept out are only woken up by a writer
+        // releasing the lock, or by this.
+        if has_readers_waiting(state)
+            && !is_write_locked(state)
+            && has_readers_waiting(self.state.fetch_and(!READERS_WAITING, Relaxed))
+        {
+            futex::wake(&self.state, i32::MAX);
+        }
+    }
+
+    /// Wakes up the waiting threads of a lock which was just released.
+    #[cold]
+    fn wake_writer_or_readers(&self, mut state: u32) {
+        debug_assert!(is_unlocked(state));
+
+        // Only writers are waiting, so wake one of them up.
+        if state == WRITERS_WAITING {
+            match self.state.compare_exchange(state, 0, Relaxed, Relaxed) {
+                Ok(_) => {
+                    self.wake_writer();
+                    return;
+                }
+                Err(x) => state = x,
+            }
+        }
+
+        // Both are waiting, so wake up a writer first, leaving the readers
+        // waiting until it is done. If no writer was actually waiting, wake
+        // the readers up instead.
+        if state == READERS_WAITING + WRITERS_WAITING {
+            if self
+                .state
+                .compare_exchange(state, READERS_WAITING, Relaxed, Relaxed)
+                .is_err()
+            {
+                // The lock was taken again, so its next unlock wakes them.
+                return;
+            }
+            if self.wake_writer() {
+                return;
+            }
+            state = READERS_WAITING;
+        }
+
+        // Only readers are waiting, so wake all of them up.
+        if state == READERS_WAITING
+            && self
+                .state
+                .compare_exchange(state, 0, Relaxed, Relaxed)
+                .is_ok()
+        {
+            futex::wake(&self.state, i32::MAX);
+        }
+    }
+
+    /// Returns whether a writer was woken up, or `false` if that is unknown.
+    #[inline]
+    fn wake_writer(&self) -> bool {
+        self.writer_notify.fetch_add(1, Release);
+        futex::wake(&self.writer_notify, 1)
+    }
+
+    // Spins while the lock is held and nobody is waiting yet.
+    #[inline]
+    fn spin_until(&self, f: impl Fn(u32) -> bool) -> u32 {
+        let mut spin = SPIN_LIMIT;
+        loop {
+            let state = self.state.load(Relaxed);
+            if f(state) || spin == 0 {
+                return state;
+            }
+            std::hint::spin_loop();
+            spin -= 1;
+        }
+    }
+
+    #[inline]
+    fn spin_write(&self) -> u32 {
+        self.spin_until(|state| is_unlocked(state) || has_writers_waiting(state))
+    }
+
+    #[inline]
+    fn spin_read(&self) -> u32 {
+        self.spin_until(|state| {
+            !is_write_locked(state) || has_readers_waiting(state) || has_writers_waiting(state)
+        })
+    }
+
+    /// Releases a read lock without a guard.
+    ///
+    /// # Safety
+    ///
+    /// The lock must be read locked, and the guard which locked it forgotten.
+    #[inline]
+    pub unsafe fn read_unlock(self: Pin<&Self>) {
+        drop(ReadGuard { lock: self });
+    }
+
+    /// Releases the write lock without a guard.
+    ///
+    /// # Safety
+    ///
+    /// The lock must be write locked, and the guard which locked it forgotten.
+    #[inline]
+    pub unsafe fn write_unlock(self: Pin<&Self>) {
+        drop(WriteGuard { lock: self });
+    }
+}
+
+pub struct ReadGuard<'a> {
+    lock: Pin<&'a RwLock>,
+}
+impl Clone for ReadGuard<'_> {
+    /// Adds a reader without waiting, as the lock is already held for reading.
+    ///
+    /// Panics if the maximum number of readers is reached.
+    #[inline]
+    fn clone(&self) -> Self {
+        self.lock
+            .state
+            .fetch_update(Relaxed, Relaxed, |state| {
+                (state & MASK < MAX_READERS).then(|| state + READ_LOCKED)
+            })
+            .expect("rwlock maximum reader count exceeded");
+        ReadGuard { lock: self.lock }
+    }
+}
+impl Drop for ReadGuard<'_> {
+    #[inline]
+    fn drop(&mut self) {
+        let state = self.lock.state.fetch_sub(READ_LOCKED, Release) - READ_LOCKED;
+        // Readers only wait while the lock is write locked or a writer is
+        // waiting, so only a waiting writer needs to be woken up by the last
+        // reader.
+        if is_unlocked(state) && has_writers_waiting(state) {
+            self.lock.wake_writer_or_readers(state);
+        }
+    }
+}
+
+pub struct WriteGuard<'a> {
+    lock: Pin<&'a RwLock>,
+}
+impl Drop for WriteGuard<'_> {
+    #[inline]
+    fn drop(&mut self) {
+        let state = self.lock.state.fetch_sub(WRITE_LOCKED, Release) - WRITE_LOCKED;
+        debug_assert!(is_unlocked(state));
+        if has_writers_waiting(state) || has_readers_waiting(state) {
+            self.lock.wake_writer_or_readers(state);
+        }
+    }
+}
@@ -1,9 +1,84 @@
 cfg_if::cfg_if! {
-    if #[cfg(unix)] {
+    if #[cfg(feature = "parking-lot-core")] {
+        mod parking_lot;
+        pub use parking_lot::*;
+    } else if #[cfg(feature = "thread-park")] {
+        mod thread_park;
+        pub use thread_park::*;
+    } else if #[cfg(feature = "spin")] {
+        mod spin;
+        pub use spin::*;
+    } else if #[cfg(any(target_os = "linux", target_os = "android"))] {
+        mod linux;
+        pub use linux::*;
+    } else if #[cfg(target_os = "freebsd")] {
+        mod freebsd;
+        pub use freebsd::*;
+    } else if #[cfg(target_os = "fuchsia")] {
+        mod fuchsia;
+        pub use fuchsia::*;
+    } else if #[cfg(target_os = "netbsd")] {
+        mod netbsd;
+        pub use netbsd::*;
+    } else if #[cfg(target_os = "redox")] {
+        mod redox;
+        pub use redox::*;
+    } else if #[cfg(any(target_os = "illumos", target_os = "solaris"))] {
+        mod illumos;
+        pub use illumos::*;
+    } else if #[cfg(target_os = "emscripten")] {
+        mod emscripten;
+        pub use emscripten::*;
+    } else if #[cfg(all(target_arch = "wasm32", target_feature = "atomics"))] {
+        mod wasm;
+        pub use wasm::*;
+    } else if #[cfg(target_os = "espidf")] {
+        mod espidf;
+        pub use espidf::*;
+    } else if #[cfg(unix)] {
         mod unix;
         pub use unix::*;
+    } else if #[cfg(windows)] {
+        mod windows;
+        pub use windows::*;
     } else {
-        mod fallback;
-        pub use fallback::*;
+        mod thread_park;
+        pub use thread_park::*;
     }
 }
+
+// The futex-like wait and wake operations of the platform, if it has them,
+// whichever backend the locks are built on.
+cfg_if::cfg_if! {
+    if #[cfg(any(target_os = "linux", target_os = "android"))] {
+        #[path = "linux/futex.rs"]
+        pub mod futex;
+    } else if #[cfg(target_os = "freebsd")] {
+        #[path = "freebsd/futex.rs"]
+        pub mod futex;
+    } else if #[cfg(target_os = "fuchsia")] {
+        #[path = "fuchsia/futex.rs"]
+        pub mod futex;
+    } else if #[cfg(target_os = "netbsd")] {
+        #[path = "netbsd/futex.rs"]
+        pub mod futex;
+    } else if #[cfg(target_os = "redox")] {
+        #[path = "redox/futex.rs"]
+        pub mod futex;
+    } else if #[cfg(target_os = "emscripten")] {
+        #[path = "emscripten/futex.rs"]
+        pub mod futex;
+    } else if #[cfg(all(target_arch = "wasm32", target_feature = "atomics"))] {
+        #[path = "wasm/futex.rs"]
+        pub mod futex;
+    }
+}
+
+/// The reason why a read lock could not be acquired.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadError {
+    /// The lock is held by a writer.
+    WouldBlock,
+    /// The maximum number of readers was reached.
+    TooManyReaders,
+}
@@ -0,0 +1,213 @@
+//! Futex operations on top of `_lwp_park` and `_lwp_unpark`.
+//!
+//! Waiting threads are kept in a fixed table of buckets, chosen by the address
+//! of the futex. Each waiter is a node on the stack of its thread, linked into
+//! the list of its bucket, which is protected by a spin lock. The value of the
+//! futex is checked with that lock held, and wakers take it after changing the
+//! value, so a wakeup can not be lost in between.
+
+use std::cell::{Cell, UnsafeCell};
+use std::convert::TryInto;
+use std::hint;
+use std::io;
+use std::mem;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering::*};
+use std::thread;
+use std::time::Duration;
+
+const BUCKETS: usize = 64;
+
+struct Bucket {
+    locked: AtomicBool,
+    head: UnsafeCell<*const Waiter>,
+}
+
+unsafe impl Sync for Bucket {}
+
+struct Waiter {
+    futex: *const AtomicU32,
+    lwp: libc::lwpid_t,
+    next: Cell<*const Waiter>,
+    woken: AtomicBool,
+}
+
+#[allow(clippy::declare_interior_mutable_const)]
+const BUCKET: Bucket = Bucket {
+    locked: AtomicBool::new(false),
+    head: UnsafeCell::new(ptr::null()),
+};
+
+static TABLE: [Bucket; BUCKETS] = [BUCKET; BUCKETS];
+
+impl Bucket {
+    fn of(futex: &AtomicU32) -> &'static Bucket {
+        // Fibonacci hashing, so that neighbouring futexes are spread out.
+        let hash = (futex.as_ptr() as usize as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+        &TABLE[(hash >> (64 - BUCKETS.trailing_zeros())) as usize]
+    }
+
+    fn lock(&self) -> BucketGuard<'_> {
+        // The lock is only held for a few instructions, or a few system calls
+        // when waking up threads, so it is not worth parking for.
+        let mut spins = 0;
+        while self
+            .locked
+            .compare_exchange_weak(false, true, Acquire, Relaxed)
+            .is_err()
+        {
+            if spins < 100 {
+                spins += 1;
+                hint::spin_loop();
+            } else {
+                thread::yield_now();
+            }
+        }
+        BucketGuard { bucket: self }
+    }
+}
+
+struct BucketGuard<'a> {
+    bucket: &'a Bucket,
+}
+
+impl BucketGuard<'_> {
+    fn push(&mut self, waiter: &Waiter) {
+        unsafe {
+            waiter.next.set(*self.bucket.head.get());
+            *self.bucket.head.get() = waiter;
+        }
+    }
+
+    /// Unlinks the waiters for which `f` returns `true`, until it returns
+    /// `None`.
+    fn remove(&mut self, mut f: impl FnMut(&Waiter) -> Option<bool>) {
+        unsafe {
+            let mut link: *const Cell<*const Waiter> = self.bucket.head.get() as *const _;
+            loop {
+                let waiter = (*link).get();
+                if waiter.is_null() {
+                    return;
+                }
+                // A removed waiter may be gone as soon as `f` returns.
+                let next = (*waiter).next.get();
+                match f(&*waiter) {
+                    None => return,
+                    Some(true) => (*link).set(next),
+                    Some(false) => link = &(*waiter).next,
+                }
+            }
+        }
+    }
+}
+
+impl Drop for BucketGuard<'_> {
+    fn drop(&mut self) {
+        self.bucket.locked.store(false, Release);
+    }
+}
+
+/// Blocks the current thread while `futex` is `expected`, until it is woken up
+/// by [`wake`] or the timeout expires.
+///
+/// Returns `false` if the timeout expired. Like any futex wait, this may also
+/// return spuriously.
+pub fn wait(futex: &AtomicU32, expected: u32, timeout: Option<Duration>) -> bool {
+    // The deadline is absolute, so it does not need to be recomputed after an
+    // interruption. A timeout which can not be represented is as good as no
+    // timeout.
+    let mut deadline = timeout.and_then(|dur| {
+        let mut now: libc::timespec = unsafe { mem::zeroed() };
+        let r = unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut now) };
+        assert_eq!(r, 0);
+        let nsec = now.tv_nsec as u32 + dur.subsec_nanos();
+        let sec = now
+            .tv_sec
+            .checked_add(dur.as_secs().try_into().ok()?)?
+            .checked_add((nsec / 1_000_000_000) as libc::time_t)?;
+        Some(libc::timespec {
+            tv_sec: sec,
+            tv_nsec: (nsec % 1_000_000_000) as _,
+        })
+    });
+
+    let bucket = Bucket::of(futex);
+    let waiter = Waiter {
+        futex,
+        lwp: unsafe { libc::_lwp_self() },
+        next: Cell::new(ptr::null()),
+        woken: AtomicBool::new(false),
+    };
+    {
+        let mut guard = bucket.lock();
+        if futex.load(Relaxed) != expected {
+            return true;
+        }
+        guard.push(&waiter);
+    }
+
+    loop {
+        if waiter.woken.load(Acquire) {
+            return true;
+        }
+        let ts = deadline
+            .as_mut()
+            .map_or(ptr::null_mut(), |ts| ts as *mut libc::timespec);
+        let r = unsafe {
+            libc::_lwp_park(
+                libc::CLOCK_MONOTONIC,
+                libc::TIMER_ABSTIME,
+                ts,
+                0,
+                futex.as_ptr().cast(),
+                ptr::null_mut(),
+            )
+        };
+        if r == 0 {
+            continue;
+        }
+        match io::Error::last_os_error().raw_os_error() {
+            // A wakeup was sent before the thread went to sleep, or the
+            // thread was interrupted by a signal.
+            Some(libc::EALREADY) | Some(libc::EINTR) => {}
+            Some(libc::ETIMEDOUT) => {
+                // The waiter may have been woken up in the meantime, in which
+                // case it is already unlinked.
+                let mut guard = bucket.lock();
+                if waiter.woken.load(Acquire) {
+                    return true;
+                }
+                guard.remove(|w| Some(ptr::eq(w, &waiter)));
+                return false;
+            }
+            _ => panic!("_lwp_park failed: {}", io::Error::last_os_error()),
+        }
+    }
+}
+
+/// Wakes up at most `count` threads blocked in [`wait`] on `futex`.
+///
+/// Returns whether any thread was woken up.
+pub fn wake(futex: &AtomicU32, count: i32) -> bool {
+    let mut left = count;
+    let mut guard = Bucket::of(futex).lock();
+    guard.remove(|waiter| {
+        if left == 0 {
+            return None;
+        }
+        if !ptr::eq(waiter.futex, futex) {
+            return Some(false);
+        }
+        left -= 1;
+        // The waiter may return as soon as it sees this, and its node lives on
+        // its stack, so the thread to unpark is read before. Unparking a thread
+        // which is not parked only makes its next park return early.
+        let lwp = waiter.lwp;
+        waiter.woken.store(true, Release);
+        unsafe {
+            libc::_lwp_unpark(lwp, futex.as_ptr().cast());
+        }
+        Some(true)
+    });
+    left != count
+}
@@ -0,0 +1,17 @@
+//! A backend built on NetBSD LWP parking.
+//!
+//! `_lwp_park` and `_lwp_unpark` block and wake up a given thread, which is
+//! enough to provide the wait and wake operations of a Linux futex, so the
+//! primitives are the ones of the Linux backend, built on top of them instead.
+//! Like there, an uninitialized `Mutex`, `Condvar` or `RwLock` is already
+//! usable, and nothing is allocated by this crate or libpthread.
+
+#[path = "../linux/condvar.rs"]
+pub mod condvar;
+#[path = "../linux/mutex.rs"]
+pub mod mutex;
+#[path = "../linux/rwlock.rs"]
+pub mod rwlock;
+
+use super::futex;
+pub use crate::sys_common::{barrier, remutex};
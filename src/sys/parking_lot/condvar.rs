@@ -0,0 +1,169 @@
+use crate::sys;
+use crate::sys_common::init_assert::InitAssert;
+use parking_lot_core::{FilterOp, ParkResult, DEFAULT_PARK_TOKEN, DEFAULT_UNPARK_TOKEN};
+use std::marker::PhantomPinned;
+use std::mem;
+use std::pin::Pin;
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, Ordering::*};
+use std::time::{Duration, Instant};
+
+pub struct Condvar {
+    #[cfg(debug_assertions)]
+    initialized: InitAssert,
+    mutex: AtomicPtr<sys::mutex::Mutex>,
+    _p: PhantomPinned,
+}
+
+unsafe impl Send for Condvar {}
+unsafe impl Sync for Condvar {}
+
+impl Condvar {
+    #[inline]
+    pub const fn uninit() -> Self {
+        Self {
+            #[cfg(debug_assertions)]
+            initialized: InitAssert::new(),
+            mutex: AtomicPtr::new(ptr::null_mut()),
+            _p: PhantomPinned,
+        }
+    }
+
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            #[cfg(debug_assertions)]
+            initialized: InitAssert::initialized(()),
+            mutex: AtomicPtr::new(ptr::null_mut()),
+            _p: PhantomPinned,
+        }
+    }
+
+    #[inline]
+    pub fn init(self: Pin<&Self>) {
+        #[cfg(debug_assertions)]
+        self.initialized.init(|| {});
+    }
+
+    #[inline]
+    pub fn notify_one(self: Pin<&Self>) {
+        #[cfg(debug_assertions)]
+        {
+            self.initialized.get();
+        }
+
+        // Safety: The key is the address of this condition variable, which is
+        // pinned, and the callback does not call into `parking_lot_core`.
+        unsafe {
+            parking_lot_core::unpark_one(self.key(), |_| DEFAULT_UNPARK_TOKEN);
+        }
+    }
+
+    #[inline]
+    pub fn notify_all(self: Pin<&Self>) {
+        #[cfg(debug_assertions)]
+        {
+            self.initialized.get();
+        }
+
+        // Safety: The key is the address of this condition variable, which is
+        // pinned.
+        unsafe {
+            parking_lot_core::unpark_all(self.key(), DEFAULT_UNPARK_TOKEN);
+        }
+    }
+
+    #[inline]
+    pub fn notify_many(self: Pin<&Self>, n: usize) {
+        #[cfg(debug_assertions)]
+        {
+            self.initialized.get();
+        }
+
+        let mut left = n;
+        let filter = |_| {
+            if left == 0 {
+                return FilterOp::Stop;
+            }
+            left -= 1;
+            FilterOp::Unpark
+        };
+        // Safety: The key is the address of this condition variable, which is
+        // pinned, and the callbacks do not call into `parking_lot_core`.
+        unsafe {
+            parking_lot_core::unpark_filter(self.key(), filter, |_| DEFAULT_UNPARK_TOKEN);
+        }
+    }
+
+    #[inline]
+    pub unsafe fn wait<'a>(
+        self: Pin<&Self>,
+        lock: sys::mutex::MutexGuard<'a>,
+    ) -> sys::mutex::MutexGuard<'a> {
+        self.park(lock, None).1
+    }
+
+    #[inline]
+    pub unsafe fn wait_timeout<'a>(
+        &self,
+        lock: sys::mutex::MutexGuard<'a>,
+        dur: Duration,
+    ) -> (bool, sys::mutex::MutexGuard<'a>) {
+        // A timeout which can not be represented is as good as no timeout.
+        self.park(lock, Instant::now().checked_add(dur))
+    }
+
+    unsafe fn park<'a>(
+        &self,
+        lock: sys::mutex::MutexGuard<'a>,
+        timeout: Option<Instant>,
+    ) -> (bool, sys::mutex::MutexGuard<'a>) {
+        #[cfg(debug_assertions)]
+        {
+            self.initialized.get();
+        }
+
+        let mutex = lock.mutex;
+        self.verify(&mutex);
+        mem::forget(lock);
+
+        // The mutex is only unlocked once this thread is in the queue, so a
+        // notification sent by a thread which then acquires the mutex can not
+        // be missed.
+        let result = parking_lot_core::park(
+            self.key(),
+            || true,
+            || mutex.unlock_raw(),
+            |_, _| {},
+            DEFAULT_PARK_TOKEN,
+            timeout,
+        );
+
+        mutex.lock_raw();
+        (
+            result != ParkResult::TimedOut,
+            sys::mutex::MutexGuard { mutex },
+        )
+    }
+
+    #[inline]
+    fn key(&self) -> usize {
+        self as *const _ as usize
+    }
+
+    // Waiting on the same condition variable with different mutexes is not
+    // supported by the other backends, so we remember the first mutex and
+    // panic if another one is ever used.
+    #[inline]
+    fn verify(&self, mutex: &sys::mutex::Mutex) {
+        let mutex = mutex as *const _ as *mut _;
+        match self
+            .mutex
+            .compare_exchange(ptr::null_mut(), mutex, Relaxed, Relaxed)
+        {
+            Ok(_) => {}
+            Err(addr) if addr == mutex => {}
+            Err(_) => panic!("attempted to use a condition variable with two mutexes"),
+        }
+    }
+}
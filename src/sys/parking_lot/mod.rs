@@ -0,0 +1,12 @@
+//! A backend built on the word-sized parking primitives of `parking_lot_core`.
+//!
+//! This gives the same small, allocation-free locks on every platform, at the
+//! cost of not using the primitives of the operating system directly. Threads
+//! waiting on a lock are parked in a global hash table keyed by the address of
+//! the lock, which is stable because every primitive is pinned.
+
+pub mod condvar;
+pub mod mutex;
+pub mod rwlock;
+
+pub use crate::sys_common::{barrier, remutex};
@@ -0,0 +1,290 @@
+use crate::sys_common::init_assert::InitAssert;
+use parking_lot_core::{
+    ParkResult, SpinWait, UnparkToken, DEFAULT_PARK_TOKEN, DEFAULT_UNPARK_TOKEN,
+};
+use std::marker::PhantomPinned;
+use std::mem;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU8, Ordering::*};
+use std::thread;
+use std::time::Instant;
+
+const LOCKED_BIT: u8 = 0b01;
+const PARKED_BIT: u8 = 0b10;
+
+// Unpark token telling the woken thread that it owns the lock.
+const TOKEN_HANDOFF: UnparkToken = UnparkToken(1);
+
+pub struct Mutex {
+    state: AtomicU8,
+    handoff: bool,
+    #[cfg(debug_assertions)]
+    initialized: InitAssert,
+    _p: PhantomPinned,
+}
+
+unsafe impl Send for Mutex {}
+unsafe impl Sync for Mutex {}
+
+impl Mutex {
+    #[inline]
+    pub const fn uninit() -> Self {
+        Self {
+            state: AtomicU8::new(0),
+            handoff: false,
+            #[cfg(debug_assertions)]
+            initialized: InitAssert::new(),
+            _p: PhantomPinned,
+        }
+    }
+
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            state: AtomicU8::new(0),
+            handoff: false,
+            #[cfg(debug_assertions)]
+            initialized: InitAssert::initialized(()),
+            _p: PhantomPinned,
+        }
+    }
+
+    /// Hands the lock directly to the longest waiter on unlock, instead of
+    /// letting every thread race for it.
+    #[inline]
+    pub fn handoff(mut self, enabled: bool) -> Self {
+        self.handoff = enabled;
+        self
+    }
+
+    #[inline]
+    pub fn init(self: Pin<&Self>) {
+        #[cfg(debug_assertions)]
+        self.initialized.init(|| {});
+    }
+
+    /// Resets the mutex to unlocked, as the thread which held it may not exist
+    /// in the child of a `fork`.
+    #[cfg(unix)]
+    pub unsafe fn reinit_after_fork(self: Pin<&Self>) {
+        self.state.store(0, Relaxed);
+    }
+
+    #[inline]
+    pub fn lock(self: Pin<&Self>) -> MutexGuard<'_> {
+        self.lock_raw();
+        MutexGuard { mutex: self }
+    }
+
+    #[inline]
+    pub fn try_lock(self: Pin<&Self>) -> Option<MutexGuard<'_>> {
+        #[cfg(debug_assertions)]
+        {
+            self.initialized.get();
+        }
+
+        let mut state = self.state.load(Relaxed);
+        loop {
+            if state & LOCKED_BIT != 0 {
+                return None;
+            }
+            match self
+                .state
+                .compare_exchange_weak(state, state | LOCKED_BIT, Acquire, Relaxed)
+            {
+                Ok(_) => return Some(MutexGuard { mutex: self }),
+                Err(x) => state = x,
+            }
+        }
+    }
+
+    #[inline]
+    pub fn try_lock_until(self: Pin<&Self>, deadline: Instant) -> Option<MutexGuard<'_>> {
+        #[cfg(debug_assertions)]
+        {
+            self.initialized.get();
+        }
+
+        let locked = self
+            .state
+            .compare_exchange_weak(0, LOCKED_BIT, Acquire, Relaxed)
+            .is_ok()
+            || self.lock_slow(Some(deadline));
+        if locked {
+            Some(MutexGuard { mutex: self })
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    pub(super) fn lock_raw(&self) {
+        #[cfg(debug_assertions)]
+        {
+            self.initialized.get();
+        }
+
+        if self
+            .state
+            .compare_exchange_weak(0, LOCKED_BIT, Acquire, Relaxed)
+            .is_err()
+        {
+            self.lock_slow(None);
+        }
+    }
+
+    // Returns `false` if the deadline was reached before the lock was acquired.
+    #[cold]
+    fn lock_slow(&self, deadline: Option<Instant>) -> bool {
+        let mut spinwait = SpinWait::new();
+        let mut state = self.state.load(Relaxed);
+        loop {
+            // Grab the lock if it isn't locked, even if there is a queue on it.
+            if state & LOCKED_BIT == 0 {
+                match self
+                    .state
+                    .compare_exchange_weak(state, state | LOCKED_BIT, Acquire, Relaxed)
+                {
+                    Ok(_) => return true,
+                    Err(x) => state = x,
+                }
+                continue;
+            }
+
+            // If there is no queue, try spinning a few times.
+            if state & PARKED_BIT == 0 && spinwait.spin() {
+                state = self.state.load(Relaxed);
+                continue;
+            }
+
+            // Set the parked bit.
+            if state & PARKED_BIT == 0 {
+                if let Err(x) =
+                    self.state
+                        .compare_exchange_weak(state, state | PARKED_BIT, Relaxed, Relaxed)
+                {
+                    state = x;
+                    continue;
+                }
+            }
+
+            // Park our thread until we are woken up by an unlock.
+            let addr = self as *const _ as usize;
+            let validate = || self.state.load(Relaxed) == LOCKED_BIT | PARKED_BIT;
+            // Clear the parked bit if we were the last parked thread.
+            let timed_out = |_, was_last_thread| {
+                if was_last_thread {
+                    self.state.fetch_and(!PARKED_BIT, Relaxed);
+                }
+            };
+            // Safety: The key is the address of this mutex, which is pinned,
+            // and the callbacks do not call into `parking_lot_core`.
+            let result = unsafe {
+                parking_lot_core::park(
+                    addr,
+                    validate,
+                    || {},
+                    timed_out,
+                    DEFAULT_PARK_TOKEN,
+                    deadline,
+                )
+            };
+
+            match result {
+                // The unlocking thread left the lock locked for us.
+                ParkResult::Unparked(TOKEN_HANDOFF) => return true,
+                ParkResult::TimedOut => return false,
+                _ => {}
+            }
+
+            // Loop back and try locking again.
+            spinwait.reset();
+            state = self.state.load(Relaxed);
+        }
+    }
+
+    /// # Safety
+    ///
+    /// The mutex must be locked, and the guard which locked it forgotten.
+    #[inline]
+    pub(super) unsafe fn unlock_raw(&self) {
+        if self
+            .state
+            .compare_exchange(LOCKED_BIT, 0, Release, Relaxed)
+            .is_err()
+        {
+            self.unlock_slow(false);
+        }
+    }
+
+    /// Hands the lock to the thread which has waited the longest if `fair` is
+    /// `true` or handoff is enabled.
+    #[cold]
+    fn unlock_slow(&self, fair: bool) {
+        let handoff = self.handoff || fair;
+        let addr = self as *const _ as usize;
+        let callback = |result: parking_lot_core::UnparkResult| {
+            // Keep the lock locked, and let the woken thread own it.
+            if handoff && result.unparked_threads != 0 {
+                if !result.have_more_threads {
+                    self.state.store(LOCKED_BIT, Relaxed);
+                }
+                return TOKEN_HANDOFF;
+            }
+
+            // Clear the parked bit if there are no more parked threads, and
+            // release the lock.
+            if result.have_more_threads {
+                self.state.store(PARKED_BIT, Release);
+            } else {
+                self.state.store(0, Release);
+            }
+            DEFAULT_UNPARK_TOKEN
+        };
+        // Safety: The key is the address of this mutex, which is pinned, and
+        // the callback does not call into `parking_lot_core`.
+        let result = unsafe { parking_lot_core::unpark_one(addr, callback) };
+
+        // The woken thread can not run until it is scheduled, and we are most
+        // likely to take the lock again if we keep running, so let it go first.
+        if handoff && result.unparked_threads != 0 {
+            thread::yield_now();
+        }
+    }
+
+    /// Unlocks the mutex without a guard.
+    ///
+    /// # Safety
+    ///
+    /// The mutex must be locked, and the guard which locked it forgotten.
+    #[inline]
+    pub unsafe fn unlock(self: Pin<&Self>) {
+        self.unlock_raw();
+    }
+}
+
+pub struct MutexGuard<'a> {
+    pub(super) mutex: Pin<&'a Mutex>,
+}
+
+impl<'a> MutexGuard<'a> {
+    /// Hands the mutex to the thread which has waited the longest, and locks
+    /// it again. Does nothing if nobody is waiting.
+    #[inline]
+    pub fn bump(self) -> Self {
+        let mutex = self.mutex;
+        if mutex.state.load(Relaxed) & PARKED_BIT == 0 {
+            return self;
+        }
+        mem::forget(self);
+        mutex.unlock_slow(true);
+        mutex.lock()
+    }
+}
+
+impl Drop for MutexGuard<'_> {
+    #[inline]
+    fn drop(&mut self) {
+        unsafe { self.mutex.unlock_raw() }
+    }
+}
@@ -0,0 +1,66 @@
+use std::convert::TryInto;
+use std::ptr;
+use std::sync::atomic::{AtomicU32, Ordering::*};
+use std::time::{Duration, Instant};
+use syscall::call;
+use syscall::data::TimeSpec;
+use syscall::error::{Error, EAGAIN, EINTR, ETIMEDOUT};
+use syscall::flag::{FUTEX_WAIT, FUTEX_WAKE};
+
+/// Blocks the current thread while `futex` is `expected`, until it is woken up
+/// by [`wake`] or the timeout expires.
+///
+/// Returns `false` if the timeout expired. Like any futex wait, this may also
+/// return spuriously.
+pub fn wait(futex: &AtomicU32, expected: u32, timeout: Option<Duration>) -> bool {
+    // A timeout which can not be represented is as good as no timeout.
+    let deadline = timeout.and_then(|dur| Instant::now().checked_add(dur));
+    loop {
+        if futex.load(Relaxed) != expected {
+            return true;
+        }
+        let timespec = match deadline {
+            Some(deadline) => {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if remaining.is_zero() {
+                    return false;
+                }
+                Some(TimeSpec {
+                    tv_sec: remaining.as_secs().try_into().unwrap_or(i64::MAX),
+                    tv_nsec: remaining.subsec_nanos() as i32,
+                })
+            }
+            None => None,
+        };
+        let r = unsafe {
+            call::futex(
+                futex.as_ptr().cast(),
+                FUTEX_WAIT,
+                expected as i32,
+                timespec
+                    .as_ref()
+                    .map_or(ptr::null(), |t| t as *const TimeSpec) as usize,
+                ptr::null_mut(),
+            )
+        };
+        match r {
+            Ok(_) => return true,
+            // The futex changed before the thread went to sleep.
+            Err(Error { errno: EAGAIN }) => return true,
+            Err(Error { errno: ETIMEDOUT }) => return false,
+            // Interrupted by a signal, so sleep again with what is left of the
+            // timeout.
+            Err(Error { errno: EINTR }) => continue,
+            Err(e) => panic!("futex wait failed: {}", e),
+        }
+    }
+}
+
+/// Wakes up at most `count` threads blocked in [`wait`] on `futex`.
+///
+/// Returns whether any thread was woken up.
+#[inline]
+pub fn wake(futex: &AtomicU32, count: i32) -> bool {
+    let r = unsafe { call::futex(futex.as_ptr().cast(), FUTEX_WAKE, count, 0, ptr::null_mut()) };
+    matches!(r, Ok(woken) if woken > 0)
+}
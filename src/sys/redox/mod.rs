@@ -0,0 +1,17 @@
+//! A backend built on Redox futexes.
+//!
+//! The futex system call of the Redox kernel, which relibc builds its own
+//! locks on, has the same wait and wake operations as a Linux futex, so the
+//! primitives are the ones of the Linux backend, built on top of it instead.
+//! Like there, an uninitialized `Mutex`, `Condvar` or `RwLock` is already
+//! usable.
+
+#[path = "../linux/condvar.rs"]
+pub mod condvar;
+#[path = "../linux/mutex.rs"]
+pub mod mutex;
+#[path = "../linux/rwlock.rs"]
+pub mod rwlock;
+
+use super::futex;
+pub use crate::sys_common::{barrier, remutex};
@@ -0,0 +1,116 @@
+use crate::sys;
+use core::hint;
+use core::marker::PhantomPinned;
+use core::mem;
+use core::pin::Pin;
+use core::ptr;
+use core::sync::atomic::{AtomicPtr, AtomicU32, Ordering::*};
+use core::time::Duration;
+use std::time::Instant;
+
+pub struct Condvar {
+    // Incremented by every notification, which waiters poll for. Every
+    // notification wakes up every waiter, which is allowed as they may wake
+    // up spuriously anyway.
+    seq: AtomicU32,
+    mutex: AtomicPtr<sys::mutex::Mutex>,
+    _p: PhantomPinned,
+}
+
+unsafe impl Send for Condvar {}
+unsafe impl Sync for Condvar {}
+
+impl Condvar {
+    #[inline]
+    pub const fn uninit() -> Self {
+        Self {
+            seq: AtomicU32::new(0),
+            mutex: AtomicPtr::new(ptr::null_mut()),
+            _p: PhantomPinned,
+        }
+    }
+
+    #[inline]
+    pub const fn new() -> Self {
+        Self::uninit()
+    }
+
+    #[inline]
+    pub fn init(self: Pin<&Self>) {}
+
+    #[inline]
+    pub fn notify_one(self: Pin<&Self>) {
+        self.seq.fetch_add(1, Relaxed);
+    }
+
+    #[inline]
+    pub fn notify_all(self: Pin<&Self>) {
+        self.seq.fetch_add(1, Relaxed);
+    }
+
+    // Every spinning waiter sees the notification.
+    #[inline]
+    pub fn notify_many(self: Pin<&Self>, _n: usize) {
+        self.seq.fetch_add(1, Relaxed);
+    }
+
+    #[inline]
+    pub unsafe fn wait<'a>(
+        self: Pin<&Self>,
+        lock: sys::mutex::MutexGuard<'a>,
+    ) -> sys::mutex::MutexGuard<'a> {
+        self.sleep(lock, None).1
+    }
+
+    #[inline]
+    pub unsafe fn wait_timeout<'a>(
+        &self,
+        lock: sys::mutex::MutexGuard<'a>,
+        dur: Duration,
+    ) -> (bool, sys::mutex::MutexGuard<'a>) {
+        self.sleep(lock, Some(dur))
+    }
+
+    unsafe fn sleep<'a>(
+        &self,
+        lock: sys::mutex::MutexGuard<'a>,
+        timeout: Option<Duration>,
+    ) -> (bool, sys::mutex::MutexGuard<'a>) {
+        let mutex = lock.mutex;
+        self.verify(&mutex);
+        mem::forget(lock);
+
+        let seq = self.seq.load(Relaxed);
+        mutex.unlock_raw();
+        // A timeout which can not be represented is as good as no timeout.
+        let deadline = timeout.and_then(|dur| Instant::now().checked_add(dur));
+        let mut notified = true;
+        while self.seq.load(Relaxed) == seq {
+            if let Some(deadline) = deadline {
+                if Instant::now() >= deadline {
+                    notified = false;
+                    break;
+                }
+            }
+            hint::spin_loop();
+        }
+        mutex.lock_raw();
+        (notified, sys::mutex::MutexGuard { mutex })
+    }
+
+    // Waiting on the same condition variable with different mutexes is not
+    // supported by the other backends, so we remember the first mutex and
+    // panic if another one is ever used.
+    #[inline]
+    fn verify(&self, mutex: &sys::mutex::Mutex) {
+        let mutex = mutex as *const _ as *mut _;
+        match self
+            .mutex
+            .compare_exchange(ptr::null_mut(), mutex, Relaxed, Relaxed)
+        {
+            Ok(_) => {}
+            Err(addr) if addr == mutex => {}
+            Err(_) => panic!("attempted to use a condition variable with two mutexes"),
+        }
+    }
+}
@@ -0,0 +1,18 @@
+//! A backend built on nothing but atomics and spinning.
+//!
+//! This is the backend of the `spin` feature, which never blocks a thread in
+//! the operating system. The primitives are made of `core` atomics, but the
+//! timed waits use the clock of `std`, and the rest of the crate needs `std`
+//! too, so this does not make the crate usable on `no_std` targets. Every
+//! wait is a busy loop, including waiting on a condition variable, which
+//! polls for notifications, so it is only suitable where critical sections
+//! are short or threads are not preempted.
+//!
+//! Like with futexes, an uninitialized `Mutex`, `Condvar` or `RwLock` is
+//! already usable.
+
+pub mod condvar;
+pub mod mutex;
+pub mod rwlock;
+
+pub use crate::sys_common::{barrier, remutex};
@@ -0,0 +1,124 @@
+use core::hint;
+use core::marker::PhantomPinned;
+use core::pin::Pin;
+use core::sync::atomic::{AtomicBool, Ordering::*};
+use std::time::Instant;
+
+pub struct Mutex {
+    locked: AtomicBool,
+    _p: PhantomPinned,
+}
+
+unsafe impl Send for Mutex {}
+unsafe impl Sync for Mutex {}
+
+impl Mutex {
+    #[inline]
+    pub const fn uninit() -> Self {
+        Self {
+            locked: AtomicBool::new(false),
+            _p: PhantomPinned,
+        }
+    }
+
+    #[inline]
+    pub const fn new() -> Self {
+        Self::uninit()
+    }
+
+    // Nobody is queued, so direct handoff is not supported.
+    #[inline]
+    pub fn handoff(self, _enabled: bool) -> Self {
+        self
+    }
+
+    #[inline]
+    pub fn init(self: Pin<&Self>) {}
+
+    /// Resets the mutex to unlocked, as the thread which held it may not exist
+    /// in the child of a `fork`.
+    #[cfg(unix)]
+    pub unsafe fn reinit_after_fork(self: Pin<&Self>) {
+        self.locked.store(false, Relaxed);
+    }
+
+    #[inline]
+    pub fn lock(self: Pin<&Self>) -> MutexGuard<'_> {
+        self.lock_raw();
+        MutexGuard { mutex: self }
+    }
+
+    #[inline]
+    pub fn try_lock(self: Pin<&Self>) -> Option<MutexGuard<'_>> {
+        self.locked
+            .compare_exchange(false, true, Acquire, Relaxed)
+            .ok()
+            .map(|_| MutexGuard { mutex: self })
+    }
+
+    #[inline]
+    pub fn try_lock_until(self: Pin<&Self>, deadline: Instant) -> Option<MutexGuard<'_>> {
+        loop {
+            if let Some(guard) = self.try_lock() {
+                return Some(guard);
+            }
+            while self.locked.load(Relaxed) {
+                if Instant::now() >= deadline {
+                    return None;
+                }
+                hint::spin_loop();
+            }
+        }
+    }
+
+    #[inline]
+    pub(super) fn lock_raw(&self) {
+        while self
+            .locked
+            .compare_exchange_weak(false, true, Acquire, Relaxed)
+            .is_err()
+        {
+            // Only read the lock while it is held, so that the cache line is
+            // not fought over.
+            while self.locked.load(Relaxed) {
+                hint::spin_loop();
+            }
+        }
+    }
+
+    #[inline]
+    pub(super) fn unlock_raw(&self) {
+        self.locked.store(false, Release);
+    }
+
+    /// Unlocks the mutex without a guard.
+    ///
+    /// # Safety
+    ///
+    /// The mutex must be locked, and the guard which locked it forgotten.
+    #[inline]
+    pub unsafe fn unlock(self: Pin<&Self>) {
+        self.unlock_raw();
+    }
+}
+
+pub struct MutexGuard<'a> {
+    pub(super) mutex: Pin<&'a Mutex>,
+}
+impl<'a> MutexGuard<'a> {
+    /// Unlocks the mutex, gives the spinning threads a chance to take it, and
+    /// locks it again.
+    #[inline]
+    pub fn bump(self) -> Self {
+        let mutex = self.mutex;
+        drop(self);
+        hint::spin_loop();
+        mutex.lock()
+    }
+}
+impl Drop for MutexGuard<'_> {
+    #[inline]
+    fn drop(&mut self) {
+        self.mutex.unlock_raw();
+    }
+}
@@ -0,0 +1,198 @@
+use crate::sys::ReadError;
+use core::hint;
+use core::marker::PhantomPinned;
+use core::pin::Pin;
+use core::sync::atomic::{AtomicUsize, Ordering::*};
+use std::time::Instant;
+
+const WRITE_LOCKED: usize = 0b01;
+// A writer is spinning, so new readers stay out until it got the lock.
+const WRITER_WAITING: usize = 0b10;
+const ONE_READER: usize = 0b100;
+const MAX_READERS: usize = usize::MAX / ONE_READER;
+
+#[inline]
+fn readers(state: usize) -> usize {
+    state / ONE_READER
+}
+
+pub struct RwLock {
+    state: AtomicUsize,
+    _p: PhantomPinned,
+}
+
+unsafe impl Send for RwLock {}
+unsafe impl Sync for RwLock {}
+
+impl RwLock {
+    #[inline]
+    pub const fn uninit() -> Self {
+        Self {
+            state: AtomicUsize::new(0),
+            _p: PhantomPinned,
+        }
+    }
+
+    #[inline]
+    pub const fn new() -> Self {
+        Self::uninit()
+    }
+
+    #[inline]
+    pub fn init(self: Pin<&Self>) {}
+
+    /// Resets the lock to unlocked, as the threads which held it may not exist
+    /// in the child of a `fork`.
+    #[cfg(unix)]
+    pub unsafe fn reinit_after_fork(self: Pin<&Self>) {
+        self.state.store(0, Relaxed);
+    }
+
+    #[inline]
+    pub fn try_read(self: Pin<&Self>) -> Result<ReadGuard<'_>, ReadError> {
+        let mut state = self.state.load(Relaxed);
+        loop {
+            if state & (WRITE_LOCKED | WRITER_WAITING) != 0 {
+                return Err(ReadError::WouldBlock);
+            }
+            if readers(state) == MAX_READERS {
+                return Err(ReadError::TooManyReaders);
+            }
+            match self
+                .state
+                .compare_exchange_weak(state, state + ONE_READER, Acquire, Relaxed)
+            {
+                Ok(_) => return Ok(ReadGuard { lock: self }),
+                Err(x) => state = x,
+            }
+        }
+    }
+
+    /// Returns `None` if the maximum number of readers was reached.
+    #[inline]
+    pub fn read(self: Pin<&Self>) -> Option<ReadGuard<'_>> {
+        loop {
+            match self.try_read() {
+                Ok(guard) => return Some(guard),
+                Err(ReadError::TooManyReaders) => return None,
+                Err(ReadError::WouldBlock) => hint::spin_loop(),
+            }
+        }
+    }
+
+    /// Returns [`ReadError::WouldBlock`] if the deadline was reached.
+    #[inline]
+    pub fn try_read_until(self: Pin<&Self>, deadline: Instant) -> Result<ReadGuard<'_>, ReadError> {
+        loop {
+            match self.try_read() {
+                Err(ReadError::WouldBlock) if Instant::now() < deadline => hint::spin_loop(),
+                result => return result,
+            }
+        }
+    }
+
+    #[inline]
+    pub fn try_write(self: Pin<&Self>) -> Option<WriteGuard<'_>> {
+        let mut state = self.state.load(Relaxed);
+        loop {
+            if state & !WRITER_WAITING != 0 {
+                return None;
+            }
+            // Another waiting writer sets the bit again while it spins.
+            match self
+                .state
+                .compare_exchange_weak(state, WRITE_LOCKED, Acquire, Relaxed)
+            {
+                Ok(_) => return Some(WriteGuard { lock: self }),
+                Err(x) => state = x,
+            }
+        }
+    }
+
+    #[inline]
+    pub fn write(self: Pin<&Self>) -> WriteGuard<'_> {
+        loop {
+            if let Some(guard) = self.try_write() {
+                return guard;
+            }
+            if self.state.load(Relaxed) & WRITER_WAITING == 0 {
+                self.state.fetch_or(WRITER_WAITING, Relaxed);
+            }
+            hint::spin_loop();
+        }
+    }
+
+    #[inline]
+    pub fn try_write_until(self: Pin<&Self>, deadline: Instant) -> Option<WriteGuard<'_>> {
+        loop {
+            if let Some(guard) = self.try_write() {
+                return Some(guard);
+            }
+            if Instant::now() >= deadline {
+                // Let readers in again. Other waiting writers set the bit
+                // again while they spin.
+                self.state.fetch_and(!WRITER_WAITING, Relaxed);
+                return None;
+            }
+            if self.state.load(Relaxed) & WRITER_WAITING == 0 {
+                self.state.fetch_or(WRITER_WAITING, Relaxed);
+            }
+            hint::spin_loop();
+        }
+    }
+
+    /// Releases a read lock without a guard.
+    ///
+    /// # Safety
+    ///
+    /// The lock must be read locked, and the guard which locked it forgotten.
+    #[inline]
+    pub unsafe fn read_unlock(self: Pin<&Self>) {
+        drop(ReadGuard { lock: self });
+    }
+
+    /// Releases the write lock without a guard.
+    ///
+    /// # Safety
+    ///
+    /// The lock must be write locked, and the guard which locked it forgotten.
+    #[inline]
+    pub unsafe fn write_unlock(self: Pin<&Self>) {
+        drop(WriteGuard { lock: self });
+    }
+}
+
+pub struct ReadGuard<'a> {
+    lock: Pin<&'a RwLock>,
+}
+impl Clone for ReadGuard<'_> {
+    /// Adds a reader without waiting, as the lock is already held for reading.
+    ///
+    /// Panics if the maximum number of readers is reached.
+    #[inline]
+    fn clone(&self) -> Self {
+        self.lock
+            .state
+            .fetch_update(Relaxed, Relaxed, |state| {
+                (readers(state) < MAX_READERS).then(|| state + ONE_READER)
+            })
+            .expect("rwlock maximum reader count exceeded");
+        ReadGuard { lock: self.lock }
+    }
+}
+impl Drop for ReadGuard<'_> {
+    #[inline]
+    fn drop(&mut self) {
+        self.lock.state.fetch_sub(ONE_READER, Release);
+    }
+}
+
+pub struct WriteGuard<'a> {
+    lock: Pin<&'a RwLock>,
+}
+impl Drop for WriteGuard<'_> {
+    #[inline]
+    fn drop(&mut self) {
+        self.lock.state.fetch_and(!WRITE_LOCKED, Release);
+    }
+}
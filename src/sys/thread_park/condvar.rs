@@ -0,0 +1,144 @@
+use super::queue::{ParkResult, Queue};
+use crate::sys;
+use crate::sys_common::init_assert::InitAssert;
+use std::marker::PhantomPinned;
+use std::mem;
+use std::pin::Pin;
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, Ordering::*};
+use std::time::{Duration, Instant};
+
+pub struct Condvar {
+    queue: Queue,
+    #[cfg(debug_assertions)]
+    initialized: InitAssert,
+    mutex: AtomicPtr<sys::mutex::Mutex>,
+    _p: PhantomPinned,
+}
+
+unsafe impl Send for Condvar {}
+unsafe impl Sync for Condvar {}
+
+impl Condvar {
+    #[inline]
+    pub const fn uninit() -> Self {
+        Self {
+            queue: Queue::new(),
+            #[cfg(debug_assertions)]
+            initialized: InitAssert::new(),
+            mutex: AtomicPtr::new(ptr::null_mut()),
+            _p: PhantomPinned,
+        }
+    }
+
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            queue: Queue::new(),
+            #[cfg(debug_assertions)]
+            initialized: InitAssert::initialized(()),
+            mutex: AtomicPtr::new(ptr::null_mut()),
+            _p: PhantomPinned,
+        }
+    }
+
+    #[inline]
+    pub fn init(self: Pin<&Self>) {
+        #[cfg(debug_assertions)]
+        self.initialized.init(|| {});
+    }
+
+    #[inline]
+    pub fn notify_one(self: Pin<&Self>) {
+        #[cfg(debug_assertions)]
+        {
+            self.initialized.get();
+        }
+
+        self.queue.unpark_one(|_| false);
+    }
+
+    #[inline]
+    pub fn notify_all(self: Pin<&Self>) {
+        #[cfg(debug_assertions)]
+        {
+            self.initialized.get();
+        }
+
+        self.queue.unpark_all();
+    }
+
+    #[inline]
+    pub fn notify_many(self: Pin<&Self>, n: usize) {
+        #[cfg(debug_assertions)]
+        {
+            self.initialized.get();
+        }
+
+        for _ in 0..n {
+            if !self.queue.unpark_one(|_| false).have_more {
+                break;
+            }
+        }
+    }
+
+    #[inline]
+    pub unsafe fn wait<'a>(
+        self: Pin<&Self>,
+        lock: sys::mutex::MutexGuard<'a>,
+    ) -> sys::mutex::MutexGuard<'a> {
+        self.park(lock, None).1
+    }
+
+    #[inline]
+    pub unsafe fn wait_timeout<'a>(
+        &self,
+        lock: sys::mutex::MutexGuard<'a>,
+        dur: Duration,
+    ) -> (bool, sys::mutex::MutexGuard<'a>) {
+        // A timeout which can not be represented is as good as no timeout.
+        self.park(lock, Instant::now().checked_add(dur))
+    }
+
+    unsafe fn park<'a>(
+        &self,
+        lock: sys::mutex::MutexGuard<'a>,
+        timeout: Option<Instant>,
+    ) -> (bool, sys::mutex::MutexGuard<'a>) {
+        #[cfg(debug_assertions)]
+        {
+            self.initialized.get();
+        }
+
+        let mutex = lock.mutex;
+        self.verify(&mutex);
+        mem::forget(lock);
+
+        // The mutex is only unlocked once this thread is in the queue, so a
+        // notification sent by a thread which then acquires the mutex can not
+        // be missed.
+        let result = self.queue.park(|| true, || mutex.unlock_raw(), timeout);
+
+        mutex.lock_raw();
+        (
+            result != ParkResult::TimedOut,
+            sys::mutex::MutexGuard { mutex },
+        )
+    }
+
+    // Waiting on the same condition variable with different mutexes is not
+    // supported by the other backends, so we remember the first mutex and
+    // panic if another one is ever used.
+    #[inline]
+    fn verify(&self, mutex: &sys::mutex::Mutex) {
+        let mutex = mutex as *const _ as *mut _;
+        match self
+            .mutex
+            .compare_exchange(ptr::null_mut(), mutex, Relaxed, Relaxed)
+        {
+            Ok(_) => {}
+            Err(addr) if addr == mutex => {}
+            Err(_) => panic!("attempted to use a condition variable with two mutexes"),
+        }
+    }
+}
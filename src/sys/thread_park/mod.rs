@@ -0,0 +1,14 @@
+//! A backend built on nothing but `std::thread::park` and atomics.
+//!
+//! This is the backend used on platforms without a dedicated one, and a plain
+//! Rust reference implementation which the other backends can be tested
+//! against with the `thread-park` feature. Each primitive keeps its own queue
+//! of parked threads, protected by a spin lock, so nothing here depends on
+//! the locks of the operating system.
+
+pub mod condvar;
+pub mod mutex;
+mod queue;
+pub mod rwlock;
+
+pub use crate::sys_common::{barrier, remutex};
@@ -0,0 +1,269 @@
+use super::queue::{ParkResult, Queue, SpinWait};
+use crate::sys_common::init_assert::InitAssert;
+use std::marker::PhantomPinned;
+use std::mem;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU8, Ordering::*};
+use std::thread;
+use std::time::Instant;
+
+const LOCKED_BIT: u8 = 0b01;
+const PARKED_BIT: u8 = 0b10;
+
+pub struct Mutex {
+    state: AtomicU8,
+    queue: Queue,
+    handoff: bool,
+    #[cfg(debug_assertions)]
+    initialized: InitAssert,
+    _p: PhantomPinned,
+}
+
+unsafe impl Send for Mutex {}
+unsafe impl Sync for Mutex {}
+
+impl Mutex {
+    #[inline]
+    pub const fn uninit() -> Self {
+        Self {
+            state: AtomicU8::new(0),
+            queue: Queue::new(),
+            handoff: false,
+            #[cfg(debug_assertions)]
+            initialized: InitAssert::new(),
+            _p: PhantomPinned,
+        }
+    }
+
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            state: AtomicU8::new(0),
+            queue: Queue::new(),
+            handoff: false,
+            #[cfg(debug_assertions)]
+            initialized: InitAssert::initialized(()),
+            _p: PhantomPinned,
+        }
+    }
+
+    /// Hands the lock directly to the longest waiter on unlock, instead of
+    /// letting every thread race for it.
+    #[inline]
+    pub fn handoff(mut self, enabled: bool) -> Self {
+        self.handoff = enabled;
+        self
+    }
+
+    #[inline]
+    pub fn init(self: Pin<&Self>) {
+        #[cfg(debug_assertions)]
+        self.initialized.init(|| {});
+    }
+
+    /// Resets the mutex to unlocked, as the thread which held it may not exist
+    /// in the child of a `fork`.
+    #[cfg(unix)]
+    pub unsafe fn reinit_after_fork(self: Pin<&Self>) {
+        self.state.store(0, Relaxed);
+        self.queue.reset();
+    }
+
+    #[inline]
+    pub fn lock(self: Pin<&Self>) -> MutexGuard<'_> {
+        self.lock_raw();
+        MutexGuard { mutex: self }
+    }
+
+    #[inline]
+    pub fn try_lock(self: Pin<&Self>) -> Option<MutexGuard<'_>> {
+        #[cfg(debug_assertions)]
+        {
+            self.initialized.get();
+        }
+
+        let mut state = self.state.load(Relaxed);
+        loop {
+            if state & LOCKED_BIT != 0 {
+                return None;
+            }
+            match self
+                .state
+                .compare_exchange_weak(state, state | LOCKED_BIT, Acquire, Relaxed)
+            {
+                Ok(_) => return Some(MutexGuard { mutex: self }),
+                Err(x) => state = x,
+            }
+        }
+    }
+
+    #[inline]
+    pub fn try_lock_until(self: Pin<&Self>, deadline: Instant) -> Option<MutexGuard<'_>> {
+        #[cfg(debug_assertions)]
+        {
+            self.initialized.get();
+        }
+
+        let locked = self
+            .state
+            .compare_exchange_weak(0, LOCKED_BIT, Acquire, Relaxed)
+            .is_ok()
+            || self.lock_slow(Some(deadline));
+        if locked {
+            Some(MutexGuard { mutex: self })
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    pub(super) fn lock_raw(&self) {
+        #[cfg(debug_assertions)]
+        {
+            self.initialized.get();
+        }
+
+        if self
+            .state
+            .compare_exchange_weak(0, LOCKED_BIT, Acquire, Relaxed)
+            .is_err()
+        {
+            self.lock_slow(None);
+        }
+    }
+
+    // Returns `false` if the deadline was reached before the lock was acquired.
+    #[cold]
+    fn lock_slow(&self, deadline: Option<Instant>) -> bool {
+        let mut spinwait = SpinWait::new();
+        let mut state = self.state.load(Relaxed);
+        loop {
+            // Grab the lock if it isn't locked, even if there is a queue on it.
+            if state & LOCKED_BIT == 0 {
+                match self
+                    .state
+                    .compare_exchange_weak(state, state | LOCKED_BIT, Acquire, Relaxed)
+                {
+                    Ok(_) => return true,
+                    Err(x) => state = x,
+                }
+                continue;
+            }
+
+            // If there is no queue, try spinning a few times.
+            if state & PARKED_BIT == 0 && spinwait.spin() {
+                state = self.state.load(Relaxed);
+                continue;
+            }
+
+            // Set the parked bit.
+            if state & PARKED_BIT == 0 {
+                if let Err(x) =
+                    self.state
+                        .compare_exchange_weak(state, state | PARKED_BIT, Relaxed, Relaxed)
+                {
+                    state = x;
+                    continue;
+                }
+            }
+
+            // Park our thread until we are woken up by an unlock.
+            let validate = || self.state.load(Relaxed) == LOCKED_BIT | PARKED_BIT;
+            // A waiter which times out may leave the parked bit set, which only
+            // costs the next unlock a trip through the queue.
+            let result = self.queue.park(validate, || {}, deadline);
+
+            match result {
+                // The unlocking thread left the lock locked for us.
+                ParkResult::Unparked { handoff: true } => return true,
+                ParkResult::TimedOut => return false,
+                _ => {}
+            }
+
+            // Loop back and try locking again.
+            spinwait.reset();
+            state = self.state.load(Relaxed);
+        }
+    }
+
+    /// # Safety
+    ///
+    /// The mutex must be locked, and the guard which locked it forgotten.
+    #[inline]
+    pub(super) unsafe fn unlock_raw(&self) {
+        if self
+            .state
+            .compare_exchange(LOCKED_BIT, 0, Release, Relaxed)
+            .is_err()
+        {
+            self.unlock_slow(false);
+        }
+    }
+
+    /// Hands the lock to the thread which has waited the longest if `fair` is
+    /// `true` or handoff is enabled.
+    #[cold]
+    fn unlock_slow(&self, fair: bool) {
+        let handoff = self.handoff || fair;
+        let result = self.queue.unpark_one(|result| {
+            // Keep the lock locked, and let the woken thread own it.
+            if handoff && result.unparked {
+                if !result.have_more {
+                    self.state.store(LOCKED_BIT, Relaxed);
+                }
+                return true;
+            }
+
+            // Clear the parked bit if there are no more parked threads, and
+            // release the lock.
+            if result.have_more {
+                self.state.store(PARKED_BIT, Release);
+            } else {
+                self.state.store(0, Release);
+            }
+            false
+        });
+
+        // The woken thread can not run until it is scheduled, and we are most
+        // likely to take the lock again if we keep running, so let it go first.
+        if handoff && result.unparked {
+            thread::yield_now();
+        }
+    }
+
+    /// Unlocks the mutex without a guard.
+    ///
+    /// # Safety
+    ///
+    /// The mutex must be locked, and the guard which locked it forgotten.
+    #[inline]
+    pub unsafe fn unlock(self: Pin<&Self>) {
+        self.unlock_raw();
+    }
+}
+
+pub struct MutexGuard<'a> {
+    pub(super) mutex: Pin<&'a Mutex>,
+}
+
+impl<'a> MutexGuard<'a> {
+    /// Hands the mutex to the thread which has waited the longest, and locks
+    /// it again. Does nothing if nobody is waiting.
+    #[inline]
+    pub fn bump(self) -> Self {
+        let mutex = self.mutex;
+        if mutex.state.load(Relaxed) & PARKED_BIT == 0 {
+            return self;
+        }
+        mem::forget(self);
+        mutex.unlock_slow(true);
+        mutex.lock()
+    }
+}
+
+impl Drop for MutexGuard<'_> {
+    #[inline]
+    fn drop(&mut self) {
+        unsafe { self.mutex.unlock_raw() }
+    }
+}
@@ -0,0 +1,234 @@
+use std::cell::UnsafeCell;
+use std::collections::VecDeque;
+use std::hint;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering::*};
+use std::sync::Arc;
+use std::thread::{self, Thread};
+use std::time::Instant;
+
+const PARKED: u8 = 0;
+const UNPARKED: u8 = 1;
+const HANDOFF: u8 = 2;
+
+/// The result of [`Queue::park`].
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum ParkResult {
+    /// The thread was unparked, and whether the unparking thread handed
+    /// something over to it.
+    Unparked { handoff: bool },
+    /// The validation callback returned `false`.
+    Invalid,
+    /// The deadline was reached.
+    TimedOut,
+}
+
+/// The result of [`Queue::unpark_one`], given to its callback.
+#[derive(Debug, Copy, Clone)]
+pub struct UnparkResult {
+    /// Whether a thread was unparked.
+    pub unparked: bool,
+    /// Whether threads are still parked after this one.
+    pub have_more: bool,
+}
+
+struct Waiter {
+    thread: Thread,
+    state: AtomicU8,
+}
+
+/// A queue of parked threads.
+///
+/// This mirrors the interface of `parking_lot_core`, with one queue per
+/// primitive instead of a global table. The callbacks run while the queue is
+/// locked, so they are atomic with respect to parking and unparking.
+pub struct Queue {
+    locked: AtomicBool,
+    waiters: UnsafeCell<VecDeque<Arc<Waiter>>>,
+}
+
+unsafe impl Send for Queue {}
+unsafe impl Sync for Queue {}
+
+impl Queue {
+    pub const fn new() -> Self {
+        Self {
+            locked: AtomicBool::new(false),
+            waiters: UnsafeCell::new(VecDeque::new()),
+        }
+    }
+
+    /// Parks the current thread at the back of the queue if `validate`
+    /// returns `true`, calling `before_sleep` once it is queued.
+    pub fn park(
+        &self,
+        validate: impl FnOnce() -> bool,
+        before_sleep: impl FnOnce(),
+        deadline: Option<Instant>,
+    ) -> ParkResult {
+        let waiter = {
+            let mut waiters = self.lock();
+            if !validate() {
+                return ParkResult::Invalid;
+            }
+            let waiter = Arc::new(Waiter {
+                thread: thread::current(),
+                state: AtomicU8::new(PARKED),
+            });
+            waiters.push_back(waiter.clone());
+            waiter
+        };
+        before_sleep();
+
+        loop {
+            match waiter.state.load(Acquire) {
+                PARKED => {}
+                state => {
+                    return ParkResult::Unparked {
+                        handoff: state == HANDOFF,
+                    }
+                }
+            }
+            match deadline {
+                None => thread::park(),
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now < deadline {
+                        thread::park_timeout(deadline - now);
+                        continue;
+                    }
+                    // If the waiter is gone, it was unparked in the meantime,
+                    // and its state is already set.
+                    let mut waiters = self.lock();
+                    if let Some(i) = waiters.iter().position(|w| Arc::ptr_eq(w, &waiter)) {
+                        waiters.remove(i);
+                        return ParkResult::TimedOut;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Unparks the thread at the front of the queue.
+    ///
+    /// `callback` is called whether a thread was unparked or not, and returns
+    /// whether to hand over to the unparked thread.
+    pub fn unpark_one(&self, callback: impl FnOnce(UnparkResult) -> bool) -> UnparkResult {
+        let (waiter, result) = {
+            let mut waiters = self.lock();
+            let waiter = waiters.pop_front();
+            let result = UnparkResult {
+                unparked: waiter.is_some(),
+                have_more: !waiters.is_empty(),
+            };
+            let handoff = callback(result);
+            if let Some(waiter) = &waiter {
+                let state = if handoff { HANDOFF } else { UNPARKED };
+                waiter.state.store(state, Release);
+            }
+            (waiter, result)
+        };
+        if let Some(waiter) = waiter {
+            waiter.thread.unpark();
+        }
+        result
+    }
+
+    /// Unparks every thread in the queue.
+    pub fn unpark_all(&self) {
+        let waiters = {
+            let mut waiters = self.lock();
+            for waiter in waiters.iter() {
+                waiter.state.store(UNPARKED, Release);
+            }
+            std::mem::take(&mut *waiters)
+        };
+        for waiter in waiters {
+            waiter.thread.unpark();
+        }
+    }
+
+    /// Empties the queue, as the parked threads do not exist in the child of a
+    /// `fork`. The waiters are leaked, as the queue may have been left
+    /// half-updated.
+    #[cfg(unix)]
+    pub unsafe fn reset(&self) {
+        std::ptr::write(self.waiters.get(), VecDeque::new());
+        self.locked.store(false, Relaxed);
+    }
+
+    fn lock(&self) -> QueueGuard<'_> {
+        let mut spinwait = SpinWait::new();
+        while self
+            .locked
+            .compare_exchange_weak(false, true, Acquire, Relaxed)
+            .is_err()
+        {
+            if !spinwait.spin() {
+                thread::yield_now();
+            }
+        }
+        QueueGuard { queue: self }
+    }
+}
+
+struct QueueGuard<'a> {
+    queue: &'a Queue,
+}
+
+impl Deref for QueueGuard<'_> {
+    type Target = VecDeque<Arc<Waiter>>;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*self.queue.waiters.get() }
+    }
+}
+
+impl DerefMut for QueueGuard<'_> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { &mut *self.queue.waiters.get() }
+    }
+}
+
+impl Drop for QueueGuard<'_> {
+    #[inline]
+    fn drop(&mut self) {
+        self.queue.locked.store(false, Release);
+    }
+}
+
+/// Exponential backoff for spinning on a lock.
+pub struct SpinWait {
+    counter: u32,
+}
+
+impl SpinWait {
+    #[inline]
+    pub fn new() -> Self {
+        Self { counter: 0 }
+    }
+
+    #[inline]
+    pub fn reset(&mut self) {
+        self.counter = 0;
+    }
+
+    /// Spins for a while, or returns `false` if it is time to stop spinning.
+    #[inline]
+    pub fn spin(&mut self) -> bool {
+        if self.counter >= 10 {
+            return false;
+        }
+        self.counter += 1;
+        if self.counter <= 3 {
+            for _ in 0..1 << self.counter {
+                hint::spin_loop();
+            }
+        } else {
+            thread::yield_now();
+        }
+        true
+    }
+}
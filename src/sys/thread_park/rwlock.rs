@@ -0,0 +1,261 @@
+use super::queue::{ParkResult, Queue};
+use crate::sys::ReadError;
+use crate::sys_common::init_assert::InitAssert;
+use std::marker::PhantomPinned;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering::*};
+use std::time::Instant;
+
+const PARKED_BIT: usize = 0b01;
+const WRITER_BIT: usize = 0b10;
+const ONE_READER: usize = 0b100;
+
+pub struct RwLock {
+    // The number of readers, in units of `ONE_READER`, plus the bits above.
+    state: AtomicUsize,
+    queue: Queue,
+    #[cfg(debug_assertions)]
+    initialized: InitAssert,
+    _p: PhantomPinned,
+}
+
+unsafe impl Send for RwLock {}
+unsafe impl Sync for RwLock {}
+
+impl RwLock {
+    #[inline]
+    pub const fn uninit() -> Self {
+        Self {
+            state: AtomicUsize::new(0),
+            queue: Queue::new(),
+            #[cfg(debug_assertions)]
+            initialized: InitAssert::new(),
+            _p: PhantomPinned,
+        }
+    }
+
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            state: AtomicUsize::new(0),
+            queue: Queue::new(),
+            #[cfg(debug_assertions)]
+            initialized: InitAssert::initialized(()),
+            _p: PhantomPinned,
+        }
+    }
+
+    #[inline]
+    pub fn init(self: Pin<&Self>) {
+        #[cfg(debug_assertions)]
+        self.initialized.init(|| {});
+    }
+
+    /// Resets the lock to unlocked, as the threads which held it may not exist
+    /// in the child of a `fork`.
+    #[cfg(unix)]
+    pub unsafe fn reinit_after_fork(self: Pin<&Self>) {
+        self.state.store(0, Relaxed);
+        self.queue.reset();
+    }
+
+    #[inline]
+    pub fn try_read(self: Pin<&Self>) -> Result<ReadGuard<'_>, ReadError> {
+        #[cfg(debug_assertions)]
+        {
+            self.initialized.get();
+        }
+
+        let mut state = self.state.load(Relaxed);
+        loop {
+            if state & WRITER_BIT != 0 {
+                return Err(ReadError::WouldBlock);
+            }
+            let new = state
+                .checked_add(ONE_READER)
+                .ok_or(ReadError::TooManyReaders)?;
+            match self.state.compare_exchange_weak(state, new, Acquire, Relaxed) {
+                Ok(_) => return Ok(ReadGuard { lock: self }),
+                Err(x) => state = x,
+            }
+        }
+    }
+
+    /// Returns `None` if the maximum number of readers was reached.
+    #[inline]
+    pub fn read(self: Pin<&Self>) -> Option<ReadGuard<'_>> {
+        loop {
+            match self.try_read() {
+                Ok(guard) => return Some(guard),
+                Err(ReadError::TooManyReaders) => return None,
+                Err(ReadError::WouldBlock) => {
+                    self.park(WRITER_BIT, None);
+                }
+            }
+        }
+    }
+
+    /// Returns [`ReadError::WouldBlock`] if the deadline was reached.
+    #[inline]
+    pub fn try_read_until(self: Pin<&Self>, deadline: Instant) -> Result<ReadGuard<'_>, ReadError> {
+        loop {
+            match self.try_read() {
+                Err(ReadError::WouldBlock) => {
+                    if !self.park(WRITER_BIT, Some(deadline)) {
+                        return Err(ReadError::WouldBlock);
+                    }
+                }
+                result => return result,
+            }
+        }
+    }
+
+    #[inline]
+    pub fn try_write(self: Pin<&Self>) -> Option<WriteGuard<'_>> {
+        #[cfg(debug_assertions)]
+        {
+            self.initialized.get();
+        }
+
+        let mut state = self.state.load(Relaxed);
+        loop {
+            if state & !PARKED_BIT != 0 {
+                return None;
+            }
+            match self
+                .state
+                .compare_exchange_weak(state, state | WRITER_BIT, Acquire, Relaxed)
+            {
+                Ok(_) => return Some(WriteGuard { lock: self }),
+                Err(x) => state = x,
+            }
+        }
+    }
+
+    #[inline]
+    pub fn write(self: Pin<&Self>) -> WriteGuard<'_> {
+        loop {
+            if let Some(guard) = self.try_write() {
+                return guard;
+            }
+            self.park(!PARKED_BIT, None);
+        }
+    }
+
+    #[inline]
+    pub fn try_write_until(self: Pin<&Self>, deadline: Instant) -> Option<WriteGuard<'_>> {
+        loop {
+            if let Some(guard) = self.try_write() {
+                return Some(guard);
+            }
+            if !self.park(!PARKED_BIT, Some(deadline)) {
+                return None;
+            }
+        }
+    }
+
+    /// Parks the current thread while any of the bits of `busy` are set.
+    ///
+    /// Returns `false` if the deadline was reached.
+    #[cold]
+    fn park(&self, busy: usize, deadline: Option<Instant>) -> bool {
+        let mut state = self.state.load(Relaxed);
+        loop {
+            if state & busy == 0 {
+                return true;
+            }
+            if state & PARKED_BIT != 0 {
+                break;
+            }
+            match self
+                .state
+                .compare_exchange_weak(state, state | PARKED_BIT, Relaxed, Relaxed)
+            {
+                Ok(_) => break,
+                Err(x) => state = x,
+            }
+        }
+
+        let validate = || {
+            let state = self.state.load(Relaxed);
+            state & busy != 0 && state & PARKED_BIT != 0
+        };
+        // A thread which times out leaves the parked bit set, which only costs
+        // the next unlock a trip through the queue.
+        self.queue.park(validate, || {}, deadline) != ParkResult::TimedOut
+    }
+
+    /// Wakes up every parked thread, which then compete for the lock again.
+    #[cold]
+    fn unpark_all(&self) {
+        self.queue.unpark_all();
+    }
+
+    /// Releases a read lock without a guard.
+    ///
+    /// # Safety
+    ///
+    /// The lock must be read locked, and the guard which locked it forgotten.
+    #[inline]
+    pub unsafe fn read_unlock(self: Pin<&Self>) {
+        drop(ReadGuard { lock: self });
+    }
+
+    /// Releases the write lock without a guard.
+    ///
+    /// # Safety
+    ///
+    /// The lock must be write locked, and the guard which locked it forgotten.
+    #[inline]
+    pub unsafe fn write_unlock(self: Pin<&Self>) {
+        drop(WriteGuard { lock: self });
+    }
+}
+
+pub struct ReadGuard<'a> {
+    lock: Pin<&'a RwLock>,
+}
+impl Clone for ReadGuard<'_> {
+    /// Adds a reader without waiting, as the lock is already held for reading.
+    ///
+    /// Panics if the maximum number of readers is reached.
+    #[inline]
+    fn clone(&self) -> Self {
+        self.lock
+            .state
+            .fetch_update(Relaxed, Relaxed, |state| state.checked_add(ONE_READER))
+            .expect("rwlock maximum reader count exceeded");
+        ReadGuard { lock: self.lock }
+    }
+}
+impl Drop for ReadGuard<'_> {
+    #[inline]
+    fn drop(&mut self) {
+        let state = self.lock.state.fetch_sub(ONE_READER, Release);
+        // Only writers park while there are readers, so wake them up once
+        // the last reader leaves.
+        if state == ONE_READER | PARKED_BIT
+            && self
+                .lock
+                .state
+                .compare_exchange(PARKED_BIT, 0, Relaxed, Relaxed)
+                .is_ok()
+        {
+            self.lock.unpark_all();
+        }
+    }
+}
+
+pub struct WriteGuard<'a> {
+    lock: Pin<&'a RwLock>,
+}
+impl Drop for WriteGuard<'_> {
+    #[inline]
+    fn drop(&mut self) {
+        let state = self.lock.state.swap(0, Release);
+        debug_assert_eq!(state & !PARKED_BIT, WRITER_BIT);
+        if state & PARKED_BIT != 0 {
+            self.lock.unpark_all();
+        }
+    }
+}
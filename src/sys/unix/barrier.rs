@@ -0,0 +1,62 @@
+use crate::sys::cvt_nz;
+use crate::sys_common::init_assert::InitAssert;
+use std::convert::TryInto;
+use std::marker::PhantomPinned;
+use std::pin::Pin;
+use std::ptr;
+
+// Destroyed on drop, as some platforms allocate the barrier.
+struct PthreadBarrier(libc::pthread_barrier_t);
+
+impl Drop for PthreadBarrier {
+    fn drop(&mut self) {
+        let r = unsafe { libc::pthread_barrier_destroy(&mut self.0) };
+        debug_assert_eq!(r, 0);
+    }
+}
+
+pub struct Barrier {
+    barrier: InitAssert<PthreadBarrier>,
+    num_threads: usize,
+    _p: PhantomPinned,
+}
+
+unsafe impl Send for Barrier {}
+unsafe impl Sync for Barrier {}
+
+impl Barrier {
+    #[inline]
+    pub const fn uninit(n: usize) -> Self {
+        Self {
+            barrier: InitAssert::new(),
+            num_threads: n,
+            _p: PhantomPinned,
+        }
+    }
+
+    pub fn init(self: Pin<&Self>) {
+        // A barrier of no threads releases every thread right away, like a
+        // barrier of one. More threads than fit in the count can not exist.
+        let count = self
+            .num_threads
+            .max(1)
+            .try_into()
+            .unwrap_or(libc::c_uint::MAX);
+        unsafe {
+            self.barrier.init_with(|p| {
+                let p = ptr::addr_of_mut!((*p).0);
+                cvt_nz(libc::pthread_barrier_init(p, ptr::null(), count)).unwrap();
+            })
+        }
+    }
+
+    /// Returns whether the current thread is the leader.
+    pub fn wait(self: Pin<&Self>) -> bool {
+        let p = unsafe { ptr::addr_of_mut!((*self.barrier.get()).0) };
+        match unsafe { libc::pthread_barrier_wait(p) } {
+            0 => false,
+            libc::PTHREAD_BARRIER_SERIAL_THREAD => true,
+            r => panic!("pthread_barrier_wait failed: {}", r),
+        }
+    }
+}
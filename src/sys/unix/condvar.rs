@@ -3,8 +3,15 @@ use crate::sys_common::init_assert::InitAssert;
 use std::cell::UnsafeCell;
 use std::marker::PhantomPinned;
 use std::pin::Pin;
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, Ordering::*};
 use std::time::Duration;
 
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+use super::ulock;
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+use std::sync::atomic::AtomicU32;
+
 macro_rules! assert_init {
     ($this: expr) => {
         #[cfg(any(
@@ -36,6 +43,12 @@ pub struct Condvar {
         ))
     ))]
     initialized: InitAssert,
+    // On Apple platforms, `pthread_cond_t` is only used if waiting on an
+    // address is not supported. Otherwise this is incremented by every
+    // notification and waited on, like a futex.
+    #[cfg(any(target_os = "macos", target_os = "ios"))]
+    seq: AtomicU32,
+    mutex: AtomicPtr<libc::pthread_mutex_t>,
     _p: PhantomPinned,
 }
 
@@ -58,6 +71,9 @@ impl Condvar {
                 ))
             ))]
             initialized: InitAssert::new(),
+            #[cfg(any(target_os = "macos", target_os = "ios"))]
+            seq: AtomicU32::new(0),
+            mutex: AtomicPtr::new(ptr::null_mut()),
             _p: PhantomPinned,
         }
     }
@@ -103,6 +119,13 @@ impl Condvar {
     pub fn notify_one(self: Pin<&Self>) {
         assert_init!(self);
 
+        #[cfg(any(target_os = "macos", target_os = "ios"))]
+        if ulock::supported() {
+            self.seq.fetch_add(1, Relaxed);
+            ulock::wake(&self.seq, false);
+            return;
+        }
+
         unsafe {
             let r = libc::pthread_cond_signal(self.inner.get());
             debug_assert_eq!(r, 0);
@@ -113,18 +136,39 @@ impl Condvar {
     pub fn notify_all(self: Pin<&Self>) {
         assert_init!(self);
 
+        #[cfg(any(target_os = "macos", target_os = "ios"))]
+        if ulock::supported() {
+            self.seq.fetch_add(1, Relaxed);
+            ulock::wake(&self.seq, true);
+            return;
+        }
+
         unsafe {
             let r = libc::pthread_cond_broadcast(self.inner.get());
             debug_assert_eq!(r, 0);
         }
     }
 
+    // Condition variables can only wake one thread or all of them.
+    #[inline]
+    pub fn notify_many(self: Pin<&Self>, n: usize) {
+        for _ in 0..n {
+            self.notify_one();
+        }
+    }
+
     #[inline]
     pub unsafe fn wait<'a>(
         self: Pin<&Self>,
         lock: sys::mutex::MutexGuard<'a>,
     ) -> sys::mutex::MutexGuard<'a> {
         assert_init!(self);
+        self.verify(lock.as_raw());
+
+        #[cfg(any(target_os = "macos", target_os = "ios"))]
+        if ulock::supported() {
+            return self.sleep(lock, None).1;
+        }
 
         let r = libc::pthread_cond_wait(self.inner.get(), lock.as_raw());
         debug_assert_eq!(r, 0);
@@ -144,6 +188,7 @@ impl Condvar {
         use std::mem;
 
         assert_init!(self);
+        self.verify(lock.as_raw());
 
         let mut now: libc::timespec = mem::zeroed();
         let r = libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut now);
@@ -175,12 +220,20 @@ impl Condvar {
     #[cfg(any(target_os = "macos", target_os = "ios", target_os = "android"))]
     pub unsafe fn wait_timeout<'a>(
         &self,
-        lock: sys::mutex::MutexGuard,
+        lock: sys::mutex::MutexGuard<'a>,
         mut dur: Duration,
     ) -> (bool, sys::mutex::MutexGuard<'a>) {
-        use std::ptr;
         use std::time::Instant;
 
+        self.verify(lock.as_raw());
+
+        // Timeouts of address waits are relative and measured with a
+        // monotonic clock, so none of the below is needed.
+        #[cfg(any(target_os = "macos", target_os = "ios"))]
+        if ulock::supported() {
+            return self.sleep(lock, Some(dur));
+        }
+
         // 1000 years
         let max_dur = Duration::from_secs(1000 * 365 * 86400);
 
@@ -234,6 +287,37 @@ impl Condvar {
         // to clock shifts, so do the check ourselves
         (stable_now.elapsed() < dur, lock)
     }
+
+    #[cfg(any(target_os = "macos", target_os = "ios"))]
+    unsafe fn sleep<'a>(
+        &self,
+        lock: sys::mutex::MutexGuard<'a>,
+        timeout: Option<Duration>,
+    ) -> (bool, sys::mutex::MutexGuard<'a>) {
+        let mutex = lock.mutex();
+        let seq = self.seq.load(Relaxed);
+        drop(lock);
+        // This returns `true` on spurious wakeups, which callers of `wait` and
+        // `wait_timeout` must handle anyway, and only `false` once the timeout
+        // really expired.
+        let notified = ulock::wait(&self.seq, seq, timeout);
+        (notified, mutex.lock())
+    }
+
+    // Waiting on the same condition variable with different mutexes is undefined
+    // behaviour for pthreads, so we remember the first mutex and panic if
+    // another one is ever used.
+    #[inline]
+    fn verify(&self, mutex: *mut libc::pthread_mutex_t) {
+        match self
+            .mutex
+            .compare_exchange(ptr::null_mut(), mutex, Relaxed, Relaxed)
+        {
+            Ok(_) => {}
+            Err(addr) if addr == mutex => {}
+            Err(_) => panic!("attempted to use a condition variable with two mutexes"),
+        }
+    }
 }
 
 impl Drop for Condvar {
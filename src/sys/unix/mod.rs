@@ -1,6 +1,28 @@
+// `pthread_barrier_t` is an optional part of POSIX, which macOS and others do
+// not have.
+#[cfg(any(
+    target_os = "aix",
+    target_os = "cygwin",
+    target_os = "dragonfly",
+    target_os = "hurd",
+    target_os = "nto"
+))]
+pub mod barrier;
 pub mod condvar;
 pub mod mutex;
+pub mod remutex;
 pub mod rwlock;
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+mod ulock;
+
+#[cfg(not(any(
+    target_os = "aix",
+    target_os = "cygwin",
+    target_os = "dragonfly",
+    target_os = "hurd",
+    target_os = "nto"
+)))]
+pub use crate::sys_common::barrier;
 
 pub fn cvt_nz(error: libc::c_int) -> std::io::Result<()> {
     if error == 0 {
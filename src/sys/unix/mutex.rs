@@ -1,8 +1,11 @@
 use crate::sys::cvt_nz;
 use crate::sys_common::init_assert::InitAssert;
+use crate::sys_common::timed;
 use std::marker::PhantomPinned;
 use std::mem::MaybeUninit;
 use std::pin::Pin;
+use std::thread;
+use std::time::Instant;
 
 pub struct Mutex {
     lock: InitAssert<libc::pthread_mutex_t>,
@@ -21,33 +24,47 @@ impl Mutex {
         }
     }
 
+    // pthread mutexes do not expose their waiter queue, so direct handoff is
+    // not supported.
+    #[inline]
+    pub fn handoff(self, _enabled: bool) -> Self {
+        self
+    }
+
     pub fn init(self: Pin<&Self>) {
         unsafe {
-            self.lock.init_with(|p| {
-                let mut attr = MaybeUninit::<libc::pthread_mutexattr_t>::uninit();
-
-                cvt_nz(libc::pthread_mutexattr_init(attr.as_mut_ptr())).unwrap();
-                let attr = PthreadMutexAttr(&mut attr);
-                cvt_nz(libc::pthread_mutexattr_settype(
-                    attr.0.as_mut_ptr(),
-                    libc::PTHREAD_MUTEX_NORMAL,
-                ))
-                .unwrap();
-                cvt_nz(libc::pthread_mutex_init(p, attr.0.as_ptr())).unwrap();
-            });
+            self.lock
+                .init_with(|p| Self::init_raw(p, libc::PTHREAD_MUTEX_NORMAL))
         }
     }
 
+    /// Initializes the mutex again, unlocked, without destroying it, as the
+    /// thread which held it may not exist in the child of a `fork`.
+    pub unsafe fn reinit_after_fork(self: Pin<&Self>) {
+        Self::init_raw(self.lock.get(), libc::PTHREAD_MUTEX_NORMAL);
+    }
+
+    /// Initializes a pthread mutex of the given type, such as
+    /// `PTHREAD_MUTEX_NORMAL`.
+    pub(super) unsafe fn init_raw(p: *mut libc::pthread_mutex_t, kind: libc::c_int) {
+        let mut attr = MaybeUninit::<libc::pthread_mutexattr_t>::uninit();
+
+        cvt_nz(libc::pthread_mutexattr_init(attr.as_mut_ptr())).unwrap();
+        let attr = PthreadMutexAttr(&mut attr);
+        cvt_nz(libc::pthread_mutexattr_settype(attr.0.as_mut_ptr(), kind)).unwrap();
+        cvt_nz(libc::pthread_mutex_init(p, attr.0.as_ptr())).unwrap();
+    }
+
     #[inline]
-    pub fn lock(self: Pin<&Self>) -> MutexGuard {
+    pub fn lock(self: Pin<&Self>) -> MutexGuard<'_> {
         Self::lock_inner(self.lock.get());
         MutexGuard { mutex: self }
     }
 
     #[inline]
-    pub fn try_lock(self: Pin<&Self>) -> Option<MutexGuard> {
+    pub fn try_lock(self: Pin<&Self>) -> Option<MutexGuard<'_>> {
         unsafe {
-            let result = libc::pthread_mutex_lock(self.lock.get());
+            let result = libc::pthread_mutex_trylock(self.lock.get());
             if result == 0 {
                 Some(MutexGuard { mutex: self })
             } else {
@@ -56,22 +73,83 @@ impl Mutex {
         }
     }
 
+    // `pthread_mutex_timedlock` is an optional part of POSIX, which macOS and
+    // others do not have.
+    #[cfg(any(
+        target_os = "aix",
+        target_os = "cygwin",
+        target_os = "dragonfly",
+        target_os = "haiku",
+        target_os = "hurd",
+        target_os = "nto",
+        target_os = "openbsd"
+    ))]
+    pub fn try_lock_until(self: Pin<&Self>, deadline: Instant) -> Option<MutexGuard<'_>> {
+        let timeout = timed::realtime(deadline);
+        let result = unsafe { libc::pthread_mutex_timedlock(self.lock.get(), &timeout) };
+        if result == 0 {
+            Some(MutexGuard { mutex: self })
+        } else {
+            debug_assert_eq!(result, libc::ETIMEDOUT);
+            None
+        }
+    }
+
+    #[cfg(not(any(
+        target_os = "aix",
+        target_os = "cygwin",
+        target_os = "dragonfly",
+        target_os = "haiku",
+        target_os = "hurd",
+        target_os = "nto",
+        target_os = "openbsd"
+    )))]
+    pub fn try_lock_until(self: Pin<&Self>, deadline: Instant) -> Option<MutexGuard<'_>> {
+        timed::poll_until(deadline, || self.try_lock())
+    }
+
     fn lock_inner(x: *mut libc::pthread_mutex_t) {
         unsafe {
             let result = libc::pthread_mutex_lock(x);
             debug_assert_eq!(result, 0);
         }
     }
+
+    /// Unlocks the mutex without a guard.
+    ///
+    /// # Safety
+    ///
+    /// The mutex must be locked, and the guard which locked it forgotten.
+    #[inline]
+    pub unsafe fn unlock(self: Pin<&Self>) {
+        drop(MutexGuard { mutex: self });
+    }
 }
 
 pub struct MutexGuard<'a> {
     mutex: Pin<&'a Mutex>,
 }
-impl MutexGuard<'_> {
+impl<'a> MutexGuard<'a> {
     #[inline]
     pub fn as_raw(&self) -> *mut libc::pthread_mutex_t {
         self.mutex.lock.get()
     }
+
+    /// Unlocks the mutex, lets the threads waiting for it run, and locks it
+    /// again.
+    #[inline]
+    pub fn bump(self) -> Self {
+        let mutex = self.mutex;
+        drop(self);
+        thread::yield_now();
+        mutex.lock()
+    }
+
+    #[cfg(any(target_os = "macos", target_os = "ios"))]
+    #[inline]
+    pub(super) fn mutex(&self) -> Pin<&'a Mutex> {
+        self.mutex
+    }
 }
 impl Drop for MutexGuard<'_> {
     #[inline]
@@ -0,0 +1,62 @@
+use super::mutex::Mutex;
+use crate::sys_common::init_assert::InitAssert;
+use std::marker::PhantomPinned;
+use std::pin::Pin;
+
+/// A `PTHREAD_MUTEX_RECURSIVE` mutex, which counts the recursion itself.
+pub struct ReentrantMutex {
+    lock: InitAssert<libc::pthread_mutex_t>,
+    _p: PhantomPinned,
+}
+
+unsafe impl Send for ReentrantMutex {}
+unsafe impl Sync for ReentrantMutex {}
+
+impl ReentrantMutex {
+    #[inline]
+    pub const fn uninit() -> Self {
+        Self {
+            lock: InitAssert::new(),
+            _p: PhantomPinned,
+        }
+    }
+
+    pub fn init(self: Pin<&Self>) {
+        unsafe {
+            self.lock
+                .init_with(|p| Mutex::init_raw(p, libc::PTHREAD_MUTEX_RECURSIVE))
+        }
+    }
+
+    /// Panics if the lock count would overflow.
+    #[inline]
+    pub fn lock(self: Pin<&Self>) -> ReentrantMutexGuard<'_> {
+        let r = unsafe { libc::pthread_mutex_lock(self.lock.get()) };
+        if r == libc::EAGAIN {
+            panic!("lock count overflow in reentrant mutex");
+        }
+        debug_assert_eq!(r, 0);
+        ReentrantMutexGuard { lock: self }
+    }
+
+    /// Panics if the lock count would overflow.
+    #[inline]
+    pub fn try_lock(self: Pin<&Self>) -> Option<ReentrantMutexGuard<'_>> {
+        match unsafe { libc::pthread_mutex_trylock(self.lock.get()) } {
+            0 => Some(ReentrantMutexGuard { lock: self }),
+            libc::EAGAIN => panic!("lock count overflow in reentrant mutex"),
+            _ => None,
+        }
+    }
+}
+
+pub struct ReentrantMutexGuard<'a> {
+    lock: Pin<&'a ReentrantMutex>,
+}
+impl Drop for ReentrantMutexGuard<'_> {
+    #[inline]
+    fn drop(&mut self) {
+        let r = unsafe { libc::pthread_mutex_unlock(self.lock.lock.get()) };
+        debug_assert_eq!(r, 0);
+    }
+}
@@ -1,14 +1,29 @@
 use std::cell::UnsafeCell;
 use std::marker::PhantomPinned;
 use std::pin::Pin;
+use std::ptr;
 use std::sync::atomic::{AtomicUsize, Ordering::*};
+use std::time::Instant;
 
+#[cfg(target_os = "aix")]
+use crate::sys::cvt_nz;
+use crate::sys::ReadError;
 use crate::sys_common::init_assert::InitAssert;
+use crate::sys_common::timed;
 
 pub struct RwLock {
+    #[cfg(not(target_os = "aix"))]
     lock: UnsafeCell<libc::pthread_rwlock_t>,
+    // AIX has no static initializer for its `pthread_rwlock_t`, so the lock is
+    // initialized by `init` instead.
+    #[cfg(target_os = "aix")]
+    lock: InitAssert<libc::pthread_rwlock_t>,
     write_locked: UnsafeCell<bool>,
     num_readers: AtomicUsize,
+    // The number of read guards which were cloned from another one, and share
+    // the read lock of the guard they were cloned from. The last of them to be
+    // dropped unlocks it.
+    shared_readers: AtomicUsize,
     #[cfg(debug_assertions)]
     initialized: InitAssert,
     _p: PhantomPinned,
@@ -21,23 +36,59 @@ impl RwLock {
     #[inline]
     pub const fn uninit() -> Self {
         Self {
+            #[cfg(not(target_os = "aix"))]
             lock: UnsafeCell::new(libc::PTHREAD_RWLOCK_INITIALIZER),
+            #[cfg(target_os = "aix")]
+            lock: InitAssert::new(),
             write_locked: UnsafeCell::new(false),
             num_readers: AtomicUsize::new(0),
+            shared_readers: AtomicUsize::new(0),
             #[cfg(debug_assertions)]
             initialized: InitAssert::new(),
             _p: PhantomPinned,
         }
     }
 
+    // Only the illumos backend, whose mutexes are complete when statically
+    // initialized as well, creates read-write locks initialized.
+    #[cfg(any(target_os = "illumos", target_os = "solaris"))]
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            lock: UnsafeCell::new(libc::PTHREAD_RWLOCK_INITIALIZER),
+            write_locked: UnsafeCell::new(false),
+            num_readers: AtomicUsize::new(0),
+            shared_readers: AtomicUsize::new(0),
+            #[cfg(debug_assertions)]
+            initialized: InitAssert::initialized(()),
+            _p: PhantomPinned,
+        }
+    }
+
     #[inline]
     pub fn init(self: Pin<&Self>) {
         #[cfg(debug_assertions)]
         self.initialized.init(|| {});
+        #[cfg(target_os = "aix")]
+        unsafe {
+            self.lock.init_with(|p| Self::init_raw(p))
+        }
+    }
+
+    /// Initializes the lock again, unlocked, without destroying it, as the
+    /// threads which held it may not exist in the child of a `fork`.
+    pub unsafe fn reinit_after_fork(self: Pin<&Self>) {
+        #[cfg(not(target_os = "aix"))]
+        ptr::write(self.lock.get(), libc::PTHREAD_RWLOCK_INITIALIZER);
+        #[cfg(target_os = "aix")]
+        Self::init_raw(self.lock.get());
+        *self.write_locked.get() = false;
+        self.num_readers.store(0, Relaxed);
+        self.shared_readers.store(0, Relaxed);
     }
 
     #[inline]
-    pub fn try_read(self: Pin<&Self>) -> Option<ReadGuard> {
+    pub fn try_read(self: Pin<&Self>) -> Result<ReadGuard<'_>, ReadError> {
         #[cfg(debug_assertions)]
         {
             self.initialized.get();
@@ -48,19 +99,24 @@ impl RwLock {
             if r == 0 {
                 if *self.write_locked.get() {
                     self.unlock();
-                    None
+                    Err(ReadError::WouldBlock)
                 } else {
                     self.num_readers.fetch_add(1, Relaxed);
-                    Some(ReadGuard { lock: self })
+                    Ok(ReadGuard { lock: self })
                 }
+            } else if r == libc::EAGAIN && !cfg!(target_os = "haiku") {
+                Err(ReadError::TooManyReaders)
             } else {
-                None
+                // Haiku has no limit on readers, and can report a lock which
+                // is held with `B_WOULD_BLOCK`, which is `EAGAIN` there.
+                Err(ReadError::WouldBlock)
             }
         }
     }
 
+    /// Returns `None` if the maximum number of readers was reached.
     #[inline]
-    pub fn read(self: Pin<&Self>) -> ReadGuard {
+    pub fn read(self: Pin<&Self>) -> Option<ReadGuard<'_>> {
         #[cfg(debug_assertions)]
         {
             self.initialized.get();
@@ -83,7 +139,7 @@ impl RwLock {
             // We thus check for this situation ourselves and panic when detecting that a thread
             // got the write lock more than once, or got a read and a write lock.
             if r == libc::EAGAIN {
-                panic!("rwlock maximum reader count exceeded");
+                None
             } else if r == libc::EDEADLK || (r == 0 && *self.write_locked.get()) {
                 // Above, we make sure to only access `write_locked` when `r == 0` to avoid
                 // data races.
@@ -97,13 +153,13 @@ impl RwLock {
                 // return EAGAIN or EDEADLK or 0. We rely on that.
                 debug_assert_eq!(r, 0);
                 self.num_readers.fetch_add(1, Relaxed);
-                ReadGuard { lock: self }
+                Some(ReadGuard { lock: self })
             }
         }
     }
 
     #[inline]
-    pub fn try_write(self: Pin<&Self>) -> Option<WriteGuard> {
+    pub fn try_write(self: Pin<&Self>) -> Option<WriteGuard<'_>> {
         #[cfg(debug_assertions)]
         {
             self.initialized.get();
@@ -127,7 +183,7 @@ impl RwLock {
     }
 
     #[inline]
-    pub fn write(self: Pin<&Self>) -> WriteGuard {
+    pub fn write(self: Pin<&Self>) -> WriteGuard<'_> {
         #[cfg(debug_assertions)]
         {
             self.initialized.get();
@@ -159,23 +215,141 @@ impl RwLock {
         }
     }
 
+    /// Returns [`ReadError::WouldBlock`] if the deadline was reached.
+    #[cfg(target_os = "aix")]
+    pub fn try_read_until(self: Pin<&Self>, deadline: Instant) -> Result<ReadGuard<'_>, ReadError> {
+        #[cfg(debug_assertions)]
+        {
+            self.initialized.get();
+        }
+
+        unsafe {
+            let timeout = timed::realtime(deadline);
+            let r = libc::pthread_rwlock_timedrdlock(self.lock.get(), &timeout);
+            // See `read` for why `write_locked` is checked.
+            if r == libc::ETIMEDOUT {
+                Err(ReadError::WouldBlock)
+            } else if r == libc::EAGAIN {
+                Err(ReadError::TooManyReaders)
+            } else if r == libc::EDEADLK || (r == 0 && *self.write_locked.get()) {
+                if r == 0 {
+                    self.unlock();
+                }
+                panic!("rwlock read lock would result in deadlock");
+            } else {
+                debug_assert_eq!(r, 0);
+                self.num_readers.fetch_add(1, Relaxed);
+                Ok(ReadGuard { lock: self })
+            }
+        }
+    }
+
+    // `pthread_rwlock_timedrdlock` and `pthread_rwlock_timedwrlock` are an
+    // optional part of POSIX, which macOS and others do not have.
+    #[cfg(not(target_os = "aix"))]
+    pub fn try_read_until(self: Pin<&Self>, deadline: Instant) -> Result<ReadGuard<'_>, ReadError> {
+        timed::poll_until(deadline, || match self.try_read() {
+            Err(ReadError::WouldBlock) => None,
+            result => Some(result),
+        })
+        .unwrap_or(Err(ReadError::WouldBlock))
+    }
+
+    #[cfg(target_os = "aix")]
+    pub fn try_write_until(self: Pin<&Self>, deadline: Instant) -> Option<WriteGuard<'_>> {
+        #[cfg(debug_assertions)]
+        {
+            self.initialized.get();
+        }
+
+        unsafe {
+            let timeout = timed::realtime(deadline);
+            let r = libc::pthread_rwlock_timedwrlock(self.lock.get(), &timeout);
+            // See `write` for why `write_locked` and `num_readers` are checked.
+            if r == libc::ETIMEDOUT {
+                return None;
+            }
+            if r == libc::EDEADLK
+                || (r == 0 && *self.write_locked.get())
+                || self.num_readers.load(Relaxed) != 0
+            {
+                if r == 0 {
+                    self.unlock();
+                }
+                panic!("rwlock write lock would result in deadlock");
+            }
+            debug_assert_eq!(r, 0);
+            *self.write_locked.get() = true;
+            Some(WriteGuard { lock: self })
+        }
+    }
+
+    #[cfg(not(target_os = "aix"))]
+    pub fn try_write_until(self: Pin<&Self>, deadline: Instant) -> Option<WriteGuard<'_>> {
+        timed::poll_until(deadline, || self.try_write())
+    }
+
+    #[cfg(target_os = "aix")]
+    unsafe fn init_raw(p: *mut libc::pthread_rwlock_t) {
+        cvt_nz(libc::pthread_rwlock_init(p, ptr::null())).unwrap();
+    }
+
     #[inline]
     unsafe fn unlock(self: Pin<&Self>) {
         let result = libc::pthread_rwlock_unlock(self.lock.get());
         debug_assert_eq!(result, 0);
     }
+
+    /// Releases a read lock without a guard.
+    ///
+    /// # Safety
+    ///
+    /// The lock must be read locked, and the guard which locked it forgotten.
+    #[inline]
+    pub unsafe fn read_unlock(self: Pin<&Self>) {
+        drop(ReadGuard { lock: self });
+    }
+
+    /// Releases the write lock without a guard.
+    ///
+    /// # Safety
+    ///
+    /// The lock must be write locked, and the guard which locked it forgotten.
+    #[inline]
+    pub unsafe fn write_unlock(self: Pin<&Self>) {
+        drop(WriteGuard { lock: self });
+    }
 }
 
 pub struct ReadGuard<'a> {
     lock: Pin<&'a RwLock>,
 }
+impl Clone for ReadGuard<'_> {
+    /// Shares the read lock of this guard, as POSIX allows locking it again to
+    /// block if a writer is waiting.
+    #[inline]
+    fn clone(&self) -> Self {
+        self.lock.num_readers.fetch_add(1, Relaxed);
+        self.lock.shared_readers.fetch_add(1, Relaxed);
+        ReadGuard { lock: self.lock }
+    }
+}
 impl Drop for ReadGuard<'_> {
     #[inline]
     fn drop(&mut self) {
         unsafe {
             debug_assert!(!*self.lock.write_locked.get());
             self.lock.num_readers.fetch_sub(1, Relaxed);
-            self.lock.unlock();
+            // Any guard can give up a shared read lock, as long as one is left
+            // for every other guard.
+            if self
+                .lock
+                .shared_readers
+                .fetch_update(Relaxed, Relaxed, |n| n.checked_sub(1))
+                .is_err()
+            {
+                self.lock.unlock();
+            }
         }
     }
 }
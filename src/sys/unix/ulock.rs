@@ -0,0 +1,178 @@
+//! Waiting on an address on Apple platforms.
+//!
+//! Darwin has no public futex, but the `os_sync_wait_on_address` family
+//! (macOS 14.4 and later) and the private `__ulock_wait` and `__ulock_wake`
+//! (macOS 10.12 and later), which `libc++` and `libdispatch` use, do the same.
+//! Neither is guaranteed to exist, so they are looked up at runtime, preferring
+//! the public API, and [`supported`] tells whether either was found.
+
+use std::ffi::c_void;
+use std::sync::atomic::AtomicU32;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+const UL_COMPARE_AND_WAIT: u32 = 1;
+const ULF_WAKE_ALL: u32 = 0x100;
+const ULF_NO_ERRNO: u32 = 0x0100_0000;
+
+const OS_CLOCK_MACH_ABSOLUTE_TIME: u32 = 32;
+const OS_SYNC_WAIT_ON_ADDRESS_NONE: u32 = 0;
+const OS_SYNC_WAKE_BY_ADDRESS_NONE: u32 = 0;
+
+type OsSyncWait = unsafe extern "C" fn(*mut c_void, u64, usize, u32) -> libc::c_int;
+type OsSyncWaitWithTimeout =
+    unsafe extern "C" fn(*mut c_void, u64, usize, u32, u32, u64) -> libc::c_int;
+type OsSyncWake = unsafe extern "C" fn(*mut c_void, usize, u32) -> libc::c_int;
+type UlockWait = unsafe extern "C" fn(u32, *mut c_void, u64, u32) -> libc::c_int;
+type UlockWake = unsafe extern "C" fn(u32, *mut c_void, u64) -> libc::c_int;
+
+enum Api {
+    OsSync {
+        wait: OsSyncWait,
+        wait_with_timeout: OsSyncWaitWithTimeout,
+        wake_any: OsSyncWake,
+        wake_all: OsSyncWake,
+    },
+    Ulock {
+        wait: UlockWait,
+        wake: UlockWake,
+    },
+}
+
+static API: OnceLock<Option<Api>> = OnceLock::new();
+
+macro_rules! lookup {
+    ($name:literal as $ty:ty) => {{
+        let addr = unsafe { libc::dlsym(libc::RTLD_DEFAULT, concat!($name, "\0").as_ptr().cast()) };
+        if addr.is_null() {
+            None
+        } else {
+            Some(unsafe { std::mem::transmute::<*mut c_void, $ty>(addr) })
+        }
+    }};
+}
+
+fn lookup() -> Option<Api> {
+    let os_sync = || {
+        Some(Api::OsSync {
+            wait: lookup!("os_sync_wait_on_address" as OsSyncWait)?,
+            wait_with_timeout: lookup!(
+                "os_sync_wait_on_address_with_timeout" as OsSyncWaitWithTimeout
+            )?,
+            wake_any: lookup!("os_sync_wake_by_address_any" as OsSyncWake)?,
+            wake_all: lookup!("os_sync_wake_by_address_all" as OsSyncWake)?,
+        })
+    };
+    let ulock = || {
+        Some(Api::Ulock {
+            wait: lookup!("__ulock_wait" as UlockWait)?,
+            wake: lookup!("__ulock_wake" as UlockWake)?,
+        })
+    };
+    os_sync().or_else(ulock)
+}
+
+#[inline]
+fn api() -> Option<&'static Api> {
+    API.get_or_init(lookup).as_ref()
+}
+
+/// Whether [`wait`] and [`wake`] can be used.
+///
+/// The answer never changes during the lifetime of the process.
+#[inline]
+pub fn supported() -> bool {
+    api().is_some()
+}
+
+/// Blocks the current thread while `futex` holds `expected`, for at most
+/// `timeout`.
+///
+/// Returns `false` only if the timeout expired. Like with a futex, the thread
+/// may also wake up spuriously.
+///
+/// # Panics
+///
+/// Panics if the API is not [`supported`].
+pub fn wait(futex: &AtomicU32, expected: u32, timeout: Option<Duration>) -> bool {
+    let addr = futex.as_ptr().cast();
+    let r = match api().expect("waiting on an address is not supported") {
+        Api::OsSync {
+            wait,
+            wait_with_timeout,
+            ..
+        } => unsafe {
+            // A timeout of zero is rejected, and one of `u64::MAX` nanoseconds
+            // is over 500 years, so both are rounded into range.
+            let r = match timeout.map(|dur| dur.as_nanos().clamp(1, u64::MAX.into())) {
+                Some(nanos) => wait_with_timeout(
+                    addr,
+                    expected.into(),
+                    4,
+                    OS_SYNC_WAIT_ON_ADDRESS_NONE,
+                    OS_CLOCK_MACH_ABSOLUTE_TIME,
+                    nanos as u64,
+                ),
+                None => wait(addr, expected.into(), 4, OS_SYNC_WAIT_ON_ADDRESS_NONE),
+            };
+            if r >= 0 {
+                0
+            } else {
+                std::io::Error::last_os_error().raw_os_error().unwrap_or(0)
+            }
+        },
+        Api::Ulock { wait, .. } => unsafe {
+            // The timeout is in microseconds, and zero means none at all.
+            // Longer timeouts are cut short, which looks like a spurious
+            // wakeup to the caller.
+            let (micros, truncated) = match timeout {
+                Some(dur) => {
+                    let micros = dur.as_micros().max(1);
+                    (micros.min(u32::MAX.into()) as u32, micros > u32::MAX.into())
+                }
+                None => (0, false),
+            };
+            let r = wait(
+                UL_COMPARE_AND_WAIT | ULF_NO_ERRNO,
+                addr,
+                expected.into(),
+                micros,
+            );
+            if r >= 0 || (truncated && r == -libc::ETIMEDOUT) {
+                0
+            } else {
+                -r
+            }
+        },
+    };
+    // Any other error, such as `EINTR`, is a spurious wakeup.
+    r != libc::ETIMEDOUT
+}
+
+/// Wakes up one or all of the threads blocked in [`wait`] on `futex`.
+///
+/// # Panics
+///
+/// Panics if the API is not [`supported`].
+pub fn wake(futex: &AtomicU32, all: bool) {
+    let addr = futex.as_ptr().cast();
+    match api().expect("waiting on an address is not supported") {
+        Api::OsSync {
+            wake_any, wake_all, ..
+        } => unsafe {
+            // Fails with `ENOENT` if nobody was waiting.
+            let wake = if all { wake_all } else { wake_any };
+            wake(addr, 4, OS_SYNC_WAKE_BY_ADDRESS_NONE);
+        },
+        Api::Ulock { wake, .. } => unsafe {
+            let flags = if all { ULF_WAKE_ALL } else { 0 };
+            loop {
+                let r = wake(UL_COMPARE_AND_WAIT | ULF_NO_ERRNO | flags, addr, 0);
+                // Fails with `ENOENT` if nobody was waiting.
+                if r != -libc::EINTR {
+                    break;
+                }
+            }
+        },
+    }
+}
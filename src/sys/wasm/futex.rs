@@ -0,0 +1,34 @@
+use std::arch::wasm32;
+use std::convert::TryInto;
+use std::sync::atomic::AtomicU32;
+use std::time::Duration;
+
+/// Blocks the current thread while `futex` is `expected`, until it is woken up
+/// by [`wake`] or the timeout expires.
+///
+/// Returns `false` if the timeout expired. Like any futex wait, this may also
+/// return spuriously.
+///
+/// This traps if the current thread is not allowed to block, such as the main
+/// thread of a browser.
+pub fn wait(futex: &AtomicU32, expected: u32, timeout: Option<Duration>) -> bool {
+    // A negative timeout means no timeout, and a timeout which can not be
+    // represented is as good as no timeout.
+    let timeout = timeout
+        .and_then(|dur| dur.as_nanos().try_into().ok())
+        .unwrap_or(-1);
+    // 0 means woken up, 1 means the futex changed before the thread went to
+    // sleep, and 2 means the timeout expired.
+    let r =
+        unsafe { wasm32::memory_atomic_wait32(futex.as_ptr().cast(), expected as i32, timeout) };
+    r != 2
+}
+
+/// Wakes up at most `count` threads blocked in [`wait`] on `futex`.
+///
+/// Returns whether any thread was woken up.
+#[inline]
+pub fn wake(futex: &AtomicU32, count: i32) -> bool {
+    let woken = unsafe { wasm32::memory_atomic_notify(futex.as_ptr().cast(), count as u32) };
+    woken > 0
+}
@@ -0,0 +1,22 @@
+//! A backend built on the atomic wait and notify instructions of WebAssembly.
+//!
+//! With the `atomics` target feature, `memory.atomic.wait32` and
+//! `memory.atomic.notify` are the wait and wake operations of a Linux futex
+//! under another name, so the primitives are the ones of the Linux backend,
+//! built on top of them instead. Like there, an uninitialized `Mutex`,
+//! `Condvar` or `RwLock` is already usable.
+//!
+//! Browsers do not allow the main thread to block, and `memory.atomic.wait32`
+//! traps there instead. Locks only wait when they are contended, so the main
+//! thread may still use them as long as it never has to wait for another
+//! thread, but waiting on a condition variable always traps.
+
+#[path = "../linux/condvar.rs"]
+pub mod condvar;
+#[path = "../linux/mutex.rs"]
+pub mod mutex;
+#[path = "../linux/rwlock.rs"]
+pub mod rwlock;
+
+use super::futex;
+pub use crate::sys_common::{barrier, remutex};
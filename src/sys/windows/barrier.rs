@@ -0,0 +1,58 @@
+use crate::sys_common::init_assert::InitAssert;
+use std::convert::TryInto;
+use std::marker::PhantomPinned;
+use std::mem;
+use std::pin::Pin;
+use windows_sys::Win32::System::Threading::{
+    DeleteSynchronizationBarrier, EnterSynchronizationBarrier, InitializeSynchronizationBarrier,
+    SYNCHRONIZATION_BARRIER,
+};
+
+// Deleted on drop, unlike the other primitives of this backend.
+struct SyncBarrier(SYNCHRONIZATION_BARRIER);
+
+impl Drop for SyncBarrier {
+    fn drop(&mut self) {
+        unsafe { DeleteSynchronizationBarrier(&mut self.0) };
+    }
+}
+
+pub struct Barrier {
+    barrier: InitAssert<SyncBarrier>,
+    num_threads: usize,
+    _p: PhantomPinned,
+}
+
+unsafe impl Send for Barrier {}
+unsafe impl Sync for Barrier {}
+
+impl Barrier {
+    #[inline]
+    pub const fn uninit(n: usize) -> Self {
+        Self {
+            barrier: InitAssert::new(),
+            num_threads: n,
+            _p: PhantomPinned,
+        }
+    }
+
+    pub fn init(self: Pin<&Self>) {
+        // A barrier of no threads releases every thread right away, like a
+        // barrier of one. More threads than fit in the count can not exist.
+        let count = self.num_threads.max(1).try_into().unwrap_or(i32::MAX);
+        unsafe {
+            self.barrier.init_with(|p| {
+                p.write(SyncBarrier(mem::zeroed()));
+                // -1 is the default spin count.
+                let r = InitializeSynchronizationBarrier(&mut (*p).0, count, -1);
+                assert!(r != 0, "failed to initialize a synchronization barrier");
+            })
+        }
+    }
+
+    /// Returns whether the current thread is the leader.
+    pub fn wait(self: Pin<&Self>) -> bool {
+        let barrier = self.barrier.get();
+        unsafe { EnterSynchronizationBarrier(&mut (*barrier).0, 0) != 0 }
+    }
+}
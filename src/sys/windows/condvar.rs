@@ -0,0 +1,150 @@
+use crate::sys;
+use crate::sys_common::init_assert::InitAssert;
+use std::cell::UnsafeCell;
+use std::convert::TryFrom;
+use std::marker::PhantomPinned;
+use std::pin::Pin;
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, Ordering::*};
+use std::time::Duration;
+use windows_sys::Win32::Foundation::{GetLastError, ERROR_TIMEOUT};
+use windows_sys::Win32::System::Threading::{
+    SleepConditionVariableSRW, WakeAllConditionVariable, WakeConditionVariable, CONDITION_VARIABLE,
+    INFINITE, SRWLOCK,
+};
+
+pub struct Condvar {
+    inner: UnsafeCell<CONDITION_VARIABLE>,
+    #[cfg(debug_assertions)]
+    initialized: InitAssert,
+    mutex: AtomicPtr<SRWLOCK>,
+    _p: PhantomPinned,
+}
+
+unsafe impl Send for Condvar {}
+unsafe impl Sync for Condvar {}
+
+impl Condvar {
+    #[inline]
+    pub const fn uninit() -> Self {
+        Self {
+            inner: UnsafeCell::new(CONDITION_VARIABLE {
+                Ptr: ptr::null_mut(),
+            }),
+            #[cfg(debug_assertions)]
+            initialized: InitAssert::new(),
+            mutex: AtomicPtr::new(ptr::null_mut()),
+            _p: PhantomPinned,
+        }
+    }
+
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            inner: UnsafeCell::new(CONDITION_VARIABLE {
+                Ptr: ptr::null_mut(),
+            }),
+            #[cfg(debug_assertions)]
+            initialized: InitAssert::initialized(()),
+            mutex: AtomicPtr::new(ptr::null_mut()),
+            _p: PhantomPinned,
+        }
+    }
+
+    #[inline]
+    pub fn init(self: Pin<&Self>) {
+        #[cfg(debug_assertions)]
+        self.initialized.init(|| {});
+    }
+
+    #[inline]
+    pub fn notify_one(self: Pin<&Self>) {
+        #[cfg(debug_assertions)]
+        {
+            self.initialized.get();
+        }
+
+        unsafe { WakeConditionVariable(self.inner.get()) }
+    }
+
+    #[inline]
+    pub fn notify_all(self: Pin<&Self>) {
+        #[cfg(debug_assertions)]
+        {
+            self.initialized.get();
+        }
+
+        unsafe { WakeAllConditionVariable(self.inner.get()) }
+    }
+
+    // Condition variables can only wake one thread or all of them.
+    #[inline]
+    pub fn notify_many(self: Pin<&Self>, n: usize) {
+        for _ in 0..n {
+            self.notify_one();
+        }
+    }
+
+    #[inline]
+    pub unsafe fn wait<'a>(
+        self: Pin<&Self>,
+        lock: sys::mutex::MutexGuard<'a>,
+    ) -> sys::mutex::MutexGuard<'a> {
+        self.sleep(lock, INFINITE).1
+    }
+
+    #[inline]
+    pub unsafe fn wait_timeout<'a>(
+        &self,
+        lock: sys::mutex::MutexGuard<'a>,
+        dur: Duration,
+    ) -> (bool, sys::mutex::MutexGuard<'a>) {
+        self.sleep(lock, dur_to_ms(dur))
+    }
+
+    unsafe fn sleep<'a>(
+        &self,
+        lock: sys::mutex::MutexGuard<'a>,
+        ms: u32,
+    ) -> (bool, sys::mutex::MutexGuard<'a>) {
+        #[cfg(debug_assertions)]
+        {
+            self.initialized.get();
+        }
+        self.verify(lock.as_raw());
+
+        let r = SleepConditionVariableSRW(self.inner.get(), lock.as_raw(), ms, 0);
+        if r == 0 {
+            debug_assert_eq!(GetLastError(), ERROR_TIMEOUT);
+            (false, lock)
+        } else {
+            (true, lock)
+        }
+    }
+
+    // Waiting on the same condition variable with different mutexes is not
+    // supported by the other backends, so we remember the first mutex and
+    // panic if another one is ever used.
+    #[inline]
+    fn verify(&self, mutex: *mut SRWLOCK) {
+        match self
+            .mutex
+            .compare_exchange(ptr::null_mut(), mutex, Relaxed, Relaxed)
+        {
+            Ok(_) => {}
+            Err(addr) if addr == mutex => {}
+            Err(_) => panic!("attempted to use a condition variable with two mutexes"),
+        }
+    }
+}
+
+// Rounds up to whole milliseconds, so that a wait never times out early. A
+// timeout which can not be represented is as good as no timeout.
+fn dur_to_ms(dur: Duration) -> u32 {
+    dur.as_secs()
+        .checked_mul(1000)
+        .and_then(|ms| ms.checked_add(u64::from(dur.subsec_nanos().div_ceil(1_000_000))))
+        .and_then(|ms| u32::try_from(ms).ok())
+        .filter(|&ms| ms != INFINITE)
+        .unwrap_or(INFINITE)
+}
@@ -0,0 +1,13 @@
+//! A backend built on the slim reader/writer locks and condition variables
+//! of Windows.
+//!
+//! `SRWLOCK` and `CONDITION_VARIABLE` are pointer-sized, need neither
+//! initialization nor destruction, and are statically initialized to zero,
+//! so the primitives of this crate do not allocate on Windows either.
+
+pub mod barrier;
+pub mod condvar;
+pub mod mutex;
+pub mod rwlock;
+
+pub use crate::sys_common::remutex;
@@ -0,0 +1,127 @@
+use crate::sys_common::init_assert::InitAssert;
+use crate::sys_common::timed;
+use std::cell::UnsafeCell;
+use std::marker::PhantomPinned;
+use std::pin::Pin;
+use std::ptr;
+use std::thread;
+use std::time::Instant;
+use windows_sys::Win32::System::Threading::{
+    AcquireSRWLockExclusive, ReleaseSRWLockExclusive, TryAcquireSRWLockExclusive, SRWLOCK,
+};
+
+pub struct Mutex {
+    lock: UnsafeCell<SRWLOCK>,
+    #[cfg(debug_assertions)]
+    initialized: InitAssert,
+    _p: PhantomPinned,
+}
+
+unsafe impl Send for Mutex {}
+unsafe impl Sync for Mutex {}
+
+impl Mutex {
+    #[inline]
+    pub const fn uninit() -> Self {
+        Self {
+            lock: UnsafeCell::new(SRWLOCK {
+                Ptr: ptr::null_mut(),
+            }),
+            #[cfg(debug_assertions)]
+            initialized: InitAssert::new(),
+            _p: PhantomPinned,
+        }
+    }
+
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            lock: UnsafeCell::new(SRWLOCK {
+                Ptr: ptr::null_mut(),
+            }),
+            #[cfg(debug_assertions)]
+            initialized: InitAssert::initialized(()),
+            _p: PhantomPinned,
+        }
+    }
+
+    // SRW locks do not expose their waiter queue, so direct handoff is not
+    // supported.
+    #[inline]
+    pub fn handoff(self, _enabled: bool) -> Self {
+        self
+    }
+
+    #[inline]
+    pub fn init(self: Pin<&Self>) {
+        #[cfg(debug_assertions)]
+        self.initialized.init(|| {});
+    }
+
+    #[inline]
+    pub fn lock(self: Pin<&Self>) -> MutexGuard<'_> {
+        #[cfg(debug_assertions)]
+        {
+            self.initialized.get();
+        }
+
+        unsafe { AcquireSRWLockExclusive(self.lock.get()) }
+        MutexGuard { mutex: self }
+    }
+
+    #[inline]
+    pub fn try_lock(self: Pin<&Self>) -> Option<MutexGuard<'_>> {
+        #[cfg(debug_assertions)]
+        {
+            self.initialized.get();
+        }
+
+        if unsafe { TryAcquireSRWLockExclusive(self.lock.get()) } != 0 {
+            Some(MutexGuard { mutex: self })
+        } else {
+            None
+        }
+    }
+
+    // SRW locks can not be acquired with a timeout.
+    #[inline]
+    pub fn try_lock_until(self: Pin<&Self>, deadline: Instant) -> Option<MutexGuard<'_>> {
+        timed::poll_until(deadline, || self.try_lock())
+    }
+
+    /// Unlocks the mutex without a guard.
+    ///
+    /// # Safety
+    ///
+    /// The mutex must be locked, and the guard which locked it forgotten.
+    #[inline]
+    pub unsafe fn unlock(self: Pin<&Self>) {
+        drop(MutexGuard { mutex: self });
+    }
+}
+
+pub struct MutexGuard<'a> {
+    mutex: Pin<&'a Mutex>,
+}
+impl<'a> MutexGuard<'a> {
+    #[inline]
+    pub fn as_raw(&self) -> *mut SRWLOCK {
+        self.mutex.lock.get()
+    }
+
+    /// Unlocks the mutex, lets the threads waiting for it run, and locks it
+    /// again.
+    #[inline]
+    pub fn bump(self) -> Self {
+        let mutex = self.mutex;
+        drop(self);
+        thread::yield_now();
+        mutex.lock()
+    }
+}
+impl Drop for MutexGuard<'_> {
+    #[inline]
+    fn drop(&mut self) {
+        unsafe { ReleaseSRWLockExclusive(self.as_raw()) }
+    }
+}
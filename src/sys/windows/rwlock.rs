@@ -0,0 +1,184 @@
+use crate::sys::ReadError;
+use crate::sys_common::init_assert::InitAssert;
+use crate::sys_common::timed;
+use std::cell::UnsafeCell;
+use std::marker::PhantomPinned;
+use std::pin::Pin;
+use std::ptr;
+use std::sync::atomic::{AtomicUsize, Ordering::*};
+use std::time::Instant;
+use windows_sys::Win32::System::Threading::{
+    AcquireSRWLockExclusive, AcquireSRWLockShared, ReleaseSRWLockExclusive, ReleaseSRWLockShared,
+    TryAcquireSRWLockExclusive, TryAcquireSRWLockShared, SRWLOCK,
+};
+
+pub struct RwLock {
+    lock: UnsafeCell<SRWLOCK>,
+    // The number of read guards which were cloned from another one, and share
+    // the read lock of the guard they were cloned from. The last of them to be
+    // dropped unlocks it.
+    shared_readers: AtomicUsize,
+    #[cfg(debug_assertions)]
+    initialized: InitAssert,
+    _p: PhantomPinned,
+}
+
+unsafe impl Send for RwLock {}
+unsafe impl Sync for RwLock {}
+
+impl RwLock {
+    #[inline]
+    pub const fn uninit() -> Self {
+        Self {
+            lock: UnsafeCell::new(SRWLOCK {
+                Ptr: ptr::null_mut(),
+            }),
+            shared_readers: AtomicUsize::new(0),
+            #[cfg(debug_assertions)]
+            initialized: InitAssert::new(),
+            _p: PhantomPinned,
+        }
+    }
+
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            lock: UnsafeCell::new(SRWLOCK {
+                Ptr: ptr::null_mut(),
+            }),
+            shared_readers: AtomicUsize::new(0),
+            #[cfg(debug_assertions)]
+            initialized: InitAssert::initialized(()),
+            _p: PhantomPinned,
+        }
+    }
+
+    #[inline]
+    pub fn init(self: Pin<&Self>) {
+        #[cfg(debug_assertions)]
+        self.initialized.init(|| {});
+    }
+
+    /// SRW locks do not limit the number of readers, so this never returns
+    /// [`ReadError::TooManyReaders`].
+    #[inline]
+    pub fn try_read(self: Pin<&Self>) -> Result<ReadGuard<'_>, ReadError> {
+        #[cfg(debug_assertions)]
+        {
+            self.initialized.get();
+        }
+
+        if unsafe { TryAcquireSRWLockShared(self.lock.get()) } != 0 {
+            Ok(ReadGuard { lock: self })
+        } else {
+            Err(ReadError::WouldBlock)
+        }
+    }
+
+    /// SRW locks do not limit the number of readers, so this never returns
+    /// `None`.
+    #[inline]
+    pub fn read(self: Pin<&Self>) -> Option<ReadGuard<'_>> {
+        #[cfg(debug_assertions)]
+        {
+            self.initialized.get();
+        }
+
+        unsafe { AcquireSRWLockShared(self.lock.get()) }
+        Some(ReadGuard { lock: self })
+    }
+
+    #[inline]
+    pub fn try_write(self: Pin<&Self>) -> Option<WriteGuard<'_>> {
+        #[cfg(debug_assertions)]
+        {
+            self.initialized.get();
+        }
+
+        if unsafe { TryAcquireSRWLockExclusive(self.lock.get()) } != 0 {
+            Some(WriteGuard { lock: self })
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    pub fn write(self: Pin<&Self>) -> WriteGuard<'_> {
+        #[cfg(debug_assertions)]
+        {
+            self.initialized.get();
+        }
+
+        unsafe { AcquireSRWLockExclusive(self.lock.get()) }
+        WriteGuard { lock: self }
+    }
+
+    // SRW locks can not be acquired with a timeout.
+    #[inline]
+    pub fn try_read_until(self: Pin<&Self>, deadline: Instant) -> Result<ReadGuard<'_>, ReadError> {
+        timed::poll_until(deadline, || self.try_read().ok()).ok_or(ReadError::WouldBlock)
+    }
+
+    #[inline]
+    pub fn try_write_until(self: Pin<&Self>, deadline: Instant) -> Option<WriteGuard<'_>> {
+        timed::poll_until(deadline, || self.try_write())
+    }
+
+    /// Releases a read lock without a guard.
+    ///
+    /// # Safety
+    ///
+    /// The lock must be read locked, and the guard which locked it forgotten.
+    #[inline]
+    pub unsafe fn read_unlock(self: Pin<&Self>) {
+        drop(ReadGuard { lock: self });
+    }
+
+    /// Releases the write lock without a guard.
+    ///
+    /// # Safety
+    ///
+    /// The lock must be write locked, and the guard which locked it forgotten.
+    #[inline]
+    pub unsafe fn write_unlock(self: Pin<&Self>) {
+        drop(WriteGuard { lock: self });
+    }
+}
+
+pub struct ReadGuard<'a> {
+    lock: Pin<&'a RwLock>,
+}
+impl Clone for ReadGuard<'_> {
+    /// Shares the read lock of this guard, as SRW locks must not be acquired
+    /// recursively, not even for reading.
+    #[inline]
+    fn clone(&self) -> Self {
+        self.lock.shared_readers.fetch_add(1, Relaxed);
+        ReadGuard { lock: self.lock }
+    }
+}
+impl Drop for ReadGuard<'_> {
+    #[inline]
+    fn drop(&mut self) {
+        // Any guard can give up a shared read lock, as long as one is left for
+        // every other guard.
+        if self
+            .lock
+            .shared_readers
+            .fetch_update(Relaxed, Relaxed, |n| n.checked_sub(1))
+            .is_err()
+        {
+            unsafe { ReleaseSRWLockShared(self.lock.lock.get()) }
+        }
+    }
+}
+
+pub struct WriteGuard<'a> {
+    lock: Pin<&'a RwLock>,
+}
+impl Drop for WriteGuard<'_> {
+    #[inline]
+    fn drop(&mut self) {
+        unsafe { ReleaseSRWLockExclusive(self.lock.lock.get()) }
+    }
+}
@@ -0,0 +1,33 @@
+//! Exponential backoff for spinning.
+//!
+//! Each failed attempt to take a contended lock doubles the number of spins
+//! before the next one, up to a limit, so that the threads waiting on a lock
+//! do not all retry at once as soon as it is released. Only `core` is used,
+//! so that it works on the `spin` backend.
+
+use core::hint;
+
+// Past `1 << LIMIT` spins, waiting longer before retrying only adds latency.
+const LIMIT: u32 = 6;
+
+pub struct Backoff {
+    step: u32,
+}
+
+impl Backoff {
+    #[inline]
+    pub const fn new() -> Self {
+        Self { step: 0 }
+    }
+
+    /// Spins for the current number of rounds, and doubles it.
+    #[inline]
+    pub fn spin(&mut self) {
+        for _ in 0..1u32 << self.step {
+            hint::spin_loop();
+        }
+        if self.step < LIMIT {
+            self.step += 1;
+        }
+    }
+}
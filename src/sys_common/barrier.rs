@@ -0,0 +1,79 @@
+//! A barrier for the backends without a native one, built on the mutex and
+//! condition variable of the backend.
+
+#![allow(dead_code)]
+
+use crate::sys::{condvar, mutex};
+use std::cell::UnsafeCell;
+use std::marker::PhantomPinned;
+use std::pin::Pin;
+
+struct State {
+    count: usize,
+    generation_id: usize,
+}
+
+pub struct Barrier {
+    lock: mutex::Mutex,
+    cvar: condvar::Condvar,
+    state: UnsafeCell<State>,
+    num_threads: usize,
+    _p: PhantomPinned,
+}
+
+unsafe impl Send for Barrier {}
+unsafe impl Sync for Barrier {}
+
+impl Barrier {
+    #[inline]
+    pub const fn uninit(n: usize) -> Self {
+        Self {
+            lock: mutex::Mutex::uninit(),
+            cvar: condvar::Condvar::uninit(),
+            state: UnsafeCell::new(State {
+                count: 0,
+                generation_id: 0,
+            }),
+            num_threads: n,
+            _p: PhantomPinned,
+        }
+    }
+
+    pub fn init(self: Pin<&Self>) {
+        self.lock().init();
+        self.cvar().init();
+    }
+
+    /// Returns whether the current thread is the leader.
+    pub fn wait(self: Pin<&Self>) -> bool {
+        let mut lock = self.lock().lock();
+        // Safety: the state is only accessed with the lock held.
+        let state = unsafe { &mut *self.state.get() };
+        let local_gen = state.generation_id;
+        state.count += 1;
+        if state.count < self.num_threads {
+            // We need a while loop to guard against spurious wakeups.
+            // https://en.wikipedia.org/wiki/Spurious_wakeup
+            while local_gen == unsafe { (*self.state.get()).generation_id } {
+                // Safety: the condvar is only ever used with `self.lock`.
+                lock = unsafe { self.cvar().wait(lock) };
+            }
+            false
+        } else {
+            state.count = 0;
+            state.generation_id = state.generation_id.wrapping_add(1);
+            self.cvar().notify_all();
+            true
+        }
+    }
+
+    #[inline]
+    fn lock(self: Pin<&Self>) -> Pin<&mutex::Mutex> {
+        unsafe { self.map_unchecked(|this| &this.lock) }
+    }
+
+    #[inline]
+    fn cvar(self: Pin<&Self>) -> Pin<&condvar::Condvar> {
+        unsafe { self.map_unchecked(|this| &this.cvar) }
+    }
+}
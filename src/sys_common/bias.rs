@@ -0,0 +1,258 @@
+//! Biased locking.
+//!
+//! A biased lock belongs to the first thread which locks it. As long as no
+//! other thread tries to lock it, the owner enters and leaves the critical
+//! section with plain stores instead of atomic read-modify-write operations.
+//!
+//! The first time another thread locks it, the bias is revoked for good: the
+//! revoking thread waits for the owner to leave the critical section, and from
+//! then on every thread, including the former owner, uses the real lock.
+//!
+//! The owner may also take the real lock without revoking the bias, such as to
+//! wait on a condvar, as every other thread revokes the bias before taking the
+//! real lock. It is marked as held by the owner meanwhile, so that the owner
+//! does not enter the critical section a second time through the bias.
+//!
+//! A biased guard must be dropped by the owner, so with the `send_guard`
+//! feature, where guards are `Send`, biased locking is not supported.
+//!
+//! The owner and the revoking thread synchronize in the style of Dekker's
+//! algorithm. To keep the owner's path cheap, the expensive half of the
+//! barrier is done by the revoking thread with `membarrier` where available.
+//! Elsewhere both sides use a full fence, which is still cheaper than a
+//! read-modify-write on most platforms but not by as much.
+
+use crate::sys_common::thread::current_thread_unique_ptr;
+use std::sync::atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering::*};
+use std::thread;
+
+const BIASED: u8 = 0;
+const REVOKING: u8 = 1;
+const REVOKED: u8 = 2;
+
+cfg_send_guard! {
+    if {
+        const AVAILABLE: bool = false;
+    } else {
+        const AVAILABLE: bool = true;
+    }
+}
+
+pub struct Bias {
+    enabled: bool,
+    owner: AtomicUsize,
+    active: AtomicBool,
+    // Whether the owner holds the real lock while the bias is not revoked.
+    real: AtomicBool,
+    state: AtomicU8,
+}
+
+impl Bias {
+    pub const fn new() -> Self {
+        Self {
+            enabled: false,
+            owner: AtomicUsize::new(0),
+            active: AtomicBool::new(false),
+            real: AtomicBool::new(false),
+            state: AtomicU8::new(BIASED),
+        }
+    }
+
+    /// A bias, unless biased locking is not supported, in which case this is
+    /// the same as [`new`].
+    ///
+    /// [`new`]: Self::new
+    pub fn enabled() -> Self {
+        if AVAILABLE {
+            barrier::init();
+        }
+        Self {
+            enabled: AVAILABLE,
+            ..Self::new()
+        }
+    }
+
+    /// Enters the critical section as the owner of the bias.
+    ///
+    /// Returns `false` if the current thread does not own the bias, in which
+    /// case the real lock must be used, after calling [`revoke`].
+    ///
+    /// [`revoke`]: Self::revoke
+    #[inline]
+    pub fn enter(&self) -> bool {
+        if !self.enabled || self.state.load(Relaxed) != BIASED {
+            return false;
+        }
+        let this_thread = current_thread_unique_ptr();
+        let owner = self.owner.load(Relaxed);
+        if owner != this_thread
+            && (owner != 0
+                || self
+                    .owner
+                    .compare_exchange(0, this_thread, Relaxed, Relaxed)
+                    .is_err())
+        {
+            return false;
+        }
+        if self.active.load(Relaxed) || self.real.load(Relaxed) {
+            panic!("mutex lock would result in deadlock");
+        }
+        self.active.store(true, Relaxed);
+        barrier::light();
+        if self.state.load(Relaxed) != BIASED {
+            self.active.store(false, Release);
+            return false;
+        }
+        true
+    }
+
+    /// Leaves the critical section entered with [`enter`].
+    ///
+    /// [`enter`]: Self::enter
+    #[inline]
+    pub fn exit(&self) {
+        self.active.store(false, Release);
+    }
+
+    /// Whether the owner is inside of the critical section.
+    #[inline]
+    pub fn active(&self) -> bool {
+        self.active.load(Relaxed)
+    }
+
+    /// Whether the current thread owns the bias, which is not revoked.
+    ///
+    /// The owner can then take the real lock without calling [`revoke`], and
+    /// must mark it as held with [`set_real`] while it holds it.
+    ///
+    /// [`revoke`]: Self::revoke
+    /// [`set_real`]: Self::set_real
+    #[inline]
+    pub fn owned(&self) -> bool {
+        self.enabled
+            && self.state.load(Relaxed) == BIASED
+            && self.owner.load(Relaxed) == current_thread_unique_ptr()
+    }
+
+    /// Marks the real lock as held or released by the owner.
+    ///
+    /// This is called with the real lock held, so it may be called to release
+    /// it by any thread: if the flag is set, the real lock is the owner's.
+    #[inline]
+    pub fn set_real(&self, real: bool) {
+        if self.enabled {
+            self.real.store(real, Relaxed);
+        }
+    }
+
+    /// Leaves the critical section on behalf of an owner which does not exist
+    /// in the child of a `fork`.
+    #[cfg(unix)]
+    pub fn reset_after_fork(&self) {
+        self.active.store(false, Relaxed);
+        self.real.store(false, Relaxed);
+    }
+
+    /// Makes sure the owner is not in the critical section and will not enter
+    /// it again through [`enter`].
+    ///
+    /// Returns `false` instead of waiting for the owner if `block` is `false`.
+    ///
+    /// [`enter`]: Self::enter
+    #[inline]
+    pub fn revoke(&self, block: bool) -> bool {
+        if !self.enabled {
+            return true;
+        }
+        if self.state.load(Acquire) != REVOKED {
+            self.revoke_slow();
+        }
+        while self.active.load(Acquire) {
+            if !block {
+                return false;
+            }
+            thread::yield_now();
+        }
+        true
+    }
+
+    /// Whether the bias has already been revoked and the owner is not in the
+    /// critical section, so that the real lock can be taken.
+    ///
+    /// Unlike [`revoke`], this never revokes the bias itself, which is too
+    /// expensive for an attempt which is allowed to fail.
+    ///
+    /// [`revoke`]: Self::revoke
+    #[inline]
+    pub fn revoked(&self) -> bool {
+        !self.enabled || (self.state.load(Acquire) == REVOKED && !self.active.load(Acquire))
+    }
+
+    #[cold]
+    fn revoke_slow(&self) {
+        // Until a barrier has been issued, the owner might still be entering
+        // without having seen the new state, so every thread which sees the
+        // lock before `REVOKED` issues one itself.
+        let _ = self.state.compare_exchange(BIASED, REVOKING, Relaxed, Relaxed);
+        barrier::heavy();
+        self.state.store(REVOKED, Release);
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod barrier {
+    use super::*;
+    use std::sync::atomic::{compiler_fence, fence};
+
+    const UNKNOWN: u8 = 0;
+    const SUPPORTED: u8 = 1;
+    const UNSUPPORTED: u8 = 2;
+
+    const MEMBARRIER_CMD_QUERY: libc::c_int = 0;
+    const MEMBARRIER_CMD_GLOBAL: libc::c_int = 1;
+
+    static MEMBARRIER: AtomicU8 = AtomicU8::new(UNKNOWN);
+
+    /// Must be called before any other function of this module.
+    pub fn init() {
+        if MEMBARRIER.load(Relaxed) == UNKNOWN {
+            let r = unsafe { libc::syscall(libc::SYS_membarrier, MEMBARRIER_CMD_QUERY, 0) };
+            let supported = r >= 0 && r & libc::c_long::from(MEMBARRIER_CMD_GLOBAL) != 0;
+            MEMBARRIER.store(if supported { SUPPORTED } else { UNSUPPORTED }, Relaxed);
+        }
+    }
+
+    #[inline]
+    pub fn light() {
+        if MEMBARRIER.load(Relaxed) == SUPPORTED {
+            compiler_fence(SeqCst);
+        } else {
+            fence(SeqCst);
+        }
+    }
+
+    pub fn heavy() {
+        if MEMBARRIER.load(Relaxed) == SUPPORTED {
+            let r = unsafe { libc::syscall(libc::SYS_membarrier, MEMBARRIER_CMD_GLOBAL, 0) };
+            assert_eq!(r, 0);
+        } else {
+            fence(SeqCst);
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+mod barrier {
+    use std::sync::atomic::{fence, Ordering::SeqCst};
+
+    pub fn init() {}
+
+    #[inline]
+    pub fn light() {
+        fence(SeqCst);
+    }
+
+    pub fn heavy() {
+        fence(SeqCst);
+    }
+}
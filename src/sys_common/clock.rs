@@ -0,0 +1,58 @@
+//! The clocks which timed waits are measured against.
+//!
+//! Timeouts are measured with [`Instant`], which does not advance while the
+//! system is suspended on most platforms. Suspend-aware condvars measure them
+//! with `CLOCK_BOOTTIME` instead where it exists, which does.
+
+use std::time::{Duration, Instant};
+
+/// A point in time on the clock chosen for a wait.
+#[derive(Clone, Copy)]
+pub enum Timestamp {
+    Monotonic(Instant),
+    Boot(Duration),
+}
+
+impl Timestamp {
+    /// Returns the current time, including the time spent suspended if
+    /// `suspend_aware` is set and the platform supports it.
+    #[inline]
+    pub fn now(suspend_aware: bool) -> Self {
+        match boottime() {
+            Some(now) if suspend_aware => Timestamp::Boot(now),
+            _ => Timestamp::Monotonic(Instant::now()),
+        }
+    }
+
+    #[inline]
+    pub fn is_suspend_aware(&self) -> bool {
+        matches!(self, Timestamp::Boot(_))
+    }
+
+    #[inline]
+    pub fn elapsed(&self) -> Duration {
+        match *self {
+            Timestamp::Monotonic(start) => start.elapsed(),
+            Timestamp::Boot(start) => boottime().unwrap().saturating_sub(start),
+        }
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn boottime() -> Option<Duration> {
+    let mut now = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    let r = unsafe { libc::clock_gettime(libc::CLOCK_BOOTTIME, &mut now) };
+    if r == 0 {
+        Some(Duration::new(now.tv_sec as u64, now.tv_nsec as u32))
+    } else {
+        None
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn boottime() -> Option<Duration> {
+    None
+}
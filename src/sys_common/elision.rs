@@ -0,0 +1,130 @@
+//! Hardware lock elision.
+//!
+//! With the `elision` feature on x86_64, the fast paths of the locks first try
+//! to run the critical section as a hardware transaction (Intel TSX), without
+//! writing to the lock at all. If another thread touches the same data, or
+//! acquires the lock for real, the transaction aborts, every change made by it
+//! is discarded and execution resumes as if the elision attempt had failed, at
+//! which point the real lock is taken.
+//!
+//! Anything which can not run inside of a transaction, such as a system call,
+//! aborts it, so elided critical sections may contain arbitrary code.
+//!
+//! Without the feature, or on CPUs which do not support TSX, elision is never
+//! attempted and all of this compiles down to nothing.
+
+cfg_if::cfg_if! {
+    if #[cfg(all(feature = "elision", target_arch = "x86_64"))] {
+        use std::arch::asm;
+        use std::sync::atomic::{AtomicBool, Ordering::*};
+
+        const XBEGIN_STARTED: u32 = !0;
+
+        /// Starts a transaction if `free` returns `true` inside of it.
+        ///
+        /// When this returns `true`, the caller is running inside of a transaction,
+        /// which must be finished with [`end`]. Reading the lock state in `free`
+        /// adds it to the transaction, so any thread acquiring the lock for real
+        /// aborts the transaction.
+        #[inline]
+        pub fn elide(free: impl FnOnce() -> bool) -> bool {
+            if !is_x86_feature_detected!("rtm") {
+                return false;
+            }
+            let status: u32;
+            // Safety: RTM is supported. On abort, execution resumes after
+            // `xbegin` with the state from before it and the abort status in
+            // `eax`.
+            unsafe {
+                asm!("xbegin 2f", "2:", inout("eax") XBEGIN_STARTED => status, options(nostack));
+            }
+            if status != XBEGIN_STARTED {
+                return false;
+            }
+            if free() {
+                true
+            } else {
+                abort()
+            }
+        }
+
+        /// Commits the transaction started by [`elide`].
+        #[inline]
+        pub fn end() {
+            // Safety: Only called for guards created inside of a transaction.
+            unsafe { asm!("xend", options(nostack)) }
+        }
+
+        /// Aborts the transaction started by [`elide`], which resumes the
+        /// elision attempt as failed.
+        #[inline]
+        pub fn abort() -> ! {
+            // Safety: `xabort` does not return when inside of a transaction.
+            unsafe { asm!("xabort 0xff", options(nostack)) }
+            unreachable!("aborted a transaction outside of a transaction")
+        }
+
+        /// Whether a lock without a state readable from user space is held for
+        /// real.
+        pub struct Held(AtomicBool);
+
+        impl Held {
+            pub const fn new() -> Self {
+                Self(AtomicBool::new(false))
+            }
+
+            #[inline]
+            pub fn get(&self) -> bool {
+                self.0.load(Relaxed)
+            }
+
+            /// Called after the lock is acquired for real, before any access to
+            /// the data protected by it.
+            #[inline]
+            pub fn acquire(&self) {
+                self.0.swap(true, Acquire);
+            }
+
+            /// Called before the lock is released for real, after all access to
+            /// the data protected by it.
+            #[inline]
+            pub fn release(&self) {
+                self.0.store(false, Release)
+            }
+        }
+    } else {
+        #[inline]
+        pub fn elide(_free: impl FnOnce() -> bool) -> bool {
+            false
+        }
+
+        #[inline]
+        pub fn end() {
+            unreachable!("elision is not enabled")
+        }
+
+        #[inline]
+        pub fn abort() -> ! {
+            unreachable!("elision is not enabled")
+        }
+
+        pub struct Held;
+
+        impl Held {
+            pub const fn new() -> Self {
+                Self
+            }
+
+            #[inline]
+            pub fn get(&self) -> bool {
+                false
+            }
+
+            #[inline]
+            pub fn acquire(&self) {}
+
+            #[inline]
+            pub fn release(&self) {}
+        }
+    }
+}
@@ -0,0 +1,100 @@
+//! Tracking of the read-write locks held by each thread.
+//!
+//! With the `debug-rwlock` feature, every thread keeps a list of the
+//! [`RwLock`]s which it holds, so that acquiring one of them again in a way
+//! which can only deadlock panics with a clear message, on every platform.
+//! Without the feature, this relies on the platform, which may detect it,
+//! deadlock, or, for recursive reads, succeed.
+//!
+//! A guard sent to another thread, with the `send_guard` feature, is
+//! forgotten by the thread which drops it rather than the one which acquired
+//! it.
+//!
+//! Without the feature, all of this compiles down to nothing.
+//!
+//! [`RwLock`]: crate::RwLock
+
+use crate::LockId;
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "debug-rwlock")] {
+        use std::cell::RefCell;
+
+        thread_local! {
+            // The locks held by the current thread, and whether they are held
+            // for writing.
+            static HELD: RefCell<Vec<(LockId, bool)>> = const { RefCell::new(Vec::new()) };
+        }
+
+        /// Panics if the current thread holds the lock for writing.
+        #[inline]
+        pub fn check_read(lock: LockId) {
+            if HELD.with(|held| held.borrow().contains(&(lock, true))) {
+                panic!("rwlock read lock would result in deadlock: the current thread holds a write lock on it (rwlock {:p})", lock);
+            }
+        }
+
+        /// Panics if the current thread holds the lock.
+        #[inline]
+        pub fn check_write(lock: LockId) {
+            match HELD.with(|held| held.borrow().iter().find(|&&(l, _)| l == lock).copied()) {
+                Some((_, true)) => panic!("rwlock write lock would result in deadlock: the current thread holds a write lock on it (rwlock {:p})", lock),
+                Some((_, false)) => panic!("rwlock write lock would result in deadlock: the current thread holds a read lock on it (rwlock {:p})", lock),
+                None => {}
+            }
+        }
+
+        /// A lock held by the current thread, which is forgotten on drop.
+        pub struct Held {
+            lock: LockId,
+            write: bool,
+        }
+
+        impl Held {
+            #[inline]
+            pub fn new(lock: LockId, write: bool) -> Self {
+                HELD.with(|held| held.borrow_mut().push((lock, write)));
+                Self { lock, write }
+            }
+        }
+
+        impl Drop for Held {
+            #[inline]
+            fn drop(&mut self) {
+                forget(self.lock, self.write);
+            }
+        }
+
+        /// Forgets one hold of the lock by the current thread, such as the
+        /// one of a guard which was leaked.
+        #[inline]
+        pub fn forget(lock: LockId, write: bool) {
+            // This may run during thread destruction, after the list is gone,
+            // when nothing is checked anymore.
+            let _ = HELD.try_with(|held| {
+                let mut held = held.borrow_mut();
+                if let Some(i) = held.iter().rposition(|&h| h == (lock, write)) {
+                    held.remove(i);
+                }
+            });
+        }
+    } else {
+        #[inline]
+        pub fn check_read(_lock: LockId) {}
+
+        #[inline]
+        pub fn check_write(_lock: LockId) {}
+
+        pub struct Held;
+
+        impl Held {
+            #[inline]
+            pub fn new(_lock: LockId, _write: bool) -> Self {
+                Self
+            }
+        }
+
+        #[inline]
+        pub fn forget(_lock: LockId, _write: bool) {}
+    }
+}
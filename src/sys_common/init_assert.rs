@@ -21,6 +21,15 @@ impl<T> InitAssert<T> {
         }
     }
 
+    /// An already initialized value, for primitives which are complete when
+    /// constructed.
+    pub const fn initialized(value: T) -> Self {
+        Self {
+            state: AtomicIsize::new(INIT),
+            data: UnsafeCell::new(MaybeUninit::new(value)),
+        }
+    }
+
     #[inline]
     pub fn init<F>(&self, f: F)
     where
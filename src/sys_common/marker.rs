@@ -0,0 +1,16 @@
+cfg_send_guard! {
+    if {
+        /// Marker making lock guards `Send`.
+        ///
+        /// The `send_guard` feature is enabled, and the locks of the backend
+        /// may be unlocked by any thread.
+        pub type GuardMarker = ();
+    } else {
+        /// Marker making lock guards `!Send`.
+        ///
+        /// Either the `send_guard` feature is disabled, or the backend, such as
+        /// pthread, requires a lock to be unlocked by the thread which locked
+        /// it.
+        pub type GuardMarker = *const ();
+    }
+}
@@ -1,2 +1,15 @@
+pub mod backoff;
+pub mod barrier;
+pub mod bias;
+pub mod clock;
+pub mod elision;
+pub mod held;
 pub mod poison;
+pub mod remutex;
+pub mod take;
 pub mod init_assert;
+pub mod marker;
+pub mod thread;
+pub mod timed;
+pub mod trace;
+pub mod wait_queue;
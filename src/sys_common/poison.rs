@@ -1,10 +1,10 @@
+use crate::{LockResult, PoisonError};
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::LockResult;
-use std::sync::PoisonError;
 use std::thread;
 
 pub struct Flag {
     failed: AtomicBool,
+    enabled: bool,
 }
 
 // Note that the Ordering uses to access the `failed` field of `Flag` below is
@@ -22,11 +22,23 @@ impl Flag {
     pub const fn new() -> Flag {
         Flag {
             failed: AtomicBool::new(false),
+            enabled: true,
+        }
+    }
+
+    /// Returns a flag which never becomes poisoned.
+    pub const fn disabled() -> Flag {
+        Flag {
+            failed: AtomicBool::new(false),
+            enabled: false,
         }
     }
 
     #[inline]
     pub fn borrow(&self) -> LockResult<Guard> {
+        if !self.enabled {
+            return Ok(Guard { panicking: true });
+        }
         let ret = Guard {
             panicking: thread::panicking(),
         };
@@ -39,11 +51,26 @@ impl Flag {
 
     #[inline]
     pub fn done(&self, guard: &Guard) {
+        // A disabled flag hands out guards with `panicking` set, so it is never
+        // written to.
         if !guard.panicking && thread::panicking() {
             self.failed.store(true, Ordering::Relaxed);
         }
     }
 
+    /// Poisons the flag for a panic which was caught while the lock was held.
+    #[inline]
+    pub fn poison(&self) {
+        if self.enabled {
+            self.failed.store(true, Ordering::Relaxed);
+        }
+    }
+
+    #[inline]
+    pub fn clear(&self) {
+        self.failed.store(false, Ordering::Relaxed);
+    }
+
     #[inline]
     pub fn get(&self) -> bool {
         self.failed.load(Ordering::Relaxed)
@@ -54,6 +81,14 @@ pub struct Guard {
     panicking: bool,
 }
 
+impl Guard {
+    /// Keeps the current panic from poisoning the flag.
+    #[inline]
+    pub fn forgive(&mut self) {
+        self.panicking = true;
+    }
+}
+
 pub fn map_result<T, U, F>(result: LockResult<T>, f: F) -> LockResult<U>
 where
     F: FnOnce(T) -> U,
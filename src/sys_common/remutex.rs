@@ -0,0 +1,113 @@
+//! A re-entrant mutex for the backends without a native one, which counts the
+//! recursion itself on top of the mutex of the backend.
+
+#![allow(dead_code)]
+
+use crate::sys::mutex as sys;
+use crate::sys_common::thread::current_thread_unique_ptr;
+use std::cell::UnsafeCell;
+use std::marker::PhantomPinned;
+use std::mem;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering::*};
+
+pub struct ReentrantMutex {
+    inner: sys::Mutex,
+    owner: AtomicUsize,
+    lock_count: UnsafeCell<u32>,
+    // The guard of `inner`, held while the mutex is owned by some thread. It
+    // only ever borrows `inner`, which is pinned, so the lifetime is erased.
+    guard: UnsafeCell<Option<sys::MutexGuard<'static>>>,
+    _p: PhantomPinned,
+}
+
+unsafe impl Send for ReentrantMutex {}
+unsafe impl Sync for ReentrantMutex {}
+
+impl ReentrantMutex {
+    #[inline]
+    pub const fn uninit() -> Self {
+        Self {
+            inner: sys::Mutex::uninit(),
+            owner: AtomicUsize::new(0),
+            lock_count: UnsafeCell::new(0),
+            guard: UnsafeCell::new(None),
+            _p: PhantomPinned,
+        }
+    }
+
+    #[inline]
+    pub fn init(self: Pin<&Self>) {
+        self.inner().init()
+    }
+
+    /// Panics if the lock count would overflow.
+    pub fn lock(self: Pin<&Self>) -> ReentrantMutexGuard<'_> {
+        let this_thread = current_thread_unique_ptr();
+        // Safety: We only touch lock_count and guard when we own the lock.
+        unsafe {
+            if self.owner.load(Relaxed) == this_thread {
+                self.increment_lock_count();
+            } else {
+                let guard = self.inner().lock();
+                self.acquired(this_thread, guard);
+            }
+        }
+        ReentrantMutexGuard { lock: self }
+    }
+
+    /// Panics if the lock count would overflow.
+    pub fn try_lock(self: Pin<&Self>) -> Option<ReentrantMutexGuard<'_>> {
+        let this_thread = current_thread_unique_ptr();
+        // Safety: We only touch lock_count and guard when we own the lock.
+        unsafe {
+            if self.owner.load(Relaxed) == this_thread {
+                self.increment_lock_count();
+            } else {
+                let guard = self.inner().try_lock()?;
+                self.acquired(this_thread, guard);
+            }
+        }
+        Some(ReentrantMutexGuard { lock: self })
+    }
+
+    unsafe fn acquired(self: Pin<&Self>, this_thread: usize, guard: sys::MutexGuard<'_>) {
+        *self.guard.get() = Some(mem::transmute::<
+            sys::MutexGuard<'_>,
+            sys::MutexGuard<'static>,
+        >(guard));
+        self.owner.store(this_thread, Relaxed);
+        debug_assert_eq!(*self.lock_count.get(), 0);
+        *self.lock_count.get() = 1;
+    }
+
+    unsafe fn increment_lock_count(&self) {
+        *self.lock_count.get() = (*self.lock_count.get())
+            .checked_add(1)
+            .expect("lock count overflow in reentrant mutex");
+    }
+
+    unsafe fn unlock(&self) {
+        *self.lock_count.get() -= 1;
+        if *self.lock_count.get() == 0 {
+            self.owner.store(0, Relaxed);
+            drop((*self.guard.get()).take());
+        }
+    }
+
+    #[inline]
+    fn inner(self: Pin<&Self>) -> Pin<&sys::Mutex> {
+        unsafe { self.map_unchecked(|this| &this.inner) }
+    }
+}
+
+pub struct ReentrantMutexGuard<'a> {
+    lock: Pin<&'a ReentrantMutex>,
+}
+impl Drop for ReentrantMutexGuard<'_> {
+    #[inline]
+    fn drop(&mut self) {
+        // Safety: We own the lock.
+        unsafe { self.lock.unlock() }
+    }
+}
@@ -0,0 +1,45 @@
+//! Consuming pinned primitives.
+//!
+//! A pinned value must be dropped in place, so it can not simply be moved out
+//! of its `Pin<Box<_>>` or `Pin<Arc<_>>`. Instead, the unpinned parts of it are
+//! moved out in place, the rest is dropped in place, and only then is the
+//! memory freed.
+
+use std::mem::ManuallyDrop;
+use std::pin::Pin;
+use std::sync::Arc;
+
+/// Calls `take` on the value of a pinned box, then frees the box without
+/// dropping the value.
+///
+/// # Safety
+///
+/// `take` must move out or drop in place every field of the value, and must
+/// only move out fields which are not structurally pinned.
+pub unsafe fn take_box<T, U>(this: Pin<Box<T>>, take: impl FnOnce(&mut T) -> U) -> U {
+    let raw = Box::into_raw(Pin::into_inner_unchecked(this));
+    let result = take(&mut *raw);
+    drop(Box::from_raw(raw as *mut ManuallyDrop<T>));
+    result
+}
+
+/// Calls `take` on the value of a pinned arc if this is its only reference,
+/// strong or weak, then frees the arc without dropping the value.
+///
+/// # Safety
+///
+/// Same as [`take_box`].
+pub unsafe fn take_arc<T, U>(
+    this: Pin<Arc<T>>,
+    take: impl FnOnce(&mut T) -> U,
+) -> Result<U, Pin<Arc<T>>> {
+    let mut arc = Pin::into_inner_unchecked(this);
+    match Arc::get_mut(&mut arc) {
+        Some(value) => {
+            let result = take(value);
+            drop(Arc::from_raw(Arc::into_raw(arc) as *const ManuallyDrop<T>));
+            Ok(result)
+        }
+        None => Err(Pin::new_unchecked(arc)),
+    }
+}
@@ -0,0 +1,74 @@
+//! Timed lock acquisition.
+//!
+//! Some platform locks can not block with a timeout, such as SRW locks on
+//! Windows, or pthread mutexes where `pthread_mutex_timedlock` is missing.
+//! Those poll their `try_lock` instead, sleeping for longer and longer in
+//! between, up to a limit which bounds how late they notice the lock being
+//! released.
+
+#![allow(dead_code)]
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+// How many times to yield before sleeping.
+const YIELD_LIMIT: u32 = 10;
+const MIN_SLEEP: Duration = Duration::from_micros(50);
+const MAX_SLEEP: Duration = Duration::from_millis(1);
+
+/// Calls `try_lock` until it succeeds or the deadline is reached.
+pub fn poll_until<G>(deadline: Instant, mut try_lock: impl FnMut() -> Option<G>) -> Option<G> {
+    let mut yields = 0;
+    let mut sleep = MIN_SLEEP;
+    loop {
+        if let Some(guard) = try_lock() {
+            return Some(guard);
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            return None;
+        }
+        if yields < YIELD_LIMIT {
+            yields += 1;
+            thread::yield_now();
+        } else {
+            thread::sleep(sleep.min(deadline - now));
+            sleep = (sleep * 2).min(MAX_SLEEP);
+        }
+    }
+}
+
+/// The `CLOCK_REALTIME` time at which `deadline` is reached, for the pthread
+/// functions which take an absolute timeout on that clock.
+///
+/// Changes to the system time while waiting make the wait shorter or longer.
+#[cfg(unix)]
+pub fn realtime(deadline: Instant) -> libc::timespec {
+    use std::convert::TryInto;
+
+    let remaining = deadline.saturating_duration_since(Instant::now());
+    let mut now = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    let r = unsafe { libc::clock_gettime(libc::CLOCK_REALTIME, &mut now) };
+    assert_eq!(r, 0);
+
+    // Nanosecond calculations can't overflow because both values are below 1e9.
+    let nsec = remaining.subsec_nanos() + now.tv_nsec as u32;
+    remaining
+        .as_secs()
+        .try_into()
+        .ok()
+        .and_then(|secs: libc::time_t| secs.checked_add(now.tv_sec))
+        .and_then(|secs| secs.checked_add((nsec / 1_000_000_000) as libc::time_t))
+        .map(|secs| libc::timespec {
+            tv_sec: secs,
+            tv_nsec: (nsec % 1_000_000_000) as _,
+        })
+        // A deadline which can not be represented is as good as no deadline.
+        .unwrap_or(libc::timespec {
+            tv_sec: libc::time_t::MAX,
+            tv_nsec: 1_000_000_000 - 1,
+        })
+}
@@ -0,0 +1,88 @@
+//! Recording of lock waits and holds.
+//!
+//! With the `profiling` feature, every lock acquisition measures how long the
+//! thread waited for the lock and how long it held it, and hands the intervals
+//! to the [`LockTrace`] being recorded, if any. While nothing is recorded, this
+//! costs a relaxed load per acquisition. While something is, recording from
+//! inside of an elided critical section aborts it, so locks are always taken
+//! for real.
+//!
+//! Without the feature, all of this compiles down to nothing.
+//!
+//! [`LockTrace`]: crate::LockTrace
+
+use crate::LockId;
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "profiling")] {
+        use crate::lock_trace::{self, LockEventKind};
+        use std::time::Instant;
+
+        /// A thread waiting for a lock.
+        pub struct Wait(Option<Instant>);
+
+        impl Wait {
+            /// Called before the thread starts waiting for the lock.
+            #[inline]
+            pub fn start() -> Self {
+                Self(lock_trace::recording().then(Instant::now))
+            }
+
+            /// Called once the lock is acquired.
+            #[inline]
+            pub fn acquired(self, lock: LockId, primitive: &'static str) -> Hold {
+                match self.0 {
+                    Some(start) => {
+                        let now = Instant::now();
+                        lock_trace::record(LockEventKind::Wait, lock, primitive, start, now);
+                        Hold(Some((lock, primitive, now)))
+                    }
+                    None => Hold::start(lock, primitive),
+                }
+            }
+        }
+
+        /// A lock held by the current thread, which is recorded on drop.
+        pub struct Hold(Option<(LockId, &'static str, Instant)>);
+
+        impl Hold {
+            /// Called once the lock is acquired without waiting.
+            #[inline]
+            pub fn start(lock: LockId, primitive: &'static str) -> Self {
+                Self(lock_trace::recording().then(|| (lock, primitive, Instant::now())))
+            }
+        }
+
+        impl Drop for Hold {
+            #[inline]
+            fn drop(&mut self) {
+                if let Some((lock, primitive, start)) = self.0 {
+                    lock_trace::record(LockEventKind::Hold, lock, primitive, start, Instant::now());
+                }
+            }
+        }
+    } else {
+        pub struct Wait;
+
+        impl Wait {
+            #[inline]
+            pub fn start() -> Self {
+                Self
+            }
+
+            #[inline]
+            pub fn acquired(self, _lock: LockId, _primitive: &'static str) -> Hold {
+                Hold
+            }
+        }
+
+        pub struct Hold;
+
+        impl Hold {
+            #[inline]
+            pub fn start(_lock: LockId, _primitive: &'static str) -> Self {
+                Self
+            }
+        }
+    }
+}
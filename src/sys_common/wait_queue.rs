@@ -0,0 +1,127 @@
+//! First-in, first-out waiting.
+//!
+//! The order in which the platform condition variables wake up their waiters
+//! is unspecified, so a fair condition variable keeps its own queue instead,
+//! and parks each waiter until it reaches the front of the queue and is
+//! notified.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering::*};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::{self, Thread};
+use std::time::{Duration, Instant};
+
+pub struct Waiter {
+    thread: Thread,
+    notified: AtomicBool,
+}
+
+pub struct WaitQueue {
+    enabled: bool,
+    waiters: Mutex<VecDeque<Arc<Waiter>>>,
+}
+
+impl WaitQueue {
+    pub const fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            waiters: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    #[inline]
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Adds the current thread to the back of the queue.
+    ///
+    /// This must be called before the mutex associated with the queue is
+    /// unlocked, so that notifications sent after that reach the thread.
+    pub fn push(&self) -> Arc<Waiter> {
+        let waiter = Arc::new(Waiter {
+            thread: thread::current(),
+            notified: AtomicBool::new(false),
+        });
+        self.waiters().push_back(waiter.clone());
+        waiter
+    }
+
+    /// Blocks the current thread until `waiter` is notified, or the timeout
+    /// elapses.
+    ///
+    /// Returns `false` if the timeout elapsed, in which case the waiter has
+    /// been removed from the queue.
+    pub fn park(&self, waiter: &Arc<Waiter>, timeout: Option<Duration>) -> bool {
+        // A timeout which can not be represented is as good as no timeout.
+        let deadline = timeout.and_then(|timeout| Instant::now().checked_add(timeout));
+        while !waiter.notified.load(Acquire) {
+            match deadline {
+                None => thread::park(),
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return self.cancel(waiter);
+                    }
+                    thread::park_timeout(deadline - now);
+                }
+            }
+        }
+        true
+    }
+
+    /// Wakes up the thread at the front of the queue.
+    pub fn notify_one(&self) {
+        let waiter = self.waiters().pop_front();
+        if let Some(waiter) = waiter {
+            Self::wake(&waiter);
+        }
+    }
+
+    /// Wakes up the `n` threads at the front of the queue, in order.
+    pub fn notify_many(&self, n: usize) {
+        let waiters: Vec<_> = {
+            let mut waiters = self.waiters();
+            let n = n.min(waiters.len());
+            waiters.drain(..n).collect()
+        };
+        for waiter in &waiters {
+            Self::wake(waiter);
+        }
+    }
+
+    /// Wakes up every thread in the queue, in order.
+    pub fn notify_all(&self) {
+        let waiters = std::mem::take(&mut *self.waiters());
+        for waiter in &waiters {
+            Self::wake(waiter);
+        }
+    }
+
+    /// Removes `waiter` from the queue without waiting for it to be notified.
+    ///
+    /// Returns whether the waiter was notified before it could be removed.
+    #[cold]
+    pub fn cancel(&self, waiter: &Arc<Waiter>) -> bool {
+        let mut waiters = self.waiters();
+        match waiters.iter().position(|w| Arc::ptr_eq(w, waiter)) {
+            Some(i) => {
+                waiters.remove(i);
+                false
+            }
+            None => true,
+        }
+    }
+
+    #[inline]
+    fn wake(waiter: &Waiter) {
+        waiter.notified.store(true, Release);
+        waiter.thread.unpark();
+    }
+
+    #[inline]
+    fn waiters(&self) -> MutexGuard<'_, VecDeque<Arc<Waiter>>> {
+        // Nothing can panic while the queue is locked.
+        self.waiters.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
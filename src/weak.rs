@@ -0,0 +1,112 @@
+use std::fmt;
+use std::pin::Pin;
+use std::sync::{Arc, Weak};
+
+/// A weak reference to a pinned [`Arc`].
+///
+/// This is the pinned equivalent of [`Weak`]: it does not keep the value
+/// alive, and can be upgraded back into a `Pin<Arc<T>>` as long as a strong
+/// reference still exists. It is useful for back-references to locks created
+/// with `arc()`, which would otherwise form reference cycles.
+///
+/// The value is never moved while a `PinWeak` exists, as it can only be
+/// dropped in place once the last strong reference goes away.
+///
+/// # Examples
+///
+/// ```
+/// use pinned_sync::{Mutex, PinWeak};
+///
+/// let mutex = Mutex::arc(0);
+/// let weak = PinWeak::downgrade(&mutex);
+///
+/// *weak.upgrade().unwrap().as_ref().lock().unwrap() += 1;
+/// assert_eq!(*mutex.as_ref().lock().unwrap(), 1);
+///
+/// drop(mutex);
+/// assert!(weak.upgrade().is_none());
+/// ```
+pub struct PinWeak<T: ?Sized> {
+    inner: Weak<T>,
+}
+
+impl<T> PinWeak<T> {
+    /// Constructs a new `PinWeak<T>`, without allocating any memory. Calling
+    /// [`upgrade`] on the return value always gives [`None`].
+    ///
+    /// [`upgrade`]: Self::upgrade
+    #[inline]
+    pub fn new() -> Self {
+        Self { inner: Weak::new() }
+    }
+}
+
+impl<T: ?Sized> PinWeak<T> {
+    /// Creates a new weak reference to the value of a pinned [`Arc`].
+    #[inline]
+    pub fn downgrade(this: &Pin<Arc<T>>) -> Self {
+        // Safety: The `Arc` is only used to create the weak reference, and the
+        // value is never moved out of it.
+        let arc = unsafe { Pin::into_inner_unchecked(Pin::clone(this)) };
+        Self {
+            inner: Arc::downgrade(&arc),
+        }
+    }
+
+    /// Attempts to upgrade the weak reference to a pinned [`Arc`], delaying
+    /// dropping of the value if successful.
+    ///
+    /// Returns [`None`] if the value has since been dropped.
+    #[inline]
+    pub fn upgrade(&self) -> Option<Pin<Arc<T>>> {
+        // Safety: The value was pinned when this was created, and it has not
+        // been dropped, so it is still pinned.
+        self.inner
+            .upgrade()
+            .map(|arc| unsafe { Pin::new_unchecked(arc) })
+    }
+
+    /// Gets the number of strong pointers to the value.
+    ///
+    /// If `self` was created using [`PinWeak::new`], this will return 0.
+    #[inline]
+    pub fn strong_count(&self) -> usize {
+        self.inner.strong_count()
+    }
+
+    /// Gets the number of weak pointers to the value, or 0 if there are no
+    /// strong pointers left.
+    #[inline]
+    pub fn weak_count(&self) -> usize {
+        self.inner.weak_count()
+    }
+
+    /// Returns `true` if the two `PinWeak`s point to the same allocation, or
+    /// if both don't point to any allocation.
+    #[inline]
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        self.inner.ptr_eq(&other.inner)
+    }
+}
+
+impl<T: ?Sized> Clone for PinWeak<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T> Default for PinWeak<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for PinWeak<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("(PinWeak)")
+    }
+}
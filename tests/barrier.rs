@@ -1,9 +1,10 @@
 use pinned_sync::Barrier;
+use std::sync::atomic::{AtomicUsize, Ordering::*};
 use std::sync::mpsc::{channel, TryRecvError};
+use std::sync::Arc;
 use std::thread;
 
 #[test]
-#[cfg_attr(target_os = "emscripten", ignore)]
 fn test_barrier() {
     const N: usize = 10;
 
@@ -33,3 +34,75 @@ fn test_barrier() {
     }
     assert!(leader_found);
 }
+
+#[test]
+fn reuse() {
+    const N: usize = 4;
+    const ROUNDS: usize = 100;
+
+    let barrier = Barrier::arc(N);
+    let leaders = Arc::new(AtomicUsize::new(0));
+    let threads: Vec<_> = (0..N)
+        .map(|_| {
+            let (barrier, leaders) = (barrier.clone(), leaders.clone());
+            thread::spawn(move || {
+                for _ in 0..ROUNDS {
+                    if barrier.as_ref().wait().is_leader() {
+                        leaders.fetch_add(1, Relaxed);
+                    }
+                }
+            })
+        })
+        .collect();
+    for t in threads {
+        t.join().unwrap();
+    }
+    assert_eq!(leaders.load(Relaxed), ROUNDS);
+}
+
+#[test]
+fn zero_threads() {
+    let barrier = Barrier::boxed(0);
+    assert!(barrier.as_ref().wait().is_leader());
+}
+
+#[test]
+fn more_threads_than_barrier() {
+    const N: usize = 4;
+    const GENERATIONS: usize = 1000;
+
+    let barrier = Barrier::arc(N);
+    // The threads take as many turns as needed to fill every generation, as
+    // the ones taking each of them are arbitrary.
+    let turns = Arc::new(AtomicUsize::new(0));
+    // Every thread counts itself in `arrived` before waiting and in `passed`
+    // after, so no more threads than complete generations can have passed.
+    let arrived = Arc::new(AtomicUsize::new(0));
+    let passed = Arc::new(AtomicUsize::new(0));
+    let early = Arc::new(AtomicUsize::new(0));
+    let leaders = Arc::new(AtomicUsize::new(0));
+    let threads: Vec<_> = (0..2 * N)
+        .map(|_| {
+            let barrier = barrier.clone();
+            let (turns, arrived, passed) = (turns.clone(), arrived.clone(), passed.clone());
+            let (early, leaders) = (early.clone(), leaders.clone());
+            thread::spawn(move || {
+                while turns.fetch_add(1, Relaxed) < N * GENERATIONS {
+                    arrived.fetch_add(1, SeqCst);
+                    if barrier.as_ref().wait().is_leader() {
+                        leaders.fetch_add(1, Relaxed);
+                    }
+                    let passed = passed.fetch_add(1, SeqCst) + 1;
+                    if passed > arrived.load(SeqCst) / N * N {
+                        early.fetch_add(1, Relaxed);
+                    }
+                }
+            })
+        })
+        .collect();
+    for t in threads {
+        t.join().unwrap();
+    }
+    assert_eq!(early.load(Relaxed), 0);
+    assert_eq!(leaders.load(Relaxed), GENERATIONS);
+}
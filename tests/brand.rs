@@ -0,0 +1,61 @@
+use pinned_sync::{Brand, BrandedCell, Mutex};
+use std::sync::Arc;
+use std::thread;
+
+#[test]
+fn borrow() {
+    Brand::with(|mut brand| {
+        let a = BrandedCell::new(1);
+        let b = BrandedCell::new(2);
+
+        assert_eq!(*a.borrow(&brand) + *b.borrow(&brand), 3);
+        *a.borrow_mut(&mut brand) += 10;
+        assert_eq!(a.replace(0, &mut brand), 11);
+        assert_eq!(*a.borrow(&brand), 0);
+        assert_eq!(b.into_inner(), 2);
+    });
+}
+
+#[test]
+fn graph() {
+    struct Node<'id> {
+        value: BrandedCell<'id, u32>,
+        next: Option<Arc<Node<'id>>>,
+    }
+
+    Brand::with(|brand| {
+        let mutex = Mutex::boxed(brand);
+        let mut head = None;
+        for _ in 0..10 {
+            head = Some(Arc::new(Node {
+                value: BrandedCell::new(0),
+                next: head,
+            }));
+        }
+        let head = head.unwrap();
+
+        thread::scope(|s| {
+            for _ in 0..4 {
+                let head = head.clone();
+                let mutex = mutex.as_ref();
+                s.spawn(move || {
+                    for _ in 0..100 {
+                        let mut brand = mutex.lock().unwrap();
+                        let mut node = Some(&head);
+                        while let Some(n) = node {
+                            *n.value.borrow_mut(&mut brand) += 1;
+                            node = n.next.as_ref();
+                        }
+                    }
+                });
+            }
+        });
+
+        let brand = mutex.as_ref().lock().unwrap();
+        let mut node = Some(&head);
+        while let Some(n) = node {
+            assert_eq!(*n.value.borrow(&brand), 400);
+            node = n.next.as_ref();
+        }
+    });
+}
@@ -0,0 +1,123 @@
+use pinned_sync::channel::Channel;
+use std::sync::mpsc::{RecvTimeoutError, TryRecvError, TrySendError};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn fifo() {
+    let channel = Channel::<i32, 4>::boxed();
+    assert_eq!(channel.capacity(), 4);
+    assert!(channel.as_ref().is_empty());
+    for i in 0..4 {
+        channel.as_ref().try_send(i).unwrap();
+    }
+    assert_eq!(channel.as_ref().len(), 4);
+    match channel.as_ref().try_send(4) {
+        Err(TrySendError::Full(4)) => {}
+        r => panic!("unexpected {:?}", r),
+    }
+    assert_eq!(channel.as_ref().try_recv(), Ok(0));
+    channel.as_ref().send(4).unwrap();
+    for i in 1..5 {
+        assert_eq!(channel.as_ref().recv(), Ok(i));
+    }
+    assert_eq!(channel.as_ref().try_recv(), Err(TryRecvError::Empty));
+}
+
+#[test]
+fn close() {
+    let channel = Channel::<i32, 2>::boxed();
+    channel.as_ref().send(1).unwrap();
+    channel.as_ref().close();
+    assert!(channel.as_ref().is_closed());
+    assert_eq!(channel.as_ref().send(2).unwrap_err().0, 2);
+    match channel.as_ref().try_send(3) {
+        Err(TrySendError::Disconnected(3)) => {}
+        r => panic!("unexpected {:?}", r),
+    }
+    // The values which are left can still be received.
+    assert_eq!(channel.as_ref().recv(), Ok(1));
+    assert!(channel.as_ref().recv().is_err());
+    assert_eq!(channel.as_ref().try_recv(), Err(TryRecvError::Disconnected));
+}
+
+#[test]
+fn close_wakes_blocked() {
+    let channel = Channel::<i32, 1>::arc();
+    let receiver = {
+        let channel = channel.clone();
+        thread::spawn(move || channel.as_ref().recv())
+    };
+    thread::sleep(Duration::from_millis(50));
+    channel.as_ref().close();
+    assert!(receiver.join().unwrap().is_err());
+}
+
+#[test]
+fn recv_timeout() {
+    let channel = Channel::<i32, 1>::boxed();
+    assert_eq!(
+        channel.as_ref().recv_timeout(Duration::from_millis(10)),
+        Err(RecvTimeoutError::Timeout)
+    );
+    channel.as_ref().send(1).unwrap();
+    assert_eq!(
+        channel.as_ref().recv_timeout(Duration::from_millis(10)),
+        Ok(1)
+    );
+}
+
+#[test]
+fn mpmc() {
+    const THREADS: usize = 4;
+    const VALUES: usize = 1000;
+
+    let channel = Channel::<usize, 8>::arc();
+    let producers: Vec<_> = (0..THREADS)
+        .map(|t| {
+            let channel = channel.clone();
+            thread::spawn(move || {
+                for i in 0..VALUES {
+                    channel.as_ref().send(t * VALUES + i).unwrap();
+                }
+            })
+        })
+        .collect();
+    let consumers: Vec<_> = (0..THREADS)
+        .map(|_| {
+            let channel = channel.clone();
+            thread::spawn(move || {
+                let mut received = Vec::new();
+                while let Ok(value) = channel.as_ref().recv() {
+                    received.push(value);
+                }
+                received
+            })
+        })
+        .collect();
+    for p in producers {
+        p.join().unwrap();
+    }
+    channel.as_ref().close();
+    let mut received: Vec<_> = consumers
+        .into_iter()
+        .flat_map(|c| c.join().unwrap())
+        .collect();
+    received.sort_unstable();
+    assert_eq!(received, (0..THREADS * VALUES).collect::<Vec<_>>());
+}
+
+#[test]
+fn drops_remaining() {
+    let value = Arc::new(());
+    {
+        let channel = Channel::<Arc<()>, 4>::boxed();
+        for _ in 0..3 {
+            channel.as_ref().send(value.clone()).unwrap();
+        }
+        drop(channel.as_ref().recv());
+        assert_eq!(Arc::strong_count(&value), 3);
+    }
+    assert_eq!(Arc::strong_count(&value), 1);
+}
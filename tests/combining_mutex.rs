@@ -0,0 +1,86 @@
+use pinned_sync::CombiningMutex;
+use std::panic::{self, AssertUnwindSafe};
+use std::thread;
+
+#[test]
+fn smoke() {
+    let m = CombiningMutex::boxed(1);
+    assert_eq!(m.as_ref().run(|n| *n + 1).unwrap(), 2);
+    m.as_ref().run(|n| *n = 5).unwrap();
+    assert_eq!(m.as_ref().run(|n| *n).unwrap(), 5);
+}
+
+#[test]
+fn lots_and_lots() {
+    const J: u32 = 1000;
+    const K: u32 = 8;
+
+    let m = CombiningMutex::arc(Vec::new());
+    let handles: Vec<_> = (0..K)
+        .map(|k| {
+            let m = m.clone();
+            thread::spawn(move || {
+                for j in 0..J {
+                    let len = m.as_ref().run(|v| {
+                        v.push((k, j));
+                        v.len()
+                    });
+                    assert!(len.unwrap() >= 1);
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    let v = m.as_ref().run(|v| v.clone()).unwrap();
+    assert_eq!(v.len(), (J * K) as usize);
+    // Each thread's operations are executed in order.
+    for k in 0..K {
+        let js: Vec<_> = v
+            .iter()
+            .filter(|&&(x, _)| x == k)
+            .map(|&(_, j)| j)
+            .collect();
+        assert_eq!(js, (0..J).collect::<Vec<_>>());
+    }
+}
+
+#[test]
+fn panic_poisons() {
+    let m = CombiningMutex::arc(0);
+    let m2 = m.clone();
+    let r = thread::spawn(move || {
+        m2.as_ref()
+            .run(|_| panic!("test panic in combining mutex"))
+            .unwrap();
+    })
+    .join();
+    assert!(r.is_err());
+    assert!(m.as_ref().is_poisoned());
+    let r = m.as_ref().run(|n| {
+        *n += 1;
+        *n
+    });
+    assert_eq!(r.unwrap_err().into_inner(), 1);
+}
+
+#[test]
+fn panic_resumed_on_submitter() {
+    let m = CombiningMutex::boxed(());
+    let r = panic::catch_unwind(AssertUnwindSafe(|| {
+        m.as_ref().run(|_| panic!("test panic in combining mutex"))
+    }));
+    let payload = r.unwrap_err();
+    assert_eq!(
+        payload.downcast_ref::<&str>(),
+        Some(&"test panic in combining mutex")
+    );
+}
+
+#[test]
+fn get_mut() {
+    let mut m = CombiningMutex::uninit(10);
+    *m.get_mut().unwrap() = 20;
+    assert_eq!(m.into_inner().unwrap(), 20);
+}
@@ -1,9 +1,11 @@
-use pinned_sync::{Condvar, Mutex};
-use std::sync::atomic::{AtomicBool, Ordering};
+use pinned_sync::raw::{RawCondvar, RawMutex};
+use pinned_sync::{Condvar, Mutex, RwLock};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::mpsc::channel;
 use std::sync::Arc;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 #[test]
 fn smoke() {
@@ -13,7 +15,6 @@ fn smoke() {
 }
 
 #[test]
-#[cfg_attr(target_os = "emscripten", ignore)]
 fn notify_one() {
     let m = Mutex::arc(());
     let m2 = m.clone();
@@ -30,7 +31,6 @@ fn notify_one() {
 }
 
 #[test]
-#[cfg_attr(target_os = "emscripten", ignore)]
 fn notify_all() {
     const N: usize = 10;
 
@@ -67,7 +67,6 @@ fn notify_all() {
 }
 
 #[test]
-#[cfg_attr(target_os = "emscripten", ignore)]
 fn wait_while() {
     let m = Mutex::arc(false);
     let m2 = m.clone();
@@ -90,7 +89,6 @@ fn wait_while() {
 }
 
 #[test]
-#[cfg_attr(target_os = "emscripten", ignore)]
 fn wait_timeout_wait() {
     let m = Mutex::arc(());
     let c = Condvar::arc();
@@ -112,7 +110,6 @@ fn wait_timeout_wait() {
 }
 
 #[test]
-#[cfg_attr(target_os = "emscripten", ignore)]
 fn wait_timeout_while_wait() {
     let m = Mutex::arc(());
     let c = Condvar::arc();
@@ -127,7 +124,6 @@ fn wait_timeout_while_wait() {
 }
 
 #[test]
-#[cfg_attr(target_os = "emscripten", ignore)]
 fn wait_timeout_while_instant_satisfy() {
     let m = Mutex::arc(());
     let c = Condvar::arc();
@@ -142,7 +138,6 @@ fn wait_timeout_while_instant_satisfy() {
 }
 
 #[test]
-#[cfg_attr(target_os = "emscripten", ignore)]
 fn wait_timeout_while_wake() {
     let m = Mutex::arc(false);
     let m2 = m.clone();
@@ -168,7 +163,6 @@ fn wait_timeout_while_wake() {
 }
 
 #[test]
-#[cfg_attr(target_os = "emscripten", ignore)]
 fn wait_timeout_wake() {
     let m = Mutex::arc(());
     let c = Condvar::arc();
@@ -204,6 +198,62 @@ fn wait_timeout_wake() {
     }
 }
 
+#[test]
+fn wait_until_wait() {
+    let m = Mutex::arc(());
+    let c = Condvar::arc();
+
+    let deadline = Instant::now() + Duration::from_millis(10);
+    let mut g = m.as_ref().lock().unwrap();
+    // Every wait times out at the same deadline.
+    loop {
+        let (g2, wait) = c.as_ref().wait_until(g, deadline).unwrap();
+        g = g2;
+        if wait.timed_out() {
+            break;
+        }
+    }
+    assert!(Instant::now() >= deadline);
+
+    let (_g, wait) = c.as_ref().wait_until(g, deadline).unwrap();
+    assert!(wait.timed_out());
+}
+
+#[test]
+fn wait_while_until_wake() {
+    let m = Mutex::arc(false);
+    let m2 = m.clone();
+    let c = Condvar::arc();
+    let c2 = c.clone();
+
+    let g = m.as_ref().lock().unwrap();
+    let _t = thread::spawn(move || {
+        let mut started = m2.as_ref().lock().unwrap();
+        thread::sleep(Duration::from_millis(1));
+        *started = true;
+        c2.as_ref().notify_one();
+    });
+    let deadline = Instant::now() + Duration::from_secs(60);
+    let (g, wait) = c
+        .as_ref()
+        .wait_while_until(g, deadline, |&mut notified| !notified)
+        .unwrap();
+    assert!(!wait.timed_out());
+    assert!(*g);
+
+    // The condition is checked before the deadline.
+    let (g, wait) = c
+        .as_ref()
+        .wait_while_until(g, Instant::now(), |_| false)
+        .unwrap();
+    assert!(!wait.timed_out());
+    let (_g, wait) = c
+        .as_ref()
+        .wait_while_until(g, Instant::now(), |_| true)
+        .unwrap();
+    assert!(wait.timed_out());
+}
+
 #[test]
 #[should_panic]
 #[cfg_attr(not(unix), ignore)]
@@ -224,3 +274,465 @@ fn two_mutexes() {
     let m = Mutex::boxed(());
     let _ = c.as_ref().wait(m.as_ref().lock().unwrap()).unwrap();
 }
+
+#[test]
+fn notify_many() {
+    const N: usize = 4;
+
+    for fair in [false, true] {
+        // (waiting, tasks, done)
+        let m = Mutex::arc((0, 0, 0));
+        let c = Arc::pin(Condvar::uninit().fair(fair));
+        c.as_ref().init();
+
+        let threads: Vec<_> = (0..N)
+            .map(|_| {
+                let (m, c) = (m.clone(), c.clone());
+                thread::spawn(move || {
+                    let mut g = m.as_ref().lock().unwrap();
+                    g.0 += 1;
+                    let mut g = c.as_ref().wait_while(g, |g| g.1 == 0).unwrap();
+                    g.1 -= 1;
+                    g.2 += 1;
+                })
+            })
+            .collect();
+
+        // Hand out the tasks two at a time, once every thread is waiting.
+        let mut g = m.as_ref().lock().unwrap();
+        while g.0 < N {
+            drop(g);
+            thread::yield_now();
+            g = m.as_ref().lock().unwrap();
+        }
+        for _ in 0..N / 2 {
+            g.1 += 2;
+            c.as_ref().notify_many(2);
+            let done = g.2;
+            while g.2 < done + 2 {
+                drop(g);
+                thread::yield_now();
+                g = m.as_ref().lock().unwrap();
+            }
+        }
+        drop(g);
+        c.as_ref().notify_many(0);
+
+        for t in threads {
+            t.join().unwrap();
+        }
+        assert_eq!(*m.as_ref().lock().unwrap(), (N, 0, N));
+    }
+}
+
+#[test]
+fn wakeup_stats() {
+    let c = Condvar::boxed();
+    assert_eq!(c.as_ref().wakeup_stats(), None);
+
+    let m = Mutex::arc(0);
+    let m2 = m.clone();
+    let c = Arc::pin(Condvar::uninit().track_wakeups(true));
+    c.as_ref().init();
+    let c2 = c.clone();
+
+    let g = m.as_ref().lock().unwrap();
+    let _t = thread::spawn(move || {
+        for _ in 0..3 {
+            *m2.as_ref().lock().unwrap() += 1;
+            c2.as_ref().notify_all();
+        }
+    });
+    let g = c.as_ref().wait_while(g, |count| *count < 3).unwrap();
+    assert_eq!(*g, 3);
+    let stats = c.as_ref().wakeup_stats().unwrap();
+    assert_eq!(stats.waits(), 1);
+    assert!(stats.wakeups() >= 1);
+    assert_eq!(stats.spurious_wakeups(), stats.wakeups() - 1);
+
+    let (g, wait) = c
+        .as_ref()
+        .wait_timeout_while(g, Duration::from_millis(1), |_| true)
+        .unwrap();
+    assert!(wait.timed_out());
+    drop(g);
+    let after = c.as_ref().wakeup_stats().unwrap();
+    assert_eq!(after.waits(), 2);
+    assert_eq!(
+        after.spurious_wakeups() - stats.spurious_wakeups(),
+        after.wakeups() - stats.wakeups()
+    );
+}
+
+#[test]
+fn fair() {
+    const N: usize = 8;
+
+    // The number of waiting threads, and the order in which they woke up.
+    let m = Mutex::arc((0, Vec::new()));
+    let c = Arc::pin(Condvar::uninit().fair(true));
+    c.as_ref().init();
+
+    let mut threads = Vec::new();
+    for i in 0..N {
+        let m2 = m.clone();
+        let c2 = c.clone();
+        threads.push(thread::spawn(move || {
+            let mut g = m2.as_ref().lock().unwrap();
+            g.0 += 1;
+            let mut g = c2.as_ref().wait(g).unwrap();
+            g.1.push(i);
+        }));
+        while m.as_ref().lock().unwrap().0 <= i {
+            thread::yield_now();
+        }
+    }
+
+    for i in 0..N {
+        c.as_ref().notify_one();
+        while m.as_ref().lock().unwrap().1.len() <= i {
+            thread::yield_now();
+        }
+    }
+    for t in threads {
+        t.join().unwrap();
+    }
+    assert_eq!(m.as_ref().lock().unwrap().1, (0..N).collect::<Vec<_>>());
+
+    let (_g, wait) = c
+        .as_ref()
+        .wait_timeout(m.as_ref().lock().unwrap(), Duration::from_millis(1))
+        .unwrap();
+    assert!(wait.timed_out());
+}
+
+#[test]
+fn suspend_aware() {
+    let m = Mutex::arc(false);
+    let c = Arc::pin(Condvar::uninit().suspend_aware(true));
+    c.as_ref().init();
+
+    let start = Instant::now();
+    let (g, wait) = c
+        .as_ref()
+        .wait_timeout(m.as_ref().lock().unwrap(), Duration::from_millis(1500))
+        .unwrap();
+    assert!(wait.timed_out());
+    assert!(start.elapsed() >= Duration::from_millis(1500));
+    drop(g);
+
+    let m2 = m.clone();
+    let c2 = c.clone();
+    let t = thread::spawn(move || {
+        *m2.as_ref().lock().unwrap() = true;
+        c2.as_ref().notify_one();
+    });
+    let (g, wait) = c
+        .as_ref()
+        .wait_timeout_while(
+            m.as_ref().lock().unwrap(),
+            Duration::from_secs(60),
+            |done| !*done,
+        )
+        .unwrap();
+    assert!(!wait.timed_out());
+    assert!(*g);
+    drop(g);
+    t.join().unwrap();
+}
+
+#[test]
+fn notify_on_unlock() {
+    const N: usize = 10;
+
+    let m = Mutex::arc(0);
+    let c = Condvar::arc();
+    let (tx, rx) = channel();
+    for _ in 0..N {
+        let m = m.clone();
+        let c = c.clone();
+        let tx = tx.clone();
+        thread::spawn(move || {
+            let mut cnt = m.as_ref().lock().unwrap();
+            *cnt += 1;
+            if *cnt == N {
+                tx.send(()).unwrap();
+            }
+            while *cnt != 0 {
+                cnt = c.as_ref().wait(cnt).unwrap();
+            }
+            tx.send(()).unwrap();
+        });
+    }
+    drop(tx);
+
+    rx.recv().unwrap();
+    let mut cnt = m.as_ref().lock().unwrap();
+    *cnt = 0;
+    cnt.notify_on_unlock(c.as_ref());
+    cnt.notify_all_on_unlock(c.as_ref());
+    cnt.notify_on_unlock(c.as_ref());
+    drop(cnt);
+
+    for _ in 0..N {
+        rx.recv().unwrap();
+    }
+}
+
+// The futex-like, spin and illumos backends need no initialization.
+#[cfg(all(
+    any(
+        feature = "spin",
+        target_os = "linux",
+        target_os = "android",
+        target_os = "freebsd",
+        target_os = "fuchsia",
+        target_os = "netbsd",
+        target_os = "redox",
+        target_os = "illumos",
+        target_os = "solaris",
+        target_os = "emscripten",
+        all(target_arch = "wasm32", target_feature = "atomics")
+    ),
+    not(any(feature = "parking-lot-core", feature = "thread-park"))
+))]
+#[test]
+fn uninit_futex() {
+    use std::pin::Pin;
+
+    static M: Mutex<bool> = Mutex::uninit(false);
+    static C: Condvar = Condvar::uninit();
+    let (m, c) = (Pin::static_ref(&M), Pin::static_ref(&C));
+
+    let (g, timeout) = c.wait_timeout(m.lock().unwrap(), Duration::from_millis(10)).unwrap();
+    assert!(timeout.timed_out());
+    drop(g);
+
+    let t = thread::spawn(move || {
+        *m.lock().unwrap() = true;
+        c.notify_all();
+    });
+    let g = c.wait_while(m.lock().unwrap(), |ready| !*ready).unwrap();
+    assert!(*g);
+    drop(g);
+    t.join().unwrap();
+}
+
+// Every backend but the generic pthread one and ESP-IDF has a const `new`.
+#[cfg(any(
+    feature = "parking-lot-core",
+    feature = "thread-park",
+    feature = "spin",
+    not(unix),
+    target_os = "linux",
+    target_os = "android",
+    target_os = "freebsd",
+    target_os = "fuchsia",
+    target_os = "netbsd",
+    target_os = "redox",
+    target_os = "illumos",
+    target_os = "solaris",
+    target_os = "emscripten"
+))]
+#[test]
+fn new_static() {
+    use std::pin::Pin;
+
+    static M: Mutex<bool> = Mutex::new(false);
+    static C: Condvar = Condvar::new();
+    let (m, c) = (Pin::static_ref(&M), Pin::static_ref(&C));
+
+    let (g, timeout) = c
+        .wait_timeout(m.lock().unwrap(), Duration::from_millis(10))
+        .unwrap();
+    assert!(timeout.timed_out());
+    drop(g);
+
+    let t = thread::spawn(move || {
+        *m.lock().unwrap() = true;
+        c.notify_all();
+    });
+    let g = c.wait_while(m.lock().unwrap(), |ready| !*ready).unwrap();
+    assert!(*g);
+    drop(g);
+    t.join().unwrap();
+}
+
+#[test]
+fn wait_read() {
+    const N: usize = 4;
+
+    let lock = RwLock::arc(false);
+    let condvar = Condvar::arc();
+    let readers: Vec<_> = (0..N)
+        .map(|_| {
+            let (lock, condvar) = (lock.clone(), condvar.clone());
+            thread::spawn(move || {
+                let mut ready = lock.as_ref().read().unwrap();
+                while !*ready {
+                    ready = condvar.as_ref().wait_read(ready).unwrap();
+                }
+            })
+        })
+        .collect();
+    thread::sleep(Duration::from_millis(20));
+    *lock.as_ref().write().unwrap() = true;
+    condvar.as_ref().notify_all();
+    for reader in readers {
+        reader.join().unwrap();
+    }
+}
+
+#[test]
+fn wait_write() {
+    let lock = RwLock::arc(0);
+    let condvar = Condvar::arc();
+    let thread = {
+        let (lock, condvar) = (lock.clone(), condvar.clone());
+        thread::spawn(move || {
+            for _ in 0..100 {
+                let mut count = lock.as_ref().write().unwrap();
+                *count += 1;
+                condvar.as_ref().notify_one();
+                while *count % 2 == 1 {
+                    count = condvar.as_ref().wait_write(count).unwrap();
+                }
+            }
+        })
+    };
+    for _ in 0..100 {
+        let mut count = lock.as_ref().write().unwrap();
+        while *count % 2 == 0 {
+            count = condvar.as_ref().wait_write(count).unwrap();
+        }
+        *count += 1;
+        condvar.as_ref().notify_one();
+    }
+    thread.join().unwrap();
+    assert_eq!(*lock.as_ref().read().unwrap(), 200);
+}
+
+struct SpinLock(AtomicBool);
+
+struct SpinGuard<'a>(&'a AtomicBool);
+
+impl<'a> SpinGuard<'a> {
+    fn lock(locked: &'a AtomicBool) -> Self {
+        while locked
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            thread::yield_now();
+        }
+        SpinGuard(locked)
+    }
+}
+
+impl Drop for SpinGuard<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Release);
+    }
+}
+
+unsafe impl RawMutex for SpinLock {
+    type Guard<'a> = SpinGuard<'a>;
+
+    const UNINIT: Self = SpinLock(AtomicBool::new(false));
+
+    fn init(self: Pin<&Self>) {}
+
+    fn lock(self: Pin<&Self>) -> SpinGuard<'_> {
+        SpinGuard::lock(&self.get_ref().0)
+    }
+
+    fn try_lock(self: Pin<&Self>) -> Option<SpinGuard<'_>> {
+        let locked = &self.get_ref().0;
+        locked
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .ok()
+            .map(|_| SpinGuard(locked))
+    }
+
+    unsafe fn unlock(self: Pin<&Self>) {
+        self.0.store(false, Ordering::Release);
+    }
+}
+
+// A condition variable which spins until the number of notifications changes.
+struct SpinCondvar(AtomicUsize);
+
+impl SpinCondvar {
+    fn sleep<'a>(&self, guard: SpinGuard<'a>, deadline: Option<Instant>) -> (SpinGuard<'a>, bool) {
+        let notifications = self.0.load(Ordering::SeqCst);
+        let locked = guard.0;
+        drop(guard);
+        let mut timed_out = false;
+        while self.0.load(Ordering::SeqCst) == notifications {
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                timed_out = true;
+                break;
+            }
+            thread::yield_now();
+        }
+        (SpinGuard::lock(locked), timed_out)
+    }
+}
+
+unsafe impl RawCondvar for SpinCondvar {
+    type Mutex = SpinLock;
+
+    const UNINIT: Self = SpinCondvar(AtomicUsize::new(0));
+
+    fn init(self: Pin<&Self>) {}
+
+    fn wait<'a>(self: Pin<&Self>, guard: SpinGuard<'a>) -> SpinGuard<'a>
+    where
+        SpinLock: 'a,
+    {
+        self.sleep(guard, None).0
+    }
+
+    fn wait_timeout<'a>(
+        self: Pin<&Self>,
+        guard: SpinGuard<'a>,
+        dur: Duration,
+    ) -> (SpinGuard<'a>, bool)
+    where
+        SpinLock: 'a,
+    {
+        self.sleep(guard, Some(Instant::now() + dur))
+    }
+
+    fn notify_one(self: Pin<&Self>) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn notify_all(self: Pin<&Self>) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn custom_backend() {
+    let m = Arc::pin(Mutex::<_, SpinLock>::with_backend(false));
+    m.as_ref().init();
+    let c = Arc::pin(Condvar::<SpinCondvar>::with_backend());
+    c.as_ref().init();
+
+    let (m2, c2) = (m.clone(), c.clone());
+    let t = thread::spawn(move || {
+        *m2.as_ref().lock().unwrap() = true;
+        c2.as_ref().notify_one();
+    });
+    let g = m.as_ref().lock().unwrap();
+    let g = c.as_ref().wait_while(g, |done| !*done).unwrap();
+    assert!(*g);
+    t.join().unwrap();
+
+    let (g, result) = c
+        .as_ref()
+        .wait_timeout(g, Duration::from_millis(10))
+        .unwrap();
+    assert!(result.timed_out());
+    drop(g);
+}
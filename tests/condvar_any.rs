@@ -0,0 +1,101 @@
+use pinned_sync::{CondvarAny, FairMutex, Mutex, ReentrantMutex, SpinLock};
+use std::cell::Cell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn mutex() {
+    let mutex = Mutex::arc(0);
+    let condvar = CondvarAny::arc();
+    let thread = {
+        let (mutex, condvar) = (mutex.clone(), condvar.clone());
+        thread::spawn(move || {
+            for _ in 0..100 {
+                *mutex.as_ref().lock().unwrap() += 1;
+                condvar.as_ref().notify_all();
+            }
+        })
+    };
+    let mut count = mutex.as_ref().lock().unwrap();
+    while *count < 100 {
+        count = condvar.as_ref().wait(count).unwrap();
+    }
+    drop(count);
+    thread.join().unwrap();
+}
+
+#[test]
+fn fair_mutex() {
+    let mutex = FairMutex::arc(false);
+    let condvar = CondvarAny::arc();
+    let thread = {
+        let (mutex, condvar) = (mutex.clone(), condvar.clone());
+        thread::spawn(move || {
+            *mutex.as_ref().lock().unwrap() = true;
+            condvar.as_ref().notify_one();
+        })
+    };
+    let mut started = mutex.as_ref().lock().unwrap();
+    while !*started {
+        started = condvar.as_ref().wait(started).unwrap();
+    }
+    drop(started);
+    thread.join().unwrap();
+}
+
+#[test]
+fn reentrant_mutex() {
+    let mutex = ReentrantMutex::arc(Cell::new(false));
+    let condvar = CondvarAny::arc();
+    let thread = {
+        let (mutex, condvar) = (mutex.clone(), condvar.clone());
+        thread::spawn(move || {
+            mutex.as_ref().lock().set(true);
+            condvar.as_ref().notify_one();
+        })
+    };
+    let mut started = mutex.as_ref().lock();
+    while !started.get() {
+        started = condvar.as_ref().wait(started);
+    }
+    drop(started);
+    thread.join().unwrap();
+}
+
+#[test]
+fn spin_lock_timeout() {
+    let lock = SpinLock::boxed(());
+    let condvar = CondvarAny::boxed();
+    let guard = lock.as_ref().lock();
+    let (guard, result) = condvar
+        .as_ref()
+        .wait_timeout(guard, Duration::from_millis(10));
+    assert!(result.timed_out());
+    drop(guard);
+}
+
+#[test]
+fn notify_is_not_lost() {
+    // A notification sent right after the waiter released its lock must
+    // still wake it up.
+    for _ in 0..100 {
+        let lock = SpinLock::arc(());
+        let condvar = CondvarAny::arc();
+        let flag = Arc::new(AtomicBool::new(false));
+        let guard = lock.as_ref().lock();
+        let thread = {
+            let (lock, condvar, flag) = (lock.clone(), condvar.clone(), flag.clone());
+            thread::spawn(move || {
+                let _guard = lock.as_ref().lock();
+                flag.store(true, Ordering::Relaxed);
+                condvar.as_ref().notify_one();
+            })
+        };
+        let guard = condvar.as_ref().wait(guard);
+        drop(guard);
+        thread.join().unwrap();
+        assert!(flag.load(Ordering::Relaxed));
+    }
+}
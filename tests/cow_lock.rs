@@ -0,0 +1,59 @@
+use pinned_sync::CowLock;
+use std::collections::HashMap;
+use std::thread;
+
+#[test]
+fn snapshots() {
+    let lock = CowLock::boxed(1);
+    let before = lock.as_ref().read();
+
+    let mut w = lock.as_ref().write();
+    *w += 1;
+    // Readers are not blocked by the writer, and see the previous value.
+    assert_eq!(*lock.as_ref().read(), 1);
+    drop(w);
+
+    assert_eq!(*before, 1);
+    assert_eq!(*lock.as_ref().read(), 2);
+    assert_eq!(*lock.as_ref().replace(3), 2);
+    assert_eq!(*lock.as_ref().read(), 3);
+}
+
+#[test]
+fn writer_panic() {
+    let lock = CowLock::arc(vec![1]);
+    let lock2 = lock.clone();
+    let r = thread::spawn(move || {
+        let mut w = lock2.as_ref().write();
+        w.push(2);
+        panic!("test panic in inner thread to discard copy");
+    })
+    .join();
+    assert!(r.is_err());
+
+    assert_eq!(*lock.as_ref().read(), [1]);
+    lock.as_ref().write().push(3);
+    assert_eq!(*lock.as_ref().read(), [1, 3]);
+}
+
+#[test]
+fn concurrent_writers() {
+    const N: usize = 8;
+
+    let lock = CowLock::arc(HashMap::new());
+    let threads: Vec<_> = (0..N)
+        .map(|i| {
+            let lock = lock.clone();
+            thread::spawn(move || {
+                for j in 0..100 {
+                    lock.as_ref().write().insert((i, j), ());
+                    assert!(lock.as_ref().read().contains_key(&(i, j)));
+                }
+            })
+        })
+        .collect();
+    for t in threads {
+        t.join().unwrap();
+    }
+    assert_eq!(lock.as_ref().read().len(), N * 100);
+}
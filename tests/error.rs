@@ -0,0 +1,108 @@
+use pinned_sync::{Condvar, LockError, Mutex, PoisonError, TryLockError, WaitTimeoutError};
+use std::error::Error;
+use std::sync;
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn poison_error() {
+    let m = Mutex::arc(1);
+    let m2 = m.clone();
+    let _ = thread::spawn(move || {
+        let _lock = m2.as_ref().lock().unwrap();
+        panic!("test panic in inner thread to poison mutex");
+    })
+    .join();
+
+    let err = match m.as_ref().lock() {
+        Err(err) => err,
+        Ok(_) => panic!("lock of poisoned Mutex is Ok"),
+    };
+    assert_eq!(err.to_string(), "poisoned lock: another task failed inside");
+    assert_eq!(format!("{:?}", err), "PoisonError { .. }");
+    let _: &dyn Error = &err;
+    drop(err);
+
+    match m.as_ref().try_lock() {
+        Err(TryLockError::Poisoned(err)) => assert_eq!(**err.get_ref(), 1),
+        _ => panic!("try_lock of poisoned Mutex is not Poisoned"),
+    };
+}
+
+#[test]
+fn would_block() {
+    let m = Mutex::boxed(());
+    let _g = m.as_ref().lock().unwrap();
+    let err = match m.as_ref().try_lock() {
+        Err(err) => err,
+        Ok(_) => panic!("try_lock of locked Mutex is Ok"),
+    };
+    assert_eq!(
+        err.to_string(),
+        "try_lock failed because the operation would block"
+    );
+    assert_eq!(format!("{:?}", err), "WouldBlock");
+}
+
+#[test]
+fn std_conversions() {
+    let err: sync::PoisonError<i32> = PoisonError::new(1).into();
+    let err: PoisonError<i32> = err.into();
+    assert_eq!(err.into_inner(), 1);
+
+    let err: sync::TryLockError<i32> = TryLockError::Poisoned(PoisonError::new(2)).into();
+    match TryLockError::from(err) {
+        TryLockError::Poisoned(err) => assert_eq!(err.into_inner(), 2),
+        _ => panic!(),
+    }
+
+    let err: sync::TryLockError<i32> = TryLockError::WouldBlock.into();
+    assert!(matches!(TryLockError::from(err), TryLockError::WouldBlock));
+}
+
+#[test]
+fn lock_error() {
+    let err: LockError<i32> = sync::PoisonError::new(1).into();
+    match err {
+        LockError::Poisoned(err) => assert_eq!(err.into_inner(), 1),
+        _ => panic!(),
+    }
+
+    let err = LockError::<()>::Interrupted;
+    assert_eq!(
+        err.to_string(),
+        "waiting for the lock was interrupted by a signal"
+    );
+    assert_eq!(format!("{:?}", err), "Interrupted");
+    let _: &dyn Error = &err;
+}
+
+#[test]
+fn wait_timeout_error() {
+    let m = Mutex::arc(0);
+    let c = Condvar::boxed();
+
+    let result = c
+        .as_ref()
+        .wait_timeout(m.as_ref().lock().unwrap(), Duration::from_millis(1));
+    let err = match WaitTimeoutError::from_result(result) {
+        Err(err) => err,
+        Ok(_) => panic!("wait without notification did not time out"),
+    };
+    assert_eq!(err.to_string(), "wait on a condition variable timed out");
+    assert_eq!(format!("{:?}", err), "TimedOut(..)");
+    assert_eq!(*err.into_inner(), 0);
+
+    let m2 = m.clone();
+    let _ = thread::spawn(move || {
+        let _lock = m2.as_ref().lock().unwrap();
+        panic!("test panic in inner thread to poison mutex");
+    })
+    .join();
+    let guard = m.as_ref().lock().unwrap_or_else(PoisonError::into_inner);
+    let result = c.as_ref().wait_timeout(guard, Duration::from_millis(1));
+    assert!(matches!(
+        WaitTimeoutError::from_result(result),
+        Err(WaitTimeoutError::Poisoned(_))
+    ));
+}
@@ -0,0 +1,78 @@
+use pinned_sync::Event;
+use std::sync::atomic::{AtomicUsize, Ordering::*};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn signal_before_wait() {
+    let event = Event::boxed();
+    assert!(!event.as_ref().try_wait());
+    event.as_ref().signal();
+    // Signaling twice does not count.
+    event.as_ref().signal();
+    event.as_ref().wait();
+    assert!(!event.as_ref().try_wait());
+}
+
+#[test]
+fn wait_timeout() {
+    let event = Event::boxed();
+    assert!(!event.as_ref().wait_timeout(Duration::from_millis(10)));
+    event.as_ref().signal();
+    assert!(event.as_ref().wait_timeout(Duration::from_millis(10)));
+}
+
+#[test]
+fn handoff() {
+    const N: usize = 1000;
+
+    let ping = Event::arc();
+    let pong = Event::arc();
+    let count = Arc::new(AtomicUsize::new(0));
+
+    let (ping2, pong2, count2) = (ping.clone(), pong.clone(), count.clone());
+    let t = thread::spawn(move || {
+        for i in 0..N {
+            ping2.as_ref().wait();
+            assert_eq!(count2.fetch_add(1, Relaxed), 2 * i + 1);
+            pong2.as_ref().signal();
+        }
+    });
+
+    for i in 0..N {
+        assert_eq!(count.fetch_add(1, Relaxed), 2 * i);
+        ping.as_ref().signal();
+        pong.as_ref().wait();
+    }
+    t.join().unwrap();
+    assert_eq!(count.load(Relaxed), 2 * N);
+}
+
+#[test]
+fn wakes_one_waiter_per_signal() {
+    const N: usize = 4;
+
+    let event = Event::arc();
+    let woken = Arc::new(AtomicUsize::new(0));
+    let threads: Vec<_> = (0..N)
+        .map(|_| {
+            let (event, woken) = (event.clone(), woken.clone());
+            thread::spawn(move || {
+                event.as_ref().wait();
+                woken.fetch_add(1, SeqCst);
+            })
+        })
+        .collect();
+
+    for i in 0..N {
+        event.as_ref().signal();
+        while woken.load(SeqCst) == i {
+            thread::yield_now();
+        }
+        assert_eq!(woken.load(SeqCst), i + 1);
+    }
+    for t in threads {
+        t.join().unwrap();
+    }
+}
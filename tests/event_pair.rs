@@ -0,0 +1,43 @@
+use pinned_sync::{EventPair, EventPairSide};
+use std::sync::atomic::{AtomicUsize, Ordering::*};
+use std::sync::Arc;
+use std::thread;
+
+#[test]
+fn signal_before_wait() {
+    let pair = EventPair::boxed();
+    pair.as_ref().signal(EventPairSide::Low);
+    // Signaling twice does not count.
+    pair.as_ref().signal(EventPairSide::Low);
+    pair.as_ref().wait(EventPairSide::High);
+}
+
+#[test]
+fn alternation() {
+    const N: usize = 1000;
+
+    let pair = EventPair::arc();
+    let turn = Arc::new(AtomicUsize::new(0));
+
+    let pair2 = pair.clone();
+    let turn2 = turn.clone();
+    let t = thread::spawn(move || {
+        let pair = pair2.as_ref();
+        pair.wait(EventPairSide::High);
+        for i in 0..N {
+            assert_eq!(turn2.fetch_add(1, Relaxed), 2 * i + 1);
+            if i + 1 < N {
+                pair.signal_and_wait(EventPairSide::High);
+            } else {
+                pair.signal(EventPairSide::High);
+            }
+        }
+    });
+
+    for i in 0..N {
+        assert_eq!(turn.fetch_add(1, Relaxed), 2 * i);
+        pair.as_ref().signal_and_wait(EventPairSide::Low);
+    }
+    t.join().unwrap();
+    assert_eq!(turn.load(Relaxed), 2 * N);
+}
@@ -0,0 +1,99 @@
+use pinned_sync::FairMutex;
+use std::sync::mpsc::channel;
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn smoke() {
+    let m = FairMutex::boxed(());
+    drop(m.as_ref().lock().unwrap());
+    drop(m.as_ref().lock().unwrap());
+    let g = m.as_ref().lock().unwrap();
+    assert!(m.as_ref().try_lock().is_err());
+    drop(g);
+    assert!(m.as_ref().try_lock().is_ok());
+}
+
+#[test]
+fn lots_and_lots() {
+    const J: u32 = 1000;
+    const K: u32 = 3;
+
+    let m = FairMutex::arc(0);
+
+    let (tx, rx) = channel();
+    for _ in 0..K {
+        let tx2 = tx.clone();
+        let m2 = m.clone();
+        thread::spawn(move || {
+            for _ in 0..J {
+                *m2.as_ref().lock().unwrap() += 1;
+            }
+            tx2.send(()).unwrap();
+        });
+        let tx2 = tx.clone();
+        let m2 = m.clone();
+        thread::spawn(move || {
+            for _ in 0..J {
+                *m2.as_ref().lock().unwrap() += 1;
+            }
+            tx2.send(()).unwrap();
+        });
+    }
+
+    drop(tx);
+    for _ in 0..2 * K {
+        rx.recv().unwrap();
+    }
+    assert_eq!(*m.as_ref().lock().unwrap(), J * K * 2);
+}
+
+#[test]
+fn acquired_in_order() {
+    const N: usize = 5;
+
+    let m = FairMutex::arc(Vec::new());
+    let guard = m.as_ref().lock().unwrap();
+
+    // Queue the threads up one after the other while the lock is held.
+    let threads: Vec<_> = (0..N)
+        .map(|i| {
+            let m = m.clone();
+            let t = thread::spawn(move || m.as_ref().lock().unwrap().push(i));
+            thread::sleep(Duration::from_millis(20));
+            t
+        })
+        .collect();
+    // A waiting thread is ahead of `try_lock`.
+    assert!(m.as_ref().try_lock().is_err());
+    drop(guard);
+
+    for t in threads {
+        t.join().unwrap();
+    }
+    assert_eq!(*m.as_ref().lock().unwrap(), (0..N).collect::<Vec<_>>());
+}
+
+#[test]
+fn poison() {
+    let m = FairMutex::arc(1);
+    let m2 = m.clone();
+    let _ = thread::spawn(move || {
+        let _lock = m2.as_ref().lock().unwrap();
+        panic!();
+    })
+    .join();
+    assert!(m.as_ref().is_poisoned());
+    let guard = match m.as_ref().lock() {
+        Err(e) => e.into_inner(),
+        Ok(_) => panic!("expected a poisoned mutex"),
+    };
+    assert_eq!(*guard, 1);
+}
+
+#[test]
+fn into_inner_and_get_mut() {
+    let mut m = FairMutex::uninit(vec![1]);
+    m.get_mut().unwrap().push(2);
+    assert_eq!(m.into_inner().unwrap(), [1, 2]);
+}
@@ -0,0 +1,84 @@
+#![cfg(unix)]
+
+use pinned_sync::{reinit_on_fork, Mutex, RwLock};
+use std::pin::Pin;
+use std::sync::mpsc::channel;
+use std::thread;
+
+// Forks, runs `f` in the child and returns whether it returned `true`. The
+// child must not allocate, as another thread may hold the allocator's lock.
+fn in_child(f: impl FnOnce() -> bool) -> bool {
+    unsafe {
+        let pid = libc::fork();
+        assert!(pid >= 0);
+        if pid == 0 {
+            libc::_exit(if f() { 0 } else { 1 });
+        }
+        let mut status = 0;
+        assert_eq!(libc::waitpid(pid, &mut status, 0), pid);
+        libc::WIFEXITED(status) && libc::WEXITSTATUS(status) == 0
+    }
+}
+
+// Holds the lock in another thread while forking.
+fn forked_while_held<G>(lock: impl FnOnce() -> G + Send, child: impl FnOnce() -> bool) -> bool {
+    let (locked_tx, locked_rx) = channel();
+    let (done_tx, done_rx) = channel::<()>();
+    thread::scope(|s| {
+        s.spawn(move || {
+            let _guard = lock();
+            locked_tx.send(()).unwrap();
+            done_rx.recv().unwrap();
+        });
+        locked_rx.recv().unwrap();
+        let r = in_child(child);
+        done_tx.send(()).unwrap();
+        r
+    })
+}
+
+#[test]
+fn mutex() {
+    static M: Mutex<i32> = Mutex::uninit(0);
+    let m = Pin::static_ref(&M);
+    m.init();
+    unsafe { reinit_on_fork(m) };
+
+    assert!(forked_while_held(
+        || m.lock(),
+        || m.is_poisoned() && m.try_lock().is_err() && m.lock().is_err()
+    ));
+    assert!(!m.is_poisoned());
+    assert!(in_child(|| !m.is_poisoned() && m.try_lock().is_ok()));
+}
+
+#[test]
+fn rwlock() {
+    static L: RwLock<i32> = RwLock::uninit(0);
+    let l = Pin::static_ref(&L);
+    l.init();
+    unsafe { reinit_on_fork(l) };
+
+    assert!(forked_while_held(
+        || l.write(),
+        || l.is_poisoned() && l.write().is_err()
+    ));
+    assert!(forked_while_held(
+        || l.read(),
+        || !l.is_poisoned() && l.write().is_ok()
+    ));
+    assert!(!l.is_poisoned());
+}
+
+#[test]
+fn reinit_after_fork() {
+    let m = Mutex::boxed(0);
+    let m = m.as_ref();
+    assert!(forked_while_held(
+        || m.lock(),
+        || {
+            unsafe { m.reinit_after_fork() };
+            m.try_lock().is_err() && m.is_poisoned()
+        }
+    ));
+}
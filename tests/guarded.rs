@@ -0,0 +1,81 @@
+use pinned_sync::{GuardedBy, RawPinnedMutex};
+use std::thread;
+
+#[test]
+fn smoke() {
+    let m = RawPinnedMutex::boxed();
+    drop(m.as_ref().lock().unwrap());
+    drop(m.as_ref().lock().unwrap());
+}
+
+#[test]
+fn try_lock() {
+    let m = RawPinnedMutex::boxed();
+    let g = m.as_ref().try_lock().unwrap();
+    assert!(m.as_ref().try_lock().is_err());
+    drop(g);
+    assert!(m.as_ref().try_lock().is_ok());
+}
+
+#[test]
+fn struct_of_arrays() {
+    let m = RawPinnedMutex::boxed();
+    let xs = GuardedBy::new(m.as_ref(), vec![1, 2, 3]);
+    let ys = GuardedBy::new(m.as_ref(), vec![4, 5, 6]);
+
+    let mut guard = m.as_ref().lock().unwrap();
+    xs.get_mut(&mut guard).push(4);
+    ys.get_mut(&mut guard).push(7);
+    assert_eq!(xs.get(&guard).len(), ys.get(&guard).len());
+    drop(guard);
+
+    assert_eq!(xs.into_inner(), [1, 2, 3, 4]);
+    assert_eq!(ys.into_inner(), [4, 5, 6, 7]);
+}
+
+#[test]
+#[should_panic]
+fn wrong_guard() {
+    let m = RawPinnedMutex::boxed();
+    let m2 = RawPinnedMutex::boxed();
+    let data = GuardedBy::new(m.as_ref(), 0);
+    let guard = m2.as_ref().lock().unwrap();
+    let _ = data.get(&guard);
+}
+
+#[test]
+fn lots_and_lots() {
+    const J: u32 = 1000;
+    const K: u32 = 3;
+
+    let m = RawPinnedMutex::boxed();
+    let count = GuardedBy::new(m.as_ref(), 0);
+
+    thread::scope(|s| {
+        for _ in 0..K {
+            s.spawn(|| {
+                for _ in 0..J {
+                    let mut guard = m.as_ref().lock().unwrap();
+                    *count.get_mut(&mut guard) += 1;
+                }
+            });
+        }
+    });
+
+    assert_eq!(count.into_inner(), J * K);
+}
+
+#[test]
+fn poison() {
+    let m = RawPinnedMutex::arc();
+    let m2 = m.clone();
+    let _ = thread::spawn(move || {
+        let _lock = m2.as_ref().lock().unwrap();
+        panic!("test panic in inner thread to poison mutex");
+    })
+    .join();
+
+    assert!(m.as_ref().is_poisoned());
+    assert!(m.as_ref().lock().is_err());
+    assert!(m.as_ref().try_lock().is_err());
+}
@@ -0,0 +1,84 @@
+#![cfg(all(
+    any(target_os = "linux", target_os = "android"),
+    not(any(feature = "parking-lot-core", feature = "thread-park", feature = "spin"))
+))]
+
+use pinned_sync::{Condvar, LockError, Mutex};
+use std::mem;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::channel;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+extern "C" fn handler(_: libc::c_int) {}
+
+// Runs `f` in another thread, and sends it `SIGUSR1` until `f` returns, with
+// a handler installed without `SA_RESTART` so that its waits are interrupted.
+fn interrupted<R: Send + 'static>(f: impl FnOnce() -> R + Send + 'static) -> R {
+    unsafe {
+        let mut action: libc::sigaction = mem::zeroed();
+        action.sa_sigaction = handler as extern "C" fn(libc::c_int) as libc::sighandler_t;
+        assert_eq!(libc::sigaction(libc::SIGUSR1, &action, ptr::null_mut()), 0);
+    }
+
+    let done = Arc::new(AtomicBool::new(false));
+    let (tid_tx, tid_rx) = channel();
+    let (exit_tx, exit_rx) = channel::<()>();
+    let t = thread::spawn({
+        let done = done.clone();
+        move || {
+            tid_tx.send(unsafe { libc::pthread_self() }).unwrap();
+            let result = f();
+            done.store(true, Ordering::SeqCst);
+            // Stay alive until the signals stop, so that none is sent to a
+            // thread which exited.
+            exit_rx.recv().unwrap();
+            result
+        }
+    });
+
+    let tid = tid_rx.recv().unwrap();
+    // The first signals may arrive before the thread is blocked.
+    while !done.load(Ordering::SeqCst) {
+        unsafe {
+            assert_eq!(libc::pthread_kill(tid, libc::SIGUSR1), 0);
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    exit_tx.send(()).unwrap();
+    t.join().unwrap()
+}
+
+#[test]
+fn lock_interruptible() {
+    let m = Mutex::arc(0);
+    let g = m.as_ref().lock().unwrap();
+
+    let m2 = m.clone();
+    let interrupted = interrupted(move || {
+        matches!(m2.as_ref().lock_interruptible(), Err(LockError::Interrupted))
+    });
+    assert!(interrupted);
+
+    drop(g);
+    *m.as_ref().lock_interruptible().unwrap() += 1;
+    assert_eq!(*m.as_ref().lock().unwrap(), 1);
+}
+
+#[test]
+fn wait_interruptible() {
+    let m = Mutex::arc(0);
+    let c = Condvar::arc();
+
+    let (m2, c2) = (m.clone(), c.clone());
+    let interrupted = interrupted(move || {
+        let g = m2.as_ref().lock().unwrap();
+        matches!(c2.as_ref().wait_interruptible(g), Err(LockError::Interrupted))
+    });
+    assert!(interrupted);
+
+    // The mutex was unlocked when the wait was given up.
+    assert!(m.as_ref().try_lock().is_ok());
+}
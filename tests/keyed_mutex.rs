@@ -0,0 +1,53 @@
+use pinned_sync::KeyedMutex;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+#[test]
+fn smoke() {
+    let m = KeyedMutex::boxed();
+    let a = m.as_ref().lock(1);
+    assert_eq!(*a.key(), 1);
+    assert!(m.as_ref().is_locked(&1));
+    assert!(!m.as_ref().is_locked(&2));
+    assert!(m.as_ref().try_lock(1).is_none());
+    drop(m.as_ref().lock(2));
+    drop(a);
+    assert!(!m.as_ref().is_locked(&1));
+    assert!(m.as_ref().try_lock(1).is_some());
+}
+
+#[test]
+fn one_per_key() {
+    const N: usize = 8;
+    const J: usize = 100;
+    const KEYS: usize = 3;
+
+    let m = KeyedMutex::arc();
+    let inside: Arc<Vec<AtomicBool>> = Arc::new((0..KEYS).map(|_| AtomicBool::new(false)).collect());
+    let count = Arc::new(AtomicUsize::new(0));
+    let threads: Vec<_> = (0..N)
+        .map(|t| {
+            let m = m.clone();
+            let inside = inside.clone();
+            let count = count.clone();
+            thread::spawn(move || {
+                for j in 0..J {
+                    let key = (t + j) % KEYS;
+                    let _guard = m.as_ref().lock(key);
+                    assert!(!inside[key].swap(true, Ordering::SeqCst));
+                    thread::yield_now();
+                    inside[key].store(false, Ordering::SeqCst);
+                    count.fetch_add(1, Ordering::SeqCst);
+                }
+            })
+        })
+        .collect();
+    for t in threads {
+        t.join().unwrap();
+    }
+    assert_eq!(count.load(Ordering::SeqCst), N * J);
+    for key in 0..KEYS {
+        assert!(!m.as_ref().is_locked(&key));
+    }
+}
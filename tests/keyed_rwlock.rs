@@ -0,0 +1,62 @@
+use pinned_sync::KeyedRwLock;
+use std::sync::atomic::{AtomicIsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+#[test]
+fn smoke() {
+    let l = KeyedRwLock::boxed();
+    let r1 = l.as_ref().read(1);
+    let r2 = l.as_ref().try_read(1).unwrap();
+    assert_eq!(*r2.key(), 1);
+    assert!(l.as_ref().try_write(1).is_none());
+    assert!(l.as_ref().is_locked(&1));
+    drop(l.as_ref().write(2));
+    drop((r1, r2));
+    assert!(!l.as_ref().is_locked(&1));
+
+    let w = l.as_ref().write(1);
+    assert!(l.as_ref().try_read(1).is_none());
+    assert!(l.as_ref().try_write(1).is_none());
+    drop(w);
+    assert!(!l.as_ref().is_locked(&1));
+}
+
+#[test]
+fn frob() {
+    const N: usize = 8;
+    const J: usize = 200;
+    const KEYS: usize = 3;
+
+    let l = KeyedRwLock::arc();
+    // Number of readers per key, or -1 while a writer holds it.
+    let states: Arc<Vec<AtomicIsize>> = Arc::new((0..KEYS).map(|_| AtomicIsize::new(0)).collect());
+    let threads: Vec<_> = (0..N)
+        .map(|t| {
+            let l = l.clone();
+            let states = states.clone();
+            thread::spawn(move || {
+                for j in 0..J {
+                    let key = (t + j) % KEYS;
+                    if (t + j) % 4 == 0 {
+                        let _w = l.as_ref().write(key);
+                        assert_eq!(states[key].swap(-1, Ordering::SeqCst), 0);
+                        thread::yield_now();
+                        assert_eq!(states[key].swap(0, Ordering::SeqCst), -1);
+                    } else {
+                        let _r = l.as_ref().read(key);
+                        assert!(states[key].fetch_add(1, Ordering::SeqCst) >= 0);
+                        thread::yield_now();
+                        states[key].fetch_sub(1, Ordering::SeqCst);
+                    }
+                }
+            })
+        })
+        .collect();
+    for t in threads {
+        t.join().unwrap();
+    }
+    for key in 0..KEYS {
+        assert!(!l.as_ref().is_locked(&key));
+    }
+}
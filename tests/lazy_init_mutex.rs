@@ -0,0 +1,72 @@
+use pinned_sync::{Condvar, LazyInitMutex};
+use std::pin::Pin;
+use std::sync::Barrier;
+use std::thread;
+
+#[test]
+fn static_without_init() {
+    static M: LazyInitMutex<Vec<i32>> = LazyInitMutex::new(Vec::new());
+    let m = Pin::static_ref(&M);
+    m.lock().unwrap().push(1);
+    assert_eq!(*m.try_lock().unwrap(), [1]);
+}
+
+#[test]
+fn concurrent_first_lock() {
+    static M: LazyInitMutex<i32> = LazyInitMutex::new(0);
+    let barrier = Barrier::new(8);
+    thread::scope(|s| {
+        for _ in 0..8 {
+            s.spawn(|| {
+                barrier.wait();
+                *Pin::static_ref(&M).lock().unwrap() += 1;
+            });
+        }
+    });
+    assert_eq!(*Pin::static_ref(&M).lock().unwrap(), 8);
+}
+
+#[test]
+fn poison() {
+    let m = LazyInitMutex::arc(0);
+    let m2 = m.clone();
+    let _ = thread::spawn(move || {
+        let _guard = m2.as_ref().lock().unwrap();
+        panic!("test panic while holding the lock");
+    })
+    .join();
+    assert!(m.as_ref().is_poisoned());
+    assert!(m.as_ref().lock().is_err());
+    m.as_ref().clear_poison();
+    assert!(m.as_ref().lock().is_ok());
+}
+
+#[test]
+fn unused_into_inner_get_mut() {
+    let mut m = LazyInitMutex::new(vec![1]);
+    m.get_mut().unwrap().push(2);
+    assert_eq!(m.into_inner().unwrap(), [1, 2]);
+    drop(LazyInitMutex::boxed(()));
+}
+
+#[test]
+fn condvar() {
+    let pair = Box::pin((LazyInitMutex::new(false), Condvar::uninit()));
+    let (m, c) = unsafe {
+        (
+            pair.as_ref().map_unchecked(|pair| &pair.0),
+            pair.as_ref().map_unchecked(|pair| &pair.1),
+        )
+    };
+    c.init();
+    thread::scope(|s| {
+        s.spawn(move || {
+            *m.lock().unwrap() = true;
+            c.notify_one();
+        });
+        let mut started = m.lock().unwrap();
+        while !*started {
+            started = c.wait(started).unwrap();
+        }
+    });
+}
@@ -0,0 +1,70 @@
+use pinned_sync::LeftRight;
+use std::panic::{self, AssertUnwindSafe};
+use std::thread;
+
+#[test]
+fn smoke() {
+    let lock = LeftRight::boxed(0);
+    let r = lock.as_ref().read();
+    assert_eq!(*r, 0);
+    drop(r);
+    lock.as_ref().write(|x| *x += 1);
+    lock.as_ref().write(|x| *x += 1);
+    assert_eq!(*lock.as_ref().read(), 2);
+}
+
+#[test]
+fn into_inner() {
+    let mut lock = LeftRight::uninit(vec![1]);
+    lock.get_mut().push(2);
+    assert_eq!(lock.into_inner(), [1, 2]);
+}
+
+#[test]
+fn write_panic() {
+    let lock = LeftRight::boxed(vec![1]);
+    let r = panic::catch_unwind(AssertUnwindSafe(|| {
+        lock.as_ref().write(|v| {
+            v.push(2);
+            panic!("test panic in write operation");
+        })
+    }));
+    assert!(r.is_err());
+    assert_eq!(*lock.as_ref().read(), [1]);
+    lock.as_ref().write(|v| v.push(3));
+    assert_eq!(*lock.as_ref().read(), [1, 3]);
+    lock.as_ref().write(|v| v.push(4));
+    assert_eq!(*lock.as_ref().read(), [1, 3, 4]);
+}
+
+#[test]
+fn readers_and_writers() {
+    const N: usize = 4;
+    const M: u64 = 1000;
+
+    let lock = LeftRight::arc((0u64, 0u64));
+    let readers: Vec<_> = (0..N)
+        .map(|_| {
+            let lock = lock.clone();
+            thread::spawn(move || {
+                let mut last = 0;
+                while last < M {
+                    let r = lock.as_ref().read();
+                    // The writer keeps both fields equal.
+                    assert_eq!(r.0, r.1);
+                    assert!(r.0 >= last);
+                    last = r.0;
+                }
+            })
+        })
+        .collect();
+    for _ in 0..M {
+        lock.as_ref().write(|(a, b)| {
+            *a += 1;
+            *b += 1;
+        });
+    }
+    for t in readers {
+        t.join().unwrap();
+    }
+}
@@ -0,0 +1,77 @@
+#![cfg(feature = "profiling")]
+
+use pinned_sync::{LockEventKind, LockTrace, Mutex, RwLock};
+use std::sync::mpsc::channel;
+use std::thread;
+use std::time::Duration;
+
+// The recording is global, so this is the only test in this file.
+#[test]
+fn test_lock_trace() {
+    let m = Mutex::arc(0);
+    let l = RwLock::boxed(0);
+    let untraced = Mutex::boxed(0);
+    let _untraced = untraced.as_ref().lock().unwrap();
+
+    LockTrace::start();
+
+    let guard = m.as_ref().lock().unwrap();
+    let (tx, rx) = channel();
+    let m2 = m.clone();
+    let handle = thread::Builder::new()
+        .name("contender \"1\"".to_owned())
+        .spawn(move || {
+            tx.send(()).unwrap();
+            *m2.as_ref().lock().unwrap() += 1;
+        })
+        .unwrap();
+    rx.recv().unwrap();
+    thread::sleep(Duration::from_millis(50));
+    drop(guard);
+    handle.join().unwrap();
+
+    drop(l.as_ref().read().unwrap());
+    drop(l.as_ref().try_write().unwrap());
+
+    let trace = LockTrace::stop();
+    drop(m.as_ref().lock().unwrap());
+
+    let events: Vec<_> = trace
+        .events()
+        .iter()
+        .filter(|e| e.lock() == m.as_ref().id())
+        .collect();
+    let holds = events.iter().filter(|e| e.kind() == LockEventKind::Hold).count();
+    assert_eq!(holds, 2);
+    let waits: Vec<_> = events
+        .iter()
+        .filter(|e| e.kind() == LockEventKind::Wait)
+        .collect();
+    assert_eq!(waits.len(), 2);
+    assert!(waits.iter().any(|e| e.duration() >= Duration::from_millis(40)));
+    assert!(events.iter().all(|e| e.primitive() == "Mutex"));
+    assert_ne!(waits[0].thread(), waits[1].thread());
+
+    let primitives: Vec<_> = trace
+        .events()
+        .iter()
+        .filter(|e| e.lock() == l.as_ref().id())
+        .map(|e| (e.primitive(), e.kind()))
+        .collect();
+    assert!(primitives.contains(&("RwLock::read", LockEventKind::Hold)));
+    assert!(primitives.contains(&("RwLock::write", LockEventKind::Hold)));
+    assert!(!primitives.contains(&("RwLock::write", LockEventKind::Wait)));
+    assert!(trace
+        .events()
+        .iter()
+        .all(|e| e.lock() != untraced.as_ref().id()));
+
+    let mut json = Vec::new();
+    trace.write_chrome_trace(&mut json).unwrap();
+    let json = String::from_utf8(json).unwrap();
+    assert!(json.starts_with("{\"traceEvents\":["));
+    assert!(json.contains("\"name\":\"hold Mutex\""));
+    assert!(json.contains("\"name\":\"wait RwLock::read\""));
+    assert!(json.contains("contender \\\"1\\\""));
+    assert!(json.contains(&format!("\"lock\":\"{:p}\"", *m)));
+}
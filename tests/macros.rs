@@ -0,0 +1,55 @@
+use pinned_sync::{lock, Mutex};
+use std::thread;
+
+#[test]
+fn lock_smoke() {
+    let m = Mutex::boxed(0);
+    lock!(m => data {
+        *data += 1;
+    });
+    assert_eq!(lock!(m => data { *data }), 1);
+    assert!(m.as_ref().try_lock().is_ok());
+}
+
+#[test]
+fn lock_pin_ref() {
+    let m = Mutex::boxed(vec![1, 2]);
+    let m = m.as_ref();
+    let len = lock!(m => data {
+        data.push(3);
+        data.len()
+    });
+    assert_eq!(len, 3);
+}
+
+#[test]
+#[should_panic]
+fn lock_poison_panic() {
+    let m = Mutex::arc(1);
+    let m2 = m.clone();
+    let _ = thread::spawn(move || {
+        let _lock = m2.as_ref().lock().unwrap();
+        panic!("test panic in inner thread to poison mutex");
+    })
+    .join();
+
+    lock!(m => data { *data += 1 });
+}
+
+#[test]
+fn lock_poison_ignore() {
+    let m = Mutex::arc(1);
+    let m2 = m.clone();
+    let _ = thread::spawn(move || {
+        let _lock = m2.as_ref().lock().unwrap();
+        panic!("test panic in inner thread to poison mutex");
+    })
+    .join();
+
+    assert!(m.as_ref().is_poisoned());
+    let value = lock!(m => data, poison = ignore {
+        *data += 1;
+        *data
+    });
+    assert_eq!(value, 2);
+}
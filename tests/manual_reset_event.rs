@@ -0,0 +1,54 @@
+use pinned_sync::ManualResetEvent;
+use std::sync::atomic::{AtomicUsize, Ordering::*};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn stays_set() {
+    let event = ManualResetEvent::boxed();
+    assert!(!event.is_set());
+    event.as_ref().set();
+    event.as_ref().wait();
+    event.as_ref().wait();
+    assert!(event.is_set());
+
+    event.reset();
+    assert!(!event.is_set());
+    assert!(!event.as_ref().wait_timeout(Duration::from_millis(10)));
+}
+
+#[test]
+fn wakes_every_waiter() {
+    const N: usize = 8;
+
+    let event = ManualResetEvent::arc();
+    let started = Arc::new(AtomicUsize::new(0));
+    let threads: Vec<_> = (0..N)
+        .map(|_| {
+            let (event, started) = (event.clone(), started.clone());
+            thread::spawn(move || {
+                started.fetch_add(1, SeqCst);
+                event.as_ref().wait();
+            })
+        })
+        .collect();
+
+    while started.load(SeqCst) < N {
+        thread::yield_now();
+    }
+    event.as_ref().set();
+    for t in threads {
+        t.join().unwrap();
+    }
+}
+
+#[test]
+fn wait_timeout() {
+    let event = ManualResetEvent::arc();
+    let event2 = event.clone();
+    let t = thread::spawn(move || event2.as_ref().wait_timeout(Duration::from_secs(60)));
+    thread::sleep(Duration::from_millis(10));
+    event.as_ref().set();
+    assert!(t.join().unwrap());
+}
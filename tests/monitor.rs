@@ -0,0 +1,91 @@
+use pinned_sync::{Condvar, Monitor, Mutex};
+use std::panic;
+use std::sync::mpsc::channel;
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn notify_one() {
+    let m = Monitor::arc(false);
+    let m2 = m.clone();
+    let (tx, rx) = channel();
+    let _t = thread::spawn(move || {
+        // wait until parent gets in
+        rx.recv().unwrap();
+        *m2.as_ref().lock().unwrap() = true;
+        m2.as_ref().notify_one();
+    });
+
+    let mut lock = m.as_ref().lock().unwrap();
+    tx.send(()).unwrap();
+    assert!(!*lock);
+    while !*lock {
+        lock = m.as_ref().wait(lock).unwrap();
+    }
+}
+
+#[test]
+fn notify_all() {
+    const N: usize = 10;
+
+    // The number of waiting threads, and whether they may go on.
+    let m = Monitor::arc((0, false));
+    let threads: Vec<_> = (0..N)
+        .map(|_| {
+            let m = m.clone();
+            thread::spawn(move || {
+                let mut state = m.as_ref().lock().unwrap();
+                state.0 += 1;
+                m.as_ref().notify_all();
+                let state = m.as_ref().wait_while(state, |state| !state.1).unwrap();
+                assert!(state.1);
+            })
+        })
+        .collect();
+
+    let state = m.as_ref().lock().unwrap();
+    let mut state = m.as_ref().wait_while(state, |state| state.0 < N).unwrap();
+    state.1 = true;
+    drop(state);
+    m.as_ref().notify_all();
+    for t in threads {
+        t.join().unwrap();
+    }
+}
+
+#[test]
+fn wait_timeout() {
+    let m = Monitor::boxed(());
+    let (_guard, result) = m
+        .as_ref()
+        .wait_timeout(m.as_ref().lock().unwrap(), Duration::from_millis(1))
+        .unwrap();
+    assert!(result.timed_out());
+}
+
+#[test]
+fn from_parts() {
+    let m = Box::pin(Monitor::from_parts(
+        Mutex::uninit(0).biased(true),
+        Condvar::uninit().fair(true),
+    ));
+    m.as_ref().init();
+    let (guard, result) = m
+        .as_ref()
+        .wait_timeout_while(m.as_ref().lock().unwrap(), Duration::from_millis(1), |v| {
+            *v == 0
+        })
+        .unwrap();
+    assert!(result.timed_out());
+    assert_eq!(*guard, 0);
+}
+
+#[test]
+fn wait_with_another_mutex() {
+    let m = Monitor::boxed(0);
+    let other = Mutex::boxed(0);
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+        let _ = m.as_ref().wait(other.as_ref().lock().unwrap());
+    }));
+    assert!(result.is_err());
+}
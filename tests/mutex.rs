@@ -1,9 +1,12 @@
-use pinned_sync::{Condvar, Mutex};
+use pinned_sync::raw::RawMutex;
+use pinned_sync::{ArcMutexGuard, Condvar, Mutex, MutexGuard, TryLockError};
+use std::panic;
 use std::pin::Pin;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::mpsc::channel;
 use std::sync::Arc;
 use std::thread;
+use std::time::{Duration, Instant};
 
 struct Packet<T>(Pin<Arc<(Mutex<T>, Condvar)>>);
 impl<T> Packet<T> {
@@ -68,6 +71,48 @@ fn try_lock() {
     *m.as_ref().try_lock().unwrap() = ();
 }
 
+#[test]
+fn try_lock_for() {
+    let m = Mutex::arc(0);
+    let guard = m.as_ref().lock().unwrap();
+
+    let m2 = m.clone();
+    let start = Instant::now();
+    thread::spawn(
+        move || match m2.as_ref().try_lock_for(Duration::from_millis(50)) {
+            Err(TryLockError::WouldBlock) => {}
+            _ => panic!("acquired a locked mutex"),
+        },
+    )
+    .join()
+    .unwrap();
+    assert!(start.elapsed() >= Duration::from_millis(50));
+
+    let m2 = m.clone();
+    let t = thread::spawn(move || {
+        *m2.as_ref().try_lock_for(Duration::from_secs(60)).unwrap() += 1;
+    });
+    thread::sleep(Duration::from_millis(10));
+    drop(guard);
+    t.join().unwrap();
+    assert_eq!(*m.as_ref().try_lock_for(Duration::MAX).unwrap(), 1);
+}
+
+#[test]
+fn try_lock_until_poison() {
+    let m = Mutex::arc(());
+    let m2 = m.clone();
+    let _ = thread::spawn(move || {
+        let _lock = m2.as_ref().lock().unwrap();
+        panic!("test panic in inner thread to poison mutex");
+    })
+    .join();
+    match m.as_ref().try_lock_until(Instant::now()) {
+        Err(TryLockError::Poisoned(_)) => {}
+        _ => panic!("acquired a poisoned mutex without an error"),
+    };
+}
+
 #[test]
 fn test_into_inner() {
     let m = Mutex::boxed(NonCopy(10));
@@ -119,6 +164,44 @@ fn test_get_mut() {
     assert_eq!(unsafe { Pin::into_inner_unchecked(m) }.into_inner().unwrap(), NonCopy(20));
 }
 
+#[test]
+fn test_get_pin_mut() {
+    let mut m = Mutex::boxed(NonCopy(10));
+    *m.as_mut().get_pin_mut().unwrap() = NonCopy(20);
+    assert_eq!(*m.as_ref().lock().unwrap(), NonCopy(20));
+}
+
+#[test]
+fn test_into_inner_boxed() {
+    let m = Mutex::boxed(NonCopy(10));
+    assert_eq!(Mutex::into_inner_boxed(m).unwrap(), NonCopy(10));
+
+    let m = Mutex::boxed(NonCopy(10));
+    let _ = panic::catch_unwind(|| {
+        let _lock = m.as_ref().lock().unwrap();
+        panic!("test panic to poison the lock");
+    });
+    match Mutex::into_inner_boxed(m) {
+        Err(e) => assert_eq!(e.into_inner(), NonCopy(10)),
+        Ok(x) => panic!("into_inner_boxed of poisoned Mutex is Ok: {:?}", x),
+    }
+}
+
+#[test]
+fn test_try_into_inner_arc() {
+    let data = Arc::new(());
+    let m = Mutex::arc(data.clone());
+    let m2 = m.clone();
+    let m = Mutex::try_into_inner_arc(m).unwrap_err();
+    drop(m2);
+    let inner = Mutex::try_into_inner_arc(m)
+        .unwrap_or_else(|_| panic!())
+        .unwrap();
+    assert_eq!(Arc::strong_count(&data), 2);
+    drop(inner);
+    assert_eq!(Arc::strong_count(&data), 1);
+}
+
 #[test]
 fn test_get_mut_poison() {
     let m = Mutex::arc(NonCopy(10));
@@ -261,3 +344,578 @@ fn test_mutex_unsized() {
     let comp: &[i32] = &[4, 2, 5];
     assert_eq!(&*mutex.as_ref().lock().unwrap(), comp);
 }
+
+#[test]
+fn test_mutex_poisoning_disabled() {
+    let arc = Arc::pin(Mutex::uninit(1).poisoning(false));
+    arc.as_ref().init();
+    let arc2 = arc.clone();
+    let _ = thread::spawn(move || {
+        let _lock = arc2.as_ref().lock().unwrap();
+        panic!("test panic in inner thread to poison mutex");
+    })
+    .join();
+    assert!(!arc.as_ref().is_poisoned());
+    assert_eq!(*arc.as_ref().lock().unwrap(), 1);
+    assert!(arc.as_ref().try_lock().is_ok());
+}
+
+#[test]
+fn test_mutex_biased() {
+    const J: u32 = 1000;
+
+    let m = Arc::pin(Mutex::uninit(0).biased(true));
+    m.as_ref().init();
+    for _ in 0..J {
+        *m.as_ref().lock().unwrap() += 1;
+    }
+    assert!(m.as_ref().try_lock().is_ok());
+
+    let held = m.as_ref().lock().unwrap();
+    let m2 = m.clone();
+    let t = thread::spawn(move || {
+        for _ in 0..J {
+            *m2.as_ref().lock().unwrap() += 1;
+        }
+    });
+    drop(held);
+    for _ in 0..J {
+        *m.as_ref().lock().unwrap() += 1;
+    }
+    t.join().unwrap();
+    assert_eq!(*m.as_ref().lock().unwrap(), 3 * J);
+}
+
+#[test]
+fn test_mutex_biased_condvar() {
+    let packet = Packet(Arc::pin((Mutex::uninit(false).biased(true), Condvar::uninit())));
+    packet.mutex().init();
+    packet.condvar().init();
+    let packet2 = Packet(packet.0.clone());
+
+    let mut lock = packet.mutex().lock().unwrap();
+    let _t = thread::spawn(move || {
+        *packet2.mutex().lock().unwrap() = true;
+        packet2.condvar().notify_one();
+    });
+    while !*lock {
+        lock = packet.condvar().wait(lock).unwrap();
+    }
+}
+
+// Biased locking is disabled where guards are `Send`.
+#[cfg(not(feature = "send_guard"))]
+#[test]
+fn test_mutex_biased_owner_keeps_bias() {
+    let m = Arc::pin(Mutex::uninit(0).biased(true));
+    m.as_ref().init();
+    let c = Condvar::boxed();
+
+    // Waiting and bumping take the real lock, but do not revoke the bias.
+    let g = m.as_ref().lock().unwrap();
+    let (g, _) = c.as_ref().wait_timeout(g, Duration::from_millis(1)).unwrap();
+    // The owner holds the real lock, so it still can not lock it again.
+    let relock = panic::catch_unwind(panic::AssertUnwindSafe(|| drop(m.as_ref().lock())));
+    assert!(relock.is_err());
+    let g = MutexGuard::bump(g).unwrap();
+    drop(g);
+
+    // Attempts of other threads fail until one of them revokes the bias.
+    let m2 = m.clone();
+    assert!(thread::spawn(move || m2.as_ref().try_lock().is_err())
+        .join()
+        .unwrap());
+    let m2 = m.clone();
+    thread::spawn(move || *m2.as_ref().lock().unwrap() += 1)
+        .join()
+        .unwrap();
+    let m2 = m.clone();
+    assert!(thread::spawn(move || m2.as_ref().try_lock().is_ok())
+        .join()
+        .unwrap());
+    assert_eq!(*m.as_ref().lock().unwrap(), 1);
+}
+
+#[test]
+fn test_mutex_handoff() {
+    const J: u32 = 1000;
+    const K: u32 = 3;
+
+    let m = Arc::pin(Mutex::uninit(0).handoff(true));
+    m.as_ref().init();
+    let (tx, rx) = channel();
+    for _ in 0..K {
+        let m = m.clone();
+        let tx = tx.clone();
+        thread::spawn(move || {
+            for _ in 0..J {
+                *m.as_ref().lock().unwrap() += 1;
+            }
+            tx.send(()).unwrap();
+        });
+    }
+    drop(tx);
+    for _ in 0..K {
+        rx.recv().unwrap();
+    }
+    assert_eq!(*m.as_ref().lock().unwrap(), J * K);
+}
+
+#[cfg(all(
+    feature = "send_guard",
+    any(
+        feature = "parking-lot-core",
+        feature = "thread-park",
+        feature = "spin",
+        not(any(unix, windows)),
+        target_os = "linux",
+        target_os = "android",
+        target_os = "freebsd",
+        target_os = "fuchsia",
+        target_os = "netbsd",
+        target_os = "redox",
+        target_os = "emscripten",
+        all(target_arch = "wasm32", target_feature = "atomics"),
+    )
+))]
+#[test]
+fn test_mutex_send_guard() {
+    let m = Mutex::boxed(0);
+    let guard = m.as_ref().lock().unwrap();
+    thread::scope(|s| {
+        s.spawn(move || {
+            let mut guard = guard;
+            *guard += 1;
+        });
+    });
+    assert_eq!(*m.as_ref().lock().unwrap(), 1);
+}
+
+#[cfg(all(
+    feature = "send_guard",
+    any(
+        feature = "parking-lot-core",
+        feature = "thread-park",
+        feature = "spin",
+        not(any(unix, windows)),
+        target_os = "linux",
+        target_os = "android",
+        target_os = "freebsd",
+        target_os = "fuchsia",
+        target_os = "netbsd",
+        target_os = "redox",
+        target_os = "emscripten",
+        all(target_arch = "wasm32", target_feature = "atomics"),
+    )
+))]
+#[test]
+fn test_mutex_biased_send_guard() {
+    let m = Arc::pin(Mutex::uninit(0).biased(true));
+    m.as_ref().init();
+    let guard = m.as_ref().lock().unwrap();
+    thread::scope(|s| {
+        s.spawn(move || {
+            let mut guard = guard;
+            thread::sleep(Duration::from_millis(50));
+            *guard += 1;
+        });
+        // The thread which locked it first waits for the guard to be dropped.
+        *m.as_ref().lock().unwrap() += 1;
+    });
+    assert_eq!(*m.as_ref().lock().unwrap(), 2);
+}
+
+#[test]
+fn test_mutex_unsized_slice() {
+    let len = 3;
+    let mutex: Pin<Box<Mutex<[String]>>> = Mutex::boxed_slice((0..len).map(|i| i.to_string()));
+    mutex.as_ref().lock().unwrap()[1].push('!');
+    assert_eq!(&*mutex.as_ref().lock().unwrap(), ["0", "1!", "2"]);
+
+    let mutex: Pin<Arc<Mutex<[i32]>>> = Mutex::arc_slice(vec![1, 2, 3]);
+    let mutex2 = mutex.clone();
+    thread::spawn(move || mutex2.as_ref().lock().unwrap()[2] = 5)
+        .join()
+        .unwrap();
+    assert_eq!(&*mutex.as_ref().lock().unwrap(), [1, 2, 5]);
+
+    let mutex: Pin<Box<Mutex<[u64]>>> = Mutex::boxed_slice(Vec::new());
+    assert!(mutex.as_ref().lock().unwrap().is_empty());
+}
+
+#[test]
+fn test_lock_transactional() {
+    let m = Mutex::arc(vec![1]);
+
+    let m2 = m.clone();
+    let r = thread::spawn(move || {
+        let mut g = m2.as_ref().lock_transactional().unwrap();
+        g.push(2);
+        g.commit();
+        g.push(3);
+        panic!("test panic in inner thread to roll back mutex");
+    })
+    .join();
+    assert!(r.is_err());
+
+    assert!(!m.as_ref().is_poisoned());
+    assert_eq!(*m.as_ref().lock().unwrap(), [1, 2]);
+
+    let mut g = m.as_ref().lock_transactional().unwrap();
+    g.push(4);
+    drop(g);
+    assert_eq!(*m.as_ref().lock().unwrap(), [1, 2, 4]);
+}
+
+struct SpinLock(AtomicBool);
+
+struct SpinGuard<'a>(&'a AtomicBool);
+
+impl Drop for SpinGuard<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Release);
+    }
+}
+
+unsafe impl RawMutex for SpinLock {
+    type Guard<'a> = SpinGuard<'a>;
+
+    const UNINIT: Self = SpinLock(AtomicBool::new(false));
+
+    fn init(self: Pin<&Self>) {}
+
+    fn lock(self: Pin<&Self>) -> SpinGuard<'_> {
+        loop {
+            if let Some(guard) = self.try_lock() {
+                return guard;
+            }
+            thread::yield_now();
+        }
+    }
+
+    fn try_lock(self: Pin<&Self>) -> Option<SpinGuard<'_>> {
+        let locked = &self.get_ref().0;
+        locked
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .ok()
+            .map(|_| SpinGuard(locked))
+    }
+
+    unsafe fn unlock(self: Pin<&Self>) {
+        self.0.store(false, Ordering::Release);
+    }
+}
+
+#[test]
+fn test_custom_backend() {
+    const J: u32 = 1000;
+    const K: u32 = 3;
+
+    let m = Arc::pin(Mutex::<_, SpinLock>::with_backend(0));
+    m.as_ref().init();
+    let other = Mutex::boxed(0);
+
+    let threads: Vec<_> = (0..K)
+        .map(|_| {
+            let m = m.clone();
+            thread::spawn(move || {
+                for _ in 0..J {
+                    *m.as_ref().lock().unwrap() += 1;
+                }
+            })
+        })
+        .collect();
+    for _ in 0..J {
+        *other.as_ref().lock().unwrap() += 1;
+    }
+    for t in threads {
+        t.join().unwrap();
+    }
+    assert_eq!(*m.as_ref().lock().unwrap(), J * K);
+    assert_eq!(*other.as_ref().lock().unwrap(), J);
+
+    let guard = m.as_ref().lock().unwrap();
+    assert!(matches!(m.as_ref().try_lock(), Err(TryLockError::WouldBlock)));
+    drop(guard);
+
+    let m2 = m.clone();
+    let _ = thread::spawn(move || {
+        let _lock = m2.as_ref().lock().unwrap();
+        panic!("test panic in inner thread to poison mutex");
+    })
+    .join();
+    assert!(m.as_ref().is_poisoned());
+}
+
+#[test]
+fn test_id() {
+    let m1 = Mutex::boxed(0);
+    let m2 = Mutex::arc(0);
+    assert_eq!(m1.as_ref().id(), m1.as_ref().id());
+    assert_ne!(m1.as_ref().id(), m2.as_ref().id());
+
+    // The identifier does not change when the pointer is moved.
+    let m2_id = m2.as_ref().id();
+    let m3 = thread::spawn(move || m2.as_ref().id()).join().unwrap();
+    assert_eq!(m2_id, m3);
+
+    assert_eq!(format!("{:p}", *m1), format!("{}", m1.as_ref().id()));
+    assert_eq!(format!("{:p}", *m1), format!("{:p}", &*m1));
+}
+
+// The futex-like, spin and illumos backends need no initialization.
+#[cfg(all(
+    any(
+        feature = "spin",
+        target_os = "linux",
+        target_os = "android",
+        target_os = "freebsd",
+        target_os = "fuchsia",
+        target_os = "netbsd",
+        target_os = "redox",
+        target_os = "illumos",
+        target_os = "solaris",
+        target_os = "emscripten",
+        all(target_arch = "wasm32", target_feature = "atomics")
+    ),
+    not(any(feature = "parking-lot-core", feature = "thread-park"))
+))]
+#[test]
+fn test_uninit_futex() {
+    static M: Mutex<usize> = Mutex::uninit(0);
+    let m = Pin::static_ref(&M);
+    let threads: Vec<_> = (0..4)
+        .map(|_| {
+            thread::spawn(move || {
+                for _ in 0..1000 {
+                    *m.lock().unwrap() += 1;
+                }
+            })
+        })
+        .collect();
+    for t in threads {
+        t.join().unwrap();
+    }
+    assert_eq!(*m.lock().unwrap(), 4000);
+}
+
+// Every backend but the generic pthread one and ESP-IDF has a const `new`.
+#[cfg(any(
+    feature = "parking-lot-core",
+    feature = "thread-park",
+    feature = "spin",
+    not(unix),
+    target_os = "linux",
+    target_os = "android",
+    target_os = "freebsd",
+    target_os = "fuchsia",
+    target_os = "netbsd",
+    target_os = "redox",
+    target_os = "illumos",
+    target_os = "solaris",
+    target_os = "emscripten"
+))]
+#[test]
+fn new_static() {
+    static M: Mutex<usize> = Mutex::new(0);
+    let m = Pin::static_ref(&M);
+    let threads: Vec<_> = (0..4)
+        .map(|_| {
+            thread::spawn(move || {
+                for _ in 0..1000 {
+                    *m.lock().unwrap() += 1;
+                }
+            })
+        })
+        .collect();
+    for t in threads {
+        t.join().unwrap();
+    }
+    assert_eq!(*m.lock().unwrap(), 4000);
+    assert!(m.try_lock_for(Duration::from_millis(1)).is_ok());
+}
+
+#[test]
+fn bump() {
+    let m = Mutex::arc(0);
+    let mut g = m.as_ref().lock().unwrap();
+    *g += 1;
+    // Bumping without waiters keeps the data and the lock.
+    g = MutexGuard::bump(g).unwrap();
+    assert_eq!(*g, 1);
+    assert!(m.as_ref().try_lock().is_err());
+
+    let m2 = m.clone();
+    let t = thread::spawn(move || *m2.as_ref().lock().unwrap() += 10);
+    // The other thread increments the value once it gets the lock in between.
+    while *g == 1 {
+        g = MutexGuard::bump(g).unwrap();
+    }
+    assert_eq!(*g, 11);
+    drop(g);
+    t.join().unwrap();
+}
+
+#[test]
+fn bump_poison() {
+    let m = Mutex::arc(0);
+    let g = m.as_ref().lock().unwrap();
+
+    let m2 = m.clone();
+    let t = thread::spawn(move || {
+        let _g = m2.as_ref().lock().unwrap();
+        panic!("test panic in inner thread to poison mutex");
+    });
+    let mut g = Ok(g);
+    while !t.is_finished() {
+        g = match g {
+            Ok(g) => MutexGuard::bump(g),
+            Err(e) => MutexGuard::bump(e.into_inner()),
+        };
+    }
+    assert!(t.join().is_err());
+    assert!(g.is_err());
+}
+
+#[test]
+fn data_ptr() {
+    let m = Mutex::boxed(1);
+    let ptr = m.as_ref().data_ptr();
+    {
+        let mut g = m.as_ref().lock().unwrap();
+        assert_eq!(ptr, &mut *g as *mut i32);
+        unsafe { *ptr += 1 };
+    }
+    assert_eq!(*m.as_ref().lock().unwrap(), 2);
+}
+
+#[test]
+fn lock_raw() {
+    let m = Mutex::arc(0);
+    unsafe { m.as_ref().lock_raw() };
+    assert!(matches!(m.as_ref().try_lock(), Err(TryLockError::WouldBlock)));
+    unsafe { *m.as_ref().data_ptr() += 1 };
+
+    let m2 = m.clone();
+    let t = thread::spawn(move || *m2.as_ref().lock().unwrap() += 1);
+    unsafe { m.as_ref().unlock_raw() };
+    t.join().unwrap();
+    assert_eq!(*m.as_ref().lock().unwrap(), 2);
+}
+
+#[test]
+fn lock_raw_custom_backend() {
+    let m = Box::pin(Mutex::<_, SpinLock>::with_backend(0));
+    m.as_ref().init();
+    unsafe {
+        m.as_ref().lock_raw();
+        assert!(m.as_ref().try_lock().is_err());
+        m.as_ref().unlock_raw();
+    }
+    assert!(m.as_ref().try_lock().is_ok());
+}
+
+#[test]
+fn force_unlock() {
+    for biased in [false, true] {
+        let m = Arc::pin(Mutex::uninit(0).biased(biased));
+        m.as_ref().init();
+        *m.as_ref().lock().unwrap() += 1;
+        std::mem::forget(m.as_ref().lock().unwrap());
+        // The owner of the bias would panic on locking it again.
+        if !biased {
+            assert!(m.as_ref().try_lock().is_err());
+        }
+        unsafe { m.as_ref().force_unlock() };
+        assert!(!m.as_ref().is_poisoned());
+
+        let m2 = m.clone();
+        thread::spawn(move || *m2.as_ref().lock().unwrap() += 1)
+            .join()
+            .unwrap();
+        assert_eq!(*m.as_ref().lock().unwrap(), 2);
+    }
+}
+
+#[test]
+fn lock_arc() {
+    struct Holder {
+        guard: ArcMutexGuard<Vec<i32>>,
+    }
+
+    let m = Mutex::arc(vec![1]);
+    let mut holder = Holder {
+        guard: m.clone().lock_arc().unwrap(),
+    };
+    holder.guard.push(2);
+    assert!(matches!(m.as_ref().try_lock(), Err(TryLockError::WouldBlock)));
+    assert_eq!(ArcMutexGuard::mutex(&holder.guard).as_ref().id(), m.as_ref().id());
+
+    // The guard keeps the mutex alive.
+    drop(m);
+    let m = ArcMutexGuard::mutex(&holder.guard).clone();
+    drop(holder);
+    assert_eq!(*m.as_ref().lock().unwrap(), [1, 2]);
+}
+
+#[test]
+fn lock_arc_poison() {
+    let m = Mutex::arc(1);
+    let m2 = m.clone();
+    let result = thread::spawn(move || {
+        let _guard = m2.lock_arc().unwrap();
+        panic!("test panic in inner thread to poison mutex");
+    })
+    .join();
+    assert!(result.is_err());
+    match m.clone().lock_arc() {
+        Ok(_) => panic!("the mutex is not poisoned"),
+        Err(e) => assert_eq!(*e.into_inner(), 1),
+    }
+}
+
+#[cfg(all(
+    feature = "send_guard",
+    any(
+        feature = "parking-lot-core",
+        feature = "thread-park",
+        feature = "spin",
+        not(any(unix, windows)),
+        target_os = "linux",
+        target_os = "android",
+        target_os = "freebsd",
+        target_os = "fuchsia",
+        target_os = "netbsd",
+        target_os = "redox",
+        target_os = "emscripten",
+        all(target_arch = "wasm32", target_feature = "atomics"),
+    )
+))]
+#[test]
+fn lock_arc_send() {
+    let m = Mutex::arc(0);
+    let guard = m.clone().lock_arc().unwrap();
+    thread::spawn(move || {
+        let mut guard = guard;
+        *guard += 1;
+    })
+    .join()
+    .unwrap();
+    assert_eq!(*m.as_ref().lock().unwrap(), 1);
+}
+
+#[test]
+fn clear_poison() {
+    let m = Mutex::arc(1);
+    let m2 = m.clone();
+    let _: Result<(), _> = thread::spawn(move || {
+        let _lock = m2.as_ref().lock().unwrap();
+        panic!();
+    })
+    .join();
+    assert!(m.as_ref().is_poisoned());
+
+    m.as_ref().clear_poison();
+    assert!(!m.as_ref().is_poisoned());
+    assert_eq!(*m.as_ref().lock().unwrap(), 1);
+}
@@ -0,0 +1,105 @@
+use pinned_sync::nopoison::{Mutex, RwLock};
+use pinned_sync::Condvar;
+use std::pin::Pin;
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn mutex_panic_does_not_poison() {
+    let m = Mutex::arc(1);
+    let m2 = m.clone();
+    let result = thread::spawn(move || {
+        let mut guard = m2.as_ref().lock();
+        *guard += 1;
+        panic!("test panic while holding the lock");
+    })
+    .join();
+    assert!(result.is_err());
+    assert_eq!(*m.as_ref().lock(), 2);
+    assert!(m.as_ref().try_lock().is_some());
+}
+
+#[test]
+fn mutex_try_lock() {
+    let m = Mutex::boxed(());
+    let guard = m.as_ref().lock();
+    assert!(m.as_ref().try_lock().is_none());
+    assert!(m.as_ref().try_lock_for(Duration::from_millis(10)).is_none());
+    drop(guard);
+    assert!(m.as_ref().try_lock().is_some());
+}
+
+#[test]
+fn mutex_static() {
+    static M: Mutex<Vec<i32>> = Mutex::uninit(Vec::new());
+    let m = Pin::static_ref(&M);
+    m.init();
+    m.lock().push(1);
+    assert_eq!(*m.lock(), [1]);
+}
+
+#[test]
+fn mutex_into_inner_get_mut() {
+    let mut m = Mutex::uninit(vec![1]);
+    m.get_mut().push(2);
+    assert_eq!(m.into_inner(), [1, 2]);
+}
+
+#[test]
+fn mutex_condvar() {
+    let pair = Box::pin((Mutex::uninit(false), Condvar::uninit()));
+    let (m, c) = unsafe {
+        (
+            pair.as_ref().map_unchecked(|pair| &pair.0),
+            pair.as_ref().map_unchecked(|pair| &pair.1),
+        )
+    };
+    m.init();
+    c.init();
+    thread::scope(|s| {
+        s.spawn(move || {
+            *m.lock() = true;
+            c.notify_one();
+        });
+        let mut started = m.lock();
+        while !*started {
+            started = c.wait(started).unwrap();
+        }
+    });
+}
+
+#[test]
+fn rwlock_panic_does_not_poison() {
+    let l = RwLock::arc(1);
+    let l2 = l.clone();
+    let result = thread::spawn(move || {
+        let mut guard = l2.as_ref().write();
+        *guard += 1;
+        panic!("test panic while holding the lock");
+    })
+    .join();
+    assert!(result.is_err());
+    assert_eq!(*l.as_ref().read(), 2);
+    *l.as_ref().write() += 1;
+    assert_eq!(*l.as_ref().upgradable_read(), 3);
+}
+
+#[test]
+fn rwlock_try() {
+    let l = RwLock::boxed(0);
+    let read = l.as_ref().read();
+    assert!(l.as_ref().try_read().is_some());
+    assert!(l.as_ref().try_write().is_none());
+    drop(read);
+    let write = l.as_ref().write();
+    assert!(l.as_ref().try_read().is_none());
+    drop(write);
+    assert!(l.as_ref().try_write().is_some());
+}
+
+#[test]
+fn rwlock_into_inner_get_mut() {
+    let mut l = RwLock::uninit(vec![1]);
+    l.get_mut().push(2);
+    assert_eq!(l.into_inner(), [1, 2]);
+}
@@ -0,0 +1,57 @@
+use pinned_sync::OnceMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Barrier};
+use std::{panic, thread};
+
+#[test]
+fn smoke() {
+    let map = OnceMap::boxed();
+    assert_eq!(map.as_ref().get("a"), None);
+    assert_eq!(map.as_ref().get_or_init("a".to_string(), || 1), &1);
+    assert_eq!(map.as_ref().get_or_init("a".to_string(), || 2), &1);
+    assert_eq!(map.as_ref().get("a"), Some(&1));
+    assert_eq!(map.as_ref().get_or_init("b".to_string(), || 2), &2);
+}
+
+#[test]
+fn races() {
+    const N: usize = 8;
+    const K: usize = 100;
+
+    let map = OnceMap::arc();
+    let calls = Arc::new(AtomicUsize::new(0));
+    let barrier = Arc::new(Barrier::new(N));
+    let threads: Vec<_> = (0..N)
+        .map(|_| {
+            let map = map.clone();
+            let calls = calls.clone();
+            let barrier = barrier.clone();
+            thread::spawn(move || {
+                barrier.wait();
+                for k in 0..K {
+                    let v = map.as_ref().get_or_init(k, || {
+                        calls.fetch_add(1, Ordering::SeqCst);
+                        k * 2
+                    });
+                    assert_eq!(*v, k * 2);
+                }
+            })
+        })
+        .collect();
+    for t in threads {
+        t.join().unwrap();
+    }
+    assert_eq!(calls.load(Ordering::SeqCst), K);
+}
+
+#[test]
+fn init_panic() {
+    let map = OnceMap::boxed();
+    let r = panic::catch_unwind(|| {
+        map.as_ref()
+            .get_or_init(1, || panic!("test panic in initializer"))
+    });
+    assert!(r.is_err());
+    assert_eq!(map.as_ref().get(&1), None);
+    assert_eq!(map.as_ref().get_or_init(1, || 3), &3);
+}
@@ -0,0 +1,68 @@
+use pinned_sync::{Mutex, Ordered, RwLock};
+use std::thread;
+
+#[test]
+fn in_order() {
+    let a = Ordered::<1, _>::boxed(Mutex::uninit(1));
+    let b = Ordered::<2, _>::boxed(RwLock::uninit(2));
+    let c = Ordered::<3, _>::boxed(Mutex::uninit(3));
+
+    let ga = a.as_ref().lock().unwrap();
+    let gb = b.as_ref().read().unwrap();
+    let mut gc = c.as_ref().lock().unwrap();
+    *gc += *ga + *gb;
+    assert_eq!(*gc, 6);
+
+    // Releasing a lower level lock first is fine, but the thread still holds
+    // level 3.
+    drop(ga);
+    drop(gc);
+    drop(gb);
+
+    // Everything was released, so any order works again.
+    drop(c.as_ref().lock().unwrap());
+    drop(a.as_ref().lock().unwrap());
+}
+
+#[test]
+fn try_out_of_order() {
+    let a = Ordered::<1, _>::boxed(Mutex::uninit(()));
+    let b = Ordered::<2, _>::boxed(RwLock::uninit(()));
+
+    let _gb = b.as_ref().write().unwrap();
+    assert!(a.as_ref().try_lock().is_ok());
+}
+
+#[test]
+fn per_thread() {
+    let a = Ordered::<1, _>::arc(Mutex::uninit(()));
+    let b = Ordered::<2, _>::arc(Mutex::uninit(()));
+
+    let _gb = b.as_ref().lock().unwrap();
+    let a2 = a.clone();
+    thread::spawn(move || drop(a2.as_ref().lock().unwrap()))
+        .join()
+        .unwrap();
+}
+
+#[test]
+#[should_panic(expected = "lock order violation")]
+#[cfg_attr(not(debug_assertions), ignore)]
+fn out_of_order() {
+    let a = Ordered::<1, _>::boxed(Mutex::uninit(()));
+    let b = Ordered::<2, _>::boxed(Mutex::uninit(()));
+
+    let _gb = b.as_ref().lock().unwrap();
+    let _ga = a.as_ref().lock().unwrap();
+}
+
+#[test]
+#[should_panic(expected = "lock order violation")]
+#[cfg_attr(not(debug_assertions), ignore)]
+fn same_level() {
+    let a = Ordered::<1, _>::boxed(RwLock::uninit(()));
+    let b = Ordered::<1, _>::boxed(RwLock::uninit(()));
+
+    let _ga = a.as_ref().read().unwrap();
+    let _gb = b.as_ref().read().unwrap();
+}
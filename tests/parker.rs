@@ -0,0 +1,59 @@
+use pinned_sync::Parker;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn unpark_before_park() {
+    let parker = Parker::boxed();
+    let unparker = parker.as_ref().unparker();
+    unparker.unpark();
+    unparker.unpark();
+    // The token is only available once.
+    parker.as_ref().park();
+    assert!(!parker.as_ref().park_timeout(Duration::from_millis(10)));
+}
+
+#[test]
+fn park_timeout() {
+    let parker = Parker::boxed();
+    assert!(!parker.as_ref().park_timeout(Duration::from_millis(10)));
+    parker.as_ref().unparker().unpark();
+    assert!(parker.as_ref().park_timeout(Duration::from_millis(10)));
+}
+
+#[test]
+fn wakes_parked_thread() {
+    const N: usize = 1000;
+
+    let parker = Parker::arc();
+    let count = Arc::new(AtomicUsize::new(0));
+    let thread = {
+        let parker = parker.clone();
+        let count = count.clone();
+        thread::spawn(move || {
+            for _ in 0..N {
+                count.fetch_add(1, Ordering::Release);
+                parker.as_ref().unparker().unpark();
+            }
+        })
+    };
+    while count.load(Ordering::Acquire) < N {
+        parker.as_ref().park();
+    }
+    thread.join().unwrap();
+}
+
+#[test]
+fn unparker_in_scope() {
+    let parker = Parker::boxed();
+    let unparker = parker.as_ref().unparker();
+    thread::scope(|s| {
+        s.spawn(|| {
+            thread::sleep(Duration::from_millis(20));
+            unparker.unpark();
+        });
+        parker.as_ref().park();
+    });
+}
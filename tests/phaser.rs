@@ -0,0 +1,77 @@
+use pinned_sync::Phaser;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+#[test]
+fn phases() {
+    const N: usize = 4;
+    const PHASES: usize = 100;
+
+    let phaser = Phaser::arc(N);
+    let count = Arc::new(AtomicUsize::new(0));
+    let threads: Vec<_> = (0..N)
+        .map(|_| {
+            let phaser = phaser.clone();
+            let count = count.clone();
+            thread::spawn(move || {
+                for phase in 0..PHASES {
+                    count.fetch_add(1, Ordering::Relaxed);
+                    assert_eq!(phaser.as_ref().wait(), phase);
+                    // Every party arrived before any of them was released.
+                    assert!(count.load(Ordering::Relaxed) >= (phase + 1) * N);
+                }
+            })
+        })
+        .collect();
+    for t in threads {
+        t.join().unwrap();
+    }
+    assert_eq!(phaser.as_ref().phase(), PHASES);
+}
+
+#[test]
+fn arrive_does_not_wait() {
+    let phaser = Phaser::boxed(2);
+    assert_eq!(phaser.as_ref().arrive(), 0);
+    assert_eq!(phaser.as_ref().phase(), 0);
+    assert_eq!(phaser.as_ref().arrive(), 0);
+    assert_eq!(phaser.as_ref().phase(), 1);
+}
+
+#[test]
+fn register_and_deregister() {
+    let phaser = Phaser::arc(1);
+    assert_eq!(phaser.as_ref().register(), 0);
+    assert_eq!(phaser.as_ref().parties(), 2);
+
+    let phaser2 = phaser.clone();
+    let t = thread::spawn(move || {
+        assert_eq!(phaser2.as_ref().wait(), 0);
+        // The other party is gone, so this completes the phase on its own.
+        assert_eq!(phaser2.as_ref().wait(), 1);
+    });
+    assert_eq!(phaser.as_ref().wait(), 0);
+    assert_eq!(phaser.as_ref().arrive_and_deregister(), 1);
+    t.join().unwrap();
+    assert_eq!(phaser.as_ref().parties(), 1);
+    assert_eq!(phaser.as_ref().phase(), 2);
+}
+
+#[test]
+fn deregister_completes_phase() {
+    let phaser = Phaser::arc(2);
+    let phaser2 = phaser.clone();
+    let t = thread::spawn(move || phaser2.as_ref().wait());
+    // Whether it arrives before or after the other party, it is the last one.
+    assert_eq!(phaser.as_ref().arrive_and_deregister(), 0);
+    assert_eq!(t.join().unwrap(), 0);
+    assert_eq!(phaser.as_ref().phase(), 1);
+}
+
+#[test]
+#[should_panic]
+fn too_many_arrivals() {
+    let phaser = Phaser::boxed(0);
+    phaser.as_ref().arrive();
+}
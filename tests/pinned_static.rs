@@ -0,0 +1,56 @@
+use pinned_sync::{pinned_static, Condvar, Mutex, RwLock};
+use std::pin::Pin;
+use std::sync::Barrier;
+use std::thread;
+use std::time::Duration;
+
+pinned_static! {
+    static COUNTER: Mutex<usize> = Mutex::uninit(0);
+    /// A documented lock.
+    pub(crate) static NAMES: RwLock<Vec<&'static str>> = RwLock::uninit(Vec::new());
+    static FLAG: Mutex<bool> = Mutex::uninit(false);
+    static FLAG_CHANGED: Condvar = Condvar::uninit();
+}
+
+#[test]
+fn concurrent_first_use() {
+    let barrier = Barrier::new(8);
+    thread::scope(|s| {
+        for _ in 0..8 {
+            s.spawn(|| {
+                barrier.wait();
+                for _ in 0..100 {
+                    *COUNTER.lock().unwrap() += 1;
+                }
+            });
+        }
+    });
+    assert_eq!(*COUNTER.lock().unwrap(), 800);
+}
+
+#[test]
+fn rwlock() {
+    NAMES.write().unwrap().push("a");
+    let names: Pin<&'static RwLock<Vec<&'static str>>> = *NAMES;
+    assert_eq!(*names.read().unwrap(), ["a"]);
+}
+
+#[test]
+fn condvar() {
+    let (g, timeout) = FLAG_CHANGED
+        .wait_timeout(FLAG.lock().unwrap(), Duration::from_millis(10))
+        .unwrap();
+    assert!(timeout.timed_out());
+    drop(g);
+
+    thread::scope(|s| {
+        s.spawn(|| {
+            *FLAG.lock().unwrap() = true;
+            FLAG_CHANGED.notify_all();
+        });
+        let g = FLAG_CHANGED
+            .wait_while(FLAG.lock().unwrap(), |flag| !*flag)
+            .unwrap();
+        assert!(*g);
+    });
+}
@@ -0,0 +1,123 @@
+use pinned_sync::raw::{Condvar, FairMutex, Mutex, ReadError, ReentrantMutex, RwLock};
+use std::cell::UnsafeCell;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn mutex() {
+    let mutex = Mutex::boxed();
+    let guard = mutex.as_ref().lock();
+    assert!(mutex.as_ref().try_lock().is_none());
+    drop(guard);
+    assert!(mutex.as_ref().try_lock().is_some());
+}
+
+#[test]
+fn fair_mutex() {
+    let mutex = FairMutex::boxed();
+    let guard = mutex.as_ref().lock();
+    assert!(mutex.as_ref().try_lock().is_none());
+    drop(guard);
+    assert!(mutex.as_ref().try_lock().is_some());
+}
+
+#[test]
+fn reentrant_mutex() {
+    let mutex = ReentrantMutex::arc();
+    let outer = mutex.as_ref().lock();
+    let inner = mutex.as_ref().try_lock().unwrap();
+
+    let mutex2 = mutex.clone();
+    let other = thread::spawn(move || mutex2.as_ref().try_lock().is_some());
+    assert!(!other.join().unwrap());
+
+    drop(outer);
+    drop(inner);
+    let mutex2 = mutex.clone();
+    let other = thread::spawn(move || mutex2.as_ref().try_lock().is_some());
+    assert!(other.join().unwrap());
+}
+
+#[test]
+fn rwlock() {
+    let lock = RwLock::boxed();
+    let r1 = lock.as_ref().read().unwrap();
+    let r2 = lock.as_ref().try_read().unwrap();
+    assert!(lock.as_ref().try_write().is_none());
+    drop((r1, r2));
+
+    let w = lock.as_ref().write();
+    assert_eq!(lock.as_ref().try_read().err(), Some(ReadError::WouldBlock));
+    drop(w);
+}
+
+#[test]
+fn condvar() {
+    struct Flag {
+        mutex: Mutex,
+        cvar: Condvar,
+        set: UnsafeCell<bool>,
+    }
+    unsafe impl Sync for Flag {}
+
+    fn mutex(flag: &Pin<Arc<Flag>>) -> Pin<&Mutex> {
+        unsafe { flag.as_ref().map_unchecked(|f| &f.mutex) }
+    }
+
+    fn cvar(flag: &Pin<Arc<Flag>>) -> Pin<&Condvar> {
+        unsafe { flag.as_ref().map_unchecked(|f| &f.cvar) }
+    }
+
+    let flag = Arc::pin(Flag {
+        mutex: Mutex::uninit(),
+        cvar: Condvar::uninit(),
+        set: UnsafeCell::new(false),
+    });
+    mutex(&flag).init();
+    cvar(&flag).init();
+
+    let flag2 = flag.clone();
+    let t = thread::spawn(move || {
+        let _guard = mutex(&flag2).lock();
+        unsafe { *flag2.set.get() = true };
+        cvar(&flag2).notify_one();
+    });
+
+    let mut guard = mutex(&flag).lock();
+    while !unsafe { *flag.set.get() } {
+        guard = cvar(&flag).wait(guard);
+    }
+    drop(guard);
+    t.join().unwrap();
+
+    let (_guard, result) = cvar(&flag).wait_timeout(mutex(&flag).lock(), Duration::from_millis(1));
+    assert!(result.timed_out());
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[test]
+fn futex() {
+    use pinned_sync::raw::Futex;
+    use std::sync::atomic::Ordering;
+
+    let futex = Arc::new(Futex::new(0));
+    // The value is not the expected one, so this does not block.
+    futex.wait(1);
+    assert!(!futex.wait_timeout(0, Duration::from_millis(10)));
+
+    let thread = {
+        let futex = futex.clone();
+        thread::spawn(move || {
+            while futex.as_atomic().load(Ordering::Acquire) == 0 {
+                futex.wait(0);
+            }
+        })
+    };
+    thread::sleep(Duration::from_millis(20));
+    futex.as_atomic().store(1, Ordering::Release);
+    futex.wake_all();
+    thread.join().unwrap();
+    assert!(!futex.wake_one());
+}
@@ -0,0 +1,104 @@
+use pinned_sync::{ReentrantMutex, ReentrantRefCell};
+use std::cell::RefCell;
+use std::pin::Pin;
+use std::thread;
+
+#[test]
+fn smoke() {
+    let m = ReentrantMutex::boxed(());
+    {
+        let a = m.as_ref().lock();
+        {
+            let b = m.as_ref().lock();
+            {
+                let c = m.as_ref().lock();
+                assert_eq!(*c, ());
+            }
+            assert_eq!(*b, ());
+        }
+        assert_eq!(*a, ());
+    }
+}
+
+#[test]
+fn is_mutex() {
+    let m = ReentrantMutex::arc(RefCell::new(0));
+    let m2 = m.clone();
+    let lock = m.as_ref().lock();
+    let child = thread::spawn(move || {
+        let lock = m2.as_ref().lock();
+        assert_eq!(*lock.borrow(), 4950);
+    });
+    for i in 0..100 {
+        let lock = m.as_ref().lock();
+        *lock.borrow_mut() += i;
+    }
+    drop(lock);
+    child.join().unwrap();
+}
+
+#[test]
+fn trylock_works() {
+    let m = ReentrantMutex::arc(());
+    let m2 = m.clone();
+    let _lock = m.as_ref().try_lock();
+    let _lock2 = m.as_ref().try_lock();
+    thread::spawn(move || {
+        let lock = m2.as_ref().try_lock();
+        assert!(lock.is_none());
+    })
+    .join()
+    .unwrap();
+    let _lock3 = m.as_ref().try_lock();
+}
+
+#[test]
+fn refcell_borrow_mut() {
+    fn push(cell: Pin<&ReentrantRefCell<Vec<i32>>>, depth: i32) {
+        let guard = cell.lock();
+        guard.borrow_mut().push(depth);
+        if depth > 0 {
+            push(cell, depth - 1);
+        }
+    }
+
+    let cell = ReentrantRefCell::boxed(Vec::new());
+    push(cell.as_ref(), 3);
+    assert_eq!(*cell.as_ref().lock().borrow(), [3, 2, 1, 0]);
+    assert_eq!(unsafe { Pin::into_inner_unchecked(cell) }.into_inner(), [3, 2, 1, 0]);
+}
+
+#[test]
+fn refcell_conflicting_borrow() {
+    let cell = ReentrantRefCell::boxed(0);
+    let outer = cell.as_ref().lock();
+    let inner = cell.as_ref().lock();
+    let r = outer.borrow_mut();
+    assert!(inner.try_borrow().is_err());
+    assert!(inner.try_borrow_mut().is_err());
+    drop(r);
+    *inner.borrow_mut() += 1;
+    assert_eq!(*outer.borrow(), 1);
+}
+
+#[test]
+fn refcell_threads() {
+    let cell = ReentrantRefCell::arc(0);
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            let cell = cell.clone();
+            thread::spawn(move || {
+                for _ in 0..1000 {
+                    let guard = cell.as_ref().lock();
+                    let nested = cell.as_ref().lock();
+                    *nested.borrow_mut() += 1;
+                    drop(guard);
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    assert_eq!(*cell.as_ref().lock().borrow(), 4000);
+}
@@ -1,10 +1,16 @@
-use pinned_sync::RwLock;
+use pinned_sync::raw::{RawRwLock, ReadError};
+use pinned_sync::{
+    LockError, MappedRwLockReadGuard, ReaderOverflow, RwLock, RwLockReadGuard,
+    RwLockUpgradableReadGuard, RwLockWriteGuard, TryLockError, WriterPolicy,
+};
 use rand::{self, Rng};
+use std::panic;
 use std::pin::Pin;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::mpsc::channel;
-use std::sync::{Arc, TryLockError};
+use std::sync::Arc;
 use std::thread;
+use std::time::{Duration, Instant};
 
 #[derive(Eq, PartialEq, Debug)]
 struct NonCopy(i32);
@@ -183,6 +189,89 @@ fn test_rwlock_try_write() {
     drop(read_guard);
 }
 
+#[test]
+fn test_rwlock_try_write_for() {
+    let lock = RwLock::arc(0);
+    let read_guard = lock.as_ref().read().unwrap();
+
+    let lock2 = lock.clone();
+    let start = Instant::now();
+    thread::spawn(
+        move || match lock2.as_ref().try_write_for(Duration::from_millis(50)) {
+            Err(TryLockError::WouldBlock) => {}
+            _ => panic!("try_write_for should not succeed while read_guard is in scope"),
+        },
+    )
+    .join()
+    .unwrap();
+    assert!(start.elapsed() >= Duration::from_millis(50));
+
+    // The writer which gave up does not keep readers out.
+    drop(read_guard);
+    drop(lock.as_ref().try_read_for(Duration::from_secs(60)).unwrap());
+
+    let lock2 = lock.clone();
+    let read_guard = lock.as_ref().read().unwrap();
+    let writer = thread::spawn(move || {
+        *lock2
+            .as_ref()
+            .try_write_for(Duration::from_secs(60))
+            .unwrap() += 1;
+    });
+    thread::sleep(Duration::from_millis(10));
+    drop(read_guard);
+    writer.join().unwrap();
+    assert_eq!(*lock.as_ref().try_read_for(Duration::MAX).unwrap(), 1);
+}
+
+#[test]
+fn test_rwlock_try_read_until() {
+    let lock = RwLock::arc(0);
+    let write_guard = lock.as_ref().write().unwrap();
+
+    let lock2 = lock.clone();
+    thread::spawn(move || {
+        let deadline = Instant::now() + Duration::from_millis(50);
+        match lock2.as_ref().try_read_until(deadline) {
+            Err(TryLockError::WouldBlock) => assert!(Instant::now() >= deadline),
+            _ => panic!("try_read_until should not succeed while write_guard is in scope"),
+        }
+    })
+    .join()
+    .unwrap();
+
+    let lock2 = lock.clone();
+    let reader = thread::spawn(move || {
+        let deadline = Instant::now() + Duration::from_secs(60);
+        assert_eq!(*lock2.as_ref().try_read_until(deadline).unwrap(), 1);
+    });
+    let mut write_guard = write_guard;
+    *write_guard += 1;
+    thread::sleep(Duration::from_millis(10));
+    drop(write_guard);
+    reader.join().unwrap();
+}
+
+#[test]
+fn test_rwlock_try_write_until_writer_preferred() {
+    let lock = Arc::pin(RwLock::uninit(0).writer_policy(WriterPolicy::Preferred));
+    lock.as_ref().init();
+    let read_guard = lock.as_ref().read().unwrap();
+
+    let deadline = Instant::now() + Duration::from_millis(50);
+    let lock2 = lock.clone();
+    thread::spawn(move || {
+        assert!(lock2.as_ref().try_write_until(deadline).is_err());
+    })
+    .join()
+    .unwrap();
+
+    // The turnstile was released by the writer which gave up.
+    drop(lock.as_ref().try_read().unwrap());
+    drop(read_guard);
+    *lock.as_ref().try_write_until(Instant::now()).unwrap() += 1;
+}
+
 #[test]
 fn test_into_inner() {
     let m = RwLock::boxed(NonCopy(10));
@@ -265,3 +354,790 @@ fn test_get_mut_poison() {
         Ok(x) => panic!("get_mut of poisoned RwLock is Ok: {:?}", x),
     }
 }
+
+#[test]
+fn test_get_pin_mut() {
+    let mut m = RwLock::boxed(NonCopy(10));
+    *m.as_mut().get_pin_mut().unwrap() = NonCopy(20);
+    assert_eq!(*m.as_ref().read().unwrap(), NonCopy(20));
+}
+
+#[test]
+fn test_get_pin_mut_poison() {
+    let mut m = RwLock::boxed(NonCopy(10));
+    thread::scope(|s| {
+        let _ = s
+            .spawn(|| {
+                let _lock = m.as_ref().write().unwrap();
+                panic!("test panic in inner thread to poison RwLock");
+            })
+            .join();
+    });
+
+    assert!(m.as_ref().is_poisoned());
+    match m.as_mut().get_pin_mut() {
+        Err(e) => assert_eq!(*e.into_inner(), NonCopy(10)),
+        Ok(x) => panic!("get_pin_mut of poisoned RwLock is Ok: {:?}", x),
+    }
+}
+
+#[test]
+fn test_into_inner_boxed() {
+    let m = RwLock::boxed(NonCopy(10));
+    assert_eq!(RwLock::into_inner_boxed(m).unwrap(), NonCopy(10));
+
+    let m = RwLock::boxed(NonCopy(10));
+    let _ = panic::catch_unwind(|| {
+        let _lock = m.as_ref().write().unwrap();
+        panic!("test panic to poison the lock");
+    });
+    match RwLock::into_inner_boxed(m) {
+        Err(e) => assert_eq!(e.into_inner(), NonCopy(10)),
+        Ok(x) => panic!("into_inner_boxed of poisoned RwLock is Ok: {:?}", x),
+    }
+}
+
+#[test]
+fn test_try_into_inner_arc() {
+    let data = Arc::new(());
+    let m = RwLock::arc(data.clone());
+    let m2 = m.clone();
+    let m = RwLock::try_into_inner_arc(m).unwrap_err();
+    drop(m2);
+    let inner = RwLock::try_into_inner_arc(m)
+        .unwrap_or_else(|_| panic!())
+        .unwrap();
+    assert_eq!(Arc::strong_count(&data), 2);
+    drop(inner);
+    assert_eq!(Arc::strong_count(&data), 1);
+}
+
+#[test]
+fn test_rw_arc_poisoning_disabled() {
+    let arc = Arc::pin(RwLock::uninit(1).poisoning(false));
+    arc.as_ref().init();
+    let arc2 = arc.clone();
+    let _: Result<(), _> = thread::spawn(move || {
+        let _lock = arc2.as_ref().write().unwrap();
+        panic!();
+    })
+    .join();
+    assert!(!arc.as_ref().is_poisoned());
+    assert_eq!(*arc.as_ref().read().unwrap(), 1);
+    assert!(arc.as_ref().write().is_ok());
+}
+
+#[test]
+fn test_rw_arc_reader_overflow_block() {
+    let arc = Arc::pin(RwLock::uninit(0).reader_overflow(ReaderOverflow::Block));
+    arc.as_ref().init();
+    let guards: Vec<_> = (0..100).map(|_| arc.as_ref().read().unwrap()).collect();
+    assert!(arc.as_ref().try_write().is_err());
+    drop(guards);
+    *arc.as_ref().write().unwrap() += 1;
+    assert_eq!(*arc.as_ref().read().unwrap(), 1);
+}
+
+#[test]
+fn test_rw_arc_writer_preferred() {
+    let arc = Arc::pin(RwLock::uninit(0).writer_policy(WriterPolicy::Preferred));
+    arc.as_ref().init();
+    let read = arc.as_ref().read().unwrap();
+
+    let arc2 = arc.clone();
+    let writer = thread::spawn(move || {
+        *arc2.as_ref().write().unwrap() += 1;
+    });
+
+    // Once the writer is waiting, new readers must be kept out.
+    while arc.as_ref().try_read().is_ok() {
+        thread::yield_now();
+    }
+    assert_eq!(*read, 0);
+    drop(read);
+
+    writer.join().unwrap();
+    assert_eq!(*arc.as_ref().read().unwrap(), 1);
+}
+
+#[test]
+fn test_rw_arc_read_optimistic() {
+    let arc = RwLock::arc([0u64, 0u64]);
+    assert_eq!(arc.as_ref().try_read_optimistic().unwrap().unwrap(), [0, 0]);
+
+    let write = arc.as_ref().write().unwrap();
+    assert!(arc.as_ref().try_read_optimistic().is_none());
+    drop(write);
+
+    let arc2 = arc.clone();
+    let writer = thread::spawn(move || {
+        for i in 1..=1000 {
+            *arc2.as_ref().write().unwrap() = [i, i];
+        }
+    });
+    let mut last = 0;
+    while last != 1000 {
+        let [a, b] = arc.as_ref().read_optimistic(|&pair| pair).unwrap();
+        assert_eq!(a, b);
+        assert!(a >= last);
+        last = a;
+    }
+    writer.join().unwrap();
+
+    // Copied byte by byte, as it is not aligned for words.
+    let bytes = RwLock::boxed([1u8, 2, 3]);
+    assert_eq!(bytes.as_ref().try_read_optimistic().unwrap().unwrap(), [1, 2, 3]);
+}
+
+#[cfg(all(
+    feature = "send_guard",
+    any(
+        feature = "parking-lot-core",
+        feature = "thread-park",
+        feature = "spin",
+        not(any(unix, windows)),
+        target_os = "linux",
+        target_os = "android",
+        target_os = "freebsd",
+        target_os = "fuchsia",
+        target_os = "netbsd",
+        target_os = "redox",
+        target_os = "emscripten",
+        all(target_arch = "wasm32", target_feature = "atomics"),
+    )
+))]
+#[test]
+fn test_rwlock_send_guard() {
+    let l = RwLock::boxed(0);
+    let read = l.as_ref().read().unwrap();
+    thread::scope(|s| {
+        s.spawn(move || assert_eq!(*read, 0));
+    });
+    let write = l.as_ref().write().unwrap();
+    thread::scope(|s| {
+        s.spawn(move || {
+            let mut write = write;
+            *write += 1;
+        });
+    });
+    assert_eq!(*l.as_ref().read().unwrap(), 1);
+}
+
+#[cfg(feature = "debug-rwlock")]
+#[test]
+#[should_panic(expected = "holds a read lock")]
+fn test_rwlock_debug_write_after_read() {
+    let lock = RwLock::boxed(());
+    let _r = lock.as_ref().read().unwrap();
+    let _w = lock.as_ref().write();
+}
+
+#[cfg(feature = "debug-rwlock")]
+#[test]
+#[should_panic(expected = "holds a write lock")]
+fn test_rwlock_debug_read_after_write() {
+    let lock = RwLock::boxed(());
+    let _w = lock.as_ref().write().unwrap();
+    let _r = lock.as_ref().read();
+}
+
+#[cfg(feature = "debug-rwlock")]
+#[test]
+fn test_rwlock_debug_released() {
+    let a = RwLock::boxed(());
+    let b = RwLock::boxed(());
+    let ra = a.as_ref().read().unwrap();
+    let rb = b.as_ref().try_read().unwrap();
+    drop(ra);
+    drop(a.as_ref().write().unwrap());
+    drop(rb);
+    drop(b.as_ref().write().unwrap());
+}
+
+#[test]
+fn test_read_guard_clone() {
+    let lock = RwLock::arc(vec![1, 2, 3]);
+    let r1 = lock.as_ref().read().unwrap();
+    let r2 = RwLockReadGuard::clone(&r1);
+    assert!(matches!(
+        lock.as_ref().try_write(),
+        Err(TryLockError::WouldBlock)
+    ));
+    drop(r1);
+    assert_eq!(*r2, [1, 2, 3]);
+    assert!(matches!(
+        lock.as_ref().try_write(),
+        Err(TryLockError::WouldBlock)
+    ));
+    drop(r2);
+    lock.as_ref().write().unwrap().push(4);
+
+    // A clone of a guard of a poisoned lock does not fail.
+    let lock2 = lock.clone();
+    let _ = thread::spawn(move || {
+        let _lock = lock2.as_ref().write().unwrap();
+        panic!();
+    })
+    .join();
+    let r1 = lock.as_ref().read().err().unwrap().into_inner();
+    let r2 = RwLockReadGuard::clone(&r1);
+    drop(r1);
+    assert_eq!(r2.len(), 4);
+    drop(r2);
+    assert!(lock.as_ref().write().is_err());
+}
+
+#[test]
+fn test_id() {
+    let l1 = RwLock::boxed(0);
+    let l2 = RwLock::boxed(0);
+    assert_eq!(l1.as_ref().id(), l1.as_ref().id());
+    assert_ne!(l1.as_ref().id(), l2.as_ref().id());
+    assert_eq!(format!("{:p}", *l1), format!("{:?}", l1.as_ref().id()));
+}
+
+#[test]
+fn test_freeze() {
+    let l = RwLock::arc(0);
+    *l.as_ref().write().unwrap() = 1;
+    assert!(!l.as_ref().is_frozen());
+    l.as_ref().freeze();
+    l.as_ref().freeze();
+    assert!(l.as_ref().is_frozen());
+
+    let r1 = l.as_ref().read().unwrap();
+    let r2 = RwLockReadGuard::clone(&r1);
+    let r3 = l.as_ref().try_read().unwrap();
+    assert!(matches!(
+        l.as_ref().try_write(),
+        Err(TryLockError::WouldBlock)
+    ));
+    let l2 = l.clone();
+    thread::spawn(move || assert_eq!(*l2.as_ref().read().unwrap(), 1))
+        .join()
+        .unwrap();
+    assert_eq!((*r1, *r2, *r3), (1, 1, 1));
+}
+
+#[test]
+#[should_panic(expected = "frozen")]
+fn test_freeze_write() {
+    let l = RwLock::boxed(0);
+    l.as_ref().freeze();
+    let _ = l.as_ref().write();
+}
+
+// The futex-like and spin backends need no initialization.
+#[cfg(all(
+    any(
+        feature = "spin",
+        target_os = "linux",
+        target_os = "android",
+        target_os = "freebsd",
+        target_os = "fuchsia",
+        target_os = "netbsd",
+        target_os = "redox",
+        target_os = "emscripten",
+        all(target_arch = "wasm32", target_feature = "atomics")
+    ),
+    not(any(feature = "parking-lot-core", feature = "thread-park"))
+))]
+#[test]
+fn test_uninit_futex() {
+    static L: RwLock<usize> = RwLock::uninit(0);
+    let l = Pin::static_ref(&L);
+    let threads: Vec<_> = (0..4)
+        .map(|i| {
+            thread::spawn(move || {
+                for _ in 0..1000 {
+                    if i % 2 == 0 {
+                        *l.write().unwrap() += 1;
+                    } else {
+                        let guard = l.read().unwrap();
+                        let _clone = RwLockReadGuard::clone(&guard);
+                        assert!(*guard <= 2000);
+                    }
+                }
+            })
+        })
+        .collect();
+    for t in threads {
+        t.join().unwrap();
+    }
+    assert_eq!(*l.read().unwrap(), 2000);
+}
+
+// Every backend but the generic pthread one and ESP-IDF has a const `new`.
+#[cfg(any(
+    feature = "parking-lot-core",
+    feature = "thread-park",
+    feature = "spin",
+    not(unix),
+    target_os = "linux",
+    target_os = "android",
+    target_os = "freebsd",
+    target_os = "fuchsia",
+    target_os = "netbsd",
+    target_os = "redox",
+    target_os = "illumos",
+    target_os = "solaris",
+    target_os = "emscripten"
+))]
+#[test]
+fn new_static() {
+    static L: RwLock<usize> = RwLock::new(0);
+    let l = Pin::static_ref(&L);
+    let threads: Vec<_> = (0..4)
+        .map(|i| {
+            thread::spawn(move || {
+                for _ in 0..1000 {
+                    if i % 2 == 0 {
+                        *l.write().unwrap() += 1;
+                    } else {
+                        let guard = l.upgradable_read().unwrap();
+                        assert!(*guard <= 2000);
+                    }
+                }
+            })
+        })
+        .collect();
+    for t in threads {
+        t.join().unwrap();
+    }
+    assert_eq!(*l.read().unwrap(), 2000);
+}
+
+#[test]
+fn upgradable_read() {
+    let l = RwLock::arc(0);
+    let u = l.as_ref().upgradable_read().unwrap();
+    // Readers may share the lock, but not writers or other upgradable readers.
+    drop(l.as_ref().try_read().unwrap());
+    assert!(matches!(
+        l.as_ref().try_write(),
+        Err(TryLockError::WouldBlock)
+    ));
+    assert!(matches!(
+        l.as_ref().try_upgradable_read(),
+        Err(TryLockError::WouldBlock)
+    ));
+
+    let mut w = RwLockUpgradableReadGuard::upgrade(u);
+    assert!(matches!(
+        l.as_ref().try_read(),
+        Err(TryLockError::WouldBlock)
+    ));
+    *w += 1;
+
+    let u = RwLockWriteGuard::downgrade_to_upgradable(w);
+    assert_eq!(*l.as_ref().try_read().unwrap(), 1);
+    assert!(matches!(
+        l.as_ref().try_write(),
+        Err(TryLockError::WouldBlock)
+    ));
+    drop(u);
+    *l.as_ref().try_write().unwrap() += 1;
+    assert_eq!(*l.as_ref().try_upgradable_read().unwrap(), 2);
+}
+
+#[test]
+fn upgrade_is_atomic() {
+    const N: usize = 4;
+    const M: usize = 1000;
+
+    for policy in [WriterPolicy::Native, WriterPolicy::Preferred] {
+        let l = Arc::pin(RwLock::uninit(0).writer_policy(policy));
+        l.as_ref().init();
+        let threads: Vec<_> = (0..N)
+            .map(|i| {
+                let l = l.clone();
+                thread::spawn(move || {
+                    for _ in 0..M {
+                        if i % 2 == 0 {
+                            // Increment only if the value did not change
+                            // between the check and the write.
+                            let u = l.as_ref().upgradable_read().unwrap();
+                            let seen = *u;
+                            let mut w = RwLockUpgradableReadGuard::upgrade(u);
+                            assert_eq!(*w, seen);
+                            *w += 1;
+                        } else {
+                            *l.as_ref().write().unwrap() += 1;
+                        }
+                    }
+                })
+            })
+            .collect();
+        for t in threads {
+            t.join().unwrap();
+        }
+        assert_eq!(*l.as_ref().read().unwrap(), N * M);
+    }
+}
+
+#[test]
+fn read_guard_map() {
+    let l = RwLock::boxed((NonCopy(1), vec![2, 3]));
+    let first = RwLockReadGuard::map(l.as_ref().read().unwrap(), |pair| &pair.0);
+    assert_eq!(*first, NonCopy(1));
+    // The mapped guard keeps the shared access.
+    assert!(matches!(
+        l.as_ref().try_write(),
+        Err(TryLockError::WouldBlock)
+    ));
+    assert!(l.as_ref().try_read().is_ok());
+    drop(first);
+
+    let items = RwLockReadGuard::map(l.as_ref().read().unwrap(), |pair| &pair.1[..]);
+    let last = MappedRwLockReadGuard::map(items, |items| &items[1]);
+    assert_eq!(*last, 3);
+    drop(last);
+    assert!(l.as_ref().try_write().is_ok());
+}
+
+#[test]
+fn read_guard_try_map() {
+    let l = RwLock::boxed(vec![1, 2]);
+    let guard = match RwLockReadGuard::try_map(l.as_ref().read().unwrap(), |v| v.get(2)) {
+        Ok(_) => panic!("mapped to a missing element"),
+        Err(guard) => guard,
+    };
+    assert_eq!(*guard, [1, 2]);
+    let second = match RwLockReadGuard::try_map(guard, |v| v.get(1)) {
+        Ok(second) => second,
+        Err(_) => panic!("failed to map to an existing element"),
+    };
+    assert_eq!(*second, 2);
+    let second = match MappedRwLockReadGuard::try_map(second, |_| None::<&i32>) {
+        Ok(_) => panic!("mapped to nothing"),
+        Err(second) => second,
+    };
+    assert_eq!(*second, 2);
+    assert!(l.as_ref().try_write().is_err());
+    drop(second);
+    assert!(l.as_ref().try_write().is_ok());
+}
+
+#[test]
+fn data_ptr() {
+    let l = RwLock::boxed(NonCopy(1));
+    let ptr = l.as_ref().data_ptr();
+    {
+        let _w = l.as_ref().write().unwrap();
+        unsafe { (*ptr).0 = 2 };
+    }
+    let r = l.as_ref().read().unwrap();
+    assert_eq!(ptr as *const NonCopy, &*r as *const NonCopy);
+    assert_eq!(*r, NonCopy(2));
+}
+
+#[test]
+fn read_write_raw() {
+    let l = RwLock::arc(0);
+    unsafe {
+        l.as_ref().read_raw();
+        l.as_ref().read_raw();
+    }
+    assert!(l.as_ref().try_read().is_ok());
+    assert!(matches!(
+        l.as_ref().try_write(),
+        Err(TryLockError::WouldBlock)
+    ));
+    unsafe {
+        l.as_ref().unlock_read_raw();
+        l.as_ref().unlock_read_raw();
+        l.as_ref().write_raw();
+    }
+    assert!(matches!(
+        l.as_ref().try_read(),
+        Err(TryLockError::WouldBlock)
+    ));
+    unsafe { *l.as_ref().data_ptr() = 1 };
+
+    let l2 = l.clone();
+    let t = thread::spawn(move || *l2.as_ref().write().unwrap() += 1);
+    unsafe { l.as_ref().unlock_write_raw() };
+    t.join().unwrap();
+    assert_eq!(*l.as_ref().read().unwrap(), 2);
+}
+
+#[test]
+fn force_unlock() {
+    let l = RwLock::boxed(0);
+    std::mem::forget(l.as_ref().read().unwrap());
+    assert!(l.as_ref().try_write().is_err());
+    unsafe { l.as_ref().force_unlock_read() };
+
+    let mut w = l.as_ref().write().unwrap();
+    *w += 1;
+    std::mem::forget(w);
+    assert!(l.as_ref().try_read().is_err());
+    unsafe { l.as_ref().force_unlock_write() };
+    assert!(!l.as_ref().is_poisoned());
+    assert_eq!(*l.as_ref().read().unwrap(), 1);
+
+    l.as_ref().freeze();
+    std::mem::forget(l.as_ref().read().unwrap());
+    unsafe { l.as_ref().force_unlock_read() };
+    assert_eq!(*l.as_ref().read().unwrap(), 1);
+}
+
+#[test]
+fn clear_poison() {
+    let arc = RwLock::arc(1);
+    let arc2 = arc.clone();
+    let _: Result<(), _> = thread::spawn(move || {
+        let _lock = arc2.as_ref().write().unwrap();
+        panic!();
+    })
+    .join();
+    assert!(arc.as_ref().is_poisoned());
+
+    arc.as_ref().clear_poison();
+    assert!(!arc.as_ref().is_poisoned());
+    assert_eq!(*arc.as_ref().read().unwrap(), 1);
+    assert!(arc.as_ref().write().is_ok());
+}
+
+// A read-write lock which spins, with the number of readers in the low bits
+// and the writer in the high bit.
+struct SpinRwLock(AtomicUsize);
+
+const WRITER: usize = !(usize::MAX >> 1);
+
+struct SpinReadGuard<'a>(&'a AtomicUsize);
+
+impl Clone for SpinReadGuard<'_> {
+    fn clone(&self) -> Self {
+        self.0.fetch_add(1, Ordering::Relaxed);
+        SpinReadGuard(self.0)
+    }
+}
+
+impl Drop for SpinReadGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Release);
+    }
+}
+
+struct SpinWriteGuard<'a>(&'a AtomicUsize);
+
+impl Drop for SpinWriteGuard<'_> {
+    fn drop(&mut self) {
+        self.0.store(0, Ordering::Release);
+    }
+}
+
+unsafe impl RawRwLock for SpinRwLock {
+    type ReadGuard<'a> = SpinReadGuard<'a>;
+    type WriteGuard<'a> = SpinWriteGuard<'a>;
+
+    const UNINIT: Self = SpinRwLock(AtomicUsize::new(0));
+
+    fn init(self: Pin<&Self>) {}
+
+    fn read(self: Pin<&Self>) -> Option<SpinReadGuard<'_>> {
+        loop {
+            if let Ok(guard) = self.try_read() {
+                return Some(guard);
+            }
+            thread::yield_now();
+        }
+    }
+
+    fn try_read(self: Pin<&Self>) -> Result<SpinReadGuard<'_>, ReadError> {
+        let state = &self.get_ref().0;
+        state
+            .fetch_update(Ordering::Acquire, Ordering::Relaxed, |s| {
+                (s & WRITER == 0).then(|| s + 1)
+            })
+            .map(|_| SpinReadGuard(state))
+            .map_err(|_| ReadError::WouldBlock)
+    }
+
+    fn write(self: Pin<&Self>) -> SpinWriteGuard<'_> {
+        loop {
+            if let Some(guard) = self.try_write() {
+                return guard;
+            }
+            thread::yield_now();
+        }
+    }
+
+    fn try_write(self: Pin<&Self>) -> Option<SpinWriteGuard<'_>> {
+        let state = &self.get_ref().0;
+        state
+            .compare_exchange(0, WRITER, Ordering::Acquire, Ordering::Relaxed)
+            .ok()
+            .map(|_| SpinWriteGuard(state))
+    }
+
+    unsafe fn read_unlock(self: Pin<&Self>) {
+        self.0.fetch_sub(1, Ordering::Release);
+    }
+
+    unsafe fn write_unlock(self: Pin<&Self>) {
+        self.0.store(0, Ordering::Release);
+    }
+}
+
+#[test]
+fn custom_backend() {
+    const J: u32 = 1000;
+    const K: u32 = 3;
+
+    let lock = Arc::pin(RwLock::<_, SpinRwLock>::with_backend(0));
+    lock.as_ref().init();
+
+    let threads: Vec<_> = (0..K)
+        .map(|_| {
+            let lock = lock.clone();
+            thread::spawn(move || {
+                for _ in 0..J {
+                    *lock.as_ref().write().unwrap() += 1;
+                    let _ = *lock.as_ref().read().unwrap();
+                }
+            })
+        })
+        .collect();
+    for t in threads {
+        t.join().unwrap();
+    }
+    assert_eq!(*lock.as_ref().read().unwrap(), J * K);
+
+    let r = lock.as_ref().read().unwrap();
+    let r2 = RwLockReadGuard::clone(&r);
+    assert!(matches!(
+        lock.as_ref().try_write(),
+        Err(TryLockError::WouldBlock)
+    ));
+    drop(r);
+    drop(r2);
+    let w = lock.as_ref().write().unwrap();
+    assert!(matches!(
+        lock.as_ref().try_read(),
+        Err(TryLockError::WouldBlock)
+    ));
+    let u = RwLockWriteGuard::downgrade_to_upgradable(w);
+    assert!(lock.as_ref().try_read().is_ok());
+    assert!(lock.as_ref().try_write().is_err());
+    *RwLockUpgradableReadGuard::upgrade(u) += 1;
+    assert_eq!(*lock.as_ref().read().unwrap(), J * K + 1);
+}
+
+// A spinning read-write lock which admits at most two readers at a time.
+struct CappedRwLock(SpinRwLock);
+
+const MAX_READERS: usize = 2;
+
+unsafe impl RawRwLock for CappedRwLock {
+    type ReadGuard<'a> = SpinReadGuard<'a>;
+    type WriteGuard<'a> = SpinWriteGuard<'a>;
+
+    const UNINIT: Self = CappedRwLock(SpinRwLock::UNINIT);
+
+    fn init(self: Pin<&Self>) {}
+
+    fn read(self: Pin<&Self>) -> Option<SpinReadGuard<'_>> {
+        loop {
+            match self.try_read() {
+                Ok(guard) => return Some(guard),
+                Err(ReadError::TooManyReaders) => return None,
+                Err(ReadError::WouldBlock) => thread::yield_now(),
+            }
+        }
+    }
+
+    fn try_read(self: Pin<&Self>) -> Result<SpinReadGuard<'_>, ReadError> {
+        let state = &self.get_ref().0 .0;
+        state
+            .fetch_update(Ordering::Acquire, Ordering::Relaxed, |s| {
+                (s & WRITER == 0 && s < MAX_READERS).then(|| s + 1)
+            })
+            .map(|_| SpinReadGuard(state))
+            .map_err(|s| {
+                if s & WRITER == 0 {
+                    ReadError::TooManyReaders
+                } else {
+                    ReadError::WouldBlock
+                }
+            })
+    }
+
+    fn write(self: Pin<&Self>) -> SpinWriteGuard<'_> {
+        self.inner().write()
+    }
+
+    fn try_write(self: Pin<&Self>) -> Option<SpinWriteGuard<'_>> {
+        self.inner().try_write()
+    }
+
+    unsafe fn read_unlock(self: Pin<&Self>) {
+        self.inner().read_unlock();
+    }
+
+    unsafe fn write_unlock(self: Pin<&Self>) {
+        self.inner().write_unlock();
+    }
+}
+
+impl CappedRwLock {
+    fn inner(self: Pin<&Self>) -> Pin<&SpinRwLock> {
+        unsafe { self.map_unchecked(|this| &this.0) }
+    }
+}
+
+#[test]
+fn reader_overflow_errors() {
+    let lock = Box::pin(RwLock::<_, CappedRwLock>::with_backend(0));
+    lock.as_ref().init();
+    let _r1 = lock.as_ref().read().unwrap();
+    let _r2 = lock.as_ref().read_checked().unwrap();
+    assert!(matches!(
+        lock.as_ref().try_read(),
+        Err(TryLockError::TooManyReaders)
+    ));
+    assert!(matches!(
+        lock.as_ref().read_checked(),
+        Err(LockError::TooManyReaders)
+    ));
+    let result = panic::catch_unwind(|| drop(lock.as_ref().read()));
+    assert!(result.is_err());
+}
+
+#[test]
+fn reader_overflow_block() {
+    let lock = RwLock::<_, CappedRwLock>::with_backend(0).reader_overflow(ReaderOverflow::Block);
+    let lock = Arc::pin(lock);
+    lock.as_ref().init();
+    let r1 = lock.as_ref().read().unwrap();
+    let r2 = lock.as_ref().read().unwrap();
+
+    let (tx, rx) = channel();
+    let lock2 = lock.clone();
+    let reader = thread::spawn(move || {
+        let _r = lock2.as_ref().read().unwrap();
+        tx.send(()).unwrap();
+    });
+    // The reader waits until one of the two readers leaves.
+    assert!(rx.recv_timeout(Duration::from_millis(100)).is_err());
+    drop(r1);
+    rx.recv().unwrap();
+    reader.join().unwrap();
+
+    // It also waits for a reader which leaves without a guard.
+    std::mem::forget(r2);
+    let r3 = lock.as_ref().read().unwrap();
+    let (tx, rx) = channel();
+    let lock2 = lock.clone();
+    let reader = thread::spawn(move || {
+        let _r = lock2.as_ref().read().unwrap();
+        tx.send(()).unwrap();
+    });
+    assert!(rx.recv_timeout(Duration::from_millis(100)).is_err());
+    unsafe { lock.as_ref().force_unlock_read() };
+    rx.recv().unwrap();
+    reader.join().unwrap();
+    drop(r3);
+    assert!(lock.as_ref().try_write().is_ok());
+}
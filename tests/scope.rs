@@ -0,0 +1,68 @@
+use pinned_sync::{Barrier, Mutex, RwLock};
+
+#[test]
+fn single() {
+    const N: usize = 8;
+
+    let total = pinned_sync::scope(Mutex::uninit(0), |s, m| {
+        let threads: Vec<_> = (0..N)
+            .map(|i| s.spawn(move || *m.lock().unwrap() += i))
+            .collect();
+        for t in threads {
+            t.join().unwrap();
+        }
+        let total = *m.lock().unwrap();
+        total
+    });
+    assert_eq!(total, (0..N).sum());
+}
+
+#[test]
+fn tuple() {
+    const N: usize = 4;
+
+    let mut values = vec![0; N];
+    pinned_sync::scope(
+        (RwLock::uninit(1), Barrier::uninit(N)),
+        |s, (l, b)| {
+            for value in values.iter_mut() {
+                s.spawn(move || {
+                    *value = *l.read().unwrap();
+                    b.wait();
+                });
+            }
+        },
+    );
+    assert_eq!(values, [1; N]);
+}
+
+#[test]
+fn array() {
+    const N: usize = 8;
+
+    let total = pinned_sync::scope(
+        std::array::from_fn::<_, N, _>(Mutex::uninit),
+        |s, ms| {
+            let threads: Vec<_> = ms
+                .iter()
+                .map(|&m| s.spawn(move || *m.lock().unwrap() += 1))
+                .collect();
+            for t in threads {
+                t.join().unwrap();
+            }
+            ms.iter().map(|m| *m.lock().unwrap()).sum::<usize>()
+        },
+    );
+    assert_eq!(total, (0..N).sum::<usize>() + N);
+}
+
+#[test]
+fn init_all() {
+    let shards: std::pin::Pin<Box<[Mutex<usize>]>> =
+        Box::into_pin((0..8).map(Mutex::uninit).collect());
+    pinned_sync::init_all(shards.as_ref());
+    for (i, shard) in pinned_sync::iter_pinned(shards.as_ref()).enumerate() {
+        assert_eq!(*shard.lock().unwrap(), i);
+    }
+    assert_eq!(pinned_sync::iter_pinned(shards.as_ref()).len(), 8);
+}
@@ -0,0 +1,112 @@
+use pinned_sync::{ShardedLock, TryLockError};
+use std::sync::mpsc::channel;
+use std::thread;
+
+#[test]
+fn smoke() {
+    let l = ShardedLock::boxed(());
+    drop(l.as_ref().read().unwrap());
+    drop(l.as_ref().write().unwrap());
+    drop((l.as_ref().read().unwrap(), l.as_ref().read().unwrap()));
+    drop(l.as_ref().write().unwrap());
+}
+
+#[test]
+fn frob() {
+    const N: u32 = 10;
+    const M: usize = 1000;
+
+    let r = ShardedLock::arc(0);
+
+    let (tx, rx) = channel::<()>();
+    for _ in 0..N {
+        let tx = tx.clone();
+        let r = r.clone();
+        thread::spawn(move || {
+            for i in 0..M {
+                if i % 10 == 0 {
+                    *r.as_ref().write().unwrap() += 1;
+                } else {
+                    drop(r.as_ref().read().unwrap());
+                }
+            }
+            drop(tx);
+        });
+    }
+    drop(tx);
+    let _ = rx.recv();
+    assert_eq!(*r.as_ref().read().unwrap(), N * M as u32 / 10);
+}
+
+#[test]
+fn writer_excludes_readers_of_every_thread() {
+    let l = ShardedLock::arc(0);
+    let w = l.as_ref().write().unwrap();
+    let threads: Vec<_> = (0..16)
+        .map(|_| {
+            let l = l.clone();
+            thread::spawn(move || {
+                assert!(matches!(
+                    l.as_ref().try_read(),
+                    Err(TryLockError::WouldBlock)
+                ));
+            })
+        })
+        .collect();
+    for t in threads {
+        t.join().unwrap();
+    }
+    drop(w);
+}
+
+#[test]
+fn try_write_fails_with_a_reader() {
+    let l = ShardedLock::arc(0);
+    let l2 = l.clone();
+    let r = l.as_ref().read().unwrap();
+    thread::spawn(move || {
+        assert!(matches!(
+            l2.as_ref().try_write(),
+            Err(TryLockError::WouldBlock)
+        ));
+    })
+    .join()
+    .unwrap();
+    drop(r);
+    // The shards acquired by the failed attempt were released.
+    *l.as_ref().try_write().unwrap() += 1;
+    assert_eq!(*l.as_ref().read().unwrap(), 1);
+}
+
+#[test]
+fn poison_wr() {
+    let l = ShardedLock::arc(1);
+    let l2 = l.clone();
+    let _ = thread::spawn(move || {
+        let _lock = l2.as_ref().write().unwrap();
+        panic!();
+    })
+    .join();
+    assert!(l.as_ref().is_poisoned());
+    assert!(l.as_ref().read().is_err());
+}
+
+#[test]
+fn no_poison_rd() {
+    let l = ShardedLock::arc(1);
+    let l2 = l.clone();
+    let _ = thread::spawn(move || {
+        let _lock = l2.as_ref().read().unwrap();
+        panic!();
+    })
+    .join();
+    assert!(!l.as_ref().is_poisoned());
+    assert_eq!(*l.as_ref().write().unwrap(), 1);
+}
+
+#[test]
+fn into_inner_and_get_mut() {
+    let mut l = ShardedLock::uninit(vec![1]);
+    l.get_mut().unwrap().push(2);
+    assert_eq!(l.into_inner().unwrap(), [1, 2]);
+}
@@ -0,0 +1,111 @@
+#![cfg(unix)]
+
+use pinned_sync::SignalSafeLock;
+use std::mem::MaybeUninit;
+use std::ptr;
+use std::sync::mpsc::channel;
+use std::sync::Arc;
+use std::thread;
+
+static COUNT: SignalSafeLock<u32> = SignalSafeLock::new(0);
+
+extern "C" fn handler(_: libc::c_int) {
+    *COUNT.lock() += 1;
+}
+
+#[test]
+fn smoke() {
+    let l = SignalSafeLock::new(1);
+    *l.lock() += 1;
+    assert_eq!(*l.lock(), 2);
+    let g = l.lock();
+    assert!(l.try_lock().is_none());
+    drop(g);
+    assert!(l.try_lock().is_some());
+}
+
+// Whether `SIGUSR2` is blocked on the current thread.
+fn blocked() -> bool {
+    unsafe {
+        let mut mask = MaybeUninit::uninit();
+        assert_eq!(
+            libc::pthread_sigmask(libc::SIG_BLOCK, ptr::null(), mask.as_mut_ptr()),
+            0
+        );
+        libc::sigismember(mask.as_ptr(), libc::SIGUSR2) == 1
+    }
+}
+
+#[test]
+fn try_lock_restores_mask() {
+    let l = SignalSafeLock::new(0);
+    let (locked_tx, locked_rx) = channel();
+    let (unlock_tx, unlock_rx) = channel::<()>();
+    thread::scope(|s| {
+        let l = &l;
+        s.spawn(move || {
+            let _g = l.lock();
+            locked_tx.send(()).unwrap();
+            unlock_rx.recv().unwrap();
+        });
+        locked_rx.recv().unwrap();
+        assert!(l.try_lock().is_none());
+        assert!(!blocked());
+        unlock_tx.send(()).unwrap();
+    });
+}
+
+#[test]
+fn nested_guards() {
+    let (a, b) = (SignalSafeLock::new(0), SignalSafeLock::new(0));
+    assert!(!blocked());
+    let ga = a.lock();
+    let gb = b.lock();
+    assert!(blocked());
+    // Dropped out of order, the mask stays until the last guard is dropped.
+    drop(ga);
+    assert!(blocked());
+    drop(gb);
+    assert!(!blocked());
+}
+
+#[test]
+fn signal_delivered_on_unlock() {
+    unsafe {
+        libc::signal(
+            libc::SIGUSR1,
+            handler as extern "C" fn(libc::c_int) as libc::sighandler_t,
+        );
+    }
+    let guard = COUNT.lock();
+    let before = *guard;
+    unsafe {
+        libc::raise(libc::SIGUSR1);
+    }
+    // The signal is blocked, so the handler can not deadlock on the lock.
+    assert_eq!(*guard, before);
+    drop(guard);
+    assert_eq!(*COUNT.lock(), before + 1);
+}
+
+#[test]
+fn lots_and_lots() {
+    const J: u32 = 1000;
+    const K: u32 = 3;
+
+    let l = Arc::new(SignalSafeLock::new(0));
+    let handles: Vec<_> = (0..K)
+        .map(|_| {
+            let l = l.clone();
+            thread::spawn(move || {
+                for _ in 0..J {
+                    *l.lock() += 1;
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    assert_eq!(Arc::try_unwrap(l).unwrap().into_inner(), J * K);
+}
@@ -0,0 +1,54 @@
+use pinned_sync::SpinLock;
+use std::thread;
+
+#[test]
+fn smoke() {
+    let l = SpinLock::boxed(1);
+    *l.as_ref().lock() += 1;
+    assert_eq!(*l.as_ref().lock(), 2);
+    let g = l.as_ref().lock();
+    assert!(l.as_ref().try_lock().is_none());
+    drop(g);
+    assert!(l.as_ref().try_lock().is_some());
+}
+
+#[test]
+fn lots_and_lots() {
+    const J: u32 = 1000;
+    const K: u32 = 4;
+
+    let l = SpinLock::arc(0);
+    let threads: Vec<_> = (0..K)
+        .map(|_| {
+            let l = l.clone();
+            thread::spawn(move || {
+                for _ in 0..J {
+                    *l.as_ref().lock() += 1;
+                }
+            })
+        })
+        .collect();
+    for t in threads {
+        t.join().unwrap();
+    }
+    assert_eq!(*l.as_ref().lock(), J * K);
+}
+
+#[test]
+fn unlocked_by_panic() {
+    let l = SpinLock::arc(0);
+    let l2 = l.clone();
+    let _ = thread::spawn(move || {
+        let _g = l2.as_ref().lock();
+        panic!();
+    })
+    .join();
+    assert_eq!(*l.as_ref().lock(), 0);
+}
+
+#[test]
+fn into_inner_and_get_mut() {
+    let mut l = SpinLock::uninit(vec![1]);
+    l.get_mut().push(2);
+    assert_eq!(l.into_inner(), [1, 2]);
+}
@@ -0,0 +1,54 @@
+use pinned_sync::{Mutex, RwLock, Striped};
+use std::thread;
+
+#[test]
+fn smoke() {
+    let locks = Striped::<8, Mutex<usize>>::boxed(Mutex::uninit);
+    assert_eq!(locks.len(), 8);
+    for i in 0..8 {
+        assert_eq!(*locks.as_ref().get(i).lock().unwrap(), i);
+    }
+    let a = locks.as_ref().get_for("a");
+    assert!(std::ptr::eq(&*a, &*locks.as_ref().get_for("a")));
+}
+
+#[test]
+fn table() {
+    const N: usize = 4;
+    const K: usize = 1000;
+
+    let locks = Striped::<16, Mutex<Vec<usize>>>::arc(|_| Mutex::uninit(Vec::new()));
+    let threads: Vec<_> = (0..N)
+        .map(|t| {
+            let locks = locks.clone();
+            thread::spawn(move || {
+                for k in (t..K).step_by(N) {
+                    locks.as_ref().get_for(&k).lock().unwrap().push(k);
+                }
+            })
+        })
+        .collect();
+    for t in threads {
+        t.join().unwrap();
+    }
+
+    for k in 0..K {
+        assert!(locks.as_ref().get_for(&k).lock().unwrap().contains(&k));
+    }
+    let total: usize = (0..16)
+        .map(|i| locks.as_ref().get(i).lock().unwrap().len())
+        .sum();
+    assert_eq!(total, K);
+}
+
+#[test]
+fn scope() {
+    pinned_sync::scope(
+        Striped::<4, RwLock<i32>>::from_fn(|_| RwLock::uninit(0)),
+        |s, locks| {
+            for k in 0..4 {
+                s.spawn(move || *locks.get_for(&k).write().unwrap() += 1);
+            }
+        },
+    );
+}
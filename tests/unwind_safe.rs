@@ -0,0 +1,45 @@
+use pinned_sync::{Barrier, Condvar, Mutex, RwLock};
+use std::cell::Cell;
+use std::panic::{self, RefUnwindSafe, UnwindSafe};
+
+fn unwind_safe<T: UnwindSafe>() {}
+fn ref_unwind_safe<T: RefUnwindSafe>() {}
+
+#[test]
+fn unwind_safe_types() {
+    // `Cell` is neither `UnwindSafe` nor `RefUnwindSafe`, but poisoning makes
+    // broken invariants visible, so the locks are both.
+    unwind_safe::<Mutex<Cell<u32>>>();
+    ref_unwind_safe::<Mutex<Cell<u32>>>();
+    unwind_safe::<RwLock<Cell<u32>>>();
+    ref_unwind_safe::<RwLock<Cell<u32>>>();
+    unwind_safe::<Condvar>();
+    ref_unwind_safe::<Condvar>();
+    unwind_safe::<Barrier>();
+    ref_unwind_safe::<Barrier>();
+
+    unwind_safe::<pinned_sync::MutexGuard<'_, u32>>();
+    unwind_safe::<pinned_sync::RwLockReadGuard<'_, u32>>();
+    unwind_safe::<pinned_sync::RwLockWriteGuard<'_, u32>>();
+    ref_unwind_safe::<pinned_sync::MutexGuard<'_, u32>>();
+    ref_unwind_safe::<pinned_sync::RwLockReadGuard<'_, u32>>();
+    ref_unwind_safe::<pinned_sync::RwLockWriteGuard<'_, u32>>();
+}
+
+#[test]
+fn catch_unwind() {
+    let m = Mutex::boxed(1);
+    let l = RwLock::boxed(1);
+    let c = Condvar::boxed();
+
+    let (m, l, c) = (m.as_ref(), l.as_ref(), c.as_ref());
+    let r = panic::catch_unwind(|| {
+        c.notify_all();
+        let _m = m.lock().unwrap();
+        let _l = l.write().unwrap();
+        panic!("test panic to poison the locks");
+    });
+    assert!(r.is_err());
+    assert!(m.is_poisoned());
+    assert!(l.is_poisoned());
+}
@@ -0,0 +1,45 @@
+use pinned_sync::{Mutex, PinWeak, RwLock};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::thread;
+
+#[test]
+fn upgrade() {
+    let m = Mutex::arc(0);
+    let weak = PinWeak::downgrade(&m);
+    assert_eq!(weak.strong_count(), 1);
+    assert_eq!(weak.weak_count(), 1);
+
+    let weak2 = weak.clone();
+    thread::spawn(move || *weak2.upgrade().unwrap().as_ref().lock().unwrap() += 1)
+        .join()
+        .unwrap();
+    assert_eq!(*m.as_ref().lock().unwrap(), 1);
+
+    drop(m);
+    assert!(weak.upgrade().is_none());
+    assert_eq!(weak.strong_count(), 0);
+}
+
+#[test]
+fn new() {
+    let weak = PinWeak::<RwLock<i32>>::new();
+    assert!(weak.upgrade().is_none());
+    assert!(weak.ptr_eq(&PinWeak::default()));
+}
+
+#[test]
+fn back_reference() {
+    struct Child {
+        parent: PinWeak<Mutex<Vec<i32>>>,
+    }
+
+    let parent = Mutex::arc(Vec::new());
+    let child = Child {
+        parent: PinWeak::downgrade(&parent),
+    };
+    let upgraded: Pin<Arc<Mutex<Vec<i32>>>> = child.parent.upgrade().unwrap();
+    upgraded.as_ref().lock().unwrap().push(1);
+    assert!(child.parent.ptr_eq(&PinWeak::downgrade(&parent)));
+    assert_eq!(*parent.as_ref().lock().unwrap(), [1]);
+}